@@ -0,0 +1,504 @@
+use crate::answer_file::{
+    errors::AnswerFileError,
+    format::AnswerFormat,
+    migration::{MigrationError, SchemaVersion},
+    sections::{DiskSetup, FirstBoot, GlobalConfig, NetworkConfig, PostInstallationWebhook},
+    template::render_template,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A complete Proxmox VE auto-installer answer file.
+///
+/// `[global]`, `[network]` and `[disk-setup]` are required by the installer;
+/// `[first-boot]` and `[post-installation-webhook]` are optional.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct AnswerFile {
+    pub global: GlobalConfig,
+    pub network: NetworkConfig,
+    #[serde(rename = "disk-setup")]
+    pub disk_setup: DiskSetup,
+    #[serde(
+        rename = "first-boot",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub first_boot: Option<FirstBoot>,
+    #[serde(
+        rename = "post-installation-webhook",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub post_installation_webhook: Option<PostInstallationWebhook>,
+}
+
+impl AnswerFile {
+    /// Runs every section's `validate()`, then the cross-section
+    /// [`semantic_errors`](AnswerFile::semantic_errors) pass, stopping at the first failure.
+    pub fn validate(&self) -> Result<(), AnswerFileError> {
+        self.global.validate()?;
+        self.network.validate()?;
+        self.disk_setup.validate()?;
+
+        if let Some(first_boot) = &self.first_boot {
+            first_boot.validate()?;
+        }
+
+        if let Some(webhook) = &self.post_installation_webhook {
+            webhook.validate()?;
+        }
+
+        if let Some(e) = self.semantic_errors().into_iter().next() {
+            return Err(e.into());
+        }
+
+        Ok(())
+    }
+
+    /// Runs every section's `validate()` and collects one error per failing
+    /// section, followed by every cross-section error.
+    pub fn validate_all(&self) -> Vec<AnswerFileError> {
+        let mut errors = Vec::new();
+
+        if let Err(e) = self.global.validate() {
+            errors.push(e.into());
+        }
+        if let Err(e) = self.network.validate() {
+            errors.push(e.into());
+        }
+        if let Err(e) = self.disk_setup.validate() {
+            errors.push(e.into());
+        }
+        if let Some(Err(e)) = self.first_boot.as_ref().map(FirstBoot::validate) {
+            errors.push(e.into());
+        }
+        if let Some(Err(e)) = self
+            .post_installation_webhook
+            .as_ref()
+            .map(PostInstallationWebhook::validate)
+        {
+            errors.push(e.into());
+        }
+
+        errors.extend(
+            self.semantic_errors()
+                .into_iter()
+                .map(AnswerFileError::from),
+        );
+        errors
+    }
+
+    /* -------- FROM TOML STRING -------- */
+
+    /// Fails with [`MigrationError::Outdated`] when `s` was written against an
+    /// older [`SchemaVersion`]; see [`AnswerFile::migrate_from`].
+    pub fn from_toml_str(s: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let version = SchemaVersion::detect(s);
+        if version < SchemaVersion::CURRENT {
+            return Err(MigrationError::Outdated(version).into());
+        }
+
+        let answer: AnswerFile = toml::from_str(s)?;
+        answer.validate()?;
+        Ok(answer)
+    }
+
+    /* -------- TO TOML STRING -------- */
+
+    pub fn to_toml_string(&self) -> Result<String, Box<dyn std::error::Error>> {
+        self.validate()?;
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    /* -------- FROM JSON STRING -------- */
+
+    /// Parses the JSON form of an answer file, using the same keys as the TOML form.
+    pub fn from_json_str(s: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let answer: AnswerFile = serde_json::from_str(s)?;
+        answer.validate()?;
+        Ok(answer)
+    }
+
+    /* -------- TO JSON STRING -------- */
+
+    pub fn to_json_string(&self) -> Result<String, Box<dyn std::error::Error>> {
+        self.validate()?;
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /* -------- FROM YAML STRING -------- */
+
+    /// Parses the YAML form of an answer file, using the same keys as the TOML form.
+    pub fn from_yaml_str(s: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let answer: AnswerFile = serde_yaml::from_str(s)?;
+        answer.validate()?;
+        Ok(answer)
+    }
+
+    /* -------- TO YAML STRING -------- */
+
+    pub fn to_yaml_string(&self) -> Result<String, Box<dyn std::error::Error>> {
+        self.validate()?;
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    /* -------- JSON SCHEMA -------- */
+
+    /// JSON Schema (draft 2020-12) describing the answer format, for editors
+    /// and external validators. Keys match the TOML/JSON/YAML forms.
+    ///
+    /// Cross-field rules (e.g. `disk-list` vs `filter`) are only enforced by [`AnswerFile::validate`].
+    pub fn json_schema() -> schemars::Schema {
+        schemars::schema_for!(AnswerFile)
+    }
+
+    /* -------- ANY FORMAT -------- */
+
+    pub fn from_str_as(s: &str, format: AnswerFormat) -> Result<Self, Box<dyn std::error::Error>> {
+        match format {
+            AnswerFormat::Toml => Self::from_toml_str(s),
+            AnswerFormat::Json => Self::from_json_str(s),
+            AnswerFormat::Yaml => Self::from_yaml_str(s),
+        }
+    }
+
+    /* -------- FROM TEMPLATE -------- */
+
+    /// Expands `{{name}}` placeholders from `vars`, then parses and validates
+    /// the rendered document as `format`.
+    ///
+    /// A rendered TOML document also has its `${env:NAME}` secrets resolved
+    /// (see [`AnswerFile::resolve_secrets`]).
+    pub fn from_template_str(
+        template: &str,
+        vars: &BTreeMap<String, String>,
+        format: AnswerFormat,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let rendered = render_template(template, vars)?;
+        Self::from_str_with_secrets(&rendered, format)
+    }
+
+    pub fn to_string_as(&self, format: AnswerFormat) -> Result<String, Box<dyn std::error::Error>> {
+        match format {
+            AnswerFormat::Toml => self.to_toml_string(),
+            AnswerFormat::Json => self.to_json_string(),
+            AnswerFormat::Yaml => self.to_yaml_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer_file::sections::{
+        DiskSetupError, Filesystem, FirstBootOrdering, FirstBootSource, GlobalConfigError,
+        NetworkConfigError, NetworkSource, ZfsOptions,
+    };
+    use crate::answer_file::semantic::SemanticError;
+
+    const HASH: &str = "$6$rounds=656000$12345678$AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
+
+    fn complete_toml() -> String {
+        format!(
+            r#"
+            [global]
+            keyboard = "de"
+            country = "de"
+            timezone = "Europe/Berlin"
+            fqdn = "pve01.lab.local"
+            mailto = "admin@example.com"
+            root-password-hashed = "{HASH}"
+            reboot-mode = "power-off"
+
+            [network]
+            source = "from-answer"
+            cidr = "10.0.0.10/24"
+            dns = "10.0.0.1"
+            gateway = "10.0.0.1"
+            filter.ID_NET_NAME_MAC = "*aabbccddeeff"
+
+            [disk-setup]
+            filesystem = "zfs"
+            disk-list = ["sda", "sdb"]
+            zfs.raid = "raid1"
+            zfs.ashift = 12
+
+            [first-boot]
+            source = "from-iso"
+            ordering = "network-online"
+
+            [post-installation-webhook]
+            url = "https://ci.lab.local/hooks/done"
+        "#
+        )
+    }
+
+    /* ---------------- DEFAULTS ---------------- */
+
+    #[test]
+    fn default_answer_file_is_valid() {
+        assert!(AnswerFile::default().validate().is_ok());
+    }
+
+    /* ---------------- DESERIALIZATION ---------------- */
+
+    #[test]
+    fn complete_document_parses() {
+        let answer = AnswerFile::from_toml_str(&complete_toml()).unwrap();
+        assert_eq!(answer.global.fqdn, "pve01.lab.local");
+        assert_eq!(answer.network.source, NetworkSource::FromAnswer);
+        assert_eq!(answer.disk_setup.filesystem, Filesystem::Zfs);
+        let first_boot = answer.first_boot.unwrap();
+        assert_eq!(first_boot.source, FirstBootSource::FromIso);
+        assert_eq!(first_boot.ordering, FirstBootOrdering::NetworkOnline);
+        assert_eq!(
+            answer.post_installation_webhook.unwrap().url,
+            "https://ci.lab.local/hooks/done"
+        );
+    }
+
+    #[test]
+    fn optional_sections_may_be_omitted() {
+        let toml = r#"
+            [global]
+            [network]
+            [disk-setup]
+            disk-list = ["sda"]
+        "#;
+
+        let answer = AnswerFile::from_toml_str(toml).unwrap();
+        assert_eq!(answer.first_boot, None);
+        assert_eq!(answer.post_installation_webhook, None);
+    }
+
+    #[test]
+    fn missing_required_section_fails() {
+        let toml = r#"
+            [global]
+            [disk-setup]
+            disk-list = ["sda"]
+        "#;
+
+        let err = AnswerFile::from_toml_str(toml).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("network"), "error was: {}", msg);
+    }
+
+    #[test]
+    fn section_errors_surface_their_codes() {
+        let toml = complete_toml().replace("pve01.lab.local", "not_a_fqdn");
+        let err = AnswerFile::from_toml_str(&toml).unwrap_err();
+        assert_eq!(err.to_string(), "fqdn.invalid_format");
+    }
+
+    /* ---------------- VALIDATION ---------------- */
+
+    #[test]
+    fn validate_runs_every_section() {
+        let mut answer = AnswerFile::default();
+        answer.global.fqdn = "nope".into();
+        assert_eq!(
+            answer.validate(),
+            Err(AnswerFileError::Global(GlobalConfigError::Fqdn))
+        );
+
+        let mut answer = AnswerFile::default();
+        answer.network.dns = Some("nope".into());
+        assert_eq!(
+            answer.validate(),
+            Err(AnswerFileError::Network(NetworkConfigError::Dns))
+        );
+
+        let mut answer = AnswerFile::default();
+        answer.disk_setup.zfs = Some(ZfsOptions {
+            copies: Some(7),
+            ..ZfsOptions::default()
+        });
+        assert_eq!(
+            answer.validate(),
+            Err(AnswerFileError::DiskSetup(DiskSetupError::ZfsCopies))
+        );
+
+        let answer = AnswerFile {
+            first_boot: Some(FirstBoot {
+                source: FirstBootSource::FromUrl,
+                ..FirstBoot::default()
+            }),
+            ..AnswerFile::default()
+        };
+        assert_eq!(answer.validate().unwrap_err().section(), "first-boot");
+    }
+
+    #[test]
+    fn validate_all_collects_one_error_per_section() {
+        let mut answer = AnswerFile::default();
+        answer.global.fqdn = "nope".into();
+        answer.network.dns = Some("nope".into());
+
+        assert_eq!(
+            answer.validate_all(),
+            vec![
+                AnswerFileError::Global(GlobalConfigError::Fqdn),
+                AnswerFileError::Network(NetworkConfigError::Dns),
+                AnswerFileError::Semantic(SemanticError::StaticFieldsWithDhcp),
+            ]
+        );
+        assert!(AnswerFile::default().validate_all().is_empty());
+    }
+
+    /* ---------------- ROUND TRIP ---------------- */
+
+    #[test]
+    fn toml_round_trip_default() {
+        let answer = AnswerFile::default();
+        let toml = answer.to_toml_string().unwrap();
+        let parsed = AnswerFile::from_toml_str(&toml).unwrap();
+        assert_eq!(answer, parsed);
+    }
+
+    #[test]
+    fn toml_round_trip_complete_document() {
+        let answer = AnswerFile::from_toml_str(&complete_toml()).unwrap();
+        let toml = answer.to_toml_string().unwrap();
+        for table in [
+            "[global]",
+            "[network]",
+            "[disk-setup]",
+            "[first-boot]",
+            "[post-installation-webhook]",
+        ] {
+            assert!(toml.contains(table), "missing {} in: {}", table, toml);
+        }
+
+        let parsed = AnswerFile::from_toml_str(&toml).unwrap();
+        assert_eq!(answer, parsed);
+    }
+
+    /* ---------------- JSON ---------------- */
+
+    #[test]
+    fn json_round_trip_is_lossless() {
+        let answer = AnswerFile::from_toml_str(&complete_toml()).unwrap();
+        let json = answer.to_json_string().unwrap();
+        assert!(json.contains("\"disk-setup\""), "json was: {}", json);
+
+        let from_json = AnswerFile::from_json_str(&json).unwrap();
+        assert_eq!(answer, from_json);
+        assert_eq!(
+            from_json.to_toml_string().unwrap(),
+            answer.to_toml_string().unwrap()
+        );
+    }
+
+    #[test]
+    fn json_errors_surface_codes() {
+        let json = AnswerFile::default()
+            .to_json_string()
+            .unwrap()
+            .replace("\"ext4\"", "\"ntfs\"");
+        let err = AnswerFile::from_json_str(&json).unwrap_err();
+        assert!(
+            err.to_string().starts_with("filesystem.invalid_format"),
+            "error was: {}",
+            err
+        );
+    }
+
+    /* ---------------- YAML ---------------- */
+
+    #[test]
+    fn yaml_round_trip_is_lossless() {
+        let answer = AnswerFile::from_toml_str(&complete_toml()).unwrap();
+        let yaml = answer.to_yaml_string().unwrap();
+        assert!(yaml.contains("disk-setup:"), "yaml was: {}", yaml);
+
+        let from_yaml = AnswerFile::from_yaml_str(&yaml).unwrap();
+        assert_eq!(answer, from_yaml);
+    }
+
+    #[test]
+    fn yaml_errors_surface_codes() {
+        let yaml = "global:\n  fqdn: nope\nnetwork: {}\ndisk-setup:\n  disk-list: [sda]\n";
+        let err = AnswerFile::from_yaml_str(yaml).unwrap_err();
+        assert_eq!(err.to_string(), "fqdn.invalid_format");
+    }
+
+    /* ---------------- JSON SCHEMA ---------------- */
+
+    #[test]
+    fn json_schema_describes_sections_and_enums() {
+        let schema = serde_json::to_value(AnswerFile::json_schema()).unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+        for section in [
+            "global",
+            "network",
+            "disk-setup",
+            "first-boot",
+            "post-installation-webhook",
+        ] {
+            assert!(properties.contains_key(section), "missing {}", section);
+        }
+
+        let defs = &schema["$defs"];
+        let filesystems = defs["Filesystem"]["enum"].as_array().unwrap();
+        assert!(filesystems.contains(&"zfs".into()));
+        assert!(
+            defs["Timezone"]["enum"]
+                .as_array()
+                .unwrap()
+                .contains(&"UTC".into())
+        );
+        assert_eq!(defs["DiskList"]["minItems"], 1);
+        assert!(
+            defs["GlobalConfig"]["properties"]
+                .as_object()
+                .unwrap()
+                .contains_key("root-password-hashed")
+        );
+    }
+
+    /* ---------------- TEMPLATES ---------------- */
+
+    #[test]
+    fn templates_render_per_host_answers() {
+        let template = complete_toml()
+            .replace("pve01", "{{hostname}}")
+            .replace("10.0.0.10/24", "10.0.0.{{index}}/24");
+
+        for (hostname, index) in [("pve01", "11"), ("pve02", "12")] {
+            let vars = BTreeMap::from([
+                ("hostname".to_string(), hostname.to_string()),
+                ("index".to_string(), index.to_string()),
+            ]);
+            let answer =
+                AnswerFile::from_template_str(&template, &vars, AnswerFormat::Toml).unwrap();
+            assert_eq!(answer.global.fqdn, format!("{}.lab.local", hostname));
+            assert_eq!(
+                answer.network.cidr.unwrap(),
+                format!("10.0.0.{}/24", index).as_str()
+            );
+        }
+    }
+
+    #[test]
+    fn templates_validate_after_rendering() {
+        let template = complete_toml().replace("pve01.lab.local", "{{hostname}}");
+        let vars = BTreeMap::from([("hostname".to_string(), "bad_name".to_string())]);
+        let err = AnswerFile::from_template_str(&template, &vars, AnswerFormat::Toml).unwrap_err();
+        assert_eq!(err.to_string(), "fqdn.invalid_format");
+
+        let err = AnswerFile::from_template_str(&template, &BTreeMap::new(), AnswerFormat::Toml)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "template.unknown_variable (hostname)");
+    }
+
+    #[test]
+    fn format_dispatch() {
+        let answer = AnswerFile::default();
+        for format in [AnswerFormat::Toml, AnswerFormat::Json, AnswerFormat::Yaml] {
+            let s = answer.to_string_as(format).unwrap();
+            assert_eq!(AnswerFile::from_str_as(&s, format).unwrap(), answer);
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use crate::answer_file::{
+    answer::AnswerFile,
+    diagnostics::{AnswerDiagnostic, diagnose_str},
+    format::AnswerFormat,
+};
+use crate::util::{locate_binary, private_file, unique_suffix};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+/// Name of the upstream binary, looked up on `$PATH`.
+pub const ASSISTANT_BINARY: &str = "proxmox-auto-install-assistant";
+
+/// Environment variable overriding the assistant binary path.
+pub const ASSISTANT_ENV: &str = "PVEAUTO_ASSISTANT";
+
+/// Code of a diagnostic reported by the assistant itself.
+pub const ASSISTANT_REJECTED_CODE: &str = "assistant.rejected";
+
+/// Code used when the assistant could not be run.
+pub const ASSISTANT_UNAVAILABLE_CODE: &str = "assistant.unavailable";
+
+/// `line 12` in the assistant's TOML parse errors.
+static LINE_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\bline (\d+)").expect("invalid LINE_PATTERN"));
+
+/* ===================== ASSISTANT ===================== */
+
+/// The `proxmox-auto-install-assistant` shipped with Proxmox VE, used as a
+/// second validation backend next to the crate's own checks and to prepare
+/// installer ISOs.
+///
+/// Its `validate-answer` subcommand runs the installer's own parser, so an
+/// answer it accepts is accepted by the real installer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assistant {
+    program: PathBuf,
+}
+
+impl Assistant {
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+        }
+    }
+
+    /// `$PVEAUTO_ASSISTANT` when set, otherwise [`ASSISTANT_BINARY`] from `$PATH`.
+    pub fn locate() -> Option<Self> {
+        locate_binary(ASSISTANT_ENV, ASSISTANT_BINARY).map(Self::new)
+    }
+
+    pub fn program(&self) -> &Path {
+        &self.program
+    }
+
+    /// Runs `validate-answer` on the answer TOML `toml`.
+    ///
+    /// Returns no diagnostics when the assistant accepts the file, one
+    /// [`ASSISTANT_REJECTED_CODE`] diagnostic with its message otherwise, or
+    /// one [`ASSISTANT_UNAVAILABLE_CODE`] diagnostic when it cannot be run.
+    pub fn validate_toml(&self, toml: &str) -> Vec<AnswerDiagnostic> {
+        match self.run_validate(toml) {
+            Ok(None) => Vec::new(),
+            Ok(Some(message)) => vec![AnswerDiagnostic {
+                code: ASSISTANT_REJECTED_CODE.to_owned(),
+                line: LINE_PATTERN
+                    .captures(&message)
+                    .and_then(|c| c[1].parse().ok()),
+                detail: Some(message),
+            }],
+            Err(e) => vec![AnswerDiagnostic {
+                code: ASSISTANT_UNAVAILABLE_CODE.to_owned(),
+                line: None,
+                detail: Some(format!("{}: {}", self.program.display(), e)),
+            }],
+        }
+    }
+
+    /// The assistant's message when it rejects `toml`.
+    ///
+    /// `validate-answer` only reads files, so `toml` goes to a private temp
+    /// file that is removed afterwards.
+    fn run_validate(&self, toml: &str) -> std::io::Result<Option<String>> {
+        let file = std::env::temp_dir().join(format!(
+            "pveauto-assistant-{}-{}.toml",
+            std::process::id(),
+            unique_suffix()
+        ));
+
+        let result = (|| {
+            private_file(&file)?.write_all(toml.as_bytes())?;
+            Command::new(&self.program)
+                .arg("validate-answer")
+                .arg(&file)
+                .output()
+        })();
+        let _ = std::fs::remove_file(&file);
+        let output = result?;
+
+        if output.status.success() {
+            return Ok(None);
+        }
+
+        Ok(Some(failure_message(&output)))
+    }
+}
+
+/// The assistant's error output joined into one line, without the
+/// `Error:` / `Caused by:` decoration.
+fn failure_message(output: &Output) -> String {
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stderr),
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let message = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && *line != "Caused by:")
+        .map(|line| line.strip_prefix("Error: ").unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join(": ");
+
+    if message.is_empty() {
+        format!("exited with {}", output.status)
+    } else {
+        message
+    }
+}
+
+/* ===================== ISO PREPARATION ===================== */
+
+impl Assistant {
+    /// Runs `prepare-iso --fetch-from iso` on `iso`, embedding the answer TOML
+    /// `toml` and, when given, the first-boot script at `first_boot`, and
+    /// writing the result to `out`.
+    ///
+    /// # Returns
+    /// `Ok(None)` when the ISO was written, the assistant's message when it
+    /// failed, or an I/O error when it could not be run. A partial `out` is removed.
+    pub fn prepare_iso(
+        &self,
+        iso: &Path,
+        toml: &str,
+        first_boot: Option<&Path>,
+        out: &Path,
+    ) -> std::io::Result<Option<String>> {
+        let file = std::env::temp_dir().join(format!(
+            "pveauto-assistant-{}-{}.toml",
+            std::process::id(),
+            unique_suffix()
+        ));
+
+        let result = (|| {
+            private_file(&file)?.write_all(toml.as_bytes())?;
+            self.prepare_iso_command(iso, &file, first_boot, out)
+                .output()
+        })();
+        let _ = std::fs::remove_file(&file);
+        prepared(result?, out)
+    }
+
+    /// The `prepare-iso --fetch-from iso` command [`prepare_iso`](Assistant::prepare_iso)
+    /// runs with the answer written to `answer_file`.
+    pub fn prepare_iso_command(
+        &self,
+        iso: &Path,
+        answer_file: &Path,
+        first_boot: Option<&Path>,
+        out: &Path,
+    ) -> Command {
+        let mut command = Command::new(&self.program);
+        command
+            .arg("prepare-iso")
+            .arg(iso)
+            .args(["--fetch-from", "iso", "--answer-file"])
+            .arg(answer_file)
+            .arg("--output")
+            .arg(out);
+        if let Some(script) = first_boot {
+            command.arg("--on-first-boot").arg(script);
+        }
+        command
+    }
+
+    /// Runs `prepare-iso --fetch-from http` on `iso`, so the installer fetches
+    /// its answer from `url` (or the URL announced by DHCP or DNS when `None`),
+    /// trusting the certificate with `cert_fingerprint`; writes the result to `out`.
+    ///
+    /// # Returns
+    /// As [`prepare_iso`](Assistant::prepare_iso).
+    pub fn prepare_http_iso(
+        &self,
+        iso: &Path,
+        url: Option<&str>,
+        cert_fingerprint: Option<&str>,
+        out: &Path,
+    ) -> std::io::Result<Option<String>> {
+        let output = self
+            .prepare_http_iso_command(iso, url, cert_fingerprint, out)
+            .output()?;
+        prepared(output, out)
+    }
+
+    /// The `prepare-iso --fetch-from http` command
+    /// [`prepare_http_iso`](Assistant::prepare_http_iso) runs.
+    pub fn prepare_http_iso_command(
+        &self,
+        iso: &Path,
+        url: Option<&str>,
+        cert_fingerprint: Option<&str>,
+        out: &Path,
+    ) -> Command {
+        let mut command = Command::new(&self.program);
+        command
+            .arg("prepare-iso")
+            .arg(iso)
+            .args(["--fetch-from", "http"])
+            .arg("--output")
+            .arg(out);
+        if let Some(url) = url {
+            command.args(["--url", url]);
+        }
+        if let Some(fp) = cert_fingerprint {
+            command.args(["--cert-fingerprint", fp]);
+        }
+        command
+    }
+}
+
+/// `None` when `prepare-iso` succeeded; otherwise removes the partial `out`
+/// and returns the assistant's message.
+fn prepared(output: Output, out: &Path) -> std::io::Result<Option<String>> {
+    if output.status.success() {
+        return Ok(None);
+    }
+
+    let _ = std::fs::remove_file(out);
+    Ok(Some(failure_message(&output)))
+}
+
+/* ===================== MERGED DIAGNOSTICS ===================== */
+
+impl Assistant {
+    /// The assistant's findings for the answer `s` in `format`.
+    ///
+    /// JSON and YAML answers are converted to TOML first, which requires
+    /// them to pass the crate's own validation; otherwise nothing is sent
+    /// and no findings are returned.
+    pub fn diagnose_str(&self, s: &str, format: AnswerFormat) -> Vec<AnswerDiagnostic> {
+        let toml = match format {
+            AnswerFormat::Toml => Some(s.to_owned()),
+            _ => AnswerFile::from_str_as(s, format)
+                .and_then(|answer| answer.to_toml_string())
+                .ok(),
+        };
+        toml.map(|toml| self.validate_toml(&toml))
+            .unwrap_or_default()
+    }
+}
+
+/// [`diagnose_str`] followed by the findings of `assistant`.
+pub fn diagnose_str_with_assistant(
+    s: &str,
+    format: AnswerFormat,
+    assistant: &Assistant,
+) -> Vec<AnswerDiagnostic> {
+    let mut diagnostics = diagnose_str(s, format);
+    diagnostics.extend(assistant.diagnose_str(s, format));
+    diagnostics
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    const VALID: &str = r#"
+[global]
+fqdn = "pve01.lab.local"
+
+[network]
+source = "from-dhcp"
+
+[disk-setup]
+filesystem = "ext4"
+disk-list = ["sda"]
+"#;
+
+    /// A stand-in assistant: rejects files containing `reject-me`, echoing
+    /// the message the real binary prints for parse errors.
+    fn fake_assistant(name: &str) -> Assistant {
+        let path = std::env::temp_dir().join(format!(
+            "pveauto-fake-assistant-{}-{}",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(
+            &path,
+            "#!/bin/sh\n\
+             [ \"$1\" = validate-answer ] || exit 2\n\
+             if grep -q reject-me \"$2\"; then\n\
+             echo 'Error: Error parsing answer file' >&2\n\
+             echo '' >&2\n\
+             echo 'Caused by:' >&2\n\
+             echo '    TOML parse error at line 3, column 1' >&2\n\
+             exit 1\n\
+             fi\n\
+             echo 'The answer file was parsed successfully, no errors found!'\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        Assistant::new(path)
+    }
+
+    #[test]
+    fn accepted_file_has_no_findings() {
+        let assistant = fake_assistant("accept");
+        assert!(assistant.validate_toml(VALID).is_empty());
+        assert!(diagnose_str_with_assistant(VALID, AnswerFormat::Toml, &assistant).is_empty());
+        std::fs::remove_file(assistant.program()).unwrap();
+    }
+
+    #[test]
+    fn rejection_is_reported_with_line() {
+        let assistant = fake_assistant("reject");
+        let toml = VALID.replace("[network]", "# reject-me\n[network]");
+
+        let diagnostics = diagnose_str_with_assistant(&toml, AnswerFormat::Toml, &assistant);
+        assert_eq!(
+            diagnostics,
+            [AnswerDiagnostic {
+                code: ASSISTANT_REJECTED_CODE.into(),
+                line: Some(3),
+                detail: Some(
+                    "Error parsing answer file: TOML parse error at line 3, column 1".into()
+                ),
+            }]
+        );
+        std::fs::remove_file(assistant.program()).unwrap();
+    }
+
+    #[test]
+    fn findings_are_merged_with_own_diagnostics() {
+        let assistant = fake_assistant("merge");
+        let toml = VALID.replace("pve01.lab.local", "reject-me");
+
+        let codes: Vec<_> = diagnose_str_with_assistant(&toml, AnswerFormat::Toml, &assistant)
+            .into_iter()
+            .map(|d| d.code)
+            .collect();
+        assert_eq!(codes, ["fqdn.invalid_format", ASSISTANT_REJECTED_CODE]);
+        std::fs::remove_file(assistant.program()).unwrap();
+    }
+
+    #[test]
+    fn json_is_converted_for_the_assistant() {
+        let assistant = fake_assistant("json");
+        let json = AnswerFile::from_toml_str(VALID)
+            .unwrap()
+            .to_json_string()
+            .unwrap();
+        assert!(diagnose_str_with_assistant(&json, AnswerFormat::Json, &assistant).is_empty());
+        std::fs::remove_file(assistant.program()).unwrap();
+    }
+
+    #[test]
+    fn missing_binary_is_unavailable() {
+        let assistant = Assistant::new("/nonexistent/proxmox-auto-install-assistant");
+        let diagnostics = assistant.validate_toml(VALID);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, ASSISTANT_UNAVAILABLE_CODE);
+    }
+}
@@ -0,0 +1,415 @@
+use crate::answer_file::{
+    answer::AnswerFile,
+    errors::AnswerFileError,
+    sections::{
+        BtrfsOptions, BtrfsRaidLevel, CountryCode, DiskList, Filesystem, FirstBoot, KeyboardLayout,
+        LvmOptions, NetworkConfig, NetworkSource, PostInstallationWebhook, RebootMode, Timezone,
+        ZfsOptions, ZfsRaidLevel,
+    },
+};
+
+/// Fluent construction of an [`AnswerFile`], starting from the defaults.
+///
+/// Setters never fail; everything is checked once in [`AnswerFileBuilder::build`].
+/// Without an explicit [`country`](AnswerFileBuilder::country), the country
+/// follows the timezone (see [`CountryCode::from_timezone`]).
+///
+/// ## Example
+/// ```rs
+/// let answer = AnswerFile::builder()
+///     .fqdn("pve01.lab.local")
+///     .mailto("admin@example.com")
+///     .dhcp_network()
+///     .disks(["sda", "sdb"])
+///     .zfs_raid(ZfsRaidLevel::Raid1)
+///     .build()?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AnswerFileBuilder {
+    answer: AnswerFile,
+    disks: Option<Vec<String>>,
+    country: Option<CountryCode>,
+    mailto: Option<String>,
+    root_password_hashed: Option<String>,
+    cidr: Option<String>,
+}
+
+impl AnswerFile {
+    pub fn builder() -> AnswerFileBuilder {
+        AnswerFileBuilder::default()
+    }
+}
+
+impl AnswerFileBuilder {
+    /* ---------------- GLOBAL ---------------- */
+
+    pub fn keyboard(mut self, keyboard: KeyboardLayout) -> Self {
+        self.answer.global.keyboard = keyboard;
+        self
+    }
+
+    pub fn country(mut self, country: CountryCode) -> Self {
+        self.country = Some(country);
+        self
+    }
+
+    pub fn timezone(mut self, timezone: Timezone) -> Self {
+        self.answer.global.timezone = timezone;
+        self
+    }
+
+    pub fn fqdn(mut self, fqdn: impl Into<String>) -> Self {
+        self.answer.global.fqdn = fqdn.into();
+        self
+    }
+
+    pub fn mailto(mut self, mailto: impl Into<String>) -> Self {
+        self.mailto = Some(mailto.into());
+        self
+    }
+
+    pub fn root_password_hashed(mut self, hash: impl Into<String>) -> Self {
+        self.root_password_hashed = Some(hash.into());
+        self
+    }
+
+    /// Appends a public key to `root-ssh-keys`.
+    pub fn root_ssh_key(mut self, key: impl Into<String>) -> Self {
+        self.answer
+            .global
+            .root_ssh_keys
+            .get_or_insert_with(Vec::new)
+            .push(key.into());
+        self
+    }
+
+    pub fn reboot_mode(mut self, mode: RebootMode) -> Self {
+        self.answer.global.reboot_mode = mode;
+        self
+    }
+
+    pub fn reboot_on_error(mut self, reboot: bool) -> Self {
+        self.answer.global.reboot_on_error = reboot;
+        self
+    }
+
+    /* ---------------- NETWORK ---------------- */
+
+    /// `network.source = "from-dhcp"`
+    pub fn dhcp_network(mut self) -> Self {
+        self.answer.network = NetworkConfig::default();
+        self.cidr = None;
+        self
+    }
+
+    /// `network.source = "from-answer"` with a static address.
+    pub fn static_network(
+        mut self,
+        cidr: impl Into<String>,
+        gateway: impl Into<String>,
+        dns: impl Into<String>,
+    ) -> Self {
+        self.cidr = Some(cidr.into());
+        self.answer.network = NetworkConfig {
+            source: NetworkSource::FromAnswer,
+            cidr: None,
+            gateway: Some(gateway.into()),
+            dns: Some(dns.into()),
+            filter: self.answer.network.filter.take(),
+        };
+        self
+    }
+
+    /// Adds a `network.filter.<property> = <pattern>` NIC match.
+    pub fn network_filter(
+        mut self,
+        property: impl Into<String>,
+        pattern: impl Into<String>,
+    ) -> Self {
+        self.answer
+            .network
+            .filter
+            .get_or_insert_with(Default::default)
+            .insert(property.into(), pattern.into());
+        self
+    }
+
+    /* ---------------- DISK SETUP ---------------- */
+
+    pub fn filesystem(mut self, filesystem: Filesystem) -> Self {
+        self.answer.disk_setup.filesystem = filesystem;
+        self
+    }
+
+    /// Explicit `disk-list`; replaces any disk filter.
+    pub fn disks<I, S>(mut self, disks: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.disks = Some(disks.into_iter().map(Into::into).collect());
+        self.answer.disk_setup.filter = None;
+        self.answer.disk_setup.filter_match = None;
+        self
+    }
+
+    /// Adds a `disk-setup.filter.<property> = <pattern>` match; replaces any `disk-list`.
+    pub fn disk_filter(mut self, property: impl Into<String>, pattern: impl Into<String>) -> Self {
+        self.disks = None;
+        self.answer.disk_setup.disk_list = None;
+        self.answer
+            .disk_setup
+            .filter
+            .get_or_insert_with(Default::default)
+            .insert(property.into(), pattern.into());
+        self
+    }
+
+    /// Selects ZFS with the given `zfs.raid` level.
+    pub fn zfs_raid(mut self, raid: ZfsRaidLevel) -> Self {
+        self.answer.disk_setup.filesystem = Filesystem::Zfs;
+        self.answer
+            .disk_setup
+            .zfs
+            .get_or_insert_with(ZfsOptions::default)
+            .raid = Some(raid);
+        self
+    }
+
+    pub fn zfs_options(mut self, options: ZfsOptions) -> Self {
+        self.answer.disk_setup.filesystem = Filesystem::Zfs;
+        self.answer.disk_setup.zfs = Some(options);
+        self
+    }
+
+    pub fn lvm_options(mut self, options: LvmOptions) -> Self {
+        self.answer.disk_setup.lvm = Some(options);
+        self
+    }
+
+    /// Selects BTRFS with the given `btrfs.raid` level.
+    pub fn btrfs_raid(mut self, raid: BtrfsRaidLevel) -> Self {
+        self.answer.disk_setup.filesystem = Filesystem::Btrfs;
+        self.answer
+            .disk_setup
+            .btrfs
+            .get_or_insert_with(BtrfsOptions::default)
+            .raid = Some(raid);
+        self
+    }
+
+    /* ---------------- OPTIONAL SECTIONS ---------------- */
+
+    pub fn first_boot(mut self, first_boot: FirstBoot) -> Self {
+        self.answer.first_boot = Some(first_boot);
+        self
+    }
+
+    pub fn post_installation_webhook(
+        mut self,
+        url: impl Into<String>,
+        cert_fingerprint: Option<String>,
+    ) -> Self {
+        self.answer.post_installation_webhook = Some(PostInstallationWebhook {
+            url: url.into(),
+            cert_fingerprint,
+        });
+        self
+    }
+
+    /* ---------------- BUILD ---------------- */
+
+    /// Finalizes the answer file, running the full `AnswerFile::validate()` pass.
+    pub fn build(mut self) -> Result<AnswerFile, AnswerFileError> {
+        if let Some(disks) = self.disks.take() {
+            self.answer.disk_setup.disk_list = Some(DiskList::try_new(disks)?);
+        }
+        if let Some(mailto) = self.mailto.take() {
+            self.answer.global.mailto = mailto.parse()?;
+        }
+        if let Some(hash) = self.root_password_hashed.take() {
+            self.answer.global.root_password_hashed = hash.parse()?;
+        }
+        if let Some(cidr) = self.cidr.take() {
+            self.answer.network.cidr = Some(cidr.parse()?);
+        }
+        if let Some(country) = self
+            .country
+            .take()
+            .or_else(|| CountryCode::from_timezone(&self.answer.global.timezone))
+        {
+            self.answer.global.country = country;
+        }
+
+        self.answer.validate()?;
+        Ok(self.answer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer_file::sections::{
+        DiskSetupError, GlobalConfigError, NetworkConfigError, PostInstallationWebhookError,
+    };
+    use std::str::FromStr;
+
+    /* ---------------- DEFAULTS ---------------- */
+
+    #[test]
+    fn empty_builder_produces_default_answer() {
+        assert_eq!(
+            AnswerFile::builder().build().unwrap(),
+            AnswerFile::default()
+        );
+    }
+
+    /* ---------------- SETTERS ---------------- */
+
+    #[test]
+    fn global_setters_apply() {
+        let answer = AnswerFile::builder()
+            .keyboard(KeyboardLayout::German)
+            .country(CountryCode::from_str("de").unwrap())
+            .timezone(Timezone::from_str("Europe/Berlin").unwrap())
+            .fqdn("pve01.lab.local")
+            .mailto("admin@example.com")
+            .reboot_mode(RebootMode::PowerOff)
+            .reboot_on_error(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(answer.global.keyboard, KeyboardLayout::German);
+        assert_eq!(answer.global.country.as_str(), "de");
+        assert_eq!(answer.global.fqdn, "pve01.lab.local");
+        assert_eq!(answer.global.reboot_mode, RebootMode::PowerOff);
+        assert!(answer.global.reboot_on_error);
+    }
+
+    #[test]
+    fn static_network_then_dhcp_resets_addressing() {
+        let answer = AnswerFile::builder()
+            .static_network("10.0.0.10/24", "10.0.0.1", "10.0.0.1")
+            .dhcp_network()
+            .build()
+            .unwrap();
+        assert_eq!(answer.network, NetworkConfig::default());
+    }
+
+    #[test]
+    fn static_network_keeps_nic_filter() {
+        let answer = AnswerFile::builder()
+            .network_filter("ID_NET_NAME", "enp6s0")
+            .static_network("10.0.0.10/24", "10.0.0.1", "10.0.0.1")
+            .build()
+            .unwrap();
+        assert_eq!(answer.network.source, NetworkSource::FromAnswer);
+        assert!(answer.network.filter.unwrap().contains_key("ID_NET_NAME"));
+    }
+
+    #[test]
+    fn zfs_raid_selects_zfs() {
+        let answer = AnswerFile::builder()
+            .disks(["sda", "sdb"])
+            .zfs_raid(ZfsRaidLevel::Raid1)
+            .build()
+            .unwrap();
+        assert_eq!(answer.disk_setup.filesystem, Filesystem::Zfs);
+        assert_eq!(
+            answer.disk_setup.zfs.unwrap().raid,
+            Some(ZfsRaidLevel::Raid1)
+        );
+        assert_eq!(answer.disk_setup.disk_list.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn disk_filter_replaces_disk_list() {
+        let answer = AnswerFile::builder()
+            .disks(["sda"])
+            .disk_filter("ID_SERIAL", "S4EV*")
+            .build()
+            .unwrap();
+        assert_eq!(answer.disk_setup.disk_list, None);
+        assert!(answer.disk_setup.filter.is_some());
+    }
+
+    #[test]
+    fn btrfs_raid_selects_btrfs() {
+        let answer = AnswerFile::builder()
+            .btrfs_raid(BtrfsRaidLevel::Raid1)
+            .build()
+            .unwrap();
+        assert_eq!(answer.disk_setup.filesystem, Filesystem::Btrfs);
+    }
+
+    /* ---------------- BUILD VALIDATION ---------------- */
+
+    #[test]
+    fn country_follows_timezone_unless_set() {
+        let berlin = Timezone::from_str("Europe/Berlin").unwrap();
+
+        let answer = AnswerFile::builder()
+            .timezone(berlin.clone())
+            .build()
+            .unwrap();
+        assert_eq!(answer.global.country.as_str(), "de");
+
+        let answer = AnswerFile::builder()
+            .country(CountryCode::from_str("at").unwrap())
+            .timezone(berlin)
+            .build()
+            .unwrap();
+        assert_eq!(answer.global.country.as_str(), "at");
+    }
+
+    #[test]
+    fn build_validates_global_fields() {
+        let err = AnswerFile::builder()
+            .fqdn("not a fqdn")
+            .build()
+            .unwrap_err();
+        assert_eq!(err, AnswerFileError::Global(GlobalConfigError::Fqdn));
+    }
+
+    #[test]
+    fn build_rejects_invalid_mailto() {
+        let err = AnswerFile::builder()
+            .mailto("root@local")
+            .build()
+            .unwrap_err();
+        assert_eq!(err, AnswerFileError::Global(GlobalConfigError::Mailto));
+    }
+
+    #[test]
+    fn build_validates_network() {
+        let err = AnswerFile::builder()
+            .static_network("10.0.0.10", "10.0.0.1", "10.0.0.1")
+            .build()
+            .unwrap_err();
+        assert_eq!(err, AnswerFileError::Network(NetworkConfigError::Cidr));
+    }
+
+    #[test]
+    fn build_validates_disk_list() {
+        let err = AnswerFile::builder()
+            .disks(["sda", "sda"])
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            AnswerFileError::DiskSetup(DiskSetupError::DiskListDuplicate)
+        );
+    }
+
+    #[test]
+    fn build_validates_optional_sections() {
+        let err = AnswerFile::builder()
+            .post_installation_webhook("not-a-url", None)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            AnswerFileError::PostInstallationWebhook(PostInstallationWebhookError::Url)
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use crate::answer_file::{answer::AnswerFile, format::AnswerFormat};
+use toml_edit::{DocumentMut, Item, Table};
+
+/// Sections in the order of the Proxmox answer file reference.
+///
+/// Keys within a section follow the declaration order of its struct, which
+/// mirrors the same reference, so every serializer emits this layout.
+pub const SECTION_ORDER: [&str; 5] = [
+    "global",
+    "network",
+    "disk-setup",
+    "first-boot",
+    "post-installation-webhook",
+];
+
+/* ===================== CANONICAL ORDER ===================== */
+
+impl AnswerFile {
+    /// Serializes the answer in canonical order: [`SECTION_ORDER`], then each
+    /// section's keys as documented by Proxmox, so equal answers produce
+    /// byte-identical files.
+    pub fn to_canonical_string(
+        &self,
+        format: AnswerFormat,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        self.to_string_as(format)
+    }
+
+    /// Reorders the sections and keys of the answer TOML `source` into
+    /// canonical order without touching values, comments or formatting.
+    ///
+    /// Comments move with the key they precede. Keys the answer format does
+    /// not define keep their relative order after the known ones.
+    ///
+    /// # Errors
+    /// `source` must be a valid answer file.
+    pub fn canonicalize_toml(source: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let answer = AnswerFile::from_toml_str(source)?;
+        let reference: DocumentMut = answer.to_toml_string()?.parse()?;
+
+        let mut document: DocumentMut = source.parse()?;
+        let mut position = 0;
+        reorder(document.as_table_mut(), reference.as_table(), &mut position);
+        Ok(document.to_string())
+    }
+}
+
+/// Sorts `target`'s keys by their index in `reference` and numbers its
+/// `[header]` tables in visiting order, recursing into sub-tables.
+fn reorder(target: &mut Table, reference: &Table, position: &mut isize) {
+    let rank = |key: &str| {
+        reference
+            .iter()
+            .position(|(known, _)| known == key)
+            .unwrap_or(usize::MAX)
+    };
+    target.sort_values_by(|a, _, b, _| rank(a.get()).cmp(&rank(b.get())));
+
+    let empty = Table::new();
+    let keys: Vec<String> = target.iter().map(|(key, _)| key.to_owned()).collect();
+    for key in keys {
+        if let Some(Item::Table(table)) = target.get_mut(&key) {
+            if !table.is_dotted() {
+                *position += 1;
+                table.set_position(Some(*position));
+            }
+            let sub_reference = reference.get(&key).and_then(Item::as_table);
+            reorder(table, sub_reference.unwrap_or(&empty), position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer_file::{
+        AnswerPreset,
+        sections::{ZfsAshift, ZfsCompress, ZfsOptions, ZfsRaidLevel},
+    };
+
+    const SHUFFLED: &str = r#"[disk-setup]
+disk-list = ["sda", "sdb"]
+filesystem = "zfs" # mirror
+zfs.compress = "lz4"
+zfs.raid = "raid1"
+
+[network]
+source = "from-dhcp"
+
+[global]
+# shown in the installer summary
+timezone = "Europe/Berlin"
+mailto = "admin@example.com"
+fqdn = "pve01.lab.local"
+country = "de"
+keyboard = "de"
+"#;
+
+    fn index_of(haystack: &str, needle: &str) -> usize {
+        haystack
+            .find(needle)
+            .unwrap_or_else(|| panic!("{} not in\n{}", needle, haystack))
+    }
+
+    #[test]
+    fn serializers_follow_documented_order() {
+        let mut answer = AnswerPreset::LabZfsMirror.answer();
+        answer.disk_setup.zfs = Some(ZfsOptions {
+            raid: Some(ZfsRaidLevel::Raid1),
+            ashift: Some(ZfsAshift::try_new(12).unwrap()),
+            arc_max: Some(2048),
+            compress: Some(ZfsCompress::Lz4),
+            hdsize: Some(100.0),
+            ..Default::default()
+        });
+
+        for format in [AnswerFormat::Toml, AnswerFormat::Json, AnswerFormat::Yaml] {
+            let out = answer.to_canonical_string(format).unwrap();
+            let order = [
+                "keyboard",
+                "country",
+                "fqdn",
+                "mailto",
+                "timezone",
+                "root-password-hashed",
+                "network",
+                "disk-setup",
+                "raid",
+                "ashift",
+                "arc-max",
+                "compress",
+                "hdsize",
+            ]
+            .map(|key| index_of(&out, key));
+            assert!(
+                order.windows(2).all(|w| w[0] < w[1]),
+                "{}:\n{}",
+                format,
+                out
+            );
+            assert_eq!(answer.to_canonical_string(format).unwrap(), out);
+        }
+    }
+
+    #[test]
+    fn shuffled_file_is_reordered_keeping_comments() {
+        let canonical = AnswerFile::canonicalize_toml(SHUFFLED).unwrap();
+        assert_eq!(
+            canonical,
+            r#"
+[global]
+keyboard = "de"
+country = "de"
+fqdn = "pve01.lab.local"
+mailto = "admin@example.com"
+# shown in the installer summary
+timezone = "Europe/Berlin"
+
+[network]
+source = "from-dhcp"
+[disk-setup]
+filesystem = "zfs" # mirror
+disk-list = ["sda", "sdb"]
+zfs.raid = "raid1"
+zfs.compress = "lz4"
+"#
+        );
+        assert_eq!(
+            AnswerFile::from_toml_str(&canonical).unwrap(),
+            AnswerFile::from_toml_str(SHUFFLED).unwrap()
+        );
+    }
+
+    #[test]
+    fn canonical_file_is_unchanged() {
+        let toml = AnswerPreset::ProductionHardened
+            .answer()
+            .to_toml_string()
+            .unwrap();
+        assert_eq!(AnswerFile::canonicalize_toml(&toml).unwrap(), toml);
+    }
+}
@@ -0,0 +1,600 @@
+use crate::answer_file::{
+    answer::AnswerFile,
+    errors::AnswerFileError,
+    format::AnswerFormat,
+    migration::{MigrationError, SchemaVersion, legacy_keys},
+    sections::{FirstBootError, GlobalConfigError, PostInstallationWebhookError},
+    strict::{UNKNOWN_KEY_CODE, unknown_keys},
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fmt;
+
+/// Matches the stable `<field>.<reason>` codes emitted by the section error enums.
+static ERROR_CODE_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[a-z_]+(?:\.[a-z_]+)+$").expect("invalid ERROR_CODE_PATTERN"));
+
+/// Code used for TOML syntax/shape errors that carry no section error code.
+pub const TOML_PARSE_ERROR_CODE: &str = "toml.parse_error";
+
+/// Code used for JSON syntax/shape errors that carry no section error code.
+pub const JSON_PARSE_ERROR_CODE: &str = "json.parse_error";
+
+/// Code used for YAML syntax/shape errors that carry no section error code.
+pub const YAML_PARSE_ERROR_CODE: &str = "yaml.parse_error";
+
+/// A single answer file problem, located in the source TOML where possible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnswerDiagnostic {
+    /// Machine-stable error code (e.g. `fqdn.invalid_format`).
+    pub code: String,
+    /// 1-based line in the source document, if it could be determined.
+    pub line: Option<usize>,
+    /// Human-readable detail for parse errors, or how to fix the problem.
+    pub detail: Option<String>,
+}
+
+impl fmt::Display for AnswerDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.code)?,
+            None => f.write_str(&self.code)?,
+        }
+        if let Some(detail) = &self.detail {
+            write!(f, " ({})", detail)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses and validates an answer TOML, returning every problem found.
+///
+/// An empty result means the document is a valid [`AnswerFile`]. Deserialize
+/// errors stop parsing, so at most one is reported; validation errors are
+/// collected per section via [`AnswerFile::validate_all`]. Files written
+/// against an older [`SchemaVersion`] only report `schema.outdated`.
+pub fn diagnose_toml_str(s: &str) -> Vec<AnswerDiagnostic> {
+    let version = SchemaVersion::detect(s);
+    if version < SchemaVersion::CURRENT {
+        return vec![outdated_schema(s, version)];
+    }
+
+    let answer: AnswerFile = match toml::from_str(s) {
+        Ok(answer) => answer,
+        Err(e) => return vec![from_toml_error(s, &e)],
+    };
+
+    answer
+        .validate_all()
+        .iter()
+        .map(|e| from_answer_error(s, e))
+        .collect()
+}
+
+/// Parses and validates an answer JSON document, returning every problem found.
+///
+/// Deserialize errors carry their line; validation errors are not located
+/// since JSON keys may be laid out on any line.
+pub fn diagnose_json_str(s: &str) -> Vec<AnswerDiagnostic> {
+    let answer: AnswerFile = match serde_json::from_str(s) {
+        Ok(answer) => answer,
+        Err(e) => return vec![from_json_error(&e)],
+    };
+
+    answer
+        .validate_all()
+        .iter()
+        .map(|e| AnswerDiagnostic {
+            code: e.code().to_owned(),
+            line: None,
+            detail: hint(e.code()),
+        })
+        .collect()
+}
+
+/// Parses and validates an answer YAML document, returning every problem found.
+///
+/// Like [`diagnose_json_str`], only deserialize errors carry a line.
+pub fn diagnose_yaml_str(s: &str) -> Vec<AnswerDiagnostic> {
+    let answer: AnswerFile = match serde_yaml::from_str(s) {
+        Ok(answer) => answer,
+        Err(e) => return vec![from_yaml_error(&e)],
+    };
+
+    answer
+        .validate_all()
+        .iter()
+        .map(|e| AnswerDiagnostic {
+            code: e.code().to_owned(),
+            line: None,
+            detail: hint(e.code()),
+        })
+        .collect()
+}
+
+/// Diagnoses `s` as a document in the given `format`.
+pub fn diagnose_str(s: &str, format: AnswerFormat) -> Vec<AnswerDiagnostic> {
+    match format {
+        AnswerFormat::Toml => diagnose_toml_str(s),
+        AnswerFormat::Json => diagnose_json_str(s),
+        AnswerFormat::Yaml => diagnose_yaml_str(s),
+    }
+}
+
+/// Like [`diagnose_str`], but also reports every key the answer format does
+/// not define as `answer.unknown_key`, with the key path as detail.
+///
+/// Unknown keys are only looked for once the document parses; TOML keys are
+/// located by line.
+pub fn diagnose_str_strict(s: &str, format: AnswerFormat) -> Vec<AnswerDiagnostic> {
+    let mut diagnostics: Vec<_> = unknown_keys(s, format)
+        .into_iter()
+        .map(|key| {
+            let line = match format {
+                AnswerFormat::Toml => match key.split_once('.') {
+                    Some((section, rest)) => locate_key(s, section, rest),
+                    None => locate_section(s, &key),
+                },
+                _ => None,
+            };
+            AnswerDiagnostic {
+                code: UNKNOWN_KEY_CODE.to_owned(),
+                line,
+                detail: Some(key),
+            }
+        })
+        .collect();
+
+    diagnostics.extend(diagnose_str(s, format));
+    diagnostics
+}
+
+fn from_json_error(e: &serde_json::Error) -> AnswerDiagnostic {
+    let line = (e.line() > 0).then_some(e.line());
+    let rendered = e.to_string();
+    let suffix = format!(" at line {} column {}", e.line(), e.column());
+    let message = rendered.strip_suffix(&suffix).unwrap_or(&rendered);
+
+    from_message(message, line, JSON_PARSE_ERROR_CODE)
+}
+
+fn from_yaml_error(e: &serde_yaml::Error) -> AnswerDiagnostic {
+    let location = e.location();
+    let line = location.as_ref().map(|l| l.line());
+    let rendered = e.to_string();
+    let message = match &location {
+        Some(l) => {
+            let suffix = format!(" at line {} column {}", l.line(), l.column());
+            rendered.strip_suffix(&suffix).unwrap_or(&rendered)
+        }
+        None => &rendered,
+    };
+
+    // serde_yaml prefixes the key path, e.g. `disk-setup.zfs: <message>`.
+    let message = match message.split_once(": ") {
+        Some((path, rest)) if !path.contains(' ') => rest,
+        _ => message,
+    };
+
+    from_message(message, line, YAML_PARSE_ERROR_CODE)
+}
+
+/// Uses `message` as the code when it is a section error code, otherwise
+/// reports it as detail under `parse_code`.
+fn from_message(message: &str, line: Option<usize>, parse_code: &str) -> AnswerDiagnostic {
+    let message = message.trim();
+
+    if ERROR_CODE_PATTERN.is_match(message) {
+        AnswerDiagnostic {
+            code: message.to_owned(),
+            line,
+            detail: hint(message),
+        }
+    } else {
+        AnswerDiagnostic {
+            code: parse_code.to_owned(),
+            line,
+            detail: Some(message.to_owned()),
+        }
+    }
+}
+
+fn from_toml_error(s: &str, e: &toml::de::Error) -> AnswerDiagnostic {
+    let line = e.span().map(|span| line_of_offset(s, span.start));
+    let message = e.message().trim();
+
+    if ERROR_CODE_PATTERN.is_match(message) {
+        AnswerDiagnostic {
+            code: message.to_owned(),
+            line,
+            detail: hint(message),
+        }
+    } else {
+        AnswerDiagnostic {
+            code: TOML_PARSE_ERROR_CODE.to_owned(),
+            line,
+            detail: Some(message.to_owned()),
+        }
+    }
+}
+
+/// How to fix `code`, for errors the CLI can resolve.
+fn hint(code: &str) -> Option<String> {
+    if code == GlobalConfigError::RootPasswordPlaintext.code() {
+        return Some("hash it with `pveauto answer convert-password`".to_owned());
+    }
+    [
+        FirstBootError::CertFingerprint.code(),
+        FirstBootError::CertFingerprintRequired.code(),
+        PostInstallationWebhookError::CertFingerprint.code(),
+    ]
+    .contains(&code)
+    .then(|| {
+        "SHA-256 as `AB:CD:...`, see `openssl x509 -noout -fingerprint -sha256 -in <cert>`"
+            .to_owned()
+    })
+}
+
+/// `schema.outdated`, located at the first key of the old schema.
+fn outdated_schema(s: &str, version: SchemaVersion) -> AnswerDiagnostic {
+    let line = legacy_keys(s).first().and_then(|key| {
+        let (section, rest) = key.split_once('.')?;
+        locate_key(s, section, rest)
+    });
+
+    AnswerDiagnostic {
+        code: MigrationError::Outdated(version).code().to_owned(),
+        line,
+        detail: Some(format!("schema {}, run `pveauto answer migrate`", version)),
+    }
+}
+
+fn from_answer_error(s: &str, e: &AnswerFileError) -> AnswerDiagnostic {
+    let section = e.section();
+    let key = key_from_code(section, e.code());
+    let line = key
+        .as_deref()
+        .and_then(|k| locate_key(s, section, k))
+        .or_else(|| locate_section(s, section));
+
+    AnswerDiagnostic {
+        code: e.code().to_owned(),
+        line,
+        detail: hint(e.code()),
+    }
+}
+
+/// Derives the TOML key from a `<field>.<reason>` code,
+/// e.g. `root_password_hashed.invalid_format` → `root-password-hashed`,
+/// `zfs.arc_max.out_of_range` → `zfs.arc-max`.
+fn key_from_code(section: &str, code: &str) -> Option<String> {
+    let (field, _reason) = code.rsplit_once('.')?;
+    let section_prefix = format!("{}.", section.replace('-', "_"));
+    let field = field.strip_prefix(&section_prefix).unwrap_or(field);
+
+    if field == section.replace('-', "_") {
+        return None;
+    }
+
+    Some(field.replace('_', "-"))
+}
+
+fn line_of_offset(s: &str, offset: usize) -> usize {
+    s[..offset.min(s.len())].matches('\n').count() + 1
+}
+
+/// Finds the `[section]` header line.
+fn locate_section(s: &str, section: &str) -> Option<usize> {
+    s.lines()
+        .position(|l| table_header(l) == Some(section))
+        .map(|i| i + 1)
+}
+
+/// Finds the line defining `key` (possibly dotted) inside `[section]`,
+/// either as a dotted key or inside a `[section.<prefix>]` sub-table.
+fn locate_key(s: &str, section: &str, key: &str) -> Option<usize> {
+    let mut current: Option<String> = None;
+
+    for (i, line) in s.lines().enumerate() {
+        if let Some(header) = table_header(line) {
+            current = Some(header.to_owned());
+            continue;
+        }
+
+        let Some(table) = current.as_deref() else {
+            continue;
+        };
+        let Some(line_key) = line.split_once('=').map(|(k, _)| normalize_key(k)) else {
+            continue;
+        };
+
+        let full_key = match table.strip_prefix(section) {
+            Some("") => line_key,
+            Some(rest) if rest.starts_with('.') => format!("{}.{}", &rest[1..], line_key),
+            _ => continue,
+        };
+
+        if full_key == key {
+            return Some(i + 1);
+        }
+    }
+
+    None
+}
+
+fn table_header(line: &str) -> Option<&str> {
+    let line = line.trim();
+    line.strip_prefix('[')?
+        .strip_suffix(']')
+        .filter(|h| !h.starts_with('['))
+        .map(str::trim)
+}
+
+fn normalize_key(key: &str) -> String {
+    key.split('.')
+        .map(|part| part.trim().trim_matches('"'))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = r#"
+[global]
+fqdn = "pve01.lab.local"
+
+[network]
+source = "from-dhcp"
+
+[disk-setup]
+filesystem = "zfs"
+disk-list = ["sda", "sdb"]
+zfs.raid = "raid1"
+"#;
+
+    /* ---------------- VALID ---------------- */
+
+    #[test]
+    fn valid_document_has_no_diagnostics() {
+        assert!(diagnose_toml_str(VALID).is_empty());
+    }
+
+    /* ---------------- STRICT ---------------- */
+
+    #[test]
+    fn strict_reports_unknown_keys_with_lines() {
+        let toml = VALID.replace("[network]\n", "[network]\ncidrr = \"x\"\n");
+        assert!(diagnose_toml_str(&toml).is_empty());
+        assert_eq!(
+            diagnose_str_strict(&toml, AnswerFormat::Toml),
+            vec![AnswerDiagnostic {
+                code: "answer.unknown_key".into(),
+                line: Some(6),
+                detail: Some("network.cidrr".into()),
+            }]
+        );
+        assert!(diagnose_str_strict(VALID, AnswerFormat::Toml).is_empty());
+    }
+
+    #[test]
+    fn strict_keeps_parse_errors() {
+        let toml = VALID.replace(r#"filesystem = "zfs""#, r#"filesystem = "ntfs""#);
+        assert_eq!(
+            diagnose_str_strict(&toml, AnswerFormat::Toml),
+            diagnose_toml_str(&toml)
+        );
+    }
+
+    /* ---------------- DESERIALIZE ERRORS ---------------- */
+
+    #[test]
+    fn enum_errors_report_code_and_line() {
+        let toml = VALID.replace(r#"filesystem = "zfs""#, r#"filesystem = "ntfs""#);
+        let diags = diagnose_toml_str(&toml);
+        assert_eq!(
+            diags,
+            vec![AnswerDiagnostic {
+                code: "filesystem.invalid_format".into(),
+                line: Some(9),
+                detail: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn syntax_errors_use_parse_error_code() {
+        let diags = diagnose_toml_str("[global\nfqdn = 1");
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, TOML_PARSE_ERROR_CODE);
+        assert_eq!(diags[0].line, Some(1));
+        assert!(diags[0].detail.is_some());
+    }
+
+    /* ---------------- VALIDATION ERRORS ---------------- */
+
+    #[test]
+    fn validation_errors_are_located_by_key() {
+        let toml = VALID.replace("pve01.lab.local", "nope").replace(
+            r#"source = "from-dhcp""#,
+            "source = \"from-dhcp\"\ndns = \"x\"",
+        );
+        let diags = diagnose_toml_str(&toml);
+        assert_eq!(
+            diags,
+            vec![
+                AnswerDiagnostic {
+                    code: "fqdn.invalid_format".into(),
+                    line: Some(3),
+                    detail: None,
+                },
+                AnswerDiagnostic {
+                    code: "network.dns.invalid_format".into(),
+                    line: Some(7),
+                    detail: None,
+                },
+                AnswerDiagnostic {
+                    code: "network.from_dhcp.static_fields".into(),
+                    line: Some(5),
+                    detail: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn dotted_and_subtable_keys_are_located() {
+        let dotted = format!("{}zfs.copies = 9\n", VALID);
+        assert_eq!(diagnose_toml_str(&dotted)[0].line, Some(12));
+
+        let subtable = format!(
+            "{}\n[disk-setup.zfs]\ncopies = 9\n",
+            VALID.replace("zfs.raid = \"raid1\"\n", "")
+        );
+        let diags = diagnose_toml_str(&subtable);
+        assert_eq!(diags[0].code, "zfs.copies.out_of_range");
+        assert_eq!(diags[0].line, Some(13));
+    }
+
+    #[test]
+    fn section_level_errors_point_at_the_header() {
+        let toml = VALID.replace("disk-list = [\"sda\", \"sdb\"]\n", "");
+        let diags = diagnose_toml_str(&toml);
+        assert_eq!(diags[0].code, "disk_setup.no_disk_selection");
+        assert_eq!(diags[0].line, Some(8));
+    }
+
+    #[test]
+    fn plaintext_password_suggests_conversion() {
+        let toml = VALID.replace("[network]", "root-password = \"hunter2\"\n\n[network]");
+        let diags = diagnose_toml_str(&toml);
+        assert_eq!(diags[0].code, "root_password.plaintext");
+        assert_eq!(diags[0].line, Some(5));
+        assert!(
+            diags[0]
+                .detail
+                .as_deref()
+                .is_some_and(|d| d.contains("answer convert-password"))
+        );
+    }
+
+    #[test]
+    fn fingerprint_errors_show_the_expected_format() {
+        let toml = format!(
+            "{}\n[first-boot]\nsource = \"from-url\"\nurl = \"https://10.0.0.1/fb.sh\"\n",
+            VALID
+        );
+        let diags = diagnose_toml_str(&toml);
+        assert_eq!(diags[0].code, "first_boot.cert_fingerprint.required");
+        assert!(
+            diags[0]
+                .detail
+                .as_deref()
+                .is_some_and(|d| d.contains("-fingerprint -sha256")),
+            "{:?}",
+            diags[0]
+        );
+    }
+
+    #[test]
+    fn old_schema_points_at_first_legacy_key() {
+        let toml = VALID.replace("disk-list", "disk_list");
+        let diags = diagnose_toml_str(&toml);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, "schema.outdated");
+        assert_eq!(diags[0].line, Some(10));
+    }
+
+    /* ---------------- JSON ---------------- */
+
+    #[test]
+    fn json_errors_report_code_and_line() {
+        let json = "{\n  \"global\": {},\n  \"network\": {},\n  \"disk-setup\": {\"filesystem\": \"ntfs\"}\n}";
+        assert_eq!(
+            diagnose_json_str(json),
+            vec![AnswerDiagnostic {
+                code: "filesystem.invalid_format".into(),
+                line: Some(4),
+                detail: None,
+            }]
+        );
+
+        let diags = diagnose_str("{ nope", AnswerFormat::Json);
+        assert_eq!(diags[0].code, JSON_PARSE_ERROR_CODE);
+        assert_eq!(diags[0].line, Some(1));
+    }
+
+    #[test]
+    fn json_validation_errors_are_collected() {
+        let json =
+            r#"{"global": {"fqdn": "nope"}, "network": {}, "disk-setup": {"disk-list": ["sda"]}}"#;
+        let diags = diagnose_json_str(json);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, "fqdn.invalid_format");
+        assert_eq!(diags[0].line, None);
+    }
+
+    /* ---------------- YAML ---------------- */
+
+    #[test]
+    fn yaml_errors_report_code_and_line() {
+        let yaml = "global: {}\nnetwork: {}\ndisk-setup:\n  filesystem: ntfs\n";
+        assert_eq!(
+            diagnose_yaml_str(yaml),
+            vec![AnswerDiagnostic {
+                code: "filesystem.invalid_format".into(),
+                line: Some(4),
+                detail: None,
+            }]
+        );
+
+        let yaml = "global: {}\nnetwork: {}\ndisk-setup:\n  zfs:\n    copies: nine\n";
+        let diags = diagnose_str(yaml, AnswerFormat::Yaml);
+        assert_eq!(diags[0].code, YAML_PARSE_ERROR_CODE);
+        assert_eq!(diags[0].line, Some(5));
+        assert!(diags[0].detail.as_deref().unwrap().contains("expected u8"));
+    }
+
+    /* ---------------- HELPERS ---------------- */
+
+    #[test]
+    fn key_from_code_maps_to_toml_keys() {
+        for (section, code, key) in [
+            (
+                "global",
+                "root_password_hashed.invalid_format",
+                Some("root-password-hashed"),
+            ),
+            (
+                "disk-setup",
+                "zfs.arc_max.out_of_range",
+                Some("zfs.arc-max"),
+            ),
+            ("network", "network.cidr.invalid_format", Some("cidr")),
+            (
+                "first-boot",
+                "first_boot.cert_fingerprint.required",
+                Some("cert-fingerprint"),
+            ),
+            ("disk-setup", "disk_setup.no_disk_selection", None),
+        ] {
+            assert_eq!(
+                key_from_code(section, code).as_deref(),
+                key,
+                "code: {}",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn display_includes_line() {
+        let d = AnswerDiagnostic {
+            code: "fqdn.invalid_format".into(),
+            line: Some(3),
+            detail: None,
+        };
+        assert_eq!(d.to_string(), "line 3: fqdn.invalid_format");
+    }
+}
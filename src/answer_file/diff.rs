@@ -0,0 +1,179 @@
+use crate::answer_file::answer::AnswerFile;
+use std::fmt;
+use toml::Value;
+
+/* ===================== ANSWER CHANGE ===================== */
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A single field-level difference between two answer files.
+///
+/// `path` uses the TOML key names, e.g. `global.reboot-mode` or `disk-setup.zfs.raid`.
+/// Values are rendered as TOML literals.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnswerChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl fmt::Display for AnswerChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let old = self.old.as_deref().unwrap_or_default();
+        let new = self.new.as_deref().unwrap_or_default();
+        match self.kind {
+            ChangeKind::Added => write!(f, "+ {} = {}", self.path, new),
+            ChangeKind::Removed => write!(f, "- {} = {}", self.path, old),
+            ChangeKind::Changed => write!(f, "~ {}: {} -> {}", self.path, old, new),
+        }
+    }
+}
+
+/* ===================== DIFF ===================== */
+
+impl AnswerFile {
+    /// Compares `self` (old) with `other` (new) field by field.
+    ///
+    /// Tables are walked recursively; arrays and scalars are compared as
+    /// whole values. Changes are ordered by section, then key.
+    pub fn diff(&self, other: &AnswerFile) -> Vec<AnswerChange> {
+        let mut changes = Vec::new();
+        diff_values("", &to_value(self), &to_value(other), &mut changes);
+        changes
+    }
+}
+
+fn to_value(answer: &AnswerFile) -> Value {
+    Value::try_from(answer).unwrap_or_else(|_| Value::Table(Default::default()))
+}
+
+fn diff_values(path: &str, old: &Value, new: &Value, changes: &mut Vec<AnswerChange>) {
+    match (old, new) {
+        (Value::Table(old), Value::Table(new)) => {
+            let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match (old.get(key), new.get(key)) {
+                    (Some(o), Some(n)) => diff_values(&child, o, n, changes),
+                    (Some(o), None) => push_leaves(&child, o, ChangeKind::Removed, changes),
+                    (None, Some(n)) => push_leaves(&child, n, ChangeKind::Added, changes),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if old != new => changes.push(AnswerChange {
+            path: path.to_owned(),
+            kind: ChangeKind::Changed,
+            old: Some(old.to_string()),
+            new: Some(new.to_string()),
+        }),
+        _ => {}
+    }
+}
+
+/// Records every leaf under an added/removed table individually.
+fn push_leaves(path: &str, value: &Value, kind: ChangeKind, changes: &mut Vec<AnswerChange>) {
+    if let Value::Table(table) = value {
+        for (key, child) in table {
+            push_leaves(&format!("{}.{}", path, key), child, kind.clone(), changes);
+        }
+        return;
+    }
+
+    let rendered = Some(value.to_string());
+    let (old, new) = match kind {
+        ChangeKind::Removed => (rendered, None),
+        _ => (None, rendered),
+    };
+    changes.push(AnswerChange {
+        path: path.to_owned(),
+        kind,
+        old,
+        new,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer_file::sections::{
+        Filesystem, FirstBoot, RebootMode, ZfsOptions, ZfsRaidLevel,
+    };
+
+    #[test]
+    fn identical_files_have_no_changes() {
+        let answer = AnswerFile::default();
+        assert!(answer.diff(&answer.clone()).is_empty());
+    }
+
+    #[test]
+    fn changed_fields_are_reported_by_path() {
+        let old = AnswerFile::default();
+        let mut new = old.clone();
+        new.global.reboot_mode = RebootMode::PowerOff;
+
+        assert_eq!(
+            old.diff(&new),
+            vec![AnswerChange {
+                path: "global.reboot-mode".into(),
+                kind: ChangeKind::Changed,
+                old: Some("\"reboot\"".into()),
+                new: Some("\"power-off\"".into()),
+            }]
+        );
+        assert_eq!(
+            old.diff(&new)[0].to_string(),
+            "~ global.reboot-mode: \"reboot\" -> \"power-off\""
+        );
+    }
+
+    #[test]
+    fn nested_tables_are_walked() {
+        let mut old = AnswerFile::default();
+        old.disk_setup.filesystem = Filesystem::Zfs;
+        old.disk_setup.zfs = Some(ZfsOptions {
+            raid: Some(ZfsRaidLevel::Raid1),
+            ..ZfsOptions::default()
+        });
+        let mut new = old.clone();
+        new.disk_setup.zfs = Some(ZfsOptions {
+            raid: Some(ZfsRaidLevel::RaidZ1),
+            ..ZfsOptions::default()
+        });
+
+        let changes = old.diff(&new);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "disk-setup.zfs.raid");
+    }
+
+    #[test]
+    fn added_and_removed_sections_list_their_fields() {
+        let old = AnswerFile::default();
+        let new = AnswerFile {
+            first_boot: Some(FirstBoot::default()),
+            ..AnswerFile::default()
+        };
+
+        let added = old.diff(&new);
+        assert!(added.iter().all(|c| c.kind == ChangeKind::Added));
+        assert!(added.iter().any(|c| c.path == "first-boot.source"));
+
+        let removed = new.diff(&old);
+        assert!(removed.iter().all(|c| c.kind == ChangeKind::Removed));
+        assert_eq!(removed.len(), added.len());
+        assert!(removed[0].to_string().starts_with("- first-boot."));
+    }
+}
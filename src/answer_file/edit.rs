@@ -0,0 +1,339 @@
+use crate::answer_file::{
+    answer::AnswerFile,
+    migration::rename_keys,
+    sections::{GlobalConfigError, PasswordHashFormat, hash_password_as},
+};
+use toml_edit::{DocumentMut, Item, TableLike};
+
+/* ===================== IN-PLACE EDITING ===================== */
+
+impl AnswerFile {
+    /// Parses the answer TOML `source`, applies `edit` and returns `source`
+    /// with only the changed keys rewritten.
+    ///
+    /// Comments, key order, quoting and whitespace of untouched keys are kept;
+    /// a replaced value keeps the comments around it. Keys the edit clears
+    /// (e.g. `root-ssh-keys` set to `None`) are removed, new keys are appended
+    /// to their table.
+    ///
+    /// # Errors
+    /// `source` must be a valid answer file and the edited answer must pass
+    /// [`AnswerFile::validate`]; nothing is returned otherwise.
+    pub fn edit_in_place<F>(source: &str, edit: F) -> Result<String, Box<dyn std::error::Error>>
+    where
+        F: FnOnce(&mut AnswerFile),
+    {
+        let mut document: DocumentMut = source.parse()?;
+        let before = AnswerFile::from_toml_str(source)?;
+
+        let mut after = before.clone();
+        edit(&mut after);
+        after.validate()?;
+
+        merge(
+            document.as_table_mut(),
+            &toml::Table::try_from(&before)?,
+            &toml::Table::try_from(&after)?,
+        );
+        Ok(document.to_string())
+    }
+}
+
+/* ===================== PLAINTEXT PASSWORD ===================== */
+
+impl AnswerFile {
+    /// Replaces a plaintext `root-password` in the answer TOML `source` with
+    /// `root-password-hashed`, hashed as `format` (see [`hash_password_as`]).
+    ///
+    /// The hash takes the key's place and keeps its comments; the rest of
+    /// `source` is untouched. Returns `None` when no `root-password` is set.
+    ///
+    /// # Errors
+    /// `root-password` must be a non-empty string
+    /// ([`GlobalConfigError::RootPasswordPlaintext`] otherwise), must not be
+    /// set next to `root-password-hashed`, and the converted document must be
+    /// a valid answer file.
+    pub fn hash_plaintext_password(
+        source: &str,
+        format: PasswordHashFormat,
+        rounds: Option<u32>,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let mut document: DocumentMut = source.parse()?;
+        let Some(global) = document.get_mut("global").and_then(Item::as_table_like_mut) else {
+            return Ok(None);
+        };
+        let Some(plaintext) = global.get("root-password") else {
+            return Ok(None);
+        };
+
+        let plaintext = plaintext
+            .as_str()
+            .ok_or(GlobalConfigError::RootPasswordPlaintext)?;
+        let hash = hash_password_as(plaintext, format, rounds)?;
+
+        rename_keys(
+            global,
+            "global",
+            &[("root-password", "root-password-hashed")],
+        )?;
+        if let Some(Item::Value(value)) = global.get_mut("root-password-hashed") {
+            let decor = value.decor().clone();
+            *value = hash.into();
+            *value.decor_mut() = decor;
+        }
+
+        let converted = document.to_string();
+        AnswerFile::from_toml_str(&converted)?;
+        Ok(Some(converted))
+    }
+}
+
+/// Rewrites the keys of `target` whose value differs between `before` and `after`.
+///
+/// `before` and `after` are full serializations, so keys `target` omits
+/// (defaults) are only written when the edit changes them.
+fn merge(target: &mut dyn TableLike, before: &toml::Table, after: &toml::Table) {
+    for (key, new) in after {
+        let old = before.get(key);
+        if old == Some(new) {
+            continue;
+        }
+
+        if let toml::Value::Table(new_table) = new
+            && let Some(existing) = target.get_mut(key).and_then(Item::as_table_like_mut)
+        {
+            let empty = toml::Table::new();
+            let old_table = old.and_then(toml::Value::as_table).unwrap_or(&empty);
+            merge(existing, old_table, new_table);
+            continue;
+        }
+
+        match target.get_mut(key) {
+            Some(Item::Value(value)) => {
+                let decor = value.decor().clone();
+                *value = to_edit_value(new);
+                *value.decor_mut() = decor;
+            }
+            Some(item) => *item = to_edit_item(new),
+            None => {
+                target.insert(key, to_edit_item(new));
+            }
+        }
+    }
+
+    for key in before.keys().filter(|k| !after.contains_key(*k)) {
+        target.remove(key);
+    }
+}
+
+/// Nested tables become `[section.sub]` tables, everything else a value.
+fn to_edit_item(value: &toml::Value) -> Item {
+    match value {
+        toml::Value::Table(table) => {
+            let mut out = toml_edit::Table::new();
+            for (key, value) in table {
+                out.insert(key, to_edit_item(value));
+            }
+            Item::Table(out)
+        }
+        other => Item::Value(to_edit_value(other)),
+    }
+}
+
+fn to_edit_value(value: &toml::Value) -> toml_edit::Value {
+    match value {
+        toml::Value::String(s) => s.as_str().into(),
+        toml::Value::Integer(i) => (*i).into(),
+        toml::Value::Float(f) => (*f).into(),
+        toml::Value::Boolean(b) => (*b).into(),
+        toml::Value::Datetime(d) => d
+            .to_string()
+            .parse::<toml_edit::Datetime>()
+            .map(Into::into)
+            .unwrap_or_else(|_| d.to_string().into()),
+        toml::Value::Array(items) => items
+            .iter()
+            .map(to_edit_value)
+            .collect::<toml_edit::Array>()
+            .into(),
+        toml::Value::Table(table) => table
+            .iter()
+            .map(|(k, v)| (k.as_str(), to_edit_value(v)))
+            .collect::<toml_edit::InlineTable>()
+            .into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer_file::sections::{
+        DiskList, Filesystem, GlobalConfigError, ZfsOptions, ZfsRaidLevel,
+    };
+
+    const SOURCE: &str = r#"# Lab node, managed by hand
+[global]
+keyboard = "de" # German keyboard
+country = "de"
+timezone = "Europe/Berlin"
+fqdn = "pve01.lab.local"
+mailto = "admin@example.com"
+# rotate yearly
+root-password-hashed = "$6$rounds=656000$12345678$AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"
+
+[network]
+source = "from-dhcp"
+
+[disk-setup]
+filesystem = "ext4"
+disk-list = ["sda"]  # boot disk
+"#;
+
+    #[test]
+    fn unchanged_edit_returns_source_verbatim() {
+        assert_eq!(AnswerFile::edit_in_place(SOURCE, |_| {}).unwrap(), SOURCE);
+    }
+
+    #[test]
+    fn changed_value_keeps_comments_and_layout() {
+        let new_hash = format!("$6$rounds=656000$abcdefgh${}", "Bc".repeat(43));
+        let edited = AnswerFile::edit_in_place(SOURCE, |a| {
+            a.global.root_password_hashed = new_hash.parse().unwrap();
+        })
+        .unwrap();
+
+        assert_eq!(
+            edited,
+            SOURCE.replace(
+                AnswerFile::from_toml_str(SOURCE)
+                    .unwrap()
+                    .global
+                    .root_password_hashed
+                    .as_str(),
+                &new_hash
+            )
+        );
+        assert!(edited.contains("# rotate yearly\nroot-password-hashed"));
+    }
+
+    #[test]
+    fn trailing_comment_survives_value_change() {
+        let edited = AnswerFile::edit_in_place(SOURCE, |a| {
+            a.disk_setup.disk_list = Some(DiskList::try_new(["sdb"]).unwrap());
+        })
+        .unwrap();
+        assert!(
+            edited.contains(r#"disk-list = ["sdb"]  # boot disk"#),
+            "edited:\n{}",
+            edited
+        );
+    }
+
+    #[test]
+    fn new_keys_and_tables_are_added_and_cleared_keys_removed() {
+        let edited = AnswerFile::edit_in_place(SOURCE, |a| {
+            a.global.reboot_on_error = true;
+            a.disk_setup.filesystem = Filesystem::Zfs;
+            a.disk_setup.zfs = Some(ZfsOptions {
+                raid: Some(ZfsRaidLevel::Raid0),
+                ..Default::default()
+            });
+        })
+        .unwrap();
+        assert!(edited.starts_with("# Lab node, managed by hand\n"));
+        assert!(edited.contains("reboot-on-error = true"));
+        assert!(edited.contains("[disk-setup.zfs]"));
+
+        let parsed = AnswerFile::from_toml_str(&edited).unwrap();
+        assert_eq!(parsed.disk_setup.filesystem, Filesystem::Zfs);
+
+        let reverted = AnswerFile::edit_in_place(&edited, |a| {
+            a.disk_setup.filesystem = Filesystem::Ext4;
+            a.disk_setup.zfs = None;
+        })
+        .unwrap();
+        assert!(!reverted.contains("zfs"));
+        assert!(reverted.contains("# boot disk"));
+    }
+
+    #[test]
+    fn invalid_edits_are_rejected() {
+        let err = AnswerFile::edit_in_place(SOURCE, |a| a.global.fqdn = "nope".into()).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<crate::answer_file::AnswerFileError>(),
+            Some(&GlobalConfigError::Fqdn.into())
+        );
+    }
+
+    /* ---------------- PLAINTEXT PASSWORD ---------------- */
+
+    fn with_plaintext(value: &str) -> String {
+        let hashed = SOURCE
+            .lines()
+            .find(|l| l.starts_with("root-password-hashed"))
+            .unwrap();
+        SOURCE.replace(hashed, &format!("root-password = {} # set by hand", value))
+    }
+
+    #[test]
+    fn plaintext_password_is_rejected_when_parsed() {
+        let err = AnswerFile::from_toml_str(&with_plaintext("\"hunter2\"")).unwrap_err();
+        assert!(
+            err.to_string().contains("root_password.plaintext"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn plaintext_password_is_hashed_in_place() {
+        let source = with_plaintext("\"hunter2\"");
+        let converted =
+            AnswerFile::hash_plaintext_password(&source, PasswordHashFormat::Sha512, Some(100_000))
+                .unwrap()
+                .unwrap();
+
+        assert!(!converted.contains("hunter2"));
+        assert!(converted.contains("# rotate yearly\nroot-password-hashed = \"$6$rounds=100000$"));
+        assert!(converted.contains("\" # set by hand\n\n[network]"));
+
+        let answer = AnswerFile::from_toml_str(&converted).unwrap();
+        assert!(crate::answer_file::sections::verify_password(
+            "hunter2",
+            answer.global.root_password_hashed.as_str()
+        ));
+    }
+
+    #[test]
+    fn hashing_without_plaintext_is_a_no_op() {
+        assert_eq!(
+            AnswerFile::hash_plaintext_password(SOURCE, PasswordHashFormat::Sha512, None).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn unusable_plaintext_is_rejected() {
+        for (value, code) in [
+            ("\"\"", "root_password.empty"),
+            ("42", "root_password.plaintext"),
+        ] {
+            let err = AnswerFile::hash_plaintext_password(
+                &with_plaintext(value),
+                PasswordHashFormat::Sha512,
+                None,
+            )
+            .unwrap_err();
+            assert_eq!(err.to_string(), code);
+        }
+
+        let both = SOURCE.replace("# rotate yearly\n", "root-password = \"x\"\n");
+        let err = AnswerFile::hash_plaintext_password(&both, PasswordHashFormat::Sha512, None)
+            .unwrap_err();
+        assert!(
+            err.to_string().starts_with("schema.conflicting_keys"),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,302 @@
+use age::{Identity, IdentityFile, Recipient, secrecy::SecretString, x25519};
+use std::{
+    fmt,
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Extension appended to encrypted answer files, e.g. `answer.toml.age`.
+pub const ENCRYPTED_EXTENSION: &str = "age";
+
+/// Path of an age identity file used to decrypt answers, see [`AgeIdentity::from_env`].
+pub const AGE_IDENTITY_ENV: &str = "PVEAUTO_AGE_IDENTITY";
+
+/// Passphrase used to encrypt or decrypt answers when no identity file is set.
+pub const AGE_PASSPHRASE_ENV: &str = "PVEAUTO_AGE_PASSPHRASE";
+
+/// First bytes of every binary age file.
+const AGE_MAGIC: &[u8] = b"age-encryption.org/";
+
+/* ===================== ENCRYPTION ERROR ===================== */
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncryptionError {
+    /// A recipient is not an `age1...` public key.
+    InvalidRecipient(String),
+    /// The identity file is missing or holds no usable identity.
+    InvalidIdentity(String),
+    /// The input is encrypted but no identity or passphrase was supplied.
+    IdentityRequired,
+    Encrypt(String),
+    /// Wrong key or passphrase, or a corrupted file.
+    Decrypt(String),
+}
+
+impl EncryptionError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidRecipient(_) => "encryption.invalid_recipient",
+            Self::InvalidIdentity(_) => "encryption.invalid_identity",
+            Self::IdentityRequired => "encryption.identity_required",
+            Self::Encrypt(_) => "encryption.failed",
+            Self::Decrypt(_) => "decryption.failed",
+        }
+    }
+}
+
+impl fmt::Display for EncryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidRecipient(detail)
+            | Self::InvalidIdentity(detail)
+            | Self::Encrypt(detail)
+            | Self::Decrypt(detail) => write!(f, "{} ({})", self.code(), detail),
+            Self::IdentityRequired => f.write_str(self.code()),
+        }
+    }
+}
+
+impl std::error::Error for EncryptionError {}
+
+/* ===================== KEYS ===================== */
+
+/// Who can decrypt an encrypted answer file.
+pub enum AgeRecipients {
+    /// Anyone who knows the passphrase (scrypt).
+    Passphrase(SecretString),
+    /// Holders of the matching X25519 identities.
+    Keys(Vec<x25519::Recipient>),
+}
+
+impl AgeRecipients {
+    pub fn passphrase(passphrase: impl Into<String>) -> Self {
+        Self::Passphrase(SecretString::from(passphrase.into()))
+    }
+
+    /// Parses `age1...` public keys; at least one is required.
+    pub fn parse_keys<I, S>(keys: I) -> Result<Self, EncryptionError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let keys = keys
+            .into_iter()
+            .map(|k| {
+                let k = k.as_ref().trim();
+                x25519::Recipient::from_str(k)
+                    .map_err(|_| EncryptionError::InvalidRecipient(k.to_owned()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if keys.is_empty() {
+            return Err(EncryptionError::InvalidRecipient("none given".into()));
+        }
+        Ok(Self::Keys(keys))
+    }
+}
+
+/// Key material for decrypting an encrypted answer file.
+pub enum AgeIdentity {
+    Passphrase(SecretString),
+    /// An age identity file (`AGE-SECRET-KEY-1...` lines), as written by `age-keygen`.
+    File(PathBuf),
+}
+
+impl AgeIdentity {
+    pub fn passphrase(passphrase: impl Into<String>) -> Self {
+        Self::Passphrase(SecretString::from(passphrase.into()))
+    }
+
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self::File(path.into())
+    }
+
+    /// Reads [`AGE_IDENTITY_ENV`], falling back to [`AGE_PASSPHRASE_ENV`].
+    pub fn from_env() -> Option<Self> {
+        if let Ok(path) = std::env::var(AGE_IDENTITY_ENV)
+            && !path.is_empty()
+        {
+            return Some(Self::file(path));
+        }
+        std::env::var(AGE_PASSPHRASE_ENV)
+            .ok()
+            .filter(|p| !p.is_empty())
+            .map(Self::passphrase)
+    }
+}
+
+/* ===================== ENCRYPT / DECRYPT ===================== */
+
+/// True when `bytes` start with the age file header.
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(AGE_MAGIC)
+}
+
+/// Appends [`ENCRYPTED_EXTENSION`] to `path` unless it already ends with it.
+pub fn encrypted_path(path: &str) -> String {
+    if Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext == ENCRYPTED_EXTENSION)
+    {
+        path.to_owned()
+    } else {
+        format!("{}.{}", path, ENCRYPTED_EXTENSION)
+    }
+}
+
+/// Encrypts `plaintext` into a binary age file for `to`.
+pub fn encrypt(plaintext: &str, to: &AgeRecipients) -> Result<Vec<u8>, EncryptionError> {
+    let encrypt_err = |e: &dyn fmt::Display| EncryptionError::Encrypt(e.to_string());
+
+    match to {
+        AgeRecipients::Passphrase(passphrase) => {
+            let recipient = age::scrypt::Recipient::new(passphrase.clone());
+            age::encrypt(&recipient, plaintext.as_bytes()).map_err(|e| encrypt_err(&e))
+        }
+        AgeRecipients::Keys(keys) => {
+            let encryptor =
+                age::Encryptor::with_recipients(keys.iter().map(|k| k as &dyn Recipient))
+                    .map_err(|e| encrypt_err(&e))?;
+
+            let mut out = Vec::new();
+            let mut writer = encryptor
+                .wrap_output(&mut out)
+                .map_err(|e| encrypt_err(&e))?;
+            std::io::Write::write_all(&mut writer, plaintext.as_bytes())
+                .map_err(|e| encrypt_err(&e))?;
+            writer.finish().map_err(|e| encrypt_err(&e))?;
+            Ok(out)
+        }
+    }
+}
+
+/// Decrypts an age file produced by [`encrypt`] (or the `age` tool) back to UTF-8 text.
+pub fn decrypt(ciphertext: &[u8], with: &AgeIdentity) -> Result<String, EncryptionError> {
+    let decrypt_err = |e: &dyn fmt::Display| EncryptionError::Decrypt(e.to_string());
+
+    let identities: Vec<Box<dyn Identity + Send + Sync>> = match with {
+        AgeIdentity::Passphrase(passphrase) => {
+            vec![Box::new(age::scrypt::Identity::new(passphrase.clone()))]
+        }
+        AgeIdentity::File(path) => {
+            let invalid = |detail: String| {
+                EncryptionError::InvalidIdentity(format!("{}: {}", path.display(), detail))
+            };
+            IdentityFile::from_file(path.to_string_lossy().into_owned())
+                .map_err(|e| invalid(e.to_string()))?
+                .into_identities()
+                .map_err(|e| invalid(e.to_string()))?
+        }
+    };
+    if identities.is_empty() {
+        return Err(EncryptionError::InvalidIdentity("no identities".into()));
+    }
+
+    let decryptor = age::Decryptor::new_buffered(ciphertext).map_err(|e| decrypt_err(&e))?;
+    let mut reader = decryptor
+        .decrypt(identities.iter().map(|i| i.as_ref() as &dyn Identity))
+        .map_err(|e| decrypt_err(&e))?;
+
+    let mut plaintext = String::new();
+    reader
+        .read_to_string(&mut plaintext)
+        .map_err(|e| decrypt_err(&e))?;
+    Ok(plaintext)
+}
+
+/// Decrypts `bytes` when they are an age file, otherwise returns them as text.
+///
+/// `identity` is only called for encrypted input.
+pub fn decrypt_if_encrypted<F>(bytes: Vec<u8>, identity: F) -> Result<String, EncryptionError>
+where
+    F: FnOnce() -> Option<AgeIdentity>,
+{
+    if is_encrypted(&bytes) {
+        let identity = identity().ok_or(EncryptionError::IdentityRequired)?;
+        return decrypt(&bytes, &identity);
+    }
+    String::from_utf8(bytes).map_err(|e| EncryptionError::Decrypt(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use age::secrecy::ExposeSecret;
+
+    const TOML: &str = "[global]\nkeyboard = \"de\"\n";
+
+    /* ---------------- KEYS ---------------- */
+
+    #[test]
+    fn x25519_round_trip() {
+        let identity = x25519::Identity::generate();
+        let recipients = AgeRecipients::parse_keys([identity.to_public().to_string()]).unwrap();
+
+        let ciphertext = encrypt(TOML, &recipients).unwrap();
+        assert!(is_encrypted(&ciphertext));
+        assert!(!ciphertext.windows(6).any(|w| w == b"global"));
+
+        let file = std::env::temp_dir().join(format!("pveauto-age-{}.key", std::process::id()));
+        std::fs::write(&file, identity.to_string().expose_secret()).unwrap();
+        assert_eq!(
+            decrypt(&ciphertext, &AgeIdentity::file(&file)).unwrap(),
+            TOML
+        );
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn passphrase_round_trip_and_wrong_passphrase() {
+        let ciphertext = encrypt(TOML, &AgeRecipients::passphrase("hunter2")).unwrap();
+        assert_eq!(
+            decrypt(&ciphertext, &AgeIdentity::passphrase("hunter2")).unwrap(),
+            TOML
+        );
+
+        let err = decrypt(&ciphertext, &AgeIdentity::passphrase("wrong")).unwrap_err();
+        assert_eq!(err.code(), "decryption.failed");
+    }
+
+    #[test]
+    fn invalid_recipients_are_rejected() {
+        for keys in [vec!["ssh-ed25519 AAAA"], vec![]] {
+            assert!(matches!(
+                AgeRecipients::parse_keys(keys),
+                Err(EncryptionError::InvalidRecipient(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn missing_identity_file_is_invalid() {
+        let ciphertext = encrypt(TOML, &AgeRecipients::passphrase("x")).unwrap();
+        let err = decrypt(&ciphertext, &AgeIdentity::file("/nonexistent/key.txt")).unwrap_err();
+        assert_eq!(err.code(), "encryption.invalid_identity");
+    }
+
+    /* ---------------- TRANSPARENT LOADING ---------------- */
+
+    #[test]
+    fn plaintext_passes_through_without_identity() {
+        let text = decrypt_if_encrypted(TOML.as_bytes().to_vec(), || unreachable!()).unwrap();
+        assert_eq!(text, TOML);
+    }
+
+    #[test]
+    fn encrypted_input_requires_identity() {
+        let ciphertext = encrypt(TOML, &AgeRecipients::passphrase("x")).unwrap();
+        assert_eq!(
+            decrypt_if_encrypted(ciphertext, || None),
+            Err(EncryptionError::IdentityRequired)
+        );
+    }
+
+    /* ---------------- PATHS ---------------- */
+
+    #[test]
+    fn encrypted_path_appends_extension_once() {
+        assert_eq!(encrypted_path("answer.toml"), "answer.toml.age");
+        assert_eq!(encrypted_path("answer.toml.age"), "answer.toml.age");
+    }
+}
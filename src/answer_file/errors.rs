@@ -0,0 +1,112 @@
+use crate::answer_file::{
+    sections::{
+        DiskSetupError, FirstBootError, GlobalConfigError, NetworkConfigError,
+        PostInstallationWebhookError,
+    },
+    semantic::SemanticError,
+};
+use std::fmt;
+
+/* ===================== ANSWER FILE ERROR ===================== */
+
+/// Validation failure of a complete answer file, tagged with the section it
+/// came from. `code()` and `Display` forward the section's stable error code.
+#[derive(Debug, PartialEq)]
+pub enum AnswerFileError {
+    Global(GlobalConfigError),
+    Network(NetworkConfigError),
+    DiskSetup(DiskSetupError),
+    FirstBoot(FirstBootError),
+    PostInstallationWebhook(PostInstallationWebhookError),
+    /// A constraint spanning sections, see [`AnswerFile::semantic_errors`](crate::answer_file::AnswerFile::semantic_errors).
+    Semantic(SemanticError),
+}
+
+impl AnswerFileError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Global(e) => e.code(),
+            Self::Network(e) => e.code(),
+            Self::DiskSetup(e) => e.code(),
+            Self::FirstBoot(e) => e.code(),
+            Self::PostInstallationWebhook(e) => e.code(),
+            Self::Semantic(e) => e.code(),
+        }
+    }
+
+    /// TOML section name the error belongs to (e.g. `"disk-setup"`).
+    pub fn section(&self) -> &'static str {
+        match self {
+            Self::Global(_) => "global",
+            Self::Network(_) => "network",
+            Self::DiskSetup(_) => "disk-setup",
+            Self::FirstBoot(_) => "first-boot",
+            Self::PostInstallationWebhook(_) => "post-installation-webhook",
+            Self::Semantic(e) => e.section(),
+        }
+    }
+}
+
+impl fmt::Display for AnswerFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl std::error::Error for AnswerFileError {}
+
+macro_rules! impl_from_section_error {
+    ($($err:ty => $variant:ident),+ $(,)?) => {
+        $(
+            impl From<$err> for AnswerFileError {
+                fn from(e: $err) -> Self {
+                    Self::$variant(e)
+                }
+            }
+        )+
+    };
+}
+
+impl_from_section_error!(
+    GlobalConfigError => Global,
+    NetworkConfigError => Network,
+    DiskSetupError => DiskSetup,
+    FirstBootError => FirstBoot,
+    PostInstallationWebhookError => PostInstallationWebhook,
+    SemanticError => Semantic,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_forwards_section_code() {
+        let err = AnswerFileError::from(GlobalConfigError::Fqdn);
+        assert_eq!(err.code(), "fqdn.invalid_format");
+        assert_eq!(err.to_string(), "fqdn.invalid_format");
+    }
+
+    #[test]
+    fn section_names_match_toml_tables() {
+        for (err, section) in [
+            (AnswerFileError::from(GlobalConfigError::Fqdn), "global"),
+            (AnswerFileError::from(NetworkConfigError::Cidr), "network"),
+            (
+                AnswerFileError::from(DiskSetupError::DiskList),
+                "disk-setup",
+            ),
+            (AnswerFileError::from(FirstBootError::Url), "first-boot"),
+            (
+                AnswerFileError::from(PostInstallationWebhookError::Url),
+                "post-installation-webhook",
+            ),
+            (
+                AnswerFileError::from(SemanticError::ZfsOptionsWithoutZfs),
+                "disk-setup",
+            ),
+        ] {
+            assert_eq!(err.section(), section);
+        }
+    }
+}
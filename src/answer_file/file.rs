@@ -0,0 +1,191 @@
+use crate::answer_file::{answer::AnswerFile, format::AnswerFormat};
+use std::{
+    ffi::OsString,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Suffix of the previous version kept by [`write_atomic`], e.g. `answer.toml.bak`.
+pub const BACKUP_EXTENSION: &str = "bak";
+
+/* ===================== ATOMIC WRITES ===================== */
+
+/// Writes `contents` to `path` so readers see either the old or the new file,
+/// never a partial one.
+///
+/// # Notes
+/// - Missing parent directories are created.
+/// - The data goes to a hidden temp file next to `path`, is synced, then renamed over `path`.
+/// - With `keep_backup`, an existing `path` is first copied to [`backup_path`].
+pub fn write_atomic(path: &Path, contents: &[u8], keep_backup: bool) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(parent)?;
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let mut temp_name = OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".tmp-{}", std::process::id()));
+    let temp = parent.join(temp_name);
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&temp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+
+        if keep_backup && path.exists() {
+            std::fs::copy(path, backup_path(path))?;
+        }
+        std::fs::rename(&temp, path)
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+/// `path` with [`BACKUP_EXTENSION`] appended.
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(BACKUP_EXTENSION);
+    PathBuf::from(name)
+}
+
+/* ===================== LOAD / SAVE ===================== */
+
+impl AnswerFile {
+    /// Reads and validates the answer file at `path`, picking the format from
+    /// its extension (see [`AnswerFormat::from_path`]).
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        Self::from_path_as(path, AnswerFormat::from_path(path).unwrap_or_default())
+    }
+
+    pub fn from_path_as(
+        path: impl AsRef<Path>,
+        format: AnswerFormat,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::from_str_as(&std::fs::read_to_string(path)?, format)
+    }
+
+    /// Validates and atomically writes the answer to `path` in the format of
+    /// its extension, keeping `<path>.bak` of the previous file when `keep_backup` is set.
+    pub fn to_path(
+        &self,
+        path: impl AsRef<Path>,
+        keep_backup: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        self.to_path_as(
+            path,
+            AnswerFormat::from_path(path).unwrap_or_default(),
+            keep_backup,
+        )
+    }
+
+    pub fn to_path_as(
+        &self,
+        path: impl AsRef<Path>,
+        format: AnswerFormat,
+        keep_backup: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let contents = self.to_string_as(format)?;
+        Ok(write_atomic(
+            path.as_ref(),
+            contents.as_bytes(),
+            keep_backup,
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pveauto-file-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /* ---------------- ATOMIC WRITES ---------------- */
+
+    #[test]
+    fn write_creates_parents_and_leaves_no_temp_file() {
+        let dir = temp_dir("parents");
+        let path = dir.join("nested/answer.toml");
+
+        write_atomic(&path, b"first", false).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first");
+
+        let entries: Vec<_> = std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, vec![OsString::from("answer.toml")]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn backup_keeps_previous_version() {
+        let dir = temp_dir("backup");
+        let path = dir.join("answer.toml");
+
+        write_atomic(&path, b"first", true).unwrap();
+        assert!(!backup_path(&path).exists());
+
+        write_atomic(&path, b"second", true).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(
+            std::fs::read_to_string(backup_path(&path)).unwrap(),
+            "first"
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /* ---------------- LOAD / SAVE ---------------- */
+
+    #[test]
+    fn round_trip_picks_format_from_extension() {
+        let dir = temp_dir("round-trip");
+        let answer = AnswerFile::default();
+
+        for name in ["answer.toml", "answer.json", "answer.yml", "answer"] {
+            let path = dir.join(name);
+            answer.to_path(&path, false).unwrap();
+            assert_eq!(AnswerFile::from_path(&path).unwrap(), answer);
+        }
+        assert!(
+            std::fs::read_to_string(dir.join("answer.json"))
+                .unwrap()
+                .starts_with('{')
+        );
+        assert!(
+            std::fs::read_to_string(dir.join("answer"))
+                .unwrap()
+                .contains("[global]")
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_answer_is_not_written() {
+        let dir = temp_dir("invalid");
+        let path = dir.join("answer.toml");
+
+        let mut answer = AnswerFile::default();
+        answer.global.fqdn = "nope".into();
+        assert!(answer.to_path(&path, false).is_err());
+        assert!(!path.exists());
+    }
+}
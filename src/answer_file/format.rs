@@ -0,0 +1,106 @@
+use crate::answer_file::{
+    encryption::ENCRYPTED_EXTENSION,
+    file::BACKUP_EXTENSION,
+    macros::{config_error_enum, string_enum},
+};
+use std::path::Path;
+
+/* ===================== ANSWER FORMAT ERROR ===================== */
+
+config_error_enum!(
+    #[derive(Debug, PartialEq)]
+    pub enum AnswerFormatError {
+        Unknown => "format.invalid_format",
+    }
+);
+
+/* ===================== ANSWER FORMAT ===================== */
+
+string_enum!(
+    /// Serialization format of an answer document.
+    ///
+    /// TOML is the canonical format consumed by the Proxmox installer;
+    /// the others are converted losslessly through [`AnswerFile`](crate::answer_file::AnswerFile).
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    pub enum AnswerFormat {
+        #[default]
+        Toml => "toml",
+        Json => "json",
+        Yaml => "yaml",
+    },
+    AnswerFormatError,
+    AnswerFormatError::Unknown
+);
+
+impl AnswerFormat {
+    /// Format named by the extension of `path` (`.toml`, `.json`, `.yaml`/`.yml`),
+    /// ignoring case and a trailing encryption or backup suffix.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let mut path = path.to_path_buf();
+        while path
+            .extension()
+            .is_some_and(|ext| ext == ENCRYPTED_EXTENSION || ext == BACKUP_EXTENSION)
+        {
+            path.set_extension("");
+        }
+
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "toml" => Some(Self::Toml),
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn default_is_toml() {
+        assert_eq!(AnswerFormat::default(), AnswerFormat::Toml);
+    }
+
+    #[test]
+    fn formats_parse_and_display() {
+        for (s, expected) in [
+            ("toml", AnswerFormat::Toml),
+            ("json", AnswerFormat::Json),
+            ("yaml", AnswerFormat::Yaml),
+        ] {
+            assert_eq!(AnswerFormat::from_str(s).unwrap(), expected);
+            assert_eq!(expected.to_string(), s);
+        }
+    }
+
+    #[test]
+    fn format_from_path_extension() {
+        for (path, expected) in [
+            ("answer.toml", Some(AnswerFormat::Toml)),
+            ("dir/answer.JSON", Some(AnswerFormat::Json)),
+            ("answer.yml", Some(AnswerFormat::Yaml)),
+            ("answer.yaml.age", Some(AnswerFormat::Yaml)),
+            ("answer.json.bak", Some(AnswerFormat::Json)),
+            ("answer", None),
+            ("answer.txt", None),
+        ] {
+            assert_eq!(
+                AnswerFormat::from_path(Path::new(path)),
+                expected,
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_format_fails() {
+        assert_eq!(
+            AnswerFormat::from_str("xml"),
+            Err(AnswerFormatError::Unknown)
+        );
+        assert_eq!(AnswerFormatError::Unknown.code(), "format.invalid_format");
+    }
+}
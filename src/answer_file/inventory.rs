@@ -0,0 +1,345 @@
+use crate::answer_file::{
+    answer::AnswerFile,
+    format::AnswerFormat,
+    sections::{DiskList, FqdnTemplate, HostIdentity, NetworkSource},
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+};
+
+/// Host names become file names, so only a conservative set is allowed.
+static HOST_NAME_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[A-Za-z0-9][A-Za-z0-9_.-]{0,63}$").expect("invalid HOST_NAME_PATTERN")
+});
+
+/// udev property matched against a host's MAC to pin the management NIC.
+pub const MAC_FILTER_PROPERTY: &str = "ID_NET_NAME_MAC";
+
+/* ===================== INVENTORY ERROR ===================== */
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InventoryError {
+    /// The inventory is not valid TOML or has unknown keys.
+    Parse(String),
+    /// The inventory lists no `[[host]]`.
+    Empty,
+    /// A host name is not usable as a file name.
+    InvalidHostName(String),
+    /// Two hosts share a name or an FQDN.
+    DuplicateHost(String),
+    /// A host's answer failed to render or validate; `detail` names the host.
+    InvalidAnswer(String),
+}
+
+impl InventoryError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Parse(_) => "inventory.invalid",
+            Self::Empty => "inventory.empty",
+            Self::InvalidHostName(_) => "inventory.invalid_host_name",
+            Self::DuplicateHost(_) => "inventory.duplicate_host",
+            Self::InvalidAnswer(_) => "inventory.invalid_answer",
+        }
+    }
+}
+
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str(self.code()),
+            Self::Parse(detail)
+            | Self::InvalidHostName(detail)
+            | Self::DuplicateHost(detail)
+            | Self::InvalidAnswer(detail) => write!(f, "{} ({})", self.code(), detail),
+        }
+    }
+}
+
+impl std::error::Error for InventoryError {}
+
+/* ===================== INVENTORY ===================== */
+
+/// A `hosts.toml` listing the machines to generate answers for:
+///
+/// ```toml
+/// fqdn = "pve-{mac6}.lab.local"   # default for hosts without their own
+///
+/// [[host]]
+/// mac = "bc:24:11:0a:00:01"
+///
+/// [[host]]
+/// name = "storage01"
+/// fqdn = "storage01.lab.local"
+/// mac = "bc:24:11:0a:00:02"
+/// disk-list = ["nvme0n1", "nvme1n1"]
+/// vars = { rack = "b2" }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Inventory {
+    /// [`FqdnTemplate`] used for hosts without their own `fqdn`.
+    #[serde(default)]
+    pub fqdn: Option<String>,
+    #[serde(default, rename = "host")]
+    pub hosts: Vec<InventoryHost>,
+}
+
+/// One `[[host]]`: the values that differ from the base answer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InventoryHost {
+    /// Output file name without extension; defaults to the host's FQDN.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// [`FqdnTemplate`] for this host, overriding the inventory's.
+    #[serde(default)]
+    pub fqdn: Option<String>,
+    /// Management NIC MAC, for `{mac}`/`{mac6}` and the network filter.
+    #[serde(default)]
+    pub mac: Option<String>,
+    /// Hardware serial, for `{serial}`.
+    #[serde(default)]
+    pub serial: Option<String>,
+    /// Replaces the base answer's disk selection.
+    #[serde(default, rename = "disk-list")]
+    pub disk_list: Option<Vec<String>>,
+    /// Values for `{{name}}` placeholders in the base answer.
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+}
+
+/// A validated answer for one inventory host.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedAnswer {
+    pub name: String,
+    pub answer: AnswerFile,
+}
+
+impl Inventory {
+    pub fn from_toml_str(s: &str) -> Result<Self, InventoryError> {
+        let inventory: Self =
+            toml::from_str(s).map_err(|e| InventoryError::Parse(e.to_string()))?;
+        if inventory.hosts.is_empty() {
+            return Err(InventoryError::Empty);
+        }
+        Ok(inventory)
+    }
+
+    /// Builds one answer per host from the base answer `template` in `format`.
+    ///
+    /// For each host, `template` is rendered with the host's `vars`, then:
+    /// - `fqdn` (or the inventory's) is expanded and replaces `global.fqdn`
+    /// - `mac` pins the NIC through `filter.ID_NET_NAME_MAC` when the base
+    ///   network `source` is `from-answer`
+    /// - `disk-list` replaces the base `disk-list` or disk `filter`
+    ///
+    /// # Errors
+    /// Nothing is returned unless every host yields a valid answer under a
+    /// unique name and FQDN.
+    pub fn generate(
+        &self,
+        template: &str,
+        format: AnswerFormat,
+    ) -> Result<Vec<GeneratedAnswer>, InventoryError> {
+        let mut names = HashSet::new();
+        let mut fqdns = HashSet::new();
+        let mut generated = Vec::with_capacity(self.hosts.len());
+
+        for (index, host) in self.hosts.iter().enumerate() {
+            let label = host
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("host {}", index + 1));
+            let answer = self
+                .answer_for(host, template, format)
+                .map_err(|e| InventoryError::InvalidAnswer(format!("{}: {}", label, e)))?;
+
+            let name = host
+                .name
+                .clone()
+                .unwrap_or_else(|| answer.global.fqdn.clone());
+            if !HOST_NAME_PATTERN.is_match(&name) {
+                return Err(InventoryError::InvalidHostName(name));
+            }
+            if !names.insert(name.clone()) {
+                return Err(InventoryError::DuplicateHost(name));
+            }
+            if !fqdns.insert(answer.global.fqdn.clone()) {
+                return Err(InventoryError::DuplicateHost(answer.global.fqdn));
+            }
+
+            generated.push(GeneratedAnswer { name, answer });
+        }
+
+        Ok(generated)
+    }
+
+    fn answer_for(
+        &self,
+        host: &InventoryHost,
+        template: &str,
+        format: AnswerFormat,
+    ) -> Result<AnswerFile, Box<dyn std::error::Error>> {
+        let mut answer = AnswerFile::from_template_str(template, &host.vars, format)?;
+        let identity = HostIdentity {
+            mac: host.mac.clone(),
+            serial: host.serial.clone(),
+        };
+
+        if let Some(fqdn) = host.fqdn.as_ref().or(self.fqdn.as_ref()) {
+            answer.global.fqdn = fqdn.parse::<FqdnTemplate>()?.expand(&identity)?;
+        }
+
+        if identity.mac.is_some() && answer.network.source == NetworkSource::FromAnswer {
+            let filter = format!("*{}", identity.mac_digits()?);
+            answer.network.filter = Some(BTreeMap::from([(MAC_FILTER_PROPERTY.into(), filter)]));
+        }
+
+        if let Some(disks) = &host.disk_list {
+            answer.disk_setup.disk_list = Some(DiskList::try_new(disks)?);
+            answer.disk_setup.filter = None;
+            answer.disk_setup.filter_match = None;
+        }
+
+        answer.validate()?;
+        Ok(answer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = r#"
+[global]
+fqdn = "pve.lab.local"
+mailto = "{{contact}}@lab.local"
+
+[network]
+source = "from-answer"
+cidr = "10.0.0.10/24"
+dns = "10.0.0.1"
+gateway = "10.0.0.1"
+
+[disk-setup]
+filesystem = "ext4"
+disk-list = ["sda"]
+"#;
+
+    const HOSTS: &str = r#"
+fqdn = "pve-{mac6}.lab.local"
+
+[[host]]
+mac = "BC:24:11:0A:00:01"
+vars = { contact = "ops" }
+
+[[host]]
+name = "storage01"
+fqdn = "storage01.lab.local"
+mac = "bc:24:11:0a:00:02"
+disk-list = ["nvme0n1", "nvme1n1"]
+vars = { contact = "storage" }
+"#;
+
+    /* ---------------- PARSING ---------------- */
+
+    #[test]
+    fn inventory_parses_hosts() {
+        let inventory = Inventory::from_toml_str(HOSTS).unwrap();
+        assert_eq!(inventory.fqdn.as_deref(), Some("pve-{mac6}.lab.local"));
+        assert_eq!(inventory.hosts.len(), 2);
+        assert_eq!(inventory.hosts[1].name.as_deref(), Some("storage01"));
+    }
+
+    #[test]
+    fn empty_or_misspelled_inventories_fail() {
+        assert_eq!(Inventory::from_toml_str(""), Err(InventoryError::Empty));
+        let err = Inventory::from_toml_str("[[host]]\nmacc = \"x\"\n").unwrap_err();
+        assert_eq!(err.code(), "inventory.invalid");
+    }
+
+    /* ---------------- GENERATION ---------------- */
+
+    #[test]
+    fn hosts_get_their_own_overrides() {
+        let inventory = Inventory::from_toml_str(HOSTS).unwrap();
+        let generated = inventory.generate(BASE, AnswerFormat::Toml).unwrap();
+
+        let names: Vec<_> = generated.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, ["pve-0a0001.lab.local", "storage01"]);
+
+        let first = &generated[0].answer;
+        assert_eq!(first.global.mailto, "ops@lab.local");
+        assert_eq!(
+            first.network.filter,
+            Some(BTreeMap::from([(
+                MAC_FILTER_PROPERTY.to_string(),
+                "*bc24110a0001".to_string()
+            )]))
+        );
+        assert_eq!(
+            first.disk_setup.disk_list.as_ref().unwrap().as_slice(),
+            ["sda"]
+        );
+
+        let storage = &generated[1].answer;
+        assert_eq!(storage.global.fqdn, "storage01.lab.local");
+        assert_eq!(
+            storage.disk_setup.disk_list.as_ref().unwrap().as_slice(),
+            ["nvme0n1", "nvme1n1"]
+        );
+    }
+
+    #[test]
+    fn dhcp_hosts_keep_the_base_network() {
+        let (head, _) = BASE.split_once("[network]").unwrap();
+        let base = format!(
+            "{}[network]\nsource = \"from-dhcp\"\n\n[disk-setup]\ndisk-list = [\"sda\"]\n",
+            head
+        );
+        let inventory = Inventory::from_toml_str(HOSTS).unwrap();
+        let generated = inventory.generate(&base, AnswerFormat::Toml).unwrap();
+        assert!(generated.iter().all(|g| g.answer.network.filter.is_none()));
+    }
+
+    #[test]
+    fn invalid_hosts_are_named_in_errors() {
+        let hosts = HOSTS.replace("[\"nvme0n1\", \"nvme1n1\"]", "[\"nvme0n1\", \"nvme0n1\"]");
+        let err = Inventory::from_toml_str(&hosts)
+            .unwrap()
+            .generate(BASE, AnswerFormat::Toml)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "inventory.invalid_answer (storage01: disk_list.duplicate_entry)"
+        );
+
+        let hosts = HOSTS.replace("mac = \"BC:24:11:0A:00:01\"\n", "");
+        let err = Inventory::from_toml_str(&hosts)
+            .unwrap()
+            .generate(BASE, AnswerFormat::Toml)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "inventory.invalid_answer (host 1: fqdn.template.missing_value)"
+        );
+    }
+
+    #[test]
+    fn duplicate_hosts_fail() {
+        let hosts = HOSTS.replace("bc:24:11:0a:00:02", "bc:24:11:0a:00:01");
+        let hosts = hosts.replace("fqdn = \"storage01.lab.local\"\n", "");
+        let err = Inventory::from_toml_str(&hosts)
+            .unwrap()
+            .generate(BASE, AnswerFormat::Toml)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            InventoryError::DuplicateHost("pve-0a0001.lab.local".into())
+        );
+    }
+}
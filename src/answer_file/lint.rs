@@ -0,0 +1,164 @@
+use crate::answer_file::{
+    answer::AnswerFile,
+    macros::config_error_enum,
+    sections::{GlobalConfig, HASH_ROUNDS_RANGE},
+};
+
+/* ===================== LINT WARNING ===================== */
+
+config_error_enum!(
+    /// Risky-but-valid answer content. Codes are stable so CI can allow or deny them.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum LintWarning {
+        DefaultRootPassword => "root_password_hashed.default_placeholder",
+        LowHashRounds => "root_password_hashed.low_rounds",
+        DefaultFqdn => "fqdn.default_placeholder",
+        NoRootSshKeys => "root_ssh_keys.missing",
+        RebootOnError => "reboot_on_error.enabled",
+    }
+);
+
+impl LintWarning {
+    /// Every warning, in the order [`AnswerFile::lint`] reports them.
+    pub const ALL: [LintWarning; 5] = [
+        Self::DefaultRootPassword,
+        Self::LowHashRounds,
+        Self::DefaultFqdn,
+        Self::NoRootSshKeys,
+        Self::RebootOnError,
+    ];
+
+    /// Inverse of [`code`](LintWarning::code).
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|w| w.code() == code)
+    }
+
+    /// One-line explanation for humans, printed after the code.
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::DefaultRootPassword => "root-password-hashed is the built-in placeholder hash",
+            Self::LowHashRounds => "root-password-hashed uses fewer than 100000 rounds",
+            Self::DefaultFqdn => "fqdn is the default proxmox.lab.local",
+            Self::NoRootSshKeys => "no root-ssh-keys are set, only password login is possible",
+            Self::RebootOnError => "reboot-on-error is enabled, which hides failed installations",
+        }
+    }
+}
+
+/* ===================== LINT CHECKS ===================== */
+
+impl AnswerFile {
+    /// Warns about content that passes [`AnswerFile::validate`] but is likely a
+    /// mistake in production, in a fixed order. Does not validate.
+    pub fn lint(&self) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+        let global = &self.global;
+        let defaults = GlobalConfig::default();
+
+        if global.root_password_hashed == defaults.root_password_hashed {
+            warnings.push(LintWarning::DefaultRootPassword);
+        }
+        if hash_rounds(global.root_password_hashed.as_str())
+            .is_some_and(|rounds| rounds < *HASH_ROUNDS_RANGE.start())
+        {
+            warnings.push(LintWarning::LowHashRounds);
+        }
+        if global.fqdn.eq_ignore_ascii_case(&defaults.fqdn) {
+            warnings.push(LintWarning::DefaultFqdn);
+        }
+        if global.root_ssh_keys.as_ref().is_none_or(Vec::is_empty) {
+            warnings.push(LintWarning::NoRootSshKeys);
+        }
+        if global.reboot_on_error {
+            warnings.push(LintWarning::RebootOnError);
+        }
+
+        warnings
+    }
+}
+
+/// `N` of a SHA-crypt `$5$rounds=N$` / `$6$rounds=N$` hash.
+fn hash_rounds(hash: &str) -> Option<u32> {
+    let rest = hash
+        .strip_prefix("$6$rounds=")
+        .or_else(|| hash.strip_prefix("$5$rounds="))?;
+    rest.split('$').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl root@pve";
+
+    fn clean() -> AnswerFile {
+        let mut answer = AnswerFile::default();
+        answer.global.fqdn = "pve01.example.com".into();
+        answer.global.root_password_hashed =
+            format!("$6$rounds=656000$abcdefgh${}", "B".repeat(86))
+                .parse()
+                .unwrap();
+        answer.global.root_ssh_keys = Some(vec![KEY.into()]);
+        answer
+    }
+
+    #[test]
+    fn default_answer_warns_about_placeholders() {
+        assert_eq!(
+            AnswerFile::default().lint(),
+            vec![
+                LintWarning::DefaultRootPassword,
+                LintWarning::DefaultFqdn,
+                LintWarning::NoRootSshKeys,
+            ]
+        );
+    }
+
+    #[test]
+    fn customised_answer_is_clean() {
+        let answer = clean();
+        assert!(answer.validate().is_ok());
+        assert!(answer.lint().is_empty());
+    }
+
+    #[test]
+    fn low_rounds_and_reboot_on_error_warn() {
+        let mut answer = clean();
+        answer.global.root_password_hashed =
+            format!("$5$rounds=050000$abcdefgh${}", "B".repeat(43))
+                .parse()
+                .unwrap();
+        answer.global.reboot_on_error = true;
+        answer.global.root_ssh_keys = Some(Vec::new());
+
+        assert!(answer.validate().is_ok());
+        assert_eq!(
+            answer.lint(),
+            vec![
+                LintWarning::LowHashRounds,
+                LintWarning::NoRootSshKeys,
+                LintWarning::RebootOnError,
+            ]
+        );
+    }
+
+    #[test]
+    fn rounds_are_read_from_sha_crypt_hashes_only() {
+        assert_eq!(hash_rounds("$6$rounds=656000$salt$hash"), Some(656_000));
+        assert_eq!(hash_rounds("$5$rounds=099999$salt$hash"), Some(99_999));
+        assert_eq!(hash_rounds("$y$j9T$salt$hash"), None);
+    }
+
+    #[test]
+    fn codes_are_stable() {
+        assert_eq!(
+            LintWarning::DefaultRootPassword.code(),
+            "root_password_hashed.default_placeholder"
+        );
+        assert_eq!(
+            LintWarning::RebootOnError.to_string(),
+            "reboot_on_error.enabled"
+        );
+    }
+}
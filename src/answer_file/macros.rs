@@ -19,11 +19,14 @@
 /// - Invalid values fail **at deserialization time**, not later validation
 /// - TOML uses stable, spec-defined strings instead of Rust variant names
 ///
+/// Variants may carry their own attributes (e.g. `#[default]`).
+///
 /// ## Example
 /// ```rs
 /// string_enum!(
-///     #[derive(Debug, Clone, PartialEq, Eq)]
+///     #[derive(Debug, Clone, Default, PartialEq, Eq)]
 ///     pub enum RebootMode {
+///         #[default]
 ///         Reboot  => "reboot",
 ///         PowerOff => "power-off",
 ///     },
@@ -34,8 +37,9 @@
 ///
 /// ## Which Generates:
 /// ```rs
-/// #[derive(Debug, Clone, PartialEq, Eq)]
+/// #[derive(Debug, Clone, Default, PartialEq, Eq)]
 /// pub enum RebootMode {
+///     #[default]
 ///     Reboot,
 ///     PowerOff,
 /// }
@@ -80,14 +84,14 @@ macro_rules! string_enum {
     (
         $(#[$meta:meta])*
         $vis:vis enum $name:ident {
-            $($variant:ident => $str:expr),+ $(,)?
+            $($(#[$vmeta:meta])* $variant:ident => $str:expr),+ $(,)?
         },
         $err_ty:ty,
         $err_val:expr $(,)?
     ) => {
         $(#[$meta])*
         $vis enum $name {
-            $($variant),+
+            $($(#[$vmeta])* $variant),+
         }
 
         impl $name {
@@ -138,6 +142,20 @@ macro_rules! string_enum {
                 serializer.serialize_str(self.as_str())
             }
         }
+
+        /// JSON Schema: a string restricted to the canonical values.
+        impl ::schemars::JsonSchema for $name {
+            fn schema_name() -> ::std::borrow::Cow<'static, str> {
+                stringify!($name).into()
+            }
+
+            fn json_schema(_: &mut ::schemars::SchemaGenerator) -> ::schemars::Schema {
+                ::schemars::json_schema!({
+                    "type": "string",
+                    "enum": [$($str),+]
+                })
+            }
+        }
     };
 }
 
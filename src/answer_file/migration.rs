@@ -0,0 +1,368 @@
+use crate::answer_file::answer::AnswerFile;
+use std::{fmt, str::FromStr};
+use toml_edit::{DocumentMut, Item, Key, TableLike};
+
+/// Prefix of the comment line recording the schema version, e.g. `# pveauto-schema = 2`.
+///
+/// The installer ignores comments, so the marker is safe in any answer file.
+pub const SCHEMA_MARKER_PREFIX: &str = "# pveauto-schema =";
+
+/// Keys renamed when the installer switched from snake_case to kebab-case
+/// keys: `(table, old key, new key)`, with `table` a dotted path.
+const V1_RENAMES: [(&str, &str, &str); 7] = [
+    ("global", "root_password", "root-password"),
+    ("global", "root_password_hashed", "root-password-hashed"),
+    ("global", "root_ssh_keys", "root-ssh-keys"),
+    ("global", "reboot_on_error", "reboot-on-error"),
+    ("disk-setup", "disk_list", "disk-list"),
+    ("disk-setup", "filter_match", "filter-match"),
+    ("disk-setup.zfs", "arc_max", "arc-max"),
+];
+
+/* ===================== SCHEMA VERSION ===================== */
+
+/// Revision of the Proxmox answer file layout an answer was written against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SchemaVersion {
+    /// Proxmox VE 8.1 - 8.3: snake_case keys such as `disk_list` and `root_password`.
+    V1,
+    /// Proxmox VE 8.4 and later: kebab-case keys such as `disk-list`.
+    V2,
+}
+
+impl SchemaVersion {
+    /// The layout this crate reads and writes.
+    pub const CURRENT: Self = Self::V2;
+
+    pub fn number(self) -> u32 {
+        match self {
+            Self::V1 => 1,
+            Self::V2 => 2,
+        }
+    }
+
+    /// The `# pveauto-schema = N` marker line for this version.
+    pub fn marker(self) -> String {
+        format!("{} {}", SCHEMA_MARKER_PREFIX, self.number())
+    }
+
+    /// Version of the answer TOML `source`: its marker when present,
+    /// otherwise [`V1`](SchemaVersion::V1) if it uses any schema 1 key,
+    /// otherwise [`CURRENT`](SchemaVersion::CURRENT).
+    ///
+    /// Documents that do not parse are reported as current, so the parse
+    /// error surfaces unchanged.
+    pub fn detect(source: &str) -> Self {
+        let marked = source.lines().find_map(|line| {
+            line.trim()
+                .strip_prefix(SCHEMA_MARKER_PREFIX)
+                .and_then(|n| n.parse().ok())
+        });
+
+        match marked {
+            Some(version) => version,
+            None if !legacy_keys(source).is_empty() => Self::V1,
+            None => Self::CURRENT,
+        }
+    }
+}
+
+impl FromStr for SchemaVersion {
+    type Err = MigrationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().trim_start_matches(['v', 'V']) {
+            "1" => Ok(Self::V1),
+            "2" => Ok(Self::V2),
+            _ => Err(MigrationError::UnknownVersion(s.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.number())
+    }
+}
+
+/* ===================== MIGRATION ERROR ===================== */
+
+/// An answer file written against an older schema, or one that cannot be migrated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationError {
+    /// The file uses the given older schema; see [`AnswerFile::migrate_from`].
+    Outdated(SchemaVersion),
+    /// Both the old and the new name of a key are set, e.g. `disk_list` and `disk-list`.
+    Conflict(String),
+    /// A schema version other than those in [`SchemaVersion`].
+    UnknownVersion(String),
+}
+
+impl MigrationError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Outdated(_) => "schema.outdated",
+            Self::Conflict(_) => "schema.conflicting_keys",
+            Self::UnknownVersion(_) => "schema.unknown_version",
+        }
+    }
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Outdated(version) => write!(
+                f,
+                "{} (schema {}, run `pveauto answer migrate`)",
+                self.code(),
+                version
+            ),
+            Self::Conflict(key) | Self::UnknownVersion(key) => {
+                write!(f, "{} ({})", self.code(), key)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+/* ===================== MIGRATION ===================== */
+
+impl AnswerFile {
+    /// Upgrades the answer TOML `source`, written against schema `from`, to
+    /// [`SchemaVersion::CURRENT`] and parses it.
+    ///
+    /// # Errors
+    /// See [`AnswerFile::migrate_toml`]; the migrated file must also be a
+    /// valid answer file.
+    pub fn migrate_from(
+        source: &str,
+        from: SchemaVersion,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::from_toml_str(&Self::migrate_toml(source, from)?)
+    }
+
+    /// Renames the keys of schema `from` in `source` to their current names
+    /// and sets the schema marker, keeping values, comments and key order.
+    ///
+    /// # Errors
+    /// `source` must be valid TOML and must not set both the old and the new
+    /// name of a key ([`MigrationError::Conflict`]).
+    pub fn migrate_toml(
+        source: &str,
+        from: SchemaVersion,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let mut document: DocumentMut = source.parse()?;
+
+        if from < SchemaVersion::V2 {
+            for table in ["global", "disk-setup", "disk-setup.zfs"] {
+                let renames: Vec<_> = V1_RENAMES
+                    .iter()
+                    .filter(|(t, _, _)| *t == table)
+                    .map(|(_, old, new)| (*old, *new))
+                    .collect();
+                if let Some(target) = table_at(document.as_table_mut(), table) {
+                    rename_keys(target, table, &renames)?;
+                }
+            }
+        }
+
+        Ok(with_marker(&document.to_string(), SchemaVersion::CURRENT))
+    }
+}
+
+/// Dotted paths of every schema 1 key in `source`, e.g. `disk-setup.disk_list`.
+pub(crate) fn legacy_keys(source: &str) -> Vec<String> {
+    let Ok(root) = toml::from_str::<toml::Table>(source) else {
+        return Vec::new();
+    };
+
+    V1_RENAMES
+        .iter()
+        .filter(|(table, old, _)| {
+            table
+                .split('.')
+                .try_fold(&root, |t, key| t.get(key)?.as_table())
+                .is_some_and(|t| t.contains_key(*old))
+        })
+        .map(|(table, old, _)| format!("{}.{}", table, old))
+        .collect()
+}
+
+fn table_at<'a>(root: &'a mut dyn TableLike, path: &str) -> Option<&'a mut dyn TableLike> {
+    let mut table = root;
+    for key in path.split('.') {
+        table = table.get_mut(key)?.as_table_like_mut()?;
+    }
+    Some(table)
+}
+
+/// Renames `renames` keys of `table` in place.
+///
+/// Entries are removed and re-inserted in their original order, since
+/// `toml_edit` appends inserted keys; key decor (leading comments) is kept.
+pub(crate) fn rename_keys(
+    table: &mut dyn TableLike,
+    path: &str,
+    renames: &[(&str, &str)],
+) -> Result<(), MigrationError> {
+    if let Some((_, new)) = renames
+        .iter()
+        .find(|(old, new)| table.contains_key(old) && table.contains_key(new))
+    {
+        return Err(MigrationError::Conflict(format!("{}.{}", path, new)));
+    }
+    if !renames.iter().any(|(old, _)| table.contains_key(old)) {
+        return Ok(());
+    }
+
+    let keys: Vec<Key> = table
+        .iter()
+        .filter_map(|(key, _)| table.key(key).cloned())
+        .collect();
+    let entries: Vec<(Key, Item)> = keys
+        .into_iter()
+        .filter_map(|key| table.remove(key.get()).map(|item| (key, item)))
+        .collect();
+
+    for (key, item) in entries {
+        let key = match renames.iter().find(|(old, _)| *old == key.get()) {
+            Some((_, new)) => Key::new(*new)
+                .with_leaf_decor(key.leaf_decor().clone())
+                .with_dotted_decor(key.dotted_decor().clone()),
+            None => key,
+        };
+        table.entry_format(&key).or_insert(item);
+    }
+    Ok(())
+}
+
+/// Replaces any schema marker line in `source` with the one for `version`.
+fn with_marker(source: &str, version: SchemaVersion) -> String {
+    let body: String = source
+        .split_inclusive('\n')
+        .filter(|line| !line.trim().starts_with(SCHEMA_MARKER_PREFIX))
+        .collect();
+    format!("{}\n{}", version.marker(), body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "$6$rounds=656000$12345678$AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
+
+    fn v1_toml() -> String {
+        format!(
+            r#"[global]
+keyboard = "de"
+country = "de"
+fqdn = "pve01.lab.local"
+mailto = "admin@example.com"
+timezone = "Europe/Berlin"
+# generated with hash-password
+root_password_hashed = "{HASH}"
+reboot_on_error = true
+
+[network]
+source = "from-dhcp"
+
+[disk-setup]
+filesystem = "zfs"
+disk_list = ["sda", "sdb"] # both NVMe
+zfs.raid = "raid1"
+zfs.arc_max = 2048
+"#
+        )
+    }
+
+    /* ---------------- DETECTION ---------------- */
+
+    #[test]
+    fn legacy_keys_are_detected() {
+        let toml = v1_toml();
+        assert_eq!(SchemaVersion::detect(&toml), SchemaVersion::V1);
+        assert_eq!(
+            legacy_keys(&toml),
+            [
+                "global.root_password_hashed",
+                "global.reboot_on_error",
+                "disk-setup.disk_list",
+                "disk-setup.zfs.arc_max",
+            ]
+        );
+    }
+
+    #[test]
+    fn marker_wins_over_key_detection() {
+        let marked = format!("{}\n{}", SchemaVersion::V2.marker(), v1_toml());
+        assert_eq!(SchemaVersion::detect(&marked), SchemaVersion::V2);
+        assert_eq!(
+            SchemaVersion::detect("# pveauto-schema = 1\n[global]\n"),
+            SchemaVersion::V1
+        );
+        assert_eq!(SchemaVersion::detect("[global]\n"), SchemaVersion::CURRENT);
+        assert_eq!(SchemaVersion::detect("not toml ["), SchemaVersion::CURRENT);
+    }
+
+    #[test]
+    fn versions_parse_from_numbers() {
+        assert_eq!("1".parse(), Ok(SchemaVersion::V1));
+        assert_eq!("v2".parse(), Ok(SchemaVersion::V2));
+        assert_eq!(
+            "3".parse::<SchemaVersion>(),
+            Err(MigrationError::UnknownVersion("3".into()))
+        );
+    }
+
+    /* ---------------- MIGRATION ---------------- */
+
+    #[test]
+    fn outdated_file_fails_with_hint() {
+        let err = AnswerFile::from_toml_str(&v1_toml()).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<MigrationError>(),
+            Some(&MigrationError::Outdated(SchemaVersion::V1))
+        );
+        assert!(err.to_string().contains("answer migrate"), "{}", err);
+    }
+
+    #[test]
+    fn v1_file_migrates_keeping_comments_and_order() {
+        let migrated = AnswerFile::migrate_toml(&v1_toml(), SchemaVersion::V1).unwrap();
+        assert!(migrated.starts_with("# pveauto-schema = 2\n[global]\n"));
+        assert!(migrated.contains(&format!(
+            "# generated with hash-password\nroot-password-hashed = \"{HASH}\"\nreboot-on-error = true\n"
+        )));
+        assert!(migrated.contains(
+            "filesystem = \"zfs\"\ndisk-list = [\"sda\", \"sdb\"] # both NVMe\nzfs.raid = \"raid1\"\nzfs.arc-max = 2048\n"
+        ));
+
+        let answer = AnswerFile::migrate_from(&v1_toml(), SchemaVersion::V1).unwrap();
+        assert!(answer.global.reboot_on_error);
+        assert_eq!(answer.disk_setup.zfs.unwrap().arc_max, Some(2048));
+        assert_eq!(
+            AnswerFile::from_toml_str(&migrated).unwrap().global.fqdn,
+            "pve01.lab.local"
+        );
+    }
+
+    #[test]
+    fn current_file_only_gains_marker() {
+        let migrated = AnswerFile::migrate_toml(&v1_toml(), SchemaVersion::V1).unwrap();
+        assert_eq!(
+            AnswerFile::migrate_toml(&migrated, SchemaVersion::V2).unwrap(),
+            migrated
+        );
+    }
+
+    #[test]
+    fn conflicting_names_are_rejected() {
+        let toml = v1_toml().replace("zfs.raid", "disk-list = [\"sdc\"]\nzfs.raid");
+        let err = AnswerFile::migrate_toml(&toml, SchemaVersion::V1).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<MigrationError>(),
+            Some(&MigrationError::Conflict("disk-setup.disk-list".into()))
+        );
+    }
+}
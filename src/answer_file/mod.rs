@@ -1,2 +1,46 @@
+pub mod answer;
+pub mod assistant;
+pub mod builder;
+pub mod canonical;
+pub mod diagnostics;
+pub mod diff;
+pub mod edit;
+pub mod encryption;
+pub mod errors;
+pub mod file;
+pub mod format;
+pub mod inventory;
+pub mod lint;
 mod macros;
+pub mod migration;
+pub mod presets;
+pub mod profiles;
+pub mod secrets;
 pub mod sections;
+pub mod semantic;
+pub mod signing;
+pub mod strict;
+pub mod template;
+
+pub use answer::AnswerFile;
+pub use assistant::{Assistant, diagnose_str_with_assistant};
+pub use builder::AnswerFileBuilder;
+pub use canonical::SECTION_ORDER;
+pub use diagnostics::{
+    AnswerDiagnostic, diagnose_json_str, diagnose_str, diagnose_str_strict, diagnose_toml_str,
+    diagnose_yaml_str,
+};
+pub use diff::{AnswerChange, ChangeKind};
+pub use encryption::{AgeIdentity, AgeRecipients, EncryptionError};
+pub use errors::AnswerFileError;
+pub use format::AnswerFormat;
+pub use inventory::{GeneratedAnswer, Inventory, InventoryError};
+pub use lint::LintWarning;
+pub use migration::{MigrationError, SchemaVersion};
+pub use presets::{AnswerPreset, PresetError};
+pub use profiles::{ProfileError, ProfileStore};
+pub use secrets::SecretError;
+pub use semantic::SemanticError;
+pub use signing::{SignatureError, TrustedKeys};
+pub use strict::UnknownKeyError;
+pub use template::{TemplateError, render_template};
@@ -0,0 +1,126 @@
+use crate::answer_file::{
+    answer::AnswerFile,
+    macros::{config_error_enum, string_enum},
+    sections::{
+        Filesystem, RebootMode, ZfsAshift, ZfsChecksum, ZfsCompress, ZfsOptions, ZfsRaidLevel,
+    },
+};
+
+/* ===================== PRESET ERROR ===================== */
+
+config_error_enum!(
+    #[derive(Debug, PartialEq)]
+    pub enum PresetError {
+        Unknown => "preset.unknown",
+    }
+);
+
+/* ===================== ANSWER PRESET ===================== */
+
+string_enum!(
+    /// Built-in starting points for `answer new --preset`.
+    ///
+    /// Every preset is a valid [`AnswerFile`]; the FQDN, mail address,
+    /// password hash and SSH keys are still the placeholders to replace.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum AnswerPreset {
+        LabZfsMirror => "lab-zfs-mirror",
+        SingleDiskExt4 => "single-disk-ext4",
+        ProductionHardened => "production-hardened",
+    },
+    PresetError,
+    PresetError::Unknown
+);
+
+impl AnswerPreset {
+    pub const ALL: [AnswerPreset; 3] = [
+        Self::LabZfsMirror,
+        Self::SingleDiskExt4,
+        Self::ProductionHardened,
+    ];
+
+    /// One-line summary for listings.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::LabZfsMirror => "ZFS mirror (raid1) on sda and sdb, lz4 compression, DHCP",
+            Self::SingleDiskExt4 => "ext4 on sda, DHCP",
+            Self::ProductionHardened => {
+                "ZFS mirror with sha256 checksums and zstd, powers off instead of rebooting"
+            }
+        }
+    }
+
+    /// Builds the preset's answer file.
+    pub fn answer(&self) -> AnswerFile {
+        let builder = AnswerFile::builder().dhcp_network();
+
+        let builder = match self {
+            Self::LabZfsMirror => builder.disks(["sda", "sdb"]).zfs_options(ZfsOptions {
+                raid: Some(ZfsRaidLevel::Raid1),
+                ashift: Some(ZfsAshift::try_new(12).expect("valid ashift")),
+                compress: Some(ZfsCompress::Lz4),
+                ..Default::default()
+            }),
+            Self::SingleDiskExt4 => builder.disks(["sda"]).filesystem(Filesystem::Ext4),
+            Self::ProductionHardened => builder
+                .disks(["sda", "sdb"])
+                .zfs_options(ZfsOptions {
+                    raid: Some(ZfsRaidLevel::Raid1),
+                    ashift: Some(ZfsAshift::try_new(12).expect("valid ashift")),
+                    compress: Some(ZfsCompress::Zstd),
+                    checksum: Some(ZfsChecksum::Sha256),
+                    copies: Some(2),
+                    ..Default::default()
+                })
+                .reboot_on_error(false)
+                .reboot_mode(RebootMode::PowerOff),
+        };
+
+        builder.build().expect("built-in presets are valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn every_preset_is_valid_and_round_trips() {
+        for preset in AnswerPreset::ALL {
+            let answer = preset.answer();
+            assert!(answer.validate().is_ok(), "{}", preset);
+
+            let toml = answer.to_toml_string().unwrap();
+            assert_eq!(AnswerFile::from_toml_str_strict(&toml).unwrap(), answer);
+            assert_eq!(AnswerPreset::from_str(preset.as_str()), Ok(preset));
+        }
+    }
+
+    #[test]
+    fn presets_differ_in_disk_setup() {
+        let lab = AnswerPreset::LabZfsMirror.answer();
+        assert_eq!(lab.disk_setup.filesystem, Filesystem::Zfs);
+        assert_eq!(lab.disk_setup.zfs.unwrap().raid, Some(ZfsRaidLevel::Raid1));
+
+        let single = AnswerPreset::SingleDiskExt4.answer();
+        assert_eq!(single.disk_setup.filesystem, Filesystem::Ext4);
+        assert!(single.disk_setup.zfs.is_none());
+
+        let hardened = AnswerPreset::ProductionHardened.answer();
+        assert_eq!(hardened.global.reboot_mode, RebootMode::PowerOff);
+        assert_eq!(
+            hardened.disk_setup.zfs.unwrap().checksum,
+            Some(ZfsChecksum::Sha256)
+        );
+    }
+
+    #[test]
+    fn unknown_preset_fails() {
+        assert_eq!(
+            AnswerPreset::from_str("raid-everything"),
+            Err(PresetError::Unknown)
+        );
+        assert_eq!(PresetError::Unknown.code(), "preset.unknown");
+    }
+}
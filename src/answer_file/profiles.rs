@@ -0,0 +1,357 @@
+use crate::answer_file::{
+    answer::AnswerFile,
+    encryption::{AgeRecipients, ENCRYPTED_EXTENSION, EncryptionError, encrypt, is_encrypted},
+    file::write_atomic,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+/// Profile names become file names, so only a conservative set is allowed.
+static PROFILE_NAME_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[A-Za-z0-9][A-Za-z0-9_.-]{0,63}$").expect("invalid PROFILE_NAME_PATTERN")
+});
+
+/// Prefix that marks a path argument as a profile reference, e.g. `@homelab`.
+pub const PROFILE_REFERENCE_PREFIX: char = '@';
+
+/* ===================== PROFILE ERROR ===================== */
+
+#[derive(Debug)]
+pub enum ProfileError {
+    InvalidName(String),
+    NotFound(String),
+    /// The stored or supplied answer failed to parse or validate.
+    InvalidAnswer(String),
+    /// Encrypting the profile failed, or it is encrypted and must be decrypted first.
+    Encryption(EncryptionError),
+    Io(io::Error),
+}
+
+impl ProfileError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidName(_) => "profile.invalid_name",
+            Self::NotFound(_) => "profile.not_found",
+            Self::InvalidAnswer(_) => "profile.invalid_answer",
+            Self::Encryption(e) => e.code(),
+            Self::Io(_) => "profile.io_error",
+        }
+    }
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidName(detail) | Self::NotFound(detail) | Self::InvalidAnswer(detail) => {
+                write!(f, "{} ({})", self.code(), detail)
+            }
+            Self::Encryption(e) => write!(f, "{}", e),
+            Self::Io(e) => write!(f, "{} ({})", self.code(), e),
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+impl From<io::Error> for ProfileError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<EncryptionError> for ProfileError {
+    fn from(e: EncryptionError) -> Self {
+        Self::Encryption(e)
+    }
+}
+
+/* ===================== PROFILE STORE ===================== */
+
+/// Named answer files stored as `<root>/<name>.toml`, or `<root>/<name>.toml.age`
+/// when saved encrypted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileStore {
+    root: PathBuf,
+}
+
+impl Default for ProfileStore {
+    fn default() -> Self {
+        Self::new(Self::default_root())
+    }
+}
+
+impl ProfileStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Returns the user-writable profile directory (XDG compliant):
+    /// `$XDG_DATA_HOME/pve-auto/profiles`, falling back to
+    /// `~/.local/share/pve-auto/profiles`.
+    pub fn default_root() -> PathBuf {
+        if let Ok(dir) = std::env::var("XDG_DATA_HOME") {
+            PathBuf::from(dir).join("pve-auto/profiles")
+        } else if let Ok(home) = std::env::var("HOME") {
+            PathBuf::from(home).join(".local/share/pve-auto/profiles")
+        } else {
+            PathBuf::from("pve-auto-profiles")
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Path of the profile file for `name` (which need not exist).
+    pub fn path(&self, name: &str) -> Result<PathBuf, ProfileError> {
+        if !PROFILE_NAME_PATTERN.is_match(name) {
+            return Err(ProfileError::InvalidName(name.to_owned()));
+        }
+        Ok(self.root.join(format!("{}.toml", name)))
+    }
+
+    /// Path of the encrypted profile file for `name` (which need not exist).
+    pub fn encrypted_path(&self, name: &str) -> Result<PathBuf, ProfileError> {
+        let mut path = self.path(name)?.into_os_string();
+        path.push(".");
+        path.push(ENCRYPTED_EXTENSION);
+        Ok(path.into())
+    }
+
+    /// Path of the existing file for `name`, plain or encrypted.
+    pub fn stored_path(&self, name: &str) -> Result<PathBuf, ProfileError> {
+        [self.path(name)?, self.encrypted_path(name)?]
+            .into_iter()
+            .find(|p| p.is_file())
+            .ok_or_else(|| ProfileError::NotFound(name.to_owned()))
+    }
+
+    /// Validates `answer` and stores it as `name`, replacing any previous version.
+    pub fn save(&self, name: &str, answer: &AnswerFile) -> Result<PathBuf, ProfileError> {
+        let toml = answer
+            .to_toml_string()
+            .map_err(|e| ProfileError::InvalidAnswer(e.to_string()))?;
+        self.write(
+            self.path(name)?,
+            self.encrypted_path(name)?,
+            toml.as_bytes(),
+        )
+    }
+
+    /// Like [`save`](ProfileStore::save), but encrypts the TOML for `to` and
+    /// stores it as `<name>.toml.age`.
+    pub fn save_encrypted(
+        &self,
+        name: &str,
+        answer: &AnswerFile,
+        to: &AgeRecipients,
+    ) -> Result<PathBuf, ProfileError> {
+        let toml = answer
+            .to_toml_string()
+            .map_err(|e| ProfileError::InvalidAnswer(e.to_string()))?;
+        let encrypted = encrypt(&toml, to)?;
+        self.write(self.encrypted_path(name)?, self.path(name)?, &encrypted)
+    }
+
+    /// Writes `contents` to `path` and drops the other variant `stale` so a
+    /// name never has both a plain and an encrypted copy.
+    fn write(
+        &self,
+        path: PathBuf,
+        stale: PathBuf,
+        contents: &[u8],
+    ) -> Result<PathBuf, ProfileError> {
+        write_atomic(&path, contents, false)?;
+        match std::fs::remove_file(&stale) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        Ok(path)
+    }
+
+    /// Loads and validates the profile `name`.
+    pub fn load(&self, name: &str) -> Result<AnswerFile, ProfileError> {
+        AnswerFile::from_toml_str(&self.read(name)?)
+            .map_err(|e| ProfileError::InvalidAnswer(e.to_string()))
+    }
+
+    /// Returns the stored TOML of profile `name` as written.
+    ///
+    /// Encrypted profiles fail with `encryption.identity_required`; read
+    /// [`stored_path`](ProfileStore::stored_path) and decrypt it instead.
+    pub fn read(&self, name: &str) -> Result<String, ProfileError> {
+        let bytes = std::fs::read(self.stored_path(name)?)?;
+        if is_encrypted(&bytes) {
+            return Err(EncryptionError::IdentityRequired.into());
+        }
+        String::from_utf8(bytes)
+            .map_err(|e| ProfileError::Io(io::Error::new(io::ErrorKind::InvalidData, e)))
+    }
+
+    /// Sorted names of all stored profiles; an absent directory has none.
+    pub fn list(&self) -> Result<Vec<String>, ProfileError> {
+        let entries = match std::fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut names = Vec::new();
+        let encrypted_suffix = format!(".toml.{}", ENCRYPTED_EXTENSION);
+        for entry in entries {
+            let file_name = entry?.file_name();
+            let Some(file_name) = file_name.to_str() else {
+                continue;
+            };
+            if let Some(name) = file_name
+                .strip_suffix(encrypted_suffix.as_str())
+                .or_else(|| file_name.strip_suffix(".toml"))
+                && PROFILE_NAME_PATTERN.is_match(name)
+            {
+                names.push(name.to_owned());
+            }
+        }
+
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), ProfileError> {
+        std::fs::remove_file(self.stored_path(name)?)?;
+        Ok(())
+    }
+
+    /// Resolves a command-line path argument: `@name` refers to a stored
+    /// profile (plain or encrypted), anything else is returned unchanged.
+    pub fn resolve(&self, arg: &str) -> Result<Option<PathBuf>, ProfileError> {
+        match arg.strip_prefix(PROFILE_REFERENCE_PREFIX) {
+            Some(name) => match self.stored_path(name) {
+                Err(ProfileError::NotFound(_)) => self.path(name).map(Some),
+                other => other.map(Some),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store(name: &str) -> ProfileStore {
+        let root =
+            std::env::temp_dir().join(format!("pveauto-profiles-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&root);
+        ProfileStore::new(root)
+    }
+
+    /* ---------------- NAMES ---------------- */
+
+    #[test]
+    fn names_cannot_escape_the_store() {
+        let store = temp_store("names");
+        for name in ["../etc", "a/b", "", ".hidden", "white space"] {
+            assert!(
+                matches!(store.path(name), Err(ProfileError::InvalidName(_))),
+                "accepted: {:?}",
+                name
+            );
+        }
+        assert!(store.path("prod-zfs").unwrap().ends_with("prod-zfs.toml"));
+    }
+
+    /* ---------------- LIFECYCLE ---------------- */
+
+    #[test]
+    fn save_list_load_delete() {
+        let store = temp_store("lifecycle");
+        assert!(store.list().unwrap().is_empty());
+
+        let answer = AnswerFile::default();
+        store.save("prod-zfs", &answer).unwrap();
+        store.save("homelab", &answer).unwrap();
+
+        assert_eq!(store.list().unwrap(), vec!["homelab", "prod-zfs"]);
+        assert_eq!(store.load("homelab").unwrap(), answer);
+        assert!(store.read("homelab").unwrap().contains("[global]"));
+
+        store.delete("homelab").unwrap();
+        assert_eq!(store.list().unwrap(), vec!["prod-zfs"]);
+        assert!(matches!(
+            store.load("homelab"),
+            Err(ProfileError::NotFound(_))
+        ));
+        assert!(matches!(
+            store.delete("homelab"),
+            Err(ProfileError::NotFound(_))
+        ));
+
+        std::fs::remove_dir_all(store.root()).unwrap();
+    }
+
+    #[test]
+    fn invalid_answers_are_not_saved() {
+        let store = temp_store("invalid");
+        let mut answer = AnswerFile::default();
+        answer.global.fqdn = "nope".into();
+
+        let err = store.save("bad", &answer).unwrap_err();
+        assert_eq!(err.code(), "profile.invalid_answer");
+        assert!(store.list().unwrap().is_empty());
+    }
+
+    /* ---------------- ENCRYPTION ---------------- */
+
+    #[test]
+    fn encrypted_profiles_replace_plain_ones() {
+        use crate::answer_file::encryption::{AgeIdentity, decrypt};
+        use age::{secrecy::ExposeSecret, x25519};
+
+        let store = temp_store("encrypted");
+        let identity = x25519::Identity::generate();
+        let to = AgeRecipients::parse_keys([identity.to_public().to_string()]).unwrap();
+        let answer = AnswerFile::default();
+
+        store.save("homelab", &answer).unwrap();
+        let saved = store.save_encrypted("homelab", &answer, &to).unwrap();
+        assert!(saved.ends_with("homelab.toml.age"));
+        assert!(!store.path("homelab").unwrap().exists());
+        assert_eq!(store.list().unwrap(), vec!["homelab"]);
+        assert_eq!(store.stored_path("homelab").unwrap(), saved);
+        assert_eq!(store.resolve("@homelab").unwrap(), Some(saved.clone()));
+        assert_eq!(
+            store.read("homelab").unwrap_err().code(),
+            "encryption.identity_required"
+        );
+
+        let key = store.root().join("key.txt");
+        std::fs::write(&key, identity.to_string().expose_secret()).unwrap();
+        let toml = decrypt(&std::fs::read(&saved).unwrap(), &AgeIdentity::file(&key)).unwrap();
+        assert_eq!(AnswerFile::from_toml_str(&toml).unwrap(), answer);
+
+        store.save("homelab", &answer).unwrap();
+        assert!(!saved.exists());
+        store.delete("homelab").unwrap();
+        assert!(store.list().unwrap().is_empty());
+
+        std::fs::remove_dir_all(store.root()).unwrap();
+    }
+
+    /* ---------------- REFERENCES ---------------- */
+
+    #[test]
+    fn references_resolve_to_profile_paths() {
+        let store = temp_store("refs");
+        assert_eq!(
+            store.resolve("@homelab").unwrap(),
+            Some(store.root().join("homelab.toml"))
+        );
+        assert_eq!(store.resolve("./answer.toml").unwrap(), None);
+        assert!(store.resolve("@../x").is_err());
+    }
+}
@@ -0,0 +1,248 @@
+use crate::answer_file::{answer::AnswerFile, format::AnswerFormat};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fmt;
+use toml_edit::{DocumentMut, Item, Value};
+
+/// Opening of an environment placeholder, e.g. `${env:ROOT_HASH}`.
+pub const ENV_PLACEHOLDER_PREFIX: &str = "${env:";
+
+/// `(table, key)` of the fields whose `${env:NAME}` placeholders are resolved.
+pub const SECRET_FIELDS: [(&str, &str); 2] = [
+    ("global", "root-password-hashed"),
+    ("global", "root-ssh-keys"),
+];
+
+/// Allowed environment variable names, e.g. `PVE_ROOT_HASH`.
+static ENV_NAME_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").expect("invalid ENV_NAME_PATTERN"));
+
+/* ===================== SECRET ERROR ===================== */
+
+/// Failure while resolving `${env:NAME}` placeholders in secret fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretError {
+    /// The named variable is not set (or not valid UTF-8).
+    Unset(String),
+    /// A placeholder name is not `[A-Za-z_][A-Za-z0-9_]*`, or `${env:` has no closing `}`.
+    InvalidName(String),
+}
+
+impl SecretError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Unset(_) => "env.unset_variable",
+            Self::InvalidName(_) => "env.invalid_variable_name",
+        }
+    }
+}
+
+impl fmt::Display for SecretError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unset(name) | Self::InvalidName(name) => write!(f, "{} ({})", self.code(), name),
+        }
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+/* ===================== RESOLUTION ===================== */
+
+impl AnswerFile {
+    /// Replaces `${env:NAME}` placeholders in the [`SECRET_FIELDS`] of the
+    /// answer TOML `source` with the values of the process environment.
+    ///
+    /// See [`resolve_secrets_with`](AnswerFile::resolve_secrets_with).
+    pub fn resolve_secrets(source: &str) -> Result<String, Box<dyn std::error::Error>> {
+        Self::resolve_secrets_with(source, |name| std::env::var(name).ok())
+    }
+
+    /// Replaces `${env:NAME}` placeholders in the [`SECRET_FIELDS`] of the
+    /// answer TOML `source` with `lookup(NAME)`, so hashes and keys can stay
+    /// out of the file that is committed.
+    ///
+    /// Only those fields are touched: comments, layout and line numbers of
+    /// `source` are kept, and placeholders anywhere else are left as written.
+    /// The result is not validated.
+    ///
+    /// # Errors
+    /// [`SecretError::Unset`] when `lookup` has no value for a placeholder,
+    /// [`SecretError::InvalidName`] for a malformed one.
+    pub fn resolve_secrets_with<F>(
+        source: &str,
+        lookup: F,
+    ) -> Result<String, Box<dyn std::error::Error>>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        if !source.contains(ENV_PLACEHOLDER_PREFIX) {
+            return Ok(source.to_owned());
+        }
+
+        let mut document: DocumentMut = source.parse()?;
+        for (table, key) in SECRET_FIELDS {
+            let Some(item) = document
+                .get_mut(table)
+                .and_then(Item::as_table_like_mut)
+                .and_then(|t| t.get_mut(key))
+            else {
+                continue;
+            };
+
+            match item.as_value_mut() {
+                Some(Value::Array(values)) => {
+                    for value in values.iter_mut() {
+                        resolve_value(value, &lookup)?;
+                    }
+                }
+                Some(value) => resolve_value(value, &lookup)?,
+                None => {}
+            }
+        }
+        Ok(document.to_string())
+    }
+
+    /// [`AnswerFile::from_str_as`] after [`resolve_secrets`](AnswerFile::resolve_secrets);
+    /// JSON and YAML are parsed as given.
+    pub fn from_str_with_secrets(
+        s: &str,
+        format: AnswerFormat,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        match format {
+            AnswerFormat::Toml => Self::from_toml_str(&Self::resolve_secrets(s)?),
+            _ => Self::from_str_as(s, format),
+        }
+    }
+}
+
+/// Resolves the placeholders of a string `value`, keeping its comments.
+fn resolve_value<F>(value: &mut Value, lookup: &F) -> Result<(), SecretError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let Some(text) = value.as_str() else {
+        return Ok(());
+    };
+    if !text.contains(ENV_PLACEHOLDER_PREFIX) {
+        return Ok(());
+    }
+
+    let resolved = expand(text, lookup)?;
+    let decor = value.decor().clone();
+    *value = resolved.into();
+    *value.decor_mut() = decor;
+    Ok(())
+}
+
+/// Expands every `${env:NAME}` in `text`; values are inserted verbatim.
+fn expand<F>(text: &str, lookup: &F) -> Result<String, SecretError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find(ENV_PLACEHOLDER_PREFIX) {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + ENV_PLACEHOLDER_PREFIX.len()..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| SecretError::InvalidName(rest[start..].to_owned()))?;
+
+        let name = &after[..end];
+        if !ENV_NAME_PATTERN.is_match(name) {
+            return Err(SecretError::InvalidName(name.to_owned()));
+        }
+        let value = lookup(name).ok_or_else(|| SecretError::Unset(name.to_owned()))?;
+        out.push_str(&value);
+        rest = &after[end + 1..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "$6$rounds=656000$12345678$AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
+    const KEY: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIBJm0uQAzvhAQECdsq4DlG4cj0+VibMUZNs0gE7YmMp/ admin@lab";
+
+    const SOURCE: &str = r#"
+[global]
+fqdn = "pve01.lab.local"
+root-password-hashed = "${env:PVE_ROOT_HASH}" # from CI secrets
+root-ssh-keys = [
+    "${env:PVE_ADMIN_KEY}",
+]
+
+[network]
+source = "from-dhcp"
+
+[disk-setup]
+disk-list = ["sda"]
+"#;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "PVE_ROOT_HASH" => Some(HASH.to_owned()),
+            "PVE_ADMIN_KEY" => Some(KEY.to_owned()),
+            _ => None,
+        }
+    }
+
+    /* ---------------- RESOLUTION ---------------- */
+
+    #[test]
+    fn secret_fields_are_resolved_in_place() {
+        let resolved = AnswerFile::resolve_secrets_with(SOURCE, lookup).unwrap();
+        assert!(resolved.contains("# from CI secrets"), "{}", resolved);
+        assert_eq!(resolved.lines().count(), SOURCE.lines().count());
+
+        let answer = AnswerFile::from_toml_str(&resolved).unwrap();
+        assert_eq!(answer.global.root_password_hashed, HASH);
+        assert_eq!(answer.global.root_ssh_keys, Some(vec![KEY.to_owned()]));
+    }
+
+    #[test]
+    fn other_fields_are_left_alone() {
+        let source = SOURCE.replace("pve01.lab.local", "${env:PVE_ROOT_HASH}");
+        let resolved = AnswerFile::resolve_secrets_with(&source, lookup).unwrap();
+        assert!(resolved.contains("fqdn = \"${env:PVE_ROOT_HASH}\""));
+    }
+
+    #[test]
+    fn source_without_placeholders_is_unchanged() {
+        let source = SOURCE
+            .replace("${env:PVE_ROOT_HASH}", HASH)
+            .replace("${env:PVE_ADMIN_KEY}", KEY);
+        assert_eq!(
+            AnswerFile::resolve_secrets_with(&source, |_| None).unwrap(),
+            source
+        );
+    }
+
+    /* ---------------- ERRORS ---------------- */
+
+    #[test]
+    fn unset_variables_fail() {
+        let err = AnswerFile::resolve_secrets_with(SOURCE, |_| None).unwrap_err();
+        assert_eq!(err.to_string(), "env.unset_variable (PVE_ROOT_HASH)");
+    }
+
+    #[test]
+    fn malformed_placeholders_fail() {
+        for (placeholder, name) in [("${env:PVE-HASH}", "PVE-HASH"), ("${env:", "${env:")] {
+            let source = SOURCE.replace("${env:PVE_ROOT_HASH}\"", &format!("{}\"", placeholder));
+            let err = AnswerFile::resolve_secrets_with(&source, lookup).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("env.invalid_variable_name ({})", name),
+                "{}",
+                placeholder
+            );
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// udev property names usable as `filter.<NAME>` keys (e.g. `ID_SERIAL`).
+pub static UDEV_PROPERTY_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[A-Za-z0-9_]+$").expect("invalid UDEV_PROPERTY_PATTERN"));
+
+/// Kernel block device names as listed under `/sys/block` (e.g. `sda`, `nvme0n1`, `dm-0`).
+pub static DEVICE_NAME_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[a-z][a-z0-9]*(?:-[a-z0-9]+)*$").expect("invalid DEVICE_NAME_PATTERN")
+});
+
+/// Valid range for `zfs.ashift` (log2 of the sector size, 512 B to 64 KiB).
+pub const ZFS_ASHIFT_RANGE: std::ops::RangeInclusive<u8> = 9..=16;
+
+/// Valid range for `zfs.copies` (ZFS `copies` property).
+pub const ZFS_COPIES_RANGE: std::ops::RangeInclusive<u8> = 1..=3;
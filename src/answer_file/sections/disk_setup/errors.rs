@@ -0,0 +1,158 @@
+use crate::answer_file::macros::config_error_enum;
+
+/* ===================== DISK SETUP ERROR ===================== */
+
+config_error_enum!(
+    #[derive(Debug, PartialEq)]
+    pub enum DiskSetupError {
+        Filesystem => "filesystem.invalid_format",
+        DiskList => "disk_list.invalid_format",
+        DiskListEmpty => "disk_list.empty",
+        DiskListDuplicate => "disk_list.duplicate_entry",
+        Filter => "filter.invalid_format",
+        FilterMatch => "filter_match.invalid_format",
+        FilterMatchWithoutFilter => "filter_match.requires_filter",
+        DiskListAndFilter => "disk_setup.disk_list_and_filter",
+        NoDiskSelection => "disk_setup.no_disk_selection",
+        ZfsRaid => "zfs.raid.invalid_format",
+        ZfsRaidDiskCount => "zfs.raid.disk_count",
+        ZfsAshift => "zfs.ashift.out_of_range",
+        ZfsCompress => "zfs.compress.invalid_format",
+        ZfsChecksum => "zfs.checksum.invalid_format",
+        ZfsCopies => "zfs.copies.out_of_range",
+        ZfsArcMax => "zfs.arc_max.out_of_range",
+        ZfsHdsize => "zfs.hdsize.out_of_range",
+        LvmHdsize => "lvm.hdsize.out_of_range",
+        LvmSwapsize => "lvm.swapsize.out_of_range",
+        LvmMaxroot => "lvm.maxroot.out_of_range",
+        LvmMaxvz => "lvm.maxvz.out_of_range",
+        LvmMinfree => "lvm.minfree.out_of_range",
+        LvmSwapAndRootExceedHdsize => "lvm.hdsize.swap_and_root_exceed",
+        LvmSizesExceedHdsize => "lvm.hdsize.sizes_exceed",
+        BtrfsRaid => "btrfs.raid.invalid_format",
+        BtrfsHdsize => "btrfs.hdsize.out_of_range",
+        BtrfsCompress => "btrfs.compress.invalid_format",
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    /* ---------------- CODE MAPPING ---------------- */
+
+    #[test]
+    fn filesystem_error_code() {
+        assert_eq!(
+            DiskSetupError::Filesystem.code(),
+            "filesystem.invalid_format"
+        );
+    }
+
+    #[test]
+    fn disk_list_error_code() {
+        assert_eq!(DiskSetupError::DiskList.code(), "disk_list.invalid_format");
+    }
+
+    #[test]
+    fn disk_list_empty_and_duplicate_error_codes() {
+        assert_eq!(DiskSetupError::DiskListEmpty.code(), "disk_list.empty");
+        assert_eq!(
+            DiskSetupError::DiskListDuplicate.code(),
+            "disk_list.duplicate_entry"
+        );
+    }
+
+    #[test]
+    fn filter_error_code() {
+        assert_eq!(DiskSetupError::Filter.code(), "filter.invalid_format");
+    }
+
+    #[test]
+    fn filter_match_error_codes() {
+        assert_eq!(
+            DiskSetupError::FilterMatch.code(),
+            "filter_match.invalid_format"
+        );
+        assert_eq!(
+            DiskSetupError::FilterMatchWithoutFilter.code(),
+            "filter_match.requires_filter"
+        );
+    }
+
+    #[test]
+    fn disk_list_and_filter_error_code() {
+        assert_eq!(
+            DiskSetupError::DiskListAndFilter.code(),
+            "disk_setup.disk_list_and_filter"
+        );
+    }
+
+    #[test]
+    fn no_disk_selection_error_code() {
+        assert_eq!(
+            DiskSetupError::NoDiskSelection.code(),
+            "disk_setup.no_disk_selection"
+        );
+    }
+
+    #[test]
+    fn zfs_error_codes() {
+        for (err, code) in [
+            (DiskSetupError::ZfsRaid, "zfs.raid.invalid_format"),
+            (DiskSetupError::ZfsRaidDiskCount, "zfs.raid.disk_count"),
+            (DiskSetupError::ZfsAshift, "zfs.ashift.out_of_range"),
+            (DiskSetupError::ZfsCompress, "zfs.compress.invalid_format"),
+            (DiskSetupError::ZfsChecksum, "zfs.checksum.invalid_format"),
+            (DiskSetupError::ZfsCopies, "zfs.copies.out_of_range"),
+            (DiskSetupError::ZfsArcMax, "zfs.arc_max.out_of_range"),
+            (DiskSetupError::ZfsHdsize, "zfs.hdsize.out_of_range"),
+        ] {
+            assert_eq!(err.code(), code);
+        }
+    }
+
+    #[test]
+    fn lvm_error_codes() {
+        for (err, code) in [
+            (DiskSetupError::LvmHdsize, "lvm.hdsize.out_of_range"),
+            (DiskSetupError::LvmSwapsize, "lvm.swapsize.out_of_range"),
+            (DiskSetupError::LvmMaxroot, "lvm.maxroot.out_of_range"),
+            (DiskSetupError::LvmMaxvz, "lvm.maxvz.out_of_range"),
+            (DiskSetupError::LvmMinfree, "lvm.minfree.out_of_range"),
+            (
+                DiskSetupError::LvmSwapAndRootExceedHdsize,
+                "lvm.hdsize.swap_and_root_exceed",
+            ),
+            (
+                DiskSetupError::LvmSizesExceedHdsize,
+                "lvm.hdsize.sizes_exceed",
+            ),
+        ] {
+            assert_eq!(err.code(), code);
+        }
+    }
+
+    #[test]
+    fn btrfs_error_codes() {
+        for (err, code) in [
+            (DiskSetupError::BtrfsRaid, "btrfs.raid.invalid_format"),
+            (DiskSetupError::BtrfsHdsize, "btrfs.hdsize.out_of_range"),
+            (
+                DiskSetupError::BtrfsCompress,
+                "btrfs.compress.invalid_format",
+            ),
+        ] {
+            assert_eq!(err.code(), code);
+        }
+    }
+
+    /* ---------------- ERROR TRAIT ---------------- */
+
+    #[test]
+    fn implements_std_error() {
+        let err: &dyn Error = &DiskSetupError::Filesystem;
+        assert_eq!(err.to_string(), "filesystem.invalid_format");
+    }
+}
@@ -0,0 +1,18 @@
+pub mod constants;
+pub mod errors;
+mod models;
+mod section;
+
+pub use constants::{
+    DEVICE_NAME_PATTERN, UDEV_PROPERTY_PATTERN, ZFS_ASHIFT_RANGE, ZFS_COPIES_RANGE,
+};
+pub use errors::DiskSetupError;
+pub use models::{
+    btrfs::{BtrfsCompress, BtrfsOptions, BtrfsRaidLevel},
+    disk_list::DiskList,
+    filesystem::Filesystem,
+    filter::{FilterMatch, validate_filter},
+    lvm::LvmOptions,
+    zfs::{ZfsAshift, ZfsChecksum, ZfsCompress, ZfsOptions, ZfsRaidLevel},
+};
+pub use section::DiskSetup;
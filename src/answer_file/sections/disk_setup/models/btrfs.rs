@@ -0,0 +1,144 @@
+use crate::answer_file::macros::string_enum;
+use crate::answer_file::sections::disk_setup::errors::DiskSetupError;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/* ===================== BTRFS RAID LEVEL ===================== */
+string_enum!(
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum BtrfsRaidLevel {
+        Raid0 => "raid0",
+        Raid1 => "raid1",
+        Raid10 => "raid10",
+        Single => "single",
+    },
+    DiskSetupError,
+    DiskSetupError::BtrfsRaid
+);
+
+/* ===================== BTRFS COMPRESS ===================== */
+string_enum!(
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum BtrfsCompress {
+        On => "on",
+        Off => "off",
+        Zlib => "zlib",
+        Lzo => "lzo",
+        Zstd => "zstd",
+    },
+    DiskSetupError,
+    DiskSetupError::BtrfsCompress
+);
+
+/* ===================== BTRFS OPTIONS ===================== */
+
+/// `disk-setup.btrfs.*` options. Unset fields fall back to the installer defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct BtrfsOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raid: Option<BtrfsRaidLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hdsize: Option<f64>, // GiB
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compress: Option<BtrfsCompress>,
+}
+
+impl BtrfsOptions {
+    pub fn validate(&self) -> Result<(), DiskSetupError> {
+        if let Some(hdsize) = self.hdsize
+            && !(hdsize.is_finite() && hdsize > 0.0)
+        {
+            return Err(DiskSetupError::BtrfsHdsize);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    /* ---------------- RAID LEVEL ---------------- */
+
+    #[test]
+    fn raid_levels_parse() {
+        for (s, expected) in [
+            ("raid0", BtrfsRaidLevel::Raid0),
+            ("raid1", BtrfsRaidLevel::Raid1),
+            ("raid10", BtrfsRaidLevel::Raid10),
+            ("single", BtrfsRaidLevel::Single),
+        ] {
+            assert_eq!(BtrfsRaidLevel::from_str(s).unwrap(), expected);
+            assert_eq!(expected.to_string(), s);
+        }
+    }
+
+    #[test]
+    fn invalid_raid_level_fails_at_parse() {
+        assert_eq!(
+            BtrfsRaidLevel::from_str("raid5"),
+            Err(DiskSetupError::BtrfsRaid)
+        );
+    }
+
+    /* ---------------- VALIDATION ---------------- */
+
+    #[test]
+    fn default_is_empty_and_valid() {
+        assert!(BtrfsOptions::default().validate().is_ok());
+    }
+
+    #[test]
+    fn hdsize_must_be_positive() {
+        let opts = BtrfsOptions {
+            hdsize: Some(0.0),
+            ..BtrfsOptions::default()
+        };
+        assert_eq!(opts.validate(), Err(DiskSetupError::BtrfsHdsize));
+    }
+
+    /* ---------------- COMPRESS ---------------- */
+
+    #[test]
+    fn compress_parses() {
+        assert_eq!(BtrfsCompress::from_str("lzo"), Ok(BtrfsCompress::Lzo));
+        assert_eq!(BtrfsCompress::Zstd.to_string(), "zstd");
+        assert_eq!(
+            BtrfsCompress::from_str("lz4"),
+            Err(DiskSetupError::BtrfsCompress)
+        );
+    }
+
+    /* ---------------- SERDE ---------------- */
+
+    #[test]
+    fn serde_rejects_invalid_raid_level() {
+        let err = toml::from_str::<BtrfsOptions>(r#"raid = "raid6""#).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("btrfs.raid"), "error was: {}", msg);
+    }
+
+    #[test]
+    fn serde_rejects_unknown_compress() {
+        let err = toml::from_str::<BtrfsOptions>(r#"compress = "lz4""#).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("btrfs.compress"), "error was: {}", msg);
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let opts = BtrfsOptions {
+            raid: Some(BtrfsRaidLevel::Raid1),
+            hdsize: Some(200.0),
+            compress: Some(BtrfsCompress::Zstd),
+        };
+        let toml = toml::to_string(&opts).unwrap();
+        assert!(toml.contains(r#"raid = "raid1""#), "toml was: {}", toml);
+
+        let parsed: BtrfsOptions = toml::from_str(&toml).unwrap();
+        assert_eq!(parsed, opts);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::answer_file::sections::disk_setup::{
+    constants::DEVICE_NAME_PATTERN, errors::DiskSetupError,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{collections::HashSet, fmt};
+
+/// Explicit `disk-setup.disk-list` selection (e.g. `["sda", "nvme0n1"]`).
+///
+/// Guaranteed non-empty, free of duplicates and made of plain kernel block
+/// device names as they appear under `/sys/block`.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, Hash)]
+pub struct DiskList(Vec<String>);
+
+impl DiskList {
+    /// Explicit constructor for non-default manipulation
+    pub fn try_new<I, S>(disks: I) -> Result<Self, DiskSetupError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let disks: Vec<String> = disks
+            .into_iter()
+            .map(|d| d.as_ref().trim().to_owned())
+            .collect();
+
+        if disks.is_empty() {
+            return Err(DiskSetupError::DiskListEmpty);
+        }
+
+        let mut seen = HashSet::new();
+        for disk in &disks {
+            if !DEVICE_NAME_PATTERN.is_match(disk) {
+                return Err(DiskSetupError::DiskList);
+            }
+            if !seen.insert(disk.as_str()) {
+                return Err(DiskSetupError::DiskListDuplicate);
+            }
+        }
+
+        Ok(Self(disks))
+    }
+
+    pub fn as_slice(&self) -> &[String] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Always `false`; kept for API symmetry with `len()`.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, String> {
+        self.0.iter()
+    }
+}
+
+impl fmt::Display for DiskList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.join(", "))
+    }
+}
+
+impl schemars::JsonSchema for DiskList {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "DiskList".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "array",
+            "items": { "type": "string", "pattern": DEVICE_NAME_PATTERN.as_str() },
+            "minItems": 1,
+            "uniqueItems": true
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for DiskList {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let disks = Vec::<String>::deserialize(deserializer)?;
+        DiskList::try_new(disks).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    /* ---------------- TRY_NEW ---------------- */
+
+    #[test]
+    fn valid_disk_list() {
+        let list = DiskList::try_new(["sda", "sdb", "nvme0n1"]).unwrap();
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.as_slice()[2], "nvme0n1");
+    }
+
+    #[test]
+    fn entries_are_trimmed() {
+        let list = DiskList::try_new([" sda "]).unwrap();
+        assert_eq!(list.as_slice(), ["sda"]);
+    }
+
+    #[test]
+    fn empty_list_fails() {
+        assert_eq!(
+            DiskList::try_new(Vec::<String>::new()),
+            Err(DiskSetupError::DiskListEmpty)
+        );
+    }
+
+    #[test]
+    fn duplicates_fail() {
+        assert_eq!(
+            DiskList::try_new(["sda", "sdb", "sda"]),
+            Err(DiskSetupError::DiskListDuplicate)
+        );
+    }
+
+    #[test]
+    fn invalid_device_names_fail() {
+        for name in ["", "/dev/sda", "SDA", "sd a", "../sda", "1sda", "sda-"] {
+            assert_eq!(
+                DiskList::try_new([name]),
+                Err(DiskSetupError::DiskList),
+                "name: {:?}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn common_device_names_pass() {
+        for name in ["sda", "vdb", "xvda", "nvme0n1", "mmcblk0", "dm-0"] {
+            assert!(DiskList::try_new([name]).is_ok(), "name: {:?}", name);
+        }
+    }
+
+    /* ---------------- DISPLAY ---------------- */
+
+    #[test]
+    fn display_joins_entries() {
+        let list = DiskList::try_new(["sda", "sdb"]).unwrap();
+        assert_eq!(list.to_string(), "sda, sdb");
+    }
+
+    /* ---------------- SERDE ---------------- */
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Wrapper {
+        #[serde(rename = "disk-list")]
+        disk_list: DiskList,
+    }
+
+    #[test]
+    fn serde_rejects_duplicates() {
+        let err = toml::from_str::<Wrapper>(r#"disk-list = ["sda", "sda"]"#).unwrap_err();
+        let msg = err.to_string();
+        assert!(
+            msg.contains("disk_list.duplicate_entry"),
+            "error was: {}",
+            msg
+        );
+    }
+
+    #[test]
+    fn serde_rejects_empty_list() {
+        let err = toml::from_str::<Wrapper>(r#"disk-list = []"#).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("disk_list.empty"), "error was: {}", msg);
+    }
+
+    #[test]
+    fn serde_round_trip_is_a_plain_array() {
+        let w = Wrapper {
+            disk_list: DiskList::try_new(["sda", "sdb"]).unwrap(),
+        };
+        let toml = toml::to_string(&w).unwrap();
+        assert!(
+            toml.contains(r#"disk-list = ["sda", "sdb"]"#),
+            "toml was: {}",
+            toml
+        );
+
+        let parsed: Wrapper = toml::from_str(&toml).unwrap();
+        assert_eq!(parsed, w);
+    }
+}
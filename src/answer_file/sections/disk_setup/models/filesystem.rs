@@ -0,0 +1,95 @@
+use crate::answer_file::macros::string_enum;
+use crate::answer_file::sections::disk_setup::errors::DiskSetupError;
+
+/* ===================== FILESYSTEM ===================== */
+string_enum!(
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    pub enum Filesystem {
+        #[default]
+        Ext4 => "ext4",
+        Xfs => "xfs",
+        Zfs => "zfs",
+        Btrfs => "btrfs",
+    },
+    DiskSetupError,
+    DiskSetupError::Filesystem
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    use std::str::FromStr;
+
+    /* ---------------- DEFAULT ---------------- */
+
+    #[test]
+    fn default_is_ext4() {
+        assert_eq!(Filesystem::default(), Filesystem::Ext4);
+    }
+
+    /* ---------------- FROMSTR ---------------- */
+
+    #[test]
+    fn all_filesystems_parse() {
+        for (s, expected) in [
+            ("ext4", Filesystem::Ext4),
+            ("xfs", Filesystem::Xfs),
+            ("zfs", Filesystem::Zfs),
+            ("btrfs", Filesystem::Btrfs),
+        ] {
+            assert_eq!(Filesystem::from_str(s).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn invalid_filesystem_fails_at_parse() {
+        assert_eq!(
+            Filesystem::from_str("ntfs"),
+            Err(DiskSetupError::Filesystem)
+        );
+    }
+
+    /* ---------------- DISPLAY ---------------- */
+
+    #[test]
+    fn display_outputs_canonical_string() {
+        assert_eq!(Filesystem::Zfs.to_string(), "zfs");
+        assert_eq!(Filesystem::Btrfs.to_string(), "btrfs");
+    }
+
+    /* ---------------- SERDE ---------------- */
+
+    #[test]
+    fn serde_rejects_invalid_filesystem() {
+        let toml = r#"filesystem = "fat32""#;
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Wrapper {
+            filesystem: Filesystem,
+        }
+
+        let err = toml::from_str::<Wrapper>(toml).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("filesystem"), "error was: {}", msg);
+    }
+
+    #[test]
+    fn serde_round_trip_preserves_string_value() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Wrapper {
+            filesystem: Filesystem,
+        }
+
+        let w = Wrapper {
+            filesystem: Filesystem::Xfs,
+        };
+
+        let toml = toml::to_string(&w).unwrap();
+        assert!(toml.contains(r#"filesystem = "xfs""#));
+
+        let parsed: Wrapper = toml::from_str(&toml).unwrap();
+        assert_eq!(parsed, w);
+    }
+}
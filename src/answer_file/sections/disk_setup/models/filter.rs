@@ -0,0 +1,96 @@
+use crate::answer_file::macros::string_enum;
+use crate::answer_file::sections::disk_setup::{
+    constants::UDEV_PROPERTY_PATTERN, errors::DiskSetupError,
+};
+use std::collections::BTreeMap;
+
+/* ===================== FILTER MATCH ===================== */
+string_enum!(
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    pub enum FilterMatch {
+        #[default]
+        Any => "any",
+        All => "all",
+    },
+    DiskSetupError,
+    DiskSetupError::FilterMatch
+);
+
+/// Validates `filter.<UDEV_PROPERTY> = "<glob>"` entries.
+///
+/// Keys must be udev property names (e.g. `ID_SERIAL`) and values must be
+/// non-empty match patterns.
+pub fn validate_filter(filter: &BTreeMap<String, String>) -> Result<(), DiskSetupError> {
+    if filter.is_empty() {
+        return Err(DiskSetupError::Filter);
+    }
+
+    for (key, value) in filter {
+        if !UDEV_PROPERTY_PATTERN.is_match(key) || value.trim().is_empty() {
+            return Err(DiskSetupError::Filter);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    /* ---------------- FILTER MATCH ---------------- */
+
+    #[test]
+    fn default_is_any() {
+        assert_eq!(FilterMatch::default(), FilterMatch::Any);
+    }
+
+    #[test]
+    fn filter_match_parses() {
+        assert_eq!(FilterMatch::from_str("all").unwrap(), FilterMatch::All);
+        assert_eq!(FilterMatch::from_str("any").unwrap(), FilterMatch::Any);
+        assert_eq!(
+            FilterMatch::from_str("some"),
+            Err(DiskSetupError::FilterMatch)
+        );
+    }
+
+    /* ---------------- FILTER ENTRIES ---------------- */
+
+    #[test]
+    fn valid_filters_pass() {
+        let filter = BTreeMap::from([
+            ("ID_SERIAL".to_string(), "S4EV*".to_string()),
+            ("ID_MODEL".to_string(), "Samsung_SSD_970*".to_string()),
+        ]);
+        assert!(validate_filter(&filter).is_ok());
+    }
+
+    #[test]
+    fn empty_filter_fails() {
+        assert_eq!(
+            validate_filter(&BTreeMap::new()),
+            Err(DiskSetupError::Filter)
+        );
+    }
+
+    #[test]
+    fn invalid_property_names_fail() {
+        for key in ["", "ID SERIAL", "ID-SERIAL", "filter.ID_SERIAL"] {
+            let filter = BTreeMap::from([(key.to_string(), "x".to_string())]);
+            assert_eq!(
+                validate_filter(&filter),
+                Err(DiskSetupError::Filter),
+                "key: {:?}",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn empty_pattern_fails() {
+        let filter = BTreeMap::from([("ID_SERIAL".to_string(), "  ".to_string())]);
+        assert_eq!(validate_filter(&filter), Err(DiskSetupError::Filter));
+    }
+}
@@ -0,0 +1,199 @@
+use crate::answer_file::sections::disk_setup::errors::DiskSetupError;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/* ===================== LVM OPTIONS ===================== */
+
+/// `disk-setup.lvm.*` options for ext4/xfs installs, all sizes in GiB.
+/// Unset fields fall back to the installer defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct LvmOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hdsize: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub swapsize: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maxroot: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maxvz: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minfree: Option<f64>,
+}
+
+impl LvmOptions {
+    /// Checks each size, then, when `hdsize` is set, that the volumes fit:
+    /// `swapsize + maxroot` must not exceed it, and neither may the sum of
+    /// all of `swapsize`, `maxroot`, `maxvz` and `minfree`.
+    pub fn validate(&self) -> Result<(), DiskSetupError> {
+        if let Some(hdsize) = self.hdsize
+            && !(hdsize.is_finite() && hdsize > 0.0)
+        {
+            return Err(DiskSetupError::LvmHdsize);
+        }
+
+        for (value, err) in [
+            (self.swapsize, DiskSetupError::LvmSwapsize),
+            (self.maxroot, DiskSetupError::LvmMaxroot),
+            (self.maxvz, DiskSetupError::LvmMaxvz),
+            (self.minfree, DiskSetupError::LvmMinfree),
+        ] {
+            if let Some(v) = value
+                && !(v.is_finite() && v >= 0.0)
+            {
+                return Err(err);
+            }
+        }
+
+        if let Some(hdsize) = self.hdsize {
+            let swap_and_root = self.swapsize.unwrap_or(0.0) + self.maxroot.unwrap_or(0.0);
+            if swap_and_root > hdsize {
+                return Err(DiskSetupError::LvmSwapAndRootExceedHdsize);
+            }
+
+            let total = swap_and_root + self.maxvz.unwrap_or(0.0) + self.minfree.unwrap_or(0.0);
+            if total > hdsize {
+                return Err(DiskSetupError::LvmSizesExceedHdsize);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full() -> LvmOptions {
+        LvmOptions {
+            hdsize: Some(100.0),
+            swapsize: Some(8.0),
+            maxroot: Some(32.0),
+            maxvz: Some(0.0),
+            minfree: Some(16.0),
+        }
+    }
+
+    /* ---------------- DEFAULTS ---------------- */
+
+    #[test]
+    fn default_is_empty_and_valid() {
+        assert!(LvmOptions::default().validate().is_ok());
+    }
+
+    /* ---------------- VALIDATION ---------------- */
+
+    #[test]
+    fn full_options_are_valid() {
+        assert!(full().validate().is_ok());
+    }
+
+    #[test]
+    fn hdsize_must_be_positive() {
+        for hdsize in [0.0, -5.0, f64::INFINITY] {
+            let opts = LvmOptions {
+                hdsize: Some(hdsize),
+                ..full()
+            };
+            assert_eq!(opts.validate(), Err(DiskSetupError::LvmHdsize));
+        }
+    }
+
+    #[test]
+    fn negative_sizes_fail_with_field_specific_errors() {
+        let cases = [
+            (
+                LvmOptions {
+                    swapsize: Some(-1.0),
+                    ..full()
+                },
+                DiskSetupError::LvmSwapsize,
+            ),
+            (
+                LvmOptions {
+                    maxroot: Some(-1.0),
+                    ..full()
+                },
+                DiskSetupError::LvmMaxroot,
+            ),
+            (
+                LvmOptions {
+                    maxvz: Some(f64::NAN),
+                    ..full()
+                },
+                DiskSetupError::LvmMaxvz,
+            ),
+            (
+                LvmOptions {
+                    minfree: Some(-0.5),
+                    ..full()
+                },
+                DiskSetupError::LvmMinfree,
+            ),
+        ];
+
+        for (opts, expected) in cases {
+            assert_eq!(opts.validate(), Err(expected));
+        }
+    }
+
+    /* ---------------- SIZING ---------------- */
+
+    #[test]
+    fn swap_and_root_must_fit_hdsize() {
+        let opts = LvmOptions {
+            hdsize: Some(32.0),
+            swapsize: Some(8.0),
+            maxroot: Some(32.0),
+            maxvz: None,
+            minfree: None,
+        };
+        assert_eq!(
+            opts.validate(),
+            Err(DiskSetupError::LvmSwapAndRootExceedHdsize)
+        );
+    }
+
+    #[test]
+    fn all_volumes_must_fit_hdsize() {
+        let opts = LvmOptions {
+            maxvz: Some(50.0),
+            ..full()
+        };
+        assert_eq!(opts.validate(), Err(DiskSetupError::LvmSizesExceedHdsize));
+
+        let exact = LvmOptions {
+            maxvz: Some(44.0),
+            ..full()
+        };
+        assert!(exact.validate().is_ok());
+    }
+
+    #[test]
+    fn sizes_without_hdsize_are_not_compared() {
+        let opts = LvmOptions {
+            hdsize: None,
+            maxroot: Some(10_000.0),
+            ..full()
+        };
+        assert!(opts.validate().is_ok());
+    }
+
+    /* ---------------- SERDE ---------------- */
+
+    #[test]
+    fn serde_accepts_integer_and_float_sizes() {
+        let opts: LvmOptions = toml::from_str("hdsize = 100\nswapsize = 4.5").unwrap();
+        assert_eq!(opts.hdsize, Some(100.0));
+        assert_eq!(opts.swapsize, Some(4.5));
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let opts = full();
+        let toml = toml::to_string(&opts).unwrap();
+        let parsed: LvmOptions = toml::from_str(&toml).unwrap();
+        assert_eq!(parsed, opts);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod btrfs;
+pub mod disk_list;
+pub mod filesystem;
+pub mod filter;
+pub mod lvm;
+pub mod zfs;
@@ -0,0 +1,338 @@
+use crate::answer_file::macros::string_enum;
+use crate::answer_file::sections::disk_setup::{
+    constants::{ZFS_ASHIFT_RANGE, ZFS_COPIES_RANGE},
+    errors::DiskSetupError,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+
+/* ===================== ZFS RAID LEVEL ===================== */
+string_enum!(
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum ZfsRaidLevel {
+        Raid0 => "raid0",
+        Raid1 => "raid1",
+        Raid10 => "raid10",
+        RaidZ1 => "raidz-1",
+        RaidZ2 => "raidz-2",
+        RaidZ3 => "raidz-3",
+    },
+    DiskSetupError,
+    DiskSetupError::ZfsRaid
+);
+
+impl ZfsRaidLevel {
+    /// Fewest disks the installer accepts for this level.
+    pub fn min_disks(&self) -> usize {
+        match self {
+            Self::Raid0 => 1,
+            Self::Raid1 => 2,
+            Self::RaidZ1 => 3,
+            Self::Raid10 | Self::RaidZ2 => 4,
+            Self::RaidZ3 => 5,
+        }
+    }
+
+    /// Whether a pool of `disks` disks can be built; `raid10` also needs
+    /// an even count, as it stripes over mirrored pairs.
+    pub fn supports_disk_count(&self, disks: usize) -> bool {
+        disks >= self.min_disks() && (*self != Self::Raid10 || disks.is_multiple_of(2))
+    }
+}
+
+/* ===================== ZFS CHECKSUM ===================== */
+string_enum!(
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum ZfsChecksum {
+        On => "on",
+        Fletcher4 => "fletcher4",
+        Sha256 => "sha256",
+    },
+    DiskSetupError,
+    DiskSetupError::ZfsChecksum
+);
+
+/* ===================== ZFS COMPRESS ===================== */
+string_enum!(
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum ZfsCompress {
+        On => "on",
+        Off => "off",
+        Lzjb => "lzjb",
+        Lz4 => "lz4",
+        Zle => "zle",
+        Gzip => "gzip",
+        Zstd => "zstd",
+    },
+    DiskSetupError,
+    DiskSetupError::ZfsCompress
+);
+
+/* ===================== ZFS ASHIFT ===================== */
+
+/// `zfs.ashift`: log2 of the pool's sector size, within [`ZFS_ASHIFT_RANGE`]
+/// (e.g. `12` for 4 KiB sectors).
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Hash)]
+pub struct ZfsAshift(u8);
+
+impl ZfsAshift {
+    /// Explicit constructor for non-default manipulation
+    pub fn try_new(ashift: u8) -> Result<Self, DiskSetupError> {
+        if ZFS_ASHIFT_RANGE.contains(&ashift) {
+            Ok(Self(ashift))
+        } else {
+            Err(DiskSetupError::ZfsAshift)
+        }
+    }
+
+    pub fn get(&self) -> u8 {
+        self.0
+    }
+}
+
+impl fmt::Display for ZfsAshift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl schemars::JsonSchema for ZfsAshift {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "ZfsAshift".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "integer",
+            "minimum": ZFS_ASHIFT_RANGE.start(),
+            "maximum": ZFS_ASHIFT_RANGE.end()
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for ZfsAshift {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let ashift = u8::deserialize(deserializer)?;
+        Self::try_new(ashift).map_err(serde::de::Error::custom)
+    }
+}
+
+/* ===================== ZFS OPTIONS ===================== */
+
+/// `disk-setup.zfs.*` options. Unset fields fall back to the installer defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct ZfsOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raid: Option<ZfsRaidLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ashift: Option<ZfsAshift>,
+    #[serde(rename = "arc-max", skip_serializing_if = "Option::is_none")]
+    pub arc_max: Option<u64>, // MiB
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<ZfsChecksum>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compress: Option<ZfsCompress>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub copies: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hdsize: Option<f64>, // GiB
+}
+
+impl ZfsOptions {
+    pub fn validate(&self) -> Result<(), DiskSetupError> {
+        if let Some(copies) = self.copies
+            && !ZFS_COPIES_RANGE.contains(&copies)
+        {
+            return Err(DiskSetupError::ZfsCopies);
+        }
+
+        if self.arc_max == Some(0) {
+            return Err(DiskSetupError::ZfsArcMax);
+        }
+
+        if let Some(hdsize) = self.hdsize
+            && (!hdsize.is_finite() || hdsize <= 0.0)
+        {
+            return Err(DiskSetupError::ZfsHdsize);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn full() -> ZfsOptions {
+        ZfsOptions {
+            raid: Some(ZfsRaidLevel::Raid1),
+            ashift: Some(ZfsAshift::try_new(12).unwrap()),
+            compress: Some(ZfsCompress::Lz4),
+            checksum: Some(ZfsChecksum::On),
+            copies: Some(1),
+            arc_max: Some(2048),
+            hdsize: Some(64.0),
+        }
+    }
+
+    /* ---------------- DEFAULTS ---------------- */
+
+    #[test]
+    fn default_is_empty_and_valid() {
+        let opts = ZfsOptions::default();
+        assert_eq!(opts.raid, None);
+        assert!(opts.validate().is_ok());
+    }
+
+    /* ---------------- RAID LEVEL ENUM ---------------- */
+
+    #[test]
+    fn raid_levels_parse() {
+        assert_eq!(ZfsRaidLevel::from_str("raidz-2"), Ok(ZfsRaidLevel::RaidZ2));
+        assert_eq!(ZfsRaidLevel::RaidZ1.to_string(), "raidz-1");
+        assert_eq!(
+            ZfsRaidLevel::from_str("raid5"),
+            Err(DiskSetupError::ZfsRaid)
+        );
+    }
+
+    #[test]
+    fn raid_levels_need_enough_disks() {
+        for (raid, ok, too_few) in [
+            (ZfsRaidLevel::Raid0, 1, 0),
+            (ZfsRaidLevel::Raid1, 2, 1),
+            (ZfsRaidLevel::Raid10, 4, 3),
+            (ZfsRaidLevel::RaidZ1, 3, 2),
+            (ZfsRaidLevel::RaidZ2, 4, 3),
+            (ZfsRaidLevel::RaidZ3, 5, 4),
+        ] {
+            assert!(raid.supports_disk_count(ok), "{} with {}", raid, ok);
+            assert!(
+                !raid.supports_disk_count(too_few),
+                "{} with {}",
+                raid,
+                too_few
+            );
+        }
+        assert!(!ZfsRaidLevel::Raid10.supports_disk_count(5));
+        assert!(ZfsRaidLevel::Raid10.supports_disk_count(6));
+    }
+
+    /* ---------------- CHECKSUM ENUM ---------------- */
+
+    #[test]
+    fn checksum_parses_and_displays() {
+        let c = ZfsChecksum::from_str("fletcher4").unwrap();
+        assert_eq!(c, ZfsChecksum::Fletcher4);
+        assert_eq!(c.to_string(), "fletcher4");
+        assert_eq!(
+            ZfsChecksum::from_str("crc32"),
+            Err(DiskSetupError::ZfsChecksum)
+        );
+    }
+
+    /* ---------------- VALIDATION ---------------- */
+
+    #[test]
+    fn full_options_are_valid() {
+        assert!(full().validate().is_ok());
+    }
+
+    /* ---------------- ASHIFT ---------------- */
+
+    #[test]
+    fn ashift_bounds() {
+        assert_eq!(ZfsAshift::try_new(9).unwrap().get(), 9);
+        assert_eq!(ZfsAshift::try_new(16).unwrap().get(), 16);
+        for ashift in [0, 8, 17] {
+            assert_eq!(ZfsAshift::try_new(ashift), Err(DiskSetupError::ZfsAshift));
+        }
+    }
+
+    #[test]
+    fn copies_out_of_range_fails() {
+        for copies in [0, 4] {
+            let opts = ZfsOptions {
+                copies: Some(copies),
+                ..full()
+            };
+            assert_eq!(opts.validate(), Err(DiskSetupError::ZfsCopies));
+        }
+    }
+
+    #[test]
+    fn zero_arc_max_fails() {
+        let opts = ZfsOptions {
+            arc_max: Some(0),
+            ..full()
+        };
+        assert_eq!(opts.validate(), Err(DiskSetupError::ZfsArcMax));
+    }
+
+    #[test]
+    fn non_positive_hdsize_fails() {
+        for hdsize in [0.0, -1.0, f64::NAN] {
+            let opts = ZfsOptions {
+                hdsize: Some(hdsize),
+                ..full()
+            };
+            assert_eq!(opts.validate(), Err(DiskSetupError::ZfsHdsize));
+        }
+    }
+
+    /* ---------------- SERDE ---------------- */
+
+    #[test]
+    fn serde_uses_proxmox_key_names() {
+        let toml = toml::to_string(&full()).unwrap();
+        assert!(toml.contains("arc-max = 2048"), "toml was: {}", toml);
+        assert!(toml.contains(r#"checksum = "on""#), "toml was: {}", toml);
+    }
+
+    #[test]
+    fn serde_rejects_invalid_raid_level() {
+        let err = toml::from_str::<ZfsOptions>(r#"raid = "raid5""#).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("zfs.raid"), "error was: {}", msg);
+    }
+
+    #[test]
+    fn serde_rejects_out_of_range_ashift() {
+        let err = toml::from_str::<ZfsOptions>("ashift = 17").unwrap_err();
+        let msg = err.to_string();
+        assert!(
+            msg.contains("zfs.ashift.out_of_range"),
+            "error was: {}",
+            msg
+        );
+    }
+
+    #[test]
+    fn serde_rejects_invalid_compress() {
+        let err = toml::from_str::<ZfsOptions>(r#"compress = "brotli""#).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("zfs.compress"), "error was: {}", msg);
+    }
+
+    #[test]
+    fn serde_rejects_invalid_checksum() {
+        let err = toml::from_str::<ZfsOptions>(r#"checksum = "crc32""#).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("zfs.checksum"), "error was: {}", msg);
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let opts = full();
+        let toml = toml::to_string(&opts).unwrap();
+        let parsed: ZfsOptions = toml::from_str(&toml).unwrap();
+        assert_eq!(parsed, opts);
+    }
+}
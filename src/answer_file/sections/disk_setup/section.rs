@@ -0,0 +1,504 @@
+use crate::answer_file::sections::disk_setup::{
+    DiskSetupError,
+    models::{
+        btrfs::BtrfsOptions,
+        disk_list::DiskList,
+        filesystem::Filesystem,
+        filter::{FilterMatch, validate_filter},
+        lvm::LvmOptions,
+        zfs::ZfsOptions,
+    },
+};
+use crate::answer_file::sections::yaml::{section_from_yaml_str, section_to_yaml_string};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct DiskSetup {
+    pub filesystem: Filesystem, // "ext4", "xfs", "zfs", "btrfs"
+    /* field-level default: a missing disk-list must not fall back to "sda" */
+    #[serde(rename = "disk-list", default, skip_serializing_if = "Option::is_none")]
+    pub disk_list: Option<DiskList>,
+    /* udev property matches, e.g. filter.ID_SERIAL = "..." */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<BTreeMap<String, String>>,
+    #[serde(
+        rename = "filter-match",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub filter_match: Option<FilterMatch>, // "any" (installer default), "all"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zfs: Option<ZfsOptions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lvm: Option<LvmOptions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub btrfs: Option<BtrfsOptions>,
+}
+
+impl Default for DiskSetup {
+    fn default() -> Self {
+        Self {
+            filesystem: Filesystem::default(),
+            disk_list: Some(DiskList::try_new(["sda"]).expect("valid default disk-list")),
+            filter: None,
+            filter_match: None,
+            zfs: None,
+            lvm: None,
+            btrfs: None,
+        }
+    }
+}
+
+impl DiskSetup {
+    pub fn validate(&self) -> Result<(), DiskSetupError> {
+        let has_disk_list = self.disk_list.is_some();
+        let has_filter = self.filter.as_ref().is_some_and(|f| !f.is_empty());
+
+        match (has_disk_list, has_filter) {
+            (false, false) => return Err(DiskSetupError::NoDiskSelection),
+            (true, true) => return Err(DiskSetupError::DiskListAndFilter),
+            _ => {}
+        }
+
+        if let Some(filter) = &self.filter {
+            validate_filter(filter)?;
+        }
+
+        if self.filter_match.is_some() && !has_filter {
+            return Err(DiskSetupError::FilterMatchWithoutFilter);
+        }
+
+        if let Some(zfs) = &self.zfs {
+            zfs.validate()?;
+
+            if let (Some(raid), Some(disks)) = (zfs.raid, &self.disk_list)
+                && !raid.supports_disk_count(disks.len())
+            {
+                return Err(DiskSetupError::ZfsRaidDiskCount);
+            }
+        }
+
+        if let Some(lvm) = &self.lvm {
+            lvm.validate()?;
+        }
+
+        if let Some(btrfs) = &self.btrfs {
+            btrfs.validate()?;
+        }
+
+        Ok(())
+    }
+
+    /* -------- FROM TOML STRING (BARE OR [disk-setup]) -------- */
+
+    pub fn from_toml_str(s: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        // 1. Wrapped form: [disk-setup] (errors inside the table are preserved)
+        let table: toml::Table = toml::from_str(s)?;
+        let cfg: DiskSetup = match table.get("disk-setup") {
+            Some(section) => section.clone().try_into()?,
+            // 2. Fallback to bare form
+            None => toml::from_str(s)?,
+        };
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /* -------- TO TOML STRING (BARE OR [disk-setup]) -------- */
+
+    pub fn to_toml_string(&self, wrap: bool) -> Result<String, Box<dyn std::error::Error>> {
+        self.validate()?;
+
+        if wrap {
+            #[derive(serde::Serialize)]
+            struct Wrapped<'a> {
+                #[serde(rename = "disk-setup")]
+                disk_setup: &'a DiskSetup,
+            }
+            Ok(toml::to_string_pretty(&Wrapped { disk_setup: self })?)
+        } else {
+            Ok(toml::to_string_pretty(self)?)
+        }
+    }
+
+    /* -------- FROM YAML STRING (BARE OR disk-setup:) -------- */
+
+    pub fn from_yaml_str(s: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let cfg: DiskSetup = section_from_yaml_str(s, "disk-setup")?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /* -------- TO YAML STRING (BARE OR disk-setup:) -------- */
+
+    pub fn to_yaml_string(&self, wrap: bool) -> Result<String, Box<dyn std::error::Error>> {
+        self.validate()?;
+        section_to_yaml_string(self, "disk-setup", wrap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer_file::sections::disk_setup::models::{
+        btrfs::BtrfsCompress,
+        zfs::{ZfsCompress, ZfsRaidLevel},
+    };
+
+    /* ---------------- DEFAULTS ---------------- */
+
+    #[test]
+    fn defaults_are_correct() {
+        let cfg = DiskSetup::default();
+        assert_eq!(cfg.filesystem, Filesystem::Ext4);
+        assert_eq!(cfg.disk_list.as_ref().unwrap().as_slice(), ["sda"]);
+        assert_eq!(cfg.filter, None);
+        assert!(cfg.validate().is_ok());
+    }
+
+    /* ---------------- STRUCTURAL VALIDATION ---------------- */
+
+    #[test]
+    fn missing_disk_selection_fails() {
+        let cfg = DiskSetup {
+            disk_list: None,
+            ..DiskSetup::default()
+        };
+        assert_eq!(cfg.validate(), Err(DiskSetupError::NoDiskSelection));
+    }
+
+    #[test]
+    fn empty_disk_list_fails_at_deserialize() {
+        let toml = r#"
+            filesystem = "ext4"
+            disk-list = []
+        "#;
+
+        let err = DiskSetup::from_toml_str(toml).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("disk_list.empty"), "error was: {}", msg);
+    }
+
+    #[test]
+    fn duplicate_disk_list_entries_fail_at_deserialize() {
+        let toml = r#"
+            [disk-setup]
+            filesystem = "zfs"
+            disk-list = ["sda", "sdb", "sda"]
+        "#;
+
+        let err = DiskSetup::from_toml_str(toml).unwrap_err();
+        let msg = err.to_string();
+        assert!(
+            msg.contains("disk_list.duplicate_entry"),
+            "error was: {}",
+            msg
+        );
+    }
+
+    #[test]
+    fn filter_alone_is_a_disk_selection() {
+        let cfg = DiskSetup {
+            disk_list: None,
+            filter: Some(BTreeMap::from([("ID_SERIAL".into(), "S4EV*".into())])),
+            ..DiskSetup::default()
+        };
+        assert!(cfg.validate().is_ok());
+    }
+
+    #[test]
+    fn disk_list_and_filter_are_mutually_exclusive() {
+        let cfg = DiskSetup {
+            filter: Some(BTreeMap::from([("ID_SERIAL".into(), "S4EV*".into())])),
+            ..DiskSetup::default()
+        };
+        assert_eq!(cfg.validate(), Err(DiskSetupError::DiskListAndFilter));
+    }
+
+    #[test]
+    fn invalid_filter_entry_fails() {
+        let cfg = DiskSetup {
+            disk_list: None,
+            filter: Some(BTreeMap::from([("ID_SERIAL".into(), "".into())])),
+            ..DiskSetup::default()
+        };
+        assert_eq!(cfg.validate(), Err(DiskSetupError::Filter));
+    }
+
+    #[test]
+    fn filter_match_requires_filter() {
+        let cfg = DiskSetup {
+            filter_match: Some(FilterMatch::All),
+            ..DiskSetup::default()
+        };
+        assert_eq!(
+            cfg.validate(),
+            Err(DiskSetupError::FilterMatchWithoutFilter)
+        );
+    }
+
+    #[test]
+    fn zfs_raid_level_must_fit_the_disk_list() {
+        let cfg = |disks: &[&str]| DiskSetup {
+            filesystem: Filesystem::Zfs,
+            disk_list: Some(DiskList::try_new(disks).unwrap()),
+            zfs: Some(ZfsOptions {
+                raid: Some(ZfsRaidLevel::RaidZ1),
+                ..ZfsOptions::default()
+            }),
+            ..DiskSetup::default()
+        };
+        assert_eq!(
+            cfg(&["sda", "sdb"]).validate(),
+            Err(DiskSetupError::ZfsRaidDiskCount)
+        );
+        assert!(cfg(&["sda", "sdb", "sdc"]).validate().is_ok());
+
+        // Filters match an unknown number of disks, so they are not counted.
+        let filtered = DiskSetup {
+            disk_list: None,
+            filter: Some(BTreeMap::from([("ID_SERIAL".into(), "S4EV*".into())])),
+            ..cfg(&["sda"])
+        };
+        assert!(filtered.validate().is_ok());
+    }
+
+    /* ---------------- TOML DESERIALIZATION ---------------- */
+
+    #[test]
+    fn valid_toml_deserialization_bare() {
+        let toml = r#"
+            filesystem = "xfs"
+            disk-list = ["sda", "sdb"]
+        "#;
+
+        let cfg = DiskSetup::from_toml_str(toml).unwrap();
+        assert_eq!(cfg.filesystem, Filesystem::Xfs);
+        assert_eq!(cfg.disk_list.unwrap().as_slice(), ["sda", "sdb"]);
+    }
+
+    #[test]
+    fn valid_toml_deserialization_wrapped_with_dotted_filter() {
+        let toml = r#"
+            [disk-setup]
+            filesystem = "zfs"
+            filter.ID_SERIAL = "S4EV*"
+        "#;
+
+        let cfg = DiskSetup::from_toml_str(toml).unwrap();
+        assert_eq!(cfg.filesystem, Filesystem::Zfs);
+        assert_eq!(
+            cfg.filter.unwrap().get("ID_SERIAL").map(String::as_str),
+            Some("S4EV*")
+        );
+    }
+
+    #[test]
+    fn filter_match_deserializes() {
+        let toml = r#"
+            [disk-setup]
+            filesystem = "ext4"
+            filter.ID_SERIAL = "S4EV*"
+            filter.ID_MODEL = "Samsung*"
+            filter-match = "all"
+        "#;
+
+        let cfg = DiskSetup::from_toml_str(toml).unwrap();
+        assert_eq!(cfg.filter_match, Some(FilterMatch::All));
+        assert_eq!(cfg.filter.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn invalid_filter_match_fails_at_deserialize() {
+        let toml = r#"
+            filesystem = "ext4"
+            filter.ID_SERIAL = "S4EV*"
+            filter-match = "most"
+        "#;
+
+        let err = DiskSetup::from_toml_str(toml).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("filter_match"), "error was: {}", msg);
+    }
+
+    #[test]
+    fn invalid_toml_filesystem_fails_at_deserialize() {
+        let toml = r#"
+            filesystem = "ntfs"
+            disk-list = ["sda"]
+        "#;
+
+        let err = DiskSetup::from_toml_str(toml).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("filesystem"), "error was: {}", msg);
+    }
+
+    #[test]
+    fn zfs_options_deserialize_from_dotted_keys() {
+        let toml = r#"
+            [disk-setup]
+            filesystem = "zfs"
+            disk-list = ["sda", "sdb"]
+            zfs.raid = "raid1"
+            zfs.ashift = 12
+            zfs.arc-max = 4096
+        "#;
+
+        let cfg = DiskSetup::from_toml_str(toml).unwrap();
+        let zfs = cfg.zfs.unwrap();
+        assert_eq!(zfs.raid, Some(ZfsRaidLevel::Raid1));
+        assert_eq!(zfs.ashift.map(|a| a.get()), Some(12));
+        assert_eq!(zfs.arc_max, Some(4096));
+    }
+
+    #[test]
+    fn invalid_zfs_options_fail_section_validation() {
+        let cfg = DiskSetup {
+            filesystem: Filesystem::Zfs,
+            zfs: Some(ZfsOptions {
+                copies: Some(9),
+                ..ZfsOptions::default()
+            }),
+            ..DiskSetup::default()
+        };
+        assert_eq!(cfg.validate(), Err(DiskSetupError::ZfsCopies));
+    }
+
+    #[test]
+    fn lvm_options_deserialize_from_dotted_keys() {
+        let toml = r#"
+            [disk-setup]
+            filesystem = "ext4"
+            disk-list = ["sda"]
+            lvm.swapsize = 8
+            lvm.maxroot = 32.5
+        "#;
+
+        let cfg = DiskSetup::from_toml_str(toml).unwrap();
+        let lvm = cfg.lvm.unwrap();
+        assert_eq!(lvm.swapsize, Some(8.0));
+        assert_eq!(lvm.maxroot, Some(32.5));
+    }
+
+    #[test]
+    fn invalid_lvm_options_fail_section_validation() {
+        let cfg = DiskSetup {
+            lvm: Some(LvmOptions {
+                swapsize: Some(-2.0),
+                ..LvmOptions::default()
+            }),
+            ..DiskSetup::default()
+        };
+        assert_eq!(cfg.validate(), Err(DiskSetupError::LvmSwapsize));
+    }
+
+    #[test]
+    fn btrfs_options_deserialize_from_dotted_keys() {
+        let toml = r#"
+            [disk-setup]
+            filesystem = "btrfs"
+            disk-list = ["sda", "sdb"]
+            btrfs.raid = "raid1"
+            btrfs.compress = "zstd"
+        "#;
+
+        let cfg = DiskSetup::from_toml_str(toml).unwrap();
+        let btrfs = cfg.btrfs.unwrap();
+        assert_eq!(btrfs.raid.map(|r| r.as_str()), Some("raid1"));
+        assert_eq!(btrfs.compress, Some(BtrfsCompress::Zstd));
+    }
+
+    #[test]
+    fn invalid_btrfs_raid_fails_at_deserialize() {
+        let toml = r#"
+            filesystem = "btrfs"
+            disk-list = ["sda"]
+            btrfs.raid = "raid5"
+        "#;
+
+        let err = DiskSetup::from_toml_str(toml).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("btrfs.raid"), "error was: {}", msg);
+    }
+
+    /* ---------------- TOML SERIALIZATION ---------------- */
+
+    #[test]
+    fn serialization_uses_proxmox_key_names() {
+        let toml = DiskSetup::default().to_toml_string(true).unwrap();
+        assert!(toml.contains("[disk-setup]"), "toml was: {}", toml);
+        assert!(
+            toml.contains(r#"filesystem = "ext4""#),
+            "toml was: {}",
+            toml
+        );
+        assert!(toml.contains("disk-list"), "toml was: {}", toml);
+        assert!(!toml.contains("filter"), "toml was: {}", toml);
+    }
+
+    #[test]
+    fn toml_round_trip_bare() {
+        let cfg = DiskSetup::default();
+        let toml = cfg.to_toml_string(false).unwrap();
+        let parsed = DiskSetup::from_toml_str(&toml).unwrap();
+        assert_eq!(cfg, parsed);
+    }
+
+    #[test]
+    fn toml_round_trip_with_zfs_options() {
+        let cfg = DiskSetup {
+            filesystem: Filesystem::Zfs,
+            zfs: Some(ZfsOptions {
+                raid: Some(ZfsRaidLevel::RaidZ1),
+                compress: Some(ZfsCompress::Zstd),
+                hdsize: Some(120.5),
+                ..ZfsOptions::default()
+            }),
+            disk_list: Some(DiskList::try_new(["sda", "sdb", "sdc"]).unwrap()),
+            ..DiskSetup::default()
+        };
+        let toml = cfg.to_toml_string(true).unwrap();
+        let parsed = DiskSetup::from_toml_str(&toml).unwrap();
+        assert_eq!(cfg, parsed);
+    }
+
+    #[test]
+    fn toml_round_trip_wrapped() {
+        let cfg = DiskSetup {
+            filesystem: Filesystem::Btrfs,
+            disk_list: None,
+            filter: Some(BTreeMap::from([("ID_MODEL".into(), "Samsung*".into())])),
+            filter_match: Some(FilterMatch::All),
+            zfs: None,
+            lvm: None,
+            btrfs: None,
+        };
+        let toml = cfg.to_toml_string(true).unwrap();
+        let parsed = DiskSetup::from_toml_str(&toml).unwrap();
+        assert_eq!(cfg, parsed);
+    }
+
+    /* ---------------- YAML SERIALIZATION ---------------- */
+
+    #[test]
+    fn yaml_round_trip_bare_and_wrapped() {
+        let cfg = DiskSetup {
+            filesystem: Filesystem::Zfs,
+            zfs: Some(ZfsOptions {
+                raid: Some(ZfsRaidLevel::RaidZ1),
+                hdsize: Some(120.5),
+                ..ZfsOptions::default()
+            }),
+            disk_list: Some(DiskList::try_new(["sda", "sdb", "sdc"]).unwrap()),
+            ..DiskSetup::default()
+        };
+        for wrap in [false, true] {
+            let yaml = cfg.to_yaml_string(wrap).unwrap();
+            assert_eq!(yaml.starts_with("disk-setup:"), wrap, "yaml was: {}", yaml);
+            let parsed = DiskSetup::from_yaml_str(&yaml).unwrap();
+            assert_eq!(cfg, parsed);
+        }
+    }
+}
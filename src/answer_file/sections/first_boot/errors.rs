@@ -0,0 +1,66 @@
+use crate::answer_file::macros::config_error_enum;
+
+/* ===================== FIRST BOOT ERROR ===================== */
+
+config_error_enum!(
+    #[derive(Debug, PartialEq)]
+    pub enum FirstBootError {
+        Source => "first_boot.source.invalid_format",
+        Ordering => "first_boot.ordering.invalid_format",
+        Url => "first_boot.url.invalid_format",
+        UrlRequired => "first_boot.url.required",
+        CertFingerprint => "first_boot.cert_fingerprint.invalid_format",
+        CertFingerprintRequired => "first_boot.cert_fingerprint.required",
+        UrlWithoutFromUrl => "first_boot.url.requires_from_url",
+        Script => "first_boot.script.invalid_format",
+        ScriptWithUrl => "first_boot.script.conflicts_with_url",
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    /* ---------------- CODE MAPPING ---------------- */
+
+    #[test]
+    fn error_codes() {
+        for (err, code) in [
+            (FirstBootError::Source, "first_boot.source.invalid_format"),
+            (
+                FirstBootError::Ordering,
+                "first_boot.ordering.invalid_format",
+            ),
+            (FirstBootError::Url, "first_boot.url.invalid_format"),
+            (FirstBootError::UrlRequired, "first_boot.url.required"),
+            (
+                FirstBootError::CertFingerprint,
+                "first_boot.cert_fingerprint.invalid_format",
+            ),
+            (
+                FirstBootError::CertFingerprintRequired,
+                "first_boot.cert_fingerprint.required",
+            ),
+            (
+                FirstBootError::UrlWithoutFromUrl,
+                "first_boot.url.requires_from_url",
+            ),
+            (FirstBootError::Script, "first_boot.script.invalid_format"),
+            (
+                FirstBootError::ScriptWithUrl,
+                "first_boot.script.conflicts_with_url",
+            ),
+        ] {
+            assert_eq!(err.code(), code);
+        }
+    }
+
+    /* ---------------- ERROR TRAIT ---------------- */
+
+    #[test]
+    fn implements_std_error() {
+        let err: &dyn Error = &FirstBootError::Url;
+        assert_eq!(err.to_string(), "first_boot.url.invalid_format");
+    }
+}
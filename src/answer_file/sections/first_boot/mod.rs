@@ -0,0 +1,7 @@
+pub mod errors;
+mod models;
+mod section;
+
+pub use errors::FirstBootError;
+pub use models::{ordering::FirstBootOrdering, source::FirstBootSource};
+pub use section::FirstBoot;
@@ -0,0 +1,2 @@
+pub mod ordering;
+pub mod source;
@@ -0,0 +1,46 @@
+use crate::answer_file::macros::string_enum;
+use crate::answer_file::sections::first_boot::errors::FirstBootError;
+
+/* ===================== FIRST BOOT ORDERING ===================== */
+string_enum!(
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    pub enum FirstBootOrdering {
+        BeforeNetwork => "before-network",
+        NetworkOnline => "network-online",
+        #[default]
+        FullyUp => "fully-up",
+    },
+    FirstBootError,
+    FirstBootError::Ordering
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn default_is_fully_up() {
+        assert_eq!(FirstBootOrdering::default(), FirstBootOrdering::FullyUp);
+    }
+
+    #[test]
+    fn orderings_parse_and_display() {
+        for (s, expected) in [
+            ("before-network", FirstBootOrdering::BeforeNetwork),
+            ("network-online", FirstBootOrdering::NetworkOnline),
+            ("fully-up", FirstBootOrdering::FullyUp),
+        ] {
+            assert_eq!(FirstBootOrdering::from_str(s).unwrap(), expected);
+            assert_eq!(expected.to_string(), s);
+        }
+    }
+
+    #[test]
+    fn invalid_ordering_fails_at_parse() {
+        assert_eq!(
+            FirstBootOrdering::from_str("after-network"),
+            Err(FirstBootError::Ordering)
+        );
+    }
+}
@@ -0,0 +1,44 @@
+use crate::answer_file::macros::string_enum;
+use crate::answer_file::sections::first_boot::errors::FirstBootError;
+
+/* ===================== FIRST BOOT SOURCE ===================== */
+string_enum!(
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    pub enum FirstBootSource {
+        #[default]
+        FromIso => "from-iso",
+        FromUrl => "from-url",
+    },
+    FirstBootError,
+    FirstBootError::Source
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn default_is_from_iso() {
+        assert_eq!(FirstBootSource::default(), FirstBootSource::FromIso);
+    }
+
+    #[test]
+    fn sources_parse_and_display() {
+        for (s, expected) in [
+            ("from-iso", FirstBootSource::FromIso),
+            ("from-url", FirstBootSource::FromUrl),
+        ] {
+            assert_eq!(FirstBootSource::from_str(s).unwrap(), expected);
+            assert_eq!(expected.to_string(), s);
+        }
+    }
+
+    #[test]
+    fn invalid_source_fails_at_parse() {
+        assert_eq!(
+            FirstBootSource::from_str("from-http"),
+            Err(FirstBootError::Source)
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use crate::answer_file::sections::first_boot::{
+    FirstBootError,
+    models::{ordering::FirstBootOrdering, source::FirstBootSource},
+};
+use crate::answer_file::sections::post_installation_webhook::CERT_FINGERPRINT_PATTERN;
+use crate::answer_file::sections::yaml::{section_from_yaml_str, section_to_yaml_string};
+use oxdl::validator::is_valid_url;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct FirstBoot {
+    pub source: FirstBootSource,     // "from-iso", "from-url"
+    pub ordering: FirstBootOrdering, // "before-network", "network-online", "fully-up"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(rename = "cert-fingerprint", skip_serializing_if = "Option::is_none")]
+    pub cert_fingerprint: Option<String>,
+    /// Local path of a script to bundle into the ISO, relative to the answer
+    /// file. Not an installer key: the ISO builder copies the script and
+    /// rewrites the section to `from-iso`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+}
+
+impl FirstBoot {
+    /// `from-url` needs a valid `url`, and a `cert-fingerprint` when it is
+    /// https; a given fingerprint must be colon-separated SHA-256.
+    ///
+    /// A local `script` replaces the source entirely, so it cannot be
+    /// combined with a `url`.
+    pub fn validate(&self) -> Result<(), FirstBootError> {
+        if let Some(fp) = &self.cert_fingerprint
+            && !CERT_FINGERPRINT_PATTERN.is_match(fp)
+        {
+            return Err(FirstBootError::CertFingerprint);
+        }
+
+        if let Some(script) = &self.script {
+            if script.trim().is_empty() {
+                return Err(FirstBootError::Script);
+            }
+            if self.url.is_some() {
+                return Err(FirstBootError::ScriptWithUrl);
+            }
+            return Ok(());
+        }
+
+        match self.source {
+            FirstBootSource::FromUrl => {
+                let url = self.url.as_deref().ok_or(FirstBootError::UrlRequired)?;
+                if !is_valid_url(url) {
+                    return Err(FirstBootError::Url);
+                }
+                let https = url
+                    .get(..8)
+                    .is_some_and(|scheme| scheme.eq_ignore_ascii_case("https://"));
+                if https && self.cert_fingerprint.is_none() {
+                    return Err(FirstBootError::CertFingerprintRequired);
+                }
+            }
+            FirstBootSource::FromIso => {
+                if self.url.is_some() {
+                    return Err(FirstBootError::UrlWithoutFromUrl);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /* -------- FROM TOML STRING (BARE OR [first-boot]) -------- */
+
+    pub fn from_toml_str(s: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        // 1. Wrapped form: [first-boot] (errors inside the table are preserved)
+        let table: toml::Table = toml::from_str(s)?;
+        let cfg: FirstBoot = match table.get("first-boot") {
+            Some(section) => section.clone().try_into()?,
+            // 2. Fallback to bare form
+            None => toml::from_str(s)?,
+        };
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /* -------- TO TOML STRING (BARE OR [first-boot]) -------- */
+
+    pub fn to_toml_string(&self, wrap: bool) -> Result<String, Box<dyn std::error::Error>> {
+        self.validate()?;
+
+        if wrap {
+            #[derive(serde::Serialize)]
+            struct Wrapped<'a> {
+                #[serde(rename = "first-boot")]
+                first_boot: &'a FirstBoot,
+            }
+            Ok(toml::to_string_pretty(&Wrapped { first_boot: self })?)
+        } else {
+            Ok(toml::to_string_pretty(self)?)
+        }
+    }
+
+    /* -------- FROM YAML STRING (BARE OR first-boot:) -------- */
+
+    pub fn from_yaml_str(s: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let cfg: FirstBoot = section_from_yaml_str(s, "first-boot")?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /* -------- TO YAML STRING (BARE OR first-boot:) -------- */
+
+    pub fn to_yaml_string(&self, wrap: bool) -> Result<String, Box<dyn std::error::Error>> {
+        self.validate()?;
+        section_to_yaml_string(self, "first-boot", wrap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FINGERPRINT: &str = "AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89";
+
+    fn from_url() -> FirstBoot {
+        FirstBoot {
+            source: FirstBootSource::FromUrl,
+            ordering: FirstBootOrdering::NetworkOnline,
+            url: Some("https://10.0.0.1:8443/first-boot.sh".into()),
+            cert_fingerprint: Some(FINGERPRINT.into()),
+            script: None,
+        }
+    }
+
+    /* ---------------- DEFAULTS ---------------- */
+
+    #[test]
+    fn defaults_are_correct() {
+        let cfg = FirstBoot::default();
+        assert_eq!(cfg.source, FirstBootSource::FromIso);
+        assert_eq!(cfg.ordering, FirstBootOrdering::FullyUp);
+        assert!(cfg.validate().is_ok());
+    }
+
+    /* ---------------- STRUCTURAL VALIDATION ---------------- */
+
+    #[test]
+    fn from_url_with_url_and_fingerprint_is_valid() {
+        assert!(from_url().validate().is_ok());
+    }
+
+    #[test]
+    fn from_url_requires_url() {
+        let cfg = FirstBoot {
+            url: None,
+            ..from_url()
+        };
+        assert_eq!(cfg.validate(), Err(FirstBootError::UrlRequired));
+    }
+
+    #[test]
+    fn from_url_rejects_invalid_url() {
+        let cfg = FirstBoot {
+            url: Some("ftp://10.0.0.1/first-boot.sh".into()),
+            ..from_url()
+        };
+        assert_eq!(cfg.validate(), Err(FirstBootError::Url));
+    }
+
+    #[test]
+    fn https_url_requires_fingerprint() {
+        let cfg = FirstBoot {
+            cert_fingerprint: None,
+            ..from_url()
+        };
+        assert_eq!(cfg.validate(), Err(FirstBootError::CertFingerprintRequired));
+
+        let cfg = FirstBoot {
+            url: Some("http://10.0.0.1:8000/first-boot.sh".into()),
+            cert_fingerprint: None,
+            ..from_url()
+        };
+        assert!(cfg.validate().is_ok());
+    }
+
+    #[test]
+    fn malformed_fingerprint_fails() {
+        for fp in ["AB:CD", &FINGERPRINT.replace(':', ""), "sha256:abc"] {
+            let cfg = FirstBoot {
+                cert_fingerprint: Some(fp.into()),
+                ..from_url()
+            };
+            assert_eq!(
+                cfg.validate(),
+                Err(FirstBootError::CertFingerprint),
+                "{}",
+                fp
+            );
+        }
+    }
+
+    #[test]
+    fn from_iso_rejects_url() {
+        let cfg = FirstBoot {
+            source: FirstBootSource::FromIso,
+            ..from_url()
+        };
+        assert_eq!(cfg.validate(), Err(FirstBootError::UrlWithoutFromUrl));
+    }
+
+    #[test]
+    fn local_script_replaces_the_source() {
+        let cfg = FirstBoot {
+            source: FirstBootSource::FromUrl,
+            url: None,
+            script: Some("scripts/first-boot.sh".into()),
+            ..from_url()
+        };
+        assert!(cfg.validate().is_ok());
+
+        let cfg = FirstBoot {
+            script: Some("scripts/first-boot.sh".into()),
+            ..from_url()
+        };
+        assert_eq!(cfg.validate(), Err(FirstBootError::ScriptWithUrl));
+
+        let cfg = FirstBoot {
+            script: Some(" ".into()),
+            ..FirstBoot::default()
+        };
+        assert_eq!(cfg.validate(), Err(FirstBootError::Script));
+    }
+
+    /* ---------------- TOML DESERIALIZATION ---------------- */
+
+    #[test]
+    fn valid_toml_deserialization_wrapped() {
+        let toml = format!(
+            r#"
+            [first-boot]
+            source = "from-url"
+            ordering = "before-network"
+            url = "https://10.0.0.1/first-boot.sh"
+            cert-fingerprint = "{}"
+        "#,
+            FINGERPRINT
+        );
+
+        let cfg = FirstBoot::from_toml_str(&toml).unwrap();
+        assert_eq!(cfg.source, FirstBootSource::FromUrl);
+        assert_eq!(cfg.ordering, FirstBootOrdering::BeforeNetwork);
+    }
+
+    #[test]
+    fn invalid_ordering_fails_at_deserialize() {
+        let toml = r#"
+            [first-boot]
+            source = "from-iso"
+            ordering = "eventually"
+        "#;
+
+        let err = FirstBoot::from_toml_str(toml).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("first_boot.ordering"), "error was: {}", msg);
+    }
+
+    #[test]
+    fn missing_url_fails_validation_after_deserialize() {
+        let toml = r#"
+            source = "from-url"
+        "#;
+
+        let err = FirstBoot::from_toml_str(toml).unwrap_err();
+        assert_eq!(err.to_string(), "first_boot.url.required");
+    }
+
+    /* ---------------- TOML SERIALIZATION ---------------- */
+
+    #[test]
+    fn serialization_uses_proxmox_key_names() {
+        let toml = from_url().to_toml_string(true).unwrap();
+        assert!(toml.contains("[first-boot]"), "toml was: {}", toml);
+        assert!(toml.contains("cert-fingerprint"), "toml was: {}", toml);
+        assert!(
+            toml.contains(r#"source = "from-url""#),
+            "toml was: {}",
+            toml
+        );
+    }
+
+    #[test]
+    fn toml_round_trip_bare() {
+        let cfg = FirstBoot::default();
+        let toml = cfg.to_toml_string(false).unwrap();
+        let parsed = FirstBoot::from_toml_str(&toml).unwrap();
+        assert_eq!(cfg, parsed);
+    }
+
+    #[test]
+    fn toml_round_trip_wrapped() {
+        let cfg = from_url();
+        let toml = cfg.to_toml_string(true).unwrap();
+        let parsed = FirstBoot::from_toml_str(&toml).unwrap();
+        assert_eq!(cfg, parsed);
+    }
+
+    /* ---------------- YAML SERIALIZATION ---------------- */
+
+    #[test]
+    fn yaml_round_trip_bare_and_wrapped() {
+        let cfg = from_url();
+        for wrap in [false, true] {
+            let yaml = cfg.to_yaml_string(wrap).unwrap();
+            assert_eq!(yaml.starts_with("first-boot:"), wrap, "yaml was: {}", yaml);
+            let parsed = FirstBoot::from_yaml_str(&yaml).unwrap();
+            assert_eq!(cfg, parsed);
+        }
+    }
+}
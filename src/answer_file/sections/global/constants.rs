@@ -11,9 +11,30 @@ pub static FQDN_PATTERN: Lazy<Regex> = Lazy::new(|| {
         .expect("invalid FQDN_PATTERN")
 });
 
+/// Any supported `root-password-hashed` format; see the per-format patterns below.
 pub static HASHED_PASSWORD_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        "({})|({})|({})",
+        SHA512_CRYPT_PATTERN.as_str(),
+        SHA256_CRYPT_PATTERN.as_str(),
+        YESCRYPT_PATTERN.as_str()
+    ))
+    .expect("invalid HASHED_PASSWORD_PATTERN")
+});
+
+pub static SHA512_CRYPT_PATTERN: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"^\$6\$rounds=\d{6}\$[./A-Za-z0-9]{1,16}\$[./A-Za-z0-9]{86}$")
-        .expect("invalid HASHED_PASSWORD_PATTERN")
+        .expect("invalid SHA512_CRYPT_PATTERN")
+});
+
+pub static SHA256_CRYPT_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\$5\$rounds=\d{6}\$[./A-Za-z0-9]{1,16}\$[./A-Za-z0-9]{43}$")
+        .expect("invalid SHA256_CRYPT_PATTERN")
+});
+
+pub static YESCRYPT_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\$y\$[./A-Za-z0-9]+\$[./A-Za-z0-9]{1,86}\$[./A-Za-z0-9]{43}$")
+        .expect("invalid YESCRYPT_PATTERN")
 });
 
 #[cfg(test)]
@@ -104,12 +125,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn sha256_and_yescrypt_hashes_match() {
+        let sha256 = format!("$5$rounds=100000$14y6mKwGoJXW643/${}", "B".repeat(43));
+        let yescrypt = "$y$j9T$F5Jx5fExrKuPp53xLKQ..1$X3DX6M94c7o.9agCG9G317fhZg9SqC.5i5rd.RhAtQ7";
+        for hash in [sha256.as_str(), yescrypt] {
+            assert!(
+                HASHED_PASSWORD_PATTERN.is_match(hash),
+                "expected valid hash to match: {}",
+                hash
+            );
+        }
+        assert!(SHA256_CRYPT_PATTERN.is_match(&sha256));
+        assert!(YESCRYPT_PATTERN.is_match(yescrypt));
+        assert!(!SHA512_CRYPT_PATTERN.is_match(yescrypt));
+    }
+
     #[test]
     fn invalid_hashed_password_fails() {
         for hash in [
             "password",
             "$6$rounds=656000$short$hash",
             "$1$rounds=656000$12345678$invalid",
+            "$5$rounds=656000$12345678$tooshort",
+            "$y$j9T$salt$tooshort",
         ] {
             assert!(
                 !HASHED_PASSWORD_PATTERN.is_match(hash),
@@ -9,9 +9,19 @@ config_error_enum!(
         Country => "country.invalid_format",
         Timezone => "timezone.invalid_format",
         Fqdn => "fqdn.invalid_format",
+        FqdnTemplate => "fqdn.template.invalid_placeholder",
+        FqdnTemplateValue => "fqdn.template.missing_value",
         Mailto => "mailto.invalid_format",
         RootSshKeys => "root_ssh_keys.invalid_format",
         RootPasswordHashed => "root_password_hashed.invalid_format",
+        RootPasswordSha256 => "root_password_hashed.sha256.invalid_format",
+        RootPasswordSha512 => "root_password_hashed.sha512.invalid_format",
+        RootPasswordYescrypt => "root_password_hashed.yescrypt.invalid_format",
+        RootPasswordMethod => "root_password_hashed.unknown_method",
+        RootPasswordEmpty => "root_password.empty",
+        RootPasswordPlaintext => "root_password.plaintext",
+        RootPasswordRounds => "root_password_hashed.rounds_out_of_range",
+        RootPasswordPlaceholder => "root_password_hashed.placeholder",
         RebootOnError => "reboot_on_error.invalid_format",
         RebootMode => "reboot_mode.invalid_format",
     }
@@ -50,6 +60,18 @@ mod tests {
         assert_eq!(GlobalConfigError::Fqdn.code(), "fqdn.invalid_format");
     }
 
+    #[test]
+    fn fqdn_template_error_codes() {
+        assert_eq!(
+            GlobalConfigError::FqdnTemplate.code(),
+            "fqdn.template.invalid_placeholder"
+        );
+        assert_eq!(
+            GlobalConfigError::FqdnTemplateValue.code(),
+            "fqdn.template.missing_value"
+        );
+    }
+
     #[test]
     fn mailto_error_code() {
         assert_eq!(GlobalConfigError::Mailto.code(), "mailto.invalid_format");
@@ -71,6 +93,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn root_password_per_format_error_codes() {
+        assert_eq!(
+            GlobalConfigError::RootPasswordSha256.code(),
+            "root_password_hashed.sha256.invalid_format"
+        );
+        assert_eq!(
+            GlobalConfigError::RootPasswordSha512.code(),
+            "root_password_hashed.sha512.invalid_format"
+        );
+        assert_eq!(
+            GlobalConfigError::RootPasswordYescrypt.code(),
+            "root_password_hashed.yescrypt.invalid_format"
+        );
+    }
+
+    #[test]
+    fn root_password_method_error_code() {
+        assert_eq!(
+            GlobalConfigError::RootPasswordMethod.code(),
+            "root_password_hashed.unknown_method"
+        );
+    }
+
+    #[test]
+    fn root_password_empty_error_code() {
+        assert_eq!(
+            GlobalConfigError::RootPasswordEmpty.code(),
+            "root_password.empty"
+        );
+    }
+
+    #[test]
+    fn root_password_plaintext_error_code() {
+        assert_eq!(
+            GlobalConfigError::RootPasswordPlaintext.code(),
+            "root_password.plaintext"
+        );
+    }
+
+    #[test]
+    fn root_password_rounds_error_code() {
+        assert_eq!(
+            GlobalConfigError::RootPasswordRounds.code(),
+            "root_password_hashed.rounds_out_of_range"
+        );
+    }
+
     #[test]
     fn reboot_on_error_error_code() {
         assert_eq!(
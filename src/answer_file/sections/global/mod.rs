@@ -1,12 +1,27 @@
 pub mod constants;
 pub mod errors;
 mod models;
+pub mod password;
 mod section;
 
-pub use constants::{EMAIL_OR_LOCALHOST_PATTERN, FQDN_PATTERN, HASHED_PASSWORD_PATTERN};
+pub use constants::{
+    EMAIL_OR_LOCALHOST_PATTERN, FQDN_PATTERN, HASHED_PASSWORD_PATTERN, SHA256_CRYPT_PATTERN,
+    SHA512_CRYPT_PATTERN, YESCRYPT_PATTERN,
+};
 pub use errors::GlobalConfigError;
 pub use models::{
-    allowed_keyboards::KeyboardLayout, country::CountryCode, reboot_mode::RebootMode,
+    allowed_keyboards::KeyboardLayout,
+    country::CountryCode,
+    email::EmailAddress,
+    fqdn_template::{FqdnTemplate, HostIdentity},
+    hashed_password::{HashedPassword, PLACEHOLDER_HASH},
+    password_hash_format::PasswordHashFormat,
+    plaintext_password::PlaintextPassword,
+    reboot_mode::RebootMode,
     timezone::Timezone,
 };
+pub use password::{
+    DEFAULT_HASH_ROUNDS, HASH_ROUNDS_RANGE, hash_password, hash_password_as,
+    hash_password_with_rounds, verify_password,
+};
 pub use section::GlobalConfig;
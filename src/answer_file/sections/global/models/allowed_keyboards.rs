@@ -34,6 +34,117 @@ string_enum!(
     GlobalConfigError::Keyboard
 );
 
+/* ===================== DETECTION ===================== */
+
+impl KeyboardLayout {
+    /// Closest supported layout for this machine.
+    ///
+    /// The locale environment (`LC_ALL`, then `LANG`) is checked first,
+    /// then the X11 layout and system locale reported by `localectl status`.
+    /// `None` when neither names a supported layout (e.g. `LANG=C`).
+    pub fn detect() -> Option<Self> {
+        ["LC_ALL", "LANG"]
+            .into_iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|v| !v.is_empty())
+            .and_then(|locale| Self::from_locale(&locale))
+            .or_else(|| {
+                let output = std::process::Command::new("localectl")
+                    .arg("status")
+                    .output()
+                    .ok()?;
+                Self::from_localectl(&String::from_utf8_lossy(&output.stdout))
+            })
+    }
+
+    /// Maps a POSIX locale such as `de_CH.UTF-8` to its usual layout.
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        let name = locale.split(['.', '@']).next()?;
+        let (language, territory) = name.split_once('_').unwrap_or((name, ""));
+
+        Some(match (language, territory) {
+            ("de", "CH") => Self::GermanSwiss,
+            ("de", _) => Self::German,
+            ("da", _) => Self::Danish,
+            ("en", "GB" | "IE") => Self::EnglishUK,
+            ("en", _) => Self::EnglishUS,
+            ("es", _) => Self::Spanish,
+            ("fi", _) => Self::Finnish,
+            ("fr", "BE") => Self::FrenchBelgium,
+            ("fr", "CA") => Self::FrenchCanada,
+            ("fr", "CH") => Self::FrenchSwiss,
+            ("fr", _) => Self::French,
+            ("hu", _) => Self::Hungarian,
+            ("is", _) => Self::Icelandic,
+            ("it", _) => Self::Italian,
+            ("ja", _) => Self::Japanese,
+            ("lt", _) => Self::Lithuanian,
+            ("mk", _) => Self::Macedonian,
+            ("nl", _) => Self::Dutch,
+            ("nb" | "nn" | "no", _) => Self::Norwegian,
+            ("pl", _) => Self::Polish,
+            ("pt", "BR") => Self::PortugueseBrazil,
+            ("pt", _) => Self::Portuguese,
+            ("sv", _) => Self::Swedish,
+            ("sl", _) => Self::Slovenian,
+            ("tr", _) => Self::Turkish,
+            _ => return None,
+        })
+    }
+
+    /// Maps an X11 layout name (`setxkbmap`, `localectl`) to the installer layout.
+    pub fn from_x11_layout(layout: &str) -> Option<Self> {
+        Some(match layout {
+            "de" => Self::German,
+            "ch" => Self::GermanSwiss,
+            "dk" => Self::Danish,
+            "gb" => Self::EnglishUK,
+            "us" => Self::EnglishUS,
+            "es" => Self::Spanish,
+            "fi" => Self::Finnish,
+            "fr" => Self::French,
+            "be" => Self::FrenchBelgium,
+            "ca" => Self::FrenchCanada,
+            "hu" => Self::Hungarian,
+            "is" => Self::Icelandic,
+            "it" => Self::Italian,
+            "jp" => Self::Japanese,
+            "lt" => Self::Lithuanian,
+            "mk" => Self::Macedonian,
+            "nl" => Self::Dutch,
+            "no" => Self::Norwegian,
+            "pl" => Self::Polish,
+            "pt" => Self::Portuguese,
+            "br" => Self::PortugueseBrazil,
+            "se" => Self::Swedish,
+            "si" => Self::Slovenian,
+            "tr" => Self::Turkish,
+            _ => return None,
+        })
+    }
+
+    /// Reads `localectl status` output, preferring the first X11 layout over
+    /// the `LANG` of the system locale.
+    fn from_localectl(output: &str) -> Option<Self> {
+        let field = |name: &str| {
+            output.lines().find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                (key.trim() == name).then(|| value.trim())
+            })
+        };
+
+        field("X11 Layout")
+            .and_then(|layouts| layouts.split(',').next())
+            .and_then(|layout| Self::from_x11_layout(layout.trim()))
+            .or_else(|| {
+                field("System Locale")?
+                    .split_whitespace()
+                    .find_map(|pair| pair.strip_prefix("LANG="))
+                    .and_then(Self::from_locale)
+            })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,6 +231,44 @@ mod tests {
         assert_eq!(parsed, w);
     }
 
+    /* ---------------- DETECTION ---------------- */
+
+    #[test]
+    fn locales_map_to_closest_layout() {
+        for (locale, expected) in [
+            ("de_DE.UTF-8", Some(KeyboardLayout::German)),
+            ("de_CH.utf8", Some(KeyboardLayout::GermanSwiss)),
+            ("en_GB.UTF-8", Some(KeyboardLayout::EnglishUK)),
+            ("en_AU.UTF-8", Some(KeyboardLayout::EnglishUS)),
+            ("fr_CA", Some(KeyboardLayout::FrenchCanada)),
+            ("pt_BR.UTF-8", Some(KeyboardLayout::PortugueseBrazil)),
+            ("sv_SE.UTF-8@euro", Some(KeyboardLayout::Swedish)),
+            ("nb_NO.UTF-8", Some(KeyboardLayout::Norwegian)),
+            ("ja", Some(KeyboardLayout::Japanese)),
+            ("C.UTF-8", None),
+            ("POSIX", None),
+            ("zh_CN.UTF-8", None),
+        ] {
+            assert_eq!(KeyboardLayout::from_locale(locale), expected, "{}", locale);
+        }
+    }
+
+    #[test]
+    fn localectl_prefers_x11_layout() {
+        let output = "   System Locale: LANG=en_US.UTF-8\n       VC Keymap: de-latin1\n      X11 Layout: de,us\n";
+        assert_eq!(
+            KeyboardLayout::from_localectl(output),
+            Some(KeyboardLayout::German)
+        );
+
+        let output = "   System Locale: LC_CTYPE=C LANG=fr_BE.UTF-8\n       VC Keymap: n/a\n      X11 Layout: n/a\n";
+        assert_eq!(
+            KeyboardLayout::from_localectl(output),
+            Some(KeyboardLayout::FrenchBelgium)
+        );
+        assert_eq!(KeyboardLayout::from_localectl(""), None);
+    }
+
     /* ---------------- COVERAGE SANITY ---------------- */
 
     #[test]
@@ -1,4 +1,4 @@
-use crate::answer_file::sections::global::errors::GlobalConfigError;
+use crate::answer_file::sections::global::{errors::GlobalConfigError, models::timezone::Timezone};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::{
@@ -22,6 +22,23 @@ static PROX_COUNTRY_NAME_TO_CODE: Lazy<HashMap<String, String>> = Lazy::new(|| {
     .collect()
 });
 
+/// Country of each zone, from the tz database's `zone.tab`. Zones shared by
+/// several countries (e.g. "UTC") and countries outside the dataset are absent.
+static PROX_TIMEZONE_COUNTRIES: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
+    include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/data/timezone_countries.txt"
+    ))
+    .lines()
+    .map(str::trim)
+    .filter(|l| !l.is_empty())
+    .filter_map(|line| {
+        let (zone, code) = line.split_once(char::is_whitespace)?;
+        Some((zone.trim(), code.trim()))
+    })
+    .collect()
+});
+
 static PROX_COUNTRY_CODES: Lazy<HashSet<&'static str>> = Lazy::new(|| {
     PROX_COUNTRY_NAME_TO_CODE
         .values()
@@ -29,6 +46,13 @@ static PROX_COUNTRY_CODES: Lazy<HashSet<&'static str>> = Lazy::new(|| {
         .collect()
 });
 
+static PROX_COUNTRY_CODE_TO_NAME: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
+    PROX_COUNTRY_NAME_TO_CODE
+        .iter()
+        .map(|(name, code)| (code.as_str(), name.as_str()))
+        .collect()
+});
+
 #[derive(Debug, Clone, Serialize, PartialEq, Eq, Hash)]
 pub struct CountryCode(String);
 
@@ -42,6 +66,46 @@ impl CountryCode {
     pub fn try_new(s: &str) -> Result<Self, GlobalConfigError> {
         s.parse()
     }
+
+    /// Looks up a code by its country name, ignoring case and surrounding
+    /// whitespace (e.g. "United States" -> "us")
+    pub fn from_name(name: &str) -> Result<Self, GlobalConfigError> {
+        let name = name.trim();
+        PROX_COUNTRY_NAME_TO_CODE
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(name))
+            .map(|(_, code)| Self(code.clone()))
+            .ok_or(GlobalConfigError::Country)
+    }
+
+    /// Human-readable country name (e.g. "United States")
+    pub fn name(&self) -> &'static str {
+        PROX_COUNTRY_CODE_TO_NAME
+            .get(self.0.as_str())
+            .copied()
+            .expect("CountryCode is validated against the dataset on construction")
+    }
+
+    /// Country the zone belongs to (e.g. "Europe/Berlin" -> "de"), as a
+    /// suggestion the user can still override. `None` for zones such as "UTC".
+    pub fn from_timezone(timezone: &Timezone) -> Option<Self> {
+        PROX_TIMEZONE_COUNTRIES
+            .get(timezone.as_str())
+            .filter(|code| PROX_COUNTRY_CODES.contains(*code))
+            .map(|code| Self((*code).to_owned()))
+    }
+
+    /// All known `(name, code)` pairs, sorted by name
+    pub fn all() -> impl Iterator<Item = (&'static str, CountryCode)> {
+        let mut pairs: Vec<(&'static str, &'static str)> = PROX_COUNTRY_NAME_TO_CODE
+            .iter()
+            .map(|(name, code)| (name.as_str(), code.as_str()))
+            .collect();
+        pairs.sort_unstable();
+        pairs
+            .into_iter()
+            .map(|(name, code)| (name, Self(code.to_owned())))
+    }
 }
 
 impl FromStr for CountryCode {
@@ -69,6 +133,21 @@ impl Default for CountryCode {
     }
 }
 
+impl schemars::JsonSchema for CountryCode {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "CountryCode".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        let mut codes: Vec<&str> = PROX_COUNTRY_CODES.iter().copied().collect();
+        codes.sort_unstable();
+        schemars::json_schema!({
+            "type": "string",
+            "enum": codes
+        })
+    }
+}
+
 impl<'de> Deserialize<'de> for CountryCode {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -122,6 +201,69 @@ mod tests {
         );
     }
 
+    /* ---------------- NAMES ---------------- */
+
+    #[test]
+    fn from_name_resolves_code() {
+        let c = CountryCode::from_name("United States").unwrap();
+        assert_eq!(c.as_str(), "us");
+    }
+
+    #[test]
+    fn from_name_ignores_case_and_whitespace() {
+        let c = CountryCode::from_name("  united kingdom ").unwrap();
+        assert_eq!(c.as_str(), "gb");
+    }
+
+    #[test]
+    fn from_name_rejects_unknown_name() {
+        assert_eq!(
+            CountryCode::from_name("Atlantis"),
+            Err(GlobalConfigError::Country)
+        );
+    }
+
+    #[test]
+    fn name_reverses_lookup() {
+        assert_eq!(CountryCode::default().name(), "United States");
+        assert_eq!(CountryCode::from_str("DE").unwrap().name(), "Germany");
+    }
+
+    #[test]
+    fn all_lists_every_code_sorted_by_name() {
+        let all: Vec<_> = CountryCode::all().collect();
+        assert_eq!(all.len(), PROX_COUNTRY_CODES.len());
+        assert!(all.windows(2).all(|w| w[0].0 <= w[1].0));
+        for (name, code) in all {
+            assert_eq!(CountryCode::from_name(name).unwrap(), code);
+            assert_eq!(code.name(), name);
+        }
+    }
+
+    /* ---------------- FROM_TIMEZONE ---------------- */
+
+    #[test]
+    fn from_timezone_suggests_country() {
+        for (zone, code) in [
+            ("Europe/Berlin", "de"),
+            ("America/New_York", "us"),
+            ("America/Toronto", "ca"),
+            ("Asia/Tokyo", "jp"),
+            ("US/Eastern", "us"),
+        ] {
+            let tz = Timezone::from_str(zone).unwrap();
+            assert_eq!(CountryCode::from_timezone(&tz).unwrap().as_str(), code);
+        }
+        assert_eq!(CountryCode::from_timezone(&Timezone::default()), None);
+    }
+
+    #[test]
+    fn every_mapped_country_is_known() {
+        for code in PROX_TIMEZONE_COUNTRIES.values() {
+            assert!(PROX_COUNTRY_CODES.contains(code), "{}", code);
+        }
+    }
+
     /* ---------------- TRY_NEW ---------------- */
 
     #[test]
@@ -0,0 +1,144 @@
+use crate::answer_file::sections::global::{
+    constants::EMAIL_OR_LOCALHOST_PATTERN, errors::GlobalConfigError,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{fmt, str::FromStr};
+
+/// `mailto` address: an email address or one of the `root`/`admin`/`user`
+/// `@localhost` mailboxes, checked against [`EMAIL_OR_LOCALHOST_PATTERN`].
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, Hash)]
+pub struct EmailAddress(String);
+
+impl EmailAddress {
+    /// Canonical string value (e.g. "admin@example.com")
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Explicit constructor for non-default manipulation
+    pub fn try_new(s: &str) -> Result<Self, GlobalConfigError> {
+        s.parse()
+    }
+}
+
+impl FromStr for EmailAddress {
+    type Err = GlobalConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let email = s.trim();
+        if EMAIL_OR_LOCALHOST_PATTERN.is_match(email) {
+            Ok(Self(email.to_owned()))
+        } else {
+            Err(GlobalConfigError::Mailto)
+        }
+    }
+}
+
+impl fmt::Display for EmailAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Default for EmailAddress {
+    fn default() -> Self {
+        Self("root@localhost".into())
+    }
+}
+
+impl PartialEq<str> for EmailAddress {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for EmailAddress {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl schemars::JsonSchema for EmailAddress {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "EmailAddress".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "pattern": EMAIL_OR_LOCALHOST_PATTERN.as_str()
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for EmailAddress {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* ---------------- DEFAULT ---------------- */
+
+    #[test]
+    fn default_email_is_root_localhost_and_valid() {
+        let email = EmailAddress::default();
+        assert_eq!(email.as_str(), "root@localhost");
+        assert_eq!(EmailAddress::try_new(email.as_str()), Ok(email));
+    }
+
+    /* ---------------- FROMSTR ---------------- */
+
+    #[test]
+    fn valid_email_parses_trimmed() {
+        let email = EmailAddress::from_str("  admin@example.com ").unwrap();
+        assert_eq!(email, "admin@example.com");
+        assert_eq!(email.to_string(), "admin@example.com");
+    }
+
+    #[test]
+    fn invalid_email_fails_at_parse() {
+        for email in ["nope", "root@local", "user@", ""] {
+            assert_eq!(
+                EmailAddress::from_str(email),
+                Err(GlobalConfigError::Mailto),
+                "{}",
+                email
+            );
+        }
+    }
+
+    /* ---------------- SERDE ---------------- */
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Wrapper {
+        mailto: EmailAddress,
+    }
+
+    #[test]
+    fn serde_round_trip_preserves_string_value() {
+        let w: Wrapper = toml::from_str(r#"mailto = "ops@example.org""#).unwrap();
+        assert_eq!(w.mailto, "ops@example.org");
+        assert_eq!(
+            toml::to_string(&w).unwrap().trim(),
+            r#"mailto = "ops@example.org""#
+        );
+    }
+
+    #[test]
+    fn serde_rejects_invalid_email() {
+        let err = toml::from_str::<Wrapper>(r#"mailto = "root@local""#).unwrap_err();
+        assert!(
+            err.to_string().contains("mailto.invalid_format"),
+            "error was: {}",
+            err
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use crate::answer_file::sections::global::{constants::FQDN_PATTERN, errors::GlobalConfigError};
+use std::{fmt, str::FromStr};
+
+/// Per-host values substituted into an [`FqdnTemplate`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostIdentity {
+    /// MAC address in any common notation (`aa:bb:..`, `aa-bb-..`, `aabb.cc..`).
+    pub mac: Option<String>,
+    /// Hardware serial number, e.g. from DMI.
+    pub serial: Option<String>,
+}
+
+impl HostIdentity {
+    pub fn from_mac(mac: &str) -> Self {
+        Self {
+            mac: Some(mac.to_owned()),
+            serial: None,
+        }
+    }
+
+    pub fn from_serial(serial: &str) -> Self {
+        Self {
+            mac: None,
+            serial: Some(serial.to_owned()),
+        }
+    }
+
+    /// The 12 hex digits of the MAC, lowercase, without separators.
+    ///
+    /// # Errors
+    /// [`GlobalConfigError::FqdnTemplateValue`] if there is no MAC or it is malformed.
+    pub fn mac_digits(&self) -> Result<String, GlobalConfigError> {
+        let mac = self
+            .mac
+            .as_deref()
+            .ok_or(GlobalConfigError::FqdnTemplateValue)?;
+        let digits: String = mac
+            .chars()
+            .filter(|c| !matches!(c, ':' | '-' | '.'))
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+
+        if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(GlobalConfigError::FqdnTemplateValue);
+        }
+        Ok(digits)
+    }
+}
+
+/// An `fqdn` with `{placeholder}`s expanded per host, e.g. `pve-{mac6}.lab.local`.
+///
+/// Placeholders:
+/// - `{mac}`: all 12 hex digits of the MAC, lowercase, without separators
+/// - `{mac6}`: the last 6 hex digits of the MAC (the NIC-specific half)
+/// - `{serial}`: the serial, lowercased, with runs of other characters
+///   collapsed to `-`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FqdnTemplate(String);
+
+impl FqdnTemplate {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the template contains any placeholder.
+    pub fn is_templated(&self) -> bool {
+        self.0.contains('{')
+    }
+
+    /// Substitutes `host`'s values and checks the result against [`FQDN_PATTERN`].
+    ///
+    /// # Errors
+    /// [`GlobalConfigError::FqdnTemplateValue`] if a placeholder's value is
+    /// missing from `host` or is not a valid MAC; [`GlobalConfigError::Fqdn`]
+    /// if the expanded name is not a valid FQDN.
+    pub fn expand(&self, host: &HostIdentity) -> Result<String, GlobalConfigError> {
+        let mut out = String::with_capacity(self.0.len());
+        let mut rest = self.0.as_str();
+
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let end = after.find('}').ok_or(GlobalConfigError::FqdnTemplate)?;
+
+            let value = match &after[..end] {
+                "mac" => host.mac_digits()?,
+                "mac6" => host.mac_digits()?.split_off(6),
+                "serial" => serial_label(host)?,
+                _ => return Err(GlobalConfigError::FqdnTemplate),
+            };
+            out.push_str(&value);
+            rest = &after[end + 1..];
+        }
+        out.push_str(rest);
+
+        if out.len() > 255 || !FQDN_PATTERN.is_match(&out) {
+            return Err(GlobalConfigError::Fqdn);
+        }
+        Ok(out)
+    }
+}
+
+impl FromStr for FqdnTemplate {
+    type Err = GlobalConfigError;
+
+    /// Accepts any string whose placeholders are known and balanced; plain
+    /// FQDNs are valid templates without placeholders.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let template = s.trim();
+        let mut rest = template;
+
+        while let Some(start) = rest.find(['{', '}']) {
+            if rest[start..].starts_with('}') {
+                return Err(GlobalConfigError::FqdnTemplate);
+            }
+            let after = &rest[start + 1..];
+            let end = after.find('}').ok_or(GlobalConfigError::FqdnTemplate)?;
+            if !matches!(&after[..end], "mac" | "mac6" | "serial") {
+                return Err(GlobalConfigError::FqdnTemplate);
+            }
+            rest = &after[end + 1..];
+        }
+
+        if template.is_empty() {
+            return Err(GlobalConfigError::FqdnTemplate);
+        }
+        Ok(Self(template.to_owned()))
+    }
+}
+
+impl fmt::Display for FqdnTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+fn serial_label(host: &HostIdentity) -> Result<String, GlobalConfigError> {
+    let serial = host
+        .serial
+        .as_deref()
+        .ok_or(GlobalConfigError::FqdnTemplateValue)?;
+    let label = serial
+        .to_ascii_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if label.is_empty() {
+        return Err(GlobalConfigError::FqdnTemplateValue);
+    }
+    Ok(label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* ---------------- PARSING ---------------- */
+
+    #[test]
+    fn known_placeholders_parse() {
+        for template in [
+            "pve-{mac6}.lab.local",
+            "{mac}.lab.local",
+            "node-{serial}.example.com",
+            "pve01.lab.local",
+        ] {
+            assert!(FqdnTemplate::from_str(template).is_ok(), "{}", template);
+        }
+        assert!(
+            !FqdnTemplate::from_str("pve01.lab.local")
+                .unwrap()
+                .is_templated()
+        );
+    }
+
+    #[test]
+    fn unknown_or_unbalanced_placeholders_fail() {
+        for template in ["pve-{hostname}.lab", "pve-{mac6.lab", "pve-mac6}.lab", ""] {
+            assert_eq!(
+                FqdnTemplate::from_str(template),
+                Err(GlobalConfigError::FqdnTemplate),
+                "{}",
+                template
+            );
+        }
+    }
+
+    /* ---------------- EXPANSION ---------------- */
+
+    #[test]
+    fn mac_placeholders_expand_from_any_notation() {
+        let template = FqdnTemplate::from_str("pve-{mac6}.lab.local").unwrap();
+        for mac in ["AA:BB:CC:DD:EE:FF", "aa-bb-cc-dd-ee-ff", "aabb.ccdd.eeff"] {
+            assert_eq!(
+                template.expand(&HostIdentity::from_mac(mac)).unwrap(),
+                "pve-ddeeff.lab.local"
+            );
+        }
+
+        let full = FqdnTemplate::from_str("{mac}.lab.local").unwrap();
+        assert_eq!(
+            full.expand(&HostIdentity::from_mac("00:11:22:33:44:55"))
+                .unwrap(),
+            "001122334455.lab.local"
+        );
+    }
+
+    #[test]
+    fn serial_is_normalized_to_a_label() {
+        let template = FqdnTemplate::from_str("node-{serial}.example.com").unwrap();
+        assert_eq!(
+            template
+                .expand(&HostIdentity::from_serial("  CZ 2049_0XK/7 "))
+                .unwrap(),
+            "node-cz-2049-0xk-7.example.com"
+        );
+    }
+
+    #[test]
+    fn missing_or_malformed_values_fail() {
+        let template = FqdnTemplate::from_str("pve-{mac6}.lab.local").unwrap();
+        assert_eq!(
+            template.expand(&HostIdentity::from_serial("ABC123")),
+            Err(GlobalConfigError::FqdnTemplateValue)
+        );
+        assert_eq!(
+            template.expand(&HostIdentity::from_mac("aa:bb:cc")),
+            Err(GlobalConfigError::FqdnTemplateValue)
+        );
+    }
+
+    #[test]
+    fn expanded_value_must_match_fqdn_pattern() {
+        // A bare placeholder has no domain part.
+        let template = FqdnTemplate::from_str("{serial}").unwrap();
+        assert_eq!(
+            template.expand(&HostIdentity::from_serial("ABC123")),
+            Err(GlobalConfigError::Fqdn)
+        );
+
+        // 12 hex digits fill the label beyond 63 characters.
+        let long =
+            FqdnTemplate::from_str(&format!("{}-{{mac}}.lab.local", "a".repeat(52))).unwrap();
+        assert_eq!(
+            long.expand(&HostIdentity::from_mac("00:11:22:33:44:55")),
+            Err(GlobalConfigError::Fqdn)
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use crate::answer_file::sections::global::{
+    constants::HASHED_PASSWORD_PATTERN, errors::GlobalConfigError,
+    models::password_hash_format::PasswordHashFormat,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
+
+/// The stand-in hash of default and preset answers. It matches no password,
+/// so an installation that keeps it cannot log in as root with a password.
+pub const PLACEHOLDER_HASH: &str = "$6$rounds=656000$12345678$AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
+
+/// `root-password-hashed`: a crypt hash in one of the [`PasswordHashFormat`]s.
+///
+/// `Debug` and `Display` never print the hash; use [`as_str`](Self::as_str).
+/// Placeholder hashes (whose digest repeats one character, like
+/// [`PLACEHOLDER_HASH`]) refuse to serialize unless allowed
+/// with [`allow_placeholder`](Self::allow_placeholder); [`PLACEHOLDER_HASH`]
+/// itself is always allowed so generated templates can be written.
+#[derive(Clone)]
+pub struct HashedPassword {
+    hash: String,
+    allow_placeholder: bool,
+}
+
+impl HashedPassword {
+    /// The crypt string (e.g. "$6$rounds=656000$...")
+    pub fn as_str(&self) -> &str {
+        &self.hash
+    }
+
+    /// Explicit constructor for non-default manipulation
+    pub fn try_new(s: &str) -> Result<Self, GlobalConfigError> {
+        s.parse()
+    }
+
+    pub fn format(&self) -> PasswordHashFormat {
+        PasswordHashFormat::from_hash(&self.hash)
+            .expect("HashedPassword is validated on construction")
+    }
+
+    /// `true` when the digest is a single repeated character, as in
+    /// [`PLACEHOLDER_HASH`]; no real crypt output looks like that.
+    pub fn is_placeholder(&self) -> bool {
+        let digest = self.hash.rsplit('$').next().unwrap_or_default();
+        let mut chars = digest.chars();
+        chars.next().is_some_and(|first| chars.all(|c| c == first))
+    }
+
+    /// Lets a placeholder hash serialize, e.g. for test fixtures.
+    pub fn allow_placeholder(mut self) -> Self {
+        self.allow_placeholder = true;
+        self
+    }
+}
+
+impl FromStr for HashedPassword {
+    type Err = GlobalConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hash = s.trim();
+        PasswordHashFormat::check(hash)?;
+        Ok(Self {
+            hash: hash.to_owned(),
+            allow_placeholder: hash == PLACEHOLDER_HASH,
+        })
+    }
+}
+
+impl Default for HashedPassword {
+    fn default() -> Self {
+        Self {
+            hash: PLACEHOLDER_HASH.into(),
+            allow_placeholder: true,
+        }
+    }
+}
+
+impl PartialEq for HashedPassword {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash
+    }
+}
+
+impl Eq for HashedPassword {}
+
+impl PartialEq<str> for HashedPassword {
+    fn eq(&self, other: &str) -> bool {
+        self.hash == other
+    }
+}
+
+impl PartialEq<&str> for HashedPassword {
+    fn eq(&self, other: &&str) -> bool {
+        self.hash == *other
+    }
+}
+
+impl fmt::Debug for HashedPassword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("HashedPassword")
+            .field(&format_args!("{} <redacted>", self.format()))
+            .finish()
+    }
+}
+
+impl fmt::Display for HashedPassword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<redacted {} hash>", self.format())
+    }
+}
+
+impl schemars::JsonSchema for HashedPassword {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "HashedPassword".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "pattern": HASHED_PASSWORD_PATTERN.as_str()
+        })
+    }
+}
+
+impl Serialize for HashedPassword {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if self.is_placeholder() && !self.allow_placeholder {
+            return Err(serde::ser::Error::custom(
+                GlobalConfigError::RootPasswordPlaceholder,
+            ));
+        }
+        serializer.serialize_str(&self.hash)
+    }
+}
+
+impl<'de> Deserialize<'de> for HashedPassword {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA512: &str = "$6$rounds=656000$14y6mKwGoJXW643/$Xsr5iuWoUSsFFL.jcholVTzjBlSv1iWGNC0xCyl/wJ.m3XGj/SFXOsrmMXABQ8Id0lFJlPjqomSnyIQXovCX6.";
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Wrapper {
+        hash: HashedPassword,
+    }
+
+    /* ---------------- FROMSTR ---------------- */
+
+    #[test]
+    fn valid_hash_parses_with_its_format() {
+        let hash = HashedPassword::from_str(SHA512).unwrap();
+        assert_eq!(hash, SHA512);
+        assert_eq!(hash.format(), PasswordHashFormat::Sha512);
+        assert!(!hash.is_placeholder());
+    }
+
+    #[test]
+    fn malformed_hash_fails_at_parse() {
+        assert_eq!(
+            HashedPassword::from_str("hunter2"),
+            Err(GlobalConfigError::RootPasswordHashed)
+        );
+        assert_eq!(
+            HashedPassword::from_str("$6$rounds=656000$salt$short"),
+            Err(GlobalConfigError::RootPasswordSha512)
+        );
+    }
+
+    /* ---------------- REDACTION ---------------- */
+
+    #[test]
+    fn debug_and_display_hide_the_hash() {
+        let hash = HashedPassword::from_str(SHA512).unwrap();
+        assert_eq!(format!("{:?}", hash), "HashedPassword(sha-512 <redacted>)");
+        assert_eq!(hash.to_string(), "<redacted sha-512 hash>");
+    }
+
+    /* ---------------- SERDE ---------------- */
+
+    #[test]
+    fn serde_round_trip_preserves_hash() {
+        let w: Wrapper = toml::from_str(&format!("hash = {:?}", SHA512)).unwrap();
+        assert_eq!(w.hash, SHA512);
+        assert_eq!(
+            toml::from_str::<Wrapper>(&toml::to_string(&w).unwrap()).unwrap(),
+            w
+        );
+    }
+
+    #[test]
+    fn placeholders_only_serialize_when_allowed() {
+        let fake = format!("$6$rounds=656000$abcdefgh${}", "B".repeat(86));
+        let hash = HashedPassword::from_str(&fake).unwrap();
+        assert!(hash.is_placeholder());
+
+        let err = toml::to_string(&Wrapper { hash: hash.clone() }).unwrap_err();
+        assert!(
+            err.to_string().contains("root_password_hashed.placeholder"),
+            "error was: {}",
+            err
+        );
+
+        let allowed = Wrapper {
+            hash: hash.allow_placeholder(),
+        };
+        assert!(toml::to_string(&allowed).unwrap().contains(&fake));
+    }
+
+    #[test]
+    fn default_placeholder_round_trips() {
+        let w = Wrapper {
+            hash: HashedPassword::default(),
+        };
+        assert!(w.hash.is_placeholder());
+
+        let toml = toml::to_string(&w).unwrap();
+        let parsed: Wrapper = toml::from_str(&toml).unwrap();
+        assert_eq!(parsed.hash, PLACEHOLDER_HASH);
+        assert!(toml::to_string(&parsed).is_ok());
+    }
+}
@@ -1,4 +1,9 @@
 pub mod allowed_keyboards;
 pub mod country;
+pub mod email;
+pub mod fqdn_template;
+pub mod hashed_password;
+pub mod password_hash_format;
+pub mod plaintext_password;
 pub mod reboot_mode;
 pub mod timezone;
@@ -0,0 +1,139 @@
+use crate::answer_file::{
+    macros::string_enum,
+    sections::global::{
+        constants::{SHA256_CRYPT_PATTERN, SHA512_CRYPT_PATTERN, YESCRYPT_PATTERN},
+        errors::GlobalConfigError,
+    },
+};
+use regex::Regex;
+
+/* ===================== PASSWORD HASH FORMAT ===================== */
+string_enum!(
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    pub enum PasswordHashFormat {
+        #[default]
+        Sha512 => "sha-512",
+        Sha256 => "sha-256",
+        Yescrypt => "yescrypt",
+    },
+    crate::answer_file::sections::global::errors::GlobalConfigError,
+    crate::answer_file::sections::global::errors::GlobalConfigError::RootPasswordMethod
+);
+
+impl PasswordHashFormat {
+    /// Detects the format from the crypt prefix (`$6$`, `$5$` or `$y$`) of `hash`.
+    pub fn from_hash(hash: &str) -> Option<Self> {
+        [Self::Sha512, Self::Sha256, Self::Yescrypt]
+            .into_iter()
+            .find(|format| hash.starts_with(format.prefix()))
+    }
+
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::Sha512 => "$6$",
+            Self::Sha256 => "$5$",
+            Self::Yescrypt => "$y$",
+        }
+    }
+
+    pub fn pattern(self) -> &'static Regex {
+        match self {
+            Self::Sha512 => &SHA512_CRYPT_PATTERN,
+            Self::Sha256 => &SHA256_CRYPT_PATTERN,
+            Self::Yescrypt => &YESCRYPT_PATTERN,
+        }
+    }
+
+    /// Error reported for a hash with this prefix that does not match [`Self::pattern`].
+    pub fn error(self) -> GlobalConfigError {
+        match self {
+            Self::Sha512 => GlobalConfigError::RootPasswordSha512,
+            Self::Sha256 => GlobalConfigError::RootPasswordSha256,
+            Self::Yescrypt => GlobalConfigError::RootPasswordYescrypt,
+        }
+    }
+
+    /// Checks `hash` against its format's pattern.
+    ///
+    /// # Errors
+    /// - `RootPasswordHashed` when the prefix is not a supported format.
+    /// - The format's [`Self::error`] when the hash is malformed.
+    pub fn check(hash: &str) -> Result<Self, GlobalConfigError> {
+        let format = Self::from_hash(hash).ok_or(GlobalConfigError::RootPasswordHashed)?;
+        if format.pattern().is_match(hash) {
+            Ok(format)
+        } else {
+            Err(format.error())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const YESCRYPT: &str =
+        "$y$j9T$F5Jx5fExrKuPp53xLKQ..1$X3DX6M94c7o.9agCG9G317fhZg9SqC.5i5rd.RhAtQ7";
+
+    /* ---------------- FROMSTR ---------------- */
+
+    #[test]
+    fn method_names_parse() {
+        assert_eq!(
+            PasswordHashFormat::from_str("yescrypt").unwrap(),
+            PasswordHashFormat::Yescrypt
+        );
+        assert_eq!(
+            PasswordHashFormat::from_str("md5"),
+            Err(GlobalConfigError::RootPasswordMethod)
+        );
+        assert_eq!(PasswordHashFormat::default().as_str(), "sha-512");
+    }
+
+    /* ---------------- DETECTION ---------------- */
+
+    #[test]
+    fn formats_are_detected_by_prefix() {
+        assert_eq!(
+            PasswordHashFormat::from_hash("$5$rounds=5000$x$y"),
+            Some(PasswordHashFormat::Sha256)
+        );
+        assert_eq!(
+            PasswordHashFormat::from_hash(YESCRYPT),
+            Some(PasswordHashFormat::Yescrypt)
+        );
+        assert_eq!(PasswordHashFormat::from_hash("$1$abc$def"), None);
+    }
+
+    /* ---------------- CHECK ---------------- */
+
+    #[test]
+    fn check_reports_per_format_errors() {
+        let sha512 = format!("$6$rounds=656000$12345678${}", "A".repeat(86));
+        assert_eq!(
+            PasswordHashFormat::check(&sha512),
+            Ok(PasswordHashFormat::Sha512)
+        );
+        assert_eq!(
+            PasswordHashFormat::check(YESCRYPT),
+            Ok(PasswordHashFormat::Yescrypt)
+        );
+        assert_eq!(
+            PasswordHashFormat::check("$6$rounds=656000$short$hash"),
+            Err(GlobalConfigError::RootPasswordSha512)
+        );
+        assert_eq!(
+            PasswordHashFormat::check("$5$salt$hash"),
+            Err(GlobalConfigError::RootPasswordSha256)
+        );
+        assert_eq!(
+            PasswordHashFormat::check("$y$j9T$salt$short"),
+            Err(GlobalConfigError::RootPasswordYescrypt)
+        );
+        assert_eq!(
+            PasswordHashFormat::check("plaintext"),
+            Err(GlobalConfigError::RootPasswordHashed)
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use crate::answer_file::sections::global::errors::GlobalConfigError;
+use serde::{Deserialize, Deserializer};
+
+/// `root-password`: the installer's plaintext password option, which this
+/// crate does not support.
+///
+/// The type has no values, so `Option<PlaintextPassword>` is always `None`.
+/// Parsing a document that sets `root-password` fails with
+/// [`GlobalConfigError::RootPasswordPlaintext`] instead of silently dropping
+/// the key; `pveauto answer convert-password` hashes it in place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaintextPassword {}
+
+impl<'de> Deserialize<'de> for PlaintextPassword {
+    fn deserialize<D>(_: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Err(serde::de::Error::custom(
+            GlobalConfigError::RootPasswordPlaintext,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Wrapper {
+        #[serde(rename = "root-password")]
+        root_password: Option<PlaintextPassword>,
+    }
+
+    #[test]
+    fn any_value_is_rejected() {
+        for toml in [r#"root-password = "hunter2""#, "root-password = 42"] {
+            let err = toml::from_str::<Wrapper>(toml).unwrap_err();
+            assert_eq!(err.message(), "root_password.plaintext", "{}", toml);
+        }
+    }
+
+    #[test]
+    fn missing_key_is_none() {
+        assert!(
+            toml::from_str::<Wrapper>("")
+                .unwrap()
+                .root_password
+                .is_none()
+        );
+    }
+}
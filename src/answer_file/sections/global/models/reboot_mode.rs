@@ -2,8 +2,9 @@ use crate::answer_file::macros::string_enum;
 
 /* ===================== REBOOT MODE ===================== */
 string_enum!(
-    #[derive(Debug, Clone,  PartialEq, Eq, Hash)]
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
     pub enum RebootMode {
+        #[default]
         Reboot => "reboot",
         PowerOff => "power-off",
     },
@@ -11,12 +12,6 @@ string_enum!(
     crate::answer_file::sections::global::errors::GlobalConfigError::RebootMode
 );
 
-impl Default for RebootMode {
-    fn default() -> Self {
-        RebootMode::Reboot
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
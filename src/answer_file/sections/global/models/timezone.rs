@@ -1,7 +1,11 @@
 use crate::answer_file::sections::global::errors::GlobalConfigError;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Deserializer, Serialize};
-use std::{collections::HashSet, fmt, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    str::FromStr,
+};
 
 static PROX_TIMEZONES: Lazy<HashSet<&'static str>> = Lazy::new(|| {
     include_str!(concat!(
@@ -14,6 +18,23 @@ static PROX_TIMEZONES: Lazy<HashSet<&'static str>> = Lazy::new(|| {
     .collect()
 });
 
+/// Deprecated IANA names (backward links, e.g. "US/Eastern") mapped to the
+/// canonical zone the installer expects.
+static PROX_TIMEZONE_ALIASES: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
+    include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/data/timezone_aliases.txt"
+    ))
+    .lines()
+    .map(str::trim)
+    .filter(|l| !l.is_empty())
+    .filter_map(|line| {
+        let (alias, canonical) = line.split_once(char::is_whitespace)?;
+        Some((alias.trim(), canonical.trim()))
+    })
+    .collect()
+});
+
 #[derive(Debug, Clone, Serialize, PartialEq, Eq, Hash)]
 pub struct Timezone(String);
 
@@ -27,15 +48,25 @@ impl Timezone {
     pub fn try_new(s: &str) -> Result<Self, GlobalConfigError> {
         s.parse()
     }
+
+    /// Resolved chrono-tz zone for offset calculations
+    pub fn tz(&self) -> chrono_tz::Tz {
+        self.0
+            .parse()
+            .expect("Timezone is validated against chrono-tz on construction")
+    }
 }
 
 impl FromStr for Timezone {
     type Err = GlobalConfigError;
 
+    /// Accepts canonical zones and deprecated aliases, normalizing the latter
+    /// (e.g. "US/Eastern" -> "America/New_York").
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let tz = s.trim();
-        if PROX_TIMEZONES.contains(tz) {
-            Ok(Self(tz.to_owned()))
+        let canonical = PROX_TIMEZONE_ALIASES.get(tz).copied().unwrap_or(tz);
+        if PROX_TIMEZONES.contains(canonical) && canonical.parse::<chrono_tz::Tz>().is_ok() {
+            Ok(Self(canonical.to_owned()))
         } else {
             Err(GlobalConfigError::Timezone)
         }
@@ -54,6 +85,21 @@ impl Default for Timezone {
     }
 }
 
+impl schemars::JsonSchema for Timezone {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Timezone".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        let mut zones: Vec<&str> = PROX_TIMEZONES.iter().copied().collect();
+        zones.sort_unstable();
+        schemars::json_schema!({
+            "type": "string",
+            "enum": zones
+        })
+    }
+}
+
 impl<'de> Deserialize<'de> for Timezone {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -100,6 +146,31 @@ mod tests {
         );
     }
 
+    /* ---------------- ALIASES ---------------- */
+
+    #[test]
+    fn deprecated_alias_normalizes_to_canonical() {
+        let tz = Timezone::from_str("US/Eastern").unwrap();
+        assert_eq!(tz.as_str(), "America/New_York");
+    }
+
+    #[test]
+    fn alias_with_whitespace_normalizes() {
+        let tz = Timezone::from_str(" Asia/Calcutta ").unwrap();
+        assert_eq!(tz.as_str(), "Asia/Kolkata");
+    }
+
+    #[test]
+    fn serde_normalizes_alias() {
+        #[derive(Debug, Deserialize)]
+        struct Wrapper {
+            timezone: Timezone,
+        }
+
+        let w: Wrapper = toml::from_str(r#"timezone = "Europe/Kiev""#).unwrap();
+        assert_eq!(w.timezone.as_str(), "Europe/Kyiv");
+    }
+
     /* ---------------- TRY_NEW ---------------- */
 
     #[test]
@@ -116,6 +187,14 @@ mod tests {
         );
     }
 
+    /* ---------------- CHRONO-TZ ---------------- */
+
+    #[test]
+    fn tz_resolves_to_chrono_zone() {
+        let tz = Timezone::from_str("US/Pacific").unwrap();
+        assert_eq!(tz.tz(), chrono_tz::America::Los_Angeles);
+    }
+
     /* ---------------- DISPLAY ---------------- */
 
     #[test]
@@ -169,4 +248,27 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn dataset_zones_are_known_to_chrono_tz() {
+        for tz in PROX_TIMEZONES.iter() {
+            assert!(
+                tz.parse::<chrono_tz::Tz>().is_ok(),
+                "chrono-tz does not know {}",
+                tz
+            );
+        }
+    }
+
+    #[test]
+    fn alias_targets_are_canonical_zones() {
+        for (alias, canonical) in PROX_TIMEZONE_ALIASES.iter() {
+            assert!(
+                PROX_TIMEZONES.contains(canonical),
+                "alias {} points at unknown zone {}",
+                alias,
+                canonical
+            );
+        }
+    }
 }
@@ -0,0 +1,180 @@
+use crate::answer_file::sections::global::{
+    errors::GlobalConfigError, models::password_hash_format::PasswordHashFormat,
+};
+use sha_crypt::{Algorithm, Params, PasswordHasher, PasswordVerifier, ShaCrypt};
+use yescrypt::Yescrypt;
+
+/// Rounds used for SHA-crypt hashes; matches the common `mkpasswd -R 656000` choice.
+pub const DEFAULT_HASH_ROUNDS: u32 = 656_000;
+
+/// `root-password-hashed` requires a six digit `rounds=` value.
+pub const HASH_ROUNDS_RANGE: std::ops::RangeInclusive<u32> = 100_000..=999_999;
+
+/// 12 random bytes encode to the maximum 16 character SHA-crypt salt.
+const SHA_CRYPT_SALT_BYTES: usize = 12;
+
+/// 16 random bytes encode to the 22 character salt used by `mkpasswd -m yescrypt`.
+const YESCRYPT_SALT_BYTES: usize = 16;
+
+/// Hashes `plaintext` as SHA-512 crypt (`$6$rounds=656000$<salt>$<hash>`)
+/// with a random salt, ready for `root-password-hashed`.
+pub fn hash_password(plaintext: &str) -> Result<String, GlobalConfigError> {
+    hash_password_as(plaintext, PasswordHashFormat::Sha512, None)
+}
+
+/// Like [`hash_password`] with an explicit round count.
+pub fn hash_password_with_rounds(
+    plaintext: &str,
+    rounds: u32,
+) -> Result<String, GlobalConfigError> {
+    hash_password_as(plaintext, PasswordHashFormat::Sha512, Some(rounds))
+}
+
+/// Hashes `plaintext` in `format` with a random salt.
+///
+/// `rounds` defaults to [`DEFAULT_HASH_ROUNDS`] for the SHA-crypt formats; yescrypt
+/// uses the libxcrypt default cost (`j9T`) and takes no rounds.
+///
+/// # Errors
+/// - `RootPasswordEmpty` for an empty plaintext.
+/// - `RootPasswordRounds` when `rounds` is outside [`HASH_ROUNDS_RANGE`] or given for yescrypt.
+pub fn hash_password_as(
+    plaintext: &str,
+    format: PasswordHashFormat,
+    rounds: Option<u32>,
+) -> Result<String, GlobalConfigError> {
+    if plaintext.is_empty() {
+        return Err(GlobalConfigError::RootPasswordEmpty);
+    }
+
+    let hash = match format {
+        PasswordHashFormat::Sha512 | PasswordHashFormat::Sha256 => {
+            let rounds = rounds.unwrap_or(DEFAULT_HASH_ROUNDS);
+            if !HASH_ROUNDS_RANGE.contains(&rounds) {
+                return Err(GlobalConfigError::RootPasswordRounds);
+            }
+            let algorithm = if format == PasswordHashFormat::Sha512 {
+                Algorithm::Sha512Crypt
+            } else {
+                Algorithm::Sha256Crypt
+            };
+            let params = Params::new(rounds).map_err(|_| GlobalConfigError::RootPasswordRounds)?;
+            ShaCrypt::new(algorithm, params)
+                .hash_password_with_salt(plaintext.as_bytes(), &salt::<SHA_CRYPT_SALT_BYTES>()?)
+                .map_err(|_| format.error())?
+                .to_string()
+        }
+        PasswordHashFormat::Yescrypt => {
+            if rounds.is_some() {
+                return Err(GlobalConfigError::RootPasswordRounds);
+            }
+            Yescrypt::default()
+                .hash_password_with_salt(plaintext.as_bytes(), &salt::<YESCRYPT_SALT_BYTES>()?)
+                .map_err(|_| format.error())?
+                .to_string()
+        }
+    };
+
+    if PasswordHashFormat::check(&hash)? == format {
+        Ok(hash)
+    } else {
+        Err(format.error())
+    }
+}
+
+/// `true` when `plaintext` matches a SHA-crypt or yescrypt `hash`.
+pub fn verify_password(plaintext: &str, hash: &str) -> bool {
+    match PasswordHashFormat::from_hash(hash) {
+        Some(PasswordHashFormat::Yescrypt) => Yescrypt::default()
+            .verify_password(plaintext.as_bytes(), hash)
+            .is_ok(),
+        Some(_) => ShaCrypt::default()
+            .verify_password(plaintext.as_bytes(), hash)
+            .is_ok(),
+        None => false,
+    }
+}
+
+fn salt<const N: usize>() -> Result<[u8; N], GlobalConfigError> {
+    let mut salt = [0u8; N];
+    getrandom::fill(&mut salt).map_err(|_| GlobalConfigError::RootPasswordHashed)?;
+    Ok(salt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer_file::sections::global::constants::HASHED_PASSWORD_PATTERN;
+
+    const ROUNDS: u32 = 100_000;
+
+    #[test]
+    fn hashes_match_the_answer_pattern_and_verify() {
+        let hash = hash_password_with_rounds("s3cret!", ROUNDS).unwrap();
+        assert!(hash.starts_with("$6$rounds=100000$"), "hash was: {}", hash);
+        assert!(HASHED_PASSWORD_PATTERN.is_match(&hash));
+        assert!(verify_password("s3cret!", &hash));
+        assert!(!verify_password("wrong", &hash));
+    }
+
+    #[test]
+    fn salts_are_random() {
+        let a = hash_password_with_rounds("same", ROUNDS).unwrap();
+        let b = hash_password_with_rounds("same", ROUNDS).unwrap();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn known_vector_verifies() {
+        // Test vector from the SHA-crypt specification.
+        let hash = "$6$rounds=10000$saltstringsaltst$OW1/O6BYHV6BcXZu8QVeXbDWra3Oeqh0sbHbbMCVNSnCM/UrjmM0Dp8vOuZeHBy/YTBmSK6H9qs/y3RnOaw5v.";
+        assert!(verify_password("Hello world!", hash));
+        assert!(!verify_password("Hello world", hash));
+    }
+
+    #[test]
+    fn sha256_hashes_verify() {
+        let hash = hash_password_as("s3cret!", PasswordHashFormat::Sha256, Some(ROUNDS)).unwrap();
+        assert!(hash.starts_with("$5$rounds=100000$"), "hash was: {}", hash);
+        assert_eq!(
+            PasswordHashFormat::check(&hash),
+            Ok(PasswordHashFormat::Sha256)
+        );
+        assert!(verify_password("s3cret!", &hash));
+    }
+
+    #[test]
+    fn yescrypt_hashes_verify() {
+        let hash = hash_password_as("s3cret!", PasswordHashFormat::Yescrypt, None).unwrap();
+        assert!(hash.starts_with("$y$j9T$"), "hash was: {}", hash);
+        assert!(HASHED_PASSWORD_PATTERN.is_match(&hash));
+        assert!(verify_password("s3cret!", &hash));
+    }
+
+    #[test]
+    fn yescrypt_reference_vector_verifies() {
+        // From the yescrypt reference implementation's TESTS-OK.
+        let hash = "$y$j0/$LdJM$k7BXzSDuoGHW56SY3HxROCiA0gWRscZe2aA0q5oHPM0";
+        assert!(verify_password("pleaseletmein", hash));
+        assert!(!verify_password("bogus", hash));
+        assert!(!verify_password("pleaseletmein", "$1$abc$def"));
+    }
+
+    #[test]
+    fn invalid_input_is_rejected() {
+        assert_eq!(
+            hash_password_with_rounds("", ROUNDS),
+            Err(GlobalConfigError::RootPasswordEmpty)
+        );
+        for rounds in [5_000, 1_000_000] {
+            assert_eq!(
+                hash_password_with_rounds("pw", rounds),
+                Err(GlobalConfigError::RootPasswordRounds)
+            );
+        }
+        assert_eq!(
+            hash_password_as("pw", PasswordHashFormat::Yescrypt, Some(ROUNDS)),
+            Err(GlobalConfigError::RootPasswordRounds)
+        );
+    }
+}
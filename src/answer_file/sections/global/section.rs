@@ -1,30 +1,36 @@
 use crate::answer_file::sections::global::{
     GlobalConfigError,
-    constants::{EMAIL_OR_LOCALHOST_PATTERN, FQDN_PATTERN, HASHED_PASSWORD_PATTERN},
+    constants::FQDN_PATTERN,
     models::{
-        allowed_keyboards::KeyboardLayout, country::CountryCode, reboot_mode::RebootMode,
-        timezone::Timezone,
+        allowed_keyboards::KeyboardLayout, country::CountryCode, email::EmailAddress,
+        hashed_password::HashedPassword, plaintext_password::PlaintextPassword,
+        reboot_mode::RebootMode, timezone::Timezone,
     },
 };
+use crate::answer_file::sections::yaml::{section_from_yaml_str, section_to_yaml_string};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 #[serde(default)]
 pub struct GlobalConfig {
     pub keyboard: KeyboardLayout,
     pub country: CountryCode,
-    pub timezone: Timezone,
     pub fqdn: String,
-    pub mailto: String,
+    pub mailto: EmailAddress,
+    pub timezone: Timezone,
+    /// Always `None`; plaintext passwords are rejected while parsing.
+    #[serde(rename = "root-password", skip_serializing)]
+    #[schemars(skip)]
+    pub root_password: Option<PlaintextPassword>,
     #[serde(rename = "root-password-hashed")]
-    /* root-password (plain text) is not supported for security reasons */
-    pub root_password_hashed: String,
-    #[serde(rename = "root-ssh-keys")]
-    pub root_ssh_keys: Option<Vec<String>>,
+    pub root_password_hashed: HashedPassword,
     #[serde(rename = "reboot-on-error")]
     pub reboot_on_error: bool, // default false
     #[serde(rename = "reboot-mode")]
     pub reboot_mode: RebootMode, // "reboot", "power-off"
+    #[serde(rename = "root-ssh-keys")]
+    pub root_ssh_keys: Option<Vec<String>>,
 }
 
 impl Default for GlobalConfig {
@@ -34,38 +40,70 @@ impl Default for GlobalConfig {
             country: CountryCode::default(),
             timezone: Timezone::default(),
             fqdn: "proxmox.lab.local".into(),
-            mailto: "root@localhost".into(),
+            mailto: EmailAddress::default(),
             root_ssh_keys: None,
+            root_password: None,
             reboot_on_error: false,
             reboot_mode: RebootMode::default(),
-            root_password_hashed: format!("$6$rounds=656000$12345678${}", "A".repeat(86)),
+            root_password_hashed: HashedPassword::default(),
         }
     }
 }
 
 impl GlobalConfig {
     pub fn validate(&self) -> Result<(), GlobalConfigError> {
-        if self.fqdn.len() > 255 || !FQDN_PATTERN.is_match(&self.fqdn) {
-            return Err(GlobalConfigError::Fqdn);
-        }
-
-        if !EMAIL_OR_LOCALHOST_PATTERN.is_match(&self.mailto) {
-            return Err(GlobalConfigError::Mailto);
-        }
-
-        if !HASHED_PASSWORD_PATTERN.is_match(&self.root_password_hashed) {
-            return Err(GlobalConfigError::RootPasswordHashed);
-        }
+        check_fqdn(&self.fqdn)?;
 
         if let Some(keys) = &self.root_ssh_keys {
             for key in keys {
-                sshkeys::PublicKey::from_string(key).map_err(|_| GlobalConfigError::RootSshKeys)?;
+                check_ssh_key(key)?;
             }
         }
 
         Ok(())
     }
 
+    /* -------- SETTERS -------- */
+
+    /// Sets `fqdn` if it is valid; otherwise leaves the config unchanged and
+    /// returns the field's error, as [`validate`](GlobalConfig::validate) would.
+    pub fn set_fqdn(&mut self, fqdn: impl Into<String>) -> Result<(), GlobalConfigError> {
+        let fqdn = fqdn.into();
+        check_fqdn(&fqdn)?;
+        self.fqdn = fqdn;
+        Ok(())
+    }
+
+    /// Sets `mailto` if it parses as an [`EmailAddress`].
+    pub fn set_mailto(&mut self, mailto: &str) -> Result<(), GlobalConfigError> {
+        self.mailto = mailto.parse()?;
+        Ok(())
+    }
+
+    /// Sets `root_password_hashed` if it parses as a [`HashedPassword`]; the
+    /// error names the hash format that was recognized but malformed.
+    pub fn set_root_password_hashed(&mut self, hash: &str) -> Result<(), GlobalConfigError> {
+        self.root_password_hashed = hash.parse()?;
+        Ok(())
+    }
+
+    /// Appends `key` to `root_ssh_keys` unless it is already present.
+    ///
+    /// # Returns
+    /// `Ok(true)` when the key was added, `Ok(false)` when it was already listed,
+    /// or `RootSshKeys` when `key` is not a valid OpenSSH public key.
+    pub fn add_root_ssh_key(&mut self, key: &str) -> Result<bool, GlobalConfigError> {
+        let key = key.trim();
+        check_ssh_key(key)?;
+
+        let keys = self.root_ssh_keys.get_or_insert_with(Vec::new);
+        if keys.iter().any(|k| k == key) {
+            return Ok(false);
+        }
+        keys.push(key.to_owned());
+        Ok(true)
+    }
+
     /* -------- FROM TOML STRING (BARE OR [global]) -------- */
 
     pub fn from_toml_str(s: &str) -> Result<Self, Box<dyn std::error::Error>> {
@@ -96,6 +134,34 @@ impl GlobalConfig {
             Ok(toml::to_string_pretty(self)?)
         }
     }
+
+    /* -------- FROM YAML STRING (BARE OR global:) -------- */
+
+    pub fn from_yaml_str(s: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let cfg: GlobalConfig = section_from_yaml_str(s, "global")?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /* -------- TO YAML STRING (BARE OR global:) -------- */
+
+    pub fn to_yaml_string(&self, wrap: bool) -> Result<String, Box<dyn std::error::Error>> {
+        self.validate()?;
+        section_to_yaml_string(self, "global", wrap)
+    }
+}
+
+fn check_fqdn(fqdn: &str) -> Result<(), GlobalConfigError> {
+    if fqdn.len() > 255 || !FQDN_PATTERN.is_match(fqdn) {
+        return Err(GlobalConfigError::Fqdn);
+    }
+    Ok(())
+}
+
+fn check_ssh_key(key: &str) -> Result<(), GlobalConfigError> {
+    sshkeys::PublicKey::from_string(key)
+        .map(|_| ())
+        .map_err(|_| GlobalConfigError::RootSshKeys)
 }
 
 #[derive(serde::Deserialize)]
@@ -104,6 +170,7 @@ struct Wrapper {
 }
 
 #[cfg(test)]
+#[allow(clippy::field_reassign_with_default)]
 mod tests {
     use super::*;
     use std::str::FromStr;
@@ -184,20 +251,75 @@ mod tests {
     }
 
     #[test]
-    fn mailto_validation() {
+    fn mailto_is_checked_when_parsed() {
+        let cfg = GlobalConfig::from_toml_str(r#"mailto = "admin@example.com""#).unwrap();
+        assert_eq!(cfg.mailto, "admin@example.com");
+
+        let err = GlobalConfig::from_toml_str(r#"mailto = "nope""#).unwrap_err();
+        assert!(
+            err.to_string().contains("mailto.invalid_format"),
+            "error was: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn root_password_hash_is_checked_when_parsed() {
+        let err = GlobalConfig::from_toml_str(r#"root-password-hashed = "nope""#).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("root_password_hashed.invalid_format"),
+            "error was: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn root_password_hash_errors_are_per_format() {
         let mut cfg = GlobalConfig::default();
-        cfg.mailto = "admin@example.com".into();
-        assert!(cfg.validate().is_ok());
+        assert!(
+            cfg.set_root_password_hashed(
+                "$y$j9T$F5Jx5fExrKuPp53xLKQ..1$X3DX6M94c7o.9agCG9G317fhZg9SqC.5i5rd.RhAtQ7"
+            )
+            .is_ok()
+        );
+        assert_eq!(
+            cfg.set_root_password_hashed("$y$j9T$truncated"),
+            Err(GlobalConfigError::RootPasswordYescrypt)
+        );
+        assert_eq!(
+            cfg.set_root_password_hashed("$6$rounds=5000$salt$hash"),
+            Err(GlobalConfigError::RootPasswordSha512)
+        );
+    }
+
+    /* ---------------- SETTERS ---------------- */
 
-        cfg.mailto = "nope".into();
-        assert_eq!(cfg.validate(), Err(GlobalConfigError::Mailto));
+    #[test]
+    fn setters_accept_valid_values() {
+        let mut cfg = GlobalConfig::default();
+        cfg.set_fqdn("pve01.lab.local").unwrap();
+        cfg.set_mailto("admin@example.com").unwrap();
+        let hash = format!("$5$rounds=535000$abcdefgh${}", "B".repeat(43));
+        cfg.set_root_password_hashed(&hash).unwrap();
+
+        assert_eq!(cfg.fqdn, "pve01.lab.local");
+        assert_eq!(cfg.mailto, "admin@example.com");
+        assert_eq!(cfg.root_password_hashed, hash.as_str());
+        assert!(cfg.validate().is_ok());
     }
 
     #[test]
-    fn root_password_hash_validation() {
+    fn setters_reject_invalid_values_and_keep_old_ones() {
         let mut cfg = GlobalConfig::default();
-        cfg.root_password_hashed = "nope".into();
-        assert_eq!(cfg.validate(), Err(GlobalConfigError::RootPasswordHashed));
+
+        assert_eq!(cfg.set_fqdn("nope"), Err(GlobalConfigError::Fqdn));
+        assert_eq!(cfg.set_mailto("nope"), Err(GlobalConfigError::Mailto));
+        assert_eq!(
+            cfg.set_root_password_hashed("$6$rounds=5000$salt$hash"),
+            Err(GlobalConfigError::RootPasswordSha512)
+        );
+        assert_eq!(cfg, GlobalConfig::default());
     }
 
     /* ---------------- SSH KEY VALIDATION ---------------- */
@@ -233,6 +355,21 @@ mod tests {
         assert_eq!(cfg.validate(), Err(GlobalConfigError::RootSshKeys));
     }
 
+    #[test]
+    fn add_root_ssh_key_appends_once() {
+        let key = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIE2J8WcN6i/K3PaY5E9O+V1YxDCEV4VpWw2X2gYdEx+Z test@example";
+        let mut cfg = GlobalConfig::default();
+
+        assert_eq!(cfg.add_root_ssh_key(&format!("{}\n", key)), Ok(true));
+        assert_eq!(cfg.add_root_ssh_key(key), Ok(false));
+        assert_eq!(cfg.root_ssh_keys, Some(vec![key.to_string()]));
+        assert_eq!(
+            cfg.add_root_ssh_key("ssh-ed25519 NOT_A_REAL_KEY"),
+            Err(GlobalConfigError::RootSshKeys)
+        );
+        assert_eq!(cfg.validate(), Ok(()));
+    }
+
     #[test]
     fn no_ssh_keys_is_valid() {
         let mut cfg = GlobalConfig::default();
@@ -313,4 +450,17 @@ mod tests {
         let parsed = GlobalConfig::from_toml_str(&toml).unwrap();
         assert_eq!(cfg, parsed);
     }
+
+    /* ---------------- YAML SERIALIZATION ---------------- */
+
+    #[test]
+    fn yaml_round_trip_bare_and_wrapped() {
+        let cfg = GlobalConfig::default();
+        for wrap in [false, true] {
+            let yaml = cfg.to_yaml_string(wrap).unwrap();
+            assert_eq!(yaml.starts_with("global:"), wrap, "yaml was: {}", yaml);
+            let parsed = GlobalConfig::from_yaml_str(&yaml).unwrap();
+            assert_eq!(cfg, parsed);
+        }
+    }
 }
@@ -1,7 +1,15 @@
-// pub mod disk_setup;
-// pub mod first_boot;
+pub mod disk_setup;
+pub mod first_boot;
 pub mod global;
-// pub mod network;
-// pub mod post_installation_webhook;
+pub mod network;
+pub mod post_installation_webhook;
+mod yaml;
 
+pub use disk_setup::{
+    BtrfsCompress, BtrfsOptions, BtrfsRaidLevel, DiskList, DiskSetup, DiskSetupError, Filesystem,
+    FilterMatch, LvmOptions, ZfsAshift, ZfsChecksum, ZfsCompress, ZfsOptions, ZfsRaidLevel,
+};
+pub use first_boot::{FirstBoot, FirstBootError, FirstBootOrdering, FirstBootSource};
 pub use global::*;
+pub use network::{Cidr, NetworkConfig, NetworkConfigError, NetworkSource};
+pub use post_installation_webhook::{PostInstallationWebhook, PostInstallationWebhookError};
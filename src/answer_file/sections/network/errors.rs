@@ -0,0 +1,52 @@
+use crate::answer_file::macros::config_error_enum;
+
+/* ===================== NETWORK CONFIG ERROR ===================== */
+
+config_error_enum!(
+    #[derive(Debug, PartialEq)]
+    pub enum NetworkConfigError {
+        Source => "network.source.invalid_format",
+        Cidr => "network.cidr.invalid_format",
+        Dns => "network.dns.invalid_format",
+        Gateway => "network.gateway.invalid_format",
+        Filter => "network.filter.invalid_format",
+        Incomplete => "network.from_answer.incomplete",
+        GatewayOutsideSubnet => "network.gateway.outside_subnet",
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn error_codes() {
+        for (err, code) in [
+            (NetworkConfigError::Source, "network.source.invalid_format"),
+            (NetworkConfigError::Cidr, "network.cidr.invalid_format"),
+            (NetworkConfigError::Dns, "network.dns.invalid_format"),
+            (
+                NetworkConfigError::Gateway,
+                "network.gateway.invalid_format",
+            ),
+            (NetworkConfigError::Filter, "network.filter.invalid_format"),
+            (
+                NetworkConfigError::Incomplete,
+                "network.from_answer.incomplete",
+            ),
+            (
+                NetworkConfigError::GatewayOutsideSubnet,
+                "network.gateway.outside_subnet",
+            ),
+        ] {
+            assert_eq!(err.code(), code);
+        }
+    }
+
+    #[test]
+    fn implements_std_error() {
+        let err: &dyn Error = &NetworkConfigError::Cidr;
+        assert_eq!(err.to_string(), "network.cidr.invalid_format");
+    }
+}
@@ -0,0 +1,7 @@
+pub mod errors;
+mod models;
+mod section;
+
+pub use errors::NetworkConfigError;
+pub use models::{cidr::Cidr, filter::validate_nic_filter, source::NetworkSource};
+pub use section::NetworkConfig;
@@ -0,0 +1,180 @@
+use crate::answer_file::sections::network::errors::NetworkConfigError;
+use ipnet::IpNet;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, net::IpAddr, str::FromStr};
+
+/// `network.cidr`: the host's address with its prefix length, e.g.
+/// `192.168.1.10/24` or `2001:db8::10/64`.
+///
+/// The host bits are kept as written; [`Cidr::network`] gives the subnet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cidr(IpNet);
+
+impl Cidr {
+    /// Explicit constructor for non-default manipulation
+    pub fn try_new(s: &str) -> Result<Self, NetworkConfigError> {
+        s.parse()
+    }
+
+    /// The host address (e.g. `192.168.1.10` for `192.168.1.10/24`)
+    pub fn addr(&self) -> IpAddr {
+        self.0.addr()
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.0.prefix_len()
+    }
+
+    /// The subnet address (e.g. `192.168.1.0` for `192.168.1.10/24`)
+    pub fn network(&self) -> IpAddr {
+        self.0.network()
+    }
+
+    /// The last address of the subnet (e.g. `192.168.1.255` for `192.168.1.10/24`)
+    pub fn broadcast(&self) -> IpAddr {
+        self.0.broadcast()
+    }
+
+    pub fn netmask(&self) -> IpAddr {
+        self.0.netmask()
+    }
+
+    /// Whether `addr` lies within the subnet; always false across IP versions.
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        self.0.contains(addr)
+    }
+
+    pub fn as_ipnet(&self) -> &IpNet {
+        &self.0
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = NetworkConfigError;
+
+    /// Requires an explicit prefix: a bare address is rejected.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim()
+            .parse::<IpNet>()
+            .map(Self)
+            .map_err(|_| NetworkConfigError::Cidr)
+    }
+}
+
+impl From<IpNet> for Cidr {
+    fn from(net: IpNet) -> Self {
+        Self(net)
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl PartialEq<str> for Cidr {
+    fn eq(&self, other: &str) -> bool {
+        other.parse::<Cidr>().is_ok_and(|cidr| cidr == *self)
+    }
+}
+
+impl PartialEq<&str> for Cidr {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl schemars::JsonSchema for Cidr {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Cidr".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "description": "Host address with prefix length, e.g. 192.168.1.10/24"
+        })
+    }
+}
+
+impl Serialize for Cidr {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cidr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* ---------------- FROMSTR ---------------- */
+
+    #[test]
+    fn ipv4_and_ipv6_parse_keeping_the_host_address() {
+        let cidr = Cidr::try_new("192.168.1.10/24").unwrap();
+        assert_eq!(cidr.addr(), "192.168.1.10".parse::<IpAddr>().unwrap());
+        assert_eq!(cidr.to_string(), "192.168.1.10/24");
+
+        let cidr = Cidr::try_new(" 2001:db8::10/64 ").unwrap();
+        assert_eq!(cidr.prefix_len(), 64);
+        assert_eq!(cidr, "2001:db8::10/64");
+    }
+
+    #[test]
+    fn invalid_prefixes_fail() {
+        for s in [
+            "",
+            "192.168.1.10",
+            "192.168.1.10/33",
+            "192.168.1.10/",
+            "fe80::1/129",
+            "nope/24",
+        ] {
+            assert_eq!(Cidr::try_new(s), Err(NetworkConfigError::Cidr), "{:?}", s);
+        }
+    }
+
+    /* ---------------- ACCESSORS ---------------- */
+
+    #[test]
+    fn subnet_accessors() {
+        let cidr = Cidr::try_new("10.0.4.10/22").unwrap();
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert_eq!(cidr.network(), ip("10.0.4.0"));
+        assert_eq!(cidr.broadcast(), ip("10.0.7.255"));
+        assert_eq!(cidr.netmask(), ip("255.255.252.0"));
+
+        assert!(cidr.contains(&ip("10.0.7.1")));
+        assert!(!cidr.contains(&ip("10.0.8.1")));
+        assert!(!cidr.contains(&ip("::1")));
+    }
+
+    /* ---------------- SERDE ---------------- */
+
+    #[test]
+    fn serde_round_trip_and_error_code() {
+        let cidr: Cidr = serde_json::from_str("\"10.0.0.5/16\"").unwrap();
+        assert_eq!(serde_json::to_string(&cidr).unwrap(), "\"10.0.0.5/16\"");
+
+        let err = serde_json::from_str::<Cidr>("\"10.0.0.5\"").unwrap_err();
+        assert!(
+            err.to_string().starts_with("network.cidr.invalid_format"),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use crate::answer_file::sections::{
+    disk_setup::constants::UDEV_PROPERTY_PATTERN, network::errors::NetworkConfigError,
+};
+use std::collections::BTreeMap;
+
+/// Validates `filter.<UDEV_PROPERTY> = "<glob>"` NIC matches, e.g.
+/// `filter.ID_NET_NAME = "enp6s0"` or `filter.ID_NET_NAME_MAC = "*aabbccddeeff"`.
+///
+/// A present `filter` selects a specific NIC, so it needs at least one entry.
+/// Keys must be udev property names and values non-empty glob patterns.
+pub fn validate_nic_filter(filter: &BTreeMap<String, String>) -> Result<(), NetworkConfigError> {
+    if filter.is_empty() {
+        return Err(NetworkConfigError::Filter);
+    }
+
+    for (key, value) in filter {
+        if !UDEV_PROPERTY_PATTERN.is_match(key) || !is_glob_pattern(value) {
+            return Err(NetworkConfigError::Filter);
+        }
+    }
+
+    Ok(())
+}
+
+/// Non-blank, without whitespace, and every `[...]` class closed and non-empty.
+fn is_glob_pattern(pattern: &str) -> bool {
+    if pattern.is_empty() || pattern.chars().any(char::is_whitespace) {
+        return false;
+    }
+
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.next().is_none() => return false,
+            '[' => {
+                let rest = chars.as_str();
+                let Some(end) = rest.find(']') else {
+                    return false;
+                };
+                let class = &rest[..end];
+                if class.strip_prefix(['!', '^']).unwrap_or(class).is_empty() {
+                    return false;
+                }
+                chars = rest[end + 1..].chars();
+            }
+            ']' => return false,
+            _ => {}
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(key: &str, value: &str) -> BTreeMap<String, String> {
+        BTreeMap::from([(key.to_string(), value.to_string())])
+    }
+
+    #[test]
+    fn valid_filters_pass() {
+        for value in ["enp6s0", "enp*", "eno[12]", "*aabbccddeeff", "en?s0"] {
+            assert!(
+                validate_nic_filter(&filter("ID_NET_NAME", value)).is_ok(),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn empty_filter_fails() {
+        assert_eq!(
+            validate_nic_filter(&BTreeMap::new()),
+            Err(NetworkConfigError::Filter)
+        );
+    }
+
+    #[test]
+    fn invalid_property_names_fail() {
+        for key in ["", "ID NET NAME", "ID-NET-NAME"] {
+            assert_eq!(
+                validate_nic_filter(&filter(key, "enp6s0")),
+                Err(NetworkConfigError::Filter),
+                "{:?}",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_patterns_fail() {
+        for value in [
+            "", "  ", "enp 6s0", "eno[12", "eno[]", "eno[!]", "eno]", "enp\\",
+        ] {
+            assert_eq!(
+                validate_nic_filter(&filter("ID_NET_NAME", value)),
+                Err(NetworkConfigError::Filter),
+                "{:?}",
+                value
+            );
+        }
+    }
+}
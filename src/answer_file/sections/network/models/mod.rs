@@ -0,0 +1,3 @@
+pub mod cidr;
+pub mod filter;
+pub mod source;
@@ -0,0 +1,61 @@
+use crate::answer_file::macros::string_enum;
+
+/* ===================== NETWORK SOURCE ===================== */
+string_enum!(
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    pub enum NetworkSource {
+        #[default]
+        FromDhcp => "from-dhcp",
+        FromAnswer => "from-answer",
+    },
+    crate::answer_file::sections::network::errors::NetworkConfigError,
+    crate::answer_file::sections::network::errors::NetworkConfigError::Source
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer_file::sections::network::errors::NetworkConfigError;
+    use std::str::FromStr;
+
+    /* ---------------- DEFAULT ---------------- */
+
+    #[test]
+    fn default_is_dhcp() {
+        assert_eq!(NetworkSource::default(), NetworkSource::FromDhcp);
+    }
+
+    /* ---------------- FROMSTR ---------------- */
+
+    #[test]
+    fn installer_values_parse() {
+        assert_eq!(
+            NetworkSource::from_str("from-answer"),
+            Ok(NetworkSource::FromAnswer)
+        );
+        assert_eq!(
+            NetworkSource::from_str("from-dhcp"),
+            Ok(NetworkSource::FromDhcp)
+        );
+        assert_eq!(
+            NetworkSource::from_str("from-static"),
+            Err(NetworkConfigError::Source)
+        );
+    }
+
+    /* ---------------- SERDE ---------------- */
+
+    #[test]
+    fn serde_rejects_unknown_source_with_code() {
+        let err = serde_json::from_str::<NetworkSource>("\"dhcp\"").unwrap_err();
+        assert!(
+            err.to_string().starts_with("network.source.invalid_format"),
+            "{}",
+            err
+        );
+        assert_eq!(
+            serde_json::to_string(&NetworkSource::FromAnswer).unwrap(),
+            "\"from-answer\""
+        );
+    }
+}
@@ -0,0 +1,306 @@
+use crate::answer_file::sections::network::{
+    Cidr, NetworkConfigError, NetworkSource, validate_nic_filter,
+};
+use crate::answer_file::sections::yaml::{section_from_yaml_str, section_to_yaml_string};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, net::IpAddr};
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(default)]
+pub struct NetworkConfig {
+    pub source: NetworkSource,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cidr: Option<Cidr>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gateway: Option<String>,
+    /* udev property matches selecting the management NIC, e.g. filter.ID_NET_NAME_MAC */
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<BTreeMap<String, String>>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            source: NetworkSource::FromDhcp,
+            cidr: None,
+            dns: None,
+            gateway: None,
+            filter: None,
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// Field checks, plus the installer's rule that `from-answer` needs
+    /// `cidr`, `gateway` and `dns`; `from-dhcp` needs none of them.
+    ///
+    /// The gateway must lie inside the `cidr` subnet, which the installer
+    /// would otherwise only notice when the route fails to come up.
+    pub fn validate(&self) -> Result<(), NetworkConfigError> {
+        if self.source == NetworkSource::FromAnswer
+            && (self.cidr.is_none() || self.gateway.is_none() || self.dns.is_none())
+        {
+            return Err(NetworkConfigError::Incomplete);
+        }
+
+        if let Some(dns) = &self.dns {
+            dns.parse::<IpAddr>().map_err(|_| NetworkConfigError::Dns)?;
+        }
+
+        if let Some(gateway) = &self.gateway {
+            let gateway: IpAddr = gateway.parse().map_err(|_| NetworkConfigError::Gateway)?;
+            if let Some(cidr) = &self.cidr
+                && !cidr.contains(&gateway)
+            {
+                return Err(NetworkConfigError::GatewayOutsideSubnet);
+            }
+        }
+
+        if let Some(filter) = &self.filter {
+            validate_nic_filter(filter)?;
+        }
+
+        Ok(())
+    }
+
+    /* -------- FROM TOML STRING (BARE OR [network]) -------- */
+
+    pub fn from_toml_str(s: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        // 1. Wrapped form: [network] (errors inside the table are preserved)
+        let table: toml::Table = toml::from_str(s)?;
+        let cfg: NetworkConfig = match table.get("network") {
+            Some(section) => section.clone().try_into()?,
+            // 2. Fallback to bare form
+            None => toml::from_str(s)?,
+        };
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /* -------- TO TOML STRING (BARE OR [network]) -------- */
+
+    pub fn to_toml_string(&self, wrap: bool) -> Result<String, Box<dyn std::error::Error>> {
+        self.validate()?;
+
+        if wrap {
+            #[derive(serde::Serialize)]
+            struct Wrapped<'a> {
+                network: &'a NetworkConfig,
+            }
+            Ok(toml::to_string_pretty(&Wrapped { network: self })?)
+        } else {
+            Ok(toml::to_string_pretty(self)?)
+        }
+    }
+
+    /* -------- FROM YAML STRING (BARE OR network:) -------- */
+
+    pub fn from_yaml_str(s: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let cfg: NetworkConfig = section_from_yaml_str(s, "network")?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /* -------- TO YAML STRING (BARE OR network:) -------- */
+
+    pub fn to_yaml_string(&self, wrap: bool) -> Result<String, Box<dyn std::error::Error>> {
+        self.validate()?;
+        section_to_yaml_string(self, "network", wrap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn static_config() -> NetworkConfig {
+        NetworkConfig {
+            source: NetworkSource::FromAnswer,
+            cidr: Some("192.168.1.10/24".parse().unwrap()),
+            dns: Some("192.168.1.1".into()),
+            gateway: Some("192.168.1.1".into()),
+            filter: Some(BTreeMap::from([(
+                "ID_NET_NAME_MAC".into(),
+                "*aabbccddeeff".into(),
+            )])),
+        }
+    }
+
+    /* ---------------- DEFAULTS ---------------- */
+
+    #[test]
+    fn defaults_are_dhcp_and_valid() {
+        let cfg = NetworkConfig::default();
+        assert_eq!(cfg.source, NetworkSource::FromDhcp);
+        assert!(cfg.validate().is_ok());
+    }
+
+    /* ---------------- STRUCTURAL VALIDATION ---------------- */
+
+    #[test]
+    fn static_config_is_valid() {
+        assert!(static_config().validate().is_ok());
+    }
+
+    #[test]
+    fn unknown_source_fails_to_deserialize() {
+        let err = NetworkConfig::from_toml_str("source = \"from-static\"\n").unwrap_err();
+        assert!(
+            err.to_string().contains(NetworkConfigError::Source.code()),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn from_answer_requires_cidr_gateway_and_dns() {
+        for cfg in [
+            NetworkConfig {
+                cidr: None,
+                ..static_config()
+            },
+            NetworkConfig {
+                gateway: None,
+                ..static_config()
+            },
+            NetworkConfig {
+                dns: None,
+                ..static_config()
+            },
+        ] {
+            assert_eq!(cfg.validate(), Err(NetworkConfigError::Incomplete));
+        }
+    }
+
+    #[test]
+    fn from_dhcp_requires_no_addressing() {
+        let cfg = NetworkConfig {
+            source: NetworkSource::FromDhcp,
+            cidr: None,
+            gateway: None,
+            dns: None,
+            ..static_config()
+        };
+        assert!(cfg.validate().is_ok());
+    }
+
+    #[test]
+    fn invalid_cidr_fails_to_deserialize() {
+        for cidr in ["192.168.1.10", "192.168.1.10/33", "fe80::1/129", "nope/24"] {
+            let toml = format!("source = \"from-answer\"\ncidr = \"{}\"\n", cidr);
+            let err = NetworkConfig::from_toml_str(&toml).unwrap_err();
+            assert!(
+                err.to_string().contains(NetworkConfigError::Cidr.code()),
+                "cidr: {}: {}",
+                cidr,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_dns_and_gateway_fail() {
+        let cfg = NetworkConfig {
+            dns: Some("dns.lab.local".into()),
+            ..static_config()
+        };
+        assert_eq!(cfg.validate(), Err(NetworkConfigError::Dns));
+
+        let cfg = NetworkConfig {
+            gateway: Some("300.1.1.1".into()),
+            ..static_config()
+        };
+        assert_eq!(cfg.validate(), Err(NetworkConfigError::Gateway));
+    }
+
+    #[test]
+    fn gateway_must_be_inside_the_subnet() {
+        for gateway in ["192.168.2.1", "10.0.0.1", "fe80::1"] {
+            let cfg = NetworkConfig {
+                gateway: Some(gateway.into()),
+                ..static_config()
+            };
+            assert_eq!(
+                cfg.validate(),
+                Err(NetworkConfigError::GatewayOutsideSubnet),
+                "gateway: {}",
+                gateway
+            );
+        }
+
+        let cfg = NetworkConfig {
+            cidr: Some("192.168.1.10/16".parse().unwrap()),
+            gateway: Some("192.168.200.1".into()),
+            ..static_config()
+        };
+        assert!(cfg.validate().is_ok());
+    }
+
+    #[test]
+    fn empty_filter_or_value_fails() {
+        let cfg = NetworkConfig {
+            filter: Some(BTreeMap::from([("ID_NET_NAME".into(), "".into())])),
+            ..static_config()
+        };
+        assert_eq!(cfg.validate(), Err(NetworkConfigError::Filter));
+
+        let cfg = NetworkConfig {
+            filter: Some(BTreeMap::new()),
+            ..static_config()
+        };
+        assert_eq!(cfg.validate(), Err(NetworkConfigError::Filter));
+    }
+
+    /* ---------------- TOML ---------------- */
+
+    #[test]
+    fn valid_toml_deserialization_wrapped() {
+        let toml = r#"
+            [network]
+            source = "from-answer"
+            cidr = "10.0.0.5/16"
+            dns = "10.0.0.1"
+            gateway = "10.0.0.1"
+            filter.ID_NET_NAME = "enp6s0"
+        "#;
+
+        let cfg = NetworkConfig::from_toml_str(toml).unwrap();
+        assert_eq!(cfg.cidr.unwrap(), "10.0.0.5/16");
+        assert_eq!(
+            cfg.filter.unwrap().get("ID_NET_NAME").map(String::as_str),
+            Some("enp6s0")
+        );
+    }
+
+    #[test]
+    fn toml_round_trip_bare() {
+        let cfg = NetworkConfig::default();
+        let toml = cfg.to_toml_string(false).unwrap();
+        let parsed = NetworkConfig::from_toml_str(&toml).unwrap();
+        assert_eq!(cfg, parsed);
+    }
+
+    #[test]
+    fn toml_round_trip_wrapped() {
+        let cfg = static_config();
+        let toml = cfg.to_toml_string(true).unwrap();
+        let parsed = NetworkConfig::from_toml_str(&toml).unwrap();
+        assert_eq!(cfg, parsed);
+    }
+
+    /* ---------------- YAML SERIALIZATION ---------------- */
+
+    #[test]
+    fn yaml_round_trip_bare_and_wrapped() {
+        let cfg = static_config();
+        for wrap in [false, true] {
+            let yaml = cfg.to_yaml_string(wrap).unwrap();
+            assert_eq!(yaml.starts_with("network:"), wrap, "yaml was: {}", yaml);
+            let parsed = NetworkConfig::from_yaml_str(&yaml).unwrap();
+            assert_eq!(cfg, parsed);
+        }
+    }
+}
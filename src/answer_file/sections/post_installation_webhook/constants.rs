@@ -0,0 +1,42 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Colon-separated SHA-256 certificate fingerprint (32 hex byte pairs),
+/// as printed by `openssl x509 -noout -fingerprint -sha256`.
+pub static CERT_FINGERPRINT_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[0-9A-Fa-f]{2}(?::[0-9A-Fa-f]{2}){31}$")
+        .expect("invalid CERT_FINGERPRINT_PATTERN")
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_fingerprints_match() {
+        let upper = vec!["AB"; 32].join(":");
+        let lower = vec!["0f"; 32].join(":");
+        for fp in [upper, lower] {
+            assert!(
+                CERT_FINGERPRINT_PATTERN.is_match(&fp),
+                "expected valid fingerprint to match: {}",
+                fp
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_fingerprints_fail() {
+        let short = vec!["AB"; 31].join(":");
+        let long = vec!["AB"; 33].join(":");
+        let no_colons = "AB".repeat(32);
+        let bad_hex = format!("{}:ZZ", vec!["AB"; 31].join(":"));
+        for fp in [short, long, no_colons, bad_hex] {
+            assert!(
+                !CERT_FINGERPRINT_PATTERN.is_match(&fp),
+                "expected invalid fingerprint to fail: {}",
+                fp
+            );
+        }
+    }
+}
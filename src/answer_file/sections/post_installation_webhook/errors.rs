@@ -0,0 +1,38 @@
+use crate::answer_file::macros::config_error_enum;
+
+/* ===================== POST INSTALLATION WEBHOOK ERROR ===================== */
+
+config_error_enum!(
+    #[derive(Debug, PartialEq)]
+    pub enum PostInstallationWebhookError {
+        Url => "post_installation_webhook.url.invalid_format",
+        CertFingerprint => "post_installation_webhook.cert_fingerprint.invalid_format",
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn error_codes() {
+        assert_eq!(
+            PostInstallationWebhookError::Url.code(),
+            "post_installation_webhook.url.invalid_format"
+        );
+        assert_eq!(
+            PostInstallationWebhookError::CertFingerprint.code(),
+            "post_installation_webhook.cert_fingerprint.invalid_format"
+        );
+    }
+
+    #[test]
+    fn implements_std_error() {
+        let err: &dyn Error = &PostInstallationWebhookError::Url;
+        assert_eq!(
+            err.to_string(),
+            "post_installation_webhook.url.invalid_format"
+        );
+    }
+}
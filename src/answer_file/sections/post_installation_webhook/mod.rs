@@ -0,0 +1,7 @@
+pub mod constants;
+pub mod errors;
+mod section;
+
+pub use constants::CERT_FINGERPRINT_PATTERN;
+pub use errors::PostInstallationWebhookError;
+pub use section::PostInstallationWebhook;
@@ -0,0 +1,205 @@
+use crate::answer_file::sections::post_installation_webhook::{
+    PostInstallationWebhookError, constants::CERT_FINGERPRINT_PATTERN,
+};
+use crate::answer_file::sections::yaml::{section_from_yaml_str, section_to_yaml_string};
+use oxdl::validator::is_valid_url;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// `[post-installation-webhook]`: the installer POSTs a JSON summary to `url`
+/// once installation has finished.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct PostInstallationWebhook {
+    pub url: String,
+    #[serde(
+        rename = "cert-fingerprint",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cert_fingerprint: Option<String>,
+}
+
+impl PostInstallationWebhook {
+    pub fn validate(&self) -> Result<(), PostInstallationWebhookError> {
+        if !is_valid_url(&self.url) {
+            return Err(PostInstallationWebhookError::Url);
+        }
+
+        if let Some(fp) = &self.cert_fingerprint
+            && !CERT_FINGERPRINT_PATTERN.is_match(fp)
+        {
+            return Err(PostInstallationWebhookError::CertFingerprint);
+        }
+
+        Ok(())
+    }
+
+    /* -------- FROM TOML STRING (BARE OR [post-installation-webhook]) -------- */
+
+    pub fn from_toml_str(s: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        // 1. Wrapped form: [post-installation-webhook] (errors inside the table are preserved)
+        let table: toml::Table = toml::from_str(s)?;
+        let cfg: PostInstallationWebhook = match table.get("post-installation-webhook") {
+            Some(section) => section.clone().try_into()?,
+            // 2. Fallback to bare form
+            None => toml::from_str(s)?,
+        };
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /* -------- TO TOML STRING (BARE OR [post-installation-webhook]) -------- */
+
+    pub fn to_toml_string(&self, wrap: bool) -> Result<String, Box<dyn std::error::Error>> {
+        self.validate()?;
+
+        if wrap {
+            #[derive(serde::Serialize)]
+            struct Wrapped<'a> {
+                #[serde(rename = "post-installation-webhook")]
+                webhook: &'a PostInstallationWebhook,
+            }
+            Ok(toml::to_string_pretty(&Wrapped { webhook: self })?)
+        } else {
+            Ok(toml::to_string_pretty(self)?)
+        }
+    }
+
+    /* -------- FROM YAML STRING (BARE OR post-installation-webhook:) -------- */
+
+    pub fn from_yaml_str(s: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let cfg: PostInstallationWebhook = section_from_yaml_str(s, "post-installation-webhook")?;
+        cfg.validate()?;
+        Ok(cfg)
+    }
+
+    /* -------- TO YAML STRING (BARE OR post-installation-webhook:) -------- */
+
+    pub fn to_yaml_string(&self, wrap: bool) -> Result<String, Box<dyn std::error::Error>> {
+        self.validate()?;
+        section_to_yaml_string(self, "post-installation-webhook", wrap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn webhook() -> PostInstallationWebhook {
+        PostInstallationWebhook {
+            url: "https://ci.lab.local/hooks/pve-installed".into(),
+            cert_fingerprint: Some(vec!["4F"; 32].join(":")),
+        }
+    }
+
+    /* ---------------- STRUCTURAL VALIDATION ---------------- */
+
+    #[test]
+    fn valid_webhook_passes() {
+        assert!(webhook().validate().is_ok());
+    }
+
+    #[test]
+    fn fingerprint_is_optional() {
+        let cfg = PostInstallationWebhook {
+            cert_fingerprint: None,
+            ..webhook()
+        };
+        assert!(cfg.validate().is_ok());
+    }
+
+    #[test]
+    fn invalid_url_fails() {
+        for url in ["", "ci.lab.local/hook", "ftp://ci.lab.local/hook"] {
+            let cfg = PostInstallationWebhook {
+                url: url.into(),
+                ..webhook()
+            };
+            assert_eq!(
+                cfg.validate(),
+                Err(PostInstallationWebhookError::Url),
+                "url: {:?}",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_fingerprint_fails() {
+        let cfg = PostInstallationWebhook {
+            cert_fingerprint: Some("AB:CD".into()),
+            ..webhook()
+        };
+        assert_eq!(
+            cfg.validate(),
+            Err(PostInstallationWebhookError::CertFingerprint)
+        );
+    }
+
+    /* ---------------- TOML DESERIALIZATION ---------------- */
+
+    #[test]
+    fn valid_toml_deserialization_wrapped() {
+        let toml = r#"
+            [post-installation-webhook]
+            url = "http://10.0.0.5:8000/done"
+        "#;
+
+        let cfg = PostInstallationWebhook::from_toml_str(toml).unwrap();
+        assert_eq!(cfg.url, "http://10.0.0.5:8000/done");
+        assert_eq!(cfg.cert_fingerprint, None);
+    }
+
+    #[test]
+    fn missing_url_fails_at_deserialize() {
+        let toml = r#"
+            [post-installation-webhook]
+            cert-fingerprint = "AB"
+        "#;
+
+        let err = PostInstallationWebhook::from_toml_str(toml).unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("url"), "error was: {}", msg);
+    }
+
+    /* ---------------- TOML SERIALIZATION ---------------- */
+
+    #[test]
+    fn toml_round_trip_bare() {
+        let cfg = webhook();
+        let toml = cfg.to_toml_string(false).unwrap();
+        let parsed = PostInstallationWebhook::from_toml_str(&toml).unwrap();
+        assert_eq!(cfg, parsed);
+    }
+
+    #[test]
+    fn toml_round_trip_wrapped() {
+        let cfg = webhook();
+        let toml = cfg.to_toml_string(true).unwrap();
+        assert!(
+            toml.contains("[post-installation-webhook]"),
+            "toml was: {}",
+            toml
+        );
+        let parsed = PostInstallationWebhook::from_toml_str(&toml).unwrap();
+        assert_eq!(cfg, parsed);
+    }
+
+    /* ---------------- YAML SERIALIZATION ---------------- */
+
+    #[test]
+    fn yaml_round_trip_bare_and_wrapped() {
+        let cfg = webhook();
+        for wrap in [false, true] {
+            let yaml = cfg.to_yaml_string(wrap).unwrap();
+            assert_eq!(
+                yaml.starts_with("post-installation-webhook:"),
+                wrap,
+                "yaml was: {}",
+                yaml
+            );
+            let parsed = PostInstallationWebhook::from_yaml_str(&yaml).unwrap();
+            assert_eq!(cfg, parsed);
+        }
+    }
+}
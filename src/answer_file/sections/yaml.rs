@@ -0,0 +1,74 @@
+use serde::{Serialize, de::DeserializeOwned};
+use std::error::Error;
+
+/// Parses a section from YAML, either bare or nested under `key`.
+///
+/// Mirrors the TOML wrapped/bare detection: when the document is a mapping
+/// containing `key`, only that value is parsed so its errors are preserved.
+pub(crate) fn section_from_yaml_str<T: DeserializeOwned>(
+    s: &str,
+    key: &str,
+) -> Result<T, Box<dyn Error>> {
+    let value: serde_yaml::Value = serde_yaml::from_str(s)?;
+    match value.as_mapping().and_then(|m| m.get(key)) {
+        Some(section) => Ok(serde_yaml::from_value(section.clone())?),
+        None => Ok(serde_yaml::from_str(s)?),
+    }
+}
+
+/// Serializes a section to YAML, optionally nested under `key`.
+pub(crate) fn section_to_yaml_string<T: Serialize>(
+    section: &T,
+    key: &str,
+    wrap: bool,
+) -> Result<String, Box<dyn Error>> {
+    if wrap {
+        let mut mapping = serde_yaml::Mapping::new();
+        mapping.insert(key.into(), serde_yaml::to_value(section)?);
+        Ok(serde_yaml::to_string(&mapping)?)
+    } else {
+        Ok(serde_yaml::to_string(section)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+    #[serde(default)]
+    struct Section {
+        name: String,
+        count: u8,
+    }
+
+    #[test]
+    fn bare_and_wrapped_forms_parse() {
+        let bare: Section = section_from_yaml_str("name: a\ncount: 2\n", "sec").unwrap();
+        let wrapped: Section =
+            section_from_yaml_str("sec:\n  name: a\n  count: 2\n", "sec").unwrap();
+        assert_eq!(bare, wrapped);
+        assert_eq!(bare.count, 2);
+    }
+
+    #[test]
+    fn wrapped_errors_are_preserved() {
+        let err = section_from_yaml_str::<Section>("sec:\n  count: 900\n", "sec").unwrap_err();
+        assert!(err.to_string().contains("900"), "error was: {}", err);
+    }
+
+    #[test]
+    fn round_trip_wrapped() {
+        let section = Section {
+            name: "x".into(),
+            count: 1,
+        };
+        let yaml = section_to_yaml_string(&section, "sec", true).unwrap();
+        assert!(yaml.starts_with("sec:"), "yaml was: {}", yaml);
+        assert_eq!(
+            section_from_yaml_str::<Section>(&yaml, "sec").unwrap(),
+            section
+        );
+    }
+}
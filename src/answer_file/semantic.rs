@@ -0,0 +1,219 @@
+use crate::answer_file::{
+    answer::AnswerFile,
+    macros::config_error_enum,
+    sections::{Filesystem, FirstBootSource, NetworkSource},
+};
+use reqwest::Url;
+use std::net::IpAddr;
+
+/* ===================== SEMANTIC ERROR ===================== */
+
+config_error_enum!(
+    #[derive(Debug, PartialEq)]
+    pub enum SemanticError {
+        StaticFieldsWithDhcp => "network.from_dhcp.static_fields",
+        ZfsOptionsWithoutZfs => "disk_setup.zfs.requires_zfs_filesystem",
+        LvmOptionsWithoutLvm => "disk_setup.lvm.requires_ext4_or_xfs",
+        BtrfsOptionsWithoutBtrfs => "disk_setup.btrfs.requires_btrfs_filesystem",
+        FirstBootUrlUnreachable => "first_boot.url.unreachable",
+        WebhookUrlUnreachable => "post_installation_webhook.url.unreachable",
+    }
+);
+
+impl SemanticError {
+    /// TOML section the offending keys live in.
+    pub fn section(&self) -> &'static str {
+        match self {
+            Self::StaticFieldsWithDhcp => "network",
+            Self::ZfsOptionsWithoutZfs
+            | Self::LvmOptionsWithoutLvm
+            | Self::BtrfsOptionsWithoutBtrfs => "disk-setup",
+            Self::FirstBootUrlUnreachable => "first-boot",
+            Self::WebhookUrlUnreachable => "post-installation-webhook",
+        }
+    }
+}
+
+/* ===================== SEMANTIC CHECKS ===================== */
+
+impl AnswerFile {
+    /// Checks constraints that a single section's `validate()` cannot see,
+    /// returning every violation in section order.
+    ///
+    /// Run by [`AnswerFile::validate`] after the per-section checks.
+    pub fn semantic_errors(&self) -> Vec<SemanticError> {
+        let mut errors = Vec::new();
+
+        let network = &self.network;
+        if network.source == NetworkSource::FromDhcp
+            && (network.cidr.is_some() || network.gateway.is_some() || network.dns.is_some())
+        {
+            errors.push(SemanticError::StaticFieldsWithDhcp);
+        }
+
+        let disk = &self.disk_setup;
+        if disk.zfs.is_some() && disk.filesystem != Filesystem::Zfs {
+            errors.push(SemanticError::ZfsOptionsWithoutZfs);
+        }
+        if disk.lvm.is_some() && !matches!(disk.filesystem, Filesystem::Ext4 | Filesystem::Xfs) {
+            errors.push(SemanticError::LvmOptionsWithoutLvm);
+        }
+        if disk.btrfs.is_some() && disk.filesystem != Filesystem::Btrfs {
+            errors.push(SemanticError::BtrfsOptionsWithoutBtrfs);
+        }
+
+        // A local `script` is bundled and the section rewritten to
+        // `from-iso`, so its source is never fetched.
+        if let Some(first_boot) = &self.first_boot
+            && first_boot.source == FirstBootSource::FromUrl
+            && first_boot.script.is_none()
+            && !first_boot.url.as_deref().is_some_and(is_reachable_url)
+        {
+            errors.push(SemanticError::FirstBootUrlUnreachable);
+        }
+
+        if let Some(webhook) = &self.post_installation_webhook
+            && !is_reachable_url(&webhook.url)
+        {
+            errors.push(SemanticError::WebhookUrlUnreachable);
+        }
+
+        errors
+    }
+}
+
+/// `true` for http(s) URLs whose host can be reached from the installed node,
+/// i.e. not `localhost`, loopback or unspecified addresses.
+fn is_reachable_url(url: &str) -> bool {
+    let Ok(url) = Url::parse(url) else {
+        return false;
+    };
+    if !matches!(url.scheme(), "http" | "https") {
+        return false;
+    }
+
+    match url.host_str() {
+        None | Some("") => false,
+        Some(host) if host.eq_ignore_ascii_case("localhost") => false,
+        Some(host) => match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+            Ok(ip) => !ip.is_loopback() && !ip.is_unspecified(),
+            Err(_) => true,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer_file::sections::{
+        BtrfsOptions, FirstBoot, LvmOptions, PostInstallationWebhook, ZfsOptions,
+    };
+
+    /* ---------------- NETWORK ---------------- */
+
+    #[test]
+    fn default_answer_has_no_semantic_errors() {
+        assert!(AnswerFile::default().semantic_errors().is_empty());
+    }
+
+    #[test]
+    fn dhcp_rejects_static_fields() {
+        let mut answer = AnswerFile::default();
+        answer.network.dns = Some("1.1.1.1".into());
+        assert_eq!(
+            answer.semantic_errors(),
+            vec![SemanticError::StaticFieldsWithDhcp]
+        );
+    }
+
+    /* ---------------- DISK SETUP ---------------- */
+
+    #[test]
+    fn filesystem_options_must_match_filesystem() {
+        let mut answer = AnswerFile::default();
+        answer.disk_setup.zfs = Some(ZfsOptions::default());
+        answer.disk_setup.btrfs = Some(BtrfsOptions::default());
+        assert_eq!(
+            answer.semantic_errors(),
+            vec![
+                SemanticError::ZfsOptionsWithoutZfs,
+                SemanticError::BtrfsOptionsWithoutBtrfs,
+            ]
+        );
+
+        let mut answer = AnswerFile::default();
+        answer.disk_setup.filesystem = Filesystem::Zfs;
+        answer.disk_setup.lvm = Some(LvmOptions::default());
+        assert_eq!(
+            answer.semantic_errors(),
+            vec![SemanticError::LvmOptionsWithoutLvm]
+        );
+
+        answer.disk_setup.filesystem = Filesystem::Xfs;
+        assert!(answer.semantic_errors().is_empty());
+    }
+
+    /* ---------------- URLS ---------------- */
+
+    #[test]
+    fn urls_must_be_reachable_from_the_node() {
+        for (url, reachable) in [
+            ("https://ci.lab.local/hook", true),
+            ("http://10.0.0.5:8080/first-boot.sh", true),
+            ("https://[2001:db8::1]/hook", true),
+            ("https://localhost/hook", false),
+            ("http://127.0.0.1/hook", false),
+            ("http://[::1]/hook", false),
+            ("http://0.0.0.0/hook", false),
+            ("ftp://ci.lab.local/hook", false),
+            ("not a url", false),
+        ] {
+            assert_eq!(is_reachable_url(url), reachable, "url: {}", url);
+        }
+    }
+
+    #[test]
+    fn bundled_scripts_need_no_reachable_url() {
+        let answer = AnswerFile {
+            first_boot: Some(FirstBoot {
+                source: FirstBootSource::FromUrl,
+                script: Some("setup.sh".into()),
+                ..FirstBoot::default()
+            }),
+            ..AnswerFile::default()
+        };
+        assert!(answer.first_boot.as_ref().unwrap().validate().is_ok());
+        assert!(answer.semantic_errors().is_empty());
+    }
+
+    #[test]
+    fn loopback_urls_are_reported_per_section() {
+        let answer = AnswerFile {
+            first_boot: Some(FirstBoot {
+                source: FirstBootSource::FromUrl,
+                url: Some("https://localhost/first-boot.sh".into()),
+                cert_fingerprint: None,
+                ..FirstBoot::default()
+            }),
+            post_installation_webhook: Some(PostInstallationWebhook {
+                url: "http://127.0.0.1/hook".into(),
+                cert_fingerprint: None,
+            }),
+            ..AnswerFile::default()
+        };
+
+        let errors = answer.semantic_errors();
+        assert_eq!(
+            errors,
+            vec![
+                SemanticError::FirstBootUrlUnreachable,
+                SemanticError::WebhookUrlUnreachable,
+            ]
+        );
+        assert_eq!(errors[0].section(), "first-boot");
+        assert_eq!(
+            errors[1].code(),
+            "post_installation_webhook.url.unreachable"
+        );
+    }
+}
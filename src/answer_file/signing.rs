@@ -0,0 +1,296 @@
+use crate::answer_file::{answer::AnswerFile, format::AnswerFormat};
+use ssh_key::{Algorithm, HashAlg, LineEnding, PrivateKey, PublicKey, SshSig};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Extension of a detached signature next to its answer file, e.g. `answer.toml.sig`.
+pub const SIGNATURE_EXTENSION: &str = "sig";
+
+/// SSH signature namespace of answer signatures (`ssh-keygen -Y sign -n pveauto-answer`).
+pub const SIGNATURE_NAMESPACE: &str = "pveauto-answer";
+
+/// Path of the trusted operator keys file, see [`TrustedKeys::from_env`].
+pub const TRUSTED_KEYS_ENV: &str = "PVEAUTO_TRUSTED_KEYS";
+
+/* ===================== SIGNATURE ERROR ===================== */
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    /// A signing or trusted key is malformed, encrypted or not ed25519.
+    InvalidKey(String),
+    /// The answer has no detached signature.
+    Missing(String),
+    /// The signature is malformed or does not match the answer.
+    Invalid(String),
+    /// The signature is valid but made by a key that is not trusted.
+    UntrustedKey(String),
+    Sign(String),
+}
+
+impl SignatureError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidKey(_) => "signature.invalid_key",
+            Self::Missing(_) => "signature.missing",
+            Self::Invalid(_) => "signature.invalid",
+            Self::UntrustedKey(_) => "signature.untrusted_key",
+            Self::Sign(_) => "signing.failed",
+        }
+    }
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidKey(detail)
+            | Self::Missing(detail)
+            | Self::Invalid(detail)
+            | Self::UntrustedKey(detail)
+            | Self::Sign(detail) => write!(f, "{} ({})", self.code(), detail),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+/* ===================== SIGNING ===================== */
+
+/// `path` with [`SIGNATURE_EXTENSION`] appended.
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(SIGNATURE_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Signs the answer file bytes `contents` with the operator's ed25519 `key`,
+/// returning an armored SSH signature (`-----BEGIN SSH SIGNATURE-----`).
+///
+/// The result is what `ssh-keygen -Y sign -n pveauto-answer` writes, so
+/// either tool can produce or check it.
+///
+/// # Errors
+/// [`SignatureError::InvalidKey`] for encrypted or non-ed25519 keys.
+pub fn sign(contents: &[u8], key: &PrivateKey) -> Result<String, SignatureError> {
+    if key.is_encrypted() {
+        return Err(SignatureError::InvalidKey(
+            "private key is passphrase-protected".into(),
+        ));
+    }
+    if key.algorithm() != Algorithm::Ed25519 {
+        return Err(SignatureError::InvalidKey(key.algorithm().to_string()));
+    }
+
+    key.sign(SIGNATURE_NAMESPACE, HashAlg::Sha512, contents)
+        .and_then(|sig| sig.to_pem(LineEnding::LF))
+        .map_err(|e| SignatureError::Sign(e.to_string()))
+}
+
+/* ===================== TRUSTED KEYS ===================== */
+
+/// The ed25519 public keys of the operators whose answers may be installed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustedKeys(Vec<PublicKey>);
+
+impl TrustedKeys {
+    /// Parses one OpenSSH public key per line (`ssh-ed25519 AAAA... comment`),
+    /// skipping blank lines and `#` comments; at least one key is required.
+    pub fn parse(s: &str) -> Result<Self, SignatureError> {
+        let keys = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let key = PublicKey::from_openssh(line)
+                    .map_err(|_| SignatureError::InvalidKey(line.to_owned()))?;
+                if key.algorithm() != Algorithm::Ed25519 {
+                    return Err(SignatureError::InvalidKey(key.algorithm().to_string()));
+                }
+                Ok(key)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if keys.is_empty() {
+            return Err(SignatureError::InvalidKey("none given".into()));
+        }
+        Ok(Self(keys))
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, SignatureError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| SignatureError::InvalidKey(format!("{}: {}", path.display(), e)))?;
+        Self::parse(&contents)
+    }
+
+    /// Loads the file named by [`TRUSTED_KEYS_ENV`]; `None` when it is unset,
+    /// i.e. when signatures are not enforced.
+    pub fn from_env() -> Option<Result<Self, SignatureError>> {
+        std::env::var(TRUSTED_KEYS_ENV)
+            .ok()
+            .filter(|path| !path.is_empty())
+            .map(Self::from_file)
+    }
+
+    pub fn keys(&self) -> &[PublicKey] {
+        &self.0
+    }
+
+    /// Checks the armored `signature` of the answer file bytes `contents`,
+    /// returning the trusted key that made it.
+    pub fn verify(&self, contents: &[u8], signature: &str) -> Result<&PublicKey, SignatureError> {
+        let sig = SshSig::from_pem(signature.trim())
+            .map_err(|e| SignatureError::Invalid(e.to_string()))?;
+
+        let Some(key) = self.0.iter().find(|k| k.key_data() == sig.public_key()) else {
+            return Err(SignatureError::UntrustedKey(
+                sig.public_key().fingerprint(HashAlg::Sha256).to_string(),
+            ));
+        };
+        key.verify(SIGNATURE_NAMESPACE, contents, &sig)
+            .map_err(|e| SignatureError::Invalid(e.to_string()))?;
+        Ok(key)
+    }
+
+    /// Checks `contents`, read from `path`, against the detached signature at
+    /// [`signature_path`]`(path)`.
+    pub fn verify_detached(
+        &self,
+        path: &Path,
+        contents: &[u8],
+    ) -> Result<&PublicKey, SignatureError> {
+        let sig_path = signature_path(path);
+        let signature = std::fs::read_to_string(&sig_path)
+            .map_err(|_| SignatureError::Missing(sig_path.display().to_string()))?;
+        self.verify(contents, &signature)
+    }
+}
+
+/* ===================== SIGNED LOADING ===================== */
+
+impl AnswerFile {
+    /// Like [`AnswerFile::from_path`], but only returns the answer when
+    /// `<path>.sig` is a valid signature of the file by one of `trusted`.
+    pub fn from_signed_path(
+        path: impl AsRef<Path>,
+        trusted: &TrustedKeys,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        let contents = std::fs::read(path)?;
+        trusted.verify_detached(path, &contents)?;
+
+        Self::from_str_as(
+            std::str::from_utf8(&contents)?,
+            AnswerFormat::from_path(path).unwrap_or_default(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ssh_key::private::Ed25519Keypair;
+
+    fn keypair(seed: u8) -> PrivateKey {
+        PrivateKey::from(Ed25519Keypair::from_seed(&[seed; 32]))
+    }
+
+    fn trusted(keys: &[&PrivateKey]) -> TrustedKeys {
+        let lines: Vec<_> = keys
+            .iter()
+            .map(|k| k.public_key().to_openssh().unwrap())
+            .collect();
+        TrustedKeys::parse(&lines.join("\n")).unwrap()
+    }
+
+    /* ---------------- SIGN / VERIFY ---------------- */
+
+    #[test]
+    fn signatures_verify_with_the_trusted_key() {
+        let operator = keypair(1);
+        let answer = AnswerFile::default().to_toml_string().unwrap();
+
+        let signature = sign(answer.as_bytes(), &operator).unwrap();
+        assert!(signature.starts_with("-----BEGIN SSH SIGNATURE-----"));
+
+        let keys = trusted(&[&keypair(2), &operator]);
+        let signer = keys.verify(answer.as_bytes(), &signature).unwrap();
+        assert_eq!(signer, operator.public_key());
+    }
+
+    #[test]
+    fn tampered_answers_fail() {
+        let operator = keypair(1);
+        let answer = AnswerFile::default().to_toml_string().unwrap();
+        let signature = sign(answer.as_bytes(), &operator).unwrap();
+
+        let tampered = answer.replace("reboot-on-error = false", "reboot-on-error = true");
+        let err = trusted(&[&operator])
+            .verify(tampered.as_bytes(), &signature)
+            .unwrap_err();
+        assert_eq!(err.code(), "signature.invalid");
+    }
+
+    #[test]
+    fn untrusted_signers_are_named_by_fingerprint() {
+        let stranger = keypair(3);
+        let signature = sign(b"[global]\n", &stranger).unwrap();
+
+        let err = trusted(&[&keypair(1)])
+            .verify(b"[global]\n", &signature)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            SignatureError::UntrustedKey(
+                stranger
+                    .public_key()
+                    .fingerprint(HashAlg::Sha256)
+                    .to_string()
+            )
+        );
+    }
+
+    /* ---------------- TRUSTED KEYS ---------------- */
+
+    #[test]
+    fn trusted_keys_skip_comments_and_require_a_key() {
+        let line = keypair(1).public_key().to_openssh().unwrap();
+        let keys = TrustedKeys::parse(&format!("# ops team\n\n{} alice@lab\n", line)).unwrap();
+        assert_eq!(keys.keys().len(), 1);
+
+        for bad in ["", "# nobody\n", "ssh-ed25519 garbage"] {
+            assert_eq!(
+                TrustedKeys::parse(bad).unwrap_err().code(),
+                "signature.invalid_key",
+                "{:?}",
+                bad
+            );
+        }
+    }
+
+    /* ---------------- SIGNED LOADING ---------------- */
+
+    #[test]
+    fn signed_paths_require_a_detached_signature() {
+        let dir = std::env::temp_dir().join(format!("pveauto-signing-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("answer.toml");
+
+        let operator = keypair(1);
+        let keys = trusted(&[&operator]);
+        let answer = AnswerFile::default();
+        answer.to_path(&path, false).unwrap();
+
+        let err = AnswerFile::from_signed_path(&path, &keys).unwrap_err();
+        assert!(err.to_string().starts_with("signature.missing"), "{}", err);
+
+        let signature = sign(&std::fs::read(&path).unwrap(), &operator).unwrap();
+        std::fs::write(signature_path(&path), signature).unwrap();
+        assert_eq!(AnswerFile::from_signed_path(&path, &keys).unwrap(), answer);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
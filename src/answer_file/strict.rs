@@ -0,0 +1,264 @@
+use crate::answer_file::{answer::AnswerFile, format::AnswerFormat, sections::GlobalConfig};
+use serde::de::{Deserialize, DeserializeOwned, Deserializer};
+use std::fmt;
+
+/// Code of [`UnknownKeyError`] and of the matching strict diagnostics.
+pub const UNKNOWN_KEY_CODE: &str = "answer.unknown_key";
+
+/* ===================== UNKNOWN KEY ERROR ===================== */
+
+/// A key the answer format does not define, rejected by strict parsing.
+///
+/// Lenient parsing drops such keys silently, so a typo like `rebot-mode`
+/// falls back to the default without notice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownKeyError {
+    /// Dotted path of the offending key, e.g. `global.rebot-mode`.
+    pub key: String,
+}
+
+impl UnknownKeyError {
+    pub fn code(&self) -> &'static str {
+        UNKNOWN_KEY_CODE
+    }
+}
+
+impl fmt::Display for UnknownKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.code(), self.key)
+    }
+}
+
+impl std::error::Error for UnknownKeyError {}
+
+/* ===================== STRICT PARSING ===================== */
+
+impl AnswerFile {
+    /// Like [`from_toml_str`](AnswerFile::from_toml_str), but fails with
+    /// [`UnknownKeyError`] on the first key the answer format does not define.
+    pub fn from_toml_str_strict(s: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::from_str_as_strict(s, AnswerFormat::Toml)
+    }
+
+    /// Strict counterpart of [`from_str_as`](AnswerFile::from_str_as) for any format.
+    pub fn from_str_as_strict(
+        s: &str,
+        format: AnswerFormat,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let (answer, unknown): (AnswerFile, _) = deserialize_str(s, format)?;
+        reject_unknown(unknown, |_| true)?;
+        answer.validate()?;
+        Ok(answer)
+    }
+}
+
+impl GlobalConfig {
+    /// Like [`from_toml_str`](GlobalConfig::from_toml_str), but fails with
+    /// [`UnknownKeyError`] on the first unknown key of the section.
+    ///
+    /// Other tables next to `[global]` are not checked.
+    pub fn from_toml_str_strict(s: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        #[derive(serde::Deserialize)]
+        struct Wrapper {
+            global: GlobalConfig,
+        }
+
+        let cfg = match deserialize_str::<Wrapper>(s, AnswerFormat::Toml) {
+            Ok((wrapper, unknown)) => {
+                reject_unknown(unknown, |key| key.starts_with("global."))?;
+                wrapper.global
+            }
+            Err(_) => {
+                let (cfg, unknown) = deserialize_str::<GlobalConfig>(s, AnswerFormat::Toml)?;
+                reject_unknown(unknown, |_| true)?;
+                cfg
+            }
+        };
+        cfg.validate()?;
+        Ok(cfg)
+    }
+}
+
+/// Dotted paths of every key in `s` the answer format does not define, in
+/// document order. Empty when `s` does not parse as an answer at all.
+pub(crate) fn unknown_keys(s: &str, format: AnswerFormat) -> Vec<String> {
+    deserialize_str::<AnswerFile>(s, format)
+        .map(|(_, unknown)| unknown)
+        .unwrap_or_default()
+}
+
+/// Deserializes `s`, collecting the dotted path of every key serde ignored.
+fn deserialize_str<T: DeserializeOwned>(
+    s: &str,
+    format: AnswerFormat,
+) -> Result<(T, Vec<String>), Box<dyn std::error::Error>> {
+    let mut unknown = Vec::new();
+    let value = match format {
+        AnswerFormat::Toml => collect_ignored(toml::Deserializer::parse(s)?, &mut unknown)?,
+        AnswerFormat::Json => {
+            let mut de = serde_json::Deserializer::from_str(s);
+            let value = collect_ignored(&mut de, &mut unknown)?;
+            de.end()?;
+            value
+        }
+        AnswerFormat::Yaml => collect_ignored(serde_yaml::Deserializer::from_str(s), &mut unknown)?,
+    };
+    Ok((value, unknown))
+}
+
+fn collect_ignored<'de, D, T>(de: D, unknown: &mut Vec<String>) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    serde_ignored::deserialize(de, |path| unknown.push(key_path(&path)))
+}
+
+fn reject_unknown(
+    unknown: Vec<String>,
+    relevant: impl Fn(&str) -> bool,
+) -> Result<(), UnknownKeyError> {
+    match unknown.into_iter().find(|key| relevant(key)) {
+        Some(key) => Err(UnknownKeyError { key }),
+        None => Ok(()),
+    }
+}
+
+/// Renders `path` as the dotted key users wrote, e.g. `first-boot.sorce`,
+/// skipping the `?` segments serde_ignored adds for options and newtypes.
+fn key_path(path: &serde_ignored::Path) -> String {
+    use serde_ignored::Path;
+
+    fn push(path: &Path, out: &mut Vec<String>) {
+        match path {
+            Path::Root => {}
+            Path::Seq { parent, index } => {
+                push(parent, out);
+                out.push(index.to_string());
+            }
+            Path::Map { parent, key } => {
+                push(parent, out);
+                out.push(key.clone());
+            }
+            Path::Some { parent }
+            | Path::NewtypeStruct { parent }
+            | Path::NewtypeVariant { parent } => push(parent, out),
+        }
+    }
+
+    let mut segments = Vec::new();
+    push(path, &mut segments);
+    segments.join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_extra(section: &str, line: &str) -> String {
+        let toml = AnswerFile::default().to_toml_string().unwrap();
+        toml.replacen(
+            &format!("[{}]\n", section),
+            &format!("[{}]\n{}\n", section, line),
+            1,
+        )
+    }
+
+    fn unknown_key(err: Box<dyn std::error::Error>) -> String {
+        err.downcast_ref::<UnknownKeyError>()
+            .unwrap_or_else(|| panic!("not an unknown key error: {}", err))
+            .key
+            .clone()
+    }
+
+    /* ---------------- ANSWER FILE ---------------- */
+
+    #[test]
+    fn known_keys_parse_strictly() {
+        let toml = AnswerFile::default().to_toml_string().unwrap();
+        assert_eq!(
+            AnswerFile::from_toml_str_strict(&toml).unwrap(),
+            AnswerFile::default()
+        );
+    }
+
+    #[test]
+    fn typo_is_reported_with_its_path() {
+        let toml = with_extra("global", r#"rebot-mode = "power-off""#);
+
+        // Lenient parsing silently drops the key.
+        assert!(AnswerFile::from_toml_str(&toml).is_ok());
+
+        let err = AnswerFile::from_toml_str_strict(&toml).unwrap_err();
+        assert_eq!(err.to_string(), "answer.unknown_key (global.rebot-mode)");
+        assert_eq!(unknown_key(err), "global.rebot-mode");
+    }
+
+    #[test]
+    fn unknown_tables_and_optional_sections_are_reported() {
+        let toml = format!(
+            "{}\n[first-boot]\nsource = \"from-iso\"\nordering = \"fully-up\"\nsorce = \"x\"\n",
+            AnswerFile::default().to_toml_string().unwrap()
+        );
+        assert_eq!(
+            unknown_key(AnswerFile::from_toml_str_strict(&toml).unwrap_err()),
+            "first-boot.sorce"
+        );
+
+        let toml = format!(
+            "{}\n[extras]\na = 1\n",
+            AnswerFile::default().to_toml_string().unwrap()
+        );
+        assert_eq!(
+            unknown_key(AnswerFile::from_toml_str_strict(&toml).unwrap_err()),
+            "extras"
+        );
+    }
+
+    #[test]
+    fn json_and_yaml_are_checked() {
+        let mut json: serde_json::Value =
+            serde_json::from_str(&AnswerFile::default().to_json_string().unwrap()).unwrap();
+        json["network"]["cidrr"] = "10.0.0.2/24".into();
+        let err =
+            AnswerFile::from_str_as_strict(&json.to_string(), AnswerFormat::Json).unwrap_err();
+        assert_eq!(unknown_key(err), "network.cidrr");
+
+        let yaml = format!(
+            "{}extra: true\n",
+            AnswerFile::default().to_yaml_string().unwrap()
+        );
+        let err = AnswerFile::from_str_as_strict(&yaml, AnswerFormat::Yaml).unwrap_err();
+        assert_eq!(unknown_key(err), "extra");
+    }
+
+    #[test]
+    fn validation_still_runs() {
+        let toml = AnswerFile::default()
+            .to_toml_string()
+            .unwrap()
+            .replace("proxmox.lab.local", "nope");
+        let err = AnswerFile::from_toml_str_strict(&toml).unwrap_err();
+        assert!(err.downcast_ref::<UnknownKeyError>().is_none());
+    }
+
+    /* ---------------- GLOBAL CONFIG ---------------- */
+
+    #[test]
+    fn global_config_bare_and_wrapped() {
+        let bare = "fqdn = \"pve.lab.local\"\nmailto = \"root@localhost\"\nkeybord = \"de\"\n";
+        assert_eq!(
+            unknown_key(GlobalConfig::from_toml_str_strict(bare).unwrap_err()),
+            "keybord"
+        );
+
+        let wrapped = format!("[global]\n{}", bare.replace("keybord", "keyboard"));
+        let cfg = GlobalConfig::from_toml_str_strict(&format!("{}\n[network]\nx = 1\n", wrapped))
+            .unwrap();
+        assert_eq!(cfg.fqdn, "pve.lab.local");
+
+        let err =
+            GlobalConfig::from_toml_str_strict(&format!("{}reboot = true\n", wrapped)).unwrap_err();
+        assert_eq!(unknown_key(err), "global.reboot");
+    }
+}
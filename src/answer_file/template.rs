@@ -0,0 +1,177 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::{collections::BTreeMap, fmt};
+
+/// Allowed placeholder names, e.g. `hostname`, `mac`, `index`.
+static VARIABLE_NAME_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").expect("invalid VARIABLE_NAME_PATTERN"));
+
+/* ===================== TEMPLATE ERROR ===================== */
+
+/// Failure while expanding `{{name}}` placeholders in an answer template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// A placeholder names a variable missing from the variables map.
+    UnknownVariable(String),
+    /// A placeholder or variable name is not `[A-Za-z_][A-Za-z0-9_]*`.
+    InvalidVariableName(String),
+    /// `{{` without a closing `}}`, on the given 1-based line.
+    Unterminated(usize),
+}
+
+impl TemplateError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnknownVariable(_) => "template.unknown_variable",
+            Self::InvalidVariableName(_) => "template.invalid_variable_name",
+            Self::Unterminated(_) => "template.unterminated_placeholder",
+        }
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownVariable(name) | Self::InvalidVariableName(name) => {
+                write!(f, "{} ({})", self.code(), name)
+            }
+            Self::Unterminated(line) => write!(f, "{} (line {})", self.code(), line),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/* ===================== RENDERING ===================== */
+
+/// Expands `{{name}}` placeholders (surrounding whitespace allowed) in `template`.
+///
+/// Values are inserted verbatim, so string fields must be quoted in the
+/// template itself: `fqdn = "{{hostname}}.lab.local"`.
+///
+/// # Errors
+/// Every placeholder must name a variable in `vars`; unknown names,
+/// malformed names and unterminated placeholders are rejected.
+pub fn render_template(
+    template: &str,
+    vars: &BTreeMap<String, String>,
+) -> Result<String, TemplateError> {
+    if let Some(name) = vars.keys().find(|k| !VARIABLE_NAME_PATTERN.is_match(k)) {
+        return Err(TemplateError::InvalidVariableName(name.clone()));
+    }
+
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+
+        let Some(end) = after.find("}}") else {
+            let offset = template.len() - rest.len() + start;
+            let line = template[..offset].matches('\n').count() + 1;
+            return Err(TemplateError::Unterminated(line));
+        };
+
+        let name = after[..end].trim();
+        if !VARIABLE_NAME_PATTERN.is_match(name) {
+            return Err(TemplateError::InvalidVariableName(name.to_owned()));
+        }
+
+        let value = vars
+            .get(name)
+            .ok_or_else(|| TemplateError::UnknownVariable(name.to_owned()))?;
+        out.push_str(value);
+        rest = &after[end + 2..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Parses a `key=value` pair as given on the command line (`--var hostname=pve01`).
+pub fn parse_variable(pair: &str) -> Result<(String, String), TemplateError> {
+    let (name, value) = pair
+        .split_once('=')
+        .ok_or_else(|| TemplateError::InvalidVariableName(pair.to_owned()))?;
+    let name = name.trim();
+
+    if !VARIABLE_NAME_PATTERN.is_match(name) {
+        return Err(TemplateError::InvalidVariableName(name.to_owned()));
+    }
+    Ok((name.to_owned(), value.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    /* ---------------- RENDERING ---------------- */
+
+    #[test]
+    fn placeholders_are_expanded() {
+        let vars = vars(&[("hostname", "pve01"), ("index", "1")]);
+        let out = render_template(
+            "fqdn = \"{{hostname}}.lab.local\"\ncidr = \"10.0.0.{{ index }}/24\"\n",
+            &vars,
+        )
+        .unwrap();
+        assert_eq!(out, "fqdn = \"pve01.lab.local\"\ncidr = \"10.0.0.1/24\"\n");
+    }
+
+    #[test]
+    fn text_without_placeholders_is_unchanged() {
+        let toml = "[global]\nfilter = { ID = \"x\" }\n";
+        assert_eq!(render_template(toml, &BTreeMap::new()).unwrap(), toml);
+    }
+
+    #[test]
+    fn unknown_variable_is_rejected() {
+        assert_eq!(
+            render_template("{{mac}}", &vars(&[("hostname", "pve01")])),
+            Err(TemplateError::UnknownVariable("mac".into()))
+        );
+    }
+
+    #[test]
+    fn invalid_names_are_rejected() {
+        assert_eq!(
+            render_template("{{ host name }}", &BTreeMap::new()),
+            Err(TemplateError::InvalidVariableName("host name".into()))
+        );
+        assert_eq!(
+            render_template("", &vars(&[("1st", "x")])),
+            Err(TemplateError::InvalidVariableName("1st".into()))
+        );
+    }
+
+    #[test]
+    fn unterminated_placeholder_reports_line() {
+        let err =
+            render_template("[global]\nfqdn = \"{{hostname\"\n", &BTreeMap::new()).unwrap_err();
+        assert_eq!(err, TemplateError::Unterminated(2));
+        assert_eq!(
+            err.to_string(),
+            "template.unterminated_placeholder (line 2)"
+        );
+    }
+
+    /* ---------------- VARIABLES ---------------- */
+
+    #[test]
+    fn variables_parse_from_pairs() {
+        assert_eq!(
+            parse_variable("mac=aa:bb=cc").unwrap(),
+            ("mac".to_string(), "aa:bb=cc".to_string())
+        );
+        assert!(parse_variable("novalue").is_err());
+        assert!(parse_variable("bad-name=x").is_err());
+    }
+}
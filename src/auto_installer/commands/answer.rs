@@ -0,0 +1,848 @@
+use crate::answer_file::{
+    AgeIdentity, AgeRecipients, AnswerDiagnostic, AnswerFile, AnswerFormat, AnswerPreset,
+    Assistant, Inventory, LintWarning, ProfileStore, SchemaVersion,
+    assistant::{ASSISTANT_BINARY, ASSISTANT_ENV},
+    diagnose_str, diagnose_str_strict,
+    encryption::{AGE_PASSPHRASE_ENV, decrypt_if_encrypted, encrypt, is_encrypted},
+    file::write_atomic,
+    profiles::PROFILE_REFERENCE_PREFIX,
+    sections::PasswordHashFormat,
+};
+use crate::auto_installer::commands::{
+    constants::{CommandError, EncryptMode},
+    password::prompt_confirmed,
+    signature::verify_if_enforced,
+    wizard::{Prompter, run_wizard},
+};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// Validates the answer file at `path` with every section check.
+///
+/// `${env:NAME}` secrets in a TOML file are resolved first.
+///
+/// # Arguments
+/// * `path` — Path to an answer file, or `@name` for a stored profile.
+/// * `format` — Format of the file at `path`.
+/// * `strict` — Also report keys the answer format does not define as `answer.unknown_key`.
+/// * `assistant` — Also run the file through [`Assistant::locate`]'s
+///   `validate-answer`; fails when no assistant is installed.
+///
+/// # Returns
+/// `Ok(())` and prints `valid` to standard output when the file is valid.
+/// Otherwise prints one `<path>:<line>: <code>` entry per problem to
+/// standard error and returns `Err(CommandError)`.
+pub fn validate_answer_file(
+    path: &str,
+    format: AnswerFormat,
+    strict: bool,
+    assistant: bool,
+) -> Result<(), CommandError> {
+    let assistant = if assistant {
+        Some(Assistant::locate().ok_or_else(|| {
+            eprintln!(
+                "{} not found; install it or set ${}",
+                ASSISTANT_BINARY, ASSISTANT_ENV
+            );
+            CommandError
+        })?)
+    } else {
+        None
+    };
+
+    let contents = read_resolved_answer(path, format)?;
+    let mut diagnostics = if strict {
+        diagnose_str_strict(&contents, format)
+    } else {
+        diagnose_str(&contents, format)
+    };
+    if let Some(assistant) = assistant {
+        diagnostics.extend(assistant.diagnose_str(&contents, format));
+    }
+
+    report(path, &diagnostics)
+}
+
+/// Prints `valid`, or one `<label>:<line>: <code>` entry per problem in `contents`.
+pub(crate) fn report_diagnostics(
+    label: &str,
+    contents: &str,
+    format: AnswerFormat,
+) -> Result<(), CommandError> {
+    report(label, &diagnose_str(contents, format))
+}
+
+fn report(label: &str, diagnostics: &[AnswerDiagnostic]) -> Result<(), CommandError> {
+    if diagnostics.is_empty() {
+        println!("valid");
+        return Ok(());
+    }
+
+    print_diagnostics(label, diagnostics);
+    Err(CommandError)
+}
+
+pub(crate) fn print_diagnostics(label: &str, diagnostics: &[AnswerDiagnostic]) {
+    for d in diagnostics {
+        let location = d
+            .line
+            .map(|l| format!("{}:{}", label, l))
+            .unwrap_or(label.into());
+        match &d.detail {
+            Some(detail) => eprintln!("{}: {} ({})", location, d.code, detail),
+            None => eprintln!("{}: {}", location, d.code),
+        }
+    }
+}
+
+/// `answer lint`: validates the answer at `path`, then prints one
+/// `<path>: warning: <code> (<message>)` line per [`LintWarning`] not in `allow`.
+///
+/// # Returns
+/// `Ok(())` when the file is valid and every warning is allowed; otherwise
+/// `Err(CommandError)`, so CI can gate on it.
+pub fn lint_answer_file(
+    path: &str,
+    format: AnswerFormat,
+    allow: &[LintWarning],
+) -> Result<(), CommandError> {
+    let contents = read_resolved_answer(path, format)?;
+
+    let diagnostics = diagnose_str(&contents, format);
+    if !diagnostics.is_empty() {
+        print_diagnostics(path, &diagnostics);
+        return Err(CommandError);
+    }
+    let answer = AnswerFile::from_str_as(&contents, format).map_err(|e| {
+        eprintln!("{}: {}", path, e);
+        CommandError
+    })?;
+
+    let warnings: Vec<_> = answer
+        .lint()
+        .into_iter()
+        .filter(|w| !allow.contains(w))
+        .collect();
+    if warnings.is_empty() {
+        println!("no warnings");
+        return Ok(());
+    }
+
+    for w in &warnings {
+        println!("{}: warning: {} ({})", path, w.code(), w.message());
+    }
+    Err(CommandError)
+}
+
+/// `answer convert`: prints the answer file at `path` as canonical TOML.
+///
+/// The input is fully validated first, so only valid answers are converted;
+/// `${env:NAME}` secrets in TOML input are printed resolved.
+pub fn convert_answer_file(path: &str, format: AnswerFormat) -> Result<(), CommandError> {
+    let contents = read_resolved_answer(path, format)?;
+    let answer = AnswerFile::from_str_as(&contents, format).map_err(|e| {
+        eprintln!("{}: {}", path, e);
+        CommandError
+    })?;
+
+    print_toml(path, &answer)
+}
+
+fn print_toml(path: &str, answer: &AnswerFile) -> Result<(), CommandError> {
+    let toml = answer.to_toml_string().map_err(|e| {
+        eprintln!("{}: {}", path, e);
+        CommandError
+    })?;
+    print!("{}", toml);
+    Ok(())
+}
+
+/// `answer render`: expands `{{name}}` placeholders in the template at `path`
+/// from `vars` and prints the validated result as canonical TOML.
+pub fn render_answer_template(
+    path: &str,
+    format: AnswerFormat,
+    vars: &BTreeMap<String, String>,
+) -> Result<(), CommandError> {
+    let template = read_answer(path)?;
+    let answer = AnswerFile::from_template_str(&template, vars, format).map_err(|e| {
+        eprintln!("{}: {}", path, e);
+        CommandError
+    })?;
+
+    print_toml(path, &answer)
+}
+
+/// `answer generate`: writes one answer per host in the inventory at
+/// `inventory`, built from the base answer `template`, to
+/// `<out_dir>/<name>.<format>`.
+///
+/// # Notes
+/// - Every host is generated and validated before anything is written.
+/// - Existing files for the same hosts are replaced.
+pub fn generate_answer_files(
+    template: &str,
+    inventory: &str,
+    out_dir: &str,
+    format: AnswerFormat,
+) -> Result<(), CommandError> {
+    let base = read_answer(template)?;
+    let hosts = std::fs::read_to_string(inventory).map_err(|e| {
+        eprintln!("Failed to read {}: {}", inventory, e);
+        CommandError
+    })?;
+
+    let generated = Inventory::from_toml_str(&hosts)
+        .and_then(|inv| inv.generate(&base, format))
+        .map_err(|e| {
+            eprintln!("{}: {}", inventory, e);
+            CommandError
+        })?;
+
+    for host in &generated {
+        let out = Path::new(out_dir).join(format!("{}.{}", host.name, format));
+        write_answer(&out.to_string_lossy(), &host.answer, format, None)?;
+        println!("Wrote {}", out.display());
+    }
+    Ok(())
+}
+
+/// `answer diff`: prints the field-level changes from `old` to `new`.
+///
+/// # Returns
+/// `Ok(())` when both files are equivalent; like `diff(1)`, `Err(CommandError)`
+/// when they differ or either file cannot be loaded.
+pub fn diff_answer_files(old: &str, new: &str, format: AnswerFormat) -> Result<(), CommandError> {
+    let load = |path: &str| {
+        let contents = read_resolved_answer(path, format)?;
+        AnswerFile::from_str_as(&contents, format).map_err(|e| {
+            eprintln!("{}: {}", path, e);
+            CommandError
+        })
+    };
+    let changes = load(old)?.diff(&load(new)?);
+
+    if changes.is_empty() {
+        println!("no differences");
+        return Ok(());
+    }
+
+    for change in &changes {
+        println!("{}", change);
+    }
+    Err(CommandError)
+}
+
+/// `answer schema`: prints the answer format's JSON Schema.
+pub fn print_answer_schema() -> Result<(), CommandError> {
+    let schema = serde_json::to_string_pretty(&AnswerFile::json_schema()).map_err(|e| {
+        eprintln!("Failed to render schema: {}", e);
+        CommandError
+    })?;
+    println!("{}", schema);
+    Ok(())
+}
+
+/// `answer migrate`: upgrades the TOML answer at `path` from schema `from`
+/// (detected when `None`) to [`SchemaVersion::CURRENT`], rewriting it in place.
+///
+/// The migrated file is validated before anything is written.
+pub fn migrate_answer_file(path: &str, from: Option<SchemaVersion>) -> Result<(), CommandError> {
+    check_editable(path)?;
+    let contents = read_answer(path)?;
+    let from = from.unwrap_or_else(|| SchemaVersion::detect(&contents));
+
+    let migrated = AnswerFile::migrate_toml(&contents, from)
+        .and_then(|migrated| AnswerFile::from_toml_str(&migrated).map(|_| migrated))
+        .map_err(|e| {
+            eprintln!("{}: {}", path, e);
+            CommandError
+        })?;
+    write_atomic(Path::new(path), migrated.as_bytes(), false).map_err(|e| {
+        eprintln!("Failed to write {}: {}", path, e);
+        CommandError
+    })?;
+
+    println!(
+        "Migrated {} from schema {} to {}",
+        path,
+        from,
+        SchemaVersion::CURRENT
+    );
+    Ok(())
+}
+
+/// `answer convert-password`: replaces a plaintext `root-password` in the TOML
+/// answer at `path` with a `method` hash in `root-password-hashed`, rewriting
+/// the file in place.
+///
+/// # Returns
+/// `Err(CommandError)` when the file has no plaintext password, so scripts
+/// notice a no-op.
+pub fn convert_password(
+    path: &str,
+    method: PasswordHashFormat,
+    rounds: Option<u32>,
+) -> Result<(), CommandError> {
+    check_editable(path)?;
+    let contents = read_answer(path)?;
+
+    let converted = AnswerFile::hash_plaintext_password(&contents, method, rounds)
+        .map_err(|e| {
+            eprintln!("{}: {}", path, e);
+            CommandError
+        })?
+        .ok_or_else(|| {
+            eprintln!("{}: no plaintext root-password to convert", path);
+            CommandError
+        })?;
+    write_atomic(Path::new(path), converted.as_bytes(), false).map_err(|e| {
+        eprintln!("Failed to write {}: {}", path, e);
+        CommandError
+    })?;
+
+    println!("Hashed root-password in {} as {}", path, method);
+    Ok(())
+}
+
+/// `answer new`: writes a new answer file to `out`, interactively, from `preset`
+/// or from defaults.
+///
+/// # Notes
+/// - Refuses to overwrite an existing file.
+/// - The written file is always validated first.
+/// - With `encrypt` the file is written as an age file.
+pub fn new_answer_file(
+    out: &str,
+    interactive: bool,
+    preset: Option<AnswerPreset>,
+    format: AnswerFormat,
+    encrypt: Option<&EncryptMode>,
+) -> Result<(), CommandError> {
+    if Path::new(out).exists() {
+        eprintln!("Refusing to overwrite existing file: {}", out);
+        return Err(CommandError);
+    }
+    let recipients = encrypt.map(age_recipients).transpose()?;
+
+    let answer = if interactive {
+        run_wizard(&mut Prompter::stdio()).map_err(|e| {
+            eprintln!("Wizard aborted: {}", e);
+            CommandError
+        })?
+    } else {
+        preset.map(|p| p.answer()).unwrap_or_default()
+    };
+
+    write_answer(out, &answer, format, recipients.as_ref())?;
+    println!("Wrote {}", out);
+    Ok(())
+}
+
+/// Reads the answer at `path`; `@name` reads the stored profile `name` instead.
+pub(crate) fn read_answer(path: &str) -> Result<String, CommandError> {
+    read_answer_file(&resolve_answer_path(path)?, path)
+}
+
+/// The file behind a path argument: `@name` is the stored profile `name`.
+pub(crate) fn resolve_answer_path(path: &str) -> Result<PathBuf, CommandError> {
+    let resolved = ProfileStore::default().resolve(path).map_err(|e| {
+        eprintln!("{}: {}", path, e);
+        CommandError
+    })?;
+    Ok(resolved.unwrap_or_else(|| PathBuf::from(path)))
+}
+
+/// [`read_answer`], then resolves the `${env:NAME}` placeholders in the secret
+/// fields of a TOML answer from the environment.
+///
+/// Only for commands that do not write the answer back: the file on disk
+/// keeps its placeholders.
+pub(crate) fn read_resolved_answer(
+    path: &str,
+    format: AnswerFormat,
+) -> Result<String, CommandError> {
+    let contents = read_answer(path)?;
+    if format != AnswerFormat::Toml {
+        return Ok(contents);
+    }
+    AnswerFile::resolve_secrets(&contents).map_err(|e| {
+        eprintln!("{}: {}", path, e);
+        CommandError
+    })
+}
+
+/// Reads `file`, decrypting it when it is an age file; `label` names it in messages.
+///
+/// When `$PVEAUTO_TRUSTED_KEYS` is set, `file` must have a valid detached
+/// signature by one of those keys.
+pub(crate) fn read_answer_file(file: &Path, label: &str) -> Result<String, CommandError> {
+    let bytes = std::fs::read(file).map_err(|e| {
+        eprintln!("Failed to read {}: {}", label, e);
+        CommandError
+    })?;
+    verify_if_enforced(file, &bytes, label)?;
+    decode_answer(bytes, label)
+}
+
+/// Decrypts `bytes` when they are an age file, otherwise decodes them as UTF-8.
+///
+/// The identity comes from `$PVEAUTO_AGE_IDENTITY` / `$PVEAUTO_AGE_PASSPHRASE`,
+/// otherwise the passphrase is prompted for.
+pub(crate) fn decode_answer(bytes: Vec<u8>, label: &str) -> Result<String, CommandError> {
+    decrypt_if_encrypted(bytes, || {
+        AgeIdentity::from_env().or_else(|| {
+            rpassword::prompt_password(format!("Passphrase for {}: ", label))
+                .ok()
+                .map(AgeIdentity::passphrase)
+        })
+    })
+    .map_err(|e| {
+        eprintln!("{}: {}", label, e);
+        CommandError
+    })
+}
+
+/// True when the file at `path` exists and is age-encrypted.
+pub(crate) fn is_encrypted_file(path: &str) -> bool {
+    std::fs::read(path).is_ok_and(|bytes| is_encrypted(&bytes))
+}
+
+/// Resolves `--encrypt` / `--recipient` into age recipients, reading the
+/// passphrase from `$PVEAUTO_AGE_PASSPHRASE` or prompting for it twice.
+pub(crate) fn age_recipients(mode: &EncryptMode) -> Result<AgeRecipients, CommandError> {
+    match mode {
+        EncryptMode::Recipients(keys) => AgeRecipients::parse_keys(keys).map_err(|e| {
+            eprintln!("{}", e);
+            CommandError
+        }),
+        EncryptMode::Passphrase => match std::env::var(AGE_PASSPHRASE_ENV) {
+            Ok(passphrase) if !passphrase.is_empty() => Ok(AgeRecipients::passphrase(passphrase)),
+            _ => prompt_confirmed("Passphrase")
+                .map(AgeRecipients::passphrase)
+                .map_err(|e| {
+                    eprintln!("could not read passphrase: {}", e);
+                    CommandError
+                }),
+        },
+    }
+}
+
+/// Writes `answer` to `out` as `format`, age-encrypted for `encrypt_for` when given.
+pub(crate) fn write_answer(
+    out: &str,
+    answer: &AnswerFile,
+    format: AnswerFormat,
+    encrypt_for: Option<&AgeRecipients>,
+) -> Result<(), CommandError> {
+    let contents = answer.to_string_as(format).map_err(|e| {
+        eprintln!("Generated answer file is invalid: {}", e);
+        CommandError
+    })?;
+    let contents = match encrypt_for {
+        Some(to) => encrypt(&contents, to).map_err(|e| {
+            eprintln!("{}: {}", out, e);
+            CommandError
+        })?,
+        None => contents.into_bytes(),
+    };
+
+    write_atomic(Path::new(out), &contents, false).map_err(|e| {
+        eprintln!("Failed to write {}: {}", out, e);
+        CommandError
+    })
+}
+
+/// Reads the answer at `path` for an in-place edit, returning the source text
+/// and the parsed answer.
+///
+/// Profiles and encrypted files are refused: rewriting them in place would
+/// bypass the store or leak plaintext.
+pub(crate) fn read_editable_answer(
+    path: &str,
+    format: AnswerFormat,
+) -> Result<(String, AnswerFile), CommandError> {
+    check_editable(path)?;
+
+    let contents = read_answer(path)?;
+    let answer = AnswerFile::from_str_as(&contents, format).map_err(|e| {
+        eprintln!("{}: {}", path, e);
+        CommandError
+    })?;
+    Ok((contents, answer))
+}
+
+/// Refuses profiles and encrypted files: rewriting them in place would
+/// bypass the store or leak plaintext.
+fn check_editable(path: &str) -> Result<(), CommandError> {
+    if path.starts_with(PROFILE_REFERENCE_PREFIX) {
+        eprintln!("Profiles cannot be edited in place: {}", path);
+        return Err(CommandError);
+    }
+    if is_encrypted_file(path) {
+        eprintln!("Encrypted answer files cannot be edited in place: {}", path);
+        return Err(CommandError);
+    }
+    Ok(())
+}
+
+/// Validates `answer` and writes it back to `path`; TOML keeps the comments
+/// and layout of `original`, the text returned by [`read_editable_answer`].
+pub(crate) fn write_edited_answer(
+    path: &str,
+    original: &str,
+    answer: AnswerFile,
+    format: AnswerFormat,
+) -> Result<(), CommandError> {
+    if format != AnswerFormat::Toml {
+        return write_answer(path, &answer, format, None);
+    }
+
+    let edited = AnswerFile::edit_in_place(original, |a| *a = answer).map_err(|e| {
+        eprintln!("{}: {}", path, e);
+        CommandError
+    })?;
+    write_atomic(Path::new(path), edited.as_bytes(), false).map_err(|e| {
+        eprintln!("Failed to write {}: {}", path, e);
+        CommandError
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!("pveauto-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn valid_file_passes() {
+        let path = write_temp(
+            "valid.toml",
+            "[global]\n[network]\n[disk-setup]\ndisk-list = [\"sda\"]\n",
+        );
+        assert_eq!(
+            validate_answer_file(&path, AnswerFormat::Toml, false, false),
+            Ok(())
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn strict_rejects_unknown_keys() {
+        let path = write_temp(
+            "typo.toml",
+            "[global]\nrebot-mode = \"power-off\"\n[network]\n[disk-setup]\ndisk-list = [\"sda\"]\n",
+        );
+        assert_eq!(
+            validate_answer_file(&path, AnswerFormat::Toml, false, false),
+            Ok(())
+        );
+        assert_eq!(
+            validate_answer_file(&path, AnswerFormat::Toml, true, false),
+            Err(CommandError)
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn invalid_file_fails() {
+        let path = write_temp(
+            "invalid.toml",
+            "[global]\nfqdn = \"nope\"\n[network]\n[disk-setup]\ndisk-list = [\"sda\"]\n",
+        );
+        assert_eq!(
+            validate_answer_file(&path, AnswerFormat::Toml, false, false),
+            Err(CommandError)
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn unset_secret_variables_fail_validation() {
+        let path = write_temp(
+            "secrets.toml",
+            "[global]\nroot-password-hashed = \"${env:PVEAUTO_TEST_UNSET_HASH}\"\n[network]\n[disk-setup]\ndisk-list = [\"sda\"]\n",
+        );
+        assert_eq!(
+            read_resolved_answer(&path, AnswerFormat::Toml),
+            Err(CommandError)
+        );
+        assert_eq!(
+            validate_answer_file(&path, AnswerFormat::Toml, false, false),
+            Err(CommandError)
+        );
+        assert!(read_resolved_answer(&path, AnswerFormat::Json).is_ok());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn lint_fails_on_warnings_unless_allowed() {
+        let path = write_temp(
+            "lint.toml",
+            &AnswerFile::default().to_toml_string().unwrap(),
+        );
+        assert_eq!(
+            lint_answer_file(&path, AnswerFormat::Toml, &[]),
+            Err(CommandError)
+        );
+        assert_eq!(
+            lint_answer_file(&path, AnswerFormat::Toml, &LintWarning::ALL),
+            Ok(())
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn lint_reports_invalid_files() {
+        let path = write_temp(
+            "lint-invalid.toml",
+            "[global]\nfqdn = \"nope\"\n[network]\n[disk-setup]\ndisk-list = [\"sda\"]\n",
+        );
+        assert_eq!(
+            lint_answer_file(&path, AnswerFormat::Toml, &LintWarning::ALL),
+            Err(CommandError)
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn missing_file_fails() {
+        assert_eq!(
+            validate_answer_file("/nonexistent/answer.toml", AnswerFormat::Toml, false, false),
+            Err(CommandError)
+        );
+    }
+
+    #[test]
+    fn new_answer_file_writes_defaults_and_refuses_overwrite() {
+        let path = std::env::temp_dir().join(format!("pveauto-{}-new.toml", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(
+            new_answer_file(&path, false, None, AnswerFormat::Toml, None),
+            Ok(())
+        );
+        assert_eq!(
+            validate_answer_file(&path, AnswerFormat::Toml, false, false),
+            Ok(())
+        );
+        assert_eq!(
+            new_answer_file(&path, false, None, AnswerFormat::Toml, None),
+            Err(CommandError)
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn new_answer_file_writes_preset() {
+        let path = std::env::temp_dir().join(format!("pveauto-{}-preset.toml", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        let _ = std::fs::remove_file(&path);
+
+        let preset = AnswerPreset::LabZfsMirror;
+        assert_eq!(
+            new_answer_file(&path, false, Some(preset), AnswerFormat::Toml, None),
+            Ok(())
+        );
+        let written = AnswerFile::from_toml_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written, preset.answer());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn encrypted_answer_files_are_age_files() {
+        use crate::answer_file::encryption::decrypt;
+        use age::{secrecy::ExposeSecret, x25519};
+
+        let dir = std::env::temp_dir().join(format!("pveauto-{}-encrypted", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let out = dir.join("answer.toml.age").to_string_lossy().into_owned();
+        let key = dir.join("key.txt");
+
+        let identity = x25519::Identity::generate();
+        let mode = EncryptMode::Recipients(vec![identity.to_public().to_string()]);
+        assert_eq!(
+            new_answer_file(&out, false, None, AnswerFormat::Toml, Some(&mode)),
+            Ok(())
+        );
+        assert!(is_encrypted_file(&out));
+
+        std::fs::write(&key, identity.to_string().expose_secret()).unwrap();
+        let toml = decrypt(&std::fs::read(&out).unwrap(), &AgeIdentity::file(&key)).unwrap();
+        assert_eq!(
+            AnswerFile::from_toml_str(&toml).unwrap(),
+            AnswerFile::default()
+        );
+
+        let bad = EncryptMode::Recipients(vec!["not-a-key".into()]);
+        let other = dir.join("other.toml.age").to_string_lossy().into_owned();
+        assert_eq!(
+            new_answer_file(&other, false, None, AnswerFormat::Toml, Some(&bad)),
+            Err(CommandError)
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn json_files_validate_and_convert() {
+        let path = std::env::temp_dir().join(format!("pveauto-{}-new.json", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(
+            new_answer_file(&path, false, None, AnswerFormat::Json, None),
+            Ok(())
+        );
+        assert_eq!(
+            validate_answer_file(&path, AnswerFormat::Json, false, false),
+            Ok(())
+        );
+        assert_eq!(convert_answer_file(&path, AnswerFormat::Json), Ok(()));
+        assert_eq!(
+            validate_answer_file(&path, AnswerFormat::Toml, false, false),
+            Err(CommandError)
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn templates_render_with_variables() {
+        let path = write_temp(
+            "template.toml",
+            "[global]\nfqdn = \"{{hostname}}.lab.local\"\n[network]\n[disk-setup]\ndisk-list = [\"sda\"]\n",
+        );
+        let vars = BTreeMap::from([("hostname".to_string(), "pve01".to_string())]);
+
+        assert_eq!(
+            render_answer_template(&path, AnswerFormat::Toml, &vars),
+            Ok(())
+        );
+        assert_eq!(
+            render_answer_template(&path, AnswerFormat::Toml, &BTreeMap::new()),
+            Err(CommandError)
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn generate_writes_one_answer_per_host() {
+        let dir = std::env::temp_dir().join(format!("pveauto-{}-generated", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let out_dir = dir.to_string_lossy().into_owned();
+
+        let template = write_temp(
+            "generate-base.toml",
+            &AnswerFile::default().to_toml_string().unwrap(),
+        );
+        let inventory = write_temp(
+            "hosts.toml",
+            "fqdn = \"pve-{mac6}.lab.local\"\n\n[[host]]\nmac = \"bc:24:11:0a:00:01\"\n\n[[host]]\nname = \"storage01\"\nmac = \"bc:24:11:0a:00:02\"\ndisk-list = [\"nvme0n1\"]\n",
+        );
+
+        assert_eq!(
+            generate_answer_files(&template, &inventory, &out_dir, AnswerFormat::Toml),
+            Ok(())
+        );
+        let first = dir.join("pve-0a0001.lab.local.toml");
+        assert_eq!(
+            validate_answer_file(&first.to_string_lossy(), AnswerFormat::Toml, false, false),
+            Ok(())
+        );
+        let storage = AnswerFile::from_toml_str(
+            &std::fs::read_to_string(dir.join("storage01.toml")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(storage.global.fqdn, "pve-0a0002.lab.local");
+
+        std::fs::write(&inventory, "[[host]]\nfqdn = \"nope\"\n").unwrap();
+        assert_eq!(
+            generate_answer_files(&template, &inventory, &out_dir, AnswerFormat::Toml),
+            Err(CommandError)
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+        for path in [template, inventory] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn diff_reports_differences_as_failure() {
+        let base = AnswerFile::default().to_toml_string().unwrap();
+        let old = write_temp("diff-old.toml", &base);
+        let same = write_temp("diff-same.toml", &base);
+        let new = write_temp(
+            "diff-new.toml",
+            &base.replace("reboot-mode = \"reboot\"", "reboot-mode = \"power-off\""),
+        );
+
+        assert_eq!(diff_answer_files(&old, &same, AnswerFormat::Toml), Ok(()));
+        assert_eq!(
+            diff_answer_files(&old, &new, AnswerFormat::Toml),
+            Err(CommandError)
+        );
+        for path in [old, same, new] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn migrate_rewrites_old_keys_in_place() {
+        let path = write_temp(
+            "migrate.toml",
+            "[global]\nfqdn = \"pve01.lab.local\"\nreboot_on_error = true\n[network]\n[disk-setup]\ndisk_list = [\"sda\"]\n",
+        );
+
+        assert_eq!(
+            validate_answer_file(&path, AnswerFormat::Toml, false, false),
+            Err(CommandError)
+        );
+        assert_eq!(migrate_answer_file(&path, None), Ok(()));
+
+        let migrated = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            migrated.starts_with("# pveauto-schema = 2\n"),
+            "{}",
+            migrated
+        );
+        assert!(migrated.contains("disk-list = [\"sda\"]"), "{}", migrated);
+        assert_eq!(
+            validate_answer_file(&path, AnswerFormat::Toml, false, false),
+            Ok(())
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn convert_password_hashes_plaintext_in_place() {
+        let path = write_temp(
+            "plaintext.toml",
+            "[global]\nfqdn = \"pve01.lab.local\"\nroot-password = \"hunter2\"\n[network]\n[disk-setup]\ndisk-list = [\"sda\"]\n",
+        );
+
+        assert_eq!(
+            validate_answer_file(&path, AnswerFormat::Toml, false, false),
+            Err(CommandError)
+        );
+        assert_eq!(
+            convert_password(&path, PasswordHashFormat::Sha512, Some(100_000)),
+            Ok(())
+        );
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("hunter2"));
+        assert_eq!(
+            validate_answer_file(&path, AnswerFormat::Toml, false, false),
+            Ok(())
+        );
+        assert_eq!(
+            convert_password(&path, PasswordHashFormat::Sha512, None),
+            Err(CommandError)
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
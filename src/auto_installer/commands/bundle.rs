@@ -0,0 +1,122 @@
+use crate::auto_installer::commands::constants::CommandError;
+use crate::iso::{
+    bundle::{BundleContents, import_bundle, write_bundle},
+    constants::ISO_INDEX_URL,
+    pgp::parse_public_keys,
+    verify::{fetch_release_keyring, fetch_signed_checksums, pinned_keys, verify_iso_with},
+};
+use std::path::{Path, PathBuf};
+
+/// `bundle export`: checks the ISO at `iso` against the strongest signed
+/// checksum file published by Proxmox, as `iso verify` does, then packs it
+/// with the `answers`, the checksum file, its signature and the keyring into
+/// the bundle `out` for `bundle import` on the air-gapped side.
+///
+/// # Returns
+/// `Err(CommandError)` when the ISO does not verify or the bundle cannot be
+/// written.
+pub async fn export_bundle(
+    iso: &str,
+    answers: &[String],
+    key: Option<&str>,
+    out: &str,
+) -> Result<(), CommandError> {
+    let fail = |e: &dyn std::fmt::Display| {
+        eprintln!("{}: {}", iso, e);
+        CommandError
+    };
+    println!(
+        "Verifying {} against the checksums at {}",
+        iso, ISO_INDEX_URL
+    );
+    let keyring = match key {
+        Some(path) => std::fs::read(path).map_err(|e| {
+            eprintln!("Failed to read {}: {}", path, e);
+            CommandError
+        })?,
+        None => fetch_release_keyring().await.map_err(|e| fail(&e))?,
+    };
+    let keys = match key {
+        Some(_) => parse_public_keys(&keyring).map_err(|e| fail(&e))?,
+        None => pinned_keys(&keyring).map_err(|e| fail(&e))?,
+    };
+    let checksums = fetch_signed_checksums().await.map_err(|e| fail(&e))?;
+    let verified = verify_iso_with(
+        Path::new(iso),
+        checksums.algorithm,
+        &checksums.sums,
+        &checksums.signature,
+        &keys,
+    )
+    .map_err(|e| fail(&e))?;
+    println!("Good signature by {}", verified.signer);
+
+    let answers: Vec<PathBuf> = answers.iter().map(PathBuf::from).collect();
+    let manifest = write_bundle(
+        Path::new(out),
+        &BundleContents {
+            iso: Path::new(iso),
+            verified: &verified,
+            checksums: &checksums,
+            keyring: &keyring,
+            answers: &answers,
+        },
+    )
+    .map_err(|e| {
+        eprintln!("{}: {}", out, e);
+        CommandError
+    })?;
+
+    println!(
+        "Wrote {}: {}, {} answer file(s), {} and its signature",
+        out,
+        manifest.release,
+        answers.len(),
+        checksums.algorithm.sums_file()
+    );
+    Ok(())
+}
+
+/// `bundle import`: unpacks the bundle at `bundle` into `dir` and checks
+/// every file against its manifest and the ISO against the signed checksum
+/// file, with the keyring file `key` or, when not given, the bundled keyring
+/// holding the pinned Proxmox release key. Nothing is downloaded.
+///
+/// # Returns
+/// `Err(CommandError)` when a check fails; the unpacked files are removed.
+pub fn import_bundle_command(
+    bundle: &str,
+    dir: &str,
+    key: Option<&str>,
+) -> Result<(), CommandError> {
+    let keyring = key
+        .map(|path| {
+            std::fs::read(path).map_err(|e| {
+                eprintln!("Failed to read {}: {}", path, e);
+                CommandError
+            })
+        })
+        .transpose()?;
+    let imported =
+        import_bundle(Path::new(bundle), Path::new(dir), keyring.as_deref()).map_err(|e| {
+            eprintln!("{}: {}", bundle, e);
+            CommandError
+        })?;
+
+    println!(
+        "Checked {} files of {} against its manifest",
+        imported.manifest.files.len(),
+        bundle
+    );
+    println!("Good signature by {}", imported.verified.signer);
+    println!(
+        "{}: OK ({}, listed in {})",
+        imported.iso.display(),
+        imported.verified.file_name,
+        imported.verified.algorithm.sums_file()
+    );
+    for answer in &imported.answers {
+        println!("{}: OK", answer.display());
+    }
+    Ok(())
+}
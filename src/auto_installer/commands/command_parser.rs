@@ -1,5 +1,21 @@
-use crate::auto_installer::commands::constants::{CommandParseError, Commands, USAGE};
-use std::env;
+use crate::answer_file::{
+    AnswerFormat, AnswerPreset, LintWarning, encryption::encrypted_path,
+    sections::PasswordHashFormat, template::parse_variable,
+};
+use crate::auto_installer::commands::constants::{
+    AnswerCommand, BundleCommand, CommandParseError, Commands, EncryptMode, InventoryBuild,
+    IsoCommand, IsoType, ProfileCommand, SshKeySource, USAGE,
+};
+use crate::iso::{
+    alternates::DownloadVia,
+    channel::IsoChannel,
+    constants::{
+        AUTOINSTALL_ISO_FILE_NAME, INVENTORY_ISO_FILE_NAME, NETWORK_AUTOINSTALL_ISO_FILE_NAME,
+    },
+    scraper::version_key,
+    writer::IsoBackend,
+};
+use std::{collections::BTreeMap, env};
 
 /// Parse command from CLI arguments
 pub fn parse_command() -> Result<Commands, CommandParseError> {
@@ -16,13 +32,16 @@ where
             println!("{}", USAGE);
             Err(CommandParseError)
         }
-        Some("download") => {
-            let dest_path = args.next().unwrap_or_else(Commands::default_download_path);
-
-            Ok(Commands::Download {
-                dest_path: Some(dest_path),
-            })
-        }
+        Some("download") => parse_download_command(args),
+        Some("offline-installer") => parse_offline_installer_command(args),
+        Some("network-installer") => parse_network_installer_command(args),
+        Some("iso") => parse_iso_command(args),
+        Some("verify") => parse_verify_command(args),
+        Some("bundle") => parse_bundle_command(args),
+        Some("answer") => parse_answer_command(args),
+        Some("hash-password") => parse_hash_password_command(args),
+        Some("generate-ssh-key") => parse_generate_ssh_key_command(args),
+        Some("flash") => parse_flash_command(args),
         Some(cmd) => cmd.parse::<Commands>(),
         None => {
             eprintln!("{}", USAGE);
@@ -31,10 +50,829 @@ where
     }
 }
 
+/// Parse `answer <subcommand> ...`
+fn parse_answer_command<I>(mut args: I) -> Result<Commands, CommandParseError>
+where
+    I: Iterator<Item = String>,
+{
+    let (usage, subcommand) = match args.next().as_deref() {
+        Some("profile") => return parse_profile_command(args),
+        Some("schema") => {
+            return match args.next() {
+                None => Ok(Commands::Answer(AnswerCommand::Schema)),
+                Some(_) => {
+                    eprintln!("Usage: pveauto answer schema");
+                    Err(CommandParseError)
+                }
+            };
+        }
+        Some("validate") => (
+            "validate [--format toml|json|yaml] [--strict] [--assistant] <path>",
+            "validate",
+        ),
+        Some("lint") => (
+            "lint [--format toml|json|yaml] [--allow code]... <path>",
+            "lint",
+        ),
+        Some("new") => (
+            "new [--interactive | --preset name] [--format toml|json|yaml] [--encrypt] [--recipient age1...]... [path]",
+            "new",
+        ),
+        Some("convert") => ("convert [--format toml|json|yaml] <path>", "convert"),
+        Some("render") => (
+            "render [--format toml|json|yaml] <template> [--var key=value]...",
+            "render",
+        ),
+        Some("generate") => (
+            "generate [--format toml|json|yaml] --inventory <hosts.toml> --out-dir <dir> <template>",
+            "generate",
+        ),
+        Some("diff") => ("diff [--format toml|json|yaml] <old> <new>", "diff"),
+        Some("migrate") => ("migrate [--from 1|2] <path>", "migrate"),
+        Some("convert-password") => (
+            "convert-password [--method sha-512|sha-256|yescrypt] [--rounds N] <path>",
+            "convert-password",
+        ),
+        Some("sign") => ("sign --key <private key> <path>", "sign"),
+        Some("verify") => ("verify [--trusted-keys <file>] <path>", "verify"),
+        Some("fetch") => (
+            "fetch [--format toml|json|yaml] [--save name] <url>",
+            "fetch",
+        ),
+        Some("add-ssh-keys") => (
+            "add-ssh-keys [--format toml|json|yaml] (--github user | --gitlab user | --file path | --local | --agent)... <path>",
+            "add-ssh-keys",
+        ),
+        _ => {
+            eprintln!("{}", USAGE);
+            return Err(CommandParseError);
+        }
+    };
+
+    let usage_error = || {
+        eprintln!("Usage: pveauto answer {}", usage);
+        CommandParseError
+    };
+
+    let mut interactive = false;
+    let mut format = AnswerFormat::default();
+    let mut path = None;
+    let mut other = None;
+    let mut vars = BTreeMap::new();
+    let mut encrypt = false;
+    let mut recipients = Vec::new();
+    let mut save = None;
+    let mut allow = Vec::new();
+    let mut strict = false;
+    let mut assistant = false;
+    let mut preset = None;
+    let mut sources = Vec::new();
+    let mut from = None;
+    let mut method = PasswordHashFormat::default();
+    let mut rounds = None;
+    let mut inventory = None;
+    let mut out_dir = None;
+    let mut key = None;
+    let mut trusted_keys = None;
+    // These rewrite or check files in place and have no output format.
+    let takes_format = !matches!(
+        subcommand,
+        "migrate" | "convert-password" | "sign" | "verify"
+    );
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-i" | "--interactive" if subcommand == "new" => interactive = true,
+            "--strict" if subcommand == "validate" => strict = true,
+            "--assistant" if subcommand == "validate" => assistant = true,
+            "--allow" if subcommand == "lint" => allow.push(
+                args.next()
+                    .and_then(|code| LintWarning::from_code(&code))
+                    .ok_or_else(usage_error)?,
+            ),
+            "--github" if subcommand == "add-ssh-keys" => {
+                sources.push(SshKeySource::GitHub(args.next().ok_or_else(usage_error)?))
+            }
+            "--gitlab" if subcommand == "add-ssh-keys" => {
+                sources.push(SshKeySource::GitLab(args.next().ok_or_else(usage_error)?))
+            }
+            "--file" if subcommand == "add-ssh-keys" => {
+                sources.push(SshKeySource::File(args.next().ok_or_else(usage_error)?))
+            }
+            "--local" if subcommand == "add-ssh-keys" => sources.push(SshKeySource::LocalKeys),
+            "--agent" if subcommand == "add-ssh-keys" => sources.push(SshKeySource::Agent),
+            "--from" if subcommand == "migrate" => {
+                from = Some(
+                    args.next()
+                        .and_then(|v| v.parse().ok())
+                        .ok_or_else(usage_error)?,
+                )
+            }
+            "--method" if subcommand == "convert-password" => {
+                method = args
+                    .next()
+                    .and_then(|m| m.parse().ok())
+                    .ok_or_else(usage_error)?;
+            }
+            "--rounds" if subcommand == "convert-password" => {
+                rounds = Some(
+                    args.next()
+                        .and_then(|n| n.parse().ok())
+                        .ok_or_else(usage_error)?,
+                );
+            }
+            "--key" if subcommand == "sign" => key = Some(args.next().ok_or_else(usage_error)?),
+            "--trusted-keys" if subcommand == "verify" => {
+                trusted_keys = Some(args.next().ok_or_else(usage_error)?)
+            }
+            "--save" if subcommand == "fetch" => save = Some(args.next().ok_or_else(usage_error)?),
+            "--preset" if subcommand == "new" => {
+                let name = args.next().ok_or_else(usage_error)?;
+                preset = Some(name.parse::<AnswerPreset>().map_err(|_| {
+                    eprintln!(
+                        "Unknown preset: {} (one of: {})",
+                        name,
+                        AnswerPreset::ALL.map(|p| p.as_str()).join(", ")
+                    );
+                    CommandParseError
+                })?);
+            }
+            "--encrypt" if subcommand == "new" => encrypt = true,
+            "--recipient" if subcommand == "new" => {
+                recipients.push(args.next().ok_or_else(usage_error)?)
+            }
+            "--var" if subcommand == "render" => {
+                let (name, value) = args
+                    .next()
+                    .and_then(|pair| parse_variable(&pair).ok())
+                    .ok_or_else(usage_error)?;
+                vars.insert(name, value);
+            }
+            "--inventory" if subcommand == "generate" => {
+                inventory = Some(args.next().ok_or_else(usage_error)?)
+            }
+            "--out-dir" if subcommand == "generate" => {
+                out_dir = Some(args.next().ok_or_else(usage_error)?)
+            }
+            "--format" if takes_format => {
+                format = args
+                    .next()
+                    .and_then(|f| f.parse().ok())
+                    .ok_or_else(usage_error)?;
+            }
+            _ if takes_format && arg.starts_with("--format=") => {
+                format = arg["--format=".len()..]
+                    .parse()
+                    .map_err(|_| usage_error())?;
+            }
+            _ if path.is_none() && !arg.starts_with('-') => path = Some(arg),
+            _ if subcommand == "diff" && other.is_none() && !arg.starts_with('-') => {
+                other = Some(arg)
+            }
+            _ => return Err(usage_error()),
+        }
+    }
+
+    let command = match subcommand {
+        "validate" => AnswerCommand::Validate {
+            path: path.ok_or_else(usage_error)?,
+            format,
+            strict,
+            assistant,
+        },
+        "lint" => AnswerCommand::Lint {
+            path: path.ok_or_else(usage_error)?,
+            format,
+            allow,
+        },
+        "convert" => AnswerCommand::Convert {
+            path: path.ok_or_else(usage_error)?,
+            format,
+        },
+        "diff" => AnswerCommand::Diff {
+            old: path.ok_or_else(usage_error)?,
+            new: other.ok_or_else(usage_error)?,
+            format,
+        },
+        "migrate" => AnswerCommand::Migrate {
+            path: path.ok_or_else(usage_error)?,
+            from,
+        },
+        "convert-password" => AnswerCommand::ConvertPassword {
+            path: path.ok_or_else(usage_error)?,
+            method,
+            rounds,
+        },
+        "sign" => AnswerCommand::Sign {
+            path: path.ok_or_else(usage_error)?,
+            key: key.ok_or_else(usage_error)?,
+        },
+        "verify" => AnswerCommand::Verify {
+            path: path.ok_or_else(usage_error)?,
+            trusted_keys,
+        },
+        "fetch" => AnswerCommand::Fetch {
+            url: path.ok_or_else(usage_error)?,
+            format,
+            save,
+        },
+        "add-ssh-keys" if sources.is_empty() => return Err(usage_error()),
+        "add-ssh-keys" => AnswerCommand::AddSshKeys {
+            path: path.ok_or_else(usage_error)?,
+            format,
+            sources,
+        },
+        "render" => AnswerCommand::Render {
+            path: path.ok_or_else(usage_error)?,
+            format,
+            vars,
+        },
+        "generate" => AnswerCommand::Generate {
+            template: path.ok_or_else(usage_error)?,
+            inventory: inventory.ok_or_else(usage_error)?,
+            out_dir: out_dir.ok_or_else(usage_error)?,
+            format,
+        },
+        _ => {
+            if interactive && preset.is_some() {
+                return Err(usage_error());
+            }
+            let encrypt = encrypt_mode(encrypt, recipients);
+            let out = path.unwrap_or_else(|| format!("answer.{}", format));
+            AnswerCommand::New {
+                interactive,
+                preset,
+                out: match encrypt {
+                    Some(_) => encrypted_path(&out),
+                    None => out,
+                },
+                format,
+                encrypt,
+            }
+        }
+    };
+
+    Ok(Commands::Answer(command))
+}
+
+/// `--recipient` keys select public-key encryption, else `--encrypt` selects a passphrase.
+fn encrypt_mode(encrypt: bool, recipients: Vec<String>) -> Option<EncryptMode> {
+    if !recipients.is_empty() {
+        Some(EncryptMode::Recipients(recipients))
+    } else if encrypt {
+        Some(EncryptMode::Passphrase)
+    } else {
+        None
+    }
+}
+
+/// Parse `offline-installer [--format toml|json|yaml] [--base-iso <iso>] [--out <out>] [--deb <package.deb>]... [--assets <manifest>] [--kernel-args <args>]... [--volume-label <label>] [--backend auto|native|xorriso] [--dry-run] [--smoke-test] [--sign-key <key>] [--inventory <hosts.toml> --out-dir <dir>] <answer>`
+fn parse_offline_installer_command<I>(mut args: I) -> Result<Commands, CommandParseError>
+where
+    I: Iterator<Item = String>,
+{
+    let usage_error = || {
+        eprintln!(
+            "Usage: pveauto offline-installer [--format toml|json|yaml] [--base-iso <iso>] [--out <out>] [--deb <package.deb>]... [--assets <manifest>] [--kernel-args <args>]... [--volume-label <label>] [--backend auto|native|xorriso] [--dry-run] [--smoke-test] [--sign-key <key>] [--inventory <hosts.toml> --out-dir <dir>] <answer>"
+        );
+        CommandParseError
+    };
+
+    let mut format = AnswerFormat::default();
+    let mut iso = None;
+    let mut out = None;
+    let mut packages = Vec::new();
+    let mut assets = None;
+    let mut kernel_args = Vec::new();
+    let mut volume_label = None;
+    let mut backend = IsoBackend::default();
+    let mut dry_run = false;
+    let mut smoke_test = false;
+    let mut sign_key = None;
+    let mut inventory = None;
+    let mut out_dir = None;
+    let mut positional = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = args
+                    .next()
+                    .and_then(|f| f.parse().ok())
+                    .ok_or_else(usage_error)?;
+            }
+            "--base-iso" | "--iso" => iso = Some(args.next().ok_or_else(usage_error)?),
+            "--out" => out = Some(args.next().ok_or_else(usage_error)?),
+            "--deb" => packages.push(args.next().ok_or_else(usage_error)?),
+            "--assets" => assets = Some(args.next().ok_or_else(usage_error)?),
+            "--kernel-args" => {
+                let value = args.next().ok_or_else(usage_error)?;
+                kernel_args.extend(value.split_whitespace().map(str::to_owned));
+            }
+            "--volume-label" => volume_label = Some(args.next().ok_or_else(usage_error)?),
+            "--backend" => {
+                backend = args
+                    .next()
+                    .and_then(|b| b.parse().ok())
+                    .ok_or_else(usage_error)?;
+            }
+            "--dry-run" => dry_run = true,
+            "--smoke-test" => smoke_test = true,
+            "--sign-key" => sign_key = Some(args.next().ok_or_else(usage_error)?),
+            "--inventory" => inventory = Some(args.next().ok_or_else(usage_error)?),
+            "--out-dir" => out_dir = Some(args.next().ok_or_else(usage_error)?),
+            _ if arg.starts_with('-') => return Err(usage_error()),
+            _ => positional.push(arg),
+        }
+    }
+
+    let [answer]: [String; 1] = positional.try_into().map_err(|_| usage_error())?;
+    let inventory = match (inventory, out_dir) {
+        (Some(inventory), Some(out_dir)) => Some(InventoryBuild {
+            inventory,
+            out_dir,
+            file_name: out.take().unwrap_or_else(|| INVENTORY_ISO_FILE_NAME.into()),
+        }),
+        (None, None) => None,
+        _ => return Err(usage_error()),
+    };
+    Ok(Commands::AutoInstaller(IsoType::Offline {
+        answer,
+        format,
+        iso: iso.unwrap_or_else(Commands::default_download_path),
+        out: out.unwrap_or_else(|| AUTOINSTALL_ISO_FILE_NAME.into()),
+        packages,
+        assets,
+        kernel_args,
+        volume_label,
+        backend,
+        dry_run,
+        smoke_test,
+        sign_key,
+        inventory,
+    }))
+}
+
+/// Parse `network-installer [--base-iso <iso>] [--out <out>] [--url <url>] [--cert-fingerprint <sha256>] [--kernel-args <args>]... [--volume-label <label>] [--backend auto|native|xorriso] [--dry-run] [--smoke-test] [--sign-key <key>]`
+fn parse_network_installer_command<I>(mut args: I) -> Result<Commands, CommandParseError>
+where
+    I: Iterator<Item = String>,
+{
+    let usage_error = || {
+        eprintln!(
+            "Usage: pveauto network-installer [--base-iso <iso>] [--out <out>] [--url <url>] [--cert-fingerprint <sha256>] [--kernel-args <args>]... [--volume-label <label>] [--backend auto|native|xorriso] [--dry-run] [--smoke-test] [--sign-key <key>]"
+        );
+        CommandParseError
+    };
+
+    let mut iso = None;
+    let mut out = None;
+    let mut url = None;
+    let mut cert_fingerprint = None;
+    let mut kernel_args = Vec::new();
+    let mut volume_label = None;
+    let mut backend = IsoBackend::default();
+    let mut dry_run = false;
+    let mut smoke_test = false;
+    let mut sign_key = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--base-iso" | "--iso" => iso = Some(args.next().ok_or_else(usage_error)?),
+            "--out" => out = Some(args.next().ok_or_else(usage_error)?),
+            "--url" => url = Some(args.next().ok_or_else(usage_error)?),
+            "--cert-fingerprint" => cert_fingerprint = Some(args.next().ok_or_else(usage_error)?),
+            "--kernel-args" => {
+                let value = args.next().ok_or_else(usage_error)?;
+                kernel_args.extend(value.split_whitespace().map(str::to_owned));
+            }
+            "--volume-label" => volume_label = Some(args.next().ok_or_else(usage_error)?),
+            "--backend" => {
+                backend = args
+                    .next()
+                    .and_then(|b| b.parse().ok())
+                    .ok_or_else(usage_error)?;
+            }
+            "--dry-run" => dry_run = true,
+            "--smoke-test" => smoke_test = true,
+            "--sign-key" => sign_key = Some(args.next().ok_or_else(usage_error)?),
+            _ => return Err(usage_error()),
+        }
+    }
+
+    Ok(Commands::AutoInstaller(IsoType::Network {
+        iso: iso.unwrap_or_else(Commands::default_download_path),
+        out: out.unwrap_or_else(|| NETWORK_AUTOINSTALL_ISO_FILE_NAME.into()),
+        url,
+        cert_fingerprint,
+        kernel_args,
+        volume_label,
+        backend,
+        dry_run,
+        smoke_test,
+        sign_key,
+    }))
+}
+
+/// Parse `download [--version <version>] [--mirror <url>] [--channel <channel>] [--via <source>] [--quiet] [path]`
+fn parse_download_command<I>(mut args: I) -> Result<Commands, CommandParseError>
+where
+    I: Iterator<Item = String>,
+{
+    let usage_error = || {
+        eprintln!(
+            "Usage: pveauto download [--version <version>] [--mirror <url>] [--channel <stable|test>] [--via <direct|metalink|torrent>] [--quiet] [--stdout | path]"
+        );
+        CommandParseError
+    };
+
+    let mut version = None;
+    let mut mirror = None;
+    let mut channel = IsoChannel::Stable;
+    let mut via = DownloadVia::Direct;
+    let mut quiet = false;
+    let mut stdout = false;
+    let mut dest_path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--version" => {
+                version = Some(
+                    args.next()
+                        .filter(|v| version_key(v).is_some())
+                        .ok_or_else(usage_error)?,
+                );
+            }
+            "--mirror" => mirror = Some(args.next().ok_or_else(usage_error)?),
+            "--channel" => {
+                channel = args
+                    .next()
+                    .and_then(|c| c.parse().ok())
+                    .ok_or_else(usage_error)?;
+            }
+            "--via" => {
+                via = args
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(usage_error)?;
+            }
+            "--quiet" | "-q" => quiet = true,
+            "--stdout" => stdout = true,
+            _ if arg.starts_with('-') || dest_path.is_some() => return Err(usage_error()),
+            _ => dest_path = Some(arg),
+        }
+    }
+
+    // A beta is only fetched from the test channel, and a release from the
+    // stable one.
+    if version.as_deref().is_some_and(|v| !channel.includes(v)) {
+        return Err(usage_error());
+    }
+    if stdout {
+        if dest_path.is_some() {
+            return Err(usage_error());
+        }
+        return Ok(Commands::Download {
+            dest_path: None,
+            version,
+            mirror,
+            channel,
+            via,
+            quiet,
+            stdout,
+        });
+    }
+    let dest_path = dest_path.unwrap_or_else(|| match &version {
+        Some(version) => Commands::default_version_download_path(version),
+        None => Commands::default_channel_download_path(channel),
+    });
+    Ok(Commands::Download {
+        dest_path: Some(dest_path),
+        version,
+        mirror,
+        channel,
+        via,
+        quiet,
+        stdout,
+    })
+}
+
+/// Parse `iso list-versions [--mirror <url>] [--channel <channel>]`, `iso verify [--key <keyring>] [path]`,
+/// `iso verify-signature --key <keyring> <path>`, `iso inspect [path]` and
+/// `iso prune [--keep <n>]`
+fn parse_iso_command<I>(mut args: I) -> Result<Commands, CommandParseError>
+where
+    I: Iterator<Item = String>,
+{
+    let usage_error = || {
+        eprintln!(
+            "Usage: pveauto iso list-versions [--mirror <url>] [--channel <stable|test>]\n       pveauto iso verify [--key <keyring>] [path]\n       pveauto iso verify-signature --key <keyring> <path>\n       pveauto iso inspect [path]\n       pveauto iso prune [--keep <n>]"
+        );
+        CommandParseError
+    };
+
+    match args.next().as_deref() {
+        Some("list-versions") => {
+            let (mut mirror, mut channel) = (None, IsoChannel::Stable);
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--mirror" => mirror = Some(args.next().ok_or_else(usage_error)?),
+                    "--channel" => {
+                        channel = args
+                            .next()
+                            .and_then(|c| c.parse().ok())
+                            .ok_or_else(usage_error)?;
+                    }
+                    _ => return Err(usage_error()),
+                }
+            }
+            return Ok(Commands::Iso(IsoCommand::ListVersions { mirror, channel }));
+        }
+        Some("inspect") => {
+            return match (args.next(), args.next()) {
+                (Some(path), _) if path.starts_with('-') => Err(usage_error()),
+                (path, None) => Ok(Commands::Iso(IsoCommand::Inspect {
+                    path: path.unwrap_or_else(Commands::default_download_path),
+                })),
+                _ => Err(usage_error()),
+            };
+        }
+        Some("prune") => {
+            return match (args.next().as_deref(), args.next(), args.next()) {
+                (None, ..) => Ok(Commands::Iso(IsoCommand::Prune { keep: None })),
+                (Some("--keep"), Some(keep), None) => Ok(Commands::Iso(IsoCommand::Prune {
+                    keep: Some(keep.parse().map_err(|_| usage_error())?),
+                })),
+                _ => Err(usage_error()),
+            };
+        }
+        Some("verify-signature") => {
+            return match parse_verify_args(args, usage_error)? {
+                (Some(path), Some(key)) => {
+                    Ok(Commands::Iso(IsoCommand::VerifySignature { path, key }))
+                }
+                _ => Err(usage_error()),
+            };
+        }
+        Some("verify") => {}
+        _ => return Err(usage_error()),
+    }
+
+    let (path, key) = parse_verify_args(args, usage_error)?;
+    Ok(Commands::Iso(IsoCommand::Verify {
+        path: path.unwrap_or_else(Commands::default_download_path),
+        key,
+    }))
+}
+
+/// Parse `verify [--key <keyring>] <path>`, `iso verify` for an ISO that was
+/// not downloaded to the default path, e.g. one copied from another machine
+fn parse_verify_command<I>(args: I) -> Result<Commands, CommandParseError>
+where
+    I: Iterator<Item = String>,
+{
+    let usage_error = || {
+        eprintln!("Usage: pveauto verify [--key <keyring>] <path>");
+        CommandParseError
+    };
+
+    match parse_verify_args(args, usage_error)? {
+        (Some(path), key) => Ok(Commands::Iso(IsoCommand::Verify { path, key })),
+        (None, _) => Err(usage_error()),
+    }
+}
+
+/// Parse the `[--key <keyring>] [path]` of `verify`, `iso verify` and
+/// `iso verify-signature`
+fn parse_verify_args<I>(
+    mut args: I,
+    usage_error: impl Fn() -> CommandParseError,
+) -> Result<(Option<String>, Option<String>), CommandParseError>
+where
+    I: Iterator<Item = String>,
+{
+    let mut key = None;
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--key" => key = Some(args.next().ok_or_else(&usage_error)?),
+            _ if arg.starts_with('-') || path.is_some() => return Err(usage_error()),
+            _ => path = Some(arg),
+        }
+    }
+    Ok((path, key))
+}
+
+/// Parse `bundle export [--iso <iso>] [--answer <path>]... [--key <keyring>] <bundle>`
+/// and `bundle import [--key <keyring>] <bundle> <dir>`
+fn parse_bundle_command<I>(mut args: I) -> Result<Commands, CommandParseError>
+where
+    I: Iterator<Item = String>,
+{
+    let usage_error = || {
+        eprintln!(
+            "Usage: pveauto bundle export [--iso <iso>] [--answer <path>]... [--key <keyring>] <bundle>\n       pveauto bundle import [--key <keyring>] <bundle> <dir>"
+        );
+        CommandParseError
+    };
+
+    let subcommand = args.next().ok_or_else(usage_error)?;
+    let export = match subcommand.as_str() {
+        "export" => true,
+        "import" => false,
+        _ => return Err(usage_error()),
+    };
+    let mut iso = None;
+    let mut answers = Vec::new();
+    let mut key = None;
+    let mut positional = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--iso" if export => iso = Some(args.next().ok_or_else(usage_error)?),
+            "--answer" if export => answers.push(args.next().ok_or_else(usage_error)?),
+            "--key" => key = Some(args.next().ok_or_else(usage_error)?),
+            _ if arg.starts_with('-') => return Err(usage_error()),
+            _ => positional.push(arg),
+        }
+    }
+
+    if export {
+        let [out]: [String; 1] = positional.try_into().map_err(|_| usage_error())?;
+        return Ok(Commands::Bundle(BundleCommand::Export {
+            iso: iso.unwrap_or_else(Commands::default_download_path),
+            answers,
+            key,
+            out,
+        }));
+    }
+    let [bundle, dir]: [String; 2] = positional.try_into().map_err(|_| usage_error())?;
+    Ok(Commands::Bundle(BundleCommand::Import { bundle, dir, key }))
+}
+
+/// Parse `hash-password [--method sha-512|sha-256|yescrypt] [--rounds N]`
+fn parse_hash_password_command<I>(mut args: I) -> Result<Commands, CommandParseError>
+where
+    I: Iterator<Item = String>,
+{
+    let usage_error = || {
+        eprintln!("Usage: pveauto hash-password [--method sha-512|sha-256|yescrypt] [--rounds N]");
+        CommandParseError
+    };
+
+    let mut method = PasswordHashFormat::default();
+    let mut rounds = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--method" => {
+                method = args
+                    .next()
+                    .and_then(|m| m.parse().ok())
+                    .ok_or_else(usage_error)?;
+            }
+            "--rounds" => {
+                rounds = Some(
+                    args.next()
+                        .and_then(|n| n.parse().ok())
+                        .ok_or_else(usage_error)?,
+                );
+            }
+            _ => return Err(usage_error()),
+        }
+    }
+
+    Ok(Commands::HashPassword { method, rounds })
+}
+
+/// Parse `generate-ssh-key [--format toml|json|yaml] [--comment text] <answer> <key>`
+fn parse_generate_ssh_key_command<I>(mut args: I) -> Result<Commands, CommandParseError>
+where
+    I: Iterator<Item = String>,
+{
+    let usage_error = || {
+        eprintln!(
+            "Usage: pveauto generate-ssh-key [--format toml|json|yaml] [--comment text] <answer> <key>"
+        );
+        CommandParseError
+    };
+
+    let mut format = AnswerFormat::default();
+    let mut comment = None;
+    let mut positional = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = args
+                    .next()
+                    .and_then(|f| f.parse().ok())
+                    .ok_or_else(usage_error)?;
+            }
+            "--comment" => comment = Some(args.next().ok_or_else(usage_error)?),
+            _ if arg.starts_with('-') => return Err(usage_error()),
+            _ => positional.push(arg),
+        }
+    }
+
+    let [answer, key]: [String; 2] = positional.try_into().map_err(|_| usage_error())?;
+    Ok(Commands::GenerateSshKey {
+        answer,
+        key,
+        format,
+        comment,
+    })
+}
+
+/// Parse `flash [--iso <iso>] [--yes] <device>`
+fn parse_flash_command<I>(mut args: I) -> Result<Commands, CommandParseError>
+where
+    I: Iterator<Item = String>,
+{
+    let usage_error = || {
+        eprintln!("Usage: pveauto flash [--iso <iso>] [--yes] <device>");
+        CommandParseError
+    };
+
+    let mut iso = None;
+    let mut yes = false;
+    let mut positional = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--iso" => iso = Some(args.next().ok_or_else(usage_error)?),
+            "--yes" | "-y" => yes = true,
+            _ if arg.starts_with('-') => return Err(usage_error()),
+            _ => positional.push(arg),
+        }
+    }
+
+    let [device]: [String; 1] = positional.try_into().map_err(|_| usage_error())?;
+    Ok(Commands::Flash {
+        device,
+        iso: iso.unwrap_or_else(|| AUTOINSTALL_ISO_FILE_NAME.into()),
+        yes,
+    })
+}
+
+/// Parse `answer profile <save|list|show|delete> ...`
+fn parse_profile_command<I>(mut args: I) -> Result<Commands, CommandParseError>
+where
+    I: Iterator<Item = String>,
+{
+    let usage_error = || {
+        eprintln!(
+            "Usage: pveauto answer profile save [--format toml|json|yaml] [--encrypt] [--recipient age1...]... <name> <path>\n       pveauto answer profile list | show <name> | delete <name>"
+        );
+        CommandParseError
+    };
+
+    let subcommand = args.next().ok_or_else(usage_error)?;
+    let mut format = AnswerFormat::default();
+    let mut positional = Vec::new();
+    let mut encrypt = false;
+    let mut recipients = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" if subcommand == "save" => {
+                format = args
+                    .next()
+                    .and_then(|f| f.parse().ok())
+                    .ok_or_else(usage_error)?;
+            }
+            "--encrypt" if subcommand == "save" => encrypt = true,
+            "--recipient" if subcommand == "save" => {
+                recipients.push(args.next().ok_or_else(usage_error)?)
+            }
+            _ if arg.starts_with('-') => return Err(usage_error()),
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let command = match (subcommand.as_str(), positional.len()) {
+        ("save", 2) => ProfileCommand::Save {
+            name: positional.next().unwrap_or_default(),
+            path: positional.next().unwrap_or_default(),
+            format,
+            encrypt: encrypt_mode(encrypt, recipients),
+        },
+        ("list", 0) => ProfileCommand::List,
+        ("show", 1) => ProfileCommand::Show {
+            name: positional.next().unwrap_or_default(),
+        },
+        ("delete", 1) => ProfileCommand::Delete {
+            name: positional.next().unwrap_or_default(),
+        },
+        _ => return Err(usage_error()),
+    };
+
+    Ok(Commands::Answer(AnswerCommand::Profile(command)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::auto_installer::commands::constants::IsoType;
+    use crate::answer_file::SchemaVersion;
 
     #[test]
     fn test_parse_command() {
@@ -43,23 +881,1069 @@ mod tests {
                 vec!["download"],
                 Ok(Commands::Download {
                     dest_path: Some(Commands::default_download_path()),
+                    version: None,
+                    mirror: None,
+                    channel: IsoChannel::Stable,
+                    via: DownloadVia::Direct,
+                    quiet: false,
+                    stdout: false,
                 }),
             ),
             (
                 vec!["download", "/tmp/proxmox.iso"],
                 Ok(Commands::Download {
                     dest_path: Some("/tmp/proxmox.iso".to_string()),
+                    version: None,
+                    mirror: None,
+                    channel: IsoChannel::Stable,
+                    via: DownloadVia::Direct,
+                    quiet: false,
+                    stdout: false,
+                }),
+            ),
+            (
+                vec!["download", "--version", "8.2-1"],
+                Ok(Commands::Download {
+                    dest_path: Some(Commands::default_version_download_path("8.2-1")),
+                    version: Some("8.2-1".to_string()),
+                    mirror: None,
+                    channel: IsoChannel::Stable,
+                    via: DownloadVia::Direct,
+                    quiet: false,
+                    stdout: false,
+                }),
+            ),
+            (
+                vec!["download", "/tmp/pve.iso", "--version", "7.4-1"],
+                Ok(Commands::Download {
+                    dest_path: Some("/tmp/pve.iso".to_string()),
+                    version: Some("7.4-1".to_string()),
+                    mirror: None,
+                    channel: IsoChannel::Stable,
+                    via: DownloadVia::Direct,
+                    quiet: false,
+                    stdout: false,
                 }),
             ),
             (
-                vec!["offline-installer"],
-                Ok(Commands::AutoInstaller(IsoType::Offline)),
+                vec!["download", "--version", "latest"],
+                Err(CommandParseError),
+            ),
+            (
+                vec![
+                    "download",
+                    "--mirror",
+                    "http://mirror.lan/iso",
+                    "/tmp/pve.iso",
+                ],
+                Ok(Commands::Download {
+                    dest_path: Some("/tmp/pve.iso".to_string()),
+                    version: None,
+                    mirror: Some("http://mirror.lan/iso".to_string()),
+                    channel: IsoChannel::Stable,
+                    via: DownloadVia::Direct,
+                    quiet: false,
+                    stdout: false,
+                }),
+            ),
+            (
+                vec!["download", "-q", "--version", "8.2-1", "/tmp/pve.iso"],
+                Ok(Commands::Download {
+                    dest_path: Some("/tmp/pve.iso".to_string()),
+                    version: Some("8.2-1".to_string()),
+                    mirror: None,
+                    channel: IsoChannel::Stable,
+                    via: DownloadVia::Direct,
+                    quiet: true,
+                    stdout: false,
+                }),
+            ),
+            (
+                vec!["download", "--via", "metalink", "-q"],
+                Ok(Commands::Download {
+                    dest_path: Some(Commands::default_download_path()),
+                    version: None,
+                    mirror: None,
+                    channel: IsoChannel::Stable,
+                    via: DownloadVia::Metalink,
+                    quiet: true,
+                    stdout: false,
+                }),
+            ),
+            (
+                vec!["download", "--stdout", "--version", "8.2-1"],
+                Ok(Commands::Download {
+                    dest_path: None,
+                    version: Some("8.2-1".to_string()),
+                    mirror: None,
+                    channel: IsoChannel::Stable,
+                    via: DownloadVia::Direct,
+                    quiet: false,
+                    stdout: true,
+                }),
+            ),
+            (
+                vec!["download", "--stdout", "/tmp/pve.iso"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["download", "--channel", "test"],
+                Ok(Commands::Download {
+                    dest_path: Some(Commands::default_channel_download_path(IsoChannel::Test)),
+                    version: None,
+                    mirror: None,
+                    channel: IsoChannel::Test,
+                    via: DownloadVia::Direct,
+                    quiet: false,
+                    stdout: false,
+                }),
+            ),
+            (
+                vec!["download", "--channel", "test", "--version", "9.0-BETA-1"],
+                Ok(Commands::Download {
+                    dest_path: Some(Commands::default_version_download_path("9.0-BETA-1")),
+                    version: Some("9.0-BETA-1".to_string()),
+                    mirror: None,
+                    channel: IsoChannel::Test,
+                    via: DownloadVia::Direct,
+                    quiet: false,
+                    stdout: false,
+                }),
+            ),
+            (
+                vec!["download", "--version", "9.0-BETA-1"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["download", "--channel", "test", "--version", "8.2-1"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["download", "--channel", "beta"],
+                Err(CommandParseError),
+            ),
+            (vec!["download", "--via", "ftp"], Err(CommandParseError)),
+            (vec!["download", "--mirror"], Err(CommandParseError)),
+            (vec!["download", "a.iso", "b.iso"], Err(CommandParseError)),
+            (
+                vec!["offline-installer", "answer.toml"],
+                Ok(Commands::AutoInstaller(IsoType::Offline {
+                    answer: "answer.toml".to_string(),
+                    format: AnswerFormat::Toml,
+                    iso: Commands::default_download_path(),
+                    out: AUTOINSTALL_ISO_FILE_NAME.to_string(),
+                    packages: Vec::new(),
+                    assets: None,
+                    kernel_args: Vec::new(),
+                    volume_label: None,
+                    backend: IsoBackend::Auto,
+                    dry_run: false,
+                    smoke_test: false,
+                    sign_key: None,
+                    inventory: None,
+                })),
+            ),
+            (
+                vec![
+                    "offline-installer",
+                    "--format",
+                    "yaml",
+                    "--iso",
+                    "/tmp/pve.iso",
+                    "--out",
+                    "/tmp/auto.iso",
+                    "--deb",
+                    "a.deb",
+                    "--deb",
+                    "b.deb",
+                    "--assets",
+                    "assets.toml",
+                    "--kernel-args",
+                    "console=ttyS0,115200 proxdebug",
+                    "--kernel-args",
+                    "nomodeset",
+                    "--volume-label",
+                    "PVE-AUTO-NODE01",
+                    "--backend",
+                    "xorriso",
+                    "--dry-run",
+                    "--sign-key",
+                    "0x1140AF8F639E0C39",
+                    "answer.yaml",
+                ],
+                Ok(Commands::AutoInstaller(IsoType::Offline {
+                    answer: "answer.yaml".to_string(),
+                    format: AnswerFormat::Yaml,
+                    iso: "/tmp/pve.iso".to_string(),
+                    out: "/tmp/auto.iso".to_string(),
+                    packages: vec!["a.deb".to_string(), "b.deb".to_string()],
+                    assets: Some("assets.toml".to_string()),
+                    kernel_args: vec![
+                        "console=ttyS0,115200".to_string(),
+                        "proxdebug".to_string(),
+                        "nomodeset".to_string(),
+                    ],
+                    volume_label: Some("PVE-AUTO-NODE01".to_string()),
+                    backend: IsoBackend::Xorriso,
+                    dry_run: true,
+                    smoke_test: false,
+                    sign_key: Some("0x1140AF8F639E0C39".to_string()),
+                    inventory: None,
+                })),
+            ),
+            (
+                vec![
+                    "offline-installer",
+                    "--inventory",
+                    "hosts.toml",
+                    "--out-dir",
+                    "builds",
+                    "base.toml",
+                ],
+                Ok(Commands::AutoInstaller(IsoType::Offline {
+                    answer: "base.toml".to_string(),
+                    format: AnswerFormat::Toml,
+                    iso: Commands::default_download_path(),
+                    out: AUTOINSTALL_ISO_FILE_NAME.to_string(),
+                    packages: Vec::new(),
+                    assets: None,
+                    kernel_args: Vec::new(),
+                    volume_label: None,
+                    backend: IsoBackend::Auto,
+                    dry_run: false,
+                    smoke_test: false,
+                    sign_key: None,
+                    inventory: Some(InventoryBuild {
+                        inventory: "hosts.toml".to_string(),
+                        out_dir: "builds".to_string(),
+                        file_name: INVENTORY_ISO_FILE_NAME.to_string(),
+                    }),
+                })),
+            ),
+            (
+                vec![
+                    "offline-installer",
+                    "--inventory",
+                    "hosts.toml",
+                    "base.toml",
+                ],
+                Err(CommandParseError),
+            ),
+            (
+                vec![
+                    "offline-installer",
+                    "--inventory",
+                    "hosts.toml",
+                    "--out-dir",
+                    "builds",
+                    "--out",
+                    "pve-{version}-{fqdn}.iso",
+                    "base.toml",
+                ],
+                Ok(Commands::AutoInstaller(IsoType::Offline {
+                    answer: "base.toml".to_string(),
+                    format: AnswerFormat::Toml,
+                    iso: Commands::default_download_path(),
+                    out: AUTOINSTALL_ISO_FILE_NAME.to_string(),
+                    packages: Vec::new(),
+                    assets: None,
+                    kernel_args: Vec::new(),
+                    volume_label: None,
+                    backend: IsoBackend::Auto,
+                    dry_run: false,
+                    smoke_test: false,
+                    sign_key: None,
+                    inventory: Some(InventoryBuild {
+                        inventory: "hosts.toml".to_string(),
+                        out_dir: "builds".to_string(),
+                        file_name: "pve-{version}-{fqdn}.iso".to_string(),
+                    }),
+                })),
+            ),
+            (
+                vec!["offline-installer", "answer.toml", "--deb"],
+                Err(CommandParseError),
+            ),
+            (vec!["offline-installer"], Err(CommandParseError)),
+            (
+                vec!["offline-installer", "--iso", "answer.toml"],
+                Err(CommandParseError),
             ),
             (
                 vec!["network-installer"],
-                Ok(Commands::AutoInstaller(IsoType::Network)),
+                Ok(Commands::AutoInstaller(IsoType::Network {
+                    iso: Commands::default_download_path(),
+                    out: NETWORK_AUTOINSTALL_ISO_FILE_NAME.to_string(),
+                    url: None,
+                    cert_fingerprint: None,
+                    kernel_args: Vec::new(),
+                    volume_label: None,
+                    backend: IsoBackend::Auto,
+                    dry_run: false,
+                    smoke_test: false,
+                    sign_key: None,
+                })),
+            ),
+            (
+                vec![
+                    "network-installer",
+                    "--base-iso",
+                    "/srv/mirror/pve.iso",
+                    "--url",
+                    "https://10.0.0.1:8443/answer",
+                    "--cert-fingerprint",
+                    "AB:CD",
+                    "--out",
+                    "/tmp/net.iso",
+                    "--kernel-args",
+                    "proxdebug",
+                    "--backend",
+                    "native",
+                    "--dry-run",
+                    "--smoke-test",
+                    "--sign-key",
+                    "ops@example.com",
+                ],
+                Ok(Commands::AutoInstaller(IsoType::Network {
+                    iso: "/srv/mirror/pve.iso".to_string(),
+                    out: "/tmp/net.iso".to_string(),
+                    url: Some("https://10.0.0.1:8443/answer".to_string()),
+                    cert_fingerprint: Some("AB:CD".to_string()),
+                    kernel_args: vec!["proxdebug".to_string()],
+                    volume_label: None,
+                    backend: IsoBackend::Native,
+                    dry_run: true,
+                    smoke_test: true,
+                    sign_key: Some("ops@example.com".to_string()),
+                })),
+            ),
+            (
+                vec!["network-installer", "answer.toml"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["network-installer", "--backend", "mkisofs"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["iso", "verify"],
+                Ok(Commands::Iso(IsoCommand::Verify {
+                    path: Commands::default_download_path(),
+                    key: None,
+                })),
+            ),
+            (
+                vec!["iso", "verify", "--key", "release.gpg", "/tmp/pve.iso"],
+                Ok(Commands::Iso(IsoCommand::Verify {
+                    path: "/tmp/pve.iso".to_string(),
+                    key: Some("release.gpg".to_string()),
+                })),
+            ),
+            (
+                vec!["iso", "verify", "a.iso", "b.iso"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["verify", "/media/usb/proxmox-ve_8.2-1.iso"],
+                Ok(Commands::Iso(IsoCommand::Verify {
+                    path: "/media/usb/proxmox-ve_8.2-1.iso".to_string(),
+                    key: None,
+                })),
+            ),
+            (
+                vec!["verify", "--key", "release.gpg", "pve.iso"],
+                Ok(Commands::Iso(IsoCommand::Verify {
+                    path: "pve.iso".to_string(),
+                    key: Some("release.gpg".to_string()),
+                })),
+            ),
+            (vec!["verify"], Err(CommandParseError)),
+            (
+                vec!["verify", "--key", "release.gpg"],
+                Err(CommandParseError),
+            ),
+            (
+                vec![
+                    "iso",
+                    "verify-signature",
+                    "--key",
+                    "ops.asc",
+                    "pve-auto.iso",
+                ],
+                Ok(Commands::Iso(IsoCommand::VerifySignature {
+                    path: "pve-auto.iso".to_string(),
+                    key: "ops.asc".to_string(),
+                })),
+            ),
+            (
+                vec!["iso", "verify-signature", "pve-auto.iso"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["iso", "verify-signature", "--key", "ops.asc"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["iso", "list-versions"],
+                Ok(Commands::Iso(IsoCommand::ListVersions {
+                    mirror: None,
+                    channel: IsoChannel::Stable,
+                })),
+            ),
+            (
+                vec!["iso", "list-versions", "--mirror", "http://mirror.lan/iso"],
+                Ok(Commands::Iso(IsoCommand::ListVersions {
+                    mirror: Some("http://mirror.lan/iso".to_string()),
+                    channel: IsoChannel::Stable,
+                })),
+            ),
+            (
+                vec![
+                    "iso",
+                    "list-versions",
+                    "--channel",
+                    "test",
+                    "--mirror",
+                    "http://mirror.lan/iso",
+                ],
+                Ok(Commands::Iso(IsoCommand::ListVersions {
+                    mirror: Some("http://mirror.lan/iso".to_string()),
+                    channel: IsoChannel::Test,
+                })),
+            ),
+            (
+                vec!["iso", "list-versions", "--channel", "beta"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["iso", "list-versions", "--mirror"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["iso", "list-versions", "8.2-1"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["iso", "inspect"],
+                Ok(Commands::Iso(IsoCommand::Inspect {
+                    path: Commands::default_download_path(),
+                })),
+            ),
+            (
+                vec!["iso", "inspect", "/tmp/pve.iso"],
+                Ok(Commands::Iso(IsoCommand::Inspect {
+                    path: "/tmp/pve.iso".to_string(),
+                })),
+            ),
+            (
+                vec!["iso", "prune"],
+                Ok(Commands::Iso(IsoCommand::Prune { keep: None })),
+            ),
+            (
+                vec!["iso", "prune", "--keep", "3"],
+                Ok(Commands::Iso(IsoCommand::Prune { keep: Some(3) })),
+            ),
+            (vec!["iso", "prune", "--keep", "-1"], Err(CommandParseError)),
+            (vec!["iso", "prune", "--keep"], Err(CommandParseError)),
+            (
+                vec!["iso", "inspect", "a.iso", "b.iso"],
+                Err(CommandParseError),
+            ),
+            (vec!["iso", "inspect", "--json"], Err(CommandParseError)),
+            (vec!["iso", "extract"], Err(CommandParseError)),
+            (
+                vec!["bundle", "export", "pve.tar"],
+                Ok(Commands::Bundle(BundleCommand::Export {
+                    iso: Commands::default_download_path(),
+                    answers: vec![],
+                    key: None,
+                    out: "pve.tar".into(),
+                })),
             ),
+            (
+                vec![
+                    "bundle",
+                    "export",
+                    "--iso",
+                    "proxmox-ve_8.2-1.iso",
+                    "--answer",
+                    "a.toml",
+                    "--answer",
+                    "b.toml",
+                    "--key",
+                    "release.gpg",
+                    "pve.tar",
+                ],
+                Ok(Commands::Bundle(BundleCommand::Export {
+                    iso: "proxmox-ve_8.2-1.iso".into(),
+                    answers: vec!["a.toml".into(), "b.toml".into()],
+                    key: Some("release.gpg".into()),
+                    out: "pve.tar".into(),
+                })),
+            ),
+            (
+                vec!["bundle", "import", "--key", "release.gpg", "pve.tar", "pve"],
+                Ok(Commands::Bundle(BundleCommand::Import {
+                    bundle: "pve.tar".into(),
+                    dir: "pve".into(),
+                    key: Some("release.gpg".into()),
+                })),
+            ),
+            (vec!["bundle", "export"], Err(CommandParseError)),
+            (vec!["bundle", "export", "--answer"], Err(CommandParseError)),
+            (vec!["bundle", "import", "pve.tar"], Err(CommandParseError)),
+            (
+                vec!["bundle", "import", "--iso", "pve.iso", "pve.tar", "pve"],
+                Err(CommandParseError),
+            ),
+            (vec!["bundle"], Err(CommandParseError)),
             (vec!["serve-answers"], Ok(Commands::ServeAnswers)),
+            (
+                vec!["answer", "validate", "answer.toml"],
+                Ok(Commands::Answer(AnswerCommand::Validate {
+                    path: "answer.toml".to_string(),
+                    format: AnswerFormat::Toml,
+                    strict: false,
+                    assistant: false,
+                })),
+            ),
+            (
+                vec!["answer", "validate", "--format", "json", "answer.json"],
+                Ok(Commands::Answer(AnswerCommand::Validate {
+                    path: "answer.json".to_string(),
+                    format: AnswerFormat::Json,
+                    strict: false,
+                    assistant: false,
+                })),
+            ),
+            (
+                vec![
+                    "answer",
+                    "validate",
+                    "--strict",
+                    "--assistant",
+                    "answer.toml",
+                ],
+                Ok(Commands::Answer(AnswerCommand::Validate {
+                    path: "answer.toml".to_string(),
+                    format: AnswerFormat::Toml,
+                    strict: true,
+                    assistant: true,
+                })),
+            ),
+            (vec!["answer", "validate"], Err(CommandParseError)),
+            (
+                vec![
+                    "answer",
+                    "lint",
+                    "--allow",
+                    "root_ssh_keys.missing",
+                    "--allow",
+                    "fqdn.default_placeholder",
+                    "answer.toml",
+                ],
+                Ok(Commands::Answer(AnswerCommand::Lint {
+                    path: "answer.toml".to_string(),
+                    format: AnswerFormat::Toml,
+                    allow: vec![LintWarning::NoRootSshKeys, LintWarning::DefaultFqdn],
+                })),
+            ),
+            (
+                vec!["answer", "lint", "--allow", "bogus.code", "answer.toml"],
+                Err(CommandParseError),
+            ),
+            (vec!["answer", "lint"], Err(CommandParseError)),
+            (
+                vec!["answer", "validate", "--format", "xml", "a.toml"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "validate", "--interactive", "a.toml"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "new"],
+                Ok(Commands::Answer(AnswerCommand::New {
+                    interactive: false,
+                    preset: None,
+                    out: "answer.toml".to_string(),
+                    format: AnswerFormat::Toml,
+                    encrypt: None,
+                })),
+            ),
+            (
+                vec!["answer", "new", "--format=json"],
+                Ok(Commands::Answer(AnswerCommand::New {
+                    interactive: false,
+                    preset: None,
+                    out: "answer.json".to_string(),
+                    format: AnswerFormat::Json,
+                    encrypt: None,
+                })),
+            ),
+            (
+                vec!["answer", "new", "--interactive", "/tmp/a.toml"],
+                Ok(Commands::Answer(AnswerCommand::New {
+                    interactive: true,
+                    preset: None,
+                    out: "/tmp/a.toml".to_string(),
+                    format: AnswerFormat::Toml,
+                    encrypt: None,
+                })),
+            ),
+            (
+                vec!["answer", "new", "--encrypt"],
+                Ok(Commands::Answer(AnswerCommand::New {
+                    interactive: false,
+                    preset: None,
+                    out: "answer.toml.age".to_string(),
+                    format: AnswerFormat::Toml,
+                    encrypt: Some(EncryptMode::Passphrase),
+                })),
+            ),
+            (
+                vec![
+                    "answer",
+                    "new",
+                    "--recipient",
+                    "age1a",
+                    "--recipient",
+                    "age1b",
+                    "a.toml.age",
+                ],
+                Ok(Commands::Answer(AnswerCommand::New {
+                    interactive: false,
+                    preset: None,
+                    out: "a.toml.age".to_string(),
+                    format: AnswerFormat::Toml,
+                    encrypt: Some(EncryptMode::Recipients(vec![
+                        "age1a".to_string(),
+                        "age1b".to_string(),
+                    ])),
+                })),
+            ),
+            (vec!["answer", "new", "--recipient"], Err(CommandParseError)),
+            (
+                vec!["answer", "new", "--preset", "lab-zfs-mirror"],
+                Ok(Commands::Answer(AnswerCommand::New {
+                    interactive: false,
+                    preset: Some(AnswerPreset::LabZfsMirror),
+                    out: "answer.toml".to_string(),
+                    format: AnswerFormat::Toml,
+                    encrypt: None,
+                })),
+            ),
+            (
+                vec!["answer", "new", "--preset", "raid-everything"],
+                Err(CommandParseError),
+            ),
+            (
+                vec![
+                    "answer",
+                    "new",
+                    "--interactive",
+                    "--preset",
+                    "single-disk-ext4",
+                ],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "validate", "--encrypt", "a.toml"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "new", "a.toml", "b.toml"],
+                Err(CommandParseError),
+            ),
+            (vec!["answer", "new", "--bogus"], Err(CommandParseError)),
+            (
+                vec!["answer", "convert", "--format", "json", "a.json"],
+                Ok(Commands::Answer(AnswerCommand::Convert {
+                    path: "a.json".to_string(),
+                    format: AnswerFormat::Json,
+                })),
+            ),
+            (vec!["answer", "convert"], Err(CommandParseError)),
+            (
+                vec!["answer", "migrate", "--from", "1", "old.toml"],
+                Ok(Commands::Answer(AnswerCommand::Migrate {
+                    path: "old.toml".to_string(),
+                    from: Some(SchemaVersion::V1),
+                })),
+            ),
+            (
+                vec!["answer", "migrate", "old.toml"],
+                Ok(Commands::Answer(AnswerCommand::Migrate {
+                    path: "old.toml".to_string(),
+                    from: None,
+                })),
+            ),
+            (
+                vec!["answer", "migrate", "--from", "7", "old.toml"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "migrate", "--format", "json", "x"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "sign", "--format=json", "--key", "k", "x"],
+                Err(CommandParseError),
+            ),
+            (
+                vec![
+                    "answer",
+                    "convert-password",
+                    "--method",
+                    "yescrypt",
+                    "answer.toml",
+                ],
+                Ok(Commands::Answer(AnswerCommand::ConvertPassword {
+                    path: "answer.toml".to_string(),
+                    method: PasswordHashFormat::Yescrypt,
+                    rounds: None,
+                })),
+            ),
+            (
+                vec!["answer", "convert-password", "--rounds", "many", "a.toml"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "validate", "--rounds", "100000", "a.toml"],
+                Err(CommandParseError),
+            ),
+            (
+                vec![
+                    "answer",
+                    "render",
+                    "t.toml",
+                    "--var",
+                    "hostname=pve01",
+                    "--var",
+                    "index=2",
+                ],
+                Ok(Commands::Answer(AnswerCommand::Render {
+                    path: "t.toml".to_string(),
+                    format: AnswerFormat::Toml,
+                    vars: BTreeMap::from([
+                        ("hostname".to_string(), "pve01".to_string()),
+                        ("index".to_string(), "2".to_string()),
+                    ]),
+                })),
+            ),
+            (
+                vec!["answer", "render", "t.toml", "--var", "broken"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "sign", "--key", "operator", "answer.toml"],
+                Ok(Commands::Answer(AnswerCommand::Sign {
+                    path: "answer.toml".to_string(),
+                    key: "operator".to_string(),
+                })),
+            ),
+            (
+                vec!["answer", "sign", "answer.toml"],
+                Err(CommandParseError),
+            ),
+            (
+                vec![
+                    "answer",
+                    "verify",
+                    "--trusted-keys",
+                    "trusted_keys",
+                    "answer.toml",
+                ],
+                Ok(Commands::Answer(AnswerCommand::Verify {
+                    path: "answer.toml".to_string(),
+                    trusted_keys: Some("trusted_keys".to_string()),
+                })),
+            ),
+            (
+                vec![
+                    "answer",
+                    "generate",
+                    "--inventory",
+                    "hosts.toml",
+                    "--out-dir",
+                    "answers",
+                    "base.toml",
+                ],
+                Ok(Commands::Answer(AnswerCommand::Generate {
+                    template: "base.toml".to_string(),
+                    inventory: "hosts.toml".to_string(),
+                    out_dir: "answers".to_string(),
+                    format: AnswerFormat::Toml,
+                })),
+            ),
+            (
+                vec![
+                    "answer",
+                    "generate",
+                    "--inventory",
+                    "hosts.toml",
+                    "base.toml",
+                ],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "convert", "t.toml", "--var", "a=b"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "profile", "save", "homelab", "answer.toml"],
+                Ok(Commands::Answer(AnswerCommand::Profile(
+                    ProfileCommand::Save {
+                        name: "homelab".to_string(),
+                        path: "answer.toml".to_string(),
+                        format: AnswerFormat::Toml,
+                        encrypt: None,
+                    },
+                ))),
+            ),
+            (
+                vec![
+                    "answer",
+                    "profile",
+                    "save",
+                    "--encrypt",
+                    "homelab",
+                    "answer.toml",
+                ],
+                Ok(Commands::Answer(AnswerCommand::Profile(
+                    ProfileCommand::Save {
+                        name: "homelab".to_string(),
+                        path: "answer.toml".to_string(),
+                        format: AnswerFormat::Toml,
+                        encrypt: Some(EncryptMode::Passphrase),
+                    },
+                ))),
+            ),
+            (
+                vec!["answer", "profile", "show", "--encrypt", "homelab"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "profile", "list"],
+                Ok(Commands::Answer(AnswerCommand::Profile(
+                    ProfileCommand::List,
+                ))),
+            ),
+            (
+                vec!["answer", "profile", "show", "homelab"],
+                Ok(Commands::Answer(AnswerCommand::Profile(
+                    ProfileCommand::Show {
+                        name: "homelab".to_string(),
+                    },
+                ))),
+            ),
+            (
+                vec!["answer", "profile", "delete", "homelab"],
+                Ok(Commands::Answer(AnswerCommand::Profile(
+                    ProfileCommand::Delete {
+                        name: "homelab".to_string(),
+                    },
+                ))),
+            ),
+            (vec!["answer", "profile"], Err(CommandParseError)),
+            (
+                vec!["answer", "profile", "save", "x"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "profile", "list", "x"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "profile", "show", "--format", "json", "x"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "diff", "old.toml", "new.toml"],
+                Ok(Commands::Answer(AnswerCommand::Diff {
+                    old: "old.toml".to_string(),
+                    new: "new.toml".to_string(),
+                    format: AnswerFormat::Toml,
+                })),
+            ),
+            (vec!["answer", "diff", "old.toml"], Err(CommandParseError)),
+            (
+                vec!["answer", "diff", "a.toml", "b.toml", "c.toml"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "fetch", "http://10.0.0.1:8000/answer"],
+                Ok(Commands::Answer(AnswerCommand::Fetch {
+                    url: "http://10.0.0.1:8000/answer".to_string(),
+                    format: AnswerFormat::Toml,
+                    save: None,
+                })),
+            ),
+            (
+                vec![
+                    "answer",
+                    "fetch",
+                    "--save",
+                    "served",
+                    "--format",
+                    "json",
+                    "https://pve.lab/answer",
+                ],
+                Ok(Commands::Answer(AnswerCommand::Fetch {
+                    url: "https://pve.lab/answer".to_string(),
+                    format: AnswerFormat::Json,
+                    save: Some("served".to_string()),
+                })),
+            ),
+            (vec!["answer", "fetch"], Err(CommandParseError)),
+            (
+                vec![
+                    "answer",
+                    "add-ssh-keys",
+                    "--github",
+                    "octocat",
+                    "--gitlab",
+                    "jane",
+                    "answer.toml",
+                ],
+                Ok(Commands::Answer(AnswerCommand::AddSshKeys {
+                    path: "answer.toml".to_string(),
+                    format: AnswerFormat::Toml,
+                    sources: vec![
+                        SshKeySource::GitHub("octocat".to_string()),
+                        SshKeySource::GitLab("jane".to_string()),
+                    ],
+                })),
+            ),
+            (
+                vec![
+                    "answer",
+                    "add-ssh-keys",
+                    "--agent",
+                    "--file",
+                    "id.pub",
+                    "--local",
+                    "answer.toml",
+                ],
+                Ok(Commands::Answer(AnswerCommand::AddSshKeys {
+                    path: "answer.toml".to_string(),
+                    format: AnswerFormat::Toml,
+                    sources: vec![
+                        SshKeySource::Agent,
+                        SshKeySource::File("id.pub".to_string()),
+                        SshKeySource::LocalKeys,
+                    ],
+                })),
+            ),
+            (
+                vec!["answer", "add-ssh-keys", "answer.toml"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "add-ssh-keys", "--github", "octocat"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "fetch", "http://a", "--save"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "validate", "--save", "x", "a.toml"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["answer", "schema"],
+                Ok(Commands::Answer(AnswerCommand::Schema)),
+            ),
+            (vec!["answer", "schema", "x"], Err(CommandParseError)),
+            (vec!["answer"], Err(CommandParseError)),
+            (vec!["answer", "bogus"], Err(CommandParseError)),
+            (
+                vec!["hash-password"],
+                Ok(Commands::HashPassword {
+                    method: PasswordHashFormat::Sha512,
+                    rounds: None,
+                }),
+            ),
+            (
+                vec!["hash-password", "--rounds", "100000"],
+                Ok(Commands::HashPassword {
+                    method: PasswordHashFormat::Sha512,
+                    rounds: Some(100_000),
+                }),
+            ),
+            (
+                vec!["hash-password", "--method", "yescrypt"],
+                Ok(Commands::HashPassword {
+                    method: PasswordHashFormat::Yescrypt,
+                    rounds: None,
+                }),
+            ),
+            (
+                vec!["hash-password", "--method", "md5"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["hash-password", "--rounds", "many"],
+                Err(CommandParseError),
+            ),
+            (vec!["hash-password", "secret"], Err(CommandParseError)),
+            (
+                vec!["generate-ssh-key", "answer.toml", "id_ed25519"],
+                Ok(Commands::GenerateSshKey {
+                    answer: "answer.toml".to_string(),
+                    key: "id_ed25519".to_string(),
+                    format: AnswerFormat::Toml,
+                    comment: None,
+                }),
+            ),
+            (
+                vec![
+                    "generate-ssh-key",
+                    "--format",
+                    "yaml",
+                    "--comment",
+                    "admin@lab",
+                    "answer.yaml",
+                    "keys/pve",
+                ],
+                Ok(Commands::GenerateSshKey {
+                    answer: "answer.yaml".to_string(),
+                    key: "keys/pve".to_string(),
+                    format: AnswerFormat::Yaml,
+                    comment: Some("admin@lab".to_string()),
+                }),
+            ),
+            (
+                vec!["generate-ssh-key", "answer.toml"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["generate-ssh-key", "answer.toml", "key", "--comment"],
+                Err(CommandParseError),
+            ),
+            (
+                vec!["flash", "/dev/sdb"],
+                Ok(Commands::Flash {
+                    device: "/dev/sdb".to_string(),
+                    iso: AUTOINSTALL_ISO_FILE_NAME.to_string(),
+                    yes: false,
+                }),
+            ),
+            (
+                vec!["flash", "--iso", "pve.iso", "-y", "/dev/sdc"],
+                Ok(Commands::Flash {
+                    device: "/dev/sdc".to_string(),
+                    iso: "pve.iso".to_string(),
+                    yes: true,
+                }),
+            ),
+            (vec!["flash"], Err(CommandParseError)),
+            (
+                vec!["flash", "/dev/sdb", "/dev/sdc"],
+                Err(CommandParseError),
+            ),
+            (vec!["flash", "--iso"], Err(CommandParseError)),
             (vec!["exit"], Ok(Commands::Exit)),
             (vec!["-h"], Err(CommandParseError)),
             (vec!["--help"], Err(CommandParseError)),
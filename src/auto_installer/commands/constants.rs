@@ -1,29 +1,504 @@
-use std::{fmt, str::FromStr};
+use crate::answer_file::{
+    AnswerFormat, AnswerPreset, LintWarning, SchemaVersion, sections::PasswordHashFormat,
+};
+use crate::iso::{
+    alternates::DownloadVia,
+    channel::IsoChannel,
+    constants::{NETWORK_AUTOINSTALL_ISO_FILE_NAME, TEST_ISO_FILE_NAME},
+    scraper::iso_file_name,
+    writer::IsoBackend,
+};
+use std::{collections::BTreeMap, fmt, str::FromStr};
 
 pub const USAGE: &str = r#"
 Usage: pveauto <command> [options]
 
 Commands:
-  download [path]     Download Proxmox VE ISO to [path], defaults to:
+  download [--version <version>] [--mirror <url>] [--channel stable|test]
+           [--via direct|metalink|torrent] [--quiet] [--stdout | path]
+                      Download Proxmox VE ISO to [path], defaults to:
                         $XDG_DATA_HOME/pve-auto/proxmox-ve-latest.iso 
                         or ~/.local/share/pve-auto/proxmox-ve-latest.iso
-  offline-installer   Create unattended ISO (offline; requires MGMT MAC)
-  network-installer   Create unattended ISO (network; DHCP required)
+                        --version fetches a specific release (e.g. 8.2-1)
+                        instead of the latest, saved as proxmox-ve_<version>.iso
+                        --mirror (or $PVEAUTO_MIRROR, or download.mirror in
+                        $XDG_CONFIG_HOME/pve-auto/config.toml) downloads from
+                        a mirror of enterprise.proxmox.com/iso/, checked
+                        against its SHA512SUMS or SHA256SUMS (MD5SUMS is
+                        refused); of several download.mirrors, the fastest
+                        is picked
+                        --channel test fetches the newest beta ISO (e.g.
+                        9.0-BETA-1) from the directory listing instead of
+                        the latest release, saved as proxmox-ve-test.iso;
+                        --version takes betas with it
+                        --via metalink|torrent fetches the ISO from the
+                        URLs in <iso>.meta4 or the web seeds of
+                        <iso>.torrent when published, else directly
+                        An interrupted download resumes from
+                        [path].part; progress shows bytes,
+                        speed and ETA, and --quiet (-q) prints errors only
+                        ISOs are kept in $XDG_CACHE_HOME/pve-auto/iso and
+                        [path] links to them; unchanged releases are not
+                        downloaded or hashed again
+                        Failed requests and dropped downloads are retried
+                        with backoff, see [download.retry] in config.toml
+                        --stdout streams the ISO to standard output as it
+                        is downloaded, e.g. into dd, without storing it;
+                        exits non-zero if its checksum does not match
+  offline-installer [--format toml|json|yaml] [--base-iso <iso>]
+                    [--out <out>] [--deb <package.deb>]... [--assets <manifest>]
+                    [--kernel-args <args>]... [--volume-label <label>]
+                    [--backend auto|native|xorriso] [--dry-run]
+                    [--smoke-test] [--sign-key <key>]
+                    [--inventory <hosts.toml> --out-dir <dir>] <answer>
+                      Write a copy of <iso> (default: the download path)
+                        with <answer> embedded to <out> (default
+                        proxmox-ve-autoinstall.iso), bundling a local
+                        first-boot script; uses proxmox-auto-install-assistant
+                        (or $PVEAUTO_ASSISTANT) when installed, otherwise
+                        writes the files into the ISO itself; <out>.sha256
+                        and <out>.manifest.json, a record of the base ISO,
+                        answer, files, kernel arguments and tool version,
+                        are written next to it
+                        --base-iso (or --iso) takes a Proxmox VE ISO already
+                        on disk, e.g. from an internal mirror, so nothing is
+                        downloaded; it must have the .cd-info of a Proxmox
+                        installer ISO
+                        --deb adds a package to the ISO's package pool, which
+                        the installer installs along with Proxmox VE
+                        --assets copies the files and scripts of a manifest
+                        into the ISO with a first-boot script installing them
+                        --kernel-args appends e.g. "console=ttyS0,115200" to
+                        the kernel command line of the automated boot entry
+                        --volume-label sets the ISO's label, e.g.
+                        PVE-AUTO-NODE01 (A-Z, 0-9, _ and -, at most 32)
+                        --backend picks what writes files into the ISO:
+                        xorriso (or $PVEAUTO_XORRISO), which rebuilds it, or
+                        pveauto's own writer, which appends to it; auto, the
+                        default, uses xorriso when installed
+                        --dry-run checks everything and prints the plan
+                        (base ISO, answer SHA256, files added, kernel
+                        arguments, output, commands run) without writing
+                        --smoke-test boots the written ISO in QEMU
+                        (qemu-system-x86_64 or $PVEAUTO_QEMU) on a scratch
+                        disk and fails unless the installer starts
+                        partitioning it within 15 minutes
+                        --sign-key writes <out>.asc, a detached signature
+                        of the written ISO made by GnuPG (gpg or
+                        $PVEAUTO_GPG) with <key>, for iso verify-signature
+                        --inventory writes <dir>/<name>.iso per [[host]],
+                        with <answer> as the template of answer generate,
+                        and prints a table of the builds; --out then names
+                        each host's ISO within <dir>
+                        <out> may hold {version} (of the base ISO), {fqdn},
+                        {hostname} (of the answer) and {name} (of the
+                        inventory host), e.g. pve-{version}-{fqdn}-auto.iso
+  network-installer [--base-iso <iso>] [--out <out>] [--url <url>]
+                    [--cert-fingerprint <sha256>] [--kernel-args <args>]...
+                    [--volume-label <label>] [--backend auto|native|xorriso]
+                    [--dry-run] [--smoke-test] [--sign-key <key>]
+                      Write a copy of <iso> (default: the download path) to
+                        <out> (default proxmox-ve-autoinstall-network.iso)
+                        that fetches its answer from <url>, e.g. a
+                        serve-answers server, or from the URL announced by
+                        DHCP or DNS; --cert-fingerprint pins a self-signed
+                        HTTPS certificate; --base-iso, --kernel-args,
+                        --volume-label, --backend, --dry-run, --smoke-test
+                        and --sign-key as above; <out> may hold {version}
+  iso list-versions [--mirror <url>] [--channel stable|test]
+                      List the Proxmox VE ISO versions on enterprise.proxmox.com
+                        or the mirror; --channel test lists the beta ISOs
+  iso verify [--key <keyring>] [path]
+                      Check the ISO at [path] (default: the download path)
+                        against the SHA512SUMS or SHA256SUMS published by
+                        Proxmox, after verifying its OpenPGP signature
+                        with a pinned Proxmox release key or the keys
+                        in <keyring>; an ISO whose release (from .cd-info
+                        or its name) is listed with another checksum is
+                        reported as damaged
+  verify [--key <keyring>] <path>
+                      iso verify for an ISO at <path>, e.g. one copied from
+                        another machine
+  iso verify-signature --key <keyring> <path>
+                      Check <path>.asc, the signature written by
+                        --sign-key, against the ISO at <path> and the keys
+                        in <keyring>, e.g. the signer's gpg --export output;
+                        needs no GnuPG
+  iso inspect [path]  Show the Proxmox VE version, volume label, size, boot
+                        entries and SHA256 of the ISO at [path] (default: the
+                        download path), and whether an answer file is embedded
+  iso prune [--keep <n>]
+                      Delete all but the newest <n> ISO versions (default
+                        cache.keep-versions in config.toml, or 2) from the
+                        cache with their metadata, and report the space freed
+  bundle export [--iso <iso>] [--answer <path>]... [--key <keyring>] <bundle>
+                      Verify the ISO at <iso> (default: the download path) as
+                        iso verify does, then pack it with the answer files,
+                        the checksum file, its signature, the keyring and a
+                        manifest.json into the tarball <bundle> for transfer
+                        into an air-gapped network
+  bundle import [--key <keyring>] <bundle> <dir>
+                      Unpack <bundle> into <dir> without network access and
+                        check every file against the manifest and the ISO
+                        against the signed checksum file, with the bundled
+                        keyring if it holds a pinned Proxmox release key or
+                        with the keys in <keyring>
+  flash [--iso <iso>] [--yes] <device>
+                      Write <iso> (default proxmox-ve-autoinstall.iso) to the
+                        block device <device>, then read it back to verify
+                        it; shows the device's model and size and asks for
+                        its name before erasing it unless --yes is given
   serve-answers       Start HTTP server for network installer
+  answer validate [--format toml|json|yaml] [--strict] [--assistant] <path>
+                      Validate an answer file, printing error codes and lines;
+                        --strict also rejects keys the format does not define;
+                        --assistant also runs proxmox-auto-install-assistant
+                        validate-answer (or $PVEAUTO_ASSISTANT)
+  answer lint [--format toml|json|yaml] [--allow code]... <path>
+                      Validate, then warn about risky-but-valid settings
+                        (placeholder password or FQDN, low hash rounds, no
+                        SSH keys, reboot-on-error); exits non-zero on any
+                        warning not allowed with --allow
+  answer new [--interactive | --preset name] [--format toml|json|yaml]
+             [--encrypt] [--recipient age1...]... [path]
+                      Write a new answer file to [path] (default answer.<format>),
+                        prompting for each setting with --interactive or
+                        starting from a built-in preset (lab-zfs-mirror,
+                        single-disk-ext4, production-hardened);
+                        --encrypt/--recipient write an age-encrypted
+                        <path>.age instead
+  answer convert [--format toml|json|yaml] <path>
+                      Print the answer file at <path> as canonical TOML
+  answer render [--format toml|json|yaml] <template> [--var key=value]...
+                      Expand {{key}} placeholders in <template>, validate the
+                        result and print it as canonical TOML
+  answer generate [--format toml|json|yaml] --inventory <hosts.toml>
+                  --out-dir <dir> <template>
+                      Write one validated answer per [[host]] in the
+                        inventory to <dir>/<name>.<format>, overriding the
+                        template's fqdn ({mac}, {mac6}, {serial} expanded),
+                        NIC filter and disk-list per host
+  answer diff [--format toml|json|yaml] <old> <new>
+                      Compare two answer files field by field; exits non-zero
+                        when they differ
+  answer schema       Print the JSON Schema of the answer format
+  answer convert-password [--method sha-512|sha-256|yescrypt] [--rounds N] <path>
+                      Replace a plaintext root-password in the TOML answer
+                        at <path> with root-password-hashed, in place
+  answer migrate [--from 1|2] <path>
+                      Rename the keys of an answer file written for an older
+                        installer (e.g. disk_list) to the current names in
+                        place; the schema is detected unless --from is given
+  answer sign --key <private key> <path>
+                      Write a detached signature of <path> to <path>.sig with
+                        an ed25519 OpenSSH key (as ssh-keygen -Y sign
+                        -n pveauto-answer does)
+  answer verify [--trusted-keys <file>] <path>
+                      Check <path>.sig against the public keys in <file> or
+                        $PVEAUTO_TRUSTED_KEYS; while that variable is set,
+                        every command refuses answers without a valid one
+  answer fetch [--format toml|json|yaml] [--save name] <url>
+                      Download an answer file over HTTP(S) and validate it;
+                        --save stores a valid answer as profile <name>
+  answer add-ssh-keys [--format toml|json|yaml] (--github user | --gitlab user |
+                      --file path | --local | --agent)... <path>
+                      Append public keys to root-ssh-keys in <path>: those
+                        published for a GitHub or GitLab account, those in a
+                        key file or every ~/.ssh/*.pub, or the identities of
+                        the ssh-agent at $SSH_AUTH_SOCK
+  answer profile save [--format toml|json|yaml] [--encrypt]
+                      [--recipient age1...]... <name> <path>
+  answer profile list | show <name> | delete <name>
+                      Manage named answer files in
+                        $XDG_DATA_HOME/pve-auto/profiles; pass @<name> instead
+                        of a path to use a stored profile
+                      Encrypted (.age) answers are decrypted with the identity
+                        file in $PVEAUTO_AGE_IDENTITY or a passphrase from
+                        $PVEAUTO_AGE_PASSPHRASE or a prompt
+  hash-password [--method sha-512|sha-256|yescrypt] [--rounds N]
+                      Print a crypt hash for root-password-hashed, reading
+                        the password from stdin or a prompt
+  generate-ssh-key [--format toml|json|yaml] [--comment text] <answer> <key>
+                      Write a new ed25519 keypair to <key> and <key>.pub and
+                        add the public key to root-ssh-keys in <answer>
   exit                Exit program
 "#;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum IsoType {
-    Offline,
-    Network,
+    /// The answer is embedded in the ISO.
+    Offline {
+        answer: String,
+        format: AnswerFormat,
+        /// The Proxmox VE ISO to prepare.
+        iso: String,
+        out: String,
+        /// Extra Debian packages for the ISO's package pool (`--deb`).
+        packages: Vec<String>,
+        /// Manifest of post-install assets (`--assets`).
+        assets: Option<String>,
+        /// Appended to the automated boot entry's kernel command line (`--kernel-args`).
+        kernel_args: Vec<String>,
+        /// Volume identifier of the written ISO (`--volume-label`).
+        volume_label: Option<String>,
+        /// What writes files into the ISO (`--backend`).
+        backend: IsoBackend,
+        /// Print the plan instead of writing the ISO (`--dry-run`).
+        dry_run: bool,
+        /// Boot the written ISO in QEMU until the installation starts (`--smoke-test`).
+        smoke_test: bool,
+        /// GnuPG key signing the written ISO (`--sign-key`).
+        sign_key: Option<String>,
+        /// One ISO per inventory host instead of one to `out` (`--inventory`).
+        inventory: Option<InventoryBuild>,
+    },
+    /// The installer fetches its answer from an HTTP server.
+    Network {
+        iso: String,
+        out: String,
+        url: Option<String>,
+        cert_fingerprint: Option<String>,
+        /// Appended to the automated boot entry's kernel command line (`--kernel-args`).
+        kernel_args: Vec<String>,
+        /// Volume identifier of the written ISO (`--volume-label`).
+        volume_label: Option<String>,
+        /// What writes files into the ISO (`--backend`).
+        backend: IsoBackend,
+        /// Print the plan instead of writing the ISO (`--dry-run`).
+        dry_run: bool,
+        /// Boot the written ISO in QEMU until the installation starts (`--smoke-test`).
+        smoke_test: bool,
+        /// GnuPG key signing the written ISO (`--sign-key`).
+        sign_key: Option<String>,
+    },
+}
+
+/// `offline-installer --inventory <inventory> --out-dir <out_dir>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InventoryBuild {
+    /// The `hosts.toml` listing the hosts, as for `answer generate`.
+    pub inventory: String,
+    /// Directory the ISO of each host is written to.
+    pub out_dir: String,
+    /// Name of each host's ISO, an
+    /// [`OutputTemplate`](crate::iso::output::OutputTemplate) such as
+    /// `{name}.iso` (`--out`).
+    pub file_name: String,
+}
+
+/// How `--encrypt` output is protected with age.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncryptMode {
+    /// A passphrase, prompted for or read from `$PVEAUTO_AGE_PASSPHRASE`.
+    Passphrase,
+    /// One or more `age1...` public keys from `--recipient`.
+    Recipients(Vec<String>),
+}
+
+/// Where `answer add-ssh-keys` reads root SSH public keys from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SshKeySource {
+    /// The published keys of a GitHub account (`--github <user>`).
+    GitHub(String),
+    /// The published keys of a GitLab account (`--gitlab <user>`).
+    GitLab(String),
+    /// One or more keys in an OpenSSH public key file (`--file <path>`).
+    File(String),
+    /// Every `~/.ssh/*.pub` file (`--local`).
+    LocalKeys,
+    /// The identities of the running ssh-agent (`--agent`).
+    Agent,
+}
+
+impl fmt::Display for SshKeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GitHub(user) => write!(f, "github:{}", user),
+            Self::GitLab(user) => write!(f, "gitlab:{}", user),
+            Self::File(path) => f.write_str(path),
+            Self::LocalKeys => f.write_str("~/.ssh/*.pub"),
+            Self::Agent => f.write_str("ssh-agent"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileCommand {
+    Save {
+        name: String,
+        path: String,
+        format: AnswerFormat,
+        encrypt: Option<EncryptMode>,
+    },
+    List,
+    Show {
+        name: String,
+    },
+    Delete {
+        name: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IsoCommand {
+    ListVersions {
+        mirror: Option<String>,
+        /// Releases or test ISOs (`--channel`).
+        channel: IsoChannel,
+    },
+    Verify {
+        path: String,
+        /// A keyring to check the checksum file's signature with instead of
+        /// the release key.
+        key: Option<String>,
+    },
+    /// `iso verify-signature`.
+    VerifySignature {
+        path: String,
+        /// The keyring holding the key the ISO is expected to be signed with.
+        key: String,
+    },
+    Inspect {
+        path: String,
+    },
+    Prune {
+        /// Versions to keep (`--keep`); the configured or default number when `None`.
+        keep: Option<usize>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleCommand {
+    Export {
+        iso: String,
+        /// Answer files to pack (`--answer`, repeatable).
+        answers: Vec<String>,
+        /// A keyring to check the checksum file's signature with instead of
+        /// the release key; it is packed in its place.
+        key: Option<String>,
+        out: String,
+    },
+    Import {
+        bundle: String,
+        dir: String,
+        /// A keyring to check the checksum file's signature with instead of
+        /// the bundled one.
+        key: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnswerCommand {
+    Validate {
+        path: String,
+        format: AnswerFormat,
+        strict: bool,
+        assistant: bool,
+    },
+    Lint {
+        path: String,
+        format: AnswerFormat,
+        allow: Vec<LintWarning>,
+    },
+    New {
+        interactive: bool,
+        preset: Option<AnswerPreset>,
+        out: String,
+        format: AnswerFormat,
+        encrypt: Option<EncryptMode>,
+    },
+    Convert {
+        path: String,
+        format: AnswerFormat,
+    },
+    Render {
+        path: String,
+        format: AnswerFormat,
+        vars: BTreeMap<String, String>,
+    },
+    Generate {
+        template: String,
+        inventory: String,
+        out_dir: String,
+        format: AnswerFormat,
+    },
+    Diff {
+        old: String,
+        new: String,
+        format: AnswerFormat,
+    },
+    Schema,
+    Migrate {
+        path: String,
+        from: Option<SchemaVersion>,
+    },
+    ConvertPassword {
+        path: String,
+        method: PasswordHashFormat,
+        rounds: Option<u32>,
+    },
+    Sign {
+        path: String,
+        key: String,
+    },
+    Verify {
+        path: String,
+        trusted_keys: Option<String>,
+    },
+    Fetch {
+        url: String,
+        format: AnswerFormat,
+        save: Option<String>,
+    },
+    AddSshKeys {
+        path: String,
+        format: AnswerFormat,
+        sources: Vec<SshKeySource>,
+    },
+    Profile(ProfileCommand),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Commands {
-    Download { dest_path: Option<String> },
+    Download {
+        dest_path: Option<String>,
+        /// A specific release such as `8.2-1`; the latest when `None`.
+        version: Option<String>,
+        /// Base URL of a mirror of enterprise.proxmox.com/iso/ (`--mirror`).
+        mirror: Option<String>,
+        /// Whether the latest is the latest release or test ISO (`--channel`).
+        channel: IsoChannel,
+        /// Where the ISO bytes come from (`--via`).
+        via: DownloadVia,
+        /// Suppress all non-error output (`--quiet`).
+        quiet: bool,
+        /// Stream the ISO to standard output as it is downloaded, checked
+        /// once the last byte is written, instead of writing it to
+        /// `dest_path` (`--stdout`).
+        stdout: bool,
+    },
     AutoInstaller(IsoType),
+    Iso(IsoCommand),
+    Bundle(BundleCommand),
     ServeAnswers,
+    Answer(AnswerCommand),
+    HashPassword {
+        method: PasswordHashFormat,
+        rounds: Option<u32>,
+    },
+    GenerateSshKey {
+        answer: String,
+        key: String,
+        format: AnswerFormat,
+        comment: Option<String>,
+    },
+    Flash {
+        /// The block device to overwrite, e.g. `/dev/sdb`.
+        device: String,
+        iso: String,
+        /// Skip the confirmation prompt (`--yes`).
+        yes: bool,
+    },
     Help,
     Exit,
 }
@@ -36,12 +511,32 @@ impl Commands {
     /// This path is determined based on the `XDG_DATA_HOME` environment variable,
     /// falling back to `~/.local/share` if `XDG_DATA_HOME` is not set.
     pub fn default_download_path() -> String {
+        Self::default_data_path("proxmox-ve-latest.iso")
+    }
+
+    /// Returns the default download path of the latest ISO of `channel`:
+    /// [`Commands::default_download_path`] for releases, and
+    /// `proxmox-ve-test.iso` next to it for test ISOs.
+    pub fn default_channel_download_path(channel: IsoChannel) -> String {
+        match channel {
+            IsoChannel::Stable => Self::default_download_path(),
+            IsoChannel::Test => Self::default_data_path(TEST_ISO_FILE_NAME),
+        }
+    }
+
+    /// Returns the default download path of the ISO of a specific `version`,
+    /// next to the one of [`Commands::default_download_path`].
+    pub fn default_version_download_path(version: &str) -> String {
+        Self::default_data_path(&iso_file_name(version))
+    }
+
+    fn default_data_path(file_name: &str) -> String {
         if let Ok(dir) = std::env::var("XDG_DATA_HOME") {
-            format!("{}/pve-auto/proxmox-ve-latest.iso", dir)
+            format!("{}/pve-auto/{}", dir, file_name)
         } else if let Ok(home) = std::env::var("HOME") {
-            format!("{}/.local/share/pve-auto/proxmox-ve-latest.iso", home)
+            format!("{}/.local/share/pve-auto/{}", home, file_name)
         } else {
-            "proxmox-ve-latest.iso".to_string()
+            file_name.to_string()
         }
     }
 }
@@ -57,13 +552,65 @@ impl fmt::Display for CommandParseError {
 
 impl std::error::Error for CommandParseError {}
 
+/// A command ran but failed; details have already been reported to the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandError;
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "command failed")
+    }
+}
+
+impl std::error::Error for CommandError {}
+
 impl fmt::Display for Commands {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Commands::Download { .. } => write!(f, "download"),
-            Commands::AutoInstaller(IsoType::Offline) => write!(f, "offline-installer"),
-            Commands::AutoInstaller(IsoType::Network) => write!(f, "network-installer"),
+            Commands::AutoInstaller(IsoType::Offline { .. }) => write!(f, "offline-installer"),
+            Commands::AutoInstaller(IsoType::Network { .. }) => write!(f, "network-installer"),
+            Commands::Iso(IsoCommand::ListVersions { .. }) => write!(f, "iso list-versions"),
+            Commands::Iso(IsoCommand::Verify { .. }) => write!(f, "iso verify"),
+            Commands::Iso(IsoCommand::VerifySignature { .. }) => {
+                write!(f, "iso verify-signature")
+            }
+            Commands::Iso(IsoCommand::Inspect { .. }) => write!(f, "iso inspect"),
+            Commands::Iso(IsoCommand::Prune { .. }) => write!(f, "iso prune"),
+            Commands::Bundle(BundleCommand::Export { .. }) => write!(f, "bundle export"),
+            Commands::Bundle(BundleCommand::Import { .. }) => write!(f, "bundle import"),
             Commands::ServeAnswers => write!(f, "serve-answers"),
+            Commands::Answer(AnswerCommand::Validate { .. }) => write!(f, "answer validate"),
+            Commands::Answer(AnswerCommand::Lint { .. }) => write!(f, "answer lint"),
+            Commands::Answer(AnswerCommand::New { .. }) => write!(f, "answer new"),
+            Commands::Answer(AnswerCommand::Convert { .. }) => write!(f, "answer convert"),
+            Commands::Answer(AnswerCommand::Render { .. }) => write!(f, "answer render"),
+            Commands::Answer(AnswerCommand::Generate { .. }) => write!(f, "answer generate"),
+            Commands::Answer(AnswerCommand::Diff { .. }) => write!(f, "answer diff"),
+            Commands::Answer(AnswerCommand::Schema) => write!(f, "answer schema"),
+            Commands::Answer(AnswerCommand::Migrate { .. }) => write!(f, "answer migrate"),
+            Commands::Answer(AnswerCommand::ConvertPassword { .. }) => {
+                write!(f, "answer convert-password")
+            }
+            Commands::Answer(AnswerCommand::Sign { .. }) => write!(f, "answer sign"),
+            Commands::Answer(AnswerCommand::Verify { .. }) => write!(f, "answer verify"),
+            Commands::Answer(AnswerCommand::Fetch { .. }) => write!(f, "answer fetch"),
+            Commands::Answer(AnswerCommand::AddSshKeys { .. }) => write!(f, "answer add-ssh-keys"),
+            Commands::Answer(AnswerCommand::Profile(ProfileCommand::Save { .. })) => {
+                write!(f, "answer profile save")
+            }
+            Commands::Answer(AnswerCommand::Profile(ProfileCommand::List)) => {
+                write!(f, "answer profile list")
+            }
+            Commands::Answer(AnswerCommand::Profile(ProfileCommand::Show { .. })) => {
+                write!(f, "answer profile show")
+            }
+            Commands::Answer(AnswerCommand::Profile(ProfileCommand::Delete { .. })) => {
+                write!(f, "answer profile delete")
+            }
+            Commands::HashPassword { .. } => write!(f, "hash-password"),
+            Commands::GenerateSshKey { .. } => write!(f, "generate-ssh-key"),
+            Commands::Flash { .. } => write!(f, "flash"),
             Commands::Help => write!(f, "help"),
             Commands::Exit => write!(f, "exit"),
         }
@@ -77,10 +624,30 @@ impl FromStr for Commands {
         match input {
             "download" => Ok(Commands::Download {
                 dest_path: Some(Commands::default_download_path()),
+                version: None,
+                mirror: None,
+                channel: IsoChannel::Stable,
+                via: DownloadVia::Direct,
+                quiet: false,
+                stdout: false,
             }),
-            "offline-installer" => Ok(Commands::AutoInstaller(IsoType::Offline)),
-            "network-installer" => Ok(Commands::AutoInstaller(IsoType::Network)),
+            "network-installer" => Ok(Commands::AutoInstaller(IsoType::Network {
+                iso: Commands::default_download_path(),
+                out: NETWORK_AUTOINSTALL_ISO_FILE_NAME.to_string(),
+                url: None,
+                cert_fingerprint: None,
+                kernel_args: Vec::new(),
+                volume_label: None,
+                backend: IsoBackend::Auto,
+                dry_run: false,
+                smoke_test: false,
+                sign_key: None,
+            })),
             "serve-answers" => Ok(Commands::ServeAnswers),
+            "hash-password" => Ok(Commands::HashPassword {
+                method: PasswordHashFormat::default(),
+                rounds: None,
+            }),
             "exit" => Ok(Commands::Exit),
             "help" | "-h" | "--help" => Ok(Commands::Help),
             _ => Err(CommandParseError),
@@ -91,24 +658,39 @@ impl FromStr for Commands {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::iso::constants::AUTOINSTALL_ISO_FILE_NAME;
 
     #[test]
     fn test_command_from_str() {
         assert_eq!(
             Commands::from_str("download").unwrap(),
             Commands::Download {
-                dest_path: Some(Commands::default_download_path())
+                dest_path: Some(Commands::default_download_path()),
+                version: None,
+                mirror: None,
+                channel: IsoChannel::Stable,
+                via: DownloadVia::Direct,
+                quiet: false,
+                stdout: false,
             }
         );
 
-        assert_eq!(
-            Commands::from_str("offline-installer").unwrap(),
-            Commands::AutoInstaller(IsoType::Offline)
-        );
+        assert!(Commands::from_str("offline-installer").is_err());
 
         assert_eq!(
             Commands::from_str("network-installer").unwrap(),
-            Commands::AutoInstaller(IsoType::Network)
+            Commands::AutoInstaller(IsoType::Network {
+                iso: Commands::default_download_path(),
+                out: NETWORK_AUTOINSTALL_ISO_FILE_NAME.into(),
+                url: None,
+                cert_fingerprint: None,
+                kernel_args: Vec::new(),
+                volume_label: None,
+                backend: IsoBackend::Auto,
+                dry_run: false,
+                smoke_test: false,
+                sign_key: None,
+            })
         );
 
         assert_eq!(
@@ -116,6 +698,14 @@ mod tests {
             Commands::ServeAnswers
         );
 
+        assert_eq!(
+            Commands::from_str("hash-password").unwrap(),
+            Commands::HashPassword {
+                method: PasswordHashFormat::Sha512,
+                rounds: None
+            }
+        );
+
         assert_eq!(Commands::from_str("exit").unwrap(), Commands::Exit);
 
         assert_eq!(Commands::from_str("help").unwrap(), Commands::Help);
@@ -129,23 +719,223 @@ mod tests {
     fn test_command_to_string() {
         assert_eq!(
             Commands::Download {
-                dest_path: Some(Commands::default_download_path())
+                dest_path: Some(Commands::default_download_path()),
+                version: None,
+                mirror: None,
+                channel: IsoChannel::Stable,
+                via: DownloadVia::Direct,
+                quiet: false,
+                stdout: false,
             }
             .to_string(),
             "download"
         );
 
         assert_eq!(
-            Commands::AutoInstaller(IsoType::Offline).to_string(),
+            Commands::AutoInstaller(IsoType::Offline {
+                answer: "answer.toml".into(),
+                format: AnswerFormat::Toml,
+                iso: Commands::default_download_path(),
+                out: AUTOINSTALL_ISO_FILE_NAME.into(),
+                packages: Vec::new(),
+                assets: None,
+                kernel_args: Vec::new(),
+                volume_label: None,
+                backend: IsoBackend::Auto,
+                dry_run: false,
+                smoke_test: false,
+                sign_key: None,
+                inventory: None,
+            })
+            .to_string(),
             "offline-installer"
         );
 
         assert_eq!(
-            Commands::AutoInstaller(IsoType::Network).to_string(),
+            Commands::AutoInstaller(IsoType::Network {
+                iso: Commands::default_download_path(),
+                out: NETWORK_AUTOINSTALL_ISO_FILE_NAME.into(),
+                url: Some("http://10.0.0.1:8000/answer".into()),
+                cert_fingerprint: None,
+                kernel_args: Vec::new(),
+                volume_label: None,
+                backend: IsoBackend::Auto,
+                dry_run: false,
+                smoke_test: false,
+                sign_key: None,
+            })
+            .to_string(),
             "network-installer"
         );
 
+        assert_eq!(
+            Commands::Iso(IsoCommand::ListVersions {
+                mirror: None,
+                channel: IsoChannel::Stable,
+            })
+            .to_string(),
+            "iso list-versions"
+        );
+        assert_eq!(
+            Commands::Iso(IsoCommand::Verify {
+                path: Commands::default_download_path(),
+                key: None,
+            })
+            .to_string(),
+            "iso verify"
+        );
+        assert_eq!(
+            Commands::Iso(IsoCommand::VerifySignature {
+                path: "pve-auto.iso".into(),
+                key: "operator.asc".into(),
+            })
+            .to_string(),
+            "iso verify-signature"
+        );
+        assert_eq!(
+            Commands::Iso(IsoCommand::Inspect {
+                path: Commands::default_download_path(),
+            })
+            .to_string(),
+            "iso inspect"
+        );
+        assert_eq!(
+            Commands::Iso(IsoCommand::Prune { keep: Some(1) }).to_string(),
+            "iso prune"
+        );
+        assert_eq!(
+            Commands::Bundle(BundleCommand::Export {
+                iso: "pve.iso".into(),
+                answers: vec!["answer.toml".into()],
+                key: None,
+                out: "pve.tar".into(),
+            })
+            .to_string(),
+            "bundle export"
+        );
+        assert_eq!(
+            Commands::Bundle(BundleCommand::Import {
+                bundle: "pve.tar".into(),
+                dir: "pve".into(),
+                key: None,
+            })
+            .to_string(),
+            "bundle import"
+        );
         assert_eq!(Commands::ServeAnswers.to_string(), "serve-answers");
+        assert_eq!(
+            Commands::Answer(AnswerCommand::Validate {
+                path: "answer.toml".into(),
+                format: AnswerFormat::Toml,
+                strict: false,
+                assistant: false,
+            })
+            .to_string(),
+            "answer validate"
+        );
+        assert_eq!(
+            Commands::Answer(AnswerCommand::Lint {
+                path: "answer.toml".into(),
+                format: AnswerFormat::Toml,
+                allow: vec![],
+            })
+            .to_string(),
+            "answer lint"
+        );
+        assert_eq!(
+            Commands::Answer(AnswerCommand::New {
+                interactive: true,
+                preset: None,
+                out: "answer.toml".into(),
+                format: AnswerFormat::Toml,
+                encrypt: None,
+            })
+            .to_string(),
+            "answer new"
+        );
+        assert_eq!(
+            Commands::Answer(AnswerCommand::Convert {
+                path: "answer.json".into(),
+                format: AnswerFormat::Json,
+            })
+            .to_string(),
+            "answer convert"
+        );
+        assert_eq!(
+            Commands::Answer(AnswerCommand::Render {
+                path: "template.toml".into(),
+                format: AnswerFormat::Toml,
+                vars: BTreeMap::new(),
+            })
+            .to_string(),
+            "answer render"
+        );
+        assert_eq!(
+            Commands::Answer(AnswerCommand::Diff {
+                old: "a.toml".into(),
+                new: "b.toml".into(),
+                format: AnswerFormat::Toml,
+            })
+            .to_string(),
+            "answer diff"
+        );
+        assert_eq!(
+            Commands::Answer(AnswerCommand::Schema).to_string(),
+            "answer schema"
+        );
+        assert_eq!(
+            Commands::Answer(AnswerCommand::Fetch {
+                url: "http://10.0.0.1/answer".into(),
+                format: AnswerFormat::Toml,
+                save: None,
+            })
+            .to_string(),
+            "answer fetch"
+        );
+        assert_eq!(
+            Commands::Answer(AnswerCommand::AddSshKeys {
+                path: "answer.toml".into(),
+                format: AnswerFormat::Toml,
+                sources: vec![SshKeySource::GitHub("octocat".into())],
+            })
+            .to_string(),
+            "answer add-ssh-keys"
+        );
+        assert_eq!(
+            SshKeySource::GitLab("jane".into()).to_string(),
+            "gitlab:jane"
+        );
+        assert_eq!(
+            Commands::Answer(AnswerCommand::Profile(ProfileCommand::List)).to_string(),
+            "answer profile list"
+        );
+        assert_eq!(
+            Commands::HashPassword {
+                method: PasswordHashFormat::Sha256,
+                rounds: Some(100_000)
+            }
+            .to_string(),
+            "hash-password"
+        );
+        assert_eq!(
+            Commands::GenerateSshKey {
+                answer: "answer.toml".into(),
+                key: "id_ed25519".into(),
+                format: AnswerFormat::Toml,
+                comment: None,
+            }
+            .to_string(),
+            "generate-ssh-key"
+        );
+        assert_eq!(
+            Commands::Flash {
+                device: "/dev/sdb".into(),
+                iso: AUTOINSTALL_ISO_FILE_NAME.into(),
+                yes: false,
+            }
+            .to_string(),
+            "flash"
+        );
         assert_eq!(Commands::Exit.to_string(), "exit");
         assert_eq!(Commands::Help.to_string(), "help");
     }
@@ -1,5 +1,23 @@
-use crate::auto_installer::commands::constants::Commands;
-use crate::iso::downloader::download_latest_iso;
+use crate::auto_installer::commands::constants::{CommandError, Commands};
+use crate::iso::{
+    alternates::{Alternates, DownloadVia},
+    cache::IsoCache,
+    channel::IsoChannel,
+    checksum::write_sha256_sidecar,
+    config::{resolve_download_page, resolve_mirrors, resolve_retry},
+    constants::ISO_INDEX_URL,
+    downloader::{
+        download_iso_version, download_latest_iso, download_newest_listed_iso, resolve_release,
+        stream_iso,
+    },
+    http::HttpFetcher,
+    mirrors::select_mirror,
+    retry::Retrying,
+    scraper::{DownloadPage, iso_file_name},
+    space::{FAT32_MAX_FILE_SIZE, FilesystemKind, ensure_writable},
+};
+use reqwest::Client;
+use std::{path::Path, time::Duration};
 
 /// Downloads the Proxmox VE ISO to the specified destination path.
 ///
@@ -8,25 +26,231 @@ use crate::iso::downloader::download_latest_iso;
 ///   - If `None`, a default path is used.
 ///   - The default path is resolved by `Commands::default_download_path()`,
 ///     which checks `XDG_DATA_HOME` and falls back to `~/.local/share`.
+/// * `version` — Optional Proxmox VE release (e.g. `8.2-1`); the latest when `None`.
+/// * `mirror` — Optional base URL of a mirror of enterprise.proxmox.com/iso/;
+///   when `None`, `$PVEAUTO_MIRROR` or the configured `download.mirror` is used,
+///   or the fastest of the configured `download.mirrors`.
+/// * `channel` — Whether the latest is the latest release or the latest test
+///   ISO, a beta of the next major release.
+/// * `via` — Fetch the ISO from the URLs in its metalink or torrent, when one
+///   is published next to it, instead of from the ISO URL.
+/// * `quiet` — Suppresses the progress bar and status lines; errors are still
+///   printed.
+/// * `stdout` — Streams the ISO to standard output as it is downloaded
+///   instead of placing it at a path, e.g. to pipe it into `dd`; implies
+///   `quiet`.
 ///
 /// # Returns
-/// This function returns no value and reports download progress and status
-/// via standard output.
+/// `Err(CommandError)` when the download or verification failed, so scripts
+/// can rely on the exit status. Progress is reported on standard error and
+/// status via standard output.
 ///
 /// # Notes
-/// - This function will not overwrite an existing valid ISO file at the destination.
+/// - ISOs are downloaded into the cache of `IsoCache::default_dir()`
+///   (`$XDG_CACHE_HOME/pve-auto/iso`) and hard-linked (or copied) to the
+///   destination; an unchanged upstream ISO is neither fetched nor hashed again.
+/// - When the latest version is the cached one, the destination is reported as
+///   already up to date if it is a link or same-size, same-mtime copy of it,
+///   and otherwise replaced by it; nothing is hashed either way.
+/// - From a mirror, "latest" is the newest version in its directory listing,
+///   and checksums come from its SHA512SUMS or SHA256SUMS.
+/// - With several configured mirrors, each is probed with a small ranged
+///   request and the fastest is used; the result is cached for
+///   `download.benchmark-ttl-secs`.
+/// - Otherwise it is scraped from the download page, which can be overridden
+///   with `$PVEAUTO_DOWNLOAD_PAGE` or `download.page-url`, along with the
+///   pattern the scraped ISO URL must match.
+/// - Test ISOs are not on the download page, so the latest of the test
+///   channel is always the newest beta in the directory listing, of the
+///   mirror or of enterprise.proxmox.com/iso/.
+/// - A `sha256sum`-compatible `<path>.sha256` is written next to the ISO.
+/// - With `stdout`, the ISO is written as it is downloaded and nothing is
+///   stored; a checksum mismatch is only found after the last byte and
+///   reported as a failure, so the consumer must discard the output then.
+/// - A destination on a read-only filesystem is refused before anything is
+///   fetched, one on FAT32 is warned about, and an ISO too large for it is
+///   refused before it is written there.
+/// - Failed requests and interrupted downloads are retried with exponential
+///   backoff as configured in `[download.retry]`.
 /// - Errors during download or verification are printed to standard error.
-pub async fn download_pve_iso(dest_path: Option<String>) {
-    let path = dest_path.unwrap_or_else(Commands::default_download_path);
+pub async fn download_pve_iso(
+    dest_path: Option<String>,
+    version: Option<String>,
+    mirror: Option<String>,
+    channel: IsoChannel,
+    via: DownloadVia,
+    quiet: bool,
+    stdout: bool,
+) -> Result<(), CommandError> {
+    // Status lines would corrupt the stream.
+    let quiet = quiet || stdout;
+    let cache = IsoCache::new(IsoCache::default_dir());
+    let path = match (dest_path, &version) {
+        (Some(path), _) => path,
+        (None, Some(version)) => Commands::default_version_download_path(version),
+        (None, None) => Commands::default_channel_download_path(channel),
+    };
+    if !stdout {
+        check_destination(&path, quiet)?;
+    }
+    let (mirrors, ttl, retry, page) = match resolve_mirrors(mirror.as_deref()).and_then(|mirrors| {
+        let retry = resolve_retry()?;
+        Ok((mirrors, retry, resolve_download_page()?))
+    }) {
+        Ok(((mirrors, ttl), retry, page)) => (mirrors, ttl, retry, page),
+        Err(e) => {
+            eprintln!("Download failed: {}", e);
+            return Err(CommandError);
+        }
+    };
+    let mirror = match &mirrors[..] {
+        [] => None,
+        [mirror] => Some(mirror.clone()),
+        [..] => Some(fastest_mirror(&cache, &mirrors, ttl, version.as_deref(), quiet).await?),
+    };
+    let http = Alternates::new(Retrying::new(Client::new(), retry, quiet), via, quiet);
+    if let (Some(mirror), false) = (&mirror, quiet) {
+        println!("Using mirror {}", mirror);
+    }
+    if stdout {
+        return stream_to_stdout(
+            &http,
+            &cache,
+            &page,
+            mirror.as_deref(),
+            version.as_deref(),
+            channel,
+        )
+        .await;
+    }
 
-    println!("Downloading Proxmox VE @ Latest -> {}", path);
-    let result = download_latest_iso(&path, None).await;
+    let result = match (&version, &mirror, channel) {
+        (Some(version), ..) => {
+            if !quiet {
+                println!("Downloading Proxmox VE @ {} -> {}", version, path);
+            }
+            let index_url = mirror.as_deref().unwrap_or(ISO_INDEX_URL);
+            download_iso_version(&http, &cache, index_url, version, &path, quiet).await
+        }
+        (None, mirror, channel) if mirror.is_some() || channel == IsoChannel::Test => {
+            let latest = match channel {
+                IsoChannel::Stable => "Latest",
+                IsoChannel::Test => "Latest test ISO",
+            };
+            if !quiet {
+                println!("Downloading Proxmox VE @ {} -> {}", latest, path);
+            }
+            let index_url = mirror.as_deref().unwrap_or(ISO_INDEX_URL);
+            download_newest_listed_iso(&http, &cache, index_url, channel, &path, quiet)
+                .await
+                .map(|(version, path, sha256, downloaded)| {
+                    if !quiet {
+                        match mirror {
+                            Some(_) => println!("{} on mirror: {}", latest, version),
+                            None => println!("{}: {}", latest, version),
+                        }
+                    }
+                    (path, sha256, downloaded)
+                })
+        }
+        (None, ..) => {
+            if !quiet {
+                println!("Downloading Proxmox VE @ Latest -> {}", path);
+            }
+            download_latest_iso(&http, &cache, &page, &path, quiet).await
+        }
+    };
     match result {
-        Ok((_path, _sha256, downloaded)) => {
-            if downloaded {
+        Ok((path, sha256, downloaded)) => {
+            if downloaded && !quiet {
                 println!("Download completed successfully.");
             }
+            let sidecar = write_sha256_sidecar(Path::new(&path), &sha256).map_err(|e| {
+                eprintln!("Failed to write the checksum of {}: {}", path, e);
+                CommandError
+            })?;
+            if !quiet {
+                println!("Wrote {}", sidecar.display());
+            }
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("Download failed: {}", e);
+            Err(CommandError)
         }
-        Err(e) => eprintln!("Download failed: {}", e),
     }
 }
+
+/// Refuses a `path` on a read-only filesystem and warns about one on FAT32,
+/// which the ISO might outgrow.
+fn check_destination(path: &str, quiet: bool) -> Result<(), CommandError> {
+    match ensure_writable(Path::new(path), None) {
+        Ok(Some(fs)) if fs.kind == FilesystemKind::Fat32 && !quiet => {
+            println!(
+                "Warning: {} is on FAT32, which holds files up to {} bytes; \
+                 a larger ISO is refused before it is written there.",
+                path, FAT32_MAX_FILE_SIZE
+            );
+            Ok(())
+        }
+        Ok(_) => Ok(()),
+        Err(e) => {
+            eprintln!("Download failed: {}", e);
+            Err(CommandError)
+        }
+    }
+}
+
+/// Writes the ISO `download --stdout` names to standard output as it is
+/// downloaded; see [`stream_iso`].
+async fn stream_to_stdout(
+    http: &impl HttpFetcher,
+    cache: &IsoCache,
+    page: &DownloadPage,
+    mirror: Option<&str>,
+    version: Option<&str>,
+    channel: IsoChannel,
+) -> Result<(), CommandError> {
+    let streamed = async {
+        let release = resolve_release(http, page, mirror, version, channel, true).await?;
+        stream_iso(http, cache, &release, &mut std::io::stdout(), true).await
+    };
+    streamed.await.map_err(|e| {
+        eprintln!("Download failed: {}", e);
+        CommandError
+    })
+}
+
+/// Probes `mirrors` with the ISO of `version`, or their listing for the
+/// latest one, unless a benchmark younger than `ttl` is cached, and returns
+/// the fastest.
+async fn fastest_mirror(
+    cache: &IsoCache,
+    mirrors: &[String],
+    ttl: Duration,
+    version: Option<&str>,
+    quiet: bool,
+) -> Result<String, CommandError> {
+    if !quiet {
+        println!("Picking the fastest of {} mirrors", mirrors.len());
+    }
+    let path = version.map(iso_file_name).unwrap_or_default();
+    let benchmark = select_mirror(&Client::new(), cache, mirrors, &path, ttl, |probe| {
+        if quiet {
+            return;
+        }
+        match probe.bytes_per_sec {
+            Some(speed) => println!("  {}: {:.1} MB/s", probe.mirror, speed as f64 / 1e6),
+            None => println!("  {}: no answer", probe.mirror),
+        }
+    })
+    .await
+    .map_err(|e| {
+        eprintln!("Download failed: {}", e);
+        CommandError
+    })?;
+    Ok(benchmark
+        .fastest()
+        .expect("a selected benchmark has an answering mirror")
+        .to_string())
+}
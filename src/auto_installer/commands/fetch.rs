@@ -0,0 +1,170 @@
+use crate::answer_file::{AnswerFile, AnswerFormat, ProfileStore, signing::SIGNATURE_EXTENSION};
+use crate::auto_installer::commands::{
+    answer::{decode_answer, report_diagnostics},
+    constants::CommandError,
+    signature::trusted_keys_from_env,
+};
+use reqwest::{Client, Url};
+use std::time::Duration;
+
+/// Upper bound for the whole request, so a stalled server does not hang the CLI.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `answer fetch`: downloads the answer file at `url`, validates it like
+/// `answer validate` and, with `save`, stores it as that profile.
+///
+/// # Returns
+/// `Ok(())` when the answer is valid (and saved). Download failures,
+/// non-2xx responses and invalid answers print to standard error and return
+/// `Err(CommandError)`; nothing is saved in that case.
+///
+/// # Notes
+/// - Only `http` and `https` URLs are accepted.
+/// - Encrypted (age) responses are decrypted like local files.
+/// - When `$PVEAUTO_TRUSTED_KEYS` is set, `<url>.sig` is fetched too and must
+///   be a valid signature by one of those keys.
+pub async fn fetch_answer_file(
+    store: &ProfileStore,
+    url: &str,
+    format: AnswerFormat,
+    save: Option<&str>,
+) -> Result<(), CommandError> {
+    let parsed = Url::parse(url)
+        .ok()
+        .filter(|u| matches!(u.scheme(), "http" | "https"))
+        .ok_or_else(|| {
+            eprintln!("Not an http(s) URL: {}", url);
+            CommandError
+        })?;
+
+    if let Some(name) = save {
+        store.path(name).map_err(|e| {
+            eprintln!("{}", e);
+            CommandError
+        })?;
+    }
+
+    let trusted = trusted_keys_from_env()?;
+    let mut signature_url = parsed.clone();
+    signature_url.set_path(&format!("{}.{}", parsed.path(), SIGNATURE_EXTENSION));
+
+    let bytes = download(parsed).await.map_err(|e| {
+        eprintln!("Failed to fetch {}: {}", url, e);
+        CommandError
+    })?;
+    if let Some(keys) = trusted {
+        let signature = download(signature_url.clone()).await.map_err(|e| {
+            eprintln!("Failed to fetch {}: {}", signature_url, e);
+            CommandError
+        })?;
+        keys.verify(&bytes, &String::from_utf8_lossy(&signature))
+            .map_err(|e| {
+                eprintln!("{}: {}", url, e);
+                CommandError
+            })?;
+    }
+    let contents = decode_answer(bytes, url)?;
+
+    report_diagnostics(url, &contents, format)?;
+
+    if let Some(name) = save {
+        let answer = AnswerFile::from_str_as(&contents, format).map_err(|e| {
+            eprintln!("{}: {}", url, e);
+            CommandError
+        })?;
+        let saved = store.save(name, &answer).map_err(|e| {
+            eprintln!("{}", e);
+            CommandError
+        })?;
+        println!("Saved profile {} to {}", name, saved.display());
+    }
+    Ok(())
+}
+
+async fn download(url: Url) -> Result<Vec<u8>, reqwest::Error> {
+    let client = Client::builder().timeout(FETCH_TIMEOUT).build()?;
+    let resp = client.get(url).send().await?.error_for_status()?;
+    Ok(resp.bytes().await?.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+
+    /// Serves one HTTP response with `status` and `body`, returning its URL.
+    fn serve_once(status: &'static str, body: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        format!("http://{}/answer.toml", addr)
+    }
+
+    fn temp_store(name: &str) -> ProfileStore {
+        let root =
+            std::env::temp_dir().join(format!("pveauto-fetch-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&root);
+        ProfileStore::new(root)
+    }
+
+    #[tokio::test]
+    async fn valid_answer_is_fetched_and_saved() {
+        let store = temp_store("valid");
+        let url = serve_once("200 OK", AnswerFile::default().to_toml_string().unwrap());
+
+        assert_eq!(
+            fetch_answer_file(&store, &url, AnswerFormat::Toml, Some("served")).await,
+            Ok(())
+        );
+        assert_eq!(store.load("served").unwrap(), AnswerFile::default());
+        std::fs::remove_dir_all(store.root()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn invalid_answer_is_not_saved() {
+        let store = temp_store("invalid");
+        let body = "[global]\nfqdn = \"nope\"\n[network]\n[disk-setup]\ndisk-list = [\"sda\"]\n";
+        let url = serve_once("200 OK", body.to_string());
+
+        assert_eq!(
+            fetch_answer_file(&store, &url, AnswerFormat::Toml, Some("served")).await,
+            Err(CommandError)
+        );
+        assert!(store.list().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn error_status_fails() {
+        let store = temp_store("status");
+        let url = serve_once("404 Not Found", String::new());
+        assert_eq!(
+            fetch_answer_file(&store, &url, AnswerFormat::Toml, None).await,
+            Err(CommandError)
+        );
+    }
+
+    #[tokio::test]
+    async fn non_http_urls_are_rejected() {
+        let store = temp_store("scheme");
+        for url in ["file:///etc/passwd", "answer.toml"] {
+            assert_eq!(
+                fetch_answer_file(&store, url, AnswerFormat::Toml, None).await,
+                Err(CommandError)
+            );
+        }
+    }
+}
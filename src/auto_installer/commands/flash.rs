@@ -0,0 +1,129 @@
+use crate::auto_installer::commands::constants::CommandError;
+use crate::iso::flash::{BlockDevice, flash_image};
+use std::{
+    fs,
+    io::{self, BufRead, IsTerminal, Write},
+    path::Path,
+};
+
+/// `flash`: writes the ISO at `iso` to the block device `device` and reads it
+/// back to verify it.
+///
+/// Unless `yes`, the device's model and size are shown and its name must be
+/// typed to continue; without a terminal to ask on, `--yes` is required.
+///
+/// # Returns
+/// `Err(CommandError)` when the device is mounted, too small or not a block
+/// device, when the confirmation is declined, or when writing or verifying
+/// fails.
+pub fn flash_iso(device: &str, iso: &str, yes: bool) -> Result<(), CommandError> {
+    let len = fs::metadata(iso)
+        .map_err(|e| {
+            eprintln!("{}: {}", iso, e);
+            CommandError
+        })?
+        .len();
+    let target = BlockDevice::probe(Path::new(device)).map_err(|e| {
+        eprintln!("{}", e);
+        CommandError
+    })?;
+    let mounts = fs::read_to_string("/proc/mounts").unwrap_or_default();
+    target.check_writable(len, &mounts).map_err(|e| {
+        eprintln!("{}", e);
+        CommandError
+    })?;
+
+    println!("{}", target.describe());
+    if !yes {
+        let stdin = io::stdin();
+        if !stdin.is_terminal() {
+            eprintln!("Refusing to erase {} without --yes", target.path.display());
+            return Err(CommandError);
+        }
+        let confirmed = confirm(stdin.lock(), io::stdout(), &target, iso).map_err(|e| {
+            eprintln!("could not read confirmation: {}", e);
+            CommandError
+        })?;
+        if !confirmed {
+            eprintln!("Aborted; {} was not changed", target.path.display());
+            return Err(CommandError);
+        }
+    }
+
+    println!("Writing {} to {}", iso, target.path.display());
+    let sha256 = flash_image(Path::new(iso), &target.path, false).map_err(|e| {
+        eprintln!("{}", e);
+        CommandError
+    })?;
+    println!("Verified {} bytes on {}", len, target.path.display());
+    println!("SHA256: {}", sha256);
+    Ok(())
+}
+
+/// Warns that `target` will be erased and asks for its kernel name, e.g. `sdb`.
+///
+/// # Returns
+/// `true` only when the typed name matches the device.
+fn confirm<R: BufRead, W: Write>(
+    mut input: R,
+    mut output: W,
+    target: &BlockDevice,
+    iso: &str,
+) -> io::Result<bool> {
+    write!(
+        output,
+        "All data on {} will be replaced by {}.\nType '{}' to continue: ",
+        target.path.display(),
+        iso,
+        target.name
+    )?;
+    output.flush()?;
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    Ok(line.trim() == target.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn sdb() -> BlockDevice {
+        BlockDevice {
+            path: "/dev/sdb".into(),
+            name: "sdb".into(),
+            vendor: None,
+            model: Some("Ultra".into()),
+            size: Some(8 << 30),
+            removable: true,
+        }
+    }
+
+    #[test]
+    fn only_the_device_name_confirms() {
+        let mut output = Vec::new();
+        assert!(
+            confirm(
+                Cursor::new(b"sdb\n".to_vec()),
+                &mut output,
+                &sdb(),
+                "pve.iso"
+            )
+            .unwrap()
+        );
+        assert!(
+            String::from_utf8(output)
+                .unwrap()
+                .contains("Type 'sdb' to continue")
+        );
+
+        for answer in ["y\n", "yes\n", "sda\n", ""] {
+            let input = Cursor::new(answer.as_bytes().to_vec());
+            assert!(
+                !confirm(input, Vec::new(), &sdb(), "pve.iso").unwrap(),
+                "{:?}",
+                answer
+            );
+        }
+    }
+}
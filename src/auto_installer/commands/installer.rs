@@ -0,0 +1,1927 @@
+use crate::answer_file::sections::post_installation_webhook::CERT_FINGERPRINT_PATTERN;
+use crate::answer_file::{
+    AnswerFile, AnswerFormat, Assistant,
+    assistant::{ASSISTANT_BINARY, ASSISTANT_ENV},
+    diagnose_str,
+    inventory::Inventory,
+};
+use crate::auto_installer::commands::{
+    answer::{print_diagnostics, read_resolved_answer, resolve_answer_path},
+    constants::{CommandError, InventoryBuild},
+};
+use crate::iso::{
+    assets::bundle_assets,
+    boot::patched_grub_config,
+    checksum::write_sha256_sidecar,
+    constants::{
+        AUTO_INSTALLER_MODE_FILE_NAME, ISO_ANSWER_FILE_NAME, ISO_ASSETS_FILE_NAME,
+        ISO_FIRST_BOOT_FILE_NAME, ISO_GRUB_CONFIG_PATH, SHA256_SIDECAR_SUFFIX,
+    },
+    first_boot::bundle_first_boot_script,
+    inject::{check_volume_label, embed_answer, embed_http_settings, set_volume_label},
+    inspect::check_installer_iso,
+    manifest::{
+        BUILD_MANIFEST_FORMAT, BuildManifest, ManifestAnswer, ManifestFile, manifest_path,
+        timestamp, tool_version,
+    },
+    mode::{AutoInstallerMode, HttpOptions},
+    output::{OutputTemplate, OutputValues},
+    packages::{DebPackage, load_packages},
+    signature::{GPG_BINARY, GPG_ENV, Gpg, signature_path},
+    smoke::{QEMU_BINARY, QEMU_ENV, Qemu, SMOKE_TEST_TIMEOUT, smoke_test},
+    space::{ensure_space, ensure_writable},
+    writer::{IsoBackend, IsoWriter, select_writer},
+};
+use crate::util::{private_dir, private_file, unique_suffix};
+use oxdl::validator::is_valid_url;
+use sha2::{Digest, Sha256};
+use std::{io::Write, path::Path, process::Command, time::SystemTime};
+
+/// What `offline-installer` adds to the ISO besides the answer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OfflineExtras {
+    /// Debian packages for the ISO's package pool (`--deb`).
+    pub packages: Vec<String>,
+    /// Manifest of post-install assets (`--assets`).
+    pub assets: Option<String>,
+    /// Appended to the kernel command line of the automated boot entry (`--kernel-args`).
+    pub kernel_args: Vec<String>,
+    /// Volume identifier of the written ISO (`--volume-label`).
+    pub volume_label: Option<String>,
+}
+
+/// How the installer commands write an ISO.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BuildOptions<'a> {
+    /// Adds files to the ISO (`--backend`).
+    pub backend: IsoBackend,
+    /// Print the plan instead of writing the ISO (`--dry-run`).
+    pub dry_run: bool,
+    /// Boot the written ISO in QEMU until the installation starts (`--smoke-test`).
+    pub smoke_test: bool,
+    /// GnuPG key signing the written ISO (`--sign-key`).
+    pub sign_key: Option<&'a str>,
+}
+
+/// The programs an installer build runs: the assistant, when installed, the
+/// writer adding the files it does not add and, when smoke testing, QEMU.
+#[derive(Clone, Copy)]
+struct BuildTools<'a> {
+    assistant: Option<&'a Assistant>,
+    writer: &'a dyn IsoWriter,
+    qemu: Option<&'a Qemu>,
+    /// GnuPG and the key signing the written ISO, with `--sign-key`.
+    signer: Option<(&'a Gpg, &'a str)>,
+}
+
+/// Runs `build` with the tools `options` ask for, reporting a missing one.
+fn with_tools(
+    options: BuildOptions,
+    build: impl FnOnce(&BuildTools) -> Result<(), CommandError>,
+) -> Result<(), CommandError> {
+    let writer = select_writer(options.backend).map_err(|e| {
+        eprintln!("{}", e);
+        CommandError
+    })?;
+    let qemu = match options.smoke_test {
+        true => Some(Qemu::locate().ok_or_else(|| {
+            eprintln!(
+                "{} not found (${}); install QEMU to use --smoke-test",
+                QEMU_BINARY, QEMU_ENV
+            );
+            CommandError
+        })?),
+        false => None,
+    };
+    let gpg = match options.sign_key {
+        Some(_) => Some(Gpg::locate().ok_or_else(|| {
+            eprintln!(
+                "{} not found (${}); install GnuPG to use --sign-key",
+                GPG_BINARY, GPG_ENV
+            );
+            CommandError
+        })?),
+        None => None,
+    };
+    let assistant = Assistant::locate();
+    build(&BuildTools {
+        assistant: assistant.as_ref(),
+        writer: writer.as_ref(),
+        qemu: qemu.as_ref(),
+        signer: gpg.as_ref().zip(options.sign_key),
+    })
+}
+
+/// `offline-installer`: writes a copy of the Proxmox VE ISO at `iso` to `out`
+/// with the answer at `answer` embedded, so the installer runs unattended
+/// without network access to an answer server.
+///
+/// A local `first-boot.script` is bundled into the ISO as well, resolved
+/// against the answer's directory, and the `extras` are added: packages go
+/// to its package pool, and the post-install assets of a manifest are copied
+/// into the ISO with a generated first-boot script that installs them,
+/// running the answer's own script last; a volume label replaces the
+/// original one, e.g. to tell media apart in IPMI virtual-media menus. The
+/// ISO is prepared by
+/// `proxmox-auto-install-assistant` when [`Assistant::locate`] finds it,
+/// and the files it does not add are written by the `options.backend`
+/// writer. With `options.dry_run`, everything is checked and the plan is
+/// printed instead, and nothing is written.
+///
+/// # Returns
+/// `Ok(())` once `out` is written; otherwise `Err(CommandError)` after the
+/// invalid answer, missing ISO or preparation failure has been reported.
+pub fn build_offline_installer(
+    answer: &str,
+    format: AnswerFormat,
+    iso: &str,
+    out: &str,
+    extras: &OfflineExtras,
+    options: BuildOptions,
+) -> Result<(), CommandError> {
+    with_tools(options, |tools| {
+        prepare_offline_iso(tools, answer, format, iso, out, extras, options.dry_run)
+    })
+}
+
+/// [`build_offline_installer`] with the given tools.
+fn prepare_offline_iso(
+    tools: &BuildTools,
+    answer: &str,
+    format: AnswerFormat,
+    iso: &str,
+    out: &str,
+    extras: &OfflineExtras,
+    dry_run: bool,
+) -> Result<(), CommandError> {
+    let build = OfflineBuild::prepare(*tools, iso, extras, dry_run)?;
+
+    println!("Validating {}...", answer);
+    let contents = read_resolved_answer(answer, format)?;
+    let diagnostics = diagnose_str(&contents, format);
+    if !diagnostics.is_empty() {
+        print_diagnostics(answer, &diagnostics);
+        return Err(CommandError);
+    }
+    let parsed = AnswerFile::from_str_as(&contents, format).map_err(|e| {
+        eprintln!("{}: {}", answer, e);
+        CommandError
+    })?;
+
+    let file = resolve_answer_path(answer)?;
+    let out = output_path(
+        out,
+        &OutputValues {
+            version: build.version.clone(),
+            fqdn: Some(parsed.global.fqdn.clone()),
+            name: None,
+        },
+    )?;
+    build.write(
+        parsed,
+        answer,
+        file.parent().unwrap_or(Path::new(".")),
+        &out,
+    )
+}
+
+/// `offline-installer --inventory`: writes one offline installer ISO per
+/// host of the inventory `targets.inventory` to `targets.out_dir`, named by
+/// the [`OutputTemplate`] `targets.file_name`, `{name}.iso` by default, embedding
+/// the answer built for the host from the base answer `template` as
+/// `answer generate` does. A host's name defaults to its FQDN, which may be
+/// derived from its MAC.
+///
+/// Every host's answer is generated and validated before the first ISO is
+/// built; a failed build does not stop the others, and a table of all
+/// results is printed at the end. With `options.dry_run`, each host's plan
+/// is printed instead and nothing is written.
+///
+/// # Returns
+/// `Err(CommandError)` when the inventory or an answer is invalid, or any
+/// build failed.
+pub fn build_offline_installers(
+    template: &str,
+    format: AnswerFormat,
+    targets: &InventoryBuild,
+    iso: &str,
+    extras: &OfflineExtras,
+    options: BuildOptions,
+) -> Result<(), CommandError> {
+    with_tools(options, |tools| {
+        prepare_offline_isos(
+            tools,
+            template,
+            format,
+            targets,
+            iso,
+            extras,
+            options.dry_run,
+        )
+    })
+}
+
+/// [`build_offline_installers`] with the given tools.
+fn prepare_offline_isos(
+    tools: &BuildTools,
+    template: &str,
+    format: AnswerFormat,
+    targets: &InventoryBuild,
+    iso: &str,
+    extras: &OfflineExtras,
+    dry_run: bool,
+) -> Result<(), CommandError> {
+    let build = OfflineBuild::prepare(*tools, iso, extras, dry_run)?;
+    let (inventory, out_dir) = (targets.inventory.as_str(), targets.out_dir.as_str());
+
+    println!("Generating answers from {} for {}...", template, inventory);
+    let base = read_resolved_answer(template, format)?;
+    let hosts = std::fs::read_to_string(inventory).map_err(|e| {
+        eprintln!("Failed to read {}: {}", inventory, e);
+        CommandError
+    })?;
+    let inventory_file = Inventory::from_toml_str(&hosts).map_err(|e| {
+        eprintln!("{}: {}", inventory, e);
+        CommandError
+    })?;
+    let generated = inventory_file.generate(&base, format).map_err(|e| {
+        eprintln!("{}: {}", inventory, e);
+        CommandError
+    })?;
+    if !dry_run {
+        std::fs::create_dir_all(out_dir).map_err(|e| {
+            eprintln!("Failed to create {}: {}", out_dir, e);
+            CommandError
+        })?;
+    }
+
+    let mut outs: Vec<String> = Vec::with_capacity(generated.len());
+    for host in &generated {
+        let file_name = output_path(
+            &targets.file_name,
+            &OutputValues {
+                version: build.version.clone(),
+                fqdn: Some(host.answer.global.fqdn.clone()),
+                name: Some(host.name.clone()),
+            },
+        )?;
+        let out = Path::new(out_dir).join(file_name);
+        let out = out.to_string_lossy().into_owned();
+        if let Some(other) = outs.iter().position(|o| *o == out) {
+            eprintln!(
+                "{} names {} for both {} and {}; add a placeholder such as {{name}}",
+                targets.file_name, out, generated[other].name, host.name
+            );
+            return Err(CommandError);
+        }
+        outs.push(out);
+    }
+
+    let file = resolve_answer_path(template)?;
+    let base_dir = file.parent().unwrap_or(Path::new("."));
+    let mut rows = Vec::with_capacity(generated.len());
+    for ((host, entry), out) in generated.into_iter().zip(&inventory_file.hosts).zip(outs) {
+        println!();
+        println!("[{}] {}", host.name, host.answer.global.fqdn);
+        let fqdn = host.answer.global.fqdn.clone();
+        let built = build.write(host.answer, &host.name, base_dir, &out).is_ok();
+        let status = match (built, dry_run) {
+            (true, false) => "ok",
+            (true, true) => "planned",
+            (false, _) => "FAILED",
+        };
+        rows.push([
+            host.name,
+            fqdn,
+            entry.mac.clone().unwrap_or_else(|| "-".into()),
+            status.to_string(),
+            if built { out } else { "-".into() },
+        ]);
+    }
+
+    let failed = rows.iter().filter(|row| row[3] == "FAILED").count();
+    println!();
+    print_table(["HOST", "FQDN", "MAC", "STATUS", "ISO"], &rows);
+    let done = if dry_run { "planned" } else { "built" };
+    println!("{} of {} ISOs {}", rows.len() - failed, rows.len(), done);
+    if failed > 0 {
+        return Err(CommandError);
+    }
+    Ok(())
+}
+
+/// Prints `rows` under `header` in left-aligned columns.
+fn print_table<const N: usize>(header: [&str; N], rows: &[[String; N]]) {
+    let mut widths = header.map(str::len);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let line = |cells: Vec<&str>| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("{}", padded.join("  ").trim_end());
+    };
+    line(header.to_vec());
+    for row in rows {
+        line(row.iter().map(String::as_str).collect());
+    }
+}
+
+/// What every ISO of an `offline-installer` run shares: the base ISO and the
+/// extras, loaded once.
+struct OfflineBuild<'a> {
+    tools: BuildTools<'a>,
+    iso: &'a str,
+    /// The Proxmox VE version of the base ISO, when its `.cd-info` has one.
+    version: Option<String>,
+    /// The base ISO's size and SHA256 for the manifests; not taken on dry runs.
+    base: Option<ManifestFile>,
+    extras: &'a OfflineExtras,
+    packages: Vec<DebPackage>,
+    grub_cfg: Option<Vec<u8>>,
+    /// Print the plan of each ISO instead of writing it.
+    dry_run: bool,
+}
+
+impl<'a> OfflineBuild<'a> {
+    /// Checks the base ISO and label, and loads the packages and boot configuration.
+    fn prepare(
+        tools: BuildTools<'a>,
+        iso: &'a str,
+        extras: &'a OfflineExtras,
+        dry_run: bool,
+    ) -> Result<Self, CommandError> {
+        let version = check_base_iso(iso)?;
+        check_label(extras.volume_label.as_deref())?;
+        let packages = load_packages(&extras.packages).map_err(|e| {
+            eprintln!("{}", e);
+            CommandError
+        })?;
+        let grub_cfg = grub_config_with(iso, &extras.kernel_args)?;
+        let base = match dry_run {
+            true => None,
+            false => Some(hash_base_iso(iso)?),
+        };
+        Ok(Self {
+            tools,
+            iso,
+            version,
+            base,
+            extras,
+            packages,
+            grub_cfg,
+            dry_run,
+        })
+    }
+
+    /// Writes the ISO with the validated `parsed` answer to `out`; `answer`
+    /// names it in messages and `base_dir` resolves its first-boot script.
+    fn write(
+        &self,
+        mut parsed: AnswerFile,
+        answer: &str,
+        base_dir: &Path,
+        out: &str,
+    ) -> Result<(), CommandError> {
+        let started = SystemTime::now();
+        let (iso, extras) = (self.iso, self.extras);
+        let BuildTools {
+            assistant, writer, ..
+        } = self.tools;
+        let mut script = bundle_first_boot_script(&mut parsed, base_dir).map_err(|e| {
+            eprintln!("{}: {}", answer, e);
+            CommandError
+        })?;
+        if let Some(script) = &script {
+            println!("Bundling first-boot script {}", script.path.display());
+        }
+        let archive = match &extras.assets {
+            Some(manifest) => {
+                println!("Bundling post-install assets of {}", manifest);
+                let bundle = bundle_assets(Path::new(manifest), &mut parsed, script.take())
+                    .map_err(|e| {
+                        eprintln!("{}", e);
+                        CommandError
+                    })?;
+                script = Some(bundle.script);
+                Some(bundle.archive)
+            }
+            None => None,
+        };
+        for package in &self.packages {
+            println!("Adding {} to the package pool", package.file_name);
+        }
+        let pool_paths: Vec<String> = self.packages.iter().map(DebPackage::iso_path).collect();
+        let mut extra: Vec<(&str, &[u8])> = pool_paths
+            .iter()
+            .zip(&self.packages)
+            .map(|(path, package)| (path.as_str(), package.contents.as_slice()))
+            .collect();
+        if let Some(archive) = &archive {
+            extra.push((ISO_ASSETS_FILE_NAME, archive));
+        }
+        if let Some(grub_cfg) = &self.grub_cfg {
+            extra.push((ISO_GRUB_CONFIG_PATH, grub_cfg));
+        }
+
+        let toml = parsed.to_toml_string().map_err(|e| {
+            eprintln!("{}: {}", answer, e);
+            CommandError
+        })?;
+
+        let mode = AutoInstallerMode::iso().to_toml_string();
+        let mut embedded = vec![
+            (AUTO_INSTALLER_MODE_FILE_NAME, mode.as_bytes()),
+            (ISO_ANSWER_FILE_NAME, toml.as_bytes()),
+        ];
+        if let Some(script) = &script {
+            embedded.push((ISO_FIRST_BOOT_FILE_NAME, script.contents.as_bytes()));
+        }
+        if self.dry_run {
+            let prepared = assistant.map(|assistant| {
+                let first_boot = match (&archive, &script) {
+                    (Some(_), Some(_)) => Some(Path::new("<first-boot script>")),
+                    (_, script) => script.as_ref().map(|s| s.path.as_path()),
+                };
+                assistant.prepare_iso_command(
+                    Path::new(iso),
+                    Path::new("<answer.toml>"),
+                    first_boot,
+                    Path::new(out),
+                )
+            });
+            BuildPlan {
+                iso,
+                answer: format!(
+                    "embedded as {} (SHA256 {:x})",
+                    ISO_ANSWER_FILE_NAME,
+                    Sha256::digest(&toml)
+                ),
+                files: plan_files(assistant, &embedded, &extra),
+                kernel_args: &extras.kernel_args,
+                volume_label: extras.volume_label.as_deref(),
+                out,
+                signed: self.tools.signer.is_some(),
+                commands: plan_commands(&self.tools, prepared, iso, out, &embedded, &extra),
+            }
+            .print();
+            return Ok(());
+        }
+
+        check_space(out, iso, assistant.is_some(), &extra)?;
+        match assistant {
+            Some(assistant) => {
+                println!(
+                    "Embedding answer into {} with {}...",
+                    iso,
+                    assistant.program().display()
+                );
+                // The assets' script is generated; the assistant needs it in a
+                // file. It runs as root on every installed host, so it goes to
+                // a private temp dir no other user can plant or swap it in.
+                let generated = match (&archive, &script) {
+                    (Some(_), Some(script)) => {
+                        let dir = std::env::temp_dir().join(format!(
+                            "pveauto-first-boot-script-{}-{}",
+                            std::process::id(),
+                            unique_suffix()
+                        ));
+                        let path = dir.join("first-boot.sh");
+                        let written =
+                            private_dir(&dir)
+                                .map_err(|e| (dir.clone(), e))
+                                .and_then(|()| {
+                                    private_file(&path)
+                                        .and_then(|mut file| {
+                                            file.write_all(script.contents.as_bytes())
+                                        })
+                                        .map_err(|e| (path.clone(), e))
+                                });
+                        if let Err((failed, e)) = written {
+                            let _ = std::fs::remove_dir_all(&dir);
+                            eprintln!("{}: {}", failed.display(), e);
+                            return Err(CommandError);
+                        }
+                        Some((dir, path))
+                    }
+                    _ => None,
+                };
+                let result = assistant.prepare_iso(
+                    Path::new(iso),
+                    &toml,
+                    generated
+                        .as_ref()
+                        .map(|(_, path)| path.as_path())
+                        .or(script.as_ref().map(|s| s.path.as_path())),
+                    Path::new(out),
+                );
+                if let Some((dir, _)) = &generated {
+                    let _ = std::fs::remove_dir_all(dir);
+                }
+                let failure = result.map_err(|e| {
+                    eprintln!("{}: {}", assistant.program().display(), e);
+                    CommandError
+                })?;
+                if let Some(message) = failure {
+                    eprintln!("Failed to prepare {}: {}", out, message);
+                    return Err(CommandError);
+                }
+                add_files(writer, out, &extra)?;
+            }
+            None => {
+                println!(
+                    "{} not found (${}); embedding answer into {} with {}...",
+                    ASSISTANT_BINARY,
+                    ASSISTANT_ENV,
+                    iso,
+                    writer.describe()
+                );
+                embed_answer(
+                    writer,
+                    Path::new(iso),
+                    Path::new(out),
+                    &toml,
+                    script.as_ref(),
+                    &extra,
+                )
+                .map_err(|e| {
+                    eprintln!("Failed to prepare {}: {}", out, e);
+                    CommandError
+                })?;
+            }
+        }
+
+        apply_label(out, extras.volume_label.as_deref())?;
+        finish_iso(
+            &self.tools,
+            out,
+            BuildRecord {
+                started,
+                base: self.base.as_ref(),
+                version: self.version.clone(),
+                answer: ManifestAnswer::Iso {
+                    source: answer.to_string(),
+                    sha256: format!("{:x}", Sha256::digest(&toml)),
+                },
+                embedded: &embedded,
+                extra: &extra,
+                kernel_args: &extras.kernel_args,
+                volume_label: extras.volume_label.as_deref(),
+            },
+        )
+    }
+}
+
+/// `network-installer`: writes a copy of the Proxmox VE ISO at `iso` to `out`
+/// whose installer fetches its answer from the answer server at `url`, e.g.
+/// one run by `serve-answers`.
+///
+/// Without `url` the installer discovers the server through DHCP or DNS.
+/// `cert_fingerprint` pins a self-signed HTTPS certificate, `kernel_args`
+/// are appended to the automated boot entry and `volume_label` replaces the
+/// ISO's label. Like
+/// [`build_offline_installer`], the assistant is used when installed, the
+/// `options.backend` writer adds the other files, and `options.dry_run`
+/// prints the plan instead of writing anything.
+///
+/// # Returns
+/// `Ok(())` once `out` is written; otherwise `Err(CommandError)` after the
+/// invalid setting, missing ISO or preparation failure has been reported.
+pub fn build_network_installer(
+    iso: &str,
+    out: &str,
+    url: Option<&str>,
+    cert_fingerprint: Option<&str>,
+    kernel_args: &[String],
+    volume_label: Option<&str>,
+    options: BuildOptions,
+) -> Result<(), CommandError> {
+    with_tools(options, |tools| {
+        prepare_network_iso(
+            tools,
+            iso,
+            out,
+            &AutoInstallerMode::http(url, cert_fingerprint).http,
+            kernel_args,
+            volume_label,
+            options.dry_run,
+        )
+    })
+}
+
+/// [`build_network_installer`] with the given tools.
+fn prepare_network_iso(
+    tools: &BuildTools,
+    iso: &str,
+    out: &str,
+    http: &HttpOptions,
+    kernel_args: &[String],
+    volume_label: Option<&str>,
+    dry_run: bool,
+) -> Result<(), CommandError> {
+    let started = SystemTime::now();
+    let BuildTools {
+        assistant, writer, ..
+    } = *tools;
+    let (url, cert_fingerprint) = (http.url.as_deref(), http.cert_fingerprint.as_deref());
+    if let Some(url) = url.filter(|url| !is_valid_url(url)) {
+        eprintln!("Invalid answer URL: {}", url);
+        return Err(CommandError);
+    }
+    if let Some(fp) = cert_fingerprint.filter(|fp| !CERT_FINGERPRINT_PATTERN.is_match(fp)) {
+        eprintln!(
+            "Invalid certificate fingerprint: {}; expected SHA-256 as AB:CD:...",
+            fp
+        );
+        return Err(CommandError);
+    }
+    let version = check_base_iso(iso)?;
+    check_label(volume_label)?;
+    let out = &output_path(
+        out,
+        &OutputValues {
+            version: version.clone(),
+            ..OutputValues::default()
+        },
+    )?;
+    let grub_cfg = grub_config_with(iso, kernel_args)?;
+    let extra: Vec<(&str, &[u8])> = grub_cfg
+        .iter()
+        .map(|grub_cfg| (ISO_GRUB_CONFIG_PATH, grub_cfg.as_slice()))
+        .collect();
+
+    let source = url.unwrap_or("the URL announced by DHCP or DNS");
+    let mode = AutoInstallerMode::http(url, cert_fingerprint).to_toml_string();
+    let embedded = [(AUTO_INSTALLER_MODE_FILE_NAME, mode.as_bytes())];
+    if dry_run {
+        let prepared = assistant.map(|assistant| {
+            assistant.prepare_http_iso_command(
+                Path::new(iso),
+                url,
+                cert_fingerprint,
+                Path::new(out),
+            )
+        });
+        BuildPlan {
+            iso,
+            answer: match cert_fingerprint {
+                Some(fp) => format!("fetched from {}, trusting {}", source, fp),
+                None => format!("fetched from {}", source),
+            },
+            files: plan_files(assistant, &embedded, &extra),
+            kernel_args,
+            volume_label,
+            out,
+            signed: tools.signer.is_some(),
+            commands: plan_commands(tools, prepared, iso, out, &embedded, &extra),
+        }
+        .print();
+        return Ok(());
+    }
+
+    check_space(out, iso, assistant.is_some(), &extra)?;
+    let base = hash_base_iso(iso)?;
+    match assistant {
+        Some(assistant) => {
+            println!(
+                "Configuring {} to fetch its answer from {} with {}...",
+                iso,
+                source,
+                assistant.program().display()
+            );
+            let failure = assistant
+                .prepare_http_iso(Path::new(iso), url, cert_fingerprint, Path::new(out))
+                .map_err(|e| {
+                    eprintln!("{}: {}", assistant.program().display(), e);
+                    CommandError
+                })?;
+            if let Some(message) = failure {
+                eprintln!("Failed to prepare {}: {}", out, message);
+                return Err(CommandError);
+            }
+            add_files(writer, out, &extra)?;
+        }
+        None => {
+            println!(
+                "{} not found (${}); configuring {} to fetch its answer from {} with {}...",
+                ASSISTANT_BINARY,
+                ASSISTANT_ENV,
+                iso,
+                source,
+                writer.describe()
+            );
+            let mode = AutoInstallerMode::http(url, cert_fingerprint);
+            embed_http_settings(writer, Path::new(iso), Path::new(out), &mode, &extra).map_err(
+                |e| {
+                    eprintln!("Failed to prepare {}: {}", out, e);
+                    CommandError
+                },
+            )?;
+        }
+    }
+
+    apply_label(out, volume_label)?;
+    finish_iso(
+        tools,
+        out,
+        BuildRecord {
+            started,
+            base: Some(&base),
+            version,
+            answer: ManifestAnswer::Http {
+                url: url.map(str::to_owned),
+                cert_fingerprint: cert_fingerprint.map(str::to_owned),
+            },
+            embedded: &embedded,
+            extra: &extra,
+            kernel_args,
+            volume_label,
+        },
+    )
+}
+
+/// What an installer build would write, printed by `--dry-run` instead.
+struct BuildPlan<'a> {
+    iso: &'a str,
+    /// Where the installer gets its answer.
+    answer: String,
+    /// The files added to the ISO, with their size and what adds them.
+    files: Vec<String>,
+    kernel_args: &'a [String],
+    volume_label: Option<&'a str>,
+    out: &'a str,
+    /// Whether a signature of `out` is written too.
+    signed: bool,
+    /// The external commands that would be run, as typed in a shell.
+    commands: Vec<String>,
+}
+
+impl BuildPlan<'_> {
+    fn print(&self) {
+        println!("Dry run, nothing is written:");
+        println!("  Base ISO:     {}", describe_iso(self.iso));
+        println!("  Answer:       {}", self.answer);
+        for (i, file) in self.files.iter().enumerate() {
+            let label = if i == 0 { "Files:" } else { "" };
+            println!("  {:<14}{}", label, file);
+        }
+        match self.kernel_args {
+            [] => println!("  Kernel args:  unchanged"),
+            args => println!("  Kernel args:  {} appended", args.join(" ")),
+        }
+        println!(
+            "  Volume label: {}",
+            self.volume_label.unwrap_or("unchanged")
+        );
+        let signature = match self.signed {
+            true => format!(", {}", signature_path(Path::new(self.out)).display()),
+            false => String::new(),
+        };
+        println!(
+            "  Output:       {}, {}{}, {}{}",
+            self.out,
+            self.out,
+            SHA256_SIDECAR_SUFFIX,
+            manifest_path(Path::new(self.out)).display(),
+            signature
+        );
+        if self.commands.is_empty() {
+            println!("  Commands:     none, pveauto writes the ISO itself");
+        }
+        for (i, command) in self.commands.iter().enumerate() {
+            let label = if i == 0 { "Commands:" } else { "" };
+            println!("  {:<14}{}", label, command);
+        }
+    }
+}
+
+/// The path, release and size of the base ISO, as far as it can be read.
+fn describe_iso(iso: &str) -> String {
+    let size = std::fs::metadata(iso).map_or(0, |m| m.len());
+    let release = check_installer_iso(Path::new(iso))
+        .ok()
+        .and_then(|(product, version)| {
+            Some(format!(
+                "{} {}",
+                product.as_deref().unwrap_or("Proxmox VE"),
+                version?
+            ))
+        });
+    let size = format!("{:.2} GiB", size as f64 / (1u64 << 30) as f64);
+    match release {
+        Some(release) => format!("{} ({}, {})", iso, release, size),
+        None => format!("{} ({})", iso, size),
+    }
+}
+
+/// The plan's lines for the `embedded` files, added by the assistant when
+/// there is one, and the `extra` files pveauto adds itself.
+fn plan_files(
+    assistant: Option<&Assistant>,
+    embedded: &[(&str, &[u8])],
+    extra: &[(&str, &[u8])],
+) -> Vec<String> {
+    let by = assistant.map(|assistant| {
+        let program = assistant.program();
+        let name = program.file_name().unwrap_or(program.as_os_str());
+        format!(", by {}", name.to_string_lossy())
+    });
+    let line = |(name, contents): &(&str, &[u8]), by: &str| {
+        format!("{} ({} bytes{})", name, contents.len(), by)
+    };
+    embedded
+        .iter()
+        .map(|file| line(file, by.as_deref().unwrap_or_default()))
+        .chain(extra.iter().map(|file| line(file, "")))
+        .collect()
+}
+
+/// The plan's command lines: the assistant's `prepared` command, when there
+/// is an assistant, the writer's adding the files the assistant does not,
+/// GnuPG's signing the ISO, then QEMU's booting it for a smoke test.
+fn plan_commands(
+    tools: &BuildTools,
+    prepared: Option<Command>,
+    iso: &str,
+    out: &str,
+    embedded: &[(&str, &[u8])],
+    extra: &[(&str, &[u8])],
+) -> Vec<String> {
+    let (source, added) = match prepared {
+        Some(_) => (out, extra.to_vec()),
+        None => (iso, [embedded, extra].concat()),
+    };
+    let paths: Vec<&str> = added.iter().map(|(path, _)| *path).collect();
+    let written = match paths.is_empty() {
+        true => None,
+        false => tools
+            .writer
+            .command(Path::new(source), Path::new(out), &paths),
+    };
+    let signed = tools
+        .signer
+        .map(|(gpg, key)| gpg.sign_command(key, Path::new(out), &signature_path(Path::new(out))));
+    let booted = tools.qemu.map(|qemu| {
+        qemu.boot_command(
+            Path::new(out),
+            Path::new("<scratch disk>"),
+            Path::new("<serial log>"),
+        )
+    });
+    prepared
+        .iter()
+        .chain(&written)
+        .chain(&signed)
+        .chain(&booted)
+        .map(command_line)
+        .collect()
+}
+
+/// `command` as it would be typed in a shell.
+fn command_line(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| {
+            let arg = arg.to_string_lossy();
+            if !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./:=,<>@%+".contains(c))
+            {
+                arg.into_owned()
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The GRUB configuration of `iso` with `kernel_args` appended to its
+/// automated boot entry, or `None` without arguments.
+fn grub_config_with(iso: &str, kernel_args: &[String]) -> Result<Option<Vec<u8>>, CommandError> {
+    if kernel_args.is_empty() {
+        return Ok(None);
+    }
+    let grub_cfg = patched_grub_config(Path::new(iso), kernel_args).map_err(|e| {
+        eprintln!("{}: {}", iso, e);
+        CommandError
+    })?;
+    println!(
+        "Appending `{}` to the kernel command line",
+        kernel_args.join(" ")
+    );
+    Ok(Some(grub_cfg))
+}
+
+/// Adds the `extra` files to the ISO `out` written by the assistant,
+/// removing it on failure.
+fn add_files(
+    writer: &dyn IsoWriter,
+    out: &str,
+    extra: &[(&str, &[u8])],
+) -> Result<(), CommandError> {
+    if extra.is_empty() {
+        return Ok(());
+    }
+    writer
+        .write(Path::new(out), Path::new(out), extra)
+        .map_err(|e| {
+            eprintln!("Failed to add files to {}: {}", out, e);
+            let _ = std::fs::remove_file(out);
+            CommandError
+        })
+}
+
+/// Fails early when the filesystem of `out` cannot hold the ISO built from
+/// `iso` and `extra`: when it is read-only, FAT32 and the ISO 4 GiB or more,
+/// or too full. When the assistant writes `out` and the files are added
+/// afterwards, `out` is rewritten through a second copy, which needs twice
+/// the room; [`ensure_space`] keeps some headroom on top.
+fn check_space(
+    out: &str,
+    iso: &str,
+    assisted: bool,
+    extra: &[(&str, &[u8])],
+) -> Result<(), CommandError> {
+    let size = std::fs::metadata(iso).map_or(0, |meta| meta.len())
+        + extra.iter().map(|(_, data)| data.len() as u64).sum::<u64>();
+    let copies = if assisted && !extra.is_empty() { 2 } else { 1 };
+    ensure_writable(Path::new(out), Some(size))
+        .map(|_| ())
+        .and_then(|_| ensure_space(Path::new(out), copies * size))
+        .map_err(|e| {
+            eprintln!("Cannot write {}: {}", out, e);
+            CommandError
+        })
+}
+
+fn check_label(volume_label: Option<&str>) -> Result<(), CommandError> {
+    volume_label
+        .map_or(Ok(()), check_volume_label)
+        .map_err(|e| {
+            eprintln!("{}", e);
+            CommandError
+        })
+}
+
+/// Sets the volume label of the written ISO `out`, removing it on failure.
+fn apply_label(out: &str, volume_label: Option<&str>) -> Result<(), CommandError> {
+    let Some(label) = volume_label else {
+        return Ok(());
+    };
+    println!("Setting the volume label to {}", label);
+    set_volume_label(Path::new(out), label).map_err(|e| {
+        eprintln!("Failed to label {}: {}", out, e);
+        let _ = std::fs::remove_file(out);
+        CommandError
+    })
+}
+
+/// What went into a written ISO, for its [`BuildManifest`].
+struct BuildRecord<'a> {
+    started: SystemTime,
+    /// The base ISO, hashed unless this is a dry run.
+    base: Option<&'a ManifestFile>,
+    version: Option<String>,
+    answer: ManifestAnswer,
+    /// The files the assistant adds, when there is one, else pveauto.
+    embedded: &'a [(&'a str, &'a [u8])],
+    /// The files pveauto adds itself.
+    extra: &'a [(&'a str, &'a [u8])],
+    kernel_args: &'a [String],
+    volume_label: Option<&'a str>,
+}
+
+/// Completes the written ISO `out`: writes its checksum file, signs it when
+/// signing, records `build` in its manifest once the base ISO is hashed and
+/// smoke tests it when asked to.
+fn finish_iso(tools: &BuildTools, out: &str, build: BuildRecord) -> Result<(), CommandError> {
+    let iso = write_checksum(out)?;
+    sign_iso(tools.signer, out)?;
+
+    let mut prepared_by: Vec<String> = tools
+        .assistant
+        .iter()
+        .map(|assistant| format!("{} ({})", ASSISTANT_BINARY, assistant.program().display()))
+        .collect();
+    if tools.assistant.is_none() || !build.extra.is_empty() {
+        prepared_by.push(tools.writer.describe());
+    }
+    let Some(base) = build.base else {
+        return smoke_test_iso(tools.qemu, out);
+    };
+    let manifest = BuildManifest {
+        format: BUILD_MANIFEST_FORMAT,
+        tool: tool_version(),
+        prepared_by,
+        started: timestamp(build.started),
+        finished: timestamp(SystemTime::now()),
+        base_iso: base.clone(),
+        version: build.version,
+        iso,
+        answer: build.answer,
+        files: build
+            .embedded
+            .iter()
+            .chain(build.extra)
+            .map(|(path, contents)| ManifestFile::new(path, contents))
+            .collect(),
+        kernel_args: build.kernel_args.to_vec(),
+        volume_label: build.volume_label.map(str::to_owned),
+        signed_by: tools.signer.map(|(_, key)| key.to_string()),
+    };
+    let path = manifest.write(Path::new(out)).map_err(|e| {
+        eprintln!("Failed to write the manifest of {}: {}", out, e);
+        CommandError
+    })?;
+    println!("Wrote {}", path.display());
+    smoke_test_iso(tools.qemu, out)
+}
+
+/// Reports the written ISO `out` and writes its `sha256sum`-style checksum
+/// file next to it.
+///
+/// # Returns
+/// The ISO's manifest entry.
+fn write_checksum(out: &str) -> Result<ManifestFile, CommandError> {
+    println!("Wrote {}", out);
+    let iso = ManifestFile::of_file(Path::new(out)).map_err(|e| {
+        eprintln!("Failed to write the checksum of {}: {}", out, e);
+        CommandError
+    })?;
+    let sidecar = write_sha256_sidecar(Path::new(out), &iso.sha256).map_err(|e| {
+        eprintln!("Failed to write the checksum of {}: {}", out, e);
+        CommandError
+    })?;
+    println!("Wrote {}", sidecar.display());
+    Ok(iso)
+}
+
+/// Hashes the base ISO `iso` for the manifests of the ISOs built from it.
+fn hash_base_iso(iso: &str) -> Result<ManifestFile, CommandError> {
+    ManifestFile::of_file(Path::new(iso)).map_err(|e| {
+        eprintln!("Failed to read {}: {}", iso, e);
+        CommandError
+    })
+}
+
+/// Signs the written ISO `out` with the key of `signer`, when signing,
+/// writing the detached signature next to it.
+fn sign_iso(signer: Option<(&Gpg, &str)>, out: &str) -> Result<(), CommandError> {
+    let Some((gpg, key)) = signer else {
+        return Ok(());
+    };
+    println!(
+        "Signing {} with {} using {}...",
+        out,
+        key,
+        gpg.program().display()
+    );
+    let signature = gpg.sign(key, Path::new(out)).map_err(|e| {
+        eprintln!("Failed to sign {}: {}", out, e);
+        CommandError
+    })?;
+    println!("Wrote {}", signature.display());
+    Ok(())
+}
+
+/// Boots the written ISO `out` in `qemu`, when smoke testing, until the
+/// installer starts installing.
+fn smoke_test_iso(qemu: Option<&Qemu>, out: &str) -> Result<(), CommandError> {
+    let Some(qemu) = qemu else {
+        return Ok(());
+    };
+    println!(
+        "Smoke testing {} with {} (up to {} minutes)...",
+        out,
+        qemu.program().display(),
+        SMOKE_TEST_TIMEOUT.as_secs() / 60
+    );
+    let elapsed = smoke_test(qemu, Path::new(out), SMOKE_TEST_TIMEOUT).map_err(|e| {
+        eprintln!("Smoke test of {} failed: {}", out, e);
+        CommandError
+    })?;
+    println!(
+        "Smoke test passed: the installer started partitioning the disk after {} s",
+        elapsed.as_secs()
+    );
+    Ok(())
+}
+
+/// Checks that the base ISO `iso` exists and is a Proxmox installer ISO.
+///
+/// # Returns
+/// Its Proxmox VE version, when its `.cd-info` has one.
+fn check_base_iso(iso: &str) -> Result<Option<String>, CommandError> {
+    if !Path::new(iso).is_file() {
+        eprintln!(
+            "Base ISO not found: {}; run `pveauto download` first or pass --base-iso",
+            iso
+        );
+        return Err(CommandError);
+    }
+    let (_, version) = check_installer_iso(Path::new(iso)).map_err(|e| {
+        eprintln!("{} does not look like a Proxmox installer ISO: {}", iso, e);
+        CommandError
+    })?;
+    Ok(version)
+}
+
+/// The output path `out`, an [`OutputTemplate`], expanded with `values`.
+fn output_path(out: &str, values: &OutputValues) -> Result<String, CommandError> {
+    out.parse::<OutputTemplate>()
+        .and_then(|template| template.expand(values))
+        .map_err(|e| {
+            eprintln!("Invalid output path {}: {}", out, e);
+            CommandError
+        })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::iso::inject::{
+        inject_files,
+        tests::{image, nested_image, pool_image},
+        volume_label,
+    };
+    use crate::iso::writer::{NativeWriter, Xorriso};
+    use sha2::{Digest, Sha256};
+    use std::os::unix::fs::PermissionsExt;
+
+    const ANSWER: &str = r#"
+[global]
+fqdn = "pve01.lab.local"
+
+[network]
+source = "from-dhcp"
+
+[disk-setup]
+filesystem = "ext4"
+disk-list = ["sda"]
+
+[first-boot]
+script = "setup.sh"
+"#;
+
+    /// A stand-in assistant whose `prepare-iso` writes the base ISO followed
+    /// by the answer and the first-boot script, or by the HTTP settings, to `--output`.
+    const FAKE_ASSISTANT: &str = "#!/bin/sh\n\
+        [ \"$1\" = prepare-iso ] || exit 2\n\
+        iso=$2; shift 2\n\
+        while [ $# -gt 0 ]; do\n\
+        case $1 in\n\
+        --fetch-from) from=$2 ;;\n\
+        --answer-file) answer=$2 ;;\n\
+        --output) out=$2 ;;\n\
+        --on-first-boot) script=$2 ;;\n\
+        --url) url=$2 ;;\n\
+        --cert-fingerprint) fp=$2 ;;\n\
+        esac\n\
+        shift 2\n\
+        done\n\
+        if [ \"$from\" = http ]; then\n\
+        { cat \"$iso\"; echo \"http $url $fp\"; } > \"$out\"; exit 0\n\
+        fi\n\
+        grep -q reject-me \"$answer\" && { echo 'Error: bad answer' >&2; exit 1; }\n\
+        cat \"$iso\" \"$answer\" ${script:+\"$script\"} > \"$out\"\n";
+
+    fn setup(name: &str) -> (std::path::PathBuf, Assistant) {
+        let dir =
+            std::env::temp_dir().join(format!("pveauto-installer-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let program = dir.join("assistant");
+        std::fs::write(&program, FAKE_ASSISTANT).unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+        write_base(&dir, &image(true, false));
+        std::fs::write(dir.join("setup.sh"), "#!/bin/sh\necho first boot\n").unwrap();
+        (dir, Assistant::new(program))
+    }
+
+    fn native(assistant: Option<&Assistant>) -> BuildTools<'_> {
+        BuildTools {
+            assistant,
+            writer: &NativeWriter,
+            qemu: None,
+            signer: None,
+        }
+    }
+
+    /// Writes `image` with the `.cd-info` of a Proxmox VE ISO added to
+    /// `dir/base.iso`.
+    fn write_base(dir: &Path, image: &[u8]) {
+        let plain = dir.join("plain.iso");
+        std::fs::write(&plain, image).unwrap();
+        let cd_info = b"PRODUCTLONG='Proxmox VE'\nRELEASE='8.2'\nISORELEASE='1'\n";
+        inject_files(&plain, &dir.join("base.iso"), &[(".cd-info", cd_info)]).unwrap();
+    }
+
+    fn path(dir: &Path, name: &str) -> String {
+        dir.join(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn answer_and_script_are_embedded() {
+        let (dir, assistant) = setup("embed");
+        std::fs::write(dir.join("answer.toml"), ANSWER).unwrap();
+        let out = path(&dir, "out.iso");
+
+        assert_eq!(
+            prepare_offline_iso(
+                &native(Some(&assistant)),
+                &path(&dir, "answer.toml"),
+                AnswerFormat::Toml,
+                &path(&dir, "base.iso"),
+                &out,
+                &OfflineExtras::default(),
+                false,
+            ),
+            Ok(())
+        );
+        let base = std::fs::read(dir.join("base.iso")).unwrap();
+        let written = std::fs::read(&out).unwrap();
+        assert!(written.starts_with(&base));
+        let written = String::from_utf8_lossy(&written[base.len()..]);
+        assert!(written.contains(r#"source = "from-iso""#), "{}", written);
+        assert!(!written.contains("script ="), "{}", written);
+        assert!(written.ends_with("echo first boot\n"), "{}", written);
+
+        let manifest: BuildManifest =
+            serde_json::from_slice(&std::fs::read(dir.join("out.iso.manifest.json")).unwrap())
+                .unwrap();
+        assert_eq!(
+            manifest.base_iso.sha256,
+            format!("{:x}", Sha256::digest(&base))
+        );
+        assert_eq!(manifest.version.as_deref(), Some("8.2-1"));
+        assert_eq!(
+            manifest.iso,
+            ManifestFile::new(&out, &std::fs::read(&out).unwrap())
+        );
+        assert_eq!(
+            manifest.answer,
+            ManifestAnswer::Iso {
+                source: path(&dir, "answer.toml"),
+                sha256: manifest.files[1].sha256.clone(),
+            }
+        );
+        let files: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            files,
+            [
+                AUTO_INSTALLER_MODE_FILE_NAME,
+                ISO_ANSWER_FILE_NAME,
+                ISO_FIRST_BOOT_FILE_NAME
+            ]
+        );
+        assert_eq!(
+            manifest.prepared_by,
+            [format!(
+                "{} ({})",
+                ASSISTANT_BINARY,
+                assistant.program().display()
+            )]
+        );
+        assert_eq!(manifest.signed_by, None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn output_paths_are_templated() {
+        let (dir, _) = setup("template");
+        std::fs::write(dir.join("answer.toml"), ANSWER).unwrap();
+        let offline = |out: &str| {
+            prepare_offline_iso(
+                &native(None),
+                &path(&dir, "answer.toml"),
+                AnswerFormat::Toml,
+                &path(&dir, "base.iso"),
+                &path(&dir, out),
+                &OfflineExtras::default(),
+                false,
+            )
+        };
+        let network = |out: &str| {
+            prepare_network_iso(
+                &native(None),
+                &path(&dir, "base.iso"),
+                &path(&dir, out),
+                &HttpOptions::default(),
+                &[],
+                None,
+                false,
+            )
+        };
+
+        assert_eq!(offline("pve-{version}-{fqdn}-auto.iso"), Ok(()));
+        assert!(dir.join("pve-8.2-1-pve01.lab.local-auto.iso").is_file());
+        assert_eq!(network("{hostname}-net.iso"), Err(CommandError));
+        assert_eq!(network("pve-{version}-net.iso"), Ok(()));
+        assert!(dir.join("pve-8.2-1-net.iso").is_file());
+        assert_eq!(offline("pve-{mac}.iso"), Err(CommandError));
+
+        std::fs::write(
+            dir.join("hosts.toml"),
+            "[[host]]\nname = \"a\"\nfqdn = \"a.lab.local\"\n\n\
+             [[host]]\nname = \"b\"\nfqdn = \"b.lab.local\"\n",
+        )
+        .unwrap();
+        let inventory = |file_name: &str| {
+            prepare_offline_isos(
+                &native(None),
+                &path(&dir, "answer.toml"),
+                AnswerFormat::Toml,
+                &InventoryBuild {
+                    inventory: path(&dir, "hosts.toml"),
+                    out_dir: path(&dir, "builds"),
+                    file_name: file_name.into(),
+                },
+                &path(&dir, "base.iso"),
+                &OfflineExtras::default(),
+                false,
+            )
+        };
+        // Both hosts would be written to the same file.
+        assert_eq!(inventory("pve-{version}.iso"), Err(CommandError));
+        assert!(!dir.join("builds/pve-8.2-1.iso").exists());
+        assert_eq!(inventory("{hostname}-{version}.iso"), Ok(()));
+        assert!(dir.join("builds/a-8.2-1.iso").is_file());
+        assert!(dir.join("builds/b-8.2-1.iso").is_file());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn failures_are_reported() {
+        let (dir, assistant) = setup("fail");
+        let build = |answer: &str, iso: &str| {
+            std::fs::write(dir.join("answer.toml"), answer).unwrap();
+            prepare_offline_iso(
+                &native(Some(&assistant)),
+                &path(&dir, "answer.toml"),
+                AnswerFormat::Toml,
+                &path(&dir, iso),
+                &path(&dir, "out.iso"),
+                &OfflineExtras::default(),
+                false,
+            )
+        };
+
+        assert_eq!(build(ANSWER, "missing.iso"), Err(CommandError));
+        assert_eq!(
+            build(&ANSWER.replace("pve01.lab.local", "nope"), "base.iso"),
+            Err(CommandError)
+        );
+        assert_eq!(
+            build(&ANSWER.replace("setup.sh", "missing.sh"), "base.iso"),
+            Err(CommandError)
+        );
+        assert_eq!(
+            build(&ANSWER.replace("pve01", "reject-me"), "base.iso"),
+            Err(CommandError)
+        );
+        assert!(!dir.join("out.iso").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn without_assistant_the_answer_is_injected_natively() {
+        let (dir, _) = setup("native");
+        std::fs::write(dir.join("answer.toml"), ANSWER).unwrap();
+        let build = |packages: &[String]| {
+            prepare_offline_iso(
+                &native(None),
+                &path(&dir, "answer.toml"),
+                AnswerFormat::Toml,
+                &path(&dir, "base.iso"),
+                &path(&dir, "out.iso"),
+                &OfflineExtras {
+                    packages: packages.to_vec(),
+                    ..Default::default()
+                },
+                false,
+            )
+        };
+
+        // Without a .cd-info, it is no installer ISO.
+        std::fs::write(dir.join("base.iso"), pool_image(true)).unwrap();
+        assert_eq!(build(&[]), Err(CommandError));
+        assert!(!dir.join("out.iso").exists());
+
+        write_base(&dir, &pool_image(true));
+        std::fs::write(dir.join("extra.deb"), "!<arch>\ndebian-binary extra").unwrap();
+        std::fs::write(dir.join("text.deb"), "not a package").unwrap();
+        assert_eq!(build(&[path(&dir, "text.deb")]), Err(CommandError));
+        assert!(!dir.join("out.iso").exists());
+
+        assert_eq!(build(&[path(&dir, "extra.deb")]), Ok(()));
+        let written = std::fs::read(dir.join("out.iso")).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("out.iso.sha256")).unwrap(),
+            format!("{:x}  out.iso\n", Sha256::digest(&written))
+        );
+        for needle in [
+            "mode = \"iso\"",
+            r#"fqdn = "pve01.lab.local""#,
+            "echo first boot",
+            "debian-binary extra",
+        ] {
+            assert!(
+                written
+                    .windows(needle.len())
+                    .any(|w| w == needle.as_bytes()),
+                "{}",
+                needle
+            );
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn assets_are_installed_by_a_generated_first_boot_script() {
+        let (dir, _) = setup("assets");
+        std::fs::write(dir.join("answer.toml"), ANSWER).unwrap();
+        write_base(&dir, &image(true, false));
+        std::fs::write(dir.join("motd"), "managed by pveauto\n").unwrap();
+        std::fs::write(
+            dir.join("assets.toml"),
+            "[[file]]\nsource = \"motd\"\ndestination = \"/etc/motd\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            prepare_offline_iso(
+                &native(None),
+                &path(&dir, "answer.toml"),
+                AnswerFormat::Toml,
+                &path(&dir, "base.iso"),
+                &path(&dir, "out.iso"),
+                &OfflineExtras {
+                    assets: Some(path(&dir, "assets.toml")),
+                    ..Default::default()
+                },
+                false,
+            ),
+            Ok(())
+        );
+        let written = std::fs::read(dir.join("out.iso")).unwrap();
+        for needle in [
+            "managed by pveauto",
+            "echo first boot",
+            "install -D -m 0644 files/0 '/etc/motd'\n./run/0\n",
+            "source = \"from-iso\"",
+        ] {
+            assert!(
+                written
+                    .windows(needle.len())
+                    .any(|w| w == needle.as_bytes()),
+                "{}",
+                needle
+            );
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn the_assistant_gets_the_generated_script_from_a_private_dir() {
+        let (dir, assistant) = setup("assets-assistant");
+        std::fs::write(dir.join("answer.toml"), ANSWER).unwrap();
+        std::fs::write(dir.join("motd"), "managed by pveauto\n").unwrap();
+        std::fs::write(
+            dir.join("assets.toml"),
+            "[[file]]\nsource = \"motd\"\ndestination = \"/etc/motd\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            prepare_offline_iso(
+                &native(Some(&assistant)),
+                &path(&dir, "answer.toml"),
+                AnswerFormat::Toml,
+                &path(&dir, "base.iso"),
+                &path(&dir, "out.iso"),
+                &OfflineExtras {
+                    assets: Some(path(&dir, "assets.toml")),
+                    ..Default::default()
+                },
+                false,
+            ),
+            Ok(())
+        );
+        let written = std::fs::read(dir.join("out.iso")).unwrap();
+        let needle = "install -D -m 0644 files/0 '/etc/motd'\n";
+        assert!(
+            written
+                .windows(needle.len())
+                .any(|w| w == needle.as_bytes())
+        );
+        let prefix = format!("pveauto-first-boot-script-{}-", std::process::id());
+        assert!(!std::fs::read_dir(std::env::temp_dir()).unwrap().any(|e| {
+            e.unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with(&prefix)
+        }));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn the_volume_label_is_replaced() {
+        let (dir, _) = setup("label");
+        std::fs::write(dir.join("answer.toml"), ANSWER).unwrap();
+        write_base(&dir, &image(true, false));
+        let build = |label: &str| {
+            prepare_offline_iso(
+                &native(None),
+                &path(&dir, "answer.toml"),
+                AnswerFormat::Toml,
+                &path(&dir, "base.iso"),
+                &path(&dir, "out.iso"),
+                &OfflineExtras {
+                    volume_label: Some(label.into()),
+                    ..Default::default()
+                },
+                false,
+            )
+        };
+
+        assert_eq!(build("pve auto"), Err(CommandError));
+        assert!(!dir.join("out.iso").exists());
+        assert_eq!(build("PVE-AUTO-NODE01"), Ok(()));
+        let mut written = std::fs::File::open(dir.join("out.iso")).unwrap();
+        assert_eq!(volume_label(&mut written).unwrap(), "PVE-AUTO-NODE01");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// A stand-in GnuPG that writes which file it signed with which key to
+    /// `--output`, or fails for the key `unknown`.
+    const FAKE_GPG: &str = "#!/bin/sh\n\
+        while [ $# -gt 1 ]; do\n\
+        case $1 in\n\
+        --local-user) key=$2 ;;\n\
+        --output) out=$2 ;;\n\
+        esac\n\
+        shift\n\
+        done\n\
+        [ \"$key\" = unknown ] && { echo 'gpg: signing failed: No secret key' >&2; exit 2; }\n\
+        echo \"$1 signed by $key\" > \"$out\"\n";
+
+    #[test]
+    fn written_isos_are_signed() {
+        let (dir, _) = setup("sign");
+        std::fs::write(dir.join("answer.toml"), ANSWER).unwrap();
+        let program = dir.join("gpg");
+        std::fs::write(&program, FAKE_GPG).unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let gpg = Gpg::new(program);
+        let tools = |key| BuildTools {
+            signer: Some((&gpg, key)),
+            ..native(None)
+        };
+        let (out, net) = (path(&dir, "out.iso"), path(&dir, "net.iso"));
+        let offline = |key| {
+            prepare_offline_iso(
+                &tools(key),
+                &path(&dir, "answer.toml"),
+                AnswerFormat::Toml,
+                &path(&dir, "base.iso"),
+                &out,
+                &OfflineExtras::default(),
+                false,
+            )
+        };
+
+        assert_eq!(offline("ops@example.com"), Ok(()));
+        assert_eq!(
+            std::fs::read_to_string(dir.join("out.iso.asc")).unwrap(),
+            format!("{} signed by ops@example.com\n", out)
+        );
+        assert_eq!(
+            prepare_network_iso(
+                &tools("ops@example.com"),
+                &path(&dir, "base.iso"),
+                &net,
+                &HttpOptions::default(),
+                &[],
+                None,
+                false,
+            ),
+            Ok(())
+        );
+        assert!(dir.join("net.iso.asc").is_file());
+        let manifest: BuildManifest =
+            serde_json::from_slice(&std::fs::read(dir.join("net.iso.manifest.json")).unwrap())
+                .unwrap();
+        assert_eq!(manifest.signed_by.as_deref(), Some("ops@example.com"));
+        assert_eq!(
+            manifest.answer,
+            ManifestAnswer::Http {
+                url: None,
+                cert_fingerprint: None
+            }
+        );
+
+        assert_eq!(offline("unknown"), Err(CommandError));
+        assert!(!dir.join("out.iso.asc").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn inventory_hosts_get_an_iso_each() {
+        let (dir, _) = setup("inventory");
+        std::fs::write(dir.join("base.toml"), ANSWER).unwrap();
+        write_base(&dir, &image(true, false));
+        std::fs::write(
+            dir.join("hosts.toml"),
+            "fqdn = \"pve-{mac6}.lab.local\"\n\n\
+             [[host]]\nmac = \"bc:24:11:0a:00:01\"\n\n\
+             [[host]]\nname = \"blocked\"\nmac = \"bc:24:11:0a:00:02\"\n",
+        )
+        .unwrap();
+        let out_dir = dir.join("builds");
+        std::fs::create_dir_all(out_dir.join("blocked.iso")).unwrap();
+        let build = |inventory: &str| {
+            prepare_offline_isos(
+                &native(None),
+                &path(&dir, "base.toml"),
+                AnswerFormat::Toml,
+                &InventoryBuild {
+                    inventory: path(&dir, inventory),
+                    out_dir: out_dir.to_string_lossy().into_owned(),
+                    file_name: "{name}.iso".into(),
+                },
+                &path(&dir, "base.iso"),
+                &OfflineExtras::default(),
+                false,
+            )
+        };
+
+        assert_eq!(build("hosts.toml"), Err(CommandError));
+        let written = std::fs::read(out_dir.join("pve-0a0001.lab.local.iso")).unwrap();
+        for needle in [r#"fqdn = "pve-0a0001.lab.local""#, "echo first boot"] {
+            assert!(
+                written
+                    .windows(needle.len())
+                    .any(|w| w == needle.as_bytes()),
+                "{}",
+                needle
+            );
+        }
+
+        std::fs::remove_dir_all(&out_dir).unwrap();
+        assert_eq!(build("hosts.toml"), Ok(()));
+        assert!(out_dir.join("blocked.iso").is_file());
+        assert_eq!(build("missing.toml"), Err(CommandError));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn dry_runs_write_nothing() {
+        let (dir, assistant) = setup("dry-run");
+        std::fs::write(dir.join("answer.toml"), ANSWER).unwrap();
+        std::fs::write(
+            dir.join("hosts.toml"),
+            "[[host]]\nname = \"a\"\nfqdn = \"a.lab.local\"\n",
+        )
+        .unwrap();
+        let out = path(&dir, "out.iso");
+
+        for assistant in [Some(&assistant), None] {
+            assert_eq!(
+                prepare_offline_iso(
+                    &native(assistant),
+                    &path(&dir, "answer.toml"),
+                    AnswerFormat::Toml,
+                    &path(&dir, "base.iso"),
+                    &out,
+                    &OfflineExtras::default(),
+                    true,
+                ),
+                Ok(())
+            );
+            assert_eq!(
+                prepare_network_iso(
+                    &native(assistant),
+                    &path(&dir, "base.iso"),
+                    &out,
+                    &HttpOptions::default(),
+                    &[],
+                    None,
+                    true,
+                ),
+                Ok(())
+            );
+        }
+        let targets = InventoryBuild {
+            inventory: path(&dir, "hosts.toml"),
+            out_dir: path(&dir, "builds"),
+            file_name: "{name}.iso".into(),
+        };
+        assert_eq!(
+            prepare_offline_isos(
+                &native(None),
+                &path(&dir, "answer.toml"),
+                AnswerFormat::Toml,
+                &targets,
+                &path(&dir, "base.iso"),
+                &OfflineExtras::default(),
+                true,
+            ),
+            Ok(())
+        );
+        assert!(!dir.join("out.iso").exists());
+        assert!(!dir.join("builds").exists());
+
+        // Invalid settings still fail.
+        assert_eq!(
+            prepare_offline_iso(
+                &native(None),
+                &path(&dir, "answer.toml"),
+                AnswerFormat::Toml,
+                &path(&dir, "missing.iso"),
+                &out,
+                &OfflineExtras::default(),
+                true,
+            ),
+            Err(CommandError)
+        );
+
+        let command = assistant.prepare_http_iso_command(
+            Path::new("base.iso"),
+            Some("https://10.0.0.1/answer?id=a b"),
+            None,
+            Path::new("it's.iso"),
+        );
+        assert_eq!(
+            command_line(&command),
+            format!(
+                "{} prepare-iso base.iso --fetch-from http --output 'it'\\''s.iso' --url 'https://10.0.0.1/answer?id=a b'",
+                assistant.program().display()
+            )
+        );
+
+        let xorriso = Xorriso::new("/usr/bin/xorriso");
+        let files: [(&str, &[u8]); 2] = [("answer.toml", b"x"), ("boot/grub/grub.cfg", b"y")];
+        assert_eq!(
+            plan_commands(
+                &BuildTools {
+                    assistant: None,
+                    writer: &xorriso,
+                    qemu: Some(&Qemu::new("qemu")),
+                    signer: Some((&Gpg::new("gpg"), "ops@example.com")),
+                },
+                None,
+                "base.iso",
+                "out.iso",
+                &files[..1],
+                &files[1..]
+            ),
+            [
+                "/usr/bin/xorriso -abort_on FAILURE -indev base.iso -outdev out.iso \
+              -boot_image any replay -map <answer.toml> /answer.toml \
+              -map <boot/grub/grub.cfg> /boot/grub/grub.cfg -end",
+                "gpg --batch --yes --armor --digest-algo SHA512 --local-user ops@example.com \
+              --output out.iso.asc --detach-sign out.iso",
+                "qemu -name pveauto-smoke-test -machine accel=kvm:tcg -m 4096 -smp 2 \
+              -display none -no-reboot -boot d -nic user,model=e1000 \
+              -drive 'file=<scratch disk>,format=raw,if=ide,index=0' \
+              -drive file=out.iso,media=cdrom,if=ide,index=2,readonly=on \
+              -serial 'file:<serial log>'"
+            ]
+        );
+        assert_eq!(
+            plan_commands(&native(None), None, "base.iso", "out.iso", &files, &[]),
+            Vec::<String>::new()
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /* ---------------- NETWORK INSTALLER ---------------- */
+
+    const FINGERPRINT: &str = "AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89";
+
+    #[test]
+    fn network_iso_is_prepared_with_url_and_fingerprint() {
+        let (dir, assistant) = setup("network");
+        let out = path(&dir, "out.iso");
+        let url = "https://10.0.0.1:8443/answer";
+
+        assert_eq!(
+            prepare_network_iso(
+                &native(Some(&assistant)),
+                &path(&dir, "base.iso"),
+                &out,
+                &AutoInstallerMode::http(Some(url), Some(FINGERPRINT)).http,
+                &[],
+                None,
+                false,
+            ),
+            Ok(())
+        );
+        let base = std::fs::read(dir.join("base.iso")).unwrap();
+        assert_eq!(
+            std::fs::read(&out).unwrap(),
+            [base, format!("http {} {}\n", url, FINGERPRINT).into_bytes()].concat()
+        );
+
+        assert_eq!(
+            prepare_network_iso(
+                &native(None),
+                &path(&dir, "base.iso"),
+                &out,
+                &AutoInstallerMode::http(Some(url), None).http,
+                &[],
+                None,
+                false,
+            ),
+            Ok(())
+        );
+        let written = std::fs::read(&out).unwrap();
+        let needle = format!("mode = \"http\"\n\n[http]\nurl = \"{}\"\n", url);
+        assert!(
+            written
+                .windows(needle.len())
+                .any(|w| w == needle.as_bytes())
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_network_settings_are_rejected() {
+        let (dir, assistant) = setup("network-invalid");
+        let build = |url, fp| {
+            prepare_network_iso(
+                &native(Some(&assistant)),
+                &path(&dir, "base.iso"),
+                &path(&dir, "out.iso"),
+                &AutoInstallerMode::http(url, fp).http,
+                &[],
+                None,
+                false,
+            )
+        };
+
+        assert_eq!(
+            build(Some("ftp://10.0.0.1/answer"), None),
+            Err(CommandError)
+        );
+        assert_eq!(build(None, Some("AB:CD")), Err(CommandError));
+        assert!(!dir.join("out.iso").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn kernel_args_are_appended_to_the_automated_entry() {
+        let (dir, _) = setup("kernel-args");
+        let grub_cfg =
+            "menuentry 'Automated' {\n\tlinux /boot/linux26 ro proxmox-start-auto-installer\n}\n";
+        let base = nested_image(
+            true,
+            ["boot", "grub"],
+            Some(("grub.cfg", grub_cfg.as_bytes())),
+        );
+        write_base(&dir, &base);
+        let out = path(&dir, "out.iso");
+        let build = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            prepare_network_iso(
+                &native(None),
+                &path(&dir, "base.iso"),
+                &out,
+                &HttpOptions::default(),
+                &args,
+                None,
+                false,
+            )
+        };
+
+        assert_eq!(build(&["console=ttyS0,115200", "proxdebug"]), Ok(()));
+        let written = std::fs::read(&out).unwrap();
+        for needle in [
+            "proxmox-start-auto-installer console=ttyS0,115200 proxdebug\n",
+            "mode = \"http\"",
+        ] {
+            assert!(
+                written
+                    .windows(needle.len())
+                    .any(|w| w == needle.as_bytes()),
+                "{}",
+                needle
+            );
+        }
+
+        std::fs::remove_file(&out).unwrap();
+        assert_eq!(build(&["x=$(id)"]), Err(CommandError));
+        write_base(&dir, &image(true, false));
+        assert_eq!(build(&["proxdebug"]), Err(CommandError));
+        assert!(!dir.join("out.iso").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,184 @@
+use crate::auto_installer::commands::constants::CommandError;
+use crate::iso::{
+    cache::IsoCache,
+    channel::IsoChannel,
+    config::{resolve_keep_versions, resolve_mirror, resolve_retry},
+    constants::ISO_INDEX_URL,
+    inspect::inspect_iso,
+    retry::Retrying,
+    scraper::list_iso_versions,
+    signature::{signature_path, verify_signature},
+    verify::verify_iso,
+};
+use reqwest::Client;
+use std::path::Path;
+
+/// `iso list-versions`: prints the Proxmox VE ISO versions of `channel`
+/// published on enterprise.proxmox.com, or on `mirror` (else `$PVEAUTO_MIRROR`
+/// or the configured `download.mirror`), newest first.
+pub async fn list_iso_versions_command(
+    mirror: Option<&str>,
+    channel: IsoChannel,
+) -> Result<(), CommandError> {
+    let mirror = resolve_mirror(mirror).map_err(|e| {
+        eprintln!("{}", e);
+        CommandError
+    })?;
+    let retry = resolve_retry().map_err(|e| {
+        eprintln!("{}", e);
+        CommandError
+    })?;
+    let index_url = mirror.as_deref().unwrap_or(ISO_INDEX_URL);
+    let http = Retrying::new(Client::new(), retry, false);
+    let versions = list_iso_versions(&http, index_url, channel)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to list {}: {}", index_url, e);
+            CommandError
+        })?;
+
+    for version in versions.iter().rev() {
+        println!("{}", version);
+    }
+    Ok(())
+}
+
+/// `iso verify`: checks the ISO at `path` against the strongest signed
+/// checksum file published by Proxmox (`SHA512SUMS`, else `SHA256SUMS`),
+/// verifying the signature with the keyring file `key` or, when not given,
+/// the pinned Proxmox release keys.
+///
+/// # Returns
+/// `Ok(())` and prints the matching release file name when the ISO is listed
+/// in a correctly signed checksum file; otherwise `Err(CommandError)`.
+pub async fn verify_iso_image(path: &str, key: Option<&str>) -> Result<(), CommandError> {
+    println!(
+        "Verifying {} against the checksums at {}",
+        path, ISO_INDEX_URL
+    );
+    let verified = verify_iso(Path::new(path), key.map(Path::new))
+        .await
+        .map_err(|e| {
+            eprintln!("{}: {}", path, e);
+            CommandError
+        })?;
+
+    println!("Good signature by {}", verified.signer);
+    println!(
+        "{}: OK ({}, listed in {})",
+        path,
+        verified.file_name,
+        verified.algorithm.sums_file()
+    );
+    Ok(())
+}
+
+/// `iso verify-signature`: checks `<path>.asc`, the detached signature
+/// written by `--sign-key`, over the ISO at `path` against the keys of the
+/// keyring file `key`.
+///
+/// # Returns
+/// `Ok(())` and prints the signer's fingerprint when the signature is valid
+/// and made by one of the keys; otherwise `Err(CommandError)`.
+pub fn verify_iso_signature(path: &str, key: &str) -> Result<(), CommandError> {
+    let iso = Path::new(path);
+    println!(
+        "Verifying {} against {}",
+        path,
+        signature_path(iso).display()
+    );
+    let signer = verify_signature(iso, Path::new(key)).map_err(|e| {
+        eprintln!("{}: {}", path, e);
+        CommandError
+    })?;
+
+    println!("Good signature by {}", signer.fingerprint_hex());
+    println!("{}: OK", path);
+    Ok(())
+}
+
+/// `iso inspect`: prints the Proxmox VE version, volume label, size, boot
+/// structures and SHA256 of the ISO at `path`, and whether an answer file is
+/// embedded.
+///
+/// # Returns
+/// `Err(CommandError)` when `path` cannot be read as an ISO 9660 image.
+pub fn inspect_iso_image(path: &str) -> Result<(), CommandError> {
+    let inspection = inspect_iso(Path::new(path)).map_err(|e| {
+        eprintln!("{}: {}", path, e);
+        CommandError
+    })?;
+
+    let product = match (&inspection.product, &inspection.version) {
+        (Some(product), Some(version)) => format!("{} {}", product, version),
+        (None, Some(version)) => format!("Proxmox VE {}", version),
+        (Some(product), None) => product.clone(),
+        (None, None) => "unknown (no .cd-info)".to_string(),
+    };
+    let answer = match (inspection.answer_embedded, &inspection.auto_installer_mode) {
+        (true, Some(mode)) => format!("embedded (mode: {})", mode),
+        (true, None) => "embedded".to_string(),
+        (false, Some(mode)) => format!("not embedded (mode: {})", mode),
+        (false, None) => "not embedded".to_string(),
+    };
+    let embedded = |yes: bool| if yes { "embedded" } else { "not embedded" };
+    let boot = &inspection.boot;
+    let boot: Vec<&str> = [
+        (boot.bios(), "BIOS"),
+        (boot.uefi(), "UEFI"),
+        (boot.mbr, "isohybrid MBR"),
+        (boot.gpt, "GPT"),
+    ]
+    .into_iter()
+    .filter_map(|(present, name)| present.then_some(name))
+    .collect();
+
+    println!("ISO:          {}", path);
+    println!("Product:      {}", product);
+    println!("Volume label: {}", inspection.volume_label);
+    println!(
+        "Size:         {:.2} GiB ({} bytes)",
+        inspection.size as f64 / (1u64 << 30) as f64,
+        inspection.size
+    );
+    println!("Answer file:  {}", answer);
+    println!("First boot:   {}", embedded(inspection.first_boot_embedded));
+    if boot.is_empty() {
+        println!("Boot:         none found");
+    } else {
+        println!("Boot:         {}", boot.join(", "));
+    }
+    println!("SHA256:       {}", inspection.sha256);
+    Ok(())
+}
+
+/// `iso prune`: deletes all but the newest `keep` versions (else the
+/// configured `cache.keep-versions`, or 2) from the ISO cache, with their
+/// metadata, and prints what was removed and the space freed.
+pub fn prune_iso_cache(keep: Option<usize>) -> Result<(), CommandError> {
+    let keep = resolve_keep_versions(keep).map_err(|e| {
+        eprintln!("{}", e);
+        CommandError
+    })?;
+    let cache = IsoCache::new(IsoCache::default_dir());
+    let report = cache.prune(keep).map_err(|e| {
+        eprintln!("{}", e);
+        CommandError
+    })?;
+
+    for version in &report.removed {
+        println!("Removed Proxmox VE {}", version);
+    }
+    if report.kept.is_empty() {
+        println!("Kept no versions");
+    } else {
+        println!("Kept {}", report.kept.join(", "));
+    }
+    println!(
+        "Freed {:.2} GiB ({} bytes) in {}",
+        report.freed as f64 / (1u64 << 30) as f64,
+        report.freed,
+        cache.dir().display()
+    );
+    Ok(())
+}
@@ -0,0 +1,505 @@
+use crate::answer_file::{AnswerFile, AnswerFormat};
+use crate::auto_installer::commands::{
+    answer::{read_editable_answer, write_edited_answer},
+    constants::{CommandError, SshKeySource},
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::Client;
+use std::{
+    io::{self, Read, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Base URL serving `<user>.keys` for GitHub accounts.
+pub const GITHUB_URL: &str = "https://github.com";
+
+/// Base URL serving `<user>.keys` for GitLab accounts.
+pub const GITLAB_URL: &str = "https://gitlab.com";
+
+/// Socket of the running ssh-agent, read for `--agent`.
+pub const SSH_AUTH_SOCK_ENV: &str = "SSH_AUTH_SOCK";
+
+/// Upper bound for each key download and agent reply.
+const KEYS_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// ssh-agent protocol messages (draft-miller-ssh-agent, section 6.1).
+const SSH_AGENTC_REQUEST_IDENTITIES: u8 = 11;
+const SSH_AGENT_IDENTITIES_ANSWER: u8 = 12;
+
+/// Agent replies larger than this are rejected rather than buffered.
+const MAX_AGENT_REPLY: usize = 256 * 1024;
+
+/// Account names both forges accept; anything else could escape the URL path.
+static FORGE_USER_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[A-Za-z0-9][A-Za-z0-9._-]{0,254}$").expect("invalid FORGE_USER_PATTERN")
+});
+
+impl SshKeySource {
+    /// `<forge>/<user>.keys` for GitHub and GitLab sources, or `None` for
+    /// local sources and implausible account names.
+    pub fn keys_url(&self) -> Option<String> {
+        let (base, user) = match self {
+            Self::GitHub(user) => (GITHUB_URL, user),
+            Self::GitLab(user) => (GITLAB_URL, user),
+            Self::File(_) | Self::LocalKeys | Self::Agent => return None,
+        };
+        FORGE_USER_PATTERN
+            .is_match(user)
+            .then(|| format!("{}/{}.keys", base, user))
+    }
+}
+
+/// Where the keys of one [`SshKeySource`] are read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum KeyLocation {
+    Url(String),
+    File(PathBuf),
+    Agent(PathBuf),
+}
+
+/// `answer add-ssh-keys`: reads the public keys of every source and appends
+/// the new ones to `root-ssh-keys` of the answer file at `answer_path`.
+///
+/// # Notes
+/// - All sources are read and every key is validated before anything is written.
+/// - Keys already listed are skipped; TOML answers keep their comments and formatting.
+/// - A source without any keys is an error, since it usually means a typo in the
+///   user name or an empty agent.
+pub async fn add_ssh_keys(
+    answer_path: &str,
+    format: AnswerFormat,
+    sources: &[SshKeySource],
+) -> Result<(), CommandError> {
+    let mut locations = Vec::new();
+    for source in sources {
+        for location in locate(source)? {
+            locations.push((source.to_string(), location));
+        }
+    }
+
+    add_keys_from(answer_path, format, &locations).await
+}
+
+fn locate(source: &SshKeySource) -> Result<Vec<KeyLocation>, CommandError> {
+    match source {
+        SshKeySource::GitHub(_) | SshKeySource::GitLab(_) => source
+            .keys_url()
+            .map(|url| vec![KeyLocation::Url(url)])
+            .ok_or_else(|| {
+                eprintln!("Invalid user name: {}", source);
+                CommandError
+            }),
+        SshKeySource::File(path) => Ok(vec![KeyLocation::File(path.into())]),
+        SshKeySource::LocalKeys => {
+            let dir = std::env::var("HOME")
+                .map(|home| Path::new(&home).join(".ssh"))
+                .map_err(|_| {
+                    eprintln!("HOME is not set; cannot find ~/.ssh");
+                    CommandError
+                })?;
+            let files = public_key_files(&dir).map_err(|e| {
+                eprintln!("Failed to read {}: {}", dir.display(), e);
+                CommandError
+            })?;
+            if files.is_empty() {
+                eprintln!("No *.pub files in {}", dir.display());
+                return Err(CommandError);
+            }
+            Ok(files.into_iter().map(KeyLocation::File).collect())
+        }
+        SshKeySource::Agent => std::env::var_os(SSH_AUTH_SOCK_ENV)
+            .filter(|sock| !sock.is_empty())
+            .map(|sock| vec![KeyLocation::Agent(sock.into())])
+            .ok_or_else(|| {
+                eprintln!("{} is not set; is ssh-agent running?", SSH_AUTH_SOCK_ENV);
+                CommandError
+            }),
+    }
+}
+
+/// `*.pub` files directly in `dir`, sorted by name.
+fn public_key_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "pub") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+async fn add_keys_from(
+    answer_path: &str,
+    format: AnswerFormat,
+    locations: &[(String, KeyLocation)],
+) -> Result<(), CommandError> {
+    let (contents, mut answer) = read_editable_answer(answer_path, format)?;
+
+    let client = Client::builder()
+        .timeout(KEYS_TIMEOUT)
+        .build()
+        .map_err(|e| {
+            eprintln!("Failed to create HTTP client: {}", e);
+            CommandError
+        })?;
+
+    let mut added = 0;
+    for (source, location) in locations {
+        let label = match location {
+            KeyLocation::File(path) => path.display().to_string(),
+            KeyLocation::Url(_) | KeyLocation::Agent(_) => source.clone(),
+        };
+        let body = read_keys(&client, location).await.map_err(|e| {
+            eprintln!("Failed to read keys of {}: {}", label, e);
+            CommandError
+        })?;
+        let count = add_keys(&mut answer, &label, &body)?;
+        println!("{}: {} new key(s)", label, count);
+        added += count;
+    }
+
+    if added > 0 {
+        write_edited_answer(answer_path, &contents, answer, format)?;
+    }
+    println!("Added {} key(s) to {}", added, answer_path);
+    Ok(())
+}
+
+/// Public keys at `location`, one OpenSSH line per key.
+async fn read_keys(
+    client: &Client,
+    location: &KeyLocation,
+) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(match location {
+        KeyLocation::Url(url) => {
+            client
+                .get(url)
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?
+        }
+        KeyLocation::File(path) => std::fs::read_to_string(path)?,
+        KeyLocation::Agent(socket) => agent_keys(socket)?,
+    })
+}
+
+/// Lists the identities of the ssh-agent listening on `socket` as OpenSSH
+/// public key lines, keeping each key's comment.
+fn agent_keys(socket: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(KEYS_TIMEOUT))?;
+    stream.write_all(&[0, 0, 0, 1, SSH_AGENTC_REQUEST_IDENTITIES])?;
+
+    let mut len = [0u8; 4];
+    stream.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_AGENT_REPLY {
+        return Err("agent reply too large".into());
+    }
+    let mut reply = vec![0u8; len];
+    stream.read_exact(&mut reply)?;
+
+    let mut reader = AgentReader(&reply);
+    if reader.byte()? != SSH_AGENT_IDENTITIES_ANSWER {
+        return Err("unexpected agent reply".into());
+    }
+
+    let mut lines = Vec::new();
+    for _ in 0..reader.u32()? {
+        let blob = reader.string()?;
+        let comment = String::from_utf8_lossy(reader.string()?);
+        let mut key = ssh_key::PublicKey::from_bytes(blob)?;
+        key.set_comment(comment);
+        lines.push(key.to_openssh()?);
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Reads the big-endian fields of an ssh-agent message.
+struct AgentReader<'a>(&'a [u8]);
+
+impl<'a> AgentReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], &'static str> {
+        if self.0.len() < n {
+            return Err("truncated agent reply");
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn byte(&mut self) -> Result<u8, &'static str> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, &'static str> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn string(&mut self) -> Result<&'a [u8], &'static str> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
+
+/// Adds every key of a `.keys` body (one OpenSSH public key per line).
+///
+/// # Returns
+/// The number of keys that were not listed yet. An invalid key or an empty
+/// body prints `label` and fails.
+fn add_keys(answer: &mut AnswerFile, label: &str, body: &str) -> Result<usize, CommandError> {
+    let keys: Vec<_> = body
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    if keys.is_empty() {
+        eprintln!("{}: no public keys found", label);
+        return Err(CommandError);
+    }
+
+    let mut added = 0;
+    for (i, key) in keys.into_iter().enumerate() {
+        let new = answer.global.add_root_ssh_key(key).map_err(|e| {
+            eprintln!("{}: key {}: {}", label, i + 1, e);
+            CommandError
+        })?;
+        added += usize::from(new);
+    }
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+
+    const KEY_A: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIBJm0uQAzvhAQECdsq4DlG4cj0+VibMUZNs0gE7YmMp/";
+    const KEY_B: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIMZpnxXdVNhPRxmqZohDx3Wzv4PubXmPm+I7chvUpOQd";
+
+    /// Serves one HTTP response with `status` and `body`, returning its URL.
+    fn serve_once(status: &'static str, body: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        format!("http://{}/octocat.keys", addr)
+    }
+
+    fn temp_answer(name: &str) -> String {
+        let path = std::env::temp_dir()
+            .join(format!("pveauto-keys-{}-{}", std::process::id(), name))
+            .to_string_lossy()
+            .into_owned();
+        std::fs::write(&path, AnswerFile::default().to_toml_string().unwrap()).unwrap();
+        path
+    }
+
+    /* ---------------- URLS ---------------- */
+
+    #[test]
+    fn keys_urls_per_forge() {
+        assert_eq!(
+            SshKeySource::GitHub("octocat".into()).keys_url().as_deref(),
+            Some("https://github.com/octocat.keys")
+        );
+        assert_eq!(
+            SshKeySource::GitLab("jane.doe".into())
+                .keys_url()
+                .as_deref(),
+            Some("https://gitlab.com/jane.doe.keys")
+        );
+        for user in ["", "../admin", "a/b", "-x", "a?b"] {
+            assert_eq!(
+                SshKeySource::GitHub(user.into()).keys_url(),
+                None,
+                "{}",
+                user
+            );
+        }
+    }
+
+    /* ---------------- PARSING ---------------- */
+
+    #[test]
+    fn keys_are_added_once() {
+        let mut answer = AnswerFile::default();
+        let body = format!("{}\n\n{}\n", KEY_A, KEY_B);
+        assert_eq!(add_keys(&mut answer, "github:octocat", &body), Ok(2));
+        assert_eq!(add_keys(&mut answer, "github:octocat", &body), Ok(0));
+        assert_eq!(
+            answer.global.root_ssh_keys,
+            Some(vec![KEY_A.to_string(), KEY_B.to_string()])
+        );
+    }
+
+    #[test]
+    fn empty_and_invalid_bodies_fail() {
+        let mut answer = AnswerFile::default();
+        assert_eq!(add_keys(&mut answer, "x", "\n"), Err(CommandError));
+        assert_eq!(
+            add_keys(&mut answer, "x", &format!("{}\nnot a key\n", KEY_A)),
+            Err(CommandError)
+        );
+    }
+
+    /* ---------------- COMMAND ---------------- */
+
+    #[tokio::test]
+    async fn fetched_keys_are_written_to_the_answer() {
+        let path = temp_answer("fetched.toml");
+        let url = serve_once("200 OK", format!("{}\n", KEY_A));
+        let locations = [("github:octocat".into(), KeyLocation::Url(url))];
+
+        assert_eq!(
+            add_keys_from(&path, AnswerFormat::Toml, &locations).await,
+            Ok(())
+        );
+        let answer = AnswerFile::from_path(&path).unwrap();
+        assert_eq!(answer.global.root_ssh_keys, Some(vec![KEY_A.to_string()]));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn unknown_user_leaves_the_answer_untouched() {
+        let path = temp_answer("missing.toml");
+        let before = std::fs::read_to_string(&path).unwrap();
+        let url = serve_once("404 Not Found", String::new());
+        let locations = [("gitlab:nobody".into(), KeyLocation::Url(url))];
+
+        assert_eq!(
+            add_keys_from(&path, AnswerFormat::Toml, &locations).await,
+            Err(CommandError)
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), before);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /* ---------------- LOCAL SOURCES ---------------- */
+
+    #[test]
+    fn public_key_files_are_found_and_sorted() {
+        let dir = std::env::temp_dir().join(format!("pveauto-keys-{}-ssh-dir", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["id_rsa.pub", "id_ed25519.pub", "id_ed25519", "known_hosts"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+
+        assert_eq!(
+            public_key_files(&dir).unwrap(),
+            vec![dir.join("id_ed25519.pub"), dir.join("id_rsa.pub")]
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn keys_are_read_from_files() {
+        let path = temp_answer("from-file.toml");
+        let key_file = format!("{}.pub", path);
+        std::fs::write(&key_file, format!("{} me@laptop\n", KEY_B)).unwrap();
+
+        let sources = [SshKeySource::File(key_file.clone())];
+        assert_eq!(
+            add_ssh_keys(&path, AnswerFormat::Toml, &sources).await,
+            Ok(())
+        );
+        let answer = AnswerFile::from_path(&path).unwrap();
+        assert_eq!(
+            answer.global.root_ssh_keys,
+            Some(vec![format!("{} me@laptop", KEY_B)])
+        );
+        for file in [path, key_file] {
+            std::fs::remove_file(file).unwrap();
+        }
+    }
+
+    /// Answers one identities request on a fresh socket with `keys`.
+    fn fake_agent(name: &str, keys: Vec<(Vec<u8>, &'static str)>) -> PathBuf {
+        use std::os::unix::net::UnixListener;
+
+        let socket =
+            std::env::temp_dir().join(format!("pveauto-agent-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket).unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 5];
+            stream.read_exact(&mut request).unwrap();
+            assert_eq!(request[4], SSH_AGENTC_REQUEST_IDENTITIES);
+
+            let mut body = vec![SSH_AGENT_IDENTITIES_ANSWER];
+            body.extend((keys.len() as u32).to_be_bytes());
+            for (blob, comment) in &keys {
+                body.extend((blob.len() as u32).to_be_bytes());
+                body.extend(blob);
+                body.extend((comment.len() as u32).to_be_bytes());
+                body.extend(comment.as_bytes());
+            }
+            stream
+                .write_all(&(body.len() as u32).to_be_bytes())
+                .unwrap();
+            stream.write_all(&body).unwrap();
+        });
+        socket
+    }
+
+    #[test]
+    fn agent_identities_become_openssh_lines() {
+        let key = ssh_key::PublicKey::from_openssh(KEY_A).unwrap();
+        let socket = fake_agent("list", vec![(key.to_bytes().unwrap(), "agent@host")]);
+
+        assert_eq!(
+            agent_keys(&socket).unwrap(),
+            format!("{} agent@host", KEY_A)
+        );
+        std::fs::remove_file(socket).unwrap();
+    }
+
+    #[tokio::test]
+    async fn empty_agent_fails() {
+        let path = temp_answer("empty-agent.toml");
+        let socket = fake_agent("empty", vec![]);
+        let locations = [("ssh-agent".into(), KeyLocation::Agent(socket.clone()))];
+
+        assert_eq!(
+            add_keys_from(&path, AnswerFormat::Toml, &locations).await,
+            Err(CommandError)
+        );
+        for file in [PathBuf::from(path), socket] {
+            std::fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_user_names_are_rejected_before_fetching() {
+        let sources = [SshKeySource::GitHub("../admin".into())];
+        assert_eq!(
+            add_ssh_keys("/nonexistent/answer.toml", AnswerFormat::Toml, &sources).await,
+            Err(CommandError)
+        );
+    }
+}
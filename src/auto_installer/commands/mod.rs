@@ -1,3 +1,15 @@
+pub mod answer;
+pub mod bundle;
 pub mod command_parser;
 pub mod constants;
 pub mod downloader;
+pub mod fetch;
+pub mod flash;
+pub mod installer;
+pub mod iso;
+pub mod key_import;
+pub mod password;
+pub mod profile;
+pub mod signature;
+pub mod ssh_key;
+pub mod wizard;
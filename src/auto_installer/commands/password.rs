@@ -0,0 +1,66 @@
+use crate::answer_file::sections::{PasswordHashFormat, hash_password_as};
+use crate::auto_installer::commands::constants::CommandError;
+use std::io::{self, BufRead, IsTerminal};
+
+/// `hash-password`: prints a `method` crypt hash for `root-password-hashed`.
+///
+/// The plaintext is read from the first line of standard input when it is piped,
+/// otherwise it is prompted for twice without echo.
+pub fn hash_password_command(
+    method: PasswordHashFormat,
+    rounds: Option<u32>,
+) -> Result<(), CommandError> {
+    let stdin = io::stdin();
+    let plaintext = if stdin.is_terminal() {
+        prompt_confirmed("Password")
+    } else {
+        read_plaintext(stdin.lock())
+    }
+    .map_err(|e| {
+        eprintln!("could not read password: {}", e);
+        CommandError
+    })?;
+
+    let hash = hash_password_as(&plaintext, method, rounds).map_err(|e| {
+        eprintln!("{}", e);
+        CommandError
+    })?;
+    println!("{}", hash);
+    Ok(())
+}
+
+/// Prompts for a secret twice without echo, e.g. `Password: ` then `Confirm password: `.
+pub(crate) fn prompt_confirmed(label: &str) -> io::Result<String> {
+    let secret = rpassword::prompt_password(format!("{}: ", label))?;
+    if rpassword::prompt_password(format!("Confirm {}: ", label.to_lowercase()))? != secret {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{}s do not match", label.to_lowercase()),
+        ));
+    }
+    Ok(secret)
+}
+
+/// First line of `input` without its line ending.
+fn read_plaintext<R: BufRead>(mut input: R) -> io::Result<String> {
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn piped_password_keeps_inner_whitespace() {
+        let input = Cursor::new(b" pass word \r\nignored\n".to_vec());
+        assert_eq!(read_plaintext(input).unwrap(), " pass word ");
+    }
+
+    #[test]
+    fn empty_input_reads_empty_password() {
+        assert_eq!(read_plaintext(Cursor::new(Vec::new())).unwrap(), "");
+    }
+}
@@ -0,0 +1,88 @@
+use crate::answer_file::{AnswerFile, AnswerFormat, ProfileStore};
+use crate::auto_installer::commands::{
+    answer::{age_recipients, read_answer, read_answer_file},
+    constants::{CommandError, EncryptMode},
+};
+
+/// `answer profile save`: validates the answer at `path` and stores it as `name`,
+/// age-encrypted when `encrypt` is given.
+pub fn save_profile(
+    store: &ProfileStore,
+    name: &str,
+    path: &str,
+    format: AnswerFormat,
+    encrypt: Option<&EncryptMode>,
+) -> Result<(), CommandError> {
+    let contents = read_answer(path)?;
+    let answer = AnswerFile::from_str_as(&contents, format).map_err(|e| {
+        eprintln!("{}: {}", path, e);
+        CommandError
+    })?;
+
+    let saved = match encrypt {
+        Some(mode) => store.save_encrypted(name, &answer, &age_recipients(mode)?),
+        None => store.save(name, &answer),
+    }
+    .map_err(report)?;
+    println!("Saved profile {} to {}", name, saved.display());
+    Ok(())
+}
+
+/// `answer profile list`: prints one stored profile name per line.
+pub fn list_profiles(store: &ProfileStore) -> Result<(), CommandError> {
+    for name in store.list().map_err(report)? {
+        println!("{}", name);
+    }
+    Ok(())
+}
+
+/// `answer profile show`: prints the stored TOML of profile `name`, decrypted if needed.
+pub fn show_profile(store: &ProfileStore, name: &str) -> Result<(), CommandError> {
+    let path = store.stored_path(name).map_err(report)?;
+    print!("{}", read_answer_file(&path, name)?);
+    Ok(())
+}
+
+/// `answer profile delete`: removes profile `name`.
+pub fn delete_profile(store: &ProfileStore, name: &str) -> Result<(), CommandError> {
+    store.delete(name).map_err(report)?;
+    println!("Deleted profile {}", name);
+    Ok(())
+}
+
+fn report(e: crate::answer_file::ProfileError) -> CommandError {
+    eprintln!("{}", e);
+    CommandError
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_commands_round_trip() {
+        let root = std::env::temp_dir().join(format!("pveauto-profile-cmd-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let store = ProfileStore::new(&root);
+
+        let source = root.with_extension("toml");
+        std::fs::write(&source, AnswerFile::default().to_toml_string().unwrap()).unwrap();
+        let source = source.to_string_lossy().into_owned();
+
+        assert_eq!(
+            save_profile(&store, "homelab", &source, AnswerFormat::Toml, None),
+            Ok(())
+        );
+        assert_eq!(list_profiles(&store), Ok(()));
+        assert_eq!(show_profile(&store, "homelab"), Ok(()));
+        assert_eq!(delete_profile(&store, "homelab"), Ok(()));
+        assert_eq!(show_profile(&store, "homelab"), Err(CommandError));
+        assert_eq!(
+            save_profile(&store, "../escape", &source, AnswerFormat::Toml, None),
+            Err(CommandError)
+        );
+
+        std::fs::remove_file(source).unwrap();
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::answer_file::{
+    TrustedKeys,
+    file::write_atomic,
+    signing::{TRUSTED_KEYS_ENV, sign, signature_path},
+};
+use crate::auto_installer::commands::{answer::resolve_answer_path, constants::CommandError};
+use ssh_key::{HashAlg, PrivateKey};
+use std::path::Path;
+
+/// `answer sign`: writes a detached signature of the answer file at `path`,
+/// made with the ed25519 OpenSSH private key at `key`, to `<path>.sig`.
+///
+/// The file is signed as stored, so encrypted answers are signed encrypted.
+pub fn sign_answer_file(path: &str, key: &str) -> Result<(), CommandError> {
+    let file = resolve_answer_path(path)?;
+    let private = PrivateKey::read_openssh_file(Path::new(key)).map_err(|e| {
+        eprintln!("Failed to read {}: {}", key, e);
+        CommandError
+    })?;
+    let contents = std::fs::read(&file).map_err(|e| {
+        eprintln!("Failed to read {}: {}", path, e);
+        CommandError
+    })?;
+
+    let signature = sign(&contents, &private).map_err(|e| {
+        eprintln!("{}: {}", key, e);
+        CommandError
+    })?;
+    let out = signature_path(&file);
+    write_atomic(&out, signature.as_bytes(), false).map_err(|e| {
+        eprintln!("Failed to write {}: {}", out.display(), e);
+        CommandError
+    })?;
+
+    println!("Wrote {}", out.display());
+    Ok(())
+}
+
+/// `answer verify`: checks `<path>.sig` against the keys in the file
+/// `trusted_keys`, or in `$PVEAUTO_TRUSTED_KEYS` when not given.
+///
+/// # Returns
+/// `Ok(())` and prints the signer's fingerprint when the signature is valid
+/// and made by a trusted key; otherwise `Err(CommandError)`.
+pub fn verify_answer_file(path: &str, trusted_keys: Option<&str>) -> Result<(), CommandError> {
+    let keys = match trusted_keys {
+        Some(file) => TrustedKeys::from_file(file).map_err(|e| {
+            eprintln!("{}", e);
+            CommandError
+        })?,
+        None => trusted_keys_from_env()?.ok_or_else(|| {
+            eprintln!(
+                "No trusted keys; pass --trusted-keys or set ${}",
+                TRUSTED_KEYS_ENV
+            );
+            CommandError
+        })?,
+    };
+
+    let file = resolve_answer_path(path)?;
+    let contents = std::fs::read(&file).map_err(|e| {
+        eprintln!("Failed to read {}: {}", path, e);
+        CommandError
+    })?;
+    let signer = keys.verify_detached(&file, &contents).map_err(|e| {
+        eprintln!("{}: {}", path, e);
+        CommandError
+    })?;
+
+    println!("Good signature by {}", signer.fingerprint(HashAlg::Sha256));
+    Ok(())
+}
+
+/// The keys of `$PVEAUTO_TRUSTED_KEYS`, or `None` when signatures are not enforced.
+pub(crate) fn trusted_keys_from_env() -> Result<Option<TrustedKeys>, CommandError> {
+    TrustedKeys::from_env().transpose().map_err(|e| {
+        eprintln!("${}: {}", TRUSTED_KEYS_ENV, e);
+        CommandError
+    })
+}
+
+/// When `$PVEAUTO_TRUSTED_KEYS` is set, requires `contents`, read from `file`,
+/// to carry a valid `<file>.sig` by one of those keys; `label` names it in messages.
+pub(crate) fn verify_if_enforced(
+    file: &Path,
+    contents: &[u8],
+    label: &str,
+) -> Result<(), CommandError> {
+    let Some(keys) = trusted_keys_from_env()? else {
+        return Ok(());
+    };
+    keys.verify_detached(file, contents).map_err(|e| {
+        eprintln!("{}: {}", label, e);
+        CommandError
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer_file::AnswerFile;
+    use ssh_key::{LineEnding, private::Ed25519Keypair};
+
+    #[test]
+    fn signed_answers_verify_against_trusted_keys() {
+        let dir = std::env::temp_dir().join(format!("pveauto-sign-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let answer = dir.join("answer.toml").to_string_lossy().into_owned();
+        let key = dir.join("operator");
+        let trusted = dir.join("trusted_keys").to_string_lossy().into_owned();
+        let untrusted = dir.join("untrusted_keys").to_string_lossy().into_owned();
+
+        AnswerFile::default().to_path(&answer, false).unwrap();
+        let operator = PrivateKey::from(Ed25519Keypair::from_seed(&[7; 32]));
+        operator.write_openssh_file(&key, LineEnding::LF).unwrap();
+        let stranger = PrivateKey::from(Ed25519Keypair::from_seed(&[8; 32]));
+        for (path, k) in [(&trusted, &operator), (&untrusted, &stranger)] {
+            std::fs::write(path, k.public_key().to_openssh().unwrap()).unwrap();
+        }
+
+        assert_eq!(
+            verify_answer_file(&answer, Some(&trusted)),
+            Err(CommandError)
+        );
+        assert_eq!(sign_answer_file(&answer, &key.to_string_lossy()), Ok(()));
+        assert!(dir.join("answer.toml.sig").is_file());
+        assert_eq!(verify_answer_file(&answer, Some(&trusted)), Ok(()));
+        assert_eq!(
+            verify_answer_file(&answer, Some(&untrusted)),
+            Err(CommandError)
+        );
+
+        std::fs::write(&answer, "[global]\n").unwrap();
+        assert_eq!(
+            verify_answer_file(&answer, Some(&trusted)),
+            Err(CommandError)
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+use crate::answer_file::AnswerFormat;
+use crate::auto_installer::commands::{
+    answer::{read_editable_answer, write_edited_answer},
+    constants::CommandError,
+};
+use ssh_key::{LineEnding, PrivateKey, private::Ed25519Keypair};
+use std::path::Path;
+
+/// `generate-ssh-key`: creates an ed25519 keypair at `key` / `key.pub` and adds
+/// the public key to `root-ssh-keys` of the answer file at `answer_path`.
+///
+/// # Notes
+/// - Refuses to overwrite existing key files.
+/// - The updated answer is validated before anything is written.
+/// - TOML answers keep their comments and formatting.
+/// - `comment` defaults to `root@<fqdn>` of the answer file.
+pub fn generate_ssh_key(
+    answer_path: &str,
+    key: &str,
+    format: AnswerFormat,
+    comment: Option<&str>,
+) -> Result<(), CommandError> {
+    let (contents, mut answer) = read_editable_answer(answer_path, format)?;
+
+    let public_path = format!("{}.pub", key);
+    for path in [key, public_path.as_str()] {
+        if Path::new(path).exists() {
+            eprintln!("Refusing to overwrite existing file: {}", path);
+            return Err(CommandError);
+        }
+    }
+
+    let comment = comment
+        .map(str::to_owned)
+        .unwrap_or_else(|| format!("root@{}", answer.global.fqdn));
+    let private = generate_keypair(&comment).map_err(|e| {
+        eprintln!("Failed to generate key: {}", e);
+        CommandError
+    })?;
+    let public = private.public_key().to_openssh().map_err(|e| {
+        eprintln!("Failed to encode public key: {}", e);
+        CommandError
+    })?;
+
+    let added = answer.global.add_root_ssh_key(&public).map_err(|e| {
+        eprintln!("{}: {}", answer_path, e);
+        CommandError
+    })?;
+    answer.validate().map_err(|e| {
+        eprintln!("{}: {}", answer_path, e);
+        CommandError
+    })?;
+
+    private
+        .write_openssh_file(Path::new(key), LineEnding::LF)
+        .map_err(|e| {
+            eprintln!("Failed to write {}: {}", key, e);
+            CommandError
+        })?;
+    std::fs::write(&public_path, format!("{}\n", public)).map_err(|e| {
+        eprintln!("Failed to write {}: {}", public_path, e);
+        CommandError
+    })?;
+
+    if added {
+        write_edited_answer(answer_path, &contents, answer, format)?;
+    }
+    println!("Wrote {} and {}", key, public_path);
+    println!("Added {} to {}", public, answer_path);
+    Ok(())
+}
+
+fn generate_keypair(comment: &str) -> Result<PrivateKey, getrandom::Error> {
+    let mut seed = [0u8; 32];
+    getrandom::fill(&mut seed)?;
+    let mut key = PrivateKey::from(Ed25519Keypair::from_seed(&seed));
+    key.set_comment(comment);
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer_file::AnswerFile;
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("pveauto-sshkey-{}-{}", std::process::id(), name))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn generated_keys_are_unique_and_commented() {
+        let a = generate_keypair("root@pve").unwrap();
+        let b = generate_keypair("root@pve").unwrap();
+        assert_ne!(a.public_key(), b.public_key());
+
+        let public = a.public_key().to_openssh().unwrap();
+        assert!(public.starts_with("ssh-ed25519 "), "key was: {}", public);
+        assert!(public.ends_with(" root@pve"), "key was: {}", public);
+    }
+
+    #[test]
+    fn key_is_written_and_added_to_answer() {
+        let answer_path = temp_path("answer.toml");
+        let key = temp_path("id_ed25519");
+        std::fs::write(
+            &answer_path,
+            AnswerFile::default().to_toml_string().unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            generate_ssh_key(&answer_path, &key, AnswerFormat::Toml, None),
+            Ok(())
+        );
+
+        let public = std::fs::read_to_string(format!("{}.pub", key)).unwrap();
+        let answer =
+            AnswerFile::from_toml_str(&std::fs::read_to_string(&answer_path).unwrap()).unwrap();
+        assert_eq!(
+            answer.global.root_ssh_keys,
+            Some(vec![public.trim().to_string()])
+        );
+        assert!(public.trim().ends_with("root@proxmox.lab.local"));
+        assert!(PrivateKey::read_openssh_file(Path::new(&key)).is_ok());
+
+        // Existing key files are never overwritten.
+        assert_eq!(
+            generate_ssh_key(&answer_path, &key, AnswerFormat::Toml, None),
+            Err(CommandError)
+        );
+
+        for path in [answer_path, format!("{}.pub", key), key] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn toml_comments_survive_key_generation() {
+        let answer_path = temp_path("commented.toml");
+        let key = temp_path("id_commented");
+        std::fs::write(
+            &answer_path,
+            format!(
+                "# hand-written\n{}",
+                AnswerFile::default().to_toml_string().unwrap()
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(
+            generate_ssh_key(&answer_path, &key, AnswerFormat::Toml, None),
+            Ok(())
+        );
+        let contents = std::fs::read_to_string(&answer_path).unwrap();
+        assert!(contents.starts_with("# hand-written\n"));
+        assert!(contents.contains("root-ssh-keys"));
+
+        for path in [answer_path, format!("{}.pub", key), key] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn profiles_are_rejected() {
+        assert_eq!(
+            generate_ssh_key("@homelab", &temp_path("unused"), AnswerFormat::Toml, None),
+            Err(CommandError)
+        );
+    }
+}
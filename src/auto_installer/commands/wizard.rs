@@ -0,0 +1,373 @@
+use crate::answer_file::{
+    AnswerFile, AnswerFileBuilder,
+    sections::{
+        BtrfsRaidLevel, Cidr, CountryCode, DiskList, DiskSetupError, EmailAddress, FQDN_PATTERN,
+        Filesystem, GlobalConfigError, KeyboardLayout, NetworkConfigError, Timezone, ZfsRaidLevel,
+        hash_password,
+    },
+};
+use std::{
+    fmt::Display,
+    io::{self, BufRead, Write},
+    str::FromStr,
+};
+
+/// Reads a secret without echoing it (the prompt is passed for display).
+pub type PasswordReader = Box<dyn FnMut(&str) -> io::Result<String>>;
+
+/// Line-oriented question/answer helper used by the wizard.
+///
+/// Generic over its input and output so the flow can be driven by tests.
+pub struct Prompter<R, W> {
+    input: R,
+    output: W,
+    read_password: PasswordReader,
+}
+
+impl Prompter<io::StdinLock<'static>, io::Stdout> {
+    /// Prompter bound to the terminal, reading passwords without echo.
+    pub fn stdio() -> Self {
+        Self::new(
+            io::stdin().lock(),
+            io::stdout(),
+            Box::new(|prompt| rpassword::prompt_password(prompt)),
+        )
+    }
+}
+
+impl<R: BufRead, W: Write> Prompter<R, W> {
+    pub fn new(input: R, output: W, read_password: PasswordReader) -> Self {
+        Self {
+            input,
+            output,
+            read_password,
+        }
+    }
+
+    /// Asks a free-form question; an empty answer selects `default`.
+    pub fn ask(&mut self, question: &str, default: &str) -> io::Result<String> {
+        if default.is_empty() {
+            write!(self.output, "{}: ", question)?;
+        } else {
+            write!(self.output, "{} [{}]: ", question, default)?;
+        }
+        self.output.flush()?;
+
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "input closed before the wizard finished",
+            ));
+        }
+
+        let answer = line.trim();
+        Ok(if answer.is_empty() {
+            default.to_owned()
+        } else {
+            answer.to_owned()
+        })
+    }
+
+    /// Re-asks until `check` accepts the answer, printing its error each time.
+    pub fn ask_valid<T, E, F>(&mut self, question: &str, default: &str, check: F) -> io::Result<T>
+    where
+        E: Display,
+        F: Fn(&str) -> Result<T, E>,
+    {
+        loop {
+            let answer = self.ask(question, default)?;
+            match check(&answer) {
+                Ok(value) => return Ok(value),
+                Err(e) => writeln!(self.output, "  invalid value ({}), try again", e)?,
+            }
+        }
+    }
+
+    /// Re-asks until the answer parses as `T`.
+    pub fn ask_parsed<T>(&mut self, question: &str, default: &str) -> io::Result<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.ask_valid(question, default, str::parse::<T>)
+    }
+
+    pub fn ask_yes_no(&mut self, question: &str, default: bool) -> io::Result<bool> {
+        let default = if default { "y" } else { "n" };
+        self.ask_valid(question, default, |a| match a.to_lowercase().as_str() {
+            "y" | "yes" => Ok(true),
+            "n" | "no" => Ok(false),
+            _ => Err("expected y or n"),
+        })
+    }
+
+    pub fn password(&mut self, prompt: &str) -> io::Result<String> {
+        self.output.flush()?;
+        (self.read_password)(prompt)
+    }
+
+    pub fn say(&mut self, message: &str) -> io::Result<()> {
+        writeln!(self.output, "{}", message)
+    }
+}
+
+/// Walks the user through the answer file sections and returns a validated [`AnswerFile`].
+///
+/// The keyboard layout defaults to [`KeyboardLayout::detect`], falling back to `en-us`.
+pub fn run_wizard<R: BufRead, W: Write>(p: &mut Prompter<R, W>) -> io::Result<AnswerFile> {
+    let keyboard = KeyboardLayout::detect().unwrap_or(KeyboardLayout::EnglishUS);
+    run_wizard_with(p, hash_password, keyboard)
+}
+
+fn run_wizard_with<R: BufRead, W: Write>(
+    p: &mut Prompter<R, W>,
+    hash: fn(&str) -> Result<String, GlobalConfigError>,
+    default_keyboard: KeyboardLayout,
+) -> io::Result<AnswerFile> {
+    p.say("== Global ==")?;
+    let keyboard: KeyboardLayout = p.ask_parsed("Keyboard layout", default_keyboard.as_str())?;
+    let timezone: Timezone = p.ask_parsed("Timezone", Timezone::default().as_str())?;
+    let suggested_country = CountryCode::from_timezone(&timezone).unwrap_or_default();
+    let country = p.ask_valid("Country (code or name)", suggested_country.as_str(), |a| {
+        CountryCode::from_str(a).or_else(|_| CountryCode::from_name(a))
+    })?;
+    let fqdn = p.ask_valid("FQDN", "proxmox.lab.local", |a| {
+        matching(
+            a,
+            FQDN_PATTERN.is_match(a) && a.len() <= 255,
+            "fqdn.invalid_format",
+        )
+    })?;
+    let mailto: EmailAddress = p.ask_parsed("Admin email", EmailAddress::default().as_str())?;
+    let root_password_hashed = ask_root_password(p, hash)?;
+
+    let mut builder = AnswerFile::builder()
+        .keyboard(keyboard)
+        .country(country)
+        .timezone(timezone)
+        .fqdn(fqdn)
+        .mailto(mailto.as_str())
+        .root_password_hashed(root_password_hashed);
+
+    p.say("== Disk setup ==")?;
+    builder = ask_disk_setup(p, builder)?;
+
+    p.say("== Network ==")?;
+    builder = ask_network(p, builder)?;
+
+    builder
+        .build()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.code()))
+}
+
+fn matching(answer: &str, ok: bool, code: &'static str) -> Result<String, &'static str> {
+    if ok { Ok(answer.to_owned()) } else { Err(code) }
+}
+
+fn ask_root_password<R: BufRead, W: Write>(
+    p: &mut Prompter<R, W>,
+    hash: fn(&str) -> Result<String, GlobalConfigError>,
+) -> io::Result<String> {
+    loop {
+        let password = p.password("Root password: ")?;
+        if password.is_empty() {
+            p.say("  password must not be empty")?;
+            continue;
+        }
+        if p.password("Confirm root password: ")? != password {
+            p.say("  passwords do not match, try again")?;
+            continue;
+        }
+
+        match hash(&password) {
+            Ok(hash) => return Ok(hash),
+            Err(e) => p.say(&format!("  {}", e.code()))?,
+        }
+    }
+}
+
+fn ask_disk_setup<R: BufRead, W: Write>(
+    p: &mut Prompter<R, W>,
+    builder: AnswerFileBuilder,
+) -> io::Result<AnswerFileBuilder> {
+    let filesystem: Filesystem = p.ask_parsed("Filesystem (ext4, xfs, zfs, btrfs)", "ext4")?;
+    let disks: Vec<String> = p.ask_valid("Disks (comma separated)", "sda", |a| {
+        let disks: Vec<String> = a
+            .split(',')
+            .map(|d| d.trim().to_owned())
+            .filter(|d| !d.is_empty())
+            .collect();
+        DiskList::try_new(&disks).map(|_| disks)
+    })?;
+
+    let builder = builder.disks(disks.clone()).filesystem(filesystem);
+    let single = disks.len() == 1;
+
+    Ok(match filesystem {
+        Filesystem::Zfs => {
+            let default = if single { "raid0" } else { "raid1" };
+            let raid = p.ask_valid("ZFS RAID level", default, |a| {
+                let raid: ZfsRaidLevel = a.parse()?;
+                if raid.supports_disk_count(disks.len()) {
+                    Ok(raid)
+                } else {
+                    Err(DiskSetupError::ZfsRaidDiskCount)
+                }
+            })?;
+            builder.zfs_raid(raid)
+        }
+        Filesystem::Btrfs => {
+            let default = if single { "raid0" } else { "raid1" };
+            let raid: BtrfsRaidLevel = p.ask_parsed("BTRFS RAID level", default)?;
+            builder.btrfs_raid(raid)
+        }
+        Filesystem::Ext4 | Filesystem::Xfs => builder,
+    })
+}
+
+fn ask_network<R: BufRead, W: Write>(
+    p: &mut Prompter<R, W>,
+    builder: AnswerFileBuilder,
+) -> io::Result<AnswerFileBuilder> {
+    if p.ask_yes_no("Use DHCP", true)? {
+        return Ok(builder.dhcp_network());
+    }
+
+    let cidr: Cidr = p.ask_parsed("Address (CIDR, e.g. 192.168.1.10/24)", "")?;
+    let gateway = p.ask_valid("Gateway", "", |a| {
+        let gateway: std::net::IpAddr = a.parse().map_err(|_| NetworkConfigError::Gateway)?;
+        if cidr.contains(&gateway) {
+            Ok(gateway)
+        } else {
+            Err(NetworkConfigError::GatewayOutsideSubnet)
+        }
+    })?;
+    let dns: std::net::IpAddr = p.ask_parsed("DNS server", &gateway.to_string())?;
+
+    Ok(builder.static_network(cidr.to_string(), gateway.to_string(), dns.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer_file::sections::{NetworkSource, RebootMode};
+    use std::io::Cursor;
+
+    const HASH: &str = "$6$rounds=656000$12345678$AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
+
+    fn prompter(input: &str, passwords: Vec<&'static str>) -> Prompter<Cursor<Vec<u8>>, Vec<u8>> {
+        let mut passwords = passwords.into_iter();
+        Prompter::new(
+            Cursor::new(input.as_bytes().to_vec()),
+            Vec::new(),
+            Box::new(move |_| {
+                passwords
+                    .next()
+                    .map(String::from)
+                    .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))
+            }),
+        )
+    }
+
+    /* ---------------- PROMPTER ---------------- */
+
+    #[test]
+    fn empty_answer_selects_default() {
+        let mut p = prompter("\n", vec![]);
+        assert_eq!(p.ask("Q", "dflt").unwrap(), "dflt");
+    }
+
+    #[test]
+    fn invalid_answers_are_re_asked() {
+        let mut p = prompter("enus\nde\n", vec![]);
+        let kb: KeyboardLayout = p.ask_parsed("Keyboard", "en-us").unwrap();
+        assert_eq!(kb, KeyboardLayout::German);
+        let out = String::from_utf8(p.output).unwrap();
+        assert!(
+            out.contains("keyboard.invalid_format"),
+            "output was: {}",
+            out
+        );
+    }
+
+    #[test]
+    fn closed_input_is_an_error() {
+        let mut p = prompter("", vec![]);
+        let err = p.ask("Q", "x").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    /* ---------------- WIZARD ---------------- */
+
+    fn script(lines: &[&str]) -> String {
+        lines.iter().map(|l| format!("{}\n", l)).collect()
+    }
+
+    fn stub_hash(_: &str) -> Result<String, GlobalConfigError> {
+        Ok(HASH.to_string())
+    }
+
+    fn picky_hash(password: &str) -> Result<String, GlobalConfigError> {
+        match password {
+            "secret" => Ok(HASH.to_string()),
+            _ => Err(GlobalConfigError::RootPasswordHashed),
+        }
+    }
+
+    #[test]
+    fn zfs_static_network_flow() {
+        let input = script(&[
+            "de",
+            "Europe/Berlin",
+            "",
+            "pve01.lab.local",
+            "admin@example.com",
+            "zfs",
+            "sda, sdb",
+            "",
+            "n",
+            "10.0.0.10/24",
+            "10.0.1.1",
+            "10.0.0.1",
+            "",
+        ]);
+        // First confirmation mismatches and is re-asked, as is the off-subnet gateway.
+        let mut p = prompter(&input, vec!["secret", "typo", "secret", "secret"]);
+        let answer = run_wizard_with(&mut p, stub_hash, KeyboardLayout::EnglishUS).unwrap();
+
+        assert_eq!(answer.global.keyboard, KeyboardLayout::German);
+        assert_eq!(answer.global.country.as_str(), "de");
+        assert_eq!(answer.global.fqdn, "pve01.lab.local");
+        assert_eq!(answer.global.root_password_hashed, HASH);
+        assert_eq!(answer.global.reboot_mode, RebootMode::Reboot);
+        assert_eq!(answer.disk_setup.filesystem, Filesystem::Zfs);
+        assert_eq!(
+            answer.disk_setup.zfs.unwrap().raid,
+            Some(ZfsRaidLevel::Raid1)
+        );
+        assert_eq!(answer.network.source, NetworkSource::FromAnswer);
+        assert_eq!(answer.network.dns.as_deref(), Some("10.0.0.1"));
+        let out = String::from_utf8(p.output).unwrap();
+        assert!(out.contains("do not match"));
+        assert!(out.contains("network.gateway.outside_subnet"), "{}", out);
+    }
+
+    #[test]
+    fn defaults_with_hash_error_re_asked() {
+        let input = script(&["", "", "", "", "", "", "", "y"]);
+        let mut p = prompter(&input, vec!["weak", "weak", "secret", "secret"]);
+        let answer = run_wizard_with(&mut p, picky_hash, KeyboardLayout::Swedish).unwrap();
+
+        assert_eq!(answer.global.root_password_hashed, HASH);
+        assert_eq!(answer.global.keyboard, KeyboardLayout::Swedish);
+        assert!(
+            String::from_utf8(p.output)
+                .unwrap()
+                .contains("root_password_hashed.invalid_format")
+        );
+        assert_eq!(answer.disk_setup.filesystem, Filesystem::Ext4);
+        assert_eq!(answer.network.source, NetworkSource::FromDhcp);
+        assert!(answer.validate().is_ok());
+    }
+}
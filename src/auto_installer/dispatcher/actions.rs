@@ -1,9 +1,175 @@
+use crate::answer_file::{
+    AnswerFormat, AnswerPreset, LintWarning, SchemaVersion, sections::PasswordHashFormat,
+};
+use crate::auto_installer::commands::constants::{EncryptMode, InventoryBuild, SshKeySource};
+use crate::iso::{alternates::DownloadVia, channel::IsoChannel, writer::IsoBackend};
+use std::collections::BTreeMap;
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum DispatchAction {
-    Download { dest_path: Option<String> },
-    AutoInstallerOffline,
-    AutoInstallerNetwork,
+    Download {
+        dest_path: Option<String>,
+        version: Option<String>,
+        mirror: Option<String>,
+        /// Releases or test ISOs (`--channel`).
+        channel: IsoChannel,
+        /// Where the ISO bytes come from (`--via`).
+        via: DownloadVia,
+        quiet: bool,
+        /// Stream the verified ISO to standard output (`--stdout`).
+        stdout: bool,
+    },
+    AutoInstallerOffline {
+        answer: String,
+        format: AnswerFormat,
+        iso: String,
+        out: String,
+        packages: Vec<String>,
+        assets: Option<String>,
+        kernel_args: Vec<String>,
+        volume_label: Option<String>,
+        backend: IsoBackend,
+        dry_run: bool,
+        smoke_test: bool,
+        sign_key: Option<String>,
+        inventory: Option<InventoryBuild>,
+    },
+    AutoInstallerNetwork {
+        iso: String,
+        out: String,
+        url: Option<String>,
+        cert_fingerprint: Option<String>,
+        kernel_args: Vec<String>,
+        volume_label: Option<String>,
+        backend: IsoBackend,
+        dry_run: bool,
+        smoke_test: bool,
+        sign_key: Option<String>,
+    },
+    IsoListVersions {
+        mirror: Option<String>,
+        channel: IsoChannel,
+    },
+    IsoVerify {
+        path: String,
+        key: Option<String>,
+    },
+    IsoVerifySignature {
+        path: String,
+        key: String,
+    },
+    IsoInspect {
+        path: String,
+    },
+    IsoPrune {
+        keep: Option<usize>,
+    },
+    BundleExport {
+        iso: String,
+        answers: Vec<String>,
+        key: Option<String>,
+        out: String,
+    },
+    BundleImport {
+        bundle: String,
+        dir: String,
+        key: Option<String>,
+    },
     ServeAnswers,
+    AnswerValidate {
+        path: String,
+        format: AnswerFormat,
+        strict: bool,
+        assistant: bool,
+    },
+    AnswerLint {
+        path: String,
+        format: AnswerFormat,
+        allow: Vec<LintWarning>,
+    },
+    AnswerNew {
+        interactive: bool,
+        preset: Option<AnswerPreset>,
+        out: String,
+        format: AnswerFormat,
+        encrypt: Option<EncryptMode>,
+    },
+    AnswerConvert {
+        path: String,
+        format: AnswerFormat,
+    },
+    AnswerRender {
+        path: String,
+        format: AnswerFormat,
+        vars: BTreeMap<String, String>,
+    },
+    AnswerGenerate {
+        template: String,
+        inventory: String,
+        out_dir: String,
+        format: AnswerFormat,
+    },
+    AnswerDiff {
+        old: String,
+        new: String,
+        format: AnswerFormat,
+    },
+    AnswerSchema,
+    AnswerMigrate {
+        path: String,
+        from: Option<SchemaVersion>,
+    },
+    AnswerConvertPassword {
+        path: String,
+        method: PasswordHashFormat,
+        rounds: Option<u32>,
+    },
+    AnswerSign {
+        path: String,
+        key: String,
+    },
+    AnswerVerify {
+        path: String,
+        trusted_keys: Option<String>,
+    },
+    AnswerFetch {
+        url: String,
+        format: AnswerFormat,
+        save: Option<String>,
+    },
+    AnswerAddSshKeys {
+        path: String,
+        format: AnswerFormat,
+        sources: Vec<SshKeySource>,
+    },
+    ProfileSave {
+        name: String,
+        path: String,
+        format: AnswerFormat,
+        encrypt: Option<EncryptMode>,
+    },
+    ProfileList,
+    ProfileShow {
+        name: String,
+    },
+    ProfileDelete {
+        name: String,
+    },
+    HashPassword {
+        method: PasswordHashFormat,
+        rounds: Option<u32>,
+    },
+    GenerateSshKey {
+        answer: String,
+        key: String,
+        format: AnswerFormat,
+        comment: Option<String>,
+    },
+    Flash {
+        device: String,
+        iso: String,
+        yes: bool,
+    },
     Help,
     Exit,
 }
@@ -1,20 +1,245 @@
-use crate::auto_installer::commands::downloader::download_pve_iso;
+use crate::answer_file::ProfileStore;
+use crate::auto_installer::commands::{
+    answer::{
+        convert_answer_file, convert_password, diff_answer_files, generate_answer_files,
+        lint_answer_file, migrate_answer_file, new_answer_file, print_answer_schema,
+        render_answer_template, validate_answer_file,
+    },
+    bundle::{export_bundle, import_bundle_command},
+    constants::CommandError,
+    downloader::download_pve_iso,
+    fetch::fetch_answer_file,
+    flash::flash_iso,
+    installer::{
+        BuildOptions, OfflineExtras, build_network_installer, build_offline_installer,
+        build_offline_installers,
+    },
+    iso::{
+        inspect_iso_image, list_iso_versions_command, prune_iso_cache, verify_iso_image,
+        verify_iso_signature,
+    },
+    key_import::add_ssh_keys,
+    password::hash_password_command,
+    profile::{delete_profile, list_profiles, save_profile, show_profile},
+    signature::{sign_answer_file, verify_answer_file},
+    ssh_key::generate_ssh_key,
+};
 use crate::auto_installer::dispatcher::actions::DispatchAction;
 
-pub async fn execute(action: DispatchAction) {
+/// Runs the dispatched action.
+///
+/// # Returns
+/// `Err(CommandError)` when the action failed and the process should exit non-zero.
+/// Failure details have already been printed to standard error.
+pub async fn execute(action: DispatchAction) -> Result<(), CommandError> {
     match action {
-        DispatchAction::Download { dest_path } => {
-            download_pve_iso(dest_path).await;
+        DispatchAction::Download {
+            dest_path,
+            version,
+            mirror,
+            channel,
+            via,
+            quiet,
+            stdout,
+        } => {
+            return download_pve_iso(dest_path, version, mirror, channel, via, quiet, stdout).await;
         }
-        DispatchAction::AutoInstallerOffline => {
-            println!("Selected: offline installer");
+        DispatchAction::AutoInstallerOffline {
+            answer,
+            format,
+            iso,
+            out,
+            packages,
+            assets,
+            kernel_args,
+            volume_label,
+            backend,
+            dry_run,
+            smoke_test,
+            sign_key,
+            inventory,
+        } => {
+            let extras = OfflineExtras {
+                packages,
+                assets,
+                kernel_args,
+                volume_label,
+            };
+            let options = BuildOptions {
+                backend,
+                dry_run,
+                smoke_test,
+                sign_key: sign_key.as_deref(),
+            };
+            if let Some(build) = inventory {
+                return build_offline_installers(&answer, format, &build, &iso, &extras, options);
+            }
+            return build_offline_installer(&answer, format, &iso, &out, &extras, options);
         }
-        DispatchAction::AutoInstallerNetwork => {
-            println!("Selected: network installer");
+        DispatchAction::AutoInstallerNetwork {
+            iso,
+            out,
+            url,
+            cert_fingerprint,
+            kernel_args,
+            volume_label,
+            backend,
+            dry_run,
+            smoke_test,
+            sign_key,
+        } => {
+            return build_network_installer(
+                &iso,
+                &out,
+                url.as_deref(),
+                cert_fingerprint.as_deref(),
+                &kernel_args,
+                volume_label.as_deref(),
+                BuildOptions {
+                    backend,
+                    dry_run,
+                    smoke_test,
+                    sign_key: sign_key.as_deref(),
+                },
+            );
+        }
+        DispatchAction::IsoListVersions { mirror, channel } => {
+            return list_iso_versions_command(mirror.as_deref(), channel).await;
+        }
+        DispatchAction::IsoVerify { path, key } => {
+            return verify_iso_image(&path, key.as_deref()).await;
+        }
+        DispatchAction::IsoVerifySignature { path, key } => {
+            return verify_iso_signature(&path, &key);
+        }
+        DispatchAction::IsoInspect { path } => {
+            return inspect_iso_image(&path);
+        }
+        DispatchAction::IsoPrune { keep } => {
+            return prune_iso_cache(keep);
+        }
+        DispatchAction::BundleExport {
+            iso,
+            answers,
+            key,
+            out,
+        } => {
+            return export_bundle(&iso, &answers, key.as_deref(), &out).await;
+        }
+        DispatchAction::BundleImport { bundle, dir, key } => {
+            return import_bundle_command(&bundle, &dir, key.as_deref());
         }
         DispatchAction::ServeAnswers => {
             println!("Selected: serve answers");
         }
+        DispatchAction::AnswerValidate {
+            path,
+            format,
+            strict,
+            assistant,
+        } => {
+            return validate_answer_file(&path, format, strict, assistant);
+        }
+        DispatchAction::AnswerLint {
+            path,
+            format,
+            allow,
+        } => {
+            return lint_answer_file(&path, format, &allow);
+        }
+        DispatchAction::AnswerNew {
+            interactive,
+            preset,
+            out,
+            format,
+            encrypt,
+        } => {
+            return new_answer_file(&out, interactive, preset, format, encrypt.as_ref());
+        }
+        DispatchAction::AnswerConvert { path, format } => {
+            return convert_answer_file(&path, format);
+        }
+        DispatchAction::AnswerRender { path, format, vars } => {
+            return render_answer_template(&path, format, &vars);
+        }
+        DispatchAction::AnswerGenerate {
+            template,
+            inventory,
+            out_dir,
+            format,
+        } => {
+            return generate_answer_files(&template, &inventory, &out_dir, format);
+        }
+        DispatchAction::AnswerDiff { old, new, format } => {
+            return diff_answer_files(&old, &new, format);
+        }
+        DispatchAction::AnswerSchema => {
+            return print_answer_schema();
+        }
+        DispatchAction::AnswerMigrate { path, from } => {
+            return migrate_answer_file(&path, from);
+        }
+        DispatchAction::AnswerConvertPassword {
+            path,
+            method,
+            rounds,
+        } => {
+            return convert_password(&path, method, rounds);
+        }
+        DispatchAction::AnswerSign { path, key } => {
+            return sign_answer_file(&path, &key);
+        }
+        DispatchAction::AnswerVerify { path, trusted_keys } => {
+            return verify_answer_file(&path, trusted_keys.as_deref());
+        }
+        DispatchAction::AnswerFetch { url, format, save } => {
+            return fetch_answer_file(&ProfileStore::default(), &url, format, save.as_deref())
+                .await;
+        }
+        DispatchAction::AnswerAddSshKeys {
+            path,
+            format,
+            sources,
+        } => {
+            return add_ssh_keys(&path, format, &sources).await;
+        }
+        DispatchAction::ProfileSave {
+            name,
+            path,
+            format,
+            encrypt,
+        } => {
+            return save_profile(
+                &ProfileStore::default(),
+                &name,
+                &path,
+                format,
+                encrypt.as_ref(),
+            );
+        }
+        DispatchAction::ProfileList => {
+            return list_profiles(&ProfileStore::default());
+        }
+        DispatchAction::ProfileShow { name } => {
+            return show_profile(&ProfileStore::default(), &name);
+        }
+        DispatchAction::ProfileDelete { name } => {
+            return delete_profile(&ProfileStore::default(), &name);
+        }
+        DispatchAction::HashPassword { method, rounds } => {
+            return hash_password_command(method, rounds);
+        }
+        DispatchAction::GenerateSshKey {
+            answer,
+            key,
+            format,
+            comment,
+        } => {
+            return generate_ssh_key(&answer, &key, format, comment.as_deref());
+        }
+        DispatchAction::Flash { device, iso, yes } => {
+            return flash_iso(&device, &iso, yes);
+        }
         DispatchAction::Help => {
             println!("Selected: help");
         }
@@ -22,4 +247,5 @@ pub async fn execute(action: DispatchAction) {
             println!("Selected: exit");
         }
     }
+    Ok(())
 }
@@ -1,5 +1,7 @@
 use crate::auto_installer::{
-    commands::constants::{Commands, IsoType},
+    commands::constants::{
+        AnswerCommand, BundleCommand, Commands, IsoCommand, IsoType, ProfileCommand,
+    },
     dispatcher::actions::DispatchAction,
 };
 
@@ -8,10 +10,214 @@ pub mod execute;
 
 pub fn dispatch(cmd: Commands) -> DispatchAction {
     match cmd {
-        Commands::Download { dest_path } => DispatchAction::Download { dest_path },
-        Commands::AutoInstaller(IsoType::Offline) => DispatchAction::AutoInstallerOffline,
-        Commands::AutoInstaller(IsoType::Network) => DispatchAction::AutoInstallerNetwork,
+        Commands::Download {
+            dest_path,
+            version,
+            mirror,
+            channel,
+            via,
+            quiet,
+            stdout,
+        } => DispatchAction::Download {
+            dest_path,
+            version,
+            mirror,
+            channel,
+            via,
+            quiet,
+            stdout,
+        },
+        Commands::AutoInstaller(IsoType::Offline {
+            answer,
+            format,
+            iso,
+            out,
+            packages,
+            assets,
+            kernel_args,
+            volume_label,
+            backend,
+            dry_run,
+            smoke_test,
+            sign_key,
+            inventory,
+        }) => DispatchAction::AutoInstallerOffline {
+            answer,
+            format,
+            iso,
+            out,
+            packages,
+            assets,
+            kernel_args,
+            volume_label,
+            backend,
+            dry_run,
+            smoke_test,
+            sign_key,
+            inventory,
+        },
+        Commands::AutoInstaller(IsoType::Network {
+            iso,
+            out,
+            url,
+            cert_fingerprint,
+            kernel_args,
+            volume_label,
+            backend,
+            dry_run,
+            smoke_test,
+            sign_key,
+        }) => DispatchAction::AutoInstallerNetwork {
+            iso,
+            out,
+            url,
+            cert_fingerprint,
+            kernel_args,
+            volume_label,
+            backend,
+            dry_run,
+            smoke_test,
+            sign_key,
+        },
+        Commands::Iso(IsoCommand::ListVersions { mirror, channel }) => {
+            DispatchAction::IsoListVersions { mirror, channel }
+        }
+        Commands::Iso(IsoCommand::Verify { path, key }) => DispatchAction::IsoVerify { path, key },
+        Commands::Iso(IsoCommand::VerifySignature { path, key }) => {
+            DispatchAction::IsoVerifySignature { path, key }
+        }
+        Commands::Iso(IsoCommand::Inspect { path }) => DispatchAction::IsoInspect { path },
+        Commands::Iso(IsoCommand::Prune { keep }) => DispatchAction::IsoPrune { keep },
+        Commands::Bundle(BundleCommand::Export {
+            iso,
+            answers,
+            key,
+            out,
+        }) => DispatchAction::BundleExport {
+            iso,
+            answers,
+            key,
+            out,
+        },
+        Commands::Bundle(BundleCommand::Import { bundle, dir, key }) => {
+            DispatchAction::BundleImport { bundle, dir, key }
+        }
         Commands::ServeAnswers => DispatchAction::ServeAnswers,
+        Commands::Answer(AnswerCommand::Validate {
+            path,
+            format,
+            strict,
+            assistant,
+        }) => DispatchAction::AnswerValidate {
+            path,
+            format,
+            strict,
+            assistant,
+        },
+        Commands::Answer(AnswerCommand::Lint {
+            path,
+            format,
+            allow,
+        }) => DispatchAction::AnswerLint {
+            path,
+            format,
+            allow,
+        },
+        Commands::Answer(AnswerCommand::New {
+            interactive,
+            preset,
+            out,
+            format,
+            encrypt,
+        }) => DispatchAction::AnswerNew {
+            interactive,
+            preset,
+            out,
+            format,
+            encrypt,
+        },
+        Commands::Answer(AnswerCommand::Convert { path, format }) => {
+            DispatchAction::AnswerConvert { path, format }
+        }
+        Commands::Answer(AnswerCommand::Render { path, format, vars }) => {
+            DispatchAction::AnswerRender { path, format, vars }
+        }
+        Commands::Answer(AnswerCommand::Generate {
+            template,
+            inventory,
+            out_dir,
+            format,
+        }) => DispatchAction::AnswerGenerate {
+            template,
+            inventory,
+            out_dir,
+            format,
+        },
+        Commands::Answer(AnswerCommand::Diff { old, new, format }) => {
+            DispatchAction::AnswerDiff { old, new, format }
+        }
+        Commands::Answer(AnswerCommand::Schema) => DispatchAction::AnswerSchema,
+        Commands::Answer(AnswerCommand::Migrate { path, from }) => {
+            DispatchAction::AnswerMigrate { path, from }
+        }
+        Commands::Answer(AnswerCommand::ConvertPassword {
+            path,
+            method,
+            rounds,
+        }) => DispatchAction::AnswerConvertPassword {
+            path,
+            method,
+            rounds,
+        },
+        Commands::Answer(AnswerCommand::Sign { path, key }) => {
+            DispatchAction::AnswerSign { path, key }
+        }
+        Commands::Answer(AnswerCommand::Verify { path, trusted_keys }) => {
+            DispatchAction::AnswerVerify { path, trusted_keys }
+        }
+        Commands::Answer(AnswerCommand::Fetch { url, format, save }) => {
+            DispatchAction::AnswerFetch { url, format, save }
+        }
+        Commands::Answer(AnswerCommand::AddSshKeys {
+            path,
+            format,
+            sources,
+        }) => DispatchAction::AnswerAddSshKeys {
+            path,
+            format,
+            sources,
+        },
+        Commands::Answer(AnswerCommand::Profile(cmd)) => match cmd {
+            ProfileCommand::Save {
+                name,
+                path,
+                format,
+                encrypt,
+            } => DispatchAction::ProfileSave {
+                name,
+                path,
+                format,
+                encrypt,
+            },
+            ProfileCommand::List => DispatchAction::ProfileList,
+            ProfileCommand::Show { name } => DispatchAction::ProfileShow { name },
+            ProfileCommand::Delete { name } => DispatchAction::ProfileDelete { name },
+        },
+        Commands::HashPassword { method, rounds } => {
+            DispatchAction::HashPassword { method, rounds }
+        }
+        Commands::GenerateSshKey {
+            answer,
+            key,
+            format,
+            comment,
+        } => DispatchAction::GenerateSshKey {
+            answer,
+            key,
+            format,
+            comment,
+        },
+        Commands::Flash { device, iso, yes } => DispatchAction::Flash { device, iso, yes },
         Commands::Help => DispatchAction::Help,
         Commands::Exit => DispatchAction::Exit,
     }
@@ -20,7 +226,11 @@ pub fn dispatch(cmd: Commands) -> DispatchAction {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::auto_installer::commands::constants::{Commands, IsoType};
+    use crate::answer_file::{
+        AnswerFormat, LintWarning, SchemaVersion, sections::PasswordHashFormat,
+    };
+    use crate::auto_installer::commands::constants::{EncryptMode, InventoryBuild, SshKeySource};
+    use crate::iso::{alternates::DownloadVia, channel::IsoChannel, writer::IsoBackend};
 
     #[test]
     fn test_dispatch() {
@@ -28,20 +238,388 @@ mod tests {
             (
                 Commands::Download {
                     dest_path: Some(Commands::default_download_path()),
+                    version: Some("8.2-1".into()),
+                    mirror: None,
+                    channel: IsoChannel::Stable,
+                    via: DownloadVia::Direct,
+                    quiet: false,
+                    stdout: false,
                 },
                 DispatchAction::Download {
                     dest_path: Some(Commands::default_download_path()),
+                    version: Some("8.2-1".into()),
+                    mirror: None,
+                    channel: IsoChannel::Stable,
+                    via: DownloadVia::Direct,
+                    quiet: false,
+                    stdout: false,
                 },
             ),
             (
-                Commands::AutoInstaller(IsoType::Offline),
-                DispatchAction::AutoInstallerOffline,
+                Commands::AutoInstaller(IsoType::Offline {
+                    answer: "answer.toml".into(),
+                    format: AnswerFormat::Toml,
+                    iso: "pve.iso".into(),
+                    out: "auto.iso".into(),
+                    packages: vec!["extra.deb".into()],
+                    assets: Some("assets.toml".into()),
+                    kernel_args: vec!["proxdebug".into()],
+                    volume_label: Some("PVE-AUTO-NODE01".into()),
+                    backend: IsoBackend::Auto,
+                    dry_run: false,
+                    smoke_test: false,
+                    sign_key: None,
+                    inventory: Some(InventoryBuild {
+                        inventory: "hosts.toml".into(),
+                        out_dir: "builds".into(),
+                        file_name: "{name}.iso".into(),
+                    }),
+                }),
+                DispatchAction::AutoInstallerOffline {
+                    answer: "answer.toml".into(),
+                    format: AnswerFormat::Toml,
+                    iso: "pve.iso".into(),
+                    out: "auto.iso".into(),
+                    packages: vec!["extra.deb".into()],
+                    assets: Some("assets.toml".into()),
+                    kernel_args: vec!["proxdebug".into()],
+                    volume_label: Some("PVE-AUTO-NODE01".into()),
+                    backend: IsoBackend::Auto,
+                    dry_run: false,
+                    smoke_test: false,
+                    sign_key: None,
+                    inventory: Some(InventoryBuild {
+                        inventory: "hosts.toml".into(),
+                        out_dir: "builds".into(),
+                        file_name: "{name}.iso".into(),
+                    }),
+                },
             ),
             (
-                Commands::AutoInstaller(IsoType::Network),
-                DispatchAction::AutoInstallerNetwork,
+                Commands::AutoInstaller(IsoType::Network {
+                    iso: "pve.iso".into(),
+                    out: "net.iso".into(),
+                    url: Some("http://10.0.0.1:8000/answer".into()),
+                    cert_fingerprint: None,
+                    kernel_args: Vec::new(),
+                    volume_label: None,
+                    backend: IsoBackend::Auto,
+                    dry_run: false,
+                    smoke_test: false,
+                    sign_key: None,
+                }),
+                DispatchAction::AutoInstallerNetwork {
+                    iso: "pve.iso".into(),
+                    out: "net.iso".into(),
+                    url: Some("http://10.0.0.1:8000/answer".into()),
+                    cert_fingerprint: None,
+                    kernel_args: Vec::new(),
+                    volume_label: None,
+                    backend: IsoBackend::Auto,
+                    dry_run: false,
+                    smoke_test: false,
+                    sign_key: None,
+                },
+            ),
+            (
+                Commands::Iso(IsoCommand::ListVersions {
+                    mirror: Some("http://mirror.lan/iso/".into()),
+                    channel: IsoChannel::Test,
+                }),
+                DispatchAction::IsoListVersions {
+                    mirror: Some("http://mirror.lan/iso/".into()),
+                    channel: IsoChannel::Test,
+                },
+            ),
+            (
+                Commands::Iso(IsoCommand::Verify {
+                    path: "pve.iso".into(),
+                    key: Some("release.gpg".into()),
+                }),
+                DispatchAction::IsoVerify {
+                    path: "pve.iso".into(),
+                    key: Some("release.gpg".into()),
+                },
+            ),
+            (
+                Commands::Iso(IsoCommand::VerifySignature {
+                    path: "pve-auto.iso".into(),
+                    key: "ops.asc".into(),
+                }),
+                DispatchAction::IsoVerifySignature {
+                    path: "pve-auto.iso".into(),
+                    key: "ops.asc".into(),
+                },
+            ),
+            (
+                Commands::Iso(IsoCommand::Inspect {
+                    path: "pve.iso".into(),
+                }),
+                DispatchAction::IsoInspect {
+                    path: "pve.iso".into(),
+                },
+            ),
+            (
+                Commands::Iso(IsoCommand::Prune { keep: None }),
+                DispatchAction::IsoPrune { keep: None },
+            ),
+            (
+                Commands::Bundle(BundleCommand::Export {
+                    iso: "pve.iso".into(),
+                    answers: vec!["answer.toml".into()],
+                    key: Some("release.gpg".into()),
+                    out: "pve.tar".into(),
+                }),
+                DispatchAction::BundleExport {
+                    iso: "pve.iso".into(),
+                    answers: vec!["answer.toml".into()],
+                    key: Some("release.gpg".into()),
+                    out: "pve.tar".into(),
+                },
+            ),
+            (
+                Commands::Bundle(BundleCommand::Import {
+                    bundle: "pve.tar".into(),
+                    dir: "pve".into(),
+                    key: None,
+                }),
+                DispatchAction::BundleImport {
+                    bundle: "pve.tar".into(),
+                    dir: "pve".into(),
+                    key: None,
+                },
             ),
             (Commands::ServeAnswers, DispatchAction::ServeAnswers),
+            (
+                Commands::Answer(AnswerCommand::Validate {
+                    path: "answer.toml".into(),
+                    format: AnswerFormat::Toml,
+                    strict: true,
+                    assistant: true,
+                }),
+                DispatchAction::AnswerValidate {
+                    path: "answer.toml".into(),
+                    format: AnswerFormat::Toml,
+                    strict: true,
+                    assistant: true,
+                },
+            ),
+            (
+                Commands::Answer(AnswerCommand::Lint {
+                    path: "answer.toml".into(),
+                    format: AnswerFormat::Toml,
+                    allow: vec![LintWarning::NoRootSshKeys],
+                }),
+                DispatchAction::AnswerLint {
+                    path: "answer.toml".into(),
+                    format: AnswerFormat::Toml,
+                    allow: vec![LintWarning::NoRootSshKeys],
+                },
+            ),
+            (
+                Commands::Answer(AnswerCommand::New {
+                    interactive: true,
+                    preset: None,
+                    out: "answer.toml".into(),
+                    format: AnswerFormat::Toml,
+                    encrypt: None,
+                }),
+                DispatchAction::AnswerNew {
+                    interactive: true,
+                    preset: None,
+                    out: "answer.toml".into(),
+                    format: AnswerFormat::Toml,
+                    encrypt: None,
+                },
+            ),
+            (
+                Commands::Answer(AnswerCommand::Convert {
+                    path: "answer.json".into(),
+                    format: AnswerFormat::Json,
+                }),
+                DispatchAction::AnswerConvert {
+                    path: "answer.json".into(),
+                    format: AnswerFormat::Json,
+                },
+            ),
+            (
+                Commands::Answer(AnswerCommand::Render {
+                    path: "template.toml".into(),
+                    format: AnswerFormat::Toml,
+                    vars: Default::default(),
+                }),
+                DispatchAction::AnswerRender {
+                    path: "template.toml".into(),
+                    format: AnswerFormat::Toml,
+                    vars: Default::default(),
+                },
+            ),
+            (
+                Commands::Answer(AnswerCommand::Generate {
+                    template: "base.toml".into(),
+                    inventory: "hosts.toml".into(),
+                    out_dir: "answers".into(),
+                    format: AnswerFormat::Toml,
+                }),
+                DispatchAction::AnswerGenerate {
+                    template: "base.toml".into(),
+                    inventory: "hosts.toml".into(),
+                    out_dir: "answers".into(),
+                    format: AnswerFormat::Toml,
+                },
+            ),
+            (
+                Commands::Answer(AnswerCommand::Profile(ProfileCommand::Save {
+                    name: "homelab".into(),
+                    path: "answer.toml".into(),
+                    format: AnswerFormat::Toml,
+                    encrypt: Some(EncryptMode::Passphrase),
+                })),
+                DispatchAction::ProfileSave {
+                    name: "homelab".into(),
+                    path: "answer.toml".into(),
+                    format: AnswerFormat::Toml,
+                    encrypt: Some(EncryptMode::Passphrase),
+                },
+            ),
+            (
+                Commands::Answer(AnswerCommand::Diff {
+                    old: "a.toml".into(),
+                    new: "b.toml".into(),
+                    format: AnswerFormat::Toml,
+                }),
+                DispatchAction::AnswerDiff {
+                    old: "a.toml".into(),
+                    new: "b.toml".into(),
+                    format: AnswerFormat::Toml,
+                },
+            ),
+            (
+                Commands::Answer(AnswerCommand::Schema),
+                DispatchAction::AnswerSchema,
+            ),
+            (
+                Commands::Answer(AnswerCommand::Migrate {
+                    path: "old.toml".into(),
+                    from: Some(SchemaVersion::V1),
+                }),
+                DispatchAction::AnswerMigrate {
+                    path: "old.toml".into(),
+                    from: Some(SchemaVersion::V1),
+                },
+            ),
+            (
+                Commands::Answer(AnswerCommand::ConvertPassword {
+                    path: "answer.toml".into(),
+                    method: PasswordHashFormat::Sha256,
+                    rounds: Some(500_000),
+                }),
+                DispatchAction::AnswerConvertPassword {
+                    path: "answer.toml".into(),
+                    method: PasswordHashFormat::Sha256,
+                    rounds: Some(500_000),
+                },
+            ),
+            (
+                Commands::Answer(AnswerCommand::Sign {
+                    path: "answer.toml".into(),
+                    key: "operator".into(),
+                }),
+                DispatchAction::AnswerSign {
+                    path: "answer.toml".into(),
+                    key: "operator".into(),
+                },
+            ),
+            (
+                Commands::Answer(AnswerCommand::Verify {
+                    path: "answer.toml".into(),
+                    trusted_keys: None,
+                }),
+                DispatchAction::AnswerVerify {
+                    path: "answer.toml".into(),
+                    trusted_keys: None,
+                },
+            ),
+            (
+                Commands::Answer(AnswerCommand::Fetch {
+                    url: "https://pve.lab/answer".into(),
+                    format: AnswerFormat::Json,
+                    save: Some("served".into()),
+                }),
+                DispatchAction::AnswerFetch {
+                    url: "https://pve.lab/answer".into(),
+                    format: AnswerFormat::Json,
+                    save: Some("served".into()),
+                },
+            ),
+            (
+                Commands::Answer(AnswerCommand::AddSshKeys {
+                    path: "answer.toml".into(),
+                    format: AnswerFormat::Toml,
+                    sources: vec![SshKeySource::GitHub("octocat".into())],
+                }),
+                DispatchAction::AnswerAddSshKeys {
+                    path: "answer.toml".into(),
+                    format: AnswerFormat::Toml,
+                    sources: vec![SshKeySource::GitHub("octocat".into())],
+                },
+            ),
+            (
+                Commands::Answer(AnswerCommand::Profile(ProfileCommand::List)),
+                DispatchAction::ProfileList,
+            ),
+            (
+                Commands::Answer(AnswerCommand::Profile(ProfileCommand::Show {
+                    name: "homelab".into(),
+                })),
+                DispatchAction::ProfileShow {
+                    name: "homelab".into(),
+                },
+            ),
+            (
+                Commands::Answer(AnswerCommand::Profile(ProfileCommand::Delete {
+                    name: "homelab".into(),
+                })),
+                DispatchAction::ProfileDelete {
+                    name: "homelab".into(),
+                },
+            ),
+            (
+                Commands::HashPassword {
+                    method: PasswordHashFormat::Yescrypt,
+                    rounds: None,
+                },
+                DispatchAction::HashPassword {
+                    method: PasswordHashFormat::Yescrypt,
+                    rounds: None,
+                },
+            ),
+            (
+                Commands::GenerateSshKey {
+                    answer: "answer.toml".into(),
+                    key: "id_ed25519".into(),
+                    format: AnswerFormat::Toml,
+                    comment: Some("root@pve".into()),
+                },
+                DispatchAction::GenerateSshKey {
+                    answer: "answer.toml".into(),
+                    key: "id_ed25519".into(),
+                    format: AnswerFormat::Toml,
+                    comment: Some("root@pve".into()),
+                },
+            ),
+            (
+                Commands::Flash {
+                    device: "/dev/sdb".into(),
+                    iso: "pve.iso".into(),
+                    yes: true,
+                },
+                DispatchAction::Flash {
+                    device: "/dev/sdb".into(),
+                    iso: "pve.iso".into(),
+                    yes: true,
+                },
+            ),
             (Commands::Help, DispatchAction::Help),
             (Commands::Exit, DispatchAction::Exit),
         ];
@@ -0,0 +1,315 @@
+use crate::iso::{
+    http::{HttpFetcher, HttpResponse},
+    metalink::parse_metalink,
+    retry::RetryPolicy,
+    torrent::parse_torrent,
+};
+use reqwest::{Method, StatusCode, header::HeaderMap};
+use std::{collections::HashMap, error::Error, fmt, str::FromStr, sync::Mutex};
+
+/// Where the bytes of an ISO are downloaded from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DownloadVia {
+    /// The ISO URL itself.
+    #[default]
+    Direct,
+    /// The mirrors of `<iso>.meta4`, a Metalink 4 document, by priority.
+    Metalink,
+    /// The web seeds of `<iso>.torrent`.
+    Torrent,
+}
+
+impl DownloadVia {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Direct => "direct",
+            Self::Metalink => "metalink",
+            Self::Torrent => "torrent",
+        }
+    }
+
+    /// The suffix of the file that lists the alternate URLs of an ISO.
+    fn suffix(self) -> Option<&'static str> {
+        match self {
+            Self::Direct => None,
+            Self::Metalink => Some(".meta4"),
+            Self::Torrent => Some(".torrent"),
+        }
+    }
+}
+
+impl fmt::Display for DownloadVia {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DownloadVia {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "direct" => Ok(Self::Direct),
+            "metalink" => Ok(Self::Metalink),
+            "torrent" => Ok(Self::Torrent),
+            _ => Err(format!("unknown download source: {}", s)),
+        }
+    }
+}
+
+/// An [`HttpFetcher`] that downloads ISOs from the URLs listed in their
+/// metalink or torrent, as `via` says, when one is published next to them.
+///
+/// `GET` requests for a `.iso` are sent to each alternate URL in turn, with
+/// the same headers so that resumed downloads keep their range, and to the
+/// ISO URL itself when none answers. Everything else, including the checksum
+/// the download is verified against, still comes from the ISO's own server.
+#[derive(Debug)]
+pub struct Alternates<F> {
+    inner: F,
+    via: DownloadVia,
+    quiet: bool,
+    /// Alternate URLs by ISO URL, looked up once per ISO.
+    resolved: Mutex<HashMap<String, Vec<String>>>,
+}
+
+impl<F: HttpFetcher> Alternates<F> {
+    /// Wraps `inner`; the chosen source and failing URLs are reported on
+    /// standard error unless `quiet`.
+    pub fn new(inner: F, via: DownloadVia, quiet: bool) -> Self {
+        Self {
+            inner,
+            via,
+            quiet,
+            resolved: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn note(&self, message: &str) {
+        if !self.quiet {
+            eprintln!("{}", message);
+        }
+    }
+
+    /// The alternate URLs of the ISO at `url`; none when its metalink or
+    /// torrent is missing or unusable.
+    async fn alternates(&self, url: &str) -> Vec<String> {
+        if let Some(urls) = self.resolved.lock().unwrap().get(url) {
+            return urls.clone();
+        }
+        let urls = match self.lookup(url).await {
+            Ok(urls) => {
+                self.note(&format!(
+                    "Downloading {} from {} {} URL(s)",
+                    file_name(url),
+                    urls.len(),
+                    self.via
+                ));
+                urls
+            }
+            Err(e) => {
+                self.note(&format!(
+                    "No usable {} for {} ({}). Downloading it directly.",
+                    self.via,
+                    file_name(url),
+                    e
+                ));
+                Vec::new()
+            }
+        };
+        self.resolved
+            .lock()
+            .unwrap()
+            .insert(url.to_string(), urls.clone());
+        urls
+    }
+
+    async fn lookup(&self, url: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let Some(suffix) = self.via.suffix() else {
+            return Ok(Vec::new());
+        };
+        let mut resp = self
+            .inner
+            .get(&format!("{}{}", url, suffix), HeaderMap::new())
+            .await?
+            .error_for_status()?;
+        let mut body = Vec::new();
+        while let Some(chunk) = resp.chunk().await? {
+            body.extend_from_slice(&chunk);
+        }
+        let urls = match self.via {
+            DownloadVia::Metalink => {
+                parse_metalink(&String::from_utf8(body)?, file_name(url))?.urls
+            }
+            _ => parse_torrent(&body)?.web_seeds,
+        };
+        if urls.is_empty() {
+            return Err("it lists no HTTP URL".into());
+        }
+        Ok(urls)
+    }
+}
+
+impl<F: HttpFetcher> HttpFetcher for Alternates<F> {
+    async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        headers: HeaderMap,
+    ) -> Result<HttpResponse, Box<dyn Error>> {
+        if method != Method::GET || self.via == DownloadVia::Direct || !url.ends_with(".iso") {
+            return self.inner.send(method, url, headers).await;
+        }
+        for alternate in self.alternates(url).await {
+            let reason = match self.inner.get(&alternate, headers.clone()).await {
+                Ok(resp) if usable(resp.status) => return Ok(resp),
+                Ok(resp) => resp.status.to_string(),
+                Err(e) => e.to_string(),
+            };
+            self.note(&format!(
+                "{} failed ({}). Trying the next URL...",
+                alternate, reason
+            ));
+        }
+        self.inner.send(method, url, headers).await
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        self.inner.retry_policy()
+    }
+}
+
+/// Whether a response to a (possibly ranged) download can be used; `416`
+/// tells the downloader its partial file is already complete.
+fn usable(status: StatusCode) -> bool {
+    !(status.is_client_error() || status.is_server_error())
+        || status == StatusCode::RANGE_NOT_SATISFIABLE
+}
+
+fn file_name(url: &str) -> &str {
+    url.rsplit('/').next().unwrap_or(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iso::{
+        checksum::Checksum, downloader::download_resumable, http::tests::MockFetcher,
+    };
+    use sha2::{Digest, Sha256};
+
+    const ISO_URL: &str = "https://enterprise.proxmox.com/iso/proxmox-ve_8.2-1.iso";
+    const BODY: &[u8] = b"not really an iso";
+
+    fn temp_iso(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!(
+            "pveauto-alternates-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join("pve.iso").to_string_lossy().into_owned()
+    }
+
+    fn checksum() -> Checksum {
+        Checksum::sha256(&format!("{:x}", Sha256::digest(BODY))).unwrap()
+    }
+
+    #[tokio::test]
+    async fn isos_are_fetched_from_metalink_mirrors_in_order() {
+        let metalink = r#"<metalink xmlns="urn:ietf:params:xml:ns:metalink">
+            <file name="proxmox-ve_8.2-1.iso">
+              <url priority="2">http://mirror.example/proxmox-ve_8.2-1.iso</url>
+              <url priority="1">http://down.example/proxmox-ve_8.2-1.iso</url>
+            </file></metalink>"#;
+        let http = Alternates::new(
+            MockFetcher::default()
+                .route(&format!("{}.meta4", ISO_URL), None, metalink)
+                .route("http://mirror.example/proxmox-ve_8.2-1.iso", None, BODY),
+            DownloadVia::Metalink,
+            true,
+        );
+        let iso = temp_iso("metalink");
+
+        download_resumable(&http, ISO_URL, &iso, &checksum(), true)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&iso).unwrap(), BODY);
+        assert_eq!(
+            http.inner.requests(),
+            [
+                format!("GET {}.meta4", ISO_URL),
+                "GET http://down.example/proxmox-ve_8.2-1.iso".into(),
+                "GET http://mirror.example/proxmox-ve_8.2-1.iso".into(),
+            ]
+        );
+
+        // The alternates are looked up once; other requests go to the source.
+        http.get(ISO_URL, HeaderMap::new()).await.unwrap();
+        http.head(ISO_URL).await.unwrap();
+        assert_eq!(
+            http.inner.requests(),
+            [
+                "GET http://down.example/proxmox-ve_8.2-1.iso".to_string(),
+                "GET http://mirror.example/proxmox-ve_8.2-1.iso".into(),
+                format!("HEAD {}", ISO_URL),
+            ]
+        );
+        std::fs::remove_dir_all(std::path::Path::new(&iso).parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn isos_without_a_torrent_are_fetched_directly() {
+        let http = Alternates::new(
+            MockFetcher::default().route(ISO_URL, None, BODY),
+            DownloadVia::Torrent,
+            true,
+        );
+        let iso = temp_iso("torrent");
+
+        download_resumable(&http, ISO_URL, &iso, &checksum(), true)
+            .await
+            .unwrap();
+        assert_eq!(
+            http.inner.requests(),
+            [
+                format!("GET {}.torrent", ISO_URL),
+                format!("GET {}", ISO_URL)
+            ]
+        );
+
+        let seeded =
+            b"d8:url-list24:http://seed.example/iso/4:infod4:name20:proxmox-ve_8.2-1.isoee";
+        let http = Alternates::new(
+            MockFetcher::default()
+                .route(&format!("{}.torrent", ISO_URL), None, &seeded[..])
+                .route("http://seed.example/iso/proxmox-ve_8.2-1.iso", None, BODY),
+            DownloadVia::Torrent,
+            true,
+        );
+        std::fs::remove_file(&iso).unwrap();
+        download_resumable(&http, ISO_URL, &iso, &checksum(), true)
+            .await
+            .unwrap();
+        assert_eq!(
+            http.inner.requests(),
+            [
+                format!("GET {}.torrent", ISO_URL),
+                "GET http://seed.example/iso/proxmox-ve_8.2-1.iso".into(),
+            ]
+        );
+        std::fs::remove_dir_all(std::path::Path::new(&iso).parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn download_sources_round_trip() {
+        for via in [
+            DownloadVia::Direct,
+            DownloadVia::Metalink,
+            DownloadVia::Torrent,
+        ] {
+            assert_eq!(via.as_str().parse(), Ok(via));
+        }
+        assert!("bittorrent".parse::<DownloadVia>().is_err());
+    }
+}
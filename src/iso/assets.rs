@@ -0,0 +1,432 @@
+use crate::answer_file::{
+    AnswerFile,
+    sections::{FirstBoot, FirstBootSource},
+};
+use crate::iso::{
+    constants::ISO_ASSETS_FILE_NAME,
+    first_boot::FirstBootScript,
+    tar::{self, END_OF_ARCHIVE},
+};
+use serde::Deserialize;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Where the first-boot script unpacks the assets on the installed system.
+pub const ASSETS_INSTALL_DIR: &str = "/var/lib/pveauto/assets";
+
+/// Mode of copied files without a `mode` in the manifest.
+const DEFAULT_FILE_MODE: u32 = 0o644;
+
+/* ===================== ASSETS ERROR ===================== */
+
+/// Failure while bundling the post-install assets of a manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetsError {
+    /// The manifest or an asset could not be read; holds the path and the I/O error.
+    Unreadable(PathBuf, String),
+    /// The manifest is not valid TOML for [`AssetManifest`].
+    Parse(PathBuf, String),
+    /// The manifest lists no files and no scripts.
+    Empty(PathBuf),
+    /// A destination is not an absolute path without `..`, or has a control character.
+    InvalidDestination(String),
+    /// A mode is not an octal permission such as `0755`.
+    InvalidMode(String),
+    /// A script does not start with a `#!` interpreter line.
+    MissingShebang(PathBuf),
+    /// The answer fetches its first-boot script from a URL, leaving no room
+    /// for the one that installs the assets.
+    FirstBootFromUrl,
+}
+
+impl AssetsError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Unreadable(..) => "assets.unreadable",
+            Self::Parse(..) => "assets.parse",
+            Self::Empty(_) => "assets.empty",
+            Self::InvalidDestination(_) => "assets.invalid_destination",
+            Self::InvalidMode(_) => "assets.invalid_mode",
+            Self::MissingShebang(_) => "assets.missing_shebang",
+            Self::FirstBootFromUrl => "assets.first_boot_from_url",
+        }
+    }
+}
+
+impl fmt::Display for AssetsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unreadable(path, detail) | Self::Parse(path, detail) => {
+                write!(f, "{} ({}: {})", self.code(), path.display(), detail)
+            }
+            Self::Empty(path) | Self::MissingShebang(path) => {
+                write!(f, "{} ({})", self.code(), path.display())
+            }
+            Self::InvalidDestination(value) | Self::InvalidMode(value) => {
+                write!(f, "{} ({:?})", self.code(), value)
+            }
+            Self::FirstBootFromUrl => write!(
+                f,
+                "{} (remove first-boot.url or use a local first-boot.script)",
+                self.code()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AssetsError {}
+
+/* ===================== MANIFEST ===================== */
+
+/// A manifest of post-install assets, e.g. `assets.toml`:
+///
+/// ```toml
+/// [[file]]
+/// source = "files/chrony.conf"
+/// destination = "/etc/chrony/chrony.conf"
+///
+/// [[run]]
+/// source = "scripts/join-cluster.sh"
+/// ```
+///
+/// Sources are relative to the manifest's directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct AssetManifest {
+    /// Files copied onto the installed system.
+    #[serde(default)]
+    pub file: Vec<AssetFile>,
+    /// Scripts run on the installed system's first boot, in order.
+    #[serde(default)]
+    pub run: Vec<AssetScript>,
+}
+
+/// A `[[file]]` of an [`AssetManifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct AssetFile {
+    pub source: PathBuf,
+    /// Absolute path on the installed system; missing directories are created.
+    pub destination: String,
+    /// Octal permissions, `0644` when not given.
+    pub mode: Option<String>,
+}
+
+/// A `[[run]]` script of an [`AssetManifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct AssetScript {
+    pub source: PathBuf,
+}
+
+impl AssetManifest {
+    /// Reads and checks the manifest at `path`; the assets themselves are
+    /// read by [`bundle_assets`].
+    pub fn load(path: &Path) -> Result<Self, AssetsError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| AssetsError::Unreadable(path.to_path_buf(), e.to_string()))?;
+        let manifest: Self = toml::from_str(&text)
+            .map_err(|e| AssetsError::Parse(path.to_path_buf(), e.message().to_string()))?;
+
+        if manifest.file.is_empty() && manifest.run.is_empty() {
+            return Err(AssetsError::Empty(path.to_path_buf()));
+        }
+        for file in &manifest.file {
+            let destination = &file.destination;
+            if !destination.starts_with('/')
+                || destination.ends_with('/')
+                || destination.split('/').any(|c| c == "..")
+                || destination.chars().any(char::is_control)
+            {
+                return Err(AssetsError::InvalidDestination(destination.clone()));
+            }
+            file.mode()?;
+        }
+        Ok(manifest)
+    }
+}
+
+impl AssetFile {
+    /// The permissions of `mode`, or the default ones.
+    pub fn mode(&self) -> Result<u32, AssetsError> {
+        match &self.mode {
+            None => Ok(DEFAULT_FILE_MODE),
+            Some(mode) => u32::from_str_radix(mode, 8)
+                .ok()
+                .filter(|m| *m <= 0o7777 && !mode.starts_with('+'))
+                .ok_or_else(|| AssetsError::InvalidMode(mode.clone())),
+        }
+    }
+}
+
+/* ===================== BUNDLING ===================== */
+
+/// The assets of a manifest, ready to be copied into the ISO.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetBundle {
+    /// Tar archive of the assets, written to [`ISO_ASSETS_FILE_NAME`] on the ISO root.
+    pub archive: Vec<u8>,
+    /// The generated first-boot script installing them; its `path` is the
+    /// manifest's, as the script has no file of its own.
+    pub script: FirstBootScript,
+}
+
+/// Reads the manifest at `manifest` and its assets, and sets up `answer`
+/// to install them with a generated first-boot script.
+///
+/// On first boot, the script mounts the installation medium, which must
+/// still be attached, unpacks the assets to [`ASSETS_INSTALL_DIR`], copies
+/// the files to their destinations and runs the scripts from there.
+/// `first_boot`, the answer's own bundled script, runs last.
+///
+/// # Errors
+/// Returns an error if the manifest or an asset is invalid or unreadable, or
+/// if `answer` fetches its first-boot script from a URL.
+pub fn bundle_assets(
+    manifest: &Path,
+    answer: &mut AnswerFile,
+    first_boot: Option<FirstBootScript>,
+) -> Result<AssetBundle, AssetsError> {
+    let parsed = AssetManifest::load(manifest)?;
+    let base_dir = manifest.parent().unwrap_or(Path::new("."));
+    let read = |source: &Path| {
+        let path = base_dir.join(source);
+        std::fs::read(&path).map_err(|e| AssetsError::Unreadable(path, e.to_string()))
+    };
+
+    let from_url = answer
+        .first_boot
+        .as_ref()
+        .is_some_and(|section| section.source == FirstBootSource::FromUrl);
+    if from_url && first_boot.is_none() {
+        return Err(AssetsError::FirstBootFromUrl);
+    }
+
+    let mut archive = Vec::new();
+    let mut commands = Vec::new();
+    for (i, file) in parsed.file.iter().enumerate() {
+        let name = format!("files/{}", i);
+        tar::append(&mut archive, &name, 0o644, &read(&file.source)?);
+        commands.push(format!(
+            "install -D -m {:04o} {} {}",
+            file.mode()?,
+            name,
+            sh_quote(&file.destination)
+        ));
+    }
+
+    let mut scripts = Vec::new();
+    for script in &parsed.run {
+        let contents = read(&script.source)?;
+        if !contents.starts_with(b"#!") {
+            return Err(AssetsError::MissingShebang(base_dir.join(&script.source)));
+        }
+        scripts.push(contents);
+    }
+    if let Some(script) = first_boot {
+        scripts.push(script.contents.into_bytes());
+    }
+    for (i, contents) in scripts.iter().enumerate() {
+        let name = format!("run/{}", i);
+        tar::append(&mut archive, &name, 0o755, contents);
+        commands.push(format!("./{}", name));
+    }
+    archive.extend_from_slice(&END_OF_ARCHIVE);
+
+    let section = answer.first_boot.get_or_insert_with(FirstBoot::default);
+    section.source = FirstBootSource::FromIso;
+    section.script = None;
+    section.url = None;
+    section.cert_fingerprint = None;
+
+    Ok(AssetBundle {
+        archive,
+        script: FirstBootScript {
+            path: manifest.to_path_buf(),
+            contents: first_boot_script(&commands),
+        },
+    })
+}
+
+/// A script that unpacks [`ISO_ASSETS_FILE_NAME`] from the first ISO 9660
+/// medium having it and runs `commands` in the unpacked directory.
+fn first_boot_script(commands: &[String]) -> String {
+    format!(
+        r#"#!/bin/sh
+# Generated by pveauto: installs the post-install assets from the
+# installation medium.
+set -eu
+
+archive={archive}
+assets={assets}
+mnt=$(mktemp -d)
+found=
+for dev in $(blkid -o device -t TYPE=iso9660 || true); do
+    mount -o ro "$dev" "$mnt" 2>/dev/null || continue
+    if [ -f "$mnt/$archive" ]; then
+        mkdir -p "$assets"
+        tar -xf "$mnt/$archive" -C "$assets"
+        found=1
+    fi
+    umount "$mnt"
+    [ -z "$found" ] || break
+done
+rmdir "$mnt"
+if [ -z "$found" ]; then
+    echo "$archive not found; is the installation medium attached?" >&2
+    exit 1
+fi
+
+cd "$assets"
+{commands}
+"#,
+        archive = ISO_ASSETS_FILE_NAME,
+        assets = ASSETS_INSTALL_DIR,
+        commands = commands.join("\n")
+    )
+}
+
+/// Quotes `value` for a POSIX shell.
+fn sh_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer_file::sections::FirstBootOrdering;
+    use crate::iso::tar::TAR_BLOCK;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pveauto-assets-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("scripts")).unwrap();
+        dir
+    }
+
+    /// The `(name, mode, contents)` of each entry of a ustar `archive`.
+    fn untar(archive: &[u8]) -> Vec<(String, u32, Vec<u8>)> {
+        let mut entries = Vec::new();
+        let mut at = 0;
+        while archive[at] != 0 {
+            let header = &archive[at..at + TAR_BLOCK];
+            let field = |range: std::ops::Range<usize>| {
+                let text = String::from_utf8_lossy(&header[range]);
+                text.trim_end_matches(['\0', ' ']).to_string()
+            };
+            let mut check = header.to_vec();
+            check[148..156].fill(b' ');
+            let sum: u32 = check.iter().map(|&b| u32::from(b)).sum();
+            assert_eq!(u32::from_str_radix(&field(148..156), 8).unwrap(), sum);
+
+            let size = usize::from_str_radix(&field(124..136), 8).unwrap();
+            let mode = u32::from_str_radix(&field(100..108), 8).unwrap();
+            let start = at + TAR_BLOCK;
+            entries.push((field(0..100), mode, archive[start..start + size].to_vec()));
+            at = start + size.div_ceil(TAR_BLOCK) * TAR_BLOCK;
+        }
+        assert_eq!(archive.len(), at + 2 * TAR_BLOCK);
+        entries
+    }
+
+    #[test]
+    fn assets_are_archived_with_a_script_installing_them() {
+        let dir = temp_dir("bundle");
+        std::fs::write(dir.join("chrony.conf"), "server ntp.lab\n").unwrap();
+        std::fs::write(dir.join("scripts/join.sh"), "#!/bin/sh\npvecm add\n").unwrap();
+        std::fs::write(
+            dir.join("assets.toml"),
+            "[[file]]\nsource = \"chrony.conf\"\ndestination = \"/etc/chrony/it's.conf\"\n\
+             mode = \"600\"\n\n[[run]]\nsource = \"scripts/join.sh\"\n",
+        )
+        .unwrap();
+        let mut answer = AnswerFile {
+            first_boot: Some(FirstBoot {
+                ordering: FirstBootOrdering::NetworkOnline,
+                ..FirstBoot::default()
+            }),
+            ..AnswerFile::default()
+        };
+        let own = FirstBootScript {
+            path: dir.join("own.sh"),
+            contents: "#!/bin/sh\necho own\n".into(),
+        };
+
+        let bundle = bundle_assets(&dir.join("assets.toml"), &mut answer, Some(own)).unwrap();
+        assert_eq!(
+            untar(&bundle.archive),
+            [
+                ("files/0".to_string(), 0o644, b"server ntp.lab\n".to_vec()),
+                (
+                    "run/0".to_string(),
+                    0o755,
+                    b"#!/bin/sh\npvecm add\n".to_vec()
+                ),
+                (
+                    "run/1".to_string(),
+                    0o755,
+                    b"#!/bin/sh\necho own\n".to_vec()
+                ),
+            ]
+        );
+        assert!(bundle.script.contents.ends_with(
+            "cd \"$assets\"\ninstall -D -m 0600 files/0 '/etc/chrony/it'\\''s.conf'\n./run/0\n./run/1\n"
+        ));
+        let first_boot = answer.first_boot.unwrap();
+        assert_eq!(first_boot.source, FirstBootSource::FromIso);
+        assert_eq!(first_boot.ordering, FirstBootOrdering::NetworkOnline);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_manifests_are_rejected() {
+        let dir = temp_dir("invalid");
+        std::fs::write(dir.join("plain.sh"), "pvecm add\n").unwrap();
+        for (manifest, code) in [
+            ("", "assets.empty"),
+            ("[[file]]\nsource = \"a\"\n", "assets.parse"),
+            ("[[files]]\nsource = \"a\"\n", "assets.parse"),
+            (
+                "[[file]]\nsource = \"a\"\ndestination = \"etc/a\"\n",
+                "assets.invalid_destination",
+            ),
+            (
+                "[[file]]\nsource = \"a\"\ndestination = \"/etc/../a\"\n",
+                "assets.invalid_destination",
+            ),
+            (
+                "[[file]]\nsource = \"a\"\ndestination = \"/etc/a\"\nmode = \"rwx\"\n",
+                "assets.invalid_mode",
+            ),
+            (
+                "[[file]]\nsource = \"missing\"\ndestination = \"/etc/a\"\n",
+                "assets.unreadable",
+            ),
+            ("[[run]]\nsource = \"plain.sh\"\n", "assets.missing_shebang"),
+        ] {
+            std::fs::write(dir.join("assets.toml"), manifest).unwrap();
+            let err = bundle_assets(&dir.join("assets.toml"), &mut AnswerFile::default(), None)
+                .unwrap_err();
+            assert_eq!(err.code(), code, "{}", manifest);
+        }
+
+        std::fs::write(dir.join("setup.sh"), "#!/bin/sh\n").unwrap();
+        std::fs::write(dir.join("assets.toml"), "[[run]]\nsource = \"setup.sh\"\n").unwrap();
+        let mut answer = AnswerFile {
+            first_boot: Some(FirstBoot {
+                source: FirstBootSource::FromUrl,
+                url: Some("https://10.0.0.1/first-boot.sh".into()),
+                ..FirstBoot::default()
+            }),
+            ..AnswerFile::default()
+        };
+        assert_eq!(
+            bundle_assets(&dir.join("assets.toml"), &mut answer, None),
+            Err(AssetsError::FirstBootFromUrl)
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
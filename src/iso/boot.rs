@@ -0,0 +1,599 @@
+use crate::iso::{
+    constants::{AUTO_INSTALLER_KERNEL_ARG, ISO_GRUB_CONFIG_PATH},
+    inject::{
+        BLOCK_SIZE, FIRST_DESCRIPTOR_SECTOR, IsoInjectError, MAX_DESCRIPTORS, SECTOR_SIZE, array,
+        crc32, read_at,
+    },
+    reader::IsoReader,
+};
+use std::{
+    fmt,
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+/// Standard identifier of the El Torito boot record volume descriptor.
+const EL_TORITO_ID: &[u8] = b"EL TORITO SPECIFICATION";
+
+/// Size of a boot catalog entry.
+const CATALOG_ENTRY_SIZE: usize = 32;
+
+/// Characters a kernel argument may not contain, as GRUB would interpret them.
+const GRUB_SPECIAL_CHARS: &[char] = &[
+    '"', '\'', '\\', '$', ';', '&', '|', '<', '>', '{', '}', '#', '`',
+];
+
+/* ===================== BOOT CONFIG ERROR ===================== */
+
+/// Failure while adding kernel arguments to the boot entries of an ISO.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BootConfigError {
+    /// Reading the ISO failed.
+    Iso(IsoInjectError),
+    /// The ISO has no GRUB configuration at [`ISO_GRUB_CONFIG_PATH`].
+    MissingGrubConfig,
+    /// No boot entry of the GRUB configuration starts the automated installer.
+    NoAutoInstallEntry,
+    /// A kernel argument is empty, or has whitespace or GRUB syntax.
+    InvalidKernelArg(String),
+}
+
+impl BootConfigError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Iso(_) => "boot.iso",
+            Self::MissingGrubConfig => "boot.missing_grub_config",
+            Self::NoAutoInstallEntry => "boot.no_auto_install_entry",
+            Self::InvalidKernelArg(_) => "boot.invalid_kernel_arg",
+        }
+    }
+}
+
+impl fmt::Display for BootConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Iso(e) => write!(f, "{} ({})", self.code(), e),
+            Self::MissingGrubConfig => write!(f, "{} ({})", self.code(), ISO_GRUB_CONFIG_PATH),
+            Self::NoAutoInstallEntry => write!(
+                f,
+                "{} (no `linux` line with {} in {})",
+                self.code(),
+                AUTO_INSTALLER_KERNEL_ARG,
+                ISO_GRUB_CONFIG_PATH
+            ),
+            Self::InvalidKernelArg(arg) => write!(f, "{} ({:?})", self.code(), arg),
+        }
+    }
+}
+
+impl std::error::Error for BootConfigError {}
+
+impl From<IsoInjectError> for BootConfigError {
+    fn from(e: IsoInjectError) -> Self {
+        Self::Iso(e)
+    }
+}
+
+/* ===================== KERNEL ARGUMENTS ===================== */
+
+/// The GRUB configuration of the ISO at `iso` with `args` appended to the
+/// kernel command line of its automated installation entries, to be written
+/// back to [`ISO_GRUB_CONFIG_PATH`].
+///
+/// # Errors
+/// Returns an error if an argument is invalid, or the ISO cannot be read or
+/// has no automated installation entry.
+pub fn patched_grub_config(iso: &Path, args: &[String]) -> Result<Vec<u8>, BootConfigError> {
+    let mut reader = IsoReader::open(iso)?;
+    let config = reader
+        .find(ISO_GRUB_CONFIG_PATH)?
+        .filter(|entry| !entry.dir)
+        .ok_or(BootConfigError::MissingGrubConfig)?;
+    let config = reader.read(&config)?;
+    Ok(append_kernel_args(&String::from_utf8_lossy(&config), args)?.into_bytes())
+}
+
+/// `grub_cfg` with `args` appended to every `linux` line starting the
+/// automated installer, i.e. with [`AUTO_INSTALLER_KERNEL_ARG`]; arguments
+/// already on a line are not repeated.
+pub fn append_kernel_args(grub_cfg: &str, args: &[String]) -> Result<String, BootConfigError> {
+    if let Some(arg) = args.iter().find(|arg| {
+        arg.is_empty()
+            || arg
+                .chars()
+                .any(|c| c.is_whitespace() || c.is_control() || GRUB_SPECIAL_CHARS.contains(&c))
+    }) {
+        return Err(BootConfigError::InvalidKernelArg(arg.clone()));
+    }
+
+    let mut patched = String::with_capacity(grub_cfg.len());
+    let mut entries = 0;
+    for line in grub_cfg.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let mut words = content.split_whitespace();
+        let is_auto_install = matches!(words.next(), Some("linux" | "linuxefi"))
+            && words.any(|word| word == AUTO_INSTALLER_KERNEL_ARG);
+        if !is_auto_install {
+            patched.push_str(line);
+            continue;
+        }
+
+        entries += 1;
+        patched.push_str(content);
+        for arg in args {
+            if !content.split_whitespace().any(|word| word == arg) {
+                patched.push(' ');
+                patched.push_str(arg);
+            }
+        }
+        patched.push_str(&line[content.len()..]);
+    }
+
+    if entries == 0 {
+        return Err(BootConfigError::NoAutoInstallEntry);
+    }
+    Ok(patched)
+}
+
+/* ===================== BOOT RECORDS ===================== */
+
+/// Firmware an El Torito boot entry is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BootPlatform {
+    Bios,
+    Uefi,
+    /// Any other platform ID, e.g. `0x02` for PowerPC.
+    Other(u8),
+}
+
+impl From<u8> for BootPlatform {
+    fn from(id: u8) -> Self {
+        match id {
+            0x00 => Self::Bios,
+            0xEF => Self::Uefi,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// A bootable entry of the El Torito boot catalog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BootEntry {
+    pub platform: BootPlatform,
+    /// Sector of the boot image, e.g. `isolinux.bin` or the EFI system partition.
+    pub load_sector: u32,
+    /// Length of the boot image in 512-byte blocks; `0` or `1` for most EFI images.
+    pub blocks: u16,
+}
+
+/// The structures an ISO boots from, as burnt to a disc or written to a USB
+/// drive. Only intact structures are listed: a boot catalog with a bad
+/// checksum, a boot image or partition past the end of the image, or a GPT
+/// header with a bad CRC counts as missing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BootRecords {
+    /// Bootable entries of the El Torito boot catalog, in catalog order.
+    pub entries: Vec<BootEntry>,
+    /// Whether sector 0 holds an MBR with partitions, as on isohybrid images.
+    pub mbr: bool,
+    /// Whether the primary GPT header and its partition entries are intact.
+    pub gpt: bool,
+    /// Whether the backup GPT header its primary header points at is intact.
+    pub gpt_backup: bool,
+}
+
+impl BootRecords {
+    /// Whether a BIOS machine can boot the image from a disc.
+    pub fn bios(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|e| e.platform == BootPlatform::Bios)
+    }
+
+    /// Whether a UEFI machine can boot the image from a disc.
+    pub fn uefi(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|e| e.platform == BootPlatform::Uefi)
+    }
+
+    /// Checks that `patched`, a remastered copy of this image, kept every
+    /// boot structure of it unchanged.
+    ///
+    /// # Errors
+    /// Returns [`IsoInjectError::BrokenBoot`] naming the first structure lost.
+    pub fn check_preserved_in(&self, patched: &BootRecords) -> Result<(), IsoInjectError> {
+        let lost = |what: &str| Err(IsoInjectError::BrokenBoot(what.to_string()));
+        for entry in &self.entries {
+            if !patched.entries.contains(entry) {
+                return lost(&format!(
+                    "El Torito {} entry at sector {} missing",
+                    match entry.platform {
+                        BootPlatform::Bios => "BIOS".to_string(),
+                        BootPlatform::Uefi => "UEFI".to_string(),
+                        BootPlatform::Other(id) => format!("platform {:#04x}", id),
+                    },
+                    entry.load_sector
+                ));
+            }
+        }
+        if self.mbr && !patched.mbr {
+            return lost("isohybrid MBR invalid");
+        }
+        if self.gpt && !patched.gpt {
+            return lost("GPT header invalid");
+        }
+        if self.gpt_backup && !patched.gpt_backup {
+            return lost("backup GPT header invalid");
+        }
+        Ok(())
+    }
+
+    /// Checks that `rebuilt`, a copy of this image written anew with its boot
+    /// images possibly moved, still boots the same ways: BIOS and UEFI from a
+    /// disc, and from a USB drive through its MBR or GPT.
+    ///
+    /// # Errors
+    /// Returns [`IsoInjectError::BrokenBoot`] naming the first way lost.
+    pub fn check_kept_in(&self, rebuilt: &BootRecords) -> Result<(), IsoInjectError> {
+        for (kept, what) in [
+            (
+                !self.bios() || rebuilt.bios(),
+                "El Torito BIOS entry missing",
+            ),
+            (
+                !self.uefi() || rebuilt.uefi(),
+                "El Torito UEFI entry missing",
+            ),
+            (!self.mbr || rebuilt.mbr, "isohybrid MBR invalid"),
+            (!self.gpt || rebuilt.gpt, "GPT header invalid"),
+        ] {
+            if !kept {
+                return Err(IsoInjectError::BrokenBoot(what.to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the El Torito boot catalog, MBR and GPT of `image`.
+///
+/// # Errors
+/// Returns an error only if the image cannot be read; missing or malformed
+/// structures are left out of the result.
+pub fn read_boot_records(image: &mut File) -> Result<BootRecords, IsoInjectError> {
+    let len = image
+        .metadata()
+        .map_err(|e| IsoInjectError::Io(PathBuf::new(), e.to_string()))?
+        .len();
+    let mut read = |offset: u64, size: usize| {
+        if offset + size as u64 > len {
+            return Ok(None);
+        }
+        read_at(image, offset, size).map(Some)
+    };
+
+    let mut entries = Vec::new();
+    for sector in FIRST_DESCRIPTOR_SECTOR..FIRST_DESCRIPTOR_SECTOR + MAX_DESCRIPTORS {
+        let Some(descriptor) = read(sector * SECTOR_SIZE, SECTOR_SIZE as usize)? else {
+            break;
+        };
+        if &descriptor[1..6] != b"CD001" || descriptor[0] == 255 {
+            break;
+        }
+        let id = &descriptor[7..39];
+        if descriptor[0] == 0 && id.starts_with(EL_TORITO_ID) {
+            let catalog = u32::from_le_bytes(array(&descriptor[71..75]));
+            if let Some(catalog) = read(u64::from(catalog) * SECTOR_SIZE, SECTOR_SIZE as usize)? {
+                entries = catalog_entries(&catalog);
+            }
+            break;
+        }
+    }
+    entries.retain(|e| u64::from(e.load_sector) * SECTOR_SIZE < len);
+
+    let blocks = len / BLOCK_SIZE;
+    let mbr = read(0, BLOCK_SIZE as usize)?.is_some_and(|mbr| {
+        let partitions: Vec<&[u8]> = mbr[446..510]
+            .chunks(16)
+            .filter(|entry| entry[4] != 0)
+            .collect();
+        mbr[510..512] == [0x55, 0xAA]
+            && !partitions.is_empty()
+            && partitions.iter().all(|entry| {
+                let start = u64::from(u32::from_le_bytes(array(&entry[8..12])));
+                let count = u64::from(u32::from_le_bytes(array(&entry[12..16])));
+                start + count <= blocks
+            })
+    });
+
+    let (mut gpt, mut gpt_backup) = (false, false);
+    if let Some(header) = read(BLOCK_SIZE, BLOCK_SIZE as usize)?
+        && gpt_header_valid(&header)
+    {
+        let entries_lba = u64::from_le_bytes(array(&header[72..80]));
+        gpt = gpt_entries_valid(&header, &mut read, entries_lba)?;
+        let backup_lba = u64::from_le_bytes(array(&header[32..40]));
+        if backup_lba < blocks
+            && let Some(backup) = read(backup_lba * BLOCK_SIZE, BLOCK_SIZE as usize)?
+        {
+            let backup_entries_lba = u64::from_le_bytes(array(&backup[72..80]));
+            gpt_backup = gpt_header_valid(&backup)
+                && u64::from_le_bytes(array(&backup[24..32])) == backup_lba
+                && gpt_entries_valid(&backup, &mut read, backup_entries_lba)?;
+        }
+    }
+
+    Ok(BootRecords {
+        entries,
+        mbr,
+        gpt,
+        gpt_backup,
+    })
+}
+
+/// The bootable entries of a boot catalog sector, or none if its validation
+/// entry is malformed.
+fn catalog_entries(catalog: &[u8]) -> Vec<BootEntry> {
+    let validation = &catalog[..CATALOG_ENTRY_SIZE];
+    let checksum = validation.chunks(2).fold(0u16, |sum, word| {
+        sum.wrapping_add(u16::from_le_bytes([word[0], word[1]]))
+    });
+    if validation[0] != 1 || validation[30..32] != [0x55, 0xAA] || checksum != 0 {
+        return Vec::new();
+    }
+
+    let entry = |bytes: &[u8], platform: u8| {
+        (bytes[0] == 0x88).then(|| BootEntry {
+            platform: platform.into(),
+            load_sector: u32::from_le_bytes(array(&bytes[8..12])),
+            blocks: u16::from_le_bytes(array(&bytes[6..8])),
+        })
+    };
+    let mut entries: Vec<BootEntry> = entry(&catalog[32..64], validation[1]).into_iter().collect();
+
+    // Section headers, each followed by its entries and their extensions.
+    let mut chunks = catalog[64..].chunks(CATALOG_ENTRY_SIZE);
+    while let Some(header) = chunks.next() {
+        if !matches!(header[0], 0x90 | 0x91) {
+            break;
+        }
+        let count = u16::from_le_bytes(array(&header[2..4]));
+        let mut seen = 0;
+        while seen < count {
+            let Some(bytes) = chunks.next() else {
+                break;
+            };
+            if bytes[0] == 0x44 {
+                continue;
+            }
+            entries.extend(entry(bytes, header[1]));
+            seen += 1;
+        }
+        if header[0] == 0x91 {
+            break;
+        }
+    }
+    entries
+}
+
+/// Whether a GPT header block has the signature, a supported size and a matching CRC.
+fn gpt_header_valid(header: &[u8]) -> bool {
+    let size = u32::from_le_bytes(array(&header[12..16])) as usize;
+    if &header[..8] != b"EFI PART" || !(92..=BLOCK_SIZE as usize).contains(&size) {
+        return false;
+    }
+    let mut sealed = header[..size].to_vec();
+    sealed[16..20].fill(0);
+    crc32(&sealed) == u32::from_le_bytes(array(&header[16..20]))
+}
+
+/// Whether the partition entries at `lba` match the CRC in `header`.
+fn gpt_entries_valid<F>(header: &[u8], read: &mut F, lba: u64) -> Result<bool, IsoInjectError>
+where
+    F: FnMut(u64, usize) -> Result<Option<Vec<u8>>, IsoInjectError>,
+{
+    let len = u64::from(u32::from_le_bytes(array(&header[80..84])))
+        * u64::from(u32::from_le_bytes(array(&header[84..88])));
+    let Some(entries) = read(lba * BLOCK_SIZE, len as usize)? else {
+        return Ok(false);
+    };
+    Ok(crc32(&entries) == u32::from_le_bytes(array(&header[88..92])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iso::inject::{inject_files, tests::image, tests::nested_image};
+
+    const GRUB_CFG: &str = "menuentry 'Install Proxmox VE (Graphical)' {\n\
+        \tlinux\t/boot/linux26 ro ramdisk_size=16777216 rw quiet splash=silent\n\
+        }\n\
+        menuentry 'Install Proxmox VE (Automated)' {\r\n\
+        \tlinux\t/boot/linux26 ro quiet splash=silent proxmox-start-auto-installer\r\n\
+        \tinitrd\t/boot/initrd.img\r\n\
+        }\n";
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn args_are_appended_to_the_automated_entry() {
+        let patched = append_kernel_args(
+            GRUB_CFG,
+            &args(&["console=ttyS0,115200", "proxdebug", "quiet"]),
+        )
+        .unwrap();
+        assert_eq!(
+            patched,
+            GRUB_CFG.replace(
+                "proxmox-start-auto-installer\r\n",
+                "proxmox-start-auto-installer console=ttyS0,115200 proxdebug\r\n"
+            )
+        );
+        assert_eq!(append_kernel_args(GRUB_CFG, &[]).unwrap(), GRUB_CFG);
+    }
+
+    #[test]
+    fn invalid_args_and_configs_are_rejected() {
+        for arg in ["", "a b", "x=$(id)", "a;b", "\"quoted\""] {
+            assert_eq!(
+                append_kernel_args(GRUB_CFG, &args(&[arg])),
+                Err(BootConfigError::InvalidKernelArg(arg.into()))
+            );
+        }
+        let graphical_only = GRUB_CFG.replace("proxmox-start-auto-installer", "");
+        assert_eq!(
+            append_kernel_args(&graphical_only, &args(&["proxdebug"])),
+            Err(BootConfigError::NoAutoInstallEntry)
+        );
+    }
+
+    #[test]
+    fn the_grub_config_is_read_from_the_iso() {
+        let iso = std::env::temp_dir().join(format!("pveauto-boot-{}.iso", std::process::id()));
+        let image = nested_image(
+            true,
+            ["boot", "grub"],
+            Some(("grub.cfg", GRUB_CFG.as_bytes())),
+        );
+        std::fs::write(&iso, image).unwrap();
+        let patched = patched_grub_config(&iso, &args(&["proxdebug"])).unwrap();
+        assert!(
+            String::from_utf8(patched)
+                .unwrap()
+                .contains("proxmox-start-auto-installer proxdebug\r\n")
+        );
+
+        let image = nested_image(true, ["boot", "isolinux"], None);
+        std::fs::write(&iso, image).unwrap();
+        assert_eq!(
+            patched_grub_config(&iso, &args(&["proxdebug"])),
+            Err(BootConfigError::MissingGrubConfig)
+        );
+        std::fs::remove_file(iso).unwrap();
+    }
+
+    /* ---------------- BOOT RECORDS ---------------- */
+
+    const CATALOG_SECTOR: usize = 25;
+    const BOOT_IMAGE_SECTOR: u32 = 21;
+
+    /// [`image`] with an El Torito boot record before its terminator and a
+    /// catalog with a BIOS entry and a UEFI section.
+    fn bootable_image() -> Vec<u8> {
+        let sector = SECTOR_SIZE as usize;
+        let mut image = image(true, false);
+        image.copy_within(17 * sector..18 * sector, 18 * sector);
+        let record = &mut image[17 * sector..18 * sector];
+        record.fill(0);
+        record[1..6].copy_from_slice(b"CD001");
+        record[6] = 1;
+        record[7..7 + EL_TORITO_ID.len()].copy_from_slice(EL_TORITO_ID);
+        record[71..75].copy_from_slice(&(CATALOG_SECTOR as u32).to_le_bytes());
+
+        let catalog = &mut image[CATALOG_SECTOR * sector..(CATALOG_SECTOR + 1) * sector];
+        catalog[0] = 1;
+        catalog[30..32].copy_from_slice(&[0x55, 0xAA]);
+        let sum = catalog[..32].chunks(2).fold(0u16, |sum, w| {
+            sum.wrapping_add(u16::from_le_bytes([w[0], w[1]]))
+        });
+        catalog[28..30].copy_from_slice(&0u16.wrapping_sub(sum).to_le_bytes());
+        for (at, blocks) in [(32, 4u16), (96, 0)] {
+            catalog[at] = 0x88;
+            catalog[at + 6..at + 8].copy_from_slice(&blocks.to_le_bytes());
+            catalog[at + 8..at + 12].copy_from_slice(&BOOT_IMAGE_SECTOR.to_le_bytes());
+        }
+        catalog[64..68].copy_from_slice(&[0x91, 0xEF, 1, 0]);
+        image
+    }
+
+    fn records_of(image: &[u8], name: &str) -> (std::path::PathBuf, BootRecords) {
+        let iso = std::env::temp_dir().join(format!(
+            "pveauto-boot-records-{}-{}.iso",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&iso, image).unwrap();
+        let records = read_boot_records(&mut File::open(&iso).unwrap()).unwrap();
+        (iso, records)
+    }
+
+    #[test]
+    fn boot_records_are_read() {
+        let (iso, records) = records_of(&bootable_image(), "read");
+        assert_eq!(
+            records,
+            BootRecords {
+                entries: vec![
+                    BootEntry {
+                        platform: BootPlatform::Bios,
+                        load_sector: BOOT_IMAGE_SECTOR,
+                        blocks: 4,
+                    },
+                    BootEntry {
+                        platform: BootPlatform::Uefi,
+                        load_sector: BOOT_IMAGE_SECTOR,
+                        blocks: 0,
+                    },
+                ],
+                mbr: true,
+                gpt: true,
+                gpt_backup: false,
+            }
+        );
+        assert!(records.bios() && records.uefi());
+        std::fs::remove_file(iso).unwrap();
+
+        let mut broken = bootable_image();
+        broken[CATALOG_SECTOR * SECTOR_SIZE as usize + 28] ^= 1;
+        broken[BLOCK_SIZE as usize + 16] ^= 1;
+        let (iso, records) = records_of(&broken, "broken");
+        assert_eq!(
+            records,
+            BootRecords {
+                mbr: true,
+                ..Default::default()
+            }
+        );
+        std::fs::remove_file(iso).unwrap();
+
+        let (iso, records) = records_of(b"not an iso", "text");
+        assert_eq!(records, BootRecords::default());
+        std::fs::remove_file(iso).unwrap();
+    }
+
+    #[test]
+    fn remastering_keeps_the_boot_records() {
+        let (iso, original) = records_of(&bootable_image(), "remaster");
+        inject_files(&iso, &iso, &[("answer.toml", b"x")]).unwrap();
+        let patched = read_boot_records(&mut File::open(&iso).unwrap()).unwrap();
+        assert_eq!(patched.entries, original.entries);
+        assert!(patched.mbr && patched.gpt && patched.gpt_backup);
+        assert_eq!(original.check_preserved_in(&patched), Ok(()));
+        std::fs::remove_file(iso).unwrap();
+
+        let without_uefi = BootRecords {
+            entries: original.entries[..1].to_vec(),
+            ..patched.clone()
+        };
+        assert_eq!(
+            original
+                .check_preserved_in(&without_uefi)
+                .unwrap_err()
+                .to_string(),
+            "iso.broken_boot (El Torito UEFI entry at sector 21 missing after remastering)"
+        );
+        let without_backup = BootRecords {
+            gpt_backup: false,
+            ..patched.clone()
+        };
+        assert_eq!(
+            patched.check_preserved_in(&without_backup),
+            Err(IsoInjectError::BrokenBoot(
+                "backup GPT header invalid".into()
+            ))
+        );
+    }
+}
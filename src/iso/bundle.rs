@@ -0,0 +1,669 @@
+use crate::iso::{
+    checksum::{ChecksumAlgorithm, Hasher},
+    constants::{BUNDLE_KEYRING_FILE_NAME, BUNDLE_MANIFEST_FILE_NAME},
+    pgp::parse_public_keys,
+    tar::{self, END_OF_ARCHIVE, MAX_ENTRY_SIZE, MAX_NAME_LEN, read_header},
+    verify::{IsoVerifyError, SignedChecksums, VerifiedIso, pinned_keys, verify_iso_with},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+/// Version of the manifest layout written by [`write_bundle`].
+const BUNDLE_FORMAT: u32 = 1;
+
+/// Directory of the answer files in a bundle.
+const ANSWERS_DIR: &str = "answers";
+
+/// Largest manifest read from a bundle; real ones are a few hundred bytes.
+const MAX_MANIFEST_SIZE: u64 = 1 << 20;
+
+/* ===================== BUNDLE ERROR ===================== */
+
+/// Failure while writing or importing an air-gap bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleError {
+    /// Reading or writing failed; holds the path and the I/O error.
+    Io(PathBuf, String),
+    /// A file is too large for a ustar entry or its name too long; holds the name.
+    Unsupported(String),
+    /// Two answer files have the same file name; holds the name.
+    DuplicateName(String),
+    /// The archive is not a bundle written by [`write_bundle`]; holds the reason.
+    Malformed(String),
+    /// The bundle has a file its manifest does not list; holds the name.
+    Unexpected(String),
+    /// A file listed in the manifest is not in the bundle; holds the name.
+    Missing(String),
+    /// A file's size or SHA256 differs from the manifest's; holds the name.
+    Mismatch(String),
+    /// The ISO is another release than the manifest names.
+    WrongRelease { expected: String, found: String },
+    /// The checksum file's signature or the ISO's checksum does not verify.
+    Verify(IsoVerifyError),
+}
+
+impl BundleError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io(..) => "bundle.io",
+            Self::Unsupported(_) => "bundle.unsupported",
+            Self::DuplicateName(_) => "bundle.duplicate_name",
+            Self::Malformed(_) => "bundle.malformed",
+            Self::Unexpected(_) => "bundle.unexpected",
+            Self::Missing(_) => "bundle.missing",
+            Self::Mismatch(_) => "bundle.mismatch",
+            Self::WrongRelease { .. } => "bundle.wrong_release",
+            Self::Verify(_) => "bundle.verify",
+        }
+    }
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, detail) => write!(f, "{} ({}: {})", self.code(), path.display(), detail),
+            Self::Unsupported(name)
+            | Self::DuplicateName(name)
+            | Self::Malformed(name)
+            | Self::Unexpected(name)
+            | Self::Missing(name)
+            | Self::Mismatch(name) => write!(f, "{} ({})", self.code(), name),
+            Self::WrongRelease { expected, found } => write!(
+                f,
+                "{} (the manifest names {}, the ISO is {})",
+                self.code(),
+                expected,
+                found
+            ),
+            Self::Verify(e) => write!(f, "{} ({})", self.code(), e),
+        }
+    }
+}
+
+impl std::error::Error for BundleError {}
+
+impl From<IsoVerifyError> for BundleError {
+    fn from(e: IsoVerifyError) -> Self {
+        Self::Verify(e)
+    }
+}
+
+/* ===================== MANIFEST ===================== */
+
+/// What a file in a bundle is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BundleRole {
+    Iso,
+    Answer,
+    /// The checksum file the ISO is listed in, e.g. `SHA512SUMS`.
+    Checksums,
+    /// The detached signature of the checksum file.
+    Signature,
+    /// The keyring the signature was checked with at export.
+    Keyring,
+}
+
+/// A file in a bundle, as listed in its manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleFile {
+    /// Path in the bundle, relative and `/`-separated.
+    pub path: String,
+    pub role: BundleRole,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// The `manifest.json` that starts every bundle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format: u32,
+    /// The published file name of the ISO, as listed in the checksum file.
+    pub release: String,
+    /// Fingerprint of the key that signed the checksum file.
+    pub signer: String,
+    pub files: Vec<BundleFile>,
+}
+
+impl BundleManifest {
+    /// The path of the one file of `role`.
+    pub fn path_of(&self, role: BundleRole) -> Result<&str, BundleError> {
+        let mut files = self.files.iter().filter(|file| file.role == role);
+        match (files.next(), files.next()) {
+            (Some(file), None) => Ok(&file.path),
+            _ => Err(BundleError::Malformed(format!(
+                "the manifest must list one {:?} file",
+                role
+            ))),
+        }
+    }
+
+    /// Rejects manifests of another format, with unsafe or repeated paths, or
+    /// without exactly one ISO, checksum file, signature and keyring.
+    fn check(&self) -> Result<(), BundleError> {
+        if self.format != BUNDLE_FORMAT {
+            return Err(BundleError::Malformed(format!(
+                "format {} is not supported",
+                self.format
+            )));
+        }
+        for (i, file) in self.files.iter().enumerate() {
+            let valid = |component: &str| {
+                !component.is_empty()
+                    && component != "."
+                    && component != ".."
+                    && !component.contains(['\\', '\0'])
+            };
+            if !file.path.split('/').all(valid)
+                || file.path == BUNDLE_MANIFEST_FILE_NAME
+                || self.files[..i].iter().any(|other| other.path == file.path)
+            {
+                return Err(BundleError::Malformed(format!(
+                    "invalid path {:?}",
+                    file.path
+                )));
+            }
+        }
+        for role in [
+            BundleRole::Iso,
+            BundleRole::Checksums,
+            BundleRole::Signature,
+            BundleRole::Keyring,
+        ] {
+            self.path_of(role)?;
+        }
+        Ok(())
+    }
+}
+
+/* ===================== EXPORT ===================== */
+
+/// What [`write_bundle`] packs.
+#[derive(Debug, Clone, Copy)]
+pub struct BundleContents<'a> {
+    pub iso: &'a Path,
+    /// The result of checking `iso` against `checksums` with [`verify_iso_with`].
+    pub verified: &'a VerifiedIso,
+    pub checksums: &'a SignedChecksums,
+    /// The keyring `checksums` was verified with.
+    pub keyring: &'a [u8],
+    pub answers: &'a [PathBuf],
+}
+
+/// Writes a ustar archive to `out` holding a `manifest.json`, the ISO under
+/// its published file name, the answer files under `answers/`, the checksum
+/// file, its signature and the keyring, for carrying into an air-gapped
+/// network and checking there with [`import_bundle`].
+///
+/// The manifest lists the size and SHA256 of every other file. The ISO is
+/// hashed again while it is copied and must still have the verified SHA256.
+/// `out` is written through a `.partial` file and only replaced on success.
+///
+/// # Errors
+/// Returns an error if a file cannot be read or written, an ISO of 8 GiB or
+/// more, two answer files with the same name, or an ISO that changed since it
+/// was verified.
+pub fn write_bundle(out: &Path, contents: &BundleContents) -> Result<BundleManifest, BundleError> {
+    let sums_file = contents.checksums.algorithm.sums_file();
+    let mut small = vec![
+        (
+            sums_file.to_string(),
+            BundleRole::Checksums,
+            contents.checksums.sums.clone(),
+        ),
+        (
+            format!("{}.asc", sums_file),
+            BundleRole::Signature,
+            contents.checksums.signature.clone(),
+        ),
+        (
+            BUNDLE_KEYRING_FILE_NAME.to_string(),
+            BundleRole::Keyring,
+            contents.keyring.to_vec(),
+        ),
+    ];
+    for answer in contents.answers {
+        let name = answer
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .ok_or_else(|| BundleError::Io(answer.clone(), "not a file".into()))?;
+        let path = format!("{}/{}", ANSWERS_DIR, name);
+        if small.iter().any(|(other, ..)| *other == path) {
+            return Err(BundleError::DuplicateName(name.into_owned()));
+        }
+        let bytes = fs::read(answer).map_err(|e| io_error(answer, e))?;
+        small.push((path, BundleRole::Answer, bytes));
+    }
+
+    let iso_size = fs::metadata(contents.iso)
+        .map_err(|e| io_error(contents.iso, e))?
+        .len();
+    let mut files = vec![BundleFile {
+        path: contents.verified.file_name.clone(),
+        role: BundleRole::Iso,
+        size: iso_size,
+        sha256: contents.verified.sha256.clone(),
+    }];
+    files.extend(small.iter().map(|(path, role, bytes)| BundleFile {
+        path: path.clone(),
+        role: *role,
+        size: bytes.len() as u64,
+        sha256: sha256(bytes),
+    }));
+    if let Some(file) = files
+        .iter()
+        .find(|file| file.path.len() > MAX_NAME_LEN || file.size > MAX_ENTRY_SIZE)
+    {
+        return Err(BundleError::Unsupported(file.path.clone()));
+    }
+    let manifest = BundleManifest {
+        format: BUNDLE_FORMAT,
+        release: contents.verified.file_name.clone(),
+        signer: contents.verified.signer.clone(),
+        files,
+    };
+    manifest.check()?;
+
+    let mut partial = out.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    let result = write_archive(&partial, &manifest, contents.iso, &small)
+        .and_then(|_| fs::rename(&partial, out).map_err(|e| io_error(out, e)));
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    result.map(|_| manifest)
+}
+
+fn write_archive(
+    path: &Path,
+    manifest: &BundleManifest,
+    iso: &Path,
+    small: &[(String, BundleRole, Vec<u8>)],
+) -> Result<(), BundleError> {
+    let write_error = |e: io::Error| io_error(path, e);
+    let json =
+        serde_json::to_vec_pretty(manifest).map_err(|e| BundleError::Malformed(e.to_string()))?;
+    let mut head = Vec::new();
+    tar::append(&mut head, BUNDLE_MANIFEST_FILE_NAME, 0o644, &json);
+
+    let iso_file = &manifest.files[0];
+    let mut archive = BufWriter::new(File::create(path).map_err(write_error)?);
+    archive.write_all(&head).map_err(write_error)?;
+    archive
+        .write_all(&tar::header(&iso_file.path, 0o644, iso_file.size))
+        .map_err(write_error)?;
+    let mut source = File::open(iso).map_err(|e| io_error(iso, e))?;
+    let sha256 = copy_hashed(&mut source, &mut archive, iso_file.size).map_err(write_error)?;
+    if sha256 != iso_file.sha256 {
+        return Err(BundleError::Mismatch(iso.display().to_string()));
+    }
+
+    let mut tail = vec![0; tar::padding(iso_file.size)];
+    for (name, _, bytes) in small {
+        tar::append(&mut tail, name, 0o644, bytes);
+    }
+    tail.extend_from_slice(&END_OF_ARCHIVE);
+    archive.write_all(&tail).map_err(write_error)?;
+    archive
+        .into_inner()
+        .map_err(|e| write_error(e.into_error()))?
+        .sync_all()
+        .map_err(write_error)
+}
+
+/* ===================== IMPORT ===================== */
+
+/// The contents of a bundle unpacked and checked by [`import_bundle`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedBundle {
+    pub manifest: BundleManifest,
+    /// Where the ISO was written.
+    pub iso: PathBuf,
+    /// Where the answer files were written, in the bundle's order.
+    pub answers: Vec<PathBuf>,
+    pub verified: VerifiedIso,
+}
+
+/// Unpacks the bundle at `bundle` into `dir` and checks it: every file must
+/// be listed in the manifest with its size and SHA256, the checksum file's
+/// signature must verify, and the ISO must be the release the checksum file
+/// and the manifest name.
+///
+/// The signature is checked against `keyring` when given, otherwise against
+/// the bundled keyring, whose key must be a pinned release key; nothing is
+/// downloaded. Existing files in `dir` are never overwritten, and the files
+/// unpacked so far are removed when a check fails.
+///
+/// # Errors
+/// Any of the checks failing, a file existing in `dir`, or a file that cannot
+/// be read or written.
+pub fn import_bundle(
+    bundle: &Path,
+    dir: &Path,
+    keyring: Option<&[u8]>,
+) -> Result<ImportedBundle, BundleError> {
+    let mut unpacked = Vec::new();
+    let result = unpack(bundle, dir, &mut unpacked)
+        .and_then(|manifest| check_unpacked(dir, manifest, keyring));
+    if result.is_err() {
+        for path in unpacked.iter().rev() {
+            let _ = fs::remove_file(path);
+        }
+    }
+    result
+}
+
+/// Unpacks `bundle` into `dir`, recording each file created in `unpacked`,
+/// and returns its manifest once every file matches it.
+fn unpack(
+    bundle: &Path,
+    dir: &Path,
+    unpacked: &mut Vec<PathBuf>,
+) -> Result<BundleManifest, BundleError> {
+    let read_error = |e: io::Error| io_error(bundle, e);
+    let mut archive = BufReader::new(File::open(bundle).map_err(read_error)?);
+
+    let manifest: BundleManifest = match read_header(&mut archive).map_err(read_error)? {
+        Some(entry)
+            if entry.regular
+                && entry.name == BUNDLE_MANIFEST_FILE_NAME
+                && entry.size <= MAX_MANIFEST_SIZE =>
+        {
+            let mut json = vec![0; entry.size as usize + tar::padding(entry.size)];
+            archive.read_exact(&mut json).map_err(read_error)?;
+            json.truncate(entry.size as usize);
+            serde_json::from_slice(&json).map_err(|e| {
+                BundleError::Malformed(format!("{}: {}", BUNDLE_MANIFEST_FILE_NAME, e))
+            })?
+        }
+        _ => {
+            return Err(BundleError::Malformed(format!(
+                "{} is not its first file",
+                BUNDLE_MANIFEST_FILE_NAME
+            )));
+        }
+    };
+    manifest.check()?;
+
+    let mut pending = manifest.files.clone();
+    while let Some(entry) = read_header(&mut archive).map_err(read_error)? {
+        let file = pending
+            .iter()
+            .position(|file| entry.regular && file.path == entry.name)
+            .map(|i| pending.swap_remove(i))
+            .ok_or_else(|| BundleError::Unexpected(entry.name.clone()))?;
+        if entry.size != file.size {
+            return Err(BundleError::Mismatch(file.path));
+        }
+
+        let path = dir.join(&file.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| io_error(parent, e))?;
+        }
+        let mut out = File::create_new(&path).map_err(|e| io_error(&path, e))?;
+        unpacked.push(path.clone());
+        let sha256 = copy_hashed(&mut archive, &mut out, file.size).map_err(read_error)?;
+        out.sync_all().map_err(|e| io_error(&path, e))?;
+        io::copy(
+            &mut (&mut archive).take(tar::padding(file.size) as u64),
+            &mut io::sink(),
+        )
+        .map_err(read_error)?;
+        if !sha256.eq_ignore_ascii_case(&file.sha256) {
+            return Err(BundleError::Mismatch(file.path));
+        }
+    }
+    match pending.first() {
+        Some(file) => Err(BundleError::Missing(file.path.clone())),
+        None => Ok(manifest),
+    }
+}
+
+fn check_unpacked(
+    dir: &Path,
+    manifest: BundleManifest,
+    keyring: Option<&[u8]>,
+) -> Result<ImportedBundle, BundleError> {
+    let read = |role| {
+        let path = dir.join(manifest.path_of(role)?);
+        fs::read(&path).map_err(|e| io_error(&path, e))
+    };
+    let keys = match keyring {
+        Some(keyring) => parse_public_keys(keyring).map_err(IsoVerifyError::from)?,
+        None => pinned_keys(&read(BundleRole::Keyring)?)?,
+    };
+    let sums_file = manifest.path_of(BundleRole::Checksums)?;
+    let algorithm = ChecksumAlgorithm::STRONGEST_FIRST
+        .into_iter()
+        .find(|algorithm| algorithm.sums_file() == sums_file)
+        .ok_or_else(|| BundleError::Malformed(format!("{} is not a checksum file", sums_file)))?;
+
+    let iso = dir.join(manifest.path_of(BundleRole::Iso)?);
+    let verified = verify_iso_with(
+        &iso,
+        algorithm,
+        &read(BundleRole::Checksums)?,
+        &read(BundleRole::Signature)?,
+        &keys,
+    )?;
+    if verified.file_name != manifest.release {
+        return Err(BundleError::WrongRelease {
+            expected: manifest.release,
+            found: verified.file_name,
+        });
+    }
+
+    let answers = manifest
+        .files
+        .iter()
+        .filter(|file| file.role == BundleRole::Answer)
+        .map(|file| dir.join(&file.path))
+        .collect();
+    Ok(ImportedBundle {
+        manifest,
+        iso,
+        answers,
+        verified,
+    })
+}
+
+/* ===================== HELPERS ===================== */
+
+/// Copies exactly `size` bytes from `reader` to `writer` and returns their
+/// lower-case hex SHA256.
+fn copy_hashed<R: Read, W: Write>(reader: &mut R, writer: &mut W, size: u64) -> io::Result<String> {
+    let mut hasher = Hasher::new(ChecksumAlgorithm::Sha256);
+    let mut buf = vec![0; 1 << 20];
+    let mut left = size;
+    while left > 0 {
+        let len = buf.len().min(usize::try_from(left).unwrap_or(usize::MAX));
+        let read = reader.read(&mut buf[..len])?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        hasher.update(&buf[..read]);
+        writer.write_all(&buf[..read])?;
+        left -= read as u64;
+    }
+    Ok(hasher.finish().sha256)
+}
+
+fn sha256(bytes: &[u8]) -> String {
+    let mut hasher = Hasher::new(ChecksumAlgorithm::Sha256);
+    hasher.update(bytes);
+    hasher.finish().sha256
+}
+
+fn io_error(path: &Path, e: io::Error) -> BundleError {
+    BundleError::Io(path.to_path_buf(), e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iso::pgp::tests::{keyring, private_key, sign};
+
+    const ISO: &[u8] = b"not really an iso";
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pveauto-bundle-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes a bundle of a test ISO and `answer.toml` signed by a test key
+    /// into `dir`, returning its path and the test keyring.
+    fn export(dir: &Path) -> (PathBuf, Vec<u8>) {
+        let iso = dir.join("proxmox-ve-latest.iso");
+        fs::write(&iso, ISO).unwrap();
+        let answer = dir.join("answer.toml");
+        fs::write(&answer, "[global]\nkeyboard = \"en-us\"\n").unwrap();
+
+        let key = private_key(0);
+        let keyring = keyring(&key);
+        let sums = format!("{}  proxmox-ve_8.2-1.iso\n", sha256(ISO)).into_bytes();
+        let checksums = SignedChecksums {
+            algorithm: ChecksumAlgorithm::Sha256,
+            signature: sign(&key, &sums, 0),
+            sums,
+        };
+        let keys = parse_public_keys(&keyring).unwrap();
+        let verified = verify_iso_with(
+            &iso,
+            checksums.algorithm,
+            &checksums.sums,
+            &checksums.signature,
+            &keys,
+        )
+        .unwrap();
+
+        let out = dir.join("bundle.tar");
+        let manifest = write_bundle(
+            &out,
+            &BundleContents {
+                iso: &iso,
+                verified: &verified,
+                checksums: &checksums,
+                keyring: &keyring,
+                answers: &[answer],
+            },
+        )
+        .unwrap();
+        assert_eq!(manifest.release, "proxmox-ve_8.2-1.iso");
+        assert_eq!(
+            manifest
+                .files
+                .iter()
+                .map(|file| (file.path.as_str(), file.role))
+                .collect::<Vec<_>>(),
+            [
+                ("proxmox-ve_8.2-1.iso", BundleRole::Iso),
+                ("SHA256SUMS", BundleRole::Checksums),
+                ("SHA256SUMS.asc", BundleRole::Signature),
+                ("release-keyring.gpg", BundleRole::Keyring),
+                ("answers/answer.toml", BundleRole::Answer),
+            ]
+        );
+        (out, keyring)
+    }
+
+    #[test]
+    fn bundles_are_unpacked_and_verified_on_import() {
+        let dir = temp_dir("import");
+        let (bundle, keyring) = export(&dir);
+        assert!(!dir.join("bundle.tar.partial").exists());
+        let dest = dir.join("imported");
+
+        // The test key is not a pinned release key.
+        assert_eq!(
+            import_bundle(&bundle, &dest, None),
+            Err(BundleError::Verify(IsoVerifyError::UntrustedKey))
+        );
+        assert!(!dest.join("proxmox-ve_8.2-1.iso").exists());
+
+        let imported = import_bundle(&bundle, &dest, Some(&keyring[..])).unwrap();
+        assert_eq!(imported.iso, dest.join("proxmox-ve_8.2-1.iso"));
+        assert_eq!(fs::read(&imported.iso).unwrap(), ISO);
+        assert_eq!(imported.answers, [dest.join("answers/answer.toml")]);
+        assert_eq!(
+            fs::read(&imported.answers[0]).unwrap(),
+            fs::read(dir.join("answer.toml")).unwrap()
+        );
+        assert_eq!(imported.verified.file_name, "proxmox-ve_8.2-1.iso");
+
+        // Existing files are not overwritten.
+        assert!(matches!(
+            import_bundle(&bundle, &dest, Some(&keyring[..])),
+            Err(BundleError::Io(..))
+        ));
+        assert!(imported.iso.exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn tampered_bundles_are_rejected() {
+        let dir = temp_dir("tampered");
+        let (bundle, keyring) = export(&dir);
+        let original = fs::read(&bundle).unwrap();
+        let import = |bytes: &[u8], name: &str| {
+            let path = dir.join(name);
+            fs::write(&path, bytes).unwrap();
+            import_bundle(
+                &path,
+                &dir.join(name).with_extension("d"),
+                Some(&keyring[..]),
+            )
+        };
+
+        let mut changed = original.clone();
+        let at = changed
+            .windows(5)
+            .position(|window| window == b"en-us")
+            .unwrap();
+        changed[at] = b'd';
+        assert_eq!(
+            import(&changed, "changed.tar"),
+            Err(BundleError::Mismatch("answers/answer.toml".into()))
+        );
+        assert!(!dir.join("changed.d/answers/answer.toml").exists());
+        assert!(!dir.join("changed.d/proxmox-ve_8.2-1.iso").exists());
+
+        let mut extra = original[..original.len() - END_OF_ARCHIVE.len()].to_vec();
+        tar::append(&mut extra, "../escape", 0o644, b"x");
+        extra.extend_from_slice(&END_OF_ARCHIVE);
+        assert_eq!(
+            import(&extra, "extra.tar"),
+            Err(BundleError::Unexpected("../escape".into()))
+        );
+
+        let mut manifest: BundleManifest = {
+            let mut reader = &original[..];
+            let entry = read_header(&mut reader).unwrap().unwrap();
+            serde_json::from_slice(&reader[..entry.size as usize]).unwrap()
+        };
+        manifest.files[4].path = "../answer.toml".into();
+        let mut forged = Vec::new();
+        let json = serde_json::to_vec(&manifest).unwrap();
+        tar::append(&mut forged, BUNDLE_MANIFEST_FILE_NAME, 0o644, &json);
+        forged.extend_from_slice(&END_OF_ARCHIVE);
+        assert_eq!(
+            import(&forged, "forged.tar"),
+            Err(BundleError::Malformed(
+                "invalid path \"../answer.toml\"".into()
+            ))
+        );
+        assert!(matches!(
+            import(&original[..2048], "truncated.tar"),
+            Err(BundleError::Io(..))
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,589 @@
+use crate::iso::{
+    checksum::{ChecksumAlgorithm, Hashes},
+    constants::{ISO_CACHE_METADATA_SUFFIX, PARTIAL_DOWNLOAD_SUFFIX, QUARANTINE_INFIX},
+    mirrors::MirrorBenchmark,
+    scraper::{iso_file_name, iso_version, version_key},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use toml::value::Datetime;
+
+/// Record of the latest release as last seen on its source page.
+const LATEST_FILE_NAME: &str = "latest.toml";
+
+/// Record of the last mirror benchmark.
+const MIRRORS_FILE_NAME: &str = "mirrors.toml";
+
+/// Versions `iso prune` keeps unless told otherwise.
+pub const DEFAULT_KEEP_VERSIONS: usize = 2;
+
+/* ===================== CACHE ERROR ===================== */
+
+/// Failure while writing to the ISO cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheError {
+    /// A cache file could not be written; holds the path and the I/O error.
+    Io(PathBuf, String),
+}
+
+impl CacheError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io(..) => "iso.cache.io",
+        }
+    }
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, detail) => write!(f, "{} ({}: {})", self.code(), path.display(), detail),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+/* ===================== CACHE ENTRIES ===================== */
+
+/// Metadata of a cached ISO, stored next to it as `<file name>.toml`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CacheEntry {
+    pub version: String,
+    /// Where the ISO was downloaded from.
+    pub url: String,
+    pub sha256: String,
+    /// Recorded when the ISO was checked against a published SHA512.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha512: Option<String>,
+    /// `ETag` of `url` when the ISO was downloaded, if the server sent one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    /// Size of the verified ISO in bytes.
+    pub size: u64,
+    /// Modification time of the verified ISO, in seconds since the Unix epoch.
+    pub modified: u64,
+    /// When the ISO was downloaded and verified.
+    pub fetched: Datetime,
+}
+
+impl CacheEntry {
+    /// The recorded digest of `algorithm`, if any.
+    pub fn digest(&self, algorithm: ChecksumAlgorithm) -> Option<&str> {
+        match algorithm {
+            ChecksumAlgorithm::Md5 => None,
+            ChecksumAlgorithm::Sha256 => Some(&self.sha256),
+            ChecksumAlgorithm::Sha512 => self.sha512.as_deref(),
+        }
+    }
+
+    /// Whether the file at `path` has the size and modification time
+    /// recorded for the verified ISO: the cached ISO while it is intact, or a
+    /// link or copy of it placed by [`link_or_copy`].
+    pub fn matches_file(&self, path: &Path) -> bool {
+        fs::metadata(path).is_ok_and(|meta| {
+            meta.len() == self.size
+                && meta
+                    .modified()
+                    .is_ok_and(|modified| unix_seconds(modified) == self.modified)
+        })
+    }
+}
+
+/// The release `source` (the download page or a mirror listing) named as
+/// latest, and the `ETag` it had then.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LatestEntry {
+    pub source: String,
+    pub version: String,
+    pub etag: String,
+    pub checked: Datetime,
+}
+
+/// What [`IsoCache::prune`] kept and removed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    /// Versions left in the cache, newest first.
+    pub kept: Vec<String>,
+    /// Versions deleted with their metadata, newest first.
+    pub removed: Vec<String>,
+    /// Bytes released on disk. An ISO still hard-linked elsewhere, e.g. at
+    /// the path it was downloaded to, frees nothing.
+    pub freed: u64,
+}
+
+/* ===================== CACHE ===================== */
+
+/// A directory of downloaded ISOs, each with a [`CacheEntry`] sidecar, so
+/// that repeated downloads can tell an unchanged ISO apart without hashing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IsoCache {
+    dir: PathBuf,
+}
+
+impl IsoCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Returns the user ISO cache directory (XDG compliant):
+    /// `$XDG_CACHE_HOME/pve-auto/iso`, falling back to `~/.cache/pve-auto/iso`.
+    pub fn default_dir() -> PathBuf {
+        if let Ok(dir) = std::env::var("XDG_CACHE_HOME") {
+            PathBuf::from(dir).join("pve-auto/iso")
+        } else if let Ok(home) = std::env::var("HOME") {
+            PathBuf::from(home).join(".cache/pve-auto/iso")
+        } else {
+            PathBuf::from("pve-auto-iso-cache")
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The cached ISO of `version`, e.g. `proxmox-ve_8.2-1.iso`.
+    pub fn iso_path(&self, version: &str) -> PathBuf {
+        self.dir.join(iso_file_name(version))
+    }
+
+    fn metadata_path(&self, version: &str) -> PathBuf {
+        let mut name = iso_file_name(version);
+        name.push_str(ISO_CACHE_METADATA_SUFFIX);
+        self.dir.join(name)
+    }
+
+    /// The metadata of `version`.
+    ///
+    /// # Returns
+    /// `None` when there is none or it cannot be read, so that a damaged
+    /// cache only ever costs a fresh download.
+    pub fn entry(&self, version: &str) -> Option<CacheEntry> {
+        read_toml(&self.metadata_path(version)).filter(|e: &CacheEntry| e.version == version)
+    }
+
+    /// The metadata of `version` when its ISO still has the size and
+    /// modification time recorded after it was verified.
+    pub fn intact_entry(&self, version: &str) -> Option<CacheEntry> {
+        self.entry(version)
+            .filter(|entry| entry.matches_file(&self.iso_path(version)))
+    }
+
+    /// Records the verified ISO of `version`, now at [`IsoCache::iso_path`].
+    pub fn record(
+        &self,
+        version: &str,
+        url: &str,
+        hashes: &Hashes,
+        etag: Option<String>,
+    ) -> Result<CacheEntry, CacheError> {
+        let iso = self.iso_path(version);
+        let meta = fs::metadata(&iso).map_err(|e| io_error(&iso, e))?;
+        let entry = CacheEntry {
+            version: version.to_string(),
+            url: url.to_string(),
+            sha256: hashes.sha256.to_lowercase(),
+            sha512: hashes.sha512.as_ref().map(|sha512| sha512.to_lowercase()),
+            etag,
+            size: meta.len(),
+            modified: unix_seconds(meta.modified().map_err(|e| io_error(&iso, e))?),
+            fetched: utc_datetime(unix_seconds(SystemTime::now())),
+        };
+        write_toml(&self.metadata_path(version), &entry)?;
+        Ok(entry)
+    }
+
+    /// The versions with an ISO, metadata, a partial download or a
+    /// quarantined download in the cache, oldest first.
+    pub fn versions(&self) -> Result<Vec<String>, CacheError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(io_error(&self.dir, e)),
+        };
+        let mut versions: Vec<String> = entries
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter_map(|name| {
+                let name = name.split(QUARANTINE_INFIX).next().unwrap_or(&name);
+                let iso = name
+                    .strip_suffix(ISO_CACHE_METADATA_SUFFIX)
+                    .or_else(|| name.strip_suffix(PARTIAL_DOWNLOAD_SUFFIX))
+                    .unwrap_or(name);
+                iso_version(iso)
+            })
+            .collect();
+        versions.sort_by_key(|v| version_key(v));
+        versions.dedup();
+        Ok(versions)
+    }
+
+    /// Deletes all but the newest `keep` versions: their ISO, metadata and
+    /// any partial or quarantined download.
+    pub fn prune(&self, keep: usize) -> Result<PruneReport, CacheError> {
+        let mut kept = self.versions()?;
+        kept.reverse();
+        let removed = kept.split_off(keep.min(kept.len()));
+        let mut freed = 0;
+        for version in &removed {
+            let iso = self.iso_path(version);
+            let mut partial = iso.clone().into_os_string();
+            partial.push(PARTIAL_DOWNLOAD_SUFFIX);
+            for path in [PathBuf::from(partial), self.metadata_path(version), iso] {
+                freed += remove_file_freeing(&path)?;
+            }
+            for path in self.quarantined(version)? {
+                freed += remove_file_freeing(&path)?;
+            }
+        }
+        Ok(PruneReport {
+            kept,
+            removed,
+            freed,
+        })
+    }
+
+    /// The quarantined downloads of `version`, see [`quarantine`].
+    pub fn quarantined(&self, version: &str) -> Result<Vec<PathBuf>, CacheError> {
+        let prefix = iso_file_name(version);
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(io_error(&self.dir, e)),
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry.file_name().to_str().is_some_and(|name| {
+                    name.split_once(QUARANTINE_INFIX).is_some_and(|(file, _)| {
+                        file.strip_suffix(PARTIAL_DOWNLOAD_SUFFIX).unwrap_or(file) == prefix
+                    })
+                })
+            })
+            .map(|entry| entry.path())
+            .collect();
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// The latest release recorded for `source`.
+    pub fn latest(&self, source: &str) -> Option<LatestEntry> {
+        read_toml(&self.dir.join(LATEST_FILE_NAME)).filter(|l: &LatestEntry| l.source == source)
+    }
+
+    /// Records `version` as the latest release of `source`, whose page has `etag`.
+    pub fn record_latest(&self, source: &str, version: &str, etag: &str) -> Result<(), CacheError> {
+        let latest = LatestEntry {
+            source: source.to_string(),
+            version: version.to_string(),
+            etag: etag.to_string(),
+            checked: utc_datetime(unix_seconds(SystemTime::now())),
+        };
+        write_toml(&self.dir.join(LATEST_FILE_NAME), &latest)
+    }
+
+    /// The last recorded mirror benchmark.
+    pub fn mirror_benchmark(&self) -> Option<MirrorBenchmark> {
+        read_toml(&self.dir.join(MIRRORS_FILE_NAME))
+    }
+
+    /// Records `benchmark`, replacing the previous one.
+    pub fn record_mirror_benchmark(&self, benchmark: &MirrorBenchmark) -> Result<(), CacheError> {
+        write_toml(&self.dir.join(MIRRORS_FILE_NAME), benchmark)
+    }
+}
+
+/// Whether `a` and `b` are the same file, e.g. hard links of each other.
+pub fn same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (fs::metadata(a), fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        matches!((fs::canonicalize(a), fs::canonicalize(b)), (Ok(a), Ok(b)) if a == b)
+    }
+}
+
+/// Replaces `to` with a hard link of `from`, or with a copy when they are on
+/// different filesystems. Copies keep the modification time of `from`, so
+/// that [`CacheEntry::matches_file`] recognises them without hashing.
+pub fn link_or_copy(from: &Path, to: &Path) -> Result<(), CacheError> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|e| io_error(parent, e))?;
+    }
+    let mut tmp = to.as_os_str().to_owned();
+    tmp.push(".link");
+    let tmp = PathBuf::from(tmp);
+    let _ = fs::remove_file(&tmp);
+    fs::hard_link(from, &tmp)
+        .or_else(|_| copy_with_modified(from, &tmp))
+        .map_err(|e| io_error(&tmp, e))?;
+    fs::rename(&tmp, to).map_err(|e| io_error(to, e))
+}
+
+/// Sets aside the file at `path`, which failed its checksum, as
+/// `<path>.corrupt-<UTC time>`, so that it can be inspected and a new download
+/// never writes over it. A number is appended should that name be taken.
+///
+/// # Returns
+/// The new path of the file.
+pub fn quarantine(path: &Path) -> Result<PathBuf, CacheError> {
+    let stamp = utc_datetime(unix_seconds(SystemTime::now()))
+        .to_string()
+        .replace(['-', ':'], "");
+    let mut name = path.as_os_str().to_owned();
+    name.push(QUARANTINE_INFIX);
+    name.push(&stamp);
+    let mut quarantined = PathBuf::from(&name);
+    let mut n = 1;
+    while fs::symlink_metadata(&quarantined).is_ok() {
+        n += 1;
+        let mut numbered = name.clone();
+        numbered.push(format!("-{}", n));
+        quarantined = PathBuf::from(numbered);
+    }
+    fs::rename(path, &quarantined).map_err(|e| io_error(path, e))?;
+    Ok(quarantined)
+}
+
+fn copy_with_modified(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::copy(from, to)?;
+    let modified = fs::metadata(from)?.modified()?;
+    fs::File::options()
+        .write(true)
+        .open(to)?
+        .set_modified(modified)
+}
+
+/// Removes `path` if it exists.
+///
+/// # Returns
+/// The bytes released, `0` when other hard links keep the data.
+fn remove_file_freeing(path: &Path) -> Result<u64, CacheError> {
+    let meta = match fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(io_error(path, e)),
+    };
+    fs::remove_file(path).map_err(|e| io_error(path, e))?;
+    #[cfg(unix)]
+    let last_link = std::os::unix::fs::MetadataExt::nlink(&meta) <= 1;
+    #[cfg(not(unix))]
+    let last_link = true;
+    Ok(if last_link { meta.len() } else { 0 })
+}
+
+fn read_toml<T: for<'de> Deserialize<'de>>(path: &Path) -> Option<T> {
+    toml::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn write_toml<T: Serialize>(path: &Path, value: &T) -> Result<(), CacheError> {
+    let contents = toml::to_string(value).expect("cache metadata always serializes");
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| io_error(parent, e))?;
+    }
+    fs::write(path, contents).map_err(|e| io_error(path, e))
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> CacheError {
+    CacheError::Io(path.to_path_buf(), e.to_string())
+}
+
+pub(crate) fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The UTC date and time `secs` seconds after the Unix epoch.
+pub(crate) fn utc_datetime(secs: u64) -> Datetime {
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let (days, secs) = ((secs / 86_400) as i64, secs % 86_400);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
+    .parse()
+    .expect("valid RFC 3339 date-time")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache(name: &str) -> IsoCache {
+        let dir =
+            std::env::temp_dir().join(format!("pveauto-iso-cache-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        IsoCache::new(dir)
+    }
+
+    #[test]
+    fn recorded_isos_stay_intact_until_modified() {
+        let cache = temp_cache("record");
+        assert_eq!(cache.entry("8.2-1"), None);
+        fs::create_dir_all(cache.dir()).unwrap();
+        fs::write(cache.iso_path("8.2-1"), b"iso").unwrap();
+
+        let entry = cache
+            .record(
+                "8.2-1",
+                "https://enterprise.proxmox.com/iso/proxmox-ve_8.2-1.iso",
+                &Hashes {
+                    sha256: "ABCD".into(),
+                    sha512: None,
+                },
+                Some("\"etag\"".into()),
+            )
+            .unwrap();
+        assert_eq!((entry.size, entry.sha256.as_str()), (3, "abcd"));
+        assert_eq!(cache.entry("8.2-1"), Some(entry.clone()));
+        assert_eq!(cache.intact_entry("8.2-1"), Some(entry));
+        assert!(cache.dir().join("proxmox-ve_8.2-1.iso.toml").exists());
+
+        fs::write(cache.iso_path("8.2-1"), b"changed").unwrap();
+        assert_eq!(cache.intact_entry("8.2-1"), None);
+        fs::write(cache.dir().join("proxmox-ve_8.2-1.iso.toml"), "version = ").unwrap();
+        assert_eq!(cache.entry("8.2-1"), None);
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn the_latest_release_is_recorded_per_source() {
+        let cache = temp_cache("latest");
+        cache
+            .record_latest("https://mirror.lan/iso/", "8.2-1", "\"abc\"")
+            .unwrap();
+        let latest = cache.latest("https://mirror.lan/iso/").unwrap();
+        assert_eq!(
+            (latest.version.as_str(), latest.etag.as_str()),
+            ("8.2-1", "\"abc\"")
+        );
+        assert_eq!(cache.latest("https://other.lan/iso/"), None);
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn linked_copies_are_the_same_file() {
+        let cache = temp_cache("link");
+        fs::create_dir_all(cache.dir()).unwrap();
+        let (iso, dest) = (cache.iso_path("8.2-1"), cache.dir().join("out/pve.iso"));
+        fs::write(&iso, b"iso").unwrap();
+        assert!(!same_file(&iso, &dest));
+        link_or_copy(&iso, &dest).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), b"iso");
+        assert!(same_file(&iso, &dest));
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn corrupt_files_are_set_aside_under_unique_names() {
+        let cache = temp_cache("quarantine");
+        fs::create_dir_all(cache.dir()).unwrap();
+        let part = cache.dir().join("proxmox-ve_8.2-1.iso.part");
+        let mut kept = Vec::new();
+        for contents in ["bad", "worse"] {
+            fs::write(&part, contents).unwrap();
+            kept.push(quarantine(&part).unwrap());
+        }
+        assert!(!part.exists());
+        assert_ne!(kept[0], kept[1]);
+        let name = kept[0].file_name().unwrap().to_str().unwrap();
+        let stamp = name
+            .strip_prefix("proxmox-ve_8.2-1.iso.part.corrupt-")
+            .unwrap();
+        assert!(stamp.len() == 16 && stamp.ends_with('Z'), "{}", stamp);
+        assert_eq!(fs::read(&kept[1]).unwrap(), b"worse");
+
+        kept.sort();
+        assert_eq!(cache.quarantined("8.2-1").unwrap(), kept);
+        assert_eq!(cache.quarantined("8.2-10").unwrap(), Vec::<PathBuf>::new());
+        assert_eq!(cache.versions().unwrap(), ["8.2-1"]);
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn pruning_keeps_the_newest_versions() {
+        let cache = temp_cache("prune");
+        fs::create_dir_all(cache.dir()).unwrap();
+        for version in ["7.4-1", "8.1-2", "8.2-1", "8.10-1"] {
+            fs::write(cache.iso_path(version), b"iso").unwrap();
+            cache
+                .record(
+                    version,
+                    "http://mirror.lan/",
+                    &Hashes {
+                        sha256: "ab".into(),
+                        sha512: None,
+                    },
+                    None,
+                )
+                .unwrap();
+        }
+        fs::write(cache.dir().join("proxmox-ve_9.0-1.iso.part"), b"pa").unwrap();
+        let corrupt = quarantine(&cache.iso_path("8.1-2")).unwrap();
+        fs::write(cache.iso_path("8.1-2"), b"iso").unwrap();
+        let placed = cache.dir().join("out.iso");
+        link_or_copy(&cache.iso_path("7.4-1"), &placed).unwrap();
+        assert_eq!(
+            cache.versions().unwrap(),
+            ["7.4-1", "8.1-2", "8.2-1", "8.10-1", "9.0-1"]
+        );
+
+        let metadata = fs::metadata(cache.dir().join("proxmox-ve_8.1-2.iso.toml"))
+            .unwrap()
+            .len();
+        let report = cache.prune(3).unwrap();
+        assert_eq!(report.kept, ["9.0-1", "8.10-1", "8.2-1"]);
+        assert_eq!(report.removed, ["8.1-2", "7.4-1"]);
+        // The hard-linked 7.4-1 ISO is still at `placed`.
+        assert_eq!(report.freed, 2 * 3 + 2 * metadata);
+        assert!(!cache.iso_path("8.1-2").exists());
+        assert!(!corrupt.exists());
+        assert!(!cache.dir().join("proxmox-ve_7.4-1.iso.toml").exists());
+        assert_eq!(fs::read(&placed).unwrap(), b"iso");
+
+        assert_eq!(cache.prune(3).unwrap().removed, Vec::<String>::new());
+        assert_eq!(cache.prune(0).unwrap().kept, Vec::<String>::new());
+        assert!(!cache.dir().join("proxmox-ve_9.0-1.iso.part").exists());
+        fs::remove_dir_all(cache.dir()).unwrap();
+        assert_eq!(cache.prune(1), Ok(PruneReport::default()));
+    }
+
+    #[test]
+    fn fetch_dates_are_utc() {
+        assert_eq!(utc_datetime(0).to_string(), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            utc_datetime(1_700_000_000).to_string(),
+            "2023-11-14T22:13:20Z"
+        );
+        assert_eq!(
+            utc_datetime(951_782_400).to_string(),
+            "2000-02-29T00:00:00Z"
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use crate::iso::scraper::version_key;
+use std::{fmt, str::FromStr};
+
+/// The Proxmox VE ISOs a download or listing looks at.
+///
+/// Releases are announced on the download page and published in the
+/// directory listing at [`ISO_INDEX_URL`](crate::iso::constants::ISO_INDEX_URL);
+/// test ISOs, the betas of the next major release such as
+/// `proxmox-ve_9.0-BETA-1.iso`, are only published in the listing and its
+/// mirrors, so the test channel is always read from there.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IsoChannel {
+    #[default]
+    Stable,
+    Test,
+}
+
+impl IsoChannel {
+    /// Whether `version` belongs to this channel: a release such as `8.2-1`
+    /// to [`Self::Stable`], a beta such as `9.0-BETA-1` to [`Self::Test`].
+    pub fn includes(self, version: &str) -> bool {
+        version_key(version).is_some_and(|(_, _, released, _)| released == (self == Self::Stable))
+    }
+
+    /// The key the newest ISO of this channel in the listing at `index_url`
+    /// is recorded under in the cache: the URL itself for releases, as the
+    /// download page fallback shares it, and `<url>#test` for test ISOs, so
+    /// the channels never reuse each other's ISO.
+    pub fn listing_key(self, index_url: &str) -> String {
+        match self {
+            Self::Stable => index_url.to_string(),
+            Self::Test => format!("{}#{}", index_url, self),
+        }
+    }
+
+    /// The error of a directory listing without an ISO of this channel.
+    pub fn nothing_listed(self) -> String {
+        match self {
+            Self::Stable => "No Proxmox VE ISO found in the directory listing".into(),
+            Self::Test => "No Proxmox VE test ISO found in the directory listing".into(),
+        }
+    }
+}
+
+impl FromStr for IsoChannel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stable" => Ok(Self::Stable),
+            "test" => Ok(Self::Test),
+            _ => Err(format!("unknown ISO channel: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for IsoChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Stable => "stable",
+            Self::Test => "test",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_belong_to_one_channel() {
+        assert!(IsoChannel::Stable.includes("8.2-1"));
+        assert!(!IsoChannel::Test.includes("8.2-1"));
+        assert!(IsoChannel::Test.includes("9.0-BETA-1"));
+        assert!(!IsoChannel::Stable.includes("9.0-BETA-1"));
+        assert!(!IsoChannel::Test.includes("latest"));
+
+        assert_eq!("test".parse(), Ok(IsoChannel::Test));
+        assert_eq!(
+            "beta".parse::<IsoChannel>(),
+            Err("unknown ISO channel: beta".into())
+        );
+        assert_eq!(
+            IsoChannel::Test.listing_key("http://mirror.lan/iso/"),
+            "http://mirror.lan/iso/#test"
+        );
+        assert_eq!(
+            IsoChannel::Stable.listing_key("http://mirror.lan/iso/"),
+            "http://mirror.lan/iso/"
+        );
+    }
+}
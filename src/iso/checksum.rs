@@ -0,0 +1,311 @@
+use crate::iso::constants::SHA256_SIDECAR_SUFFIX;
+use sha2::{Digest, Sha256, Sha512};
+use std::{
+    fmt,
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+};
+
+/* ===================== CHECKSUM ERROR ===================== */
+
+/// A published checksum that cannot be used to verify an ISO.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChecksumError {
+    /// Not a hex digest of the algorithm's length; holds the algorithm and
+    /// the checksum.
+    Malformed(ChecksumAlgorithm, String),
+    /// The algorithm is broken, so a matching checksum proves nothing.
+    Untrusted(ChecksumAlgorithm),
+}
+
+impl ChecksumError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Malformed(..) => "iso.checksum.malformed",
+            Self::Untrusted(_) => "iso.checksum.untrusted",
+        }
+    }
+}
+
+impl fmt::Display for ChecksumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed(algorithm, checksum) => {
+                write!(f, "{} ({}: {})", self.code(), algorithm, checksum)
+            }
+            Self::Untrusted(algorithm) => write!(
+                f,
+                "{} (only {} is published; {} collisions are cheap to forge, publish SHA256SUMS or SHA512SUMS)",
+                self.code(),
+                algorithm.sums_file(),
+                algorithm
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ChecksumError {}
+
+/* ===================== ALGORITHMS ===================== */
+
+/// Hash functions that checksum files are published with, weakest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ChecksumAlgorithm {
+    Md5,
+    Sha256,
+    Sha512,
+}
+
+impl ChecksumAlgorithm {
+    /// Every algorithm, in the order their checksum files are looked for.
+    pub const STRONGEST_FIRST: [Self; 3] = [Self::Sha512, Self::Sha256, Self::Md5];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5",
+            Self::Sha256 => "SHA256",
+            Self::Sha512 => "SHA512",
+        }
+    }
+
+    /// The conventional name of the checksum file, e.g. `SHA512SUMS`.
+    pub fn sums_file(self) -> &'static str {
+        match self {
+            Self::Md5 => "MD5SUMS",
+            Self::Sha256 => "SHA256SUMS",
+            Self::Sha512 => "SHA512SUMS",
+        }
+    }
+
+    /// Length of a hex digest.
+    pub fn hex_len(self) -> usize {
+        match self {
+            Self::Md5 => 32,
+            Self::Sha256 => 64,
+            Self::Sha512 => 128,
+        }
+    }
+
+    /// Whether ISOs are verified with it; MD5 is not.
+    pub fn is_trusted(self) -> bool {
+        self != Self::Md5
+    }
+}
+
+impl fmt::Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/* ===================== CHECKSUMS ===================== */
+
+/// A published checksum of an ISO, in lower-case hex.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksum {
+    pub algorithm: ChecksumAlgorithm,
+    pub hex: String,
+}
+
+impl Checksum {
+    /// # Errors
+    /// [`ChecksumError::Untrusted`] for MD5, and [`ChecksumError::Malformed`]
+    /// when `hex` is not a digest of `algorithm`.
+    pub fn new(algorithm: ChecksumAlgorithm, hex: &str) -> Result<Self, ChecksumError> {
+        if !algorithm.is_trusted() {
+            return Err(ChecksumError::Untrusted(algorithm));
+        }
+        if hex.len() != algorithm.hex_len() || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ChecksumError::Malformed(algorithm, hex.to_string()));
+        }
+        Ok(Self {
+            algorithm,
+            hex: hex.to_lowercase(),
+        })
+    }
+
+    /// A SHA256 checksum, such as the one on the download page.
+    pub fn sha256(hex: &str) -> Result<Self, ChecksumError> {
+        Self::new(ChecksumAlgorithm::Sha256, hex)
+    }
+
+    /// Whether `hashes` includes this checksum.
+    pub fn matches(&self, hashes: &Hashes) -> bool {
+        hashes
+            .get(self.algorithm)
+            .is_some_and(|hex| hex.eq_ignore_ascii_case(&self.hex))
+    }
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.algorithm, self.hex)
+    }
+}
+
+/* ===================== HASHING ===================== */
+
+/// Lower-case hex digests of one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hashes {
+    /// Always computed: the cache and the download results identify ISOs by it.
+    pub sha256: String,
+    /// Only computed when a SHA512 checksum is to be checked.
+    pub sha512: Option<String>,
+}
+
+impl Hashes {
+    /// The digest of `algorithm`, when it was computed.
+    pub fn get(&self, algorithm: ChecksumAlgorithm) -> Option<&str> {
+        match algorithm {
+            ChecksumAlgorithm::Md5 => None,
+            ChecksumAlgorithm::Sha256 => Some(&self.sha256),
+            ChecksumAlgorithm::Sha512 => self.sha512.as_deref(),
+        }
+    }
+}
+
+/// Hashes bytes as they pass through with SHA256 and, to check a checksum
+/// of it, SHA512.
+#[derive(Debug, Clone)]
+pub struct Hasher {
+    sha256: Sha256,
+    sha512: Option<Sha512>,
+}
+
+impl Hasher {
+    /// A hasher for checking a checksum of `algorithm`.
+    pub fn new(algorithm: ChecksumAlgorithm) -> Self {
+        Self {
+            sha256: Sha256::new(),
+            sha512: (algorithm == ChecksumAlgorithm::Sha512).then(Sha512::new),
+        }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.sha256.update(bytes);
+        if let Some(sha512) = &mut self.sha512 {
+            sha512.update(bytes);
+        }
+    }
+
+    pub fn finish(self) -> Hashes {
+        Hashes {
+            sha256: hex(&self.sha256.finalize()),
+            sha512: self.sha512.map(|sha512| hex(&sha512.finalize())),
+        }
+    }
+}
+
+impl io::Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Hashes the file at `path` for checking a checksum of `algorithm`.
+pub fn hash_file(path: &Path, algorithm: ChecksumAlgorithm) -> io::Result<Hashes> {
+    let mut hasher = Hasher::new(algorithm);
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finish())
+}
+
+/// Writes `sha256`, the checksum of the file at `path`, to `<path>.sha256` in
+/// the format of `sha256sum`, so that `sha256sum -c` checks the file from the
+/// directory it is in, without `pveauto`.
+///
+/// # Returns
+/// The path of the checksum file.
+pub fn write_sha256_sidecar(path: &Path, sha256: &str) -> io::Result<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file path"))?;
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(SHA256_SIDECAR_SUFFIX);
+    let sidecar = PathBuf::from(sidecar);
+    std::fs::write(
+        &sidecar,
+        format!("{}  {}\n", sha256.to_lowercase(), name.to_string_lossy()),
+    )?;
+    Ok(sidecar)
+}
+
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn md5_and_malformed_checksums_are_rejected() {
+        let sha512 = Checksum::new(ChecksumAlgorithm::Sha512, &"AB".repeat(64)).unwrap();
+        assert_eq!(sha512.hex, "ab".repeat(64));
+        assert_eq!(
+            Checksum::new(ChecksumAlgorithm::Md5, &"0".repeat(32))
+                .unwrap_err()
+                .to_string(),
+            "iso.checksum.untrusted (only MD5SUMS is published; MD5 collisions are cheap to forge, publish SHA256SUMS or SHA512SUMS)"
+        );
+        for malformed in ["a".repeat(64), "g".repeat(128), String::new()] {
+            assert_eq!(
+                Checksum::new(ChecksumAlgorithm::Sha512, &malformed),
+                Err(ChecksumError::Malformed(
+                    ChecksumAlgorithm::Sha512,
+                    malformed
+                ))
+            );
+        }
+        assert!(ChecksumAlgorithm::STRONGEST_FIRST.is_sorted_by(|a, b| a > b));
+    }
+
+    #[test]
+    fn sha512_is_only_computed_when_checked() {
+        let path =
+            std::env::temp_dir().join(format!("pveauto-checksum-{}.iso", std::process::id()));
+        std::fs::write(&path, b"not really an iso").unwrap();
+
+        let hashes = hash_file(&path, ChecksumAlgorithm::Sha256).unwrap();
+        assert_eq!(
+            hashes.sha256,
+            format!("{:x}", Sha256::digest(b"not really an iso"))
+        );
+        assert_eq!(hashes.sha512, None);
+
+        let hashes = hash_file(&path, ChecksumAlgorithm::Sha512).unwrap();
+        let sha512 = format!("{:x}", Sha512::digest(b"not really an iso"));
+        assert!(
+            Checksum::new(ChecksumAlgorithm::Sha512, &sha512)
+                .unwrap()
+                .matches(&hashes)
+        );
+        assert!(Checksum::sha256(&hashes.sha256).unwrap().matches(&hashes));
+        assert!(
+            !Checksum::new(ChecksumAlgorithm::Sha512, &"0".repeat(128))
+                .unwrap()
+                .matches(&hashes)
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn sidecars_use_the_sha256sum_format() {
+        let dir = std::env::temp_dir().join(format!("pveauto-sidecar-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let iso = dir.join("proxmox-ve_8.2-1.iso");
+        let sidecar = write_sha256_sidecar(&iso, &"AB".repeat(32)).unwrap();
+        assert_eq!(sidecar, dir.join("proxmox-ve_8.2-1.iso.sha256"));
+        assert_eq!(
+            std::fs::read_to_string(&sidecar).unwrap(),
+            format!("{}  proxmox-ve_8.2-1.iso\n", "ab".repeat(32))
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
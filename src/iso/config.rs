@@ -0,0 +1,477 @@
+use crate::iso::{
+    cache::DEFAULT_KEEP_VERSIONS, mirrors::DEFAULT_BENCHMARK_TTL, retry::RetryPolicy,
+    scraper::DownloadPage,
+};
+use regex::Regex;
+use reqwest::Url;
+use serde::Deserialize;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Environment variable naming the configuration file to use instead of the default.
+pub const CONFIG_ENV: &str = "PVEAUTO_CONFIG";
+
+/// Environment variable overriding the mirror of the configuration file.
+pub const MIRROR_ENV: &str = "PVEAUTO_MIRROR";
+
+/// Environment variable overriding the download page URL of the configuration file.
+pub const DOWNLOAD_PAGE_ENV: &str = "PVEAUTO_DOWNLOAD_PAGE";
+
+/// Environment variable overriding the ISO URL pattern of the configuration file.
+pub const ISO_URL_PATTERN_ENV: &str = "PVEAUTO_ISO_URL_PATTERN";
+
+/* ===================== CONFIG ERROR ===================== */
+
+/// Failure while loading the configuration or resolving a setting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The configuration file could not be read; holds the path and the I/O error.
+    Io(PathBuf, String),
+    /// The configuration file is not valid TOML for [`Config`].
+    Parse(PathBuf, String),
+    /// A mirror is not an absolute `http` or `https` URL.
+    InvalidMirror(String),
+    /// The download page is not an absolute `http` or `https` URL.
+    InvalidPageUrl(String),
+    /// The ISO URL pattern is not a valid regex; holds the pattern and the
+    /// regex error.
+    InvalidPattern(String, String),
+}
+
+impl ConfigError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io(..) => "config.io",
+            Self::Parse(..) => "config.parse",
+            Self::InvalidMirror(_) => "config.invalid_mirror",
+            Self::InvalidPageUrl(_) => "config.invalid_page_url",
+            Self::InvalidPattern(..) => "config.invalid_pattern",
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, detail) | Self::Parse(path, detail) => {
+                write!(f, "{} ({}: {})", self.code(), path.display(), detail)
+            }
+            Self::InvalidMirror(url) | Self::InvalidPageUrl(url) => {
+                write!(f, "{} ({})", self.code(), url)
+            }
+            Self::InvalidPattern(pattern, detail) => {
+                write!(f, "{} ({}: {})", self.code(), pattern, detail)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/* ===================== CONFIG ===================== */
+
+/// Settings of `config.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub download: DownloadConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+}
+
+/// The `[download]` table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct DownloadConfig {
+    /// Base URL of a mirror of `https://enterprise.proxmox.com/iso/`.
+    pub mirror: Option<String>,
+    /// Further mirrors; with more than one, the fastest is picked for each
+    /// download.
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+    /// How long a mirror benchmark is reused, in seconds.
+    pub benchmark_ttl_secs: Option<u64>,
+    /// The download page the latest release is scraped from.
+    pub page_url: Option<String>,
+    /// The regex the ISO URL scraped from the download page must match.
+    pub iso_url_pattern: Option<String>,
+    #[serde(default)]
+    pub retry: RetryConfig,
+}
+
+/// The `[cache]` table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct CacheConfig {
+    /// Newest ISO versions `iso prune` keeps.
+    pub keep_versions: Option<usize>,
+}
+
+/// The `[download.retry]` table; unset keys keep the [`RetryPolicy`] defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RetryConfig {
+    /// Tries in total per request or download; `1` disables retrying.
+    pub attempts: Option<u32>,
+    /// Wait before the first retry in milliseconds, doubled for each further one.
+    pub base_delay_ms: Option<u64>,
+    /// Upper bound of a random extra wait in milliseconds.
+    pub jitter_ms: Option<u64>,
+}
+
+impl RetryConfig {
+    pub fn policy(&self) -> RetryPolicy {
+        let default = RetryPolicy::default();
+        RetryPolicy {
+            attempts: self.attempts.unwrap_or(default.attempts).max(1),
+            base_delay: self
+                .base_delay_ms
+                .map_or(default.base_delay, Duration::from_millis),
+            jitter: self.jitter_ms.map_or(default.jitter, Duration::from_millis),
+        }
+    }
+}
+
+impl Config {
+    /// Returns the user configuration file (XDG compliant):
+    /// `$XDG_CONFIG_HOME/pve-auto/config.toml`, falling back to
+    /// `~/.config/pve-auto/config.toml`.
+    pub fn default_path() -> PathBuf {
+        if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
+            PathBuf::from(dir).join("pve-auto/config.toml")
+        } else if let Ok(home) = std::env::var("HOME") {
+            PathBuf::from(home).join(".config/pve-auto/config.toml")
+        } else {
+            PathBuf::from("pve-auto-config.toml")
+        }
+    }
+
+    /// Loads the file named by `$PVEAUTO_CONFIG`, or the default file.
+    ///
+    /// # Returns
+    /// The default settings when `$PVEAUTO_CONFIG` is unset and the default
+    /// file does not exist.
+    pub fn load() -> Result<Self, ConfigError> {
+        match std::env::var_os(CONFIG_ENV) {
+            Some(path) => Self::from_path(Path::new(&path)),
+            None => {
+                let path = Self::default_path();
+                if path.exists() {
+                    Self::from_path(&path)
+                } else {
+                    Ok(Self::default())
+                }
+            }
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::Io(path.to_path_buf(), e.to_string()))?;
+        toml::from_str(&contents).map_err(|e| ConfigError::Parse(path.to_path_buf(), e.to_string()))
+    }
+
+    /// The mirror to download from: `flag` (`--mirror`), else `env`
+    /// (`$PVEAUTO_MIRROR`), else `download.mirror`, else the first of
+    /// `download.mirrors`.
+    ///
+    /// # Returns
+    /// `None` when no mirror is set, otherwise the URL with a trailing `/`.
+    pub fn mirror(
+        &self,
+        flag: Option<&str>,
+        env: Option<&str>,
+    ) -> Result<Option<String>, ConfigError> {
+        flag.or(env)
+            .or(self.download.mirror.as_deref())
+            .or(self.download.mirrors.first().map(String::as_str))
+            .map(normalize_mirror)
+            .transpose()
+    }
+
+    /// The mirrors a download picks the fastest of: only `flag` or `env` when
+    /// set, as for [`Config::mirror`], otherwise `download.mirror` and
+    /// `download.mirrors`, without duplicates.
+    ///
+    /// # Returns
+    /// The URLs with a trailing `/`; empty when no mirror is set.
+    pub fn mirrors(
+        &self,
+        flag: Option<&str>,
+        env: Option<&str>,
+    ) -> Result<Vec<String>, ConfigError> {
+        if let Some(mirror) = flag.or(env) {
+            return Ok(vec![normalize_mirror(mirror)?]);
+        }
+        let mut mirrors = Vec::new();
+        for mirror in self.download.mirror.iter().chain(&self.download.mirrors) {
+            let mirror = normalize_mirror(mirror)?;
+            if !mirrors.contains(&mirror) {
+                mirrors.push(mirror);
+            }
+        }
+        Ok(mirrors)
+    }
+
+    /// How long a mirror benchmark is reused: `download.benchmark-ttl-secs`,
+    /// else [`DEFAULT_BENCHMARK_TTL`].
+    pub fn benchmark_ttl(&self) -> Duration {
+        self.download
+            .benchmark_ttl_secs
+            .map_or(DEFAULT_BENCHMARK_TTL, Duration::from_secs)
+    }
+
+    /// The download page to scrape: `page_env` (`$PVEAUTO_DOWNLOAD_PAGE`),
+    /// else `download.page-url`, with `pattern_env`
+    /// (`$PVEAUTO_ISO_URL_PATTERN`), else `download.iso-url-pattern`; each
+    /// defaults to [`DownloadPage::default`].
+    pub fn download_page(
+        &self,
+        page_env: Option<&str>,
+        pattern_env: Option<&str>,
+    ) -> Result<DownloadPage, ConfigError> {
+        let default = DownloadPage::default();
+        let url = match page_env.or(self.download.page_url.as_deref()) {
+            Some(url) => http_url(url)
+                .ok_or_else(|| ConfigError::InvalidPageUrl(url.to_string()))?
+                .to_string(),
+            None => default.url,
+        };
+        let iso_url_pattern = match pattern_env.or(self.download.iso_url_pattern.as_deref()) {
+            Some(pattern) => {
+                Regex::new(pattern)
+                    .map_err(|e| ConfigError::InvalidPattern(pattern.to_string(), e.to_string()))?;
+                pattern.to_string()
+            }
+            None => default.iso_url_pattern,
+        };
+        Ok(DownloadPage {
+            url,
+            iso_url_pattern,
+        })
+    }
+}
+
+/// Resolves the mirror of `flag`, `$PVEAUTO_MIRROR` and the configuration file.
+pub fn resolve_mirror(flag: Option<&str>) -> Result<Option<String>, ConfigError> {
+    let env = std::env::var(MIRROR_ENV).ok().filter(|v| !v.is_empty());
+    if flag.is_some() || env.is_some() {
+        return Config::default().mirror(flag, env.as_deref());
+    }
+    Config::load()?.mirror(None, None)
+}
+
+/// Resolves the mirrors of `flag`, `$PVEAUTO_MIRROR` and the configuration
+/// file, and how long their benchmark is reused.
+pub fn resolve_mirrors(flag: Option<&str>) -> Result<(Vec<String>, Duration), ConfigError> {
+    let env = std::env::var(MIRROR_ENV).ok().filter(|v| !v.is_empty());
+    let config = Config::load()?;
+    Ok((
+        config.mirrors(flag, env.as_deref())?,
+        config.benchmark_ttl(),
+    ))
+}
+
+/// Resolves the download page of `$PVEAUTO_DOWNLOAD_PAGE`,
+/// `$PVEAUTO_ISO_URL_PATTERN` and the configuration file.
+pub fn resolve_download_page() -> Result<DownloadPage, ConfigError> {
+    let env = |name| std::env::var(name).ok().filter(|v| !v.is_empty());
+    let (page, pattern) = (env(DOWNLOAD_PAGE_ENV), env(ISO_URL_PATTERN_ENV));
+    let config = match (&page, &pattern) {
+        (Some(_), Some(_)) => Config::default(),
+        _ => Config::load()?,
+    };
+    config.download_page(page.as_deref(), pattern.as_deref())
+}
+
+/// The versions `iso prune` keeps: `flag` (`--keep`), else
+/// `cache.keep-versions`, else [`DEFAULT_KEEP_VERSIONS`].
+pub fn resolve_keep_versions(flag: Option<usize>) -> Result<usize, ConfigError> {
+    match flag {
+        Some(keep) => Ok(keep),
+        None => Ok(Config::load()?
+            .cache
+            .keep_versions
+            .unwrap_or(DEFAULT_KEEP_VERSIONS)),
+    }
+}
+
+/// The retry policy of the configuration file, or the default one.
+pub fn resolve_retry() -> Result<RetryPolicy, ConfigError> {
+    Ok(Config::load()?.download.retry.policy())
+}
+
+/// Checks that `mirror` is an `http(s)` URL and appends the `/` that file
+/// names are joined to.
+pub fn normalize_mirror(mirror: &str) -> Result<String, ConfigError> {
+    let url = http_url(mirror)
+        .filter(|u| u.query().is_none() && u.fragment().is_none())
+        .ok_or_else(|| ConfigError::InvalidMirror(mirror.to_string()))?;
+    let url = url.as_str();
+    Ok(if url.ends_with('/') {
+        url.to_string()
+    } else {
+        format!("{}/", url)
+    })
+}
+
+/// `url` when it is an absolute `http(s)` URL with a host.
+fn http_url(url: &str) -> Option<Url> {
+    Url::parse(url)
+        .ok()
+        .filter(|u| matches!(u.scheme(), "http" | "https") && u.host().is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mirror_precedence_is_flag_env_file() {
+        let config: Config =
+            toml::from_str("[download]\nmirror = \"http://file.lan/pve\"\n").unwrap();
+        assert_eq!(
+            config.mirror(Some("http://flag.lan"), Some("http://env.lan")),
+            Ok(Some("http://flag.lan/".into()))
+        );
+        assert_eq!(
+            config.mirror(None, Some("http://env.lan/iso/")),
+            Ok(Some("http://env.lan/iso/".into()))
+        );
+        assert_eq!(
+            config.mirror(None, None),
+            Ok(Some("http://file.lan/pve/".into()))
+        );
+        assert_eq!(Config::default().mirror(None, None), Ok(None));
+    }
+
+    #[test]
+    fn every_configured_mirror_is_a_candidate() {
+        let config: Config = toml::from_str(
+            "[download]\nmirror = \"http://a.lan/iso\"\n\
+             mirrors = [\"http://b.lan/iso/\", \"http://a.lan/iso/\"]\n\
+             benchmark-ttl-secs = 60\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.mirrors(None, None),
+            Ok(vec!["http://a.lan/iso/".into(), "http://b.lan/iso/".into()])
+        );
+        assert_eq!(
+            config.mirrors(None, Some("http://env.lan")),
+            Ok(vec!["http://env.lan/".into()])
+        );
+        assert_eq!(config.benchmark_ttl(), Duration::from_secs(60));
+        assert_eq!(Config::default().mirrors(None, None), Ok(Vec::new()));
+        assert_eq!(Config::default().benchmark_ttl(), DEFAULT_BENCHMARK_TTL);
+
+        let listed_only: Config =
+            toml::from_str("[download]\nmirrors = [\"http://b.lan/iso\", \"ftp://c.lan\"]\n")
+                .unwrap();
+        assert_eq!(
+            listed_only.mirror(None, None),
+            Ok(Some("http://b.lan/iso/".into()))
+        );
+        assert_eq!(
+            listed_only.mirrors(None, None),
+            Err(ConfigError::InvalidMirror("ftp://c.lan".into()))
+        );
+    }
+
+    #[test]
+    fn the_download_page_can_be_overridden() {
+        assert_eq!(
+            Config::default().download_page(None, None),
+            Ok(DownloadPage::default())
+        );
+
+        let config: Config = toml::from_str(
+            "[download]\npage-url = \"https://staging.lan/downloads\"\n\
+             iso-url-pattern = '^https://staging\\.lan/iso/.*\\.iso$'\n",
+        )
+        .unwrap();
+        let page = config.download_page(None, None).unwrap();
+        assert_eq!(page.url, "https://staging.lan/downloads");
+        assert_eq!(page.iso_url_pattern, r"^https://staging\.lan/iso/.*\.iso$");
+        let page = config
+            .download_page(Some("http://env.lan/pve"), Some("^http://env"))
+            .unwrap();
+        assert_eq!(
+            (page.url.as_str(), page.iso_url_pattern.as_str()),
+            ("http://env.lan/pve", "^http://env")
+        );
+
+        assert_eq!(
+            config.download_page(Some("file:///srv/page.html"), None),
+            Err(ConfigError::InvalidPageUrl("file:///srv/page.html".into()))
+        );
+        assert_eq!(
+            config
+                .download_page(None, Some("proxmox-ve_(["))
+                .unwrap_err()
+                .code(),
+            "config.invalid_pattern"
+        );
+    }
+
+    #[test]
+    fn invalid_mirrors_are_rejected() {
+        for mirror in [
+            "ftp://mirror.lan/iso",
+            "mirror.lan/iso",
+            "http://m.lan/?a=b",
+            "",
+        ] {
+            assert_eq!(
+                normalize_mirror(mirror),
+                Err(ConfigError::InvalidMirror(mirror.into())),
+                "{}",
+                mirror
+            );
+        }
+    }
+
+    #[test]
+    fn config_files_are_parsed_strictly() {
+        let dir = std::env::temp_dir().join(format!("pveauto-config-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+
+        std::fs::write(
+            &path,
+            "[download]\nmirror = \"https://mirror.lan/proxmox/iso\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            Config::from_path(&path).unwrap().download.mirror.as_deref(),
+            Some("https://mirror.lan/proxmox/iso")
+        );
+
+        std::fs::write(
+            &path,
+            "[download.retry]\nattempts = 0\nbase-delay-ms = 250\n[cache]\nkeep-versions = 1\n",
+        )
+        .unwrap();
+        let config = Config::from_path(&path).unwrap();
+        assert_eq!(config.cache.keep_versions, Some(1));
+        let retry = config.download.retry.policy();
+        assert_eq!(retry.attempts, 1);
+        assert_eq!(retry.base_delay, Duration::from_millis(250));
+        assert_eq!(retry.jitter, RetryPolicy::default().jitter);
+
+        std::fs::write(&path, "[download]\nmirror-list = []\n").unwrap();
+        assert_eq!(Config::from_path(&path).unwrap_err().code(), "config.parse");
+        assert_eq!(
+            Config::from_path(&dir.join("missing.toml"))
+                .unwrap_err()
+                .code(),
+            "config.io"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
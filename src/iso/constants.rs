@@ -4,3 +4,98 @@ pub const PROX_DL_PG_URL: &str = "https://www.proxmox.com/en/downloads/proxmox-v
 /// Regex pattern to match Proxmox VE ISO download URLs.   
 pub const ISO_URL_REGEX_PATTERN: &str =
     r#"^https://enterprise\.proxmox\.com/iso/proxmox-ve_[\d\.]+-.*\.iso$"#;
+
+/// Directory listing of every Proxmox ISO release.
+pub const ISO_INDEX_URL: &str = "https://enterprise.proxmox.com/iso/";
+
+/// Regex pattern to match Proxmox VE ISO file names, capturing the version
+/// (e.g. `8.2-1`, or `9.0-BETA-1` for a test ISO).
+pub const ISO_FILE_NAME_REGEX_PATTERN: &str = r"^proxmox-ve_(\d+\.\d+-(?:BETA-)?\d+)\.iso$";
+
+/// Appended to an ISO's path for the `sha256sum`-style checksum file written
+/// next to it, e.g. `proxmox-ve-latest.iso.sha256`.
+pub const SHA256_SIDECAR_SUFFIX: &str = ".sha256";
+
+/// Appended to a built ISO's path for its detached, ASCII-armored OpenPGP
+/// signature, e.g. `proxmox-ve-autoinstall.iso.asc`.
+pub const ISO_SIGNATURE_SUFFIX: &str = ".asc";
+
+/// Appended to a built ISO's path for the manifest recording how it was
+/// built, e.g. `proxmox-ve-autoinstall.iso.manifest.json`.
+pub const BUILD_MANIFEST_SUFFIX: &str = ".manifest.json";
+
+/// Appended to an ISO's path while it is downloading, e.g. `proxmox-ve-latest.iso.part`.
+pub const PARTIAL_DOWNLOAD_SUFFIX: &str = ".part";
+
+/// Joins a download that failed its checksum and the UTC time it was set
+/// aside, e.g. `proxmox-ve_8.2-1.iso.part.corrupt-20240424T101500Z`.
+pub const QUARANTINE_INFIX: &str = ".corrupt-";
+
+/// Appended to a cached ISO's file name for its metadata, e.g. `proxmox-ve_8.2-1.iso.toml`.
+pub const ISO_CACHE_METADATA_SUFFIX: &str = ".toml";
+
+/// File name of the latest test ISO written by `download --channel test`,
+/// next to `proxmox-ve-latest.iso`.
+pub const TEST_ISO_FILE_NAME: &str = "proxmox-ve-test.iso";
+
+/// File name of the prepared ISO written by `offline-installer`.
+pub const AUTOINSTALL_ISO_FILE_NAME: &str = "proxmox-ve-autoinstall.iso";
+
+/// File name template of each host's ISO written by
+/// `offline-installer --inventory`.
+pub const INVENTORY_ISO_FILE_NAME: &str = "{name}.iso";
+
+/// Installer settings on the ISO root selecting where the answer is fetched from.
+pub const AUTO_INSTALLER_MODE_FILE_NAME: &str = "auto-installer-mode.toml";
+
+/// Answer file on the ISO root, read when the mode is `iso`.
+pub const ISO_ANSWER_FILE_NAME: &str = "answer.toml";
+
+/// Archive of post-install assets on the ISO root, unpacked by the generated
+/// first-boot script.
+pub const ISO_ASSETS_FILE_NAME: &str = "pveauto-assets.tar";
+
+/// Manifest of an air-gap bundle, its first entry, listing the SHA256 of every
+/// other file.
+pub const BUNDLE_MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// The keyring an air-gap bundle's checksum file was verified with at export.
+pub const BUNDLE_KEYRING_FILE_NAME: &str = "release-keyring.gpg";
+
+/// GRUB configuration of Proxmox ISOs, for both BIOS and UEFI boot.
+pub const ISO_GRUB_CONFIG_PATH: &str = "boot/grub/grub.cfg";
+
+/// Kernel argument of the boot entry that starts the automated installer.
+pub const AUTO_INSTALLER_KERNEL_ARG: &str = "proxmox-start-auto-installer";
+
+/// Release information on the root of Proxmox ISOs (`RELEASE='8.2'` etc.).
+pub const ISO_CD_INFO_FILE_NAME: &str = ".cd-info";
+
+/// First-boot script on the ISO root, for `first-boot.source = "from-iso"`.
+pub const ISO_FIRST_BOOT_FILE_NAME: &str = "proxmox-first-boot";
+
+/// Package pool of Proxmox ISOs; the installer unpacks every `*.deb` in it
+/// into the new system.
+pub const ISO_PACKAGE_POOL_DIR: &str = "proxmox/packages";
+
+/// File name of the prepared ISO written by `network-installer`.
+pub const NETWORK_AUTOINSTALL_ISO_FILE_NAME: &str = "proxmox-ve-autoinstall-network.iso";
+
+/// SHA256 checksums of every ISO published on enterprise.proxmox.com, signed
+/// in `SHA256SUMS.asc`.
+pub const SHA256SUMS_URL: &str = "https://enterprise.proxmox.com/iso/SHA256SUMS";
+
+/// The Proxmox release keyrings that sign the checksum files under
+/// [`ISO_INDEX_URL`], each with the fingerprint of its release key: Bookworm
+/// for Proxmox VE 8, Trixie for Proxmox VE 9. A downloaded keyring is only
+/// trusted for the keys among these fingerprints.
+pub const PROXMOX_RELEASE_KEYS: &[(&str, &str)] = &[
+    (
+        "https://enterprise.proxmox.com/debian/proxmox-release-bookworm.gpg",
+        "F4E136C67CDCE41AE6DE6FC81140AF8F639E0C39",
+    ),
+    (
+        "https://enterprise.proxmox.com/debian/proxmox-archive-keyring-trixie.gpg",
+        "24B30F06ECC1836A4E5EFECBA7BCD1420BFE778E",
+    ),
+];
@@ -1,62 +1,1637 @@
-use crate::iso::scraper::get_latest_iso_info;
-use oxdl::{download_with_updates, validator::verify_file_sha256};
+use crate::iso::{
+    cache::{CacheEntry, IsoCache, link_or_copy, quarantine, same_file},
+    channel::IsoChannel,
+    checksum::{Checksum, ChecksumAlgorithm, Hasher, Hashes, hash_file},
+    constants::{ISO_INDEX_URL, PARTIAL_DOWNLOAD_SUFFIX},
+    http::HttpFetcher,
+    scraper::{
+        DownloadPage, IsoRelease, Page, fetch_etag, fetch_page, get_iso_info, iso_file_name,
+        parse_iso_versions, parse_latest_iso_info,
+    },
+    space::{ensure_space, ensure_writable},
+};
+use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::{
+    StatusCode,
+    header::{CONTENT_RANGE, HeaderMap, HeaderValue, RANGE},
+};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Read, Write},
+    path::Path,
+};
 
+/// Downloads the latest Proxmox VE ISO into `cache` and places it at
+/// `to_file_path`, checked against the checksum scraped from the download
+/// `page`, usually [`DownloadPage::default`]. All requests are sent with
+/// `http`, usually a [`reqwest::Client`].
+///
+/// The page is requested with the `ETag` recorded by the last run; while it
+/// is unchanged and the cached ISO is intact, neither scraping nor hashing
+/// is repeated. When the page cannot be fetched or scraped, the newest ISO
+/// in the directory listing at [`ISO_INDEX_URL`] is downloaded instead.
+///
+/// Progress is shown as a bar on standard error and status lines are
+/// printed to standard output, unless `quiet`.
+///
+/// # Returns
+/// The path, the SHA256 checksum and whether anything was downloaded
+/// (`false` when a valid file was already there).
 pub async fn download_latest_iso(
+    http: &impl HttpFetcher,
+    cache: &IsoCache,
+    page: &DownloadPage,
     to_file_path: &str,
-    with_custom_updater: Option<Box<dyn Fn(f32) + Send + Sync>>,
+    quiet: bool,
 ) -> Result<(String, String, bool), Box<dyn std::error::Error>> {
-    let (iso_url, sha256_checksum) = get_latest_iso_info().await?;
-
-    // check if the file already exists has size and valid checksum
-    if std::path::Path::new(to_file_path).exists() {
-        println!("Existing file found. Verifying checksum...");
-        let is_valid = verify_file_sha256(to_file_path, &sha256_checksum).await?;
-        if is_valid {
-            println!("Checksum valid. Skipping download.");
-            return Ok((to_file_path.to_string(), sha256_checksum, false));
-        } else {
-            println!("Checksum mismatch. Re-downloading...");
+    let scraped = match fetch_latest(http, cache, &page.url, &page.url, quiet).await {
+        Ok(Latest::Cached(entry)) => return place_cached(cache, &entry, to_file_path, quiet),
+        Ok(Latest::Changed(latest)) => {
+            parse_latest_iso_info(&latest.body, page).map(|release| (release, latest.etag))
         }
-    } else {
-        println!("No existing file found. Proceeding to download...");
+        Err(e) => Err(e),
+    };
+    let (
+        IsoRelease {
+            version,
+            url: iso_url,
+            checksum,
+            ..
+        },
+        etag,
+    ) = match scraped {
+        Ok(scraped) => scraped,
+        Err(e) => {
+            status(
+                quiet,
+                &format!(
+                    "Could not read the latest ISO from {} ({}). Falling back to {}.",
+                    page.url, e, ISO_INDEX_URL
+                ),
+            );
+            let (_, path, sha256_checksum, downloaded) = download_newest_listed_iso(
+                http,
+                cache,
+                ISO_INDEX_URL,
+                IsoChannel::Stable,
+                to_file_path,
+                quiet,
+            )
+            .await?;
+            return Ok((path, sha256_checksum, downloaded));
+        }
+    };
+    let result = download_to_cache(
+        http,
+        cache,
+        &version,
+        &iso_url,
+        checksum,
+        to_file_path,
+        quiet,
+    )
+    .await?;
+    if let Some(etag) = &etag {
+        cache.record_latest(&page.url, &version, etag)?;
     }
+    Ok(result)
+}
 
-    let res: Result<(), oxdl::DownloadError> = download_with_updates(
+/// Downloads the Proxmox VE ISO of `version` (e.g. `8.2-1`) from `index_url`,
+/// [`ISO_INDEX_URL`](crate::iso::constants::ISO_INDEX_URL) or a mirror of it,
+/// like [`download_latest_iso`] but checked against the strongest checksum
+/// published next to it, from SHA512SUMS or else SHA256SUMS.
+///
+/// A cached ISO whose `ETag` upstream is unchanged is used without fetching
+/// the checksums or hashing it.
+pub async fn download_iso_version(
+    http: &impl HttpFetcher,
+    cache: &IsoCache,
+    index_url: &str,
+    version: &str,
+    to_file_path: &str,
+    quiet: bool,
+) -> Result<(String, String, bool), Box<dyn std::error::Error>> {
+    let iso_url = format!("{}{}", index_url, iso_file_name(version));
+    if let Some(entry) = cache
+        .intact_entry(version)
+        .filter(|e| e.url == iso_url && e.etag.is_some())
+        && fetch_etag(http, &iso_url).await.ok().flatten() == entry.etag
+    {
+        status(
+            quiet,
+            &format!(
+                "Proxmox VE {} is unchanged upstream. Using the cached ISO.",
+                version
+            ),
+        );
+        return place_cached(cache, &entry, to_file_path, quiet);
+    }
+    let IsoRelease {
+        url: iso_url,
+        checksum,
+        ..
+    } = get_iso_info(http, index_url, version).await?;
+    download_to_cache(
+        http,
+        cache,
+        version,
         &iso_url,
+        checksum,
         to_file_path,
-        with_custom_updater,
-        Some(&sha256_checksum),
+        quiet,
     )
-    .await;
-    match res {
-        Ok(_) => Ok((to_file_path.to_string(), sha256_checksum, true)),
-        Err(e) => Err(Box::new(e)),
+    .await
+}
+
+/// Downloads the newest Proxmox VE ISO of `channel` listed at `index_url`, a
+/// mirror or, for test ISOs, [`ISO_INDEX_URL`], skipping the lookup while the
+/// listing's `ETag` is unchanged.
+///
+/// # Returns
+/// Like [`download_latest_iso`], with the downloaded version first.
+pub async fn download_newest_listed_iso(
+    http: &impl HttpFetcher,
+    cache: &IsoCache,
+    index_url: &str,
+    channel: IsoChannel,
+    to_file_path: &str,
+    quiet: bool,
+) -> Result<(String, String, String, bool), Box<dyn std::error::Error>> {
+    let key = channel.listing_key(index_url);
+    let page = match fetch_latest(http, cache, index_url, &key, quiet).await? {
+        Latest::Cached(entry) => {
+            let (path, sha256_checksum, downloaded) =
+                place_cached(cache, &entry, to_file_path, quiet)?;
+            return Ok((entry.version, path, sha256_checksum, downloaded));
+        }
+        Latest::Changed(page) => page,
+    };
+    let version = parse_iso_versions(&page.body, channel)?
+        .pop()
+        .ok_or_else(|| channel.nothing_listed())?;
+    let IsoRelease {
+        url: iso_url,
+        checksum,
+        ..
+    } = get_iso_info(http, index_url, &version).await?;
+    let (path, sha256_checksum, downloaded) = download_to_cache(
+        http,
+        cache,
+        &version,
+        &iso_url,
+        checksum,
+        to_file_path,
+        quiet,
+    )
+    .await?;
+    if let Some(etag) = &page.etag {
+        cache.record_latest(&key, &version, etag)?;
+    }
+    Ok((version, path, sha256_checksum, downloaded))
+}
+
+/// The latest release of `source`, the download page or a mirror listing.
+enum Latest {
+    /// `source` still has the `ETag` recorded with this intact cached ISO.
+    Cached(CacheEntry),
+    /// `source` changed, or nothing usable was cached.
+    Changed(Page),
+}
+
+/// Fetches `source` unless it still has the `ETag` recorded in `cache` under
+/// `key`.
+async fn fetch_latest(
+    http: &impl HttpFetcher,
+    cache: &IsoCache,
+    source: &str,
+    key: &str,
+    quiet: bool,
+) -> Result<Latest, Box<dyn std::error::Error>> {
+    let cached = cache
+        .latest(key)
+        .and_then(|latest| Some((cache.intact_entry(&latest.version)?, latest)));
+    let etag = cached.as_ref().map(|(_, latest)| latest.etag.as_str());
+    match (fetch_page(http, source, etag).await?, cached) {
+        (Some(page), _) => Ok(Latest::Changed(page)),
+        (None, Some((entry, latest))) => {
+            status(
+                quiet,
+                &format!(
+                    "{} is unchanged since {}. Using the cached Proxmox VE {}.",
+                    source, latest.checked, entry.version
+                ),
+            );
+            Ok(Latest::Cached(entry))
+        }
+        (None, None) => Err("Unexpected 304 Not Modified".into()),
+    }
+}
+
+/// Makes the ISO of `version` at `iso_url` available in `cache`, then places
+/// it at `to_file_path`.
+///
+/// An intact cached ISO with the same checksum is used without hashing it
+/// again; a valid file already at `to_file_path` is adopted into the cache
+/// instead of being downloaded.
+async fn download_to_cache(
+    http: &impl HttpFetcher,
+    cache: &IsoCache,
+    version: &str,
+    iso_url: &str,
+    checksum: Checksum,
+    to_file_path: &str,
+    quiet: bool,
+) -> Result<(String, String, bool), Box<dyn std::error::Error>> {
+    let (entry, downloaded) = match cached_release(cache, version, &checksum) {
+        Some(entry) => {
+            status(
+                quiet,
+                &format!(
+                    "Proxmox VE {} found in the cache. Skipping download.",
+                    version
+                ),
+            );
+            (entry, false)
+        }
+        None => {
+            let iso = cache.iso_path(version);
+            let dest = Path::new(to_file_path);
+            let adopted = if !iso.exists() && dest.exists() {
+                status(quiet, "Existing file found. Verifying checksum...");
+                Some(hash_file(dest, checksum.algorithm)?).filter(|h| checksum.matches(h))
+            } else {
+                None
+            };
+            let (hashes, downloaded) = match adopted {
+                Some(hashes) => {
+                    status(quiet, "Checksum valid. Adding it to the cache.");
+                    link_or_copy(dest, &iso)?;
+                    (hashes, false)
+                }
+                None => {
+                    let iso = iso.to_string_lossy();
+                    download_iso_from(http, iso_url, &checksum, &iso, quiet).await?
+                }
+            };
+            let etag = fetch_etag(http, iso_url).await.ok().flatten();
+            let entry = cache.record(version, iso_url, &hashes, etag)?;
+            (entry, downloaded)
+        }
+    };
+    let (path, sha256_checksum, _) = place_cached(cache, &entry, to_file_path, quiet)?;
+    Ok((path, sha256_checksum, downloaded))
+}
+
+/// The intact cached ISO of `version` when it has `checksum`.
+fn cached_release(cache: &IsoCache, version: &str, checksum: &Checksum) -> Option<CacheEntry> {
+    cache.intact_entry(version).filter(|e| {
+        e.digest(checksum.algorithm)
+            .is_some_and(|hex| hex.eq_ignore_ascii_case(&checksum.hex))
+    })
+}
+
+/// Places the cached ISO of `entry` at `to_file_path`, as a hard link where
+/// possible. A file already there is left alone, without hashing it, when it
+/// is a link of the cached ISO or a copy with its recorded size and
+/// modification time.
+fn place_cached(
+    cache: &IsoCache,
+    entry: &CacheEntry,
+    to_file_path: &str,
+    quiet: bool,
+) -> Result<(String, String, bool), Box<dyn std::error::Error>> {
+    let iso = cache.iso_path(&entry.version);
+    let dest = Path::new(to_file_path);
+    if same_file(&iso, dest) || entry.matches_file(dest) {
+        status(
+            quiet,
+            &format!(
+                "{} is already up to date (Proxmox VE {}).",
+                to_file_path, entry.version
+            ),
+        );
+    } else {
+        ensure_writable(dest, Some(entry.size))?;
+        link_or_copy(&iso, dest)?;
     }
+    Ok((to_file_path.to_string(), entry.sha256.clone(), false))
+}
+
+/// Downloads `iso_url` to `to_file_path` unless a file with the checksum is
+/// already there.
+///
+/// # Returns
+/// The hashes of the file and whether anything was downloaded.
+async fn download_iso_from(
+    http: &impl HttpFetcher,
+    iso_url: &str,
+    checksum: &Checksum,
+    to_file_path: &str,
+    quiet: bool,
+) -> Result<(Hashes, bool), Box<dyn std::error::Error>> {
+    if Path::new(to_file_path).exists() {
+        status(quiet, "Existing file found. Verifying checksum...");
+        let hashes = hash_file(Path::new(to_file_path), checksum.algorithm)?;
+        if checksum.matches(&hashes) {
+            status(quiet, "Checksum valid. Skipping download.");
+            return Ok((hashes, false));
+        }
+        let kept = quarantine(Path::new(to_file_path))?;
+        status(
+            quiet,
+            &format!(
+                "Checksum mismatch. Kept the file as {}. Re-downloading...",
+                kept.display()
+            ),
+        );
+    } else if Path::new(&partial_path(to_file_path)).exists() {
+        status(
+            quiet,
+            "Partial download found. Checking whether it can be resumed...",
+        );
+    } else {
+        status(quiet, "No existing file found. Proceeding to download...");
+    }
+
+    let (_, hashes) = download_resumable(http, iso_url, to_file_path, checksum, quiet).await?;
+    Ok((hashes, true))
+}
+
+/// Returns the path `to_file_path` is downloaded to until its checksum is verified.
+pub fn partial_path(to_file_path: &str) -> String {
+    format!("{}{}", to_file_path, PARTIAL_DOWNLOAD_SUFFIX)
+}
+
+/// Downloads `url` to `to_file_path` through [`partial_path`], resuming an
+/// existing partial file with an HTTP `Range` request.
+///
+/// Servers that ignore the range get a full download instead. A transfer cut
+/// off midway is resumed as often as `http`'s
+/// [`retry_policy`](HttpFetcher::retry_policy) allows. The partial file is
+/// only renamed to `to_file_path` once it matches `checksum`; a resumed
+/// download that does not match is restarted from scratch once, in case the
+/// partial file was corrupt. Files that fail the checksum are not deleted but
+/// [quarantined](quarantine) next to it for inspection.
+///
+/// The hashes are computed from the bytes as they are written, so only the
+/// bytes kept from an earlier attempt are read back from disk.
+///
+/// # Returns
+/// `true` if the download was resumed, and the hashes of the file.
+/// # Errors
+/// Returns an error if the request or a write fails, or the checksum does not match.
+pub async fn download_resumable(
+    http: &impl HttpFetcher,
+    url: &str,
+    to_file_path: &str,
+    checksum: &Checksum,
+    quiet: bool,
+) -> Result<(bool, Hashes), Box<dyn std::error::Error>> {
+    if let Some(parent) = Path::new(to_file_path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let part = partial_path(to_file_path);
+    let mismatch = |kept: &Path| {
+        format!(
+            "{} hash mismatch; the download was kept as {}",
+            checksum.algorithm,
+            kept.display()
+        )
+    };
+
+    let (mut resumed, mut hashes) =
+        fetch_resuming(http, url, &part, checksum.algorithm, true, quiet).await?;
+    if !checksum.matches(&hashes) {
+        let kept = quarantine(Path::new(&part))?;
+        if !resumed {
+            return Err(mismatch(&kept).into());
+        }
+        status(
+            quiet,
+            &format!(
+                "Checksum mismatch after resuming. Kept the file as {}. Restarting download...",
+                kept.display()
+            ),
+        );
+        (resumed, hashes) =
+            fetch_resuming(http, url, &part, checksum.algorithm, false, quiet).await?;
+        if !checksum.matches(&hashes) {
+            return Err(mismatch(&quarantine(Path::new(&part))?).into());
+        }
+    }
+
+    fs::rename(&part, to_file_path)?;
+    Ok((resumed, hashes))
 }
 
-#[cfg(all(test, feature = "iso-download-tests"))]
+/// [`fetch_to_partial`], resuming from the bytes received so far when the
+/// connection drops, with the waits of `http`'s retry policy in between.
+async fn fetch_resuming(
+    http: &impl HttpFetcher,
+    url: &str,
+    part: &str,
+    algorithm: ChecksumAlgorithm,
+    mut resume: bool,
+    quiet: bool,
+) -> Result<(bool, Hashes), Box<dyn std::error::Error>> {
+    let policy = http.retry_policy();
+    let mut attempt = 1;
+    loop {
+        match fetch_to_partial(http, url, part, algorithm, resume, quiet).await {
+            Err(e) if attempt < policy.attempts && is_interrupted(e.as_ref()) => {
+                let delay = policy.delay(attempt);
+                status(
+                    quiet,
+                    &format!(
+                        "Download interrupted ({}). Resuming in {:.1}s ({}/{})...",
+                        e,
+                        delay.as_secs_f32(),
+                        attempt,
+                        policy.attempts - 1
+                    ),
+                );
+                tokio::time::sleep(delay).await;
+                resume = true;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether `error` is a dropped or timed-out connection rather than an
+/// error status, a local I/O error or a malformed response.
+fn is_interrupted(error: &(dyn std::error::Error + 'static)) -> bool {
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| !e.is_status() && !e.is_builder())
+}
+
+/// Fetches `url` into `part`, continuing after its current length when
+/// `resume` is set and the server honours the range.
+///
+/// # Returns
+/// Whether existing bytes of `part` were kept, and the hashes of the whole
+/// file for checking a checksum of `algorithm`.
+async fn fetch_to_partial(
+    http: &impl HttpFetcher,
+    url: &str,
+    part: &str,
+    algorithm: ChecksumAlgorithm,
+    mut resume: bool,
+    quiet: bool,
+) -> Result<(bool, Hashes), Box<dyn std::error::Error>> {
+    loop {
+        let offset = match fs::metadata(part) {
+            Ok(meta) if resume => meta.len(),
+            _ => 0,
+        };
+        let mut headers = HeaderMap::new();
+        if offset > 0 {
+            headers.insert(RANGE, HeaderValue::from_str(&format!("bytes={}-", offset))?);
+        }
+        let resp = http.get(url, headers).await?;
+
+        let (mut resp, mut file, mut hasher, total) = match resp.status {
+            StatusCode::PARTIAL_CONTENT if offset > 0 => {
+                let (start, total) = content_range(&resp.headers)
+                    .ok_or("Missing or invalid Content-Range in partial response")?;
+                if start != offset {
+                    return Err("Server resumed at the wrong offset".into());
+                }
+                status(
+                    quiet,
+                    &format!("Partial download found. Resuming at {} bytes...", offset),
+                );
+                let file = OpenOptions::new().append(true).open(part)?;
+                (resp, file, hash_prefix(part, offset, algorithm)?, total)
+            }
+            StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+                // Nothing left to fetch when the partial file is already complete.
+                if content_range(&resp.headers).and_then(|(_, total)| total) == Some(offset) {
+                    return Ok((true, hash_prefix(part, offset, algorithm)?.finish()));
+                }
+                resume = false;
+                continue;
+            }
+            _ => {
+                let resp = resp.error_for_status()?;
+                let resp_total = resp.content_length();
+                if offset > 0 {
+                    status(
+                        quiet,
+                        "Server does not support resuming. Restarting download...",
+                    );
+                }
+                (
+                    resp,
+                    File::create(part)?,
+                    Hasher::new(algorithm),
+                    resp_total,
+                )
+            }
+        };
+
+        let resumed = offset > 0 && resp.status == StatusCode::PARTIAL_CONTENT;
+        if let Some(total) = total {
+            ensure_writable(Path::new(part), Some(total))?;
+            ensure_space(
+                Path::new(part),
+                total.saturating_sub(if resumed { offset } else { 0 }),
+            )?;
+        }
+        let progress = progress_bar(if resumed { offset } else { 0 }, total, quiet);
+        while let Some(chunk) = resp.chunk().await? {
+            file.write_all(&chunk)?;
+            hasher.update(&chunk);
+            progress.inc(chunk.len() as u64);
+        }
+        file.sync_all()?;
+        progress.finish();
+        return Ok((resumed, hasher.finish()));
+    }
+}
+
+/// The release a download names: `version` from `mirror` or
+/// [`ISO_INDEX_URL`], else the newest ISO of `channel` in the listing of
+/// `mirror` or, for test ISOs, of [`ISO_INDEX_URL`], else the latest release
+/// on the download `page`, falling back to the listing as
+/// [`download_latest_iso`] does.
+///
+/// Unlike the downloads into the cache, nothing is skipped by `ETag`, as the
+/// checksum is needed either way.
+pub async fn resolve_release(
+    http: &impl HttpFetcher,
+    page: &DownloadPage,
+    mirror: Option<&str>,
+    version: Option<&str>,
+    channel: IsoChannel,
+    quiet: bool,
+) -> Result<IsoRelease, Box<dyn std::error::Error>> {
+    let index_url = mirror.unwrap_or(ISO_INDEX_URL);
+    match (version, mirror, channel) {
+        (Some(version), ..) => get_iso_info(http, index_url, version).await,
+        (None, mirror, channel) if mirror.is_some() || channel == IsoChannel::Test => {
+            newest_listed_release(http, index_url, channel).await
+        }
+        (None, ..) => {
+            let scraped = match fetch_page(http, &page.url, None).await {
+                Ok(Some(latest)) => parse_latest_iso_info(&latest.body, page),
+                Ok(None) => Err("Unexpected 304 Not Modified".into()),
+                Err(e) => Err(e),
+            };
+            match scraped {
+                Ok(release) => Ok(release),
+                Err(e) => {
+                    status(
+                        quiet,
+                        &format!(
+                            "Could not read the latest ISO from {} ({}). Falling back to {}.",
+                            page.url, e, ISO_INDEX_URL
+                        ),
+                    );
+                    newest_listed_release(http, ISO_INDEX_URL, IsoChannel::Stable).await
+                }
+            }
+        }
+    }
+}
+
+/// The newest release of `channel` listed at `index_url`.
+async fn newest_listed_release(
+    http: &impl HttpFetcher,
+    index_url: &str,
+    channel: IsoChannel,
+) -> Result<IsoRelease, Box<dyn std::error::Error>> {
+    let listing = fetch_page(http, index_url, None)
+        .await?
+        .ok_or("Unexpected 304 Not Modified")?;
+    let version = parse_iso_versions(&listing.body, channel)?
+        .pop()
+        .ok_or_else(|| channel.nothing_listed())?;
+    get_iso_info(http, index_url, &version).await
+}
+
+/// Writes the ISO of `release` to `out` as it is downloaded, hashing it on
+/// the way, so that nothing is stored on disk. An intact cached ISO with the
+/// release's checksum is copied from `cache` instead.
+///
+/// A transfer cut off midway is resumed after the bytes written so far, as
+/// often as `http`'s [`retry_policy`](HttpFetcher::retry_policy) allows; from
+/// a server that ignores the range, those bytes are skipped.
+///
+/// # Errors
+/// A failed request or write, or a checksum mismatch. The mismatch is only
+/// known once every byte is written, so `out` then holds a corrupt ISO that
+/// must be discarded.
+pub async fn stream_iso(
+    http: &impl HttpFetcher,
+    cache: &IsoCache,
+    release: &IsoRelease,
+    out: &mut impl Write,
+    quiet: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let checksum = &release.checksum;
+    if cached_release(cache, &release.version, checksum).is_some() {
+        status(
+            quiet,
+            &format!(
+                "Proxmox VE {} found in the cache. Skipping download.",
+                release.version
+            ),
+        );
+        std::io::copy(&mut File::open(cache.iso_path(&release.version))?, out)?;
+        out.flush()?;
+        return Ok(());
+    }
+
+    let policy = http.retry_policy();
+    let mut hasher = Hasher::new(checksum.algorithm);
+    let mut written = 0;
+    let mut attempt = 1;
+    loop {
+        match stream_from(http, &release.url, out, &mut hasher, &mut written, quiet).await {
+            Err(e) if attempt < policy.attempts && is_interrupted(e.as_ref()) => {
+                let delay = policy.delay(attempt);
+                status(
+                    quiet,
+                    &format!(
+                        "Download interrupted ({}). Resuming in {:.1}s ({}/{})...",
+                        e,
+                        delay.as_secs_f32(),
+                        attempt,
+                        policy.attempts - 1
+                    ),
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => break result?,
+        }
+    }
+    if !checksum.matches(&hasher.finish()) {
+        return Err(format!(
+            "{} hash mismatch; the ISO written is corrupt and must be discarded",
+            checksum.algorithm
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Writes `url` to `out`, continuing after the `written` bytes, and feeds
+/// what is written to `hasher`.
+async fn stream_from(
+    http: &impl HttpFetcher,
+    url: &str,
+    out: &mut impl Write,
+    hasher: &mut Hasher,
+    written: &mut u64,
+    quiet: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut headers = HeaderMap::new();
+    if *written > 0 {
+        headers.insert(
+            RANGE,
+            HeaderValue::from_str(&format!("bytes={}-", written))?,
+        );
+    }
+    let resp = http.get(url, headers).await?;
+    let (mut resp, mut skip, total) = match resp.status {
+        StatusCode::PARTIAL_CONTENT if *written > 0 => {
+            let (start, total) = content_range(&resp.headers)
+                .ok_or("Missing or invalid Content-Range in partial response")?;
+            if start != *written {
+                return Err("Server resumed at the wrong offset".into());
+            }
+            (resp, 0, total)
+        }
+        StatusCode::RANGE_NOT_SATISFIABLE
+            if *written > 0
+                && content_range(&resp.headers).and_then(|(_, total)| total) == Some(*written) =>
+        {
+            return Ok(());
+        }
+        _ => {
+            let resp = resp.error_for_status()?;
+            let total = resp.content_length();
+            (resp, *written, total)
+        }
+    };
+
+    let progress = progress_bar(*written, total, quiet);
+    while let Some(chunk) = resp.chunk().await? {
+        let skipped = skip.min(chunk.len() as u64);
+        skip -= skipped;
+        let chunk = &chunk[skipped as usize..];
+        out.write_all(chunk)?;
+        hasher.update(chunk);
+        *written += chunk.len() as u64;
+        progress.inc(chunk.len() as u64);
+    }
+    if skip > 0 {
+        return Err("Server sent fewer bytes than were already written".into());
+    }
+    out.flush()?;
+    progress.finish();
+    Ok(())
+}
+
+/// A [`Hasher`] of the first `len` bytes of `path`, to continue hashing a
+/// resumed download from.
+fn hash_prefix(path: &str, len: u64, algorithm: ChecksumAlgorithm) -> std::io::Result<Hasher> {
+    let mut hasher = Hasher::new(algorithm);
+    let copied = std::io::copy(&mut File::open(path)?.take(len), &mut hasher)?;
+    if copied != len {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(hasher)
+}
+
+/// Parses `Content-Range: bytes <start>-<end>/<total>` (or `bytes */<total>`)
+/// into the start and, unless it is `*`, the total length.
+fn content_range(headers: &HeaderMap) -> Option<(u64, Option<u64>)> {
+    let value = headers.get(CONTENT_RANGE)?.to_str().ok()?;
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let total = match total {
+        "*" => None,
+        total => Some(total.parse().ok()?),
+    };
+    let start = match range {
+        "*" => 0,
+        range => range.split_once('-')?.0.parse().ok()?,
+    };
+    Some((start, total))
+}
+
+/// A bar of transferred bytes, speed and ETA on standard error, starting at
+/// `position` of `total` bytes; a spinner when the length is unknown, and
+/// hidden when `quiet`.
+pub(crate) fn progress_bar(position: u64, total: Option<u64>, quiet: bool) -> ProgressBar {
+    if quiet {
+        return ProgressBar::hidden();
+    }
+    let (bar, template) = match total {
+        Some(total) => (
+            ProgressBar::new(total),
+            "{bar:40} {bytes}/{total_bytes} {binary_bytes_per_sec} ETA {eta}",
+        ),
+        None => (
+            ProgressBar::new_spinner(),
+            "{spinner} {bytes} {binary_bytes_per_sec}",
+        ),
+    };
+    bar.set_style(ProgressStyle::with_template(template).expect("valid progress template"));
+    bar.set_position(position);
+    bar.reset_eta();
+    bar
+}
+
+/// Prints a status line unless `quiet`.
+fn status(quiet: bool, message: &str) {
+    if !quiet {
+        println!("{}", message);
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::Path;
+    use crate::iso::{
+        constants::{PROX_DL_PG_URL, SHA256SUMS_URL},
+        http::tests::MockFetcher,
+    };
+    use reqwest::Client;
+    use sha2::{Digest, Sha256, Sha512};
+    use std::{
+        net::TcpListener,
+        sync::{Arc, Mutex},
+    };
+
+    const BODY: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+    fn sha256() -> String {
+        format!("{:x}", Sha256::digest(BODY))
+    }
+
+    fn sha512() -> String {
+        format!("{:x}", Sha512::digest(BODY))
+    }
+
+    fn checksum() -> Checksum {
+        Checksum::sha256(&sha256()).unwrap()
+    }
+
+    /// Serves `BODY` to `requests` requests, honouring `Range` only with
+    /// `ranges`, and records the range start of each request.
+    fn serve(requests: usize, ranges: bool) -> (String, Arc<Mutex<Vec<Option<usize>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        std::thread::spawn(move || {
+            for _ in 0..requests {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0u8; 4096];
+                let len = stream.read(&mut request).unwrap();
+                let request = String::from_utf8_lossy(&request[..len]).to_lowercase();
+                let start = request
+                    .lines()
+                    .find_map(|l| l.strip_prefix("range: bytes="))
+                    .and_then(|r| r.trim_end_matches('-').parse::<usize>().ok());
+                log.lock().unwrap().push(start);
+
+                let (head, body) = match start {
+                    Some(start) if ranges && start >= BODY.len() => (
+                        format!(
+                            "416 Range Not Satisfiable\r\nContent-Range: bytes */{}",
+                            BODY.len()
+                        ),
+                        &BODY[..0],
+                    ),
+                    Some(start) if ranges => (
+                        format!(
+                            "206 Partial Content\r\nContent-Range: bytes {}-{}/{}",
+                            start,
+                            BODY.len() - 1,
+                            BODY.len()
+                        ),
+                        &BODY[start..],
+                    ),
+                    _ => ("200 OK".to_string(), BODY),
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    head,
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+                stream.write_all(body).unwrap();
+            }
+        });
+        (format!("http://{}/proxmox-ve_8.2-1.iso", addr), seen)
+    }
+
+    fn temp_iso(name: &str, partial: Option<&[u8]>) -> String {
+        let dir =
+            std::env::temp_dir().join(format!("pveauto-download-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let iso = dir.join("pve.iso").to_string_lossy().into_owned();
+        if let Some(partial) = partial {
+            std::fs::write(partial_path(&iso), partial).unwrap();
+        }
+        iso
+    }
+
+    fn assert_downloaded(iso: &str) {
+        assert_eq!(std::fs::read(iso).unwrap(), BODY);
+        assert!(!Path::new(&partial_path(iso)).exists());
+        std::fs::remove_dir_all(Path::new(iso).parent().unwrap()).unwrap();
+    }
+
+    /* ---------------- RESUMING ---------------- */
+
+    #[tokio::test]
+    async fn partial_downloads_are_resumed() {
+        let iso = temp_iso("resume", Some(&BODY[..10]));
+        let (url, seen) = serve(1, true);
+        assert!(
+            download_resumable(&Client::new(), &url, &iso, &checksum(), true)
+                .await
+                .unwrap()
+                .0
+        );
+        assert_eq!(*seen.lock().unwrap(), vec![Some(10)]);
+        assert_downloaded(&iso);
+    }
+
+    #[tokio::test]
+    async fn fresh_downloads_send_no_range() {
+        let iso = temp_iso("fresh", None);
+        let (url, seen) = serve(1, true);
+        assert!(
+            !download_resumable(&Client::new(), &url, &iso, &checksum(), false)
+                .await
+                .unwrap()
+                .0
+        );
+        assert_eq!(*seen.lock().unwrap(), vec![None]);
+        assert_downloaded(&iso);
+    }
+
+    #[tokio::test]
+    async fn servers_without_ranges_get_a_full_download() {
+        let iso = temp_iso("no-ranges", Some(&BODY[..10]));
+        let (url, seen) = serve(1, false);
+        assert!(
+            !download_resumable(&Client::new(), &url, &iso, &checksum(), true)
+                .await
+                .unwrap()
+                .0
+        );
+        assert_eq!(*seen.lock().unwrap(), vec![Some(10)]);
+        assert_downloaded(&iso);
+    }
+
+    #[tokio::test]
+    async fn complete_partial_files_are_only_verified() {
+        let iso = temp_iso("complete", Some(BODY));
+        let (url, seen) = serve(1, true);
+        assert!(
+            download_resumable(&Client::new(), &url, &iso, &checksum(), true)
+                .await
+                .unwrap()
+                .0
+        );
+        assert_eq!(*seen.lock().unwrap(), vec![Some(BODY.len())]);
+        assert_downloaded(&iso);
+    }
+
+    #[tokio::test]
+    async fn corrupt_partial_files_are_downloaded_again() {
+        let iso = temp_iso("corrupt", Some(b"XXXXXXXXXX"));
+        let (url, seen) = serve(2, true);
+        assert!(
+            !download_resumable(&Client::new(), &url, &iso, &checksum(), true)
+                .await
+                .unwrap()
+                .0
+        );
+        assert_eq!(*seen.lock().unwrap(), vec![Some(10), None]);
+        assert_downloaded(&iso);
+    }
+
+    #[tokio::test]
+    async fn checksum_mismatches_are_quarantined() {
+        let iso = temp_iso("mismatch", None);
+        let (url, _) = serve(1, true);
+        let wrong = Checksum::sha256(&"0".repeat(64)).unwrap();
+        let err = download_resumable(&Client::new(), &url, &iso, &wrong, true)
+            .await
+            .unwrap_err();
+        let prefix = format!(
+            "SHA256 hash mismatch; the download was kept as {}.corrupt-",
+            partial_path(&iso)
+        );
+        assert!(err.to_string().starts_with(&prefix), "{}", err);
+
+        let (url, _) = serve(1, true);
+        let wrong = Checksum::new(ChecksumAlgorithm::Sha512, &"0".repeat(128)).unwrap();
+        let err = download_resumable(&Client::new(), &url, &iso, &wrong, true)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with("SHA512 hash mismatch"));
+        assert!(!Path::new(&iso).exists());
+        assert!(!Path::new(&partial_path(&iso)).exists());
+        // Both downloads are kept, under distinct names even within a second.
+        let kept = fs::read_dir(Path::new(&iso).parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter(|name| name.contains(".part.corrupt-"))
+            .count();
+        assert_eq!(kept, 2);
+        std::fs::remove_dir_all(Path::new(&iso).parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dropped_connections_are_resumed_with_retries() {
+        use crate::iso::retry::{RetryPolicy, Retrying};
+        use std::time::Duration;
+
+        // The first response is cut off after 10 bytes; the second honours
+        // the range the client resumes with.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        std::thread::spawn(move || {
+            for cut in [true, false] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0u8; 4096];
+                let len = stream.read(&mut request).unwrap();
+                let request = String::from_utf8_lossy(&request[..len]).to_lowercase();
+                let range = request
+                    .lines()
+                    .find_map(|l| l.strip_prefix("range: bytes="))
+                    .map(str::to_owned);
+                log.lock().unwrap().push(range);
+                let response = if cut {
+                    format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", BODY.len())
+                        .into_bytes()
+                        .into_iter()
+                        .chain(BODY[..10].iter().copied())
+                        .collect::<Vec<_>>()
+                } else {
+                    format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 10-{}/{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        BODY.len() - 1,
+                        BODY.len(),
+                        BODY.len() - 10
+                    )
+                    .into_bytes()
+                    .into_iter()
+                    .chain(BODY[10..].iter().copied())
+                    .collect()
+                };
+                stream.write_all(&response).unwrap();
+            }
+        });
+
+        let iso = temp_iso("dropped", None);
+        let policy = RetryPolicy {
+            attempts: 2,
+            base_delay: Duration::from_millis(1),
+            jitter: Duration::ZERO,
+        };
+        let http = Retrying::new(Client::new(), policy, true);
+        assert!(
+            download_resumable(&http, &url, &iso, &checksum(), true)
+                .await
+                .unwrap()
+                .0
+        );
+        assert_eq!(*seen.lock().unwrap(), vec![None, Some("10-".into())]);
+        assert_downloaded(&iso);
+    }
+
+    #[test]
+    fn resumed_hashes_continue_from_the_kept_bytes() {
+        let iso = temp_iso("prefix", Some(&BODY[..10]));
+        let part = partial_path(&iso);
+        let mut hasher = hash_prefix(&part, 10, ChecksumAlgorithm::Sha512).unwrap();
+        hasher.update(&BODY[10..]);
+        let hashes = hasher.finish();
+        assert_eq!((hashes.sha256, hashes.sha512), (sha256(), Some(sha512())));
+        assert_eq!(
+            hash_prefix(&part, 11, ChecksumAlgorithm::Sha256)
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+        std::fs::remove_dir_all(Path::new(&iso).parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn progress_bars_start_at_the_resumed_offset() {
+        let bar = progress_bar(10, Some(36), false);
+        assert_eq!((bar.position(), bar.length()), (10, Some(36)));
+        assert_eq!(progress_bar(0, None, false).length(), None);
+        assert!(progress_bar(0, Some(36), true).is_hidden());
+    }
+
+    #[test]
+    fn content_ranges_are_parsed() {
+        let range = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_RANGE, value.parse().unwrap());
+            content_range(&headers)
+        };
+        assert_eq!(range("bytes 10-35/36"), Some((10, Some(36))));
+        assert_eq!(range("bytes 10-35/*"), Some((10, None)));
+        assert_eq!(range("bytes */36"), Some((0, Some(36))));
+        assert_eq!(range("items 0-1/2"), None);
+        assert_eq!(content_range(&HeaderMap::new()), None);
+    }
+
+    /* ---------------- STREAMING ---------------- */
+
+    fn release(url: &str, checksum: Checksum) -> IsoRelease {
+        IsoRelease {
+            version: "8.2-1".into(),
+            url: url.into(),
+            checksum,
+            release_date: None,
+            size: None,
+        }
+    }
+
+    fn empty_cache(name: &str) -> IsoCache {
+        let iso = temp_iso(name, None);
+        IsoCache::new(Path::new(&iso).parent().unwrap())
+    }
+
+    #[tokio::test]
+    async fn streamed_isos_are_checked_after_the_last_byte() {
+        let cache = empty_cache("stream");
+        let (url, seen) = serve(2, true);
+        let mut out = Vec::new();
+        stream_iso(
+            &Client::new(),
+            &cache,
+            &release(&url, checksum()),
+            &mut out,
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(out, BODY);
+
+        let wrong = Checksum::sha256(&"0".repeat(64)).unwrap();
+        let mut out = Vec::new();
+        let err = stream_iso(
+            &Client::new(),
+            &cache,
+            &release(&url, wrong),
+            &mut out,
+            true,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "SHA256 hash mismatch; the ISO written is corrupt and must be discarded"
+        );
+        assert_eq!(out, BODY);
+        assert_eq!(*seen.lock().unwrap(), vec![None, None]);
+        // Nothing is stored, not even in the cache.
+        assert_eq!(fs::read_dir(cache.dir()).unwrap().count(), 0);
+        std::fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dropped_streams_skip_the_bytes_already_written() {
+        use crate::iso::retry::{RetryPolicy, Retrying};
+        use std::time::Duration;
+
+        // The first response is cut off after 10 bytes; the second ignores
+        // the range and starts over.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        std::thread::spawn(move || {
+            for cut in [true, false] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0u8; 4096];
+                let len = stream.read(&mut request).unwrap();
+                let request = String::from_utf8_lossy(&request[..len]).to_lowercase();
+                let range = request
+                    .lines()
+                    .find_map(|l| l.strip_prefix("range: bytes="))
+                    .map(str::to_owned);
+                log.lock().unwrap().push(range);
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    BODY.len()
+                );
+                stream.write_all(head.as_bytes()).unwrap();
+                stream
+                    .write_all(if cut { &BODY[..10] } else { BODY })
+                    .unwrap();
+            }
+        });
+
+        let cache = empty_cache("stream-dropped");
+        let policy = RetryPolicy {
+            attempts: 2,
+            base_delay: Duration::from_millis(1),
+            jitter: Duration::ZERO,
+        };
+        let http = Retrying::new(Client::new(), policy, true);
+        let mut out = Vec::new();
+        stream_iso(&http, &cache, &release(&url, checksum()), &mut out, true)
+            .await
+            .unwrap();
+        assert_eq!(out, BODY);
+        assert_eq!(*seen.lock().unwrap(), vec![None, Some("10-".into())]);
+        std::fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cached_isos_are_streamed_without_a_request() {
+        let cache = empty_cache("stream-cached");
+        let iso = cache.iso_path("8.2-1");
+        std::fs::write(&iso, BODY).unwrap();
+        let hashes = hash_file(&iso, ChecksumAlgorithm::Sha256).unwrap();
+        cache.record("8.2-1", ISO_URL, &hashes, None).unwrap();
+
+        // Nothing listens on the discard port, so any request fails.
+        let mut out = Vec::new();
+        let unreachable = release("http://127.0.0.1:9/proxmox-ve_8.2-1.iso", checksum());
+        stream_iso(&Client::new(), &cache, &unreachable, &mut out, true)
+            .await
+            .unwrap();
+        assert_eq!(out, BODY);
+        std::fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    /* ---------------- CACHE ---------------- */
+
+    /// Serves a mirror of one ISO, `proxmox-ve_8.2-1.iso` with `BODY`, whose
+    /// listing and ISO have ETags and whose checksums are in both SHA512SUMS
+    /// and SHA256SUMS, and records `<method> <path>` of each
+    /// request, with ` 304` when the listing was not modified.
+    fn serve_mirror() -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = [0u8; 4096];
+                let len = stream.read(&mut request).unwrap();
+                let request = String::from_utf8_lossy(&request[..len]).to_string();
+                let mut line = request.split_whitespace();
+                let (method, path) = (line.next().unwrap(), line.next().unwrap());
+                let unmodified = request
+                    .to_lowercase()
+                    .contains("if-none-match: \"listing\"");
+
+                let sums = |sum: String| format!("{}  proxmox-ve_8.2-1.iso\n", sum).into_bytes();
+                let (head, body): (&str, Vec<u8>) = match path {
+                    "/" if unmodified => ("304 Not Modified\r\nETag: \"listing\"", Vec::new()),
+                    "/" => (
+                        "200 OK\r\nETag: \"listing\"",
+                        b"<a href=\"proxmox-ve_8.2-1.iso\">iso</a>".to_vec(),
+                    ),
+                    "/SHA512SUMS" => ("200 OK", sums(sha512())),
+                    "/SHA256SUMS" => ("200 OK", sums(sha256())),
+                    "/proxmox-ve_8.2-1.iso" => ("200 OK\r\nETag: \"iso\"", BODY.to_vec()),
+                    _ => ("404 Not Found", Vec::new()),
+                };
+                log.lock().unwrap().push(format!(
+                    "{} {}{}",
+                    method,
+                    path,
+                    if path == "/" && unmodified {
+                        " 304"
+                    } else {
+                        ""
+                    }
+                ));
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    head,
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+                if method != "HEAD" {
+                    stream.write_all(&body).unwrap();
+                }
+            }
+        });
+        (format!("http://{}/", addr), seen)
+    }
+
+    #[tokio::test]
+    async fn unchanged_mirrors_are_served_from_the_cache() {
+        let dir =
+            std::env::temp_dir().join(format!("pveauto-download-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = IsoCache::new(dir.join("cache"));
+        let dest = dir.join("pve.iso").to_string_lossy().into_owned();
+        let (index_url, seen) = serve_mirror();
+        let requests = |seen: &Mutex<Vec<String>>| std::mem::take(&mut *seen.lock().unwrap());
+
+        let (version, path, sha256_checksum, downloaded) = download_newest_listed_iso(
+            &Client::new(),
+            &cache,
+            &index_url,
+            IsoChannel::Stable,
+            &dest,
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!((version.as_str(), path.as_str()), ("8.2-1", dest.as_str()));
+        assert_eq!((sha256_checksum, downloaded), (sha256(), true));
+        assert_eq!(
+            requests(&seen),
+            [
+                "GET /",
+                "GET /SHA512SUMS",
+                "GET /proxmox-ve_8.2-1.iso",
+                "HEAD /proxmox-ve_8.2-1.iso"
+            ]
+        );
+        assert_eq!(std::fs::read(&dest).unwrap(), BODY);
+        assert!(same_file(&cache.iso_path("8.2-1"), Path::new(&dest)));
+        let entry = cache.entry("8.2-1").unwrap();
+        assert_eq!(entry.etag.as_deref(), Some("\"iso\""));
+        assert_eq!(entry.sha512, Some(sha512()));
+        assert_eq!(cache.latest(&index_url).unwrap().version, "8.2-1");
+
+        std::fs::remove_file(&dest).unwrap();
+        let (_, _, _, downloaded) = download_newest_listed_iso(
+            &Client::new(),
+            &cache,
+            &index_url,
+            IsoChannel::Stable,
+            &dest,
+            true,
+        )
+        .await
+        .unwrap();
+        assert!(!downloaded);
+        assert_eq!(requests(&seen), ["GET / 304"]);
+        assert_eq!(std::fs::read(&dest).unwrap(), BODY);
+
+        let other = dir.join("other.iso").to_string_lossy().into_owned();
+        let (_, _, downloaded) =
+            download_iso_version(&Client::new(), &cache, &index_url, "8.2-1", &other, true)
+                .await
+                .unwrap();
+        assert!(!downloaded);
+        assert_eq!(requests(&seen), ["HEAD /proxmox-ve_8.2-1.iso"]);
+        assert!(same_file(&cache.iso_path("8.2-1"), Path::new(&other)));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn valid_existing_files_are_adopted_into_the_cache() {
+        let dir =
+            std::env::temp_dir().join(format!("pveauto-download-adopt-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let cache = IsoCache::new(dir.join("cache"));
+        let dest = dir.join("pve.iso").to_string_lossy().into_owned();
+        std::fs::write(&dest, BODY).unwrap();
+
+        let (_, _, downloaded) = download_to_cache(
+            &Client::new(),
+            &cache,
+            "8.2-1",
+            "http://127.0.0.1:9/proxmox-ve_8.2-1.iso",
+            checksum(),
+            &dest,
+            true,
+        )
+        .await
+        .unwrap();
+        assert!(!downloaded);
+        assert!(same_file(&cache.iso_path("8.2-1"), Path::new(&dest)));
+        assert_eq!(cache.intact_entry("8.2-1").unwrap().etag, None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /* ---------------- LATEST ISO ---------------- */
+
+    const ISO_URL: &str = "https://enterprise.proxmox.com/iso/proxmox-ve_8.2-1.iso";
+
+    fn download_page() -> String {
+        let entry = |href: &str| {
+            format!(
+                r#"<li><div class="download-entry-buttons"><a class="button-primary" href="{}">Download</a></div>
+                <div class="download-entry-info"><dl><div class="download-entry-shasum"><dd><code>{}</code></dd></div></dl></div></li>"#,
+                href,
+                sha256()
+            )
+        };
+        format!(
+            r#"<ul class="latest-downloads">{}{}</ul>"#,
+            entry("https://enterprise.proxmox.com/iso/proxmox-ve_8.2-1.pdf"),
+            entry(ISO_URL)
+        )
+    }
+
+    fn temp_cache(name: &str) -> (std::path::PathBuf, IsoCache, String) {
+        let dir =
+            std::env::temp_dir().join(format!("pveauto-download-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = IsoCache::new(dir.join("cache"));
+        let dest = dir.join("pve.iso").to_string_lossy().into_owned();
+        (dir, cache, dest)
+    }
 
+    #[tokio::test]
+    async fn latest_isos_are_scraped_from_the_download_page() {
+        let (dir, cache, dest) = temp_cache("scraped");
+        let http = MockFetcher::default()
+            .route(PROX_DL_PG_URL, Some("\"page\""), download_page())
+            .route(ISO_URL, Some("\"iso\""), BODY);
+
+        let (path, sha256_checksum, downloaded) =
+            download_latest_iso(&http, &cache, &DownloadPage::default(), &dest, true)
+                .await
+                .unwrap();
+        assert_eq!(
+            (path, sha256_checksum, downloaded),
+            (dest.clone(), sha256(), true)
+        );
+        assert_eq!(
+            http.requests(),
+            [
+                format!("GET {}", PROX_DL_PG_URL),
+                format!("GET {}", ISO_URL),
+                format!("HEAD {}", ISO_URL)
+            ]
+        );
+        assert_eq!(cache.latest(PROX_DL_PG_URL).unwrap().version, "8.2-1");
+
+        let (_, _, downloaded) =
+            download_latest_iso(&http, &cache, &DownloadPage::default(), &dest, true)
+                .await
+                .unwrap();
+        assert!(!downloaded);
+        assert_eq!(http.requests(), [format!("GET {}", PROX_DL_PG_URL)]);
+        assert_eq!(std::fs::read(&dest).unwrap(), BODY);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn up_to_date_copies_are_not_hashed_again() {
+        let (dir, cache, dest) = temp_cache("up-to-date");
+        let http = MockFetcher::default()
+            .route(PROX_DL_PG_URL, None, download_page())
+            .route(ISO_URL, None, BODY);
+        download_latest_iso(&http, &cache, &DownloadPage::default(), &dest, true)
+            .await
+            .unwrap();
+        assert_eq!(http.requests().len(), 3);
+
+        // A copy, as placed on another filesystem, keeps the cached ISO's mtime.
+        let iso = cache.iso_path("8.2-1");
+        fs::remove_file(&dest).unwrap();
+        fs::copy(&iso, &dest).unwrap();
+        let modified = fs::metadata(&iso).unwrap().modified().unwrap();
+        File::options()
+            .write(true)
+            .open(&dest)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        // Without an ETag the page is scraped again, but nothing is fetched,
+        // hashed or replaced.
+        let (_, _, downloaded) =
+            download_latest_iso(&http, &cache, &DownloadPage::default(), &dest, true)
+                .await
+                .unwrap();
+        assert!(!downloaded);
+        assert_eq!(http.requests(), [format!("GET {}", PROX_DL_PG_URL)]);
+        assert!(!same_file(&iso, Path::new(&dest)));
+
+        fs::write(&dest, b"changed").unwrap();
+        download_latest_iso(&http, &cache, &DownloadPage::default(), &dest, true)
+            .await
+            .unwrap();
+        assert!(same_file(&iso, Path::new(&dest)));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn unreadable_download_pages_fall_back_to_the_listing() {
+        let (dir, cache, dest) = temp_cache("fallback");
+        let listing = MockFetcher::default()
+            .route(
+                ISO_INDEX_URL,
+                Some("\"listing\""),
+                r#"<a href="proxmox-ve_8.2-1.iso">iso</a>"#,
+            )
+            .route(
+                SHA256SUMS_URL,
+                None,
+                format!("{}  proxmox-ve_8.2-1.iso\n", sha256()),
+            )
+            .route(ISO_URL, Some("\"iso\""), BODY);
+
+        // A page without the expected layout, then one that cannot be fetched;
+        // the second time the listing is unchanged and the cache is used.
+        let redesigned = listing.route(PROX_DL_PG_URL, None, "<html>redesigned</html>");
+        let (path, sha256_checksum, _) =
+            download_latest_iso(&redesigned, &cache, &DownloadPage::default(), &dest, true)
+                .await
+                .unwrap();
+        assert_eq!((path, sha256_checksum), (dest.clone(), sha256()));
+        assert_eq!(
+            redesigned.requests(),
+            [
+                format!("GET {}", PROX_DL_PG_URL),
+                format!("GET {}", ISO_INDEX_URL),
+                format!("GET {}SHA512SUMS", ISO_INDEX_URL),
+                format!("GET {}", SHA256SUMS_URL),
+                format!("GET {}", ISO_URL),
+                format!("HEAD {}", ISO_URL)
+            ]
+        );
+
+        let missing = MockFetcher::default().route(ISO_INDEX_URL, Some("\"listing\""), "");
+        let (_, sha256_checksum, downloaded) =
+            download_latest_iso(&missing, &cache, &DownloadPage::default(), &dest, true)
+                .await
+                .unwrap();
+        assert_eq!((sha256_checksum, downloaded), (sha256(), false));
+        assert_eq!(
+            missing.requests(),
+            [
+                format!("GET {}", PROX_DL_PG_URL),
+                format!("GET {}", ISO_INDEX_URL)
+            ]
+        );
+        assert!(cache.latest(PROX_DL_PG_URL).is_none());
+        assert_eq!(std::fs::read(&dest).unwrap(), BODY);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_isos_are_the_betas_in_the_listing() {
+        let (dir, cache, dest) = temp_cache("channel");
+        let beta_url = format!("{}proxmox-ve_9.0-BETA-1.iso", ISO_INDEX_URL);
+        let http = MockFetcher::default()
+            .route(
+                ISO_INDEX_URL,
+                Some("\"listing\""),
+                r#"<a href="proxmox-ve_8.2-1.iso">iso</a><a href="proxmox-ve_9.0-BETA-1.iso">iso</a>"#,
+            )
+            .route(
+                SHA256SUMS_URL,
+                None,
+                format!(
+                    "{0}  proxmox-ve_8.2-1.iso\n{0}  proxmox-ve_9.0-BETA-1.iso\n",
+                    sha256()
+                ),
+            )
+            .route(ISO_URL, Some("\"iso\""), BODY)
+            .route(&beta_url, Some("\"beta\""), BODY);
+
+        let (version, ..) =
+            download_newest_listed_iso(&http, &cache, ISO_INDEX_URL, IsoChannel::Test, &dest, true)
+                .await
+                .unwrap();
+        assert_eq!(version, "9.0-BETA-1");
+        assert!(http.requests().contains(&format!("GET {}", beta_url)));
+        let key = IsoChannel::Test.listing_key(ISO_INDEX_URL);
+        assert_eq!(cache.latest(&key).unwrap().version, "9.0-BETA-1");
+
+        // The unchanged listing is not taken to mean the beta is the latest
+        // release.
+        let (version, ..) = download_newest_listed_iso(
+            &http,
+            &cache,
+            ISO_INDEX_URL,
+            IsoChannel::Stable,
+            &dest,
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(version, "8.2-1");
+        assert_eq!(cache.latest(ISO_INDEX_URL).unwrap().version, "8.2-1");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn streamed_releases_are_resolved_like_downloads() {
+        let http = MockFetcher::default()
+            .route(
+                ISO_INDEX_URL,
+                None,
+                r#"<a href="proxmox-ve_8.2-1.iso">iso</a><a href="proxmox-ve_9.0-BETA-1.iso">iso</a>"#,
+            )
+            .route(
+                SHA256SUMS_URL,
+                None,
+                format!(
+                    "{0}  proxmox-ve_8.2-1.iso\n{0}  proxmox-ve_9.0-BETA-1.iso\n",
+                    sha256()
+                ),
+            )
+            .route(PROX_DL_PG_URL, None, "<html>redesigned</html>");
+        let page = DownloadPage::default();
+        let resolve =
+            |version, channel| resolve_release(&http, &page, None, version, channel, true);
+
+        let latest = resolve(None, IsoChannel::Stable).await.unwrap();
+        assert_eq!(
+            (latest.version.as_str(), latest.url.as_str()),
+            ("8.2-1", ISO_URL)
+        );
+        assert_eq!(latest.checksum, checksum());
+        let beta = resolve(None, IsoChannel::Test).await.unwrap();
+        assert_eq!(beta.version, "9.0-BETA-1");
+        let pinned = resolve(Some("8.2-1"), IsoChannel::Test).await.unwrap();
+        assert_eq!(pinned.version, "8.2-1");
+    }
+
+    #[cfg(feature = "iso-download-tests")]
     #[tokio::test]
     async fn test_download_latest_iso() {
+        use crate::iso::scraper::get_latest_iso_info;
+
         let to_file_path = "test_PROX_iso.iso";
-        let result = download_latest_iso(to_file_path, None).await;
-        let expected = get_latest_iso_info().await;
+        let cache = IsoCache::new(
+            std::env::temp_dir().join(format!("pveauto-latest-cache-{}", std::process::id())),
+        );
+        let http = Client::new();
+        let result =
+            download_latest_iso(&http, &cache, &DownloadPage::default(), to_file_path, true).await;
+        let expected = get_latest_iso_info(&http, &DownloadPage::default()).await;
         assert!(expected.is_ok());
 
         assert!(result.is_ok());
         let (downloaded_path, sha256, downloaded) = result.unwrap();
-        let (_, expected_sha256) = expected.unwrap();
+        let expected = expected.unwrap().checksum;
         assert_eq!(downloaded_path, to_file_path);
-        assert!(!sha256.is_empty());
         assert!(sha256.len() == 64);
-        assert!(sha256 == expected_sha256);
+        assert!(expected.algorithm != ChecksumAlgorithm::Sha256 || sha256 == expected.hex);
         assert!(downloaded);
         assert!(Path::new(to_file_path).exists());
 
         // Clean up
         std::fs::remove_file(to_file_path).unwrap();
+        std::fs::remove_dir_all(cache.dir()).unwrap();
     }
 }
@@ -0,0 +1,215 @@
+use crate::answer_file::{AnswerFile, sections::FirstBootSource};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Largest first-boot script the installer accepts from the ISO (1 MiB).
+pub const FIRST_BOOT_SCRIPT_MAX_BYTES: u64 = 1024 * 1024;
+
+/* ===================== FIRST BOOT SCRIPT ERROR ===================== */
+
+/// Failure while bundling the local `first-boot.script` of an answer file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FirstBootScriptError {
+    /// The script could not be read; holds the path and the I/O error.
+    Unreadable(PathBuf, String),
+    /// The script exceeds [`FIRST_BOOT_SCRIPT_MAX_BYTES`].
+    TooLarge(PathBuf),
+    /// The script is not UTF-8 text or contains NUL bytes.
+    NotText(PathBuf),
+    /// The script does not start with a `#!` interpreter line.
+    MissingShebang(PathBuf),
+}
+
+impl FirstBootScriptError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Unreadable(..) => "first_boot.script.unreadable",
+            Self::TooLarge(_) => "first_boot.script.too_large",
+            Self::NotText(_) => "first_boot.script.not_text",
+            Self::MissingShebang(_) => "first_boot.script.missing_shebang",
+        }
+    }
+}
+
+impl fmt::Display for FirstBootScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unreadable(path, detail) => {
+                write!(f, "{} ({}: {})", self.code(), path.display(), detail)
+            }
+            Self::TooLarge(path) | Self::NotText(path) | Self::MissingShebang(path) => {
+                write!(f, "{} ({})", self.code(), path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for FirstBootScriptError {}
+
+/* ===================== FIRST BOOT SCRIPT ===================== */
+
+/// A validated first-boot script, ready to be copied into the ISO.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FirstBootScript {
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+impl FirstBootScript {
+    /// Reads the script at `path`, which must be text with a `#!` line and
+    /// at most [`FIRST_BOOT_SCRIPT_MAX_BYTES`] long.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, FirstBootScriptError> {
+        let path = path.into();
+        let unreadable =
+            |e: std::io::Error| FirstBootScriptError::Unreadable(path.clone(), e.to_string());
+
+        if std::fs::metadata(&path).map_err(unreadable)?.len() > FIRST_BOOT_SCRIPT_MAX_BYTES {
+            return Err(FirstBootScriptError::TooLarge(path));
+        }
+        let bytes = std::fs::read(&path).map_err(unreadable)?;
+        if bytes.len() as u64 > FIRST_BOOT_SCRIPT_MAX_BYTES {
+            return Err(FirstBootScriptError::TooLarge(path));
+        }
+
+        let contents = match String::from_utf8(bytes) {
+            Ok(text) if !text.contains('\0') => text,
+            _ => return Err(FirstBootScriptError::NotText(path)),
+        };
+        if !contents.starts_with("#!") {
+            return Err(FirstBootScriptError::MissingShebang(path));
+        }
+
+        Ok(Self { path, contents })
+    }
+}
+
+/// Loads the local `first-boot.script` of `answer`, resolved against
+/// `base_dir` (the answer file's directory) when relative, and rewrites the
+/// section to `source = "from-iso"` without the script key, as the installer
+/// expects for a script shipped on the ISO.
+///
+/// # Returns
+/// `Ok(None)` and leaves `answer` untouched when it names no local script.
+pub fn bundle_first_boot_script(
+    answer: &mut AnswerFile,
+    base_dir: &Path,
+) -> Result<Option<FirstBootScript>, FirstBootScriptError> {
+    let Some(first_boot) = answer.first_boot.as_mut() else {
+        return Ok(None);
+    };
+    let Some(script) = first_boot.script.as_deref() else {
+        return Ok(None);
+    };
+
+    let script = FirstBootScript::load(base_dir.join(script))?;
+    first_boot.source = FirstBootSource::FromIso;
+    first_boot.script = None;
+    first_boot.url = None;
+    first_boot.cert_fingerprint = None;
+    Ok(Some(script))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer_file::sections::FirstBoot;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "pveauto-first-boot-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn with_script(script: &str) -> AnswerFile {
+        AnswerFile {
+            first_boot: Some(FirstBoot {
+                source: FirstBootSource::FromUrl,
+                script: Some(script.into()),
+                ..FirstBoot::default()
+            }),
+            ..AnswerFile::default()
+        }
+    }
+
+    /* ---------------- BUNDLING ---------------- */
+
+    #[test]
+    fn script_is_loaded_and_section_rewritten() {
+        let dir = temp_dir("bundle");
+        std::fs::write(dir.join("setup.sh"), "#!/bin/sh\necho hi\n").unwrap();
+
+        let mut answer = with_script("setup.sh");
+        let script = bundle_first_boot_script(&mut answer, &dir)
+            .unwrap()
+            .unwrap();
+        assert_eq!(script.path, dir.join("setup.sh"));
+        assert_eq!(script.contents, "#!/bin/sh\necho hi\n");
+
+        let first_boot = answer.first_boot.as_ref().unwrap();
+        assert_eq!(first_boot.source, FirstBootSource::FromIso);
+        assert_eq!(first_boot.script, None);
+        assert!(answer.validate().is_ok());
+        assert!(!answer.to_toml_string().unwrap().contains("script"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn answers_without_script_are_untouched() {
+        let mut answer = AnswerFile::default();
+        assert_eq!(
+            bundle_first_boot_script(&mut answer, Path::new(".")),
+            Ok(None)
+        );
+        assert_eq!(answer, AnswerFile::default());
+    }
+
+    /* ---------------- VALIDATION ---------------- */
+
+    #[test]
+    fn invalid_scripts_are_rejected() {
+        let dir = temp_dir("invalid");
+        let oversized = format!(
+            "#!/bin/sh\n{}",
+            "#".repeat(FIRST_BOOT_SCRIPT_MAX_BYTES as usize)
+        );
+        for (name, contents, code) in [
+            (
+                "large.sh",
+                oversized.as_bytes(),
+                "first_boot.script.too_large",
+            ),
+            (
+                "binary.sh",
+                b"#!/bin/sh\n\0\x01".as_slice(),
+                "first_boot.script.not_text",
+            ),
+            (
+                "latin1.sh",
+                b"#!/bin/sh\n\xff".as_slice(),
+                "first_boot.script.not_text",
+            ),
+            (
+                "plain.sh",
+                b"echo hi\n".as_slice(),
+                "first_boot.script.missing_shebang",
+            ),
+        ] {
+            std::fs::write(dir.join(name), contents).unwrap();
+            let err = FirstBootScript::load(dir.join(name)).unwrap_err();
+            assert_eq!(err.code(), code, "{}", name);
+        }
+
+        let mut answer = with_script("missing.sh");
+        let err = bundle_first_boot_script(&mut answer, &dir).unwrap_err();
+        assert_eq!(err.code(), "first_boot.script.unreadable");
+        assert_eq!(answer, with_script("missing.sh"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,353 @@
+use crate::iso::downloader::progress_bar;
+use sha2::{Digest, Sha256};
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+/// Size of each write to and read from the device.
+const CHUNK_SIZE: usize = 4 << 20;
+
+/* ===================== FLASH ERROR ===================== */
+
+/// Failure while writing an ISO to a block device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlashError {
+    /// Reading or writing failed; holds the path and the I/O error.
+    Io(PathBuf, String),
+    /// The target is not a block device.
+    NotBlockDevice(PathBuf),
+    /// The device or one of its partitions is mounted; holds the mount point.
+    Mounted(PathBuf, String),
+    /// The device is smaller than the image.
+    TooSmall {
+        device: PathBuf,
+        size: u64,
+        needed: u64,
+    },
+    /// What was read back from the device differs from the image.
+    VerifyMismatch(PathBuf),
+}
+
+impl FlashError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io(..) => "flash.io",
+            Self::NotBlockDevice(_) => "flash.not_block_device",
+            Self::Mounted(..) => "flash.mounted",
+            Self::TooSmall { .. } => "flash.too_small",
+            Self::VerifyMismatch(_) => "flash.verify_mismatch",
+        }
+    }
+}
+
+impl fmt::Display for FlashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, detail) => write!(f, "{} ({}: {})", self.code(), path.display(), detail),
+            Self::NotBlockDevice(path) | Self::VerifyMismatch(path) => {
+                write!(f, "{} ({})", self.code(), path.display())
+            }
+            Self::Mounted(path, mount_point) => write!(
+                f,
+                "{} ({} is mounted on {})",
+                self.code(),
+                path.display(),
+                mount_point
+            ),
+            Self::TooSmall {
+                device,
+                size,
+                needed,
+            } => write!(
+                f,
+                "{} ({} has {} bytes, the image needs {})",
+                self.code(),
+                device.display(),
+                size,
+                needed
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FlashError {}
+
+/* ===================== BLOCK DEVICES ===================== */
+
+/// A block device as described by sysfs, for confirming the target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockDevice {
+    pub path: PathBuf,
+    /// Kernel name, e.g. `sdb` for `/dev/sdb`.
+    pub name: String,
+    pub vendor: Option<String>,
+    pub model: Option<String>,
+    /// Size in bytes.
+    pub size: Option<u64>,
+    pub removable: bool,
+}
+
+impl BlockDevice {
+    /// Describes the block device at `path`, following symlinks such as
+    /// `/dev/disk/by-id/...`.
+    ///
+    /// # Errors
+    /// Returns an error if `path` does not exist or is not a block device.
+    pub fn probe(path: &Path) -> Result<Self, FlashError> {
+        let resolved = fs::canonicalize(path)
+            .map_err(|e| FlashError::Io(path.to_path_buf(), e.to_string()))?;
+        if !is_block_device(&resolved) {
+            return Err(FlashError::NotBlockDevice(path.to_path_buf()));
+        }
+        let name = resolved
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let sys = Path::new("/sys/class/block").join(&name);
+        let attribute = |file: &str| {
+            fs::read_to_string(sys.join(file))
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        Ok(Self {
+            path: resolved,
+            vendor: attribute("device/vendor"),
+            model: attribute("device/model"),
+            // sysfs counts 512-byte sectors regardless of the logical block size.
+            size: attribute("size")
+                .and_then(|s| s.parse::<u64>().ok())
+                .map(|sectors| sectors * 512),
+            removable: attribute("removable").as_deref() == Some("1"),
+            name,
+        })
+    }
+
+    /// E.g. `/dev/sdb: SanDisk Ultra, 28.64 GiB, removable`.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        let label = [self.vendor.as_deref(), self.model.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ");
+        parts.push(if label.is_empty() {
+            "unknown model".to_string()
+        } else {
+            label
+        });
+        parts.push(match self.size {
+            Some(size) => format!("{:.2} GiB", size as f64 / (1u64 << 30) as f64),
+            None => "unknown size".to_string(),
+        });
+        parts.push(if self.removable { "removable" } else { "fixed" }.to_string());
+        format!("{}: {}", self.path.display(), parts.join(", "))
+    }
+
+    /// Fails when the device or one of its partitions is mounted according
+    /// to `mounts` (the contents of `/proc/mounts`), or when it is known to be
+    /// smaller than `needed` bytes.
+    pub fn check_writable(&self, needed: u64, mounts: &str) -> Result<(), FlashError> {
+        if let Some(mount_point) = mount_points(&self.name, mounts).into_iter().next() {
+            return Err(FlashError::Mounted(self.path.clone(), mount_point));
+        }
+        match self.size {
+            Some(size) if size < needed => Err(FlashError::TooSmall {
+                device: self.path.clone(),
+                size,
+                needed,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_block_device(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    fs::metadata(path).is_ok_and(|m| m.file_type().is_block_device())
+}
+
+#[cfg(not(unix))]
+fn is_block_device(_path: &Path) -> bool {
+    false
+}
+
+/// Mount points in `mounts` (`/proc/mounts`) of the device `name` or one of
+/// its partitions, e.g. `/dev/sdb1` or `/dev/nvme0n1p2`.
+pub fn mount_points(name: &str, mounts: &str) -> Vec<String> {
+    let device = format!("/dev/{}", name);
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (source, target) = (fields.next()?, fields.next()?);
+            let partition = source.strip_prefix(&device)?;
+            let partition = partition.strip_prefix('p').unwrap_or(partition);
+            partition
+                .bytes()
+                .all(|b| b.is_ascii_digit())
+                .then(|| target.to_string())
+        })
+        .collect()
+}
+
+/* ===================== FLASHING ===================== */
+
+/// Writes the image at `iso` to the start of `device`, syncs it, and reads
+/// it back to compare against what was written.
+///
+/// The page cache of `device` is dropped before reading back, so the
+/// comparison sees what reached the device. Progress bars are shown on
+/// standard error unless `quiet`.
+///
+/// # Returns
+/// The SHA256 of the image, as written and read back.
+/// # Errors
+/// Returns an error if a read or write fails or the read-back differs.
+pub fn flash_image(iso: &Path, device: &Path, quiet: bool) -> Result<String, FlashError> {
+    let iso_error = |e: std::io::Error| FlashError::Io(iso.to_path_buf(), e.to_string());
+    let device_error = |e: std::io::Error| FlashError::Io(device.to_path_buf(), e.to_string());
+
+    let mut image = File::open(iso).map_err(iso_error)?;
+    let len = image.metadata().map_err(iso_error)?.len();
+    let mut target = OpenOptions::new()
+        .write(true)
+        .open(device)
+        .map_err(device_error)?;
+
+    let progress = progress_bar(0, Some(len), quiet);
+    let mut written = Sha256::new();
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+        let read = image.read(&mut buf).map_err(iso_error)?;
+        if read == 0 {
+            break;
+        }
+        target.write_all(&buf[..read]).map_err(device_error)?;
+        written.update(&buf[..read]);
+        progress.inc(read as u64);
+    }
+    target.sync_all().map_err(device_error)?;
+    drop_page_cache(&target);
+    progress.finish();
+
+    let progress = progress_bar(0, Some(len), quiet);
+    let mut source = File::open(device).map_err(device_error)?;
+    let mut read_back = Sha256::new();
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = &mut buf[..remaining.min(CHUNK_SIZE as u64) as usize];
+        source.read_exact(chunk).map_err(device_error)?;
+        read_back.update(&chunk[..]);
+        remaining -= chunk.len() as u64;
+        progress.inc(chunk.len() as u64);
+    }
+    progress.finish();
+
+    let (written, read_back) = (written.finalize(), read_back.finalize());
+    if written != read_back {
+        return Err(FlashError::VerifyMismatch(device.to_path_buf()));
+    }
+    Ok(written.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Asks the kernel to forget the cached pages of `file`, so that reading it
+/// again goes to the device.
+#[cfg(target_os = "linux")]
+fn drop_page_cache(file: &File) {
+    use std::os::fd::AsRawFd;
+    // SAFETY: the descriptor is open for the duration of the call.
+    // Advisory only: a failure leaves the read-back served from the cache.
+    unsafe {
+        libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn drop_page_cache(_file: &File) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pveauto-flash-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn images_are_written_and_read_back() {
+        let dir = temp_dir();
+        let (iso, device) = (dir.join("pve.iso"), dir.join("device"));
+        let image: Vec<u8> = (0..CHUNK_SIZE + 123).map(|i| (i % 251) as u8).collect();
+        fs::write(&iso, &image).unwrap();
+        fs::write(&device, vec![0xAA; image.len() + 4096]).unwrap();
+
+        let sha256 = flash_image(&iso, &device, true).unwrap();
+        assert_eq!(sha256, format!("{:x}", Sha256::digest(&image)));
+        let flashed = fs::read(&device).unwrap();
+        assert_eq!(&flashed[..image.len()], &image[..]);
+        assert!(flashed[image.len()..].iter().all(|&b| b == 0xAA));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn regular_files_are_not_block_devices() {
+        let dir = temp_dir().join("regular");
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("not-a-disk");
+        fs::write(&file, b"").unwrap();
+        assert_eq!(
+            BlockDevice::probe(&file).unwrap_err(),
+            FlashError::NotBlockDevice(file.clone())
+        );
+        assert_eq!(
+            BlockDevice::probe(&dir.join("missing")).unwrap_err().code(),
+            "flash.io"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn mounted_partitions_are_found() {
+        let mounts = "/dev/sdb1 /media/usb vfat rw 0 0\n\
+                      /dev/sdba1 /mnt/other ext4 rw 0 0\n\
+                      /dev/nvme0n1p2 / ext4 rw 0 0\n\
+                      tmpfs /tmp tmpfs rw 0 0\n";
+        assert_eq!(mount_points("sdb", mounts), ["/media/usb"]);
+        assert_eq!(mount_points("nvme0n1", mounts), ["/"]);
+        assert!(mount_points("sdc", mounts).is_empty());
+    }
+
+    #[test]
+    fn devices_are_described_for_confirmation() {
+        let device = BlockDevice {
+            path: "/dev/sdb".into(),
+            name: "sdb".into(),
+            vendor: Some("SanDisk".into()),
+            model: Some("Ultra".into()),
+            size: Some(4 << 30),
+            removable: true,
+        };
+        assert_eq!(
+            device.describe(),
+            "/dev/sdb: SanDisk Ultra, 4.00 GiB, removable"
+        );
+        assert_eq!(
+            device.check_writable(5 << 30, "").unwrap_err().code(),
+            "flash.too_small"
+        );
+        assert_eq!(
+            device.check_writable(1 << 30, "/dev/sdb1 /media/usb vfat rw 0 0\n"),
+            Err(FlashError::Mounted("/dev/sdb".into(), "/media/usb".into()))
+        );
+        assert_eq!(device.check_writable(1 << 30, ""), Ok(()));
+    }
+}
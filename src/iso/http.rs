@@ -0,0 +1,239 @@
+use crate::iso::retry::RetryPolicy;
+use reqwest::{Client, Method, StatusCode, header::HeaderMap};
+use std::{error::Error, future::Future};
+
+/// Sends the HTTP requests of the scraper and downloader.
+///
+/// Implemented for [`reqwest::Client`]; library consumers and tests can pass
+/// their own implementation instead, e.g. to serve canned pages, go through
+/// a proxy or log every request.
+pub trait HttpFetcher {
+    /// Sends a `method` request for `url` with `headers`.
+    ///
+    /// # Errors
+    /// Returns an error if no response was received; error statuses such as
+    /// `404` are responses, checked with [`HttpResponse::error_for_status`].
+    fn send(
+        &self,
+        method: Method,
+        url: &str,
+        headers: HeaderMap,
+    ) -> impl Future<Output = Result<HttpResponse, Box<dyn Error>>>;
+
+    /// Sends a `GET` request for `url` with `headers`.
+    fn get(
+        &self,
+        url: &str,
+        headers: HeaderMap,
+    ) -> impl Future<Output = Result<HttpResponse, Box<dyn Error>>> {
+        self.send(Method::GET, url, headers)
+    }
+
+    /// Sends a `HEAD` request for `url`.
+    fn head(&self, url: &str) -> impl Future<Output = Result<HttpResponse, Box<dyn Error>>> {
+        self.send(Method::HEAD, url, HeaderMap::new())
+    }
+
+    /// How often a download whose body is cut off is resumed. Requests
+    /// themselves are only retried by wrappers such as
+    /// [`Retrying`](crate::iso::retry::Retrying).
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::NONE
+    }
+}
+
+impl HttpFetcher for Client {
+    async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        headers: HeaderMap,
+    ) -> Result<HttpResponse, Box<dyn Error>> {
+        let resp = self.request(method, url).headers(headers).send().await?;
+        Ok(HttpResponse {
+            url: url.to_string(),
+            status: resp.status(),
+            headers: resp.headers().clone(),
+            body: Body::Streamed(resp),
+        })
+    }
+}
+
+/// A response of an [`HttpFetcher`], whose body is read in chunks.
+#[derive(Debug)]
+pub struct HttpResponse {
+    pub url: String,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    body: Body,
+}
+
+#[derive(Debug)]
+enum Body {
+    /// Read from the connection as it arrives.
+    Streamed(reqwest::Response),
+    /// Held in memory until it is read.
+    Buffered(Option<Vec<u8>>),
+}
+
+impl HttpResponse {
+    /// A response with the whole `body` in memory, e.g. for a mock fetcher.
+    pub fn new(url: &str, status: StatusCode, headers: HeaderMap, body: Vec<u8>) -> Self {
+        Self {
+            url: url.to_string(),
+            status,
+            headers,
+            body: Body::Buffered(Some(body)),
+        }
+    }
+
+    /// Turns a `4xx` or `5xx` status into an error.
+    pub fn error_for_status(self) -> Result<Self, Box<dyn Error>> {
+        match &self.body {
+            Body::Streamed(resp) => {
+                resp.error_for_status_ref()?;
+            }
+            Body::Buffered(_) if self.status.is_client_error() || self.status.is_server_error() => {
+                return Err(format!("HTTP status {} for url ({})", self.status, self.url).into());
+            }
+            Body::Buffered(_) => {}
+        }
+        Ok(self)
+    }
+
+    /// The length of the body, when known in advance.
+    pub fn content_length(&self) -> Option<u64> {
+        match &self.body {
+            Body::Streamed(resp) => resp.content_length(),
+            Body::Buffered(body) => body.as_ref().map(|b| b.len() as u64),
+        }
+    }
+
+    /// The next chunk of the body, or `None` once it has been read.
+    pub async fn chunk(&mut self) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        match &mut self.body {
+            Body::Streamed(resp) => Ok(resp.chunk().await?.map(|chunk| chunk.to_vec())),
+            Body::Buffered(body) => Ok(body.take().filter(|b| !b.is_empty())),
+        }
+    }
+
+    /// The whole body as text.
+    pub async fn text(self) -> Result<String, Box<dyn Error>> {
+        match self.body {
+            Body::Streamed(resp) => Ok(resp.text().await?),
+            Body::Buffered(body) => Ok(String::from_utf8(body.unwrap_or_default())?),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use reqwest::header::{ETAG, HeaderValue, IF_NONE_MATCH};
+    use std::sync::Mutex;
+
+    /// Serves fixed bodies by URL, answering `If-None-Match` with `304` when
+    /// the body's `ETag` matches, and records `<method> <url>` of each request.
+    #[derive(Default)]
+    pub(crate) struct MockFetcher {
+        routes: Vec<(String, Option<String>, Vec<u8>)>,
+        seen: Mutex<Vec<String>>,
+    }
+
+    impl MockFetcher {
+        /// Serves `body` at `url`, with `etag` when given.
+        pub fn route(mut self, url: &str, etag: Option<&str>, body: impl Into<Vec<u8>>) -> Self {
+            self.routes
+                .push((url.to_string(), etag.map(str::to_owned), body.into()));
+            self
+        }
+
+        /// The requests sent since the last call.
+        pub fn requests(&self) -> Vec<String> {
+            std::mem::take(&mut *self.seen.lock().unwrap())
+        }
+    }
+
+    impl HttpFetcher for MockFetcher {
+        async fn send(
+            &self,
+            method: Method,
+            url: &str,
+            headers: HeaderMap,
+        ) -> Result<HttpResponse, Box<dyn Error>> {
+            self.seen
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", method, url));
+            let Some((_, etag, body)) = self.routes.iter().find(|(route, ..)| route == url) else {
+                return Ok(HttpResponse::new(
+                    url,
+                    StatusCode::NOT_FOUND,
+                    HeaderMap::new(),
+                    Vec::new(),
+                ));
+            };
+            let mut response_headers = HeaderMap::new();
+            if let Some(etag) = etag {
+                response_headers.insert(ETAG, HeaderValue::from_str(etag)?);
+                if headers
+                    .get(IF_NONE_MATCH)
+                    .is_some_and(|v| v == etag.as_str())
+                {
+                    return Ok(HttpResponse::new(
+                        url,
+                        StatusCode::NOT_MODIFIED,
+                        response_headers,
+                        Vec::new(),
+                    ));
+                }
+            }
+            let body = if method == Method::HEAD {
+                Vec::new()
+            } else {
+                body.clone()
+            };
+            Ok(HttpResponse::new(
+                url,
+                StatusCode::OK,
+                response_headers,
+                body,
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn buffered_responses_read_like_streamed_ones() {
+        let fetcher = MockFetcher::default().route("http://mock/page", Some("\"v1\""), "hello");
+
+        let mut resp = fetcher
+            .get("http://mock/page", HeaderMap::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            (resp.status, resp.content_length()),
+            (StatusCode::OK, Some(5))
+        );
+        assert_eq!(resp.chunk().await.unwrap(), Some(b"hello".to_vec()));
+        assert_eq!(resp.chunk().await.unwrap(), None);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_static("\"v1\""));
+        let resp = fetcher.get("http://mock/page", headers).await.unwrap();
+        assert_eq!(resp.status, StatusCode::NOT_MODIFIED);
+
+        let missing = fetcher.head("http://mock/missing").await.unwrap();
+        assert_eq!(
+            missing.error_for_status().unwrap_err().to_string(),
+            "HTTP status 404 Not Found for url (http://mock/missing)"
+        );
+        assert_eq!(
+            fetcher.requests(),
+            [
+                "GET http://mock/page",
+                "GET http://mock/page",
+                "HEAD http://mock/missing"
+            ]
+        );
+    }
+}
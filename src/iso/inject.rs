@@ -0,0 +1,1363 @@
+use crate::iso::{
+    boot::read_boot_records,
+    constants::{AUTO_INSTALLER_MODE_FILE_NAME, ISO_ANSWER_FILE_NAME, ISO_FIRST_BOOT_FILE_NAME},
+    first_boot::FirstBootScript,
+    mode::AutoInstallerMode,
+    writer::IsoWriter,
+};
+use std::{
+    fmt,
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// Size of an ISO 9660 logical sector.
+pub const SECTOR_SIZE: u64 = 2048;
+
+/// Size of an MBR/GPT logical block in hybrid images.
+pub(crate) const BLOCK_SIZE: u64 = 512;
+
+/// The volume descriptor set starts after the 32 KiB system area.
+pub(crate) const FIRST_DESCRIPTOR_SECTOR: u64 = 16;
+
+/// Upper bound on descriptors read before giving up on a terminator.
+pub(crate) const MAX_DESCRIPTORS: u64 = 64;
+
+/// Offset of the root directory record in a volume descriptor.
+const ROOT_RECORD_OFFSET: usize = 156;
+
+/// Length of the volume identifier of a primary volume descriptor.
+pub const MAX_VOLUME_LABEL_LEN: usize = 32;
+
+/// Mode of injected files in Rock Ridge images: a regular file, `r--r--r--`.
+const INJECTED_FILE_MODE: u32 = 0o100444;
+
+/* ===================== ISO INJECT ERROR ===================== */
+
+/// Failure while adding files to an ISO image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IsoInjectError {
+    /// Reading or writing an image failed; holds the path and the I/O error.
+    Io(PathBuf, String),
+    /// The image has no ISO 9660 primary volume descriptor.
+    NotIso9660(PathBuf),
+    /// A structure of the image is malformed or outside the supported layout.
+    Unsupported(&'static str),
+    /// A file path has an empty, `.` or `..` component, or a non-printable
+    /// ASCII character.
+    InvalidName(String),
+    /// A directory of a file path is not on the image.
+    MissingDirectory(String),
+    /// A boot structure of the original image is gone or broken in the
+    /// written one; holds which.
+    BrokenBoot(String),
+    /// A volume label is empty, too long or has characters other than
+    /// `A`-`Z`, `0`-`9`, `_` and `-`.
+    InvalidVolumeLabel(String),
+    /// The image has no `.cd-info`, so it is not a Proxmox installer ISO.
+    NotInstallerIso(PathBuf),
+    /// The program of the chosen [`IsoBackend`](crate::iso::writer::IsoBackend)
+    /// is not installed; holds its name.
+    BackendUnavailable(String),
+    /// The program of the chosen backend failed; holds its message.
+    BackendFailed(String),
+}
+
+impl IsoInjectError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io(..) => "iso.io",
+            Self::NotIso9660(_) => "iso.not_iso9660",
+            Self::NotInstallerIso(_) => "iso.not_installer_iso",
+            Self::Unsupported(_) => "iso.unsupported_layout",
+            Self::InvalidName(_) => "iso.invalid_file_name",
+            Self::MissingDirectory(_) => "iso.missing_directory",
+            Self::BrokenBoot(_) => "iso.broken_boot",
+            Self::InvalidVolumeLabel(_) => "iso.invalid_volume_label",
+            Self::BackendUnavailable(_) => "iso.backend_unavailable",
+            Self::BackendFailed(_) => "iso.backend_failed",
+        }
+    }
+}
+
+impl fmt::Display for IsoInjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, detail) => write!(f, "{} ({}: {})", self.code(), path.display(), detail),
+            Self::NotIso9660(path) => write!(f, "{} ({})", self.code(), path.display()),
+            Self::NotInstallerIso(path) => {
+                write!(f, "{} ({} has no .cd-info)", self.code(), path.display())
+            }
+            Self::Unsupported(what) => write!(f, "{} ({})", self.code(), what),
+            Self::BrokenBoot(what) => write!(f, "{} ({} after remastering)", self.code(), what),
+            Self::BackendUnavailable(what) | Self::BackendFailed(what) => {
+                write!(f, "{} ({})", self.code(), what)
+            }
+            Self::InvalidName(name)
+            | Self::MissingDirectory(name)
+            | Self::InvalidVolumeLabel(name) => {
+                write!(f, "{} ({:?})", self.code(), name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for IsoInjectError {}
+
+/* ===================== INJECTION ===================== */
+
+/// Writes a copy of the ISO image at `iso` to `out` with `files`, given as
+/// `(path, contents)`, added to it; same-named files are replaced. A path is
+/// relative to the root directory, e.g. `answer.toml` or
+/// `proxmox/packages/extra.deb`, and its directories must already exist.
+///
+/// The files and new copies of the directories they go into, up to the root,
+/// are appended to the image and the volume descriptors and path tables are
+/// pointed at them, so the original contents and boot records stay untouched.
+/// The primary and Joliet trees are both updated, Rock Ridge names are
+/// written when the image uses them, and the MBR and GPT of hybrid images are
+/// grown to cover the appended data. The El Torito BIOS and UEFI entries,
+/// MBR and GPT of the result are then checked against the original's with
+/// [`BootRecords`](crate::iso::boot::BootRecords), failing with [`IsoInjectError::BrokenBoot`] rather than
+/// writing an image that no longer boots on some machines.
+///
+/// `out` is written through a `.partial` file and only replaced on success;
+/// it may be `iso` itself.
+pub fn inject_files(iso: &Path, out: &Path, files: &[(&str, &[u8])]) -> Result<(), IsoInjectError> {
+    check_file_names(files)?;
+
+    let mut partial = out.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+
+    let result = File::open(iso)
+        .and_then(|mut original| {
+            let boot = read_boot_records(&mut original);
+            std::fs::copy(iso, &partial)?;
+            Ok(boot)
+        })
+        .map_err(|e| IsoInjectError::Io(iso.to_path_buf(), e.to_string()))
+        .and_then(|boot| {
+            let boot = boot.map_err(|e| match e {
+                IsoInjectError::Io(_, detail) => IsoInjectError::Io(iso.to_path_buf(), detail),
+                other => other,
+            })?;
+            let mut image = File::options()
+                .read(true)
+                .write(true)
+                .open(&partial)
+                .map_err(|e| IsoInjectError::Io(partial.clone(), e.to_string()))?;
+            patch_image(&mut image, files)
+                .and_then(|_| boot.check_preserved_in(&read_boot_records(&mut image)?))
+                .map_err(|e| match e {
+                    IsoInjectError::Io(_, detail) => IsoInjectError::Io(partial.clone(), detail),
+                    IsoInjectError::NotIso9660(_) => IsoInjectError::NotIso9660(iso.to_path_buf()),
+                    other => other,
+                })
+        })
+        .and_then(|_| {
+            std::fs::rename(&partial, out)
+                .map_err(|e| IsoInjectError::Io(out.to_path_buf(), e.to_string()))
+        });
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+    result
+}
+
+/// Checks that the paths of `files` have no empty, `.` or `..` component and
+/// only printable ASCII characters.
+pub(crate) fn check_file_names(files: &[(&str, &[u8])]) -> Result<(), IsoInjectError> {
+    for (name, _) in files {
+        let valid = |component: &str| {
+            !component.is_empty()
+                && component != "."
+                && component != ".."
+                && component.bytes().all(|b| b.is_ascii_graphic())
+        };
+        if !name.split('/').all(valid) {
+            return Err(IsoInjectError::InvalidName((*name).to_owned()));
+        }
+    }
+    Ok(())
+}
+
+/// Writes a copy of the Proxmox VE ISO at `iso` to `out` that installs with
+/// the answer TOML `toml` and, when given, the first-boot script `first_boot`,
+/// as `proxmox-auto-install-assistant prepare-iso --fetch-from iso` does.
+/// The `extra` files, given as for [`inject_files`], are added as well, e.g.
+/// packages for the package pool. `writer` writes the image.
+pub fn embed_answer(
+    writer: &dyn IsoWriter,
+    iso: &Path,
+    out: &Path,
+    toml: &str,
+    first_boot: Option<&FirstBootScript>,
+    extra: &[(&str, &[u8])],
+) -> Result<(), IsoInjectError> {
+    let mode = AutoInstallerMode::iso().to_toml_string();
+    let mut files: Vec<(&str, &[u8])> = vec![
+        (AUTO_INSTALLER_MODE_FILE_NAME, mode.as_bytes()),
+        (ISO_ANSWER_FILE_NAME, toml.as_bytes()),
+    ];
+    if let Some(script) = first_boot {
+        files.push((ISO_FIRST_BOOT_FILE_NAME, script.contents.as_bytes()));
+    }
+    files.extend_from_slice(extra);
+    writer.write(iso, out, &files)
+}
+
+/// Writes a copy of the Proxmox VE ISO at `iso` to `out` whose installer
+/// fetches its answer over HTTP as configured by `mode`, as
+/// `proxmox-auto-install-assistant prepare-iso --fetch-from http` does,
+/// with the `extra` files added as in [`embed_answer`].
+pub fn embed_http_settings(
+    writer: &dyn IsoWriter,
+    iso: &Path,
+    out: &Path,
+    mode: &AutoInstallerMode,
+    extra: &[(&str, &[u8])],
+) -> Result<(), IsoInjectError> {
+    let mode = mode.to_toml_string();
+    let mut files: Vec<(&str, &[u8])> = vec![(AUTO_INSTALLER_MODE_FILE_NAME, mode.as_bytes())];
+    files.extend_from_slice(extra);
+    writer.write(iso, out, &files)
+}
+
+/// One directory tree of the image: the primary one or a Joliet one.
+struct Tree {
+    descriptor_sector: u64,
+    descriptor: Vec<u8>,
+    joliet: bool,
+}
+
+/// A file appended to the image.
+struct Appended<'a> {
+    /// Directories leading to the file from the root, e.g. `["proxmox", "packages"]`.
+    dirs: Vec<&'a str>,
+    name: &'a str,
+    sector: u32,
+    size: u32,
+}
+
+fn patch_image(image: &mut File, files: &[(&str, &[u8])]) -> Result<(), IsoInjectError> {
+    let old_len = image.metadata().map_err(io_error)?.len();
+    let trees = read_trees(image)?;
+    let descriptor_sectors = descriptor_sectors(image)?;
+
+    let mut end = old_len.div_ceil(SECTOR_SIZE) * SECTOR_SIZE;
+    let mut appended = Vec::with_capacity(files.len());
+    for (path, contents) in files {
+        let size = u32::try_from(contents.len())
+            .map_err(|_| IsoInjectError::Unsupported("file larger than 4 GiB"))?;
+        write_at(image, end, contents)?;
+        let (dirs, name) = match path.rsplit_once('/') {
+            Some((dirs, name)) => (dirs.split('/').collect(), name),
+            None => (Vec::new(), *path),
+        };
+        appended.push(Appended {
+            dirs,
+            name,
+            sector: sector_of(end)?,
+            size,
+        });
+        end += u64::from(size).div_ceil(SECTOR_SIZE) * SECTOR_SIZE;
+    }
+    let appended: Vec<&Appended> = appended.iter().collect();
+
+    for mut tree in trees {
+        let root = read_root(image, &tree)?;
+        let mut writer = DirectoryWriter {
+            image: &mut *image,
+            joliet: tree.joliet,
+            skip: if tree.joliet {
+                None
+            } else {
+                rock_ridge_skip(&root[0])
+            },
+            end,
+            moved: Vec::new(),
+        };
+        let (sector, size) = writer.write(&root, &appended, 0)?;
+        let DirectoryWriter {
+            end: new_end,
+            moved,
+            ..
+        } = writer;
+        end = new_end;
+
+        let record = &mut tree.descriptor[ROOT_RECORD_OFFSET..ROOT_RECORD_OFFSET + 34];
+        put_both_u32(&mut record[2..10], sector);
+        put_both_u32(&mut record[10..18], size);
+        update_path_tables(image, &tree.descriptor, &moved)?;
+        write_at(
+            image,
+            tree.descriptor_sector * SECTOR_SIZE,
+            &tree.descriptor,
+        )?;
+    }
+
+    let end = grow_partition_tables(image, old_len, end)?;
+    image.set_len(end).map_err(io_error)?;
+
+    let total_sectors = sector_of(end)?;
+    for sector in descriptor_sectors {
+        let mut descriptor = read_at(image, sector * SECTOR_SIZE, SECTOR_SIZE as usize)?;
+        put_both_u32(&mut descriptor[80..88], total_sectors);
+        write_at(image, sector * SECTOR_SIZE, &descriptor)?;
+    }
+
+    image.flush().map_err(io_error)
+}
+
+/* ===================== VOLUME DESCRIPTORS ===================== */
+
+/// The primary tree and any Joliet trees, in descriptor order.
+fn read_trees(image: &mut File) -> Result<Vec<Tree>, IsoInjectError> {
+    let mut trees = Vec::new();
+    for sector in descriptor_sectors(image)? {
+        let descriptor = read_at(image, sector * SECTOR_SIZE, SECTOR_SIZE as usize)?;
+        let joliet = match descriptor[0] {
+            1 => false,
+            2 if matches!(&descriptor[88..91], b"%/@" | b"%/C" | b"%/E") => true,
+            _ => continue,
+        };
+        trees.push(Tree {
+            descriptor_sector: sector,
+            descriptor,
+            joliet,
+        });
+    }
+
+    if !trees.iter().any(|t| !t.joliet) {
+        return Err(IsoInjectError::NotIso9660(PathBuf::new()));
+    }
+    Ok(trees)
+}
+
+/// Checks that `label` can be a volume identifier: 1 to 32 upper-case
+/// letters, digits, `_` or `-`, e.g. `PVE-AUTO-NODE01`.
+pub fn check_volume_label(label: &str) -> Result<(), IsoInjectError> {
+    let valid = (1..=MAX_VOLUME_LABEL_LEN).contains(&label.len())
+        && label
+            .bytes()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_' || b == b'-');
+    if !valid {
+        return Err(IsoInjectError::InvalidVolumeLabel(label.to_owned()));
+    }
+    Ok(())
+}
+
+/// Sets the volume identifier of the image at `iso` to `label`, in place.
+///
+/// The primary descriptor takes the whole label; Joliet descriptors, which
+/// hold 16 UCS-2 characters, take its first 16.
+pub fn set_volume_label(iso: &Path, label: &str) -> Result<(), IsoInjectError> {
+    check_volume_label(label)?;
+    let with_path = |e: IsoInjectError| match e {
+        IsoInjectError::Io(_, detail) => IsoInjectError::Io(iso.to_path_buf(), detail),
+        IsoInjectError::NotIso9660(_) => IsoInjectError::NotIso9660(iso.to_path_buf()),
+        other => other,
+    };
+    let mut image = File::options()
+        .read(true)
+        .write(true)
+        .open(iso)
+        .map_err(|e| IsoInjectError::Io(iso.to_path_buf(), e.to_string()))?;
+
+    for mut tree in read_trees(&mut image).map_err(with_path)? {
+        let field = &mut tree.descriptor[40..72];
+        if tree.joliet {
+            let units = label.encode_utf16().chain(std::iter::repeat(0x20));
+            for (unit, bytes) in units.zip(field.chunks_mut(2)) {
+                bytes.copy_from_slice(&unit.to_be_bytes());
+            }
+        } else {
+            field.fill(b' ');
+            field[..label.len()].copy_from_slice(label.as_bytes());
+        }
+        write_at(
+            &mut image,
+            tree.descriptor_sector * SECTOR_SIZE,
+            &tree.descriptor,
+        )
+        .map_err(with_path)?;
+    }
+    image.flush().map_err(io_error).map_err(with_path)
+}
+
+/// Sectors of the primary and supplementary volume descriptors.
+fn descriptor_sectors(image: &mut File) -> Result<Vec<u64>, IsoInjectError> {
+    let mut sectors = Vec::new();
+    for sector in FIRST_DESCRIPTOR_SECTOR..FIRST_DESCRIPTOR_SECTOR + MAX_DESCRIPTORS {
+        let header = match read_at(image, sector * SECTOR_SIZE, 6) {
+            Ok(header) => header,
+            Err(_) => break,
+        };
+        if &header[1..6] != b"CD001" {
+            break;
+        }
+        match header[0] {
+            255 => return Ok(sectors),
+            1 | 2 => sectors.push(sector),
+            _ => {}
+        }
+    }
+
+    if sectors.is_empty() {
+        return Err(IsoInjectError::NotIso9660(PathBuf::new()));
+    }
+    Err(IsoInjectError::Unsupported(
+        "volume descriptor set without terminator",
+    ))
+}
+
+/// Points the entries of the descriptor's path tables for the `moved`
+/// directories, given as `(old sector, new sector)`, at their new extents.
+fn update_path_tables(
+    image: &mut File,
+    descriptor: &[u8],
+    moved: &[(u32, u32)],
+) -> Result<(), IsoInjectError> {
+    let size = u32::from_le_bytes(array(&descriptor[132..136])) as usize;
+    let tables = [
+        (u32::from_le_bytes(array(&descriptor[140..144])), false),
+        (u32::from_le_bytes(array(&descriptor[144..148])), false),
+        (u32::from_be_bytes(array(&descriptor[148..152])), true),
+        (u32::from_be_bytes(array(&descriptor[152..156])), true),
+    ];
+    for (location, big_endian) in tables {
+        if location == 0 {
+            continue;
+        }
+        let offset = u64::from(location) * SECTOR_SIZE;
+        let mut table = read_at(image, offset, size)?;
+        let mut at = 0;
+        while at + 8 <= table.len() && table[at] != 0 {
+            let field = &mut table[at + 2..at + 6];
+            let sector = if big_endian {
+                u32::from_be_bytes(array(field))
+            } else {
+                u32::from_le_bytes(array(field))
+            };
+            if let Some(&(_, new)) = moved.iter().find(|(old, _)| *old == sector) {
+                field.copy_from_slice(&if big_endian {
+                    new.to_be_bytes()
+                } else {
+                    new.to_le_bytes()
+                });
+            }
+            let len = usize::from(table[at]);
+            at += 8 + len + len % 2;
+        }
+        write_at(image, offset, &table)?;
+    }
+    Ok(())
+}
+
+/* ===================== DIRECTORY RECORDS ===================== */
+
+/// The records of a tree's root directory.
+fn read_root(image: &mut File, tree: &Tree) -> Result<Vec<Vec<u8>>, IsoInjectError> {
+    read_directory(
+        image,
+        &tree.descriptor[ROOT_RECORD_OFFSET..ROOT_RECORD_OFFSET + 34],
+    )
+}
+
+/// The records of the directory `record` points to, starting with `.` and `..`.
+pub(crate) fn read_directory(
+    image: &mut File,
+    record: &[u8],
+) -> Result<Vec<Vec<u8>>, IsoInjectError> {
+    let sector = u32::from_le_bytes(array(&record[2..6]));
+    let size = u32::from_le_bytes(array(&record[10..14]));
+    let extent = read_at(image, u64::from(sector) * SECTOR_SIZE, size as usize)?;
+    let records = parse_records(&extent)?;
+
+    if records.len() < 2 || identifier(&records[0]) != [0] || identifier(&records[1]) != [1] {
+        return Err(IsoInjectError::Unsupported(
+            "directory without `.` and `..`",
+        ));
+    }
+    Ok(records)
+}
+
+/// Splits a directory extent into its records; records never cross sectors.
+fn parse_records(extent: &[u8]) -> Result<Vec<Vec<u8>>, IsoInjectError> {
+    let mut records = Vec::new();
+    for sector in extent.chunks(SECTOR_SIZE as usize) {
+        let mut offset = 0;
+        while offset < sector.len() && sector[offset] != 0 {
+            let len = usize::from(sector[offset]);
+            let record = sector
+                .get(offset..offset + len)
+                .filter(|r| len >= 34 && 33 + usize::from(r[32]) <= len)
+                .ok_or(IsoInjectError::Unsupported("malformed directory record"))?;
+            records.push(record.to_vec());
+            offset += len;
+        }
+    }
+    Ok(records)
+}
+
+fn identifier(record: &[u8]) -> &[u8] {
+    &record[33..33 + usize::from(record[32])]
+}
+
+/// The System Use field of a record, after the padding of even identifiers.
+fn system_use(record: &[u8]) -> &[u8] {
+    let len = usize::from(record[32]);
+    let start = 33 + len + usize::from(len.is_multiple_of(2));
+    record.get(start..).unwrap_or_default()
+}
+
+/// Bytes to skip at the start of every System Use field when the root `.`
+/// record carries a SUSP `SP` entry, i.e. the tree uses Rock Ridge.
+fn rock_ridge_skip(dot: &[u8]) -> Option<usize> {
+    match system_use(dot) {
+        [b'S', b'P', 7, _, 0xBE, 0xEF, skip, ..] => Some(usize::from(*skip)),
+        _ => None,
+    }
+}
+
+/// The Rock Ridge `NM` name of a record, when it has one.
+fn rock_ridge_name(record: &[u8], skip: usize) -> Option<&[u8]> {
+    let mut entries = system_use(record).get(skip..)?;
+    while let [a, b, len, ..] = *entries {
+        let len = usize::from(len);
+        if len < 4 || len > entries.len() {
+            return None;
+        }
+        if [a, b] == *b"NM" && len >= 5 {
+            return Some(&entries[5..len]);
+        }
+        entries = &entries[len..];
+    }
+    None
+}
+
+/// The identifier of `name` in the primary tree (`ANSWER.TOML;1`) or, UCS-2
+/// encoded, in a Joliet tree (`answer.toml;1`).
+fn encode_identifier(name: &str, joliet: bool) -> Vec<u8> {
+    if joliet {
+        return format!("{};1", name)
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect();
+    }
+    let name = name.to_ascii_uppercase();
+    if name.contains('.') {
+        format!("{};1", name).into_bytes()
+    } else {
+        format!("{}.;1", name).into_bytes()
+    }
+}
+
+fn directory_record(
+    identifier: &[u8],
+    sector: u32,
+    size: u32,
+    date: &[u8],
+    system_use: &[u8],
+) -> Vec<u8> {
+    let mut record = vec![0; 33];
+    put_both_u32(&mut record[2..10], sector);
+    put_both_u32(&mut record[10..18], size);
+    record[18..25].copy_from_slice(date);
+    record[28..30].copy_from_slice(&1u16.to_le_bytes());
+    record[30..32].copy_from_slice(&1u16.to_be_bytes());
+    record[32] = identifier.len() as u8;
+    record.extend_from_slice(identifier);
+    if identifier.len().is_multiple_of(2) {
+        record.push(0);
+    }
+    record.extend_from_slice(system_use);
+    if record.len() % 2 == 1 {
+        record.push(0);
+    }
+    record[0] = record.len() as u8;
+    record
+}
+
+/// Rock Ridge `PX` and `NM` entries naming a read-only regular file.
+fn rock_ridge_entries(name: &str, skip: usize) -> Vec<u8> {
+    let mut entries = vec![0; skip];
+    entries.extend_from_slice(&[b'P', b'X', 36, 1]);
+    for value in [INJECTED_FILE_MODE, 1, 0, 0] {
+        let mut field = [0; 8];
+        put_both_u32(&mut field, value);
+        entries.extend_from_slice(&field);
+    }
+    entries.extend_from_slice(&[b'N', b'M', 5 + name.len() as u8, 1, 0]);
+    entries.extend_from_slice(name.as_bytes());
+    entries
+}
+
+/// Appends rewritten directories of one tree to the image.
+struct DirectoryWriter<'a> {
+    image: &'a mut File,
+    joliet: bool,
+    /// The Rock Ridge skip of the tree, see [`rock_ridge_skip`].
+    skip: Option<usize>,
+    /// Where the next directory is written.
+    end: u64,
+    /// `(old sector, new sector)` of every rewritten directory.
+    moved: Vec<(u32, u32)>,
+}
+
+impl DirectoryWriter<'_> {
+    /// Writes a copy of the directory of `records`, `depth` levels below the
+    /// root, with the `files` that belong in it added and the subdirectories
+    /// holding the others rewritten first.
+    ///
+    /// The `..` records of rewritten subdirectories keep pointing at the
+    /// original parent, which stays intact, as do those of the root's
+    /// untouched subdirectories.
+    ///
+    /// # Returns
+    /// The sector and size of the new directory.
+    fn write(
+        &mut self,
+        records: &[Vec<u8>],
+        files: &[&Appended],
+        depth: usize,
+    ) -> Result<(u32, u32), IsoInjectError> {
+        let date = records[0][18..25].to_vec();
+        let mut entries: Vec<Vec<u8>> = records[2..].to_vec();
+
+        let mut subdirectories: Vec<&str> = files
+            .iter()
+            .filter_map(|file| file.dirs.get(depth).copied())
+            .collect();
+        subdirectories.sort_unstable();
+        subdirectories.dedup();
+        for name in subdirectories {
+            let inside: Vec<&Appended> = files
+                .iter()
+                .filter(|file| file.dirs.get(depth) == Some(&name))
+                .copied()
+                .collect();
+            let index = entries
+                .iter()
+                .position(|r| r[25] & 0x02 != 0 && record_name(r, self.joliet, self.skip) == name)
+                .ok_or_else(|| {
+                    IsoInjectError::MissingDirectory(inside[0].dirs[..=depth].join("/"))
+                })?;
+            let children = read_directory(self.image, &entries[index])?;
+            let (sector, size) = self.write(&children, &inside, depth + 1)?;
+            put_both_u32(&mut entries[index][2..10], sector);
+            put_both_u32(&mut entries[index][10..18], size);
+        }
+
+        for file in files.iter().filter(|file| file.dirs.len() == depth) {
+            let identifier = encode_identifier(file.name, self.joliet);
+            entries.retain(|record| {
+                self::identifier(record) != identifier.as_slice()
+                    && self.skip.is_none_or(|skip| {
+                        rock_ridge_name(record, skip) != Some(file.name.as_bytes())
+                    })
+            });
+            let system_use = self
+                .skip
+                .map(|skip| rock_ridge_entries(file.name, skip))
+                .unwrap_or_default();
+            if system_use.len() > (255 - 34usize).saturating_sub(identifier.len()) {
+                return Err(IsoInjectError::InvalidName(file.name.to_owned()));
+            }
+            entries.push(directory_record(
+                &identifier,
+                file.sector,
+                file.size,
+                &date,
+                &system_use,
+            ));
+        }
+        entries.sort_by(|a, b| identifier(a).cmp(identifier(b)));
+
+        let old_sector = u32::from_le_bytes(array(&records[0][2..6]));
+        let sector = sector_of(self.end)?;
+        let mut records = vec![records[0].clone(), records[1].clone()];
+        records.extend(entries);
+        let size = layout(&records).len() as u32;
+        put_both_u32(&mut records[0][2..10], sector);
+        put_both_u32(&mut records[0][10..18], size);
+        if depth == 0 {
+            put_both_u32(&mut records[1][2..10], sector);
+            put_both_u32(&mut records[1][10..18], size);
+        }
+        write_at(self.image, self.end, &layout(&records))?;
+
+        self.end += u64::from(size);
+        self.moved.push((old_sector, sector));
+        Ok((sector, size))
+    }
+}
+
+/// Packs records into whole sectors without splitting any of them.
+fn layout(records: &[Vec<u8>]) -> Vec<u8> {
+    let sector = SECTOR_SIZE as usize;
+    let mut extent = Vec::new();
+    for record in records {
+        let used = extent.len() % sector;
+        if used + record.len() > sector {
+            extent.resize(extent.len() + sector - used, 0);
+        }
+        extent.extend_from_slice(record);
+    }
+    extent.resize(extent.len().div_ceil(sector) * sector, 0);
+    extent
+}
+
+/* ===================== READING ===================== */
+
+/// The volume identifier of the image's primary volume descriptor, e.g. `PVE`.
+pub fn volume_label(image: &mut File) -> Result<String, IsoInjectError> {
+    let primary = read_trees(image)?
+        .into_iter()
+        .find(|t| !t.joliet)
+        .expect("read_trees returns a primary tree");
+    Ok(String::from_utf8_lossy(&primary.descriptor[40..72])
+        .trim_end_matches([' ', '\0'])
+        .to_string())
+}
+
+/// The root directory of the tree with the original file names.
+#[derive(Debug)]
+pub(crate) struct NamedRoot {
+    pub(crate) root: Vec<Vec<u8>>,
+    pub(crate) joliet: bool,
+    /// Rock Ridge skip of the primary tree.
+    pub(crate) skip: Option<usize>,
+}
+
+pub(crate) fn named_root(image: &mut File) -> Result<NamedRoot, IsoInjectError> {
+    let trees = read_trees(image)?;
+    let primary = trees.iter().find(|t| !t.joliet).expect("primary tree");
+    let root = read_root(image, primary)?;
+    // Without Rock Ridge, a Joliet tree has the original names.
+    Ok(
+        match (rock_ridge_skip(&root[0]), trees.iter().find(|t| t.joliet)) {
+            (None, Some(joliet)) => NamedRoot {
+                root: read_root(image, joliet)?,
+                joliet: true,
+                skip: None,
+            },
+            (skip, _) => NamedRoot {
+                root,
+                joliet: false,
+                skip,
+            },
+        },
+    )
+}
+
+/// The Rock Ridge name of `record` when the tree has them (`skip`), else its
+/// decoded identifier.
+pub(crate) fn record_name(record: &[u8], joliet: bool, skip: Option<usize>) -> String {
+    match skip.and_then(|skip| rock_ridge_name(record, skip)) {
+        Some(name) => String::from_utf8_lossy(name).into_owned(),
+        None => decode_identifier(identifier(record), joliet),
+    }
+}
+
+/// The name of a file identifier, the reverse of [`encode_identifier`].
+fn decode_identifier(identifier: &[u8], joliet: bool) -> String {
+    let name = if joliet {
+        let units: Vec<u16> = identifier
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(identifier).to_ascii_lowercase()
+    };
+    let name = name.split_once(';').map_or(name.as_str(), |(name, _)| name);
+    name.trim_end_matches('.').to_string()
+}
+
+/* ===================== PARTITION TABLES ===================== */
+
+/// Grows the partitions of a hybrid image that ended at `old_len` to the
+/// new end of the image, moving a backup GPT to the last blocks.
+///
+/// # Returns
+/// The new, sector-aligned length of the image.
+fn grow_partition_tables(image: &mut File, old_len: u64, end: u64) -> Result<u64, IsoInjectError> {
+    let old_blocks = old_len / BLOCK_SIZE;
+    let mut end = end;
+
+    if old_len >= 2 * BLOCK_SIZE {
+        let mut header = read_at(image, BLOCK_SIZE, BLOCK_SIZE as usize)?;
+        if &header[..8] == b"EFI PART"
+            && u64::from_le_bytes(array(&header[32..40])) == old_blocks - 1
+        {
+            let header_size = u32::from_le_bytes(array(&header[12..16])) as usize;
+            let entries_lba = u64::from_le_bytes(array(&header[72..80]));
+            let entries_len = u64::from(u32::from_le_bytes(array(&header[80..84])))
+                * u64::from(u32::from_le_bytes(array(&header[84..88])));
+            if !(92..=BLOCK_SIZE as usize).contains(&header_size) {
+                return Err(IsoInjectError::Unsupported("GPT header size"));
+            }
+            let entries = read_at(image, entries_lba * BLOCK_SIZE, entries_len as usize)?;
+            let entries_blocks = entries_len.div_ceil(BLOCK_SIZE);
+
+            end = (end + (entries_blocks + 1) * BLOCK_SIZE).div_ceil(SECTOR_SIZE) * SECTOR_SIZE;
+            let backup_lba = end / BLOCK_SIZE - 1;
+            let backup_entries_lba = backup_lba - entries_blocks;
+
+            header[32..40].copy_from_slice(&backup_lba.to_le_bytes());
+            header[48..56].copy_from_slice(&(backup_entries_lba - 1).to_le_bytes());
+            let mut backup = header.clone();
+            seal_gpt_header(&mut header, header_size);
+            write_at(image, BLOCK_SIZE, &header)?;
+
+            backup[24..32].copy_from_slice(&backup_lba.to_le_bytes());
+            backup[32..40].copy_from_slice(&1u64.to_le_bytes());
+            backup[72..80].copy_from_slice(&backup_entries_lba.to_le_bytes());
+            seal_gpt_header(&mut backup, header_size);
+            write_at(image, backup_entries_lba * BLOCK_SIZE, &entries)?;
+            write_at(image, backup_lba * BLOCK_SIZE, &backup)?;
+        }
+    }
+
+    let mut mbr = read_at(image, 0, BLOCK_SIZE as usize)?;
+    if mbr[510..512] == [0x55, 0xAA] {
+        let new_blocks = end / BLOCK_SIZE;
+        for entry in mbr[446..510].chunks_mut(16) {
+            let start = u64::from(u32::from_le_bytes(array(&entry[8..12])));
+            let count = u64::from(u32::from_le_bytes(array(&entry[12..16])));
+            if entry[4] != 0 && count != 0 && start + count == old_blocks {
+                let count = (new_blocks - start).min(u64::from(u32::MAX)) as u32;
+                entry[12..16].copy_from_slice(&count.to_le_bytes());
+            }
+        }
+        write_at(image, 0, &mbr)?;
+    }
+
+    Ok(end)
+}
+
+/// Recomputes the CRC32 of a GPT header of `size` bytes.
+fn seal_gpt_header(header: &mut [u8], size: usize) {
+    header[16..20].fill(0);
+    let crc = crc32(&header[..size]);
+    header[16..20].copy_from_slice(&crc.to_le_bytes());
+}
+
+/// CRC-32 (IEEE 802.3), as used by GPT.
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (!(crc & 1)).wrapping_add(1));
+        }
+    }
+    !crc
+}
+
+/* ===================== I/O ===================== */
+
+fn io_error(e: std::io::Error) -> IsoInjectError {
+    IsoInjectError::Io(PathBuf::new(), e.to_string())
+}
+
+pub(crate) fn read_at(
+    image: &mut File,
+    offset: u64,
+    len: usize,
+) -> Result<Vec<u8>, IsoInjectError> {
+    let mut buf = vec![0; len];
+    image.seek(SeekFrom::Start(offset)).map_err(io_error)?;
+    image.read_exact(&mut buf).map_err(io_error)?;
+    Ok(buf)
+}
+
+fn write_at(image: &mut File, offset: u64, bytes: &[u8]) -> Result<(), IsoInjectError> {
+    image.seek(SeekFrom::Start(offset)).map_err(io_error)?;
+    image.write_all(bytes).map_err(io_error)
+}
+
+fn sector_of(offset: u64) -> Result<u32, IsoInjectError> {
+    u32::try_from(offset / SECTOR_SIZE)
+        .map_err(|_| IsoInjectError::Unsupported("image larger than 8 TiB"))
+}
+
+/// Writes `value` in ISO 9660 both-byte order: little-endian, then big-endian.
+fn put_both_u32(field: &mut [u8], value: u32) {
+    field[..4].copy_from_slice(&value.to_le_bytes());
+    field[4..8].copy_from_slice(&value.to_be_bytes());
+}
+
+pub(crate) fn array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    bytes.try_into().expect("slice length checked by caller")
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::iso::writer::NativeWriter;
+
+    const ROOT_SECTOR: u32 = 20;
+    const PATH_TABLE_SECTOR: u32 = 19;
+    const README_SECTOR: u32 = 21;
+    const OUTER_SECTOR: u32 = 22;
+    const INNER_SECTOR: u32 = 23;
+    const NESTED_FILE_SECTOR: u32 = 24;
+    const IMAGE_SECTORS: u32 = 26;
+
+    /// A minimal image: a primary descriptor, optionally a Joliet one, a path
+    /// table and a root directory holding `readme.txt`, optionally Rock Ridge
+    /// named, plus a protective MBR and GPT.
+    pub(crate) fn image(rock_ridge: bool, joliet: bool) -> Vec<u8> {
+        let sector = SECTOR_SIZE as usize;
+        let mut image = vec![0; IMAGE_SECTORS as usize * sector];
+        let date = [125, 10, 15, 12, 0, 0, 0];
+
+        let mut sectors = vec![(16, 1u8)];
+        if joliet {
+            sectors.push((17, 2));
+        }
+        for &(at, kind) in &sectors {
+            let d = &mut image[at * sector..(at + 1) * sector];
+            d[0] = kind;
+            d[1..6].copy_from_slice(b"CD001");
+            d[6] = 1;
+            put_both_u32(&mut d[80..88], IMAGE_SECTORS);
+            put_both_u32(&mut d[132..140], 10);
+            if kind == 2 {
+                d[88..91].copy_from_slice(b"%/E");
+            }
+            d[140..144].copy_from_slice(&PATH_TABLE_SECTOR.to_le_bytes());
+            let root = directory_record(&[0], ROOT_SECTOR, SECTOR_SIZE as u32, &date, &[]);
+            d[ROOT_RECORD_OFFSET..ROOT_RECORD_OFFSET + 34].copy_from_slice(&root);
+        }
+        let end = (16 + sectors.len()) * sector;
+        image[end] = 255;
+        image[end + 1..end + 6].copy_from_slice(b"CD001");
+
+        let path_table = PATH_TABLE_SECTOR as usize * sector;
+        image[path_table] = 1;
+        image[path_table + 2..path_table + 6].copy_from_slice(&ROOT_SECTOR.to_le_bytes());
+
+        let sp: &[u8] = if rock_ridge {
+            &[b'S', b'P', 7, 1, 0xBE, 0xEF, 0]
+        } else {
+            &[]
+        };
+        let readme_su = if rock_ridge {
+            rock_ridge_entries("readme.txt", 0)
+        } else {
+            Vec::new()
+        };
+        let records = [
+            directory_record(&[0], ROOT_SECTOR, SECTOR_SIZE as u32, &date, sp),
+            directory_record(&[1], ROOT_SECTOR, SECTOR_SIZE as u32, &date, &[]),
+            directory_record(b"README.TXT;1", README_SECTOR, 6, &date, &readme_su),
+        ];
+        let root = layout(&records);
+        let at = ROOT_SECTOR as usize * sector;
+        image[at..at + root.len()].copy_from_slice(&root);
+        let at = README_SECTOR as usize * sector;
+        image[at..at + 6].copy_from_slice(b"readme");
+
+        let blocks = image.len() as u64 / BLOCK_SIZE;
+        let mbr = &mut image[446..462];
+        mbr[4] = 0xEE;
+        mbr[8..12].copy_from_slice(&1u32.to_le_bytes());
+        mbr[12..16].copy_from_slice(&(blocks as u32 - 1).to_le_bytes());
+        image[510..512].copy_from_slice(&[0x55, 0xAA]);
+
+        let mut header = vec![0; BLOCK_SIZE as usize];
+        header[..8].copy_from_slice(b"EFI PART");
+        header[12..16].copy_from_slice(&92u32.to_le_bytes());
+        header[24..32].copy_from_slice(&1u64.to_le_bytes());
+        header[32..40].copy_from_slice(&(blocks - 1).to_le_bytes());
+        header[72..80].copy_from_slice(&2u64.to_le_bytes());
+        header[80..84].copy_from_slice(&4u32.to_le_bytes());
+        header[84..88].copy_from_slice(&128u32.to_le_bytes());
+        header[88..92].copy_from_slice(&crc32(&[0; 512]).to_le_bytes());
+        seal_gpt_header(&mut header, 92);
+        image[512..1024].copy_from_slice(&header);
+        image
+    }
+
+    /// [`image`] without Joliet, plus a `proxmox/packages` directory with
+    /// its own path table entries.
+    pub(crate) fn pool_image(rock_ridge: bool) -> Vec<u8> {
+        nested_image(rock_ridge, ["proxmox", "packages"], None)
+    }
+
+    /// [`image`] without Joliet, plus the directories `outer/inner`, with
+    /// their own path table entries, and `file` in the inner one.
+    pub(crate) fn nested_image(
+        rock_ridge: bool,
+        [outer, inner]: [&str; 2],
+        file: Option<(&str, &[u8])>,
+    ) -> Vec<u8> {
+        let mut image = image(rock_ridge, false);
+        let sector = SECTOR_SIZE as usize;
+        let date = [125, 10, 15, 12, 0, 0, 0];
+        let system_use = |name: &str| {
+            if rock_ridge && !name.is_empty() {
+                rock_ridge_entries(name, 0)
+            } else {
+                Vec::new()
+            }
+        };
+        let directory = |identifier: &[u8], at: u32, name: &str| {
+            let mut record =
+                directory_record(identifier, at, SECTOR_SIZE as u32, &date, &system_use(name));
+            record[25] = 0x02;
+            record
+        };
+        let (outer_id, inner_id) = (outer.to_ascii_uppercase(), inner.to_ascii_uppercase());
+
+        let at = ROOT_SECTOR as usize * sector;
+        let mut root = parse_records(&image[at..at + sector]).unwrap();
+        root.push(directory(outer_id.as_bytes(), OUTER_SECTOR, outer));
+        root[2..].sort_by(|a, b| identifier(a).cmp(identifier(b)));
+        image[at..at + sector].copy_from_slice(&layout(&root));
+
+        let mut inner_records = vec![
+            directory(&[0], INNER_SECTOR, ""),
+            directory(&[1], OUTER_SECTOR, ""),
+        ];
+        if let Some((name, contents)) = file {
+            inner_records.push(directory_record(
+                &encode_identifier(name, false),
+                NESTED_FILE_SECTOR,
+                contents.len() as u32,
+                &date,
+                &system_use(name),
+            ));
+            let at = NESTED_FILE_SECTOR as usize * sector;
+            image[at..at + contents.len()].copy_from_slice(contents);
+        }
+        let outer_records = vec![
+            directory(&[0], OUTER_SECTOR, ""),
+            directory(&[1], ROOT_SECTOR, ""),
+            directory(inner_id.as_bytes(), INNER_SECTOR, inner),
+        ];
+        for (at, records) in [(OUTER_SECTOR, outer_records), (INNER_SECTOR, inner_records)] {
+            let at = at as usize * sector;
+            image[at..at + sector].copy_from_slice(&layout(&records));
+        }
+
+        let path_table = PATH_TABLE_SECTOR as usize * sector;
+        let mut entries = Vec::new();
+        for (identifier, at, parent) in [
+            (outer_id.as_bytes(), OUTER_SECTOR, 1u16),
+            (inner_id.as_bytes(), INNER_SECTOR, 2),
+        ] {
+            entries.extend_from_slice(&[identifier.len() as u8, 0]);
+            entries.extend_from_slice(&at.to_le_bytes());
+            entries.extend_from_slice(&parent.to_le_bytes());
+            entries.extend_from_slice(identifier);
+            if identifier.len() % 2 == 1 {
+                entries.push(0);
+            }
+        }
+        image[path_table + 10..path_table + 10 + entries.len()].copy_from_slice(&entries);
+        put_both_u32(
+            &mut image[16 * sector + 132..16 * sector + 140],
+            10 + entries.len() as u32,
+        );
+        image
+    }
+
+    pub(crate) fn inject(
+        image: &[u8],
+        name: &str,
+        files: &[(&str, &[u8])],
+    ) -> Result<Vec<u8>, IsoInjectError> {
+        let dir = std::env::temp_dir();
+        let iso = dir.join(format!(
+            "pveauto-inject-{}-{}.iso",
+            std::process::id(),
+            name
+        ));
+        let out = dir.join(format!(
+            "pveauto-inject-{}-{}-out.iso",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&iso, image).unwrap();
+        let result = inject_files(&iso, &out, files).map(|_| std::fs::read(&out).unwrap());
+        let _ = std::fs::remove_file(&iso);
+        let _ = std::fs::remove_file(&out);
+        result
+    }
+
+    /// The root records of the tree described at `descriptor_sector`.
+    fn root_records(image: &[u8], descriptor_sector: usize) -> Vec<Vec<u8>> {
+        let d = &image[descriptor_sector * SECTOR_SIZE as usize..];
+        let record = &d[ROOT_RECORD_OFFSET..ROOT_RECORD_OFFSET + 34];
+        let at = u32::from_le_bytes(array(&record[2..6])) as usize * SECTOR_SIZE as usize;
+        let size = u32::from_le_bytes(array(&record[10..14])) as usize;
+        parse_records(&image[at..at + size]).unwrap()
+    }
+
+    fn contents<'a>(image: &'a [u8], record: &[u8]) -> &'a [u8] {
+        let at = u32::from_le_bytes(array(&record[2..6])) as usize * SECTOR_SIZE as usize;
+        let size = u32::from_le_bytes(array(&record[10..14])) as usize;
+        &image[at..at + size]
+    }
+
+    fn find<'a>(records: &'a [Vec<u8>], identifier: &[u8]) -> Option<&'a Vec<u8>> {
+        records.iter().find(|r| self::identifier(r) == identifier)
+    }
+
+    /* ---------------- PRIMARY TREE ---------------- */
+
+    #[test]
+    fn files_are_added_to_the_root_directory() {
+        let out = inject(
+            &image(false, false),
+            "plain",
+            &[
+                ("answer.toml", b"[global]\n"),
+                ("proxmox-first-boot", b"#!/bin/sh\n"),
+            ],
+        )
+        .unwrap();
+
+        let records = root_records(&out, 16);
+        let names: Vec<_> = records[2..]
+            .iter()
+            .map(|r| identifier(r).to_vec())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                b"ANSWER.TOML;1".to_vec(),
+                b"PROXMOX-FIRST-BOOT.;1".to_vec(),
+                b"README.TXT;1".to_vec(),
+            ]
+        );
+        assert_eq!(contents(&out, &records[2]), b"[global]\n");
+        assert_eq!(contents(&out, &records[3]), b"#!/bin/sh\n");
+        assert_eq!(contents(&out, &records[4]), b"readme");
+
+        let root = u32::from_le_bytes(array(&records[0][2..6]));
+        assert_eq!(u32::from_le_bytes(array(&records[1][2..6])), root);
+        let path_table = PATH_TABLE_SECTOR as usize * SECTOR_SIZE as usize;
+        assert_eq!(
+            u32::from_le_bytes(array(&out[path_table + 2..path_table + 6])),
+            root
+        );
+
+        let volume = u32::from_le_bytes(array(&out[16 * 2048 + 80..16 * 2048 + 84]));
+        assert_eq!(u64::from(volume) * SECTOR_SIZE, out.len() as u64);
+    }
+
+    #[test]
+    fn existing_files_are_replaced() {
+        let once = inject(&image(true, false), "once", &[("answer.toml", b"old")]).unwrap();
+        let twice = inject(
+            &once,
+            "twice",
+            &[("answer.toml", b"new"), ("readme.txt", b"!")],
+        )
+        .unwrap();
+
+        let records = root_records(&twice, 16);
+        assert_eq!(records.len(), 4);
+        assert_eq!(
+            contents(&twice, find(&records, b"ANSWER.TOML;1").unwrap()),
+            b"new"
+        );
+        assert_eq!(
+            contents(&twice, find(&records, b"README.TXT;1").unwrap()),
+            b"!"
+        );
+    }
+
+    /* ---------------- SUBDIRECTORIES ---------------- */
+
+    #[test]
+    fn files_are_added_to_subdirectories() {
+        for rock_ridge in [false, true] {
+            let out = inject(
+                &pool_image(rock_ridge),
+                &format!("pool-{}", rock_ridge),
+                &[
+                    ("proxmox/packages/extra_1.0_all.deb", b"!<arch>\n"),
+                    ("answer.toml", b"[global]\n"),
+                ],
+            )
+            .unwrap();
+
+            let root = root_records(&out, 16);
+            assert!(find(&root, b"ANSWER.TOML;1").is_some());
+            let proxmox = find(&root, b"PROXMOX").unwrap();
+            let proxmox_records = parse_records(contents(&out, proxmox)).unwrap();
+            let packages = find(&proxmox_records, b"PACKAGES").unwrap();
+            let packages_records = parse_records(contents(&out, packages)).unwrap();
+            assert_eq!(packages_records.len(), 3);
+            let deb = find(&packages_records, b"EXTRA_1.0_ALL.DEB;1").unwrap();
+            assert_eq!(contents(&out, deb), b"!<arch>\n");
+            if rock_ridge {
+                assert_eq!(
+                    rock_ridge_name(deb, 0),
+                    Some(b"extra_1.0_all.deb".as_slice())
+                );
+            }
+
+            let sector = |record: &[u8]| u32::from_le_bytes(array(&record[2..6]));
+            assert_eq!(sector(&packages_records[0]), sector(packages));
+            assert_ne!(sector(packages), INNER_SECTOR);
+            let path_table = PATH_TABLE_SECTOR as usize * SECTOR_SIZE as usize;
+            let extents = [2, 12, 28]
+                .map(|at| u32::from_le_bytes(array(&out[path_table + at..path_table + at + 4])));
+            assert_eq!(
+                extents,
+                [sector(&root[0]), sector(proxmox), sector(packages)]
+            );
+        }
+
+        assert_eq!(
+            inject(
+                &pool_image(false),
+                "pool-missing",
+                &[("proxmox/missing/extra.deb", b"")]
+            )
+            .unwrap_err(),
+            IsoInjectError::MissingDirectory("proxmox/missing".into())
+        );
+    }
+
+    /* ---------------- ROCK RIDGE AND JOLIET ---------------- */
+
+    #[test]
+    fn rock_ridge_and_joliet_names_are_written() {
+        let out = inject(
+            &image(true, true),
+            "rr",
+            &[("auto-installer-mode.toml", b"mode = \"iso\"\n")],
+        )
+        .unwrap();
+
+        let primary = root_records(&out, 16);
+        let record = find(&primary, b"AUTO-INSTALLER-MODE.TOML;1").unwrap();
+        assert_eq!(
+            rock_ridge_name(record, 0),
+            Some(b"auto-installer-mode.toml".as_slice())
+        );
+
+        let joliet = root_records(&out, 17);
+        let record = find(
+            &joliet,
+            &encode_identifier("auto-installer-mode.toml", true),
+        )
+        .unwrap();
+        assert_eq!(contents(&out, record), b"mode = \"iso\"\n");
+        assert_eq!(rock_ridge_name(record, 0), None);
+    }
+
+    /* ---------------- PARTITION TABLES ---------------- */
+
+    #[test]
+    fn hybrid_partition_tables_cover_the_new_end() {
+        let out = inject(&image(false, false), "gpt", &[("answer.toml", b"x")]).unwrap();
+        let blocks = out.len() as u64 / BLOCK_SIZE;
+        assert_eq!(out.len() as u64 % SECTOR_SIZE, 0);
+
+        let mbr_count = u32::from_le_bytes(array(&out[458..462]));
+        assert_eq!(u64::from(mbr_count), blocks - 1);
+
+        let primary = &out[512..1024];
+        assert_eq!(u64::from_le_bytes(array(&primary[32..40])), blocks - 1);
+        let mut check = primary[..92].to_vec();
+        check[16..20].fill(0);
+        assert_eq!(crc32(&check), u32::from_le_bytes(array(&primary[16..20])));
+
+        let backup = &out[out.len() - 512..];
+        assert_eq!(&backup[..8], b"EFI PART");
+        assert_eq!(u64::from_le_bytes(array(&backup[24..32])), blocks - 1);
+        assert_eq!(u64::from_le_bytes(array(&backup[32..40])), 1);
+    }
+
+    #[test]
+    fn answer_is_embedded_with_iso_mode() {
+        let dir = std::env::temp_dir();
+        let iso = dir.join(format!("pveauto-embed-{}.iso", std::process::id()));
+        let out = dir.join(format!("pveauto-embed-{}-out.iso", std::process::id()));
+        std::fs::write(&iso, image(true, false)).unwrap();
+        let script = FirstBootScript {
+            path: "setup.sh".into(),
+            contents: "#!/bin/sh\n".into(),
+        };
+
+        embed_answer(&NativeWriter, &iso, &out, "[global]\n", Some(&script), &[]).unwrap();
+        let written = std::fs::read(&out).unwrap();
+        let records = root_records(&written, 16);
+        for (name, expected) in [
+            ("AUTO-INSTALLER-MODE.TOML;1", "mode = \"iso\"\n"),
+            ("ANSWER.TOML;1", "[global]\n"),
+            ("PROXMOX-FIRST-BOOT.;1", "#!/bin/sh\n"),
+        ] {
+            let record = find(&records, name.as_bytes()).unwrap();
+            assert_eq!(contents(&written, record), expected.as_bytes(), "{}", name);
+        }
+        std::fs::remove_file(iso).unwrap();
+        std::fs::remove_file(out).unwrap();
+    }
+
+    /* ---------------- READING ---------------- */
+
+    #[test]
+    fn volume_labels_are_set_in_every_descriptor() {
+        let path =
+            std::env::temp_dir().join(format!("pveauto-inject-{}-label.iso", std::process::id()));
+        std::fs::write(&path, image(false, true)).unwrap();
+        set_volume_label(&path, "PVE-AUTO-NODE01-RACK4").unwrap();
+        assert_eq!(
+            volume_label(&mut File::open(&path).unwrap()).unwrap(),
+            "PVE-AUTO-NODE01-RACK4"
+        );
+        let joliet = std::fs::read(&path).unwrap()[17 * 2048 + 40..17 * 2048 + 72].to_vec();
+        let units: Vec<u16> = joliet
+            .chunks(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        assert_eq!(String::from_utf16(&units).unwrap(), "PVE-AUTO-NODE01-");
+
+        for label in ["", "pve", "PVE AUTO", "PVE.AUTO", &"X".repeat(33)] {
+            assert_eq!(
+                set_volume_label(&path, label),
+                Err(IsoInjectError::InvalidVolumeLabel(label.to_owned()))
+            );
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn crc32_matches_the_reference_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    /* ---------------- ERRORS ---------------- */
+
+    #[test]
+    fn invalid_input_is_rejected() {
+        let err = inject(&vec![0; 40 * 2048], "blank", &[("answer.toml", b"x")]).unwrap_err();
+        assert_eq!(err.code(), "iso.not_iso9660");
+
+        for name in ["", "..", "a b", "/a", "a//b", "a/../b"] {
+            let err = inject(&image(false, false), "name", &[(name, b"x")]).unwrap_err();
+            assert_eq!(err, IsoInjectError::InvalidName(name.into()));
+        }
+        let err = inject(&image(false, false), "name", &[("a/b", b"x")]).unwrap_err();
+        assert_eq!(err, IsoInjectError::MissingDirectory("a".into()));
+
+        let missing = Path::new("/nonexistent/pveauto.iso");
+        let out = std::env::temp_dir().join("pveauto-inject-missing-out.iso");
+        let err = inject_files(missing, &out, &[]).unwrap_err();
+        assert_eq!(err.code(), "iso.io");
+        assert!(!out.exists());
+    }
+}
@@ -0,0 +1,223 @@
+use crate::iso::{
+    boot::{BootRecords, read_boot_records},
+    constants::{
+        AUTO_INSTALLER_MODE_FILE_NAME, ISO_ANSWER_FILE_NAME, ISO_CD_INFO_FILE_NAME,
+        ISO_FIRST_BOOT_FILE_NAME,
+    },
+    inject::IsoInjectError,
+    reader::{IsoEntry, IsoReader},
+    verify::{IsoVerifyError, sha256_file},
+};
+use std::path::Path;
+
+/// What a local ISO image is, as reported by `iso inspect`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IsoInspection {
+    /// Volume identifier of the primary volume descriptor, e.g. `PVE`.
+    pub volume_label: String,
+    /// Size of the image in bytes.
+    pub size: u64,
+    /// `PRODUCTLONG` of the ISO's `.cd-info`, e.g. `Proxmox VE`.
+    pub product: Option<String>,
+    /// `RELEASE` and `ISORELEASE` of the ISO's `.cd-info`, e.g. `8.2-1`.
+    pub version: Option<String>,
+    /// Whether `answer.toml` is on the ISO root.
+    pub answer_embedded: bool,
+    /// `mode` of the `auto-installer-mode.toml` on the ISO root, e.g. `iso`.
+    pub auto_installer_mode: Option<String>,
+    /// Whether a first-boot script is on the ISO root.
+    pub first_boot_embedded: bool,
+    /// The El Torito entries and partition tables the ISO boots from.
+    pub boot: BootRecords,
+    pub sha256: String,
+}
+
+/// Reads the ISO 9660 image at `iso` and reports its Proxmox release, label,
+/// size, checksum, boot structures and whether it was already prepared for an
+/// automated installation.
+///
+/// # Errors
+/// Returns an error if `iso` cannot be read or is not an ISO 9660 image.
+pub fn inspect_iso(iso: &Path) -> Result<IsoInspection, IsoInjectError> {
+    let mut reader = IsoReader::open(iso)?;
+    let size = reader
+        .image()
+        .metadata()
+        .map_err(|e| IsoInjectError::Io(iso.to_path_buf(), e.to_string()))?
+        .len();
+    let volume_label = reader.volume_label()?;
+    let files = reader.list("")?.unwrap_or_default();
+    let boot = read_boot_records(reader.image()).map_err(|e| match e {
+        IsoInjectError::Io(_, detail) => IsoInjectError::Io(iso.to_path_buf(), detail),
+        other => other,
+    })?;
+
+    let find = |name: &str| {
+        files
+            .iter()
+            .find(|f| !f.dir && f.path.eq_ignore_ascii_case(name))
+    };
+    let mut read = |file: &IsoEntry| -> Result<String, IsoInjectError> {
+        Ok(String::from_utf8_lossy(&reader.read(file)?).into_owned())
+    };
+
+    let (product, version) = match find(ISO_CD_INFO_FILE_NAME) {
+        Some(file) => parse_cd_info(&read(file)?),
+        None => (None, None),
+    };
+    let auto_installer_mode = match find(AUTO_INSTALLER_MODE_FILE_NAME) {
+        Some(file) => Some(installer_mode(&read(file)?)),
+        None => None,
+    };
+    let sha256 = sha256_file(iso).map_err(|e| match e {
+        IsoVerifyError::Io(path, detail) => IsoInjectError::Io(path, detail),
+        other => IsoInjectError::Io(iso.to_path_buf(), other.to_string()),
+    })?;
+
+    Ok(IsoInspection {
+        volume_label,
+        size,
+        product,
+        version,
+        answer_embedded: find(ISO_ANSWER_FILE_NAME).is_some(),
+        auto_installer_mode,
+        first_boot_embedded: find(ISO_FIRST_BOOT_FILE_NAME).is_some(),
+        boot,
+        sha256,
+    })
+}
+
+/// Checks that the image at `iso` is a Proxmox installer ISO, i.e. has a
+/// `.cd-info` on its root, before it is remastered, and returns its product
+/// and version as [`parse_cd_info`] does.
+///
+/// # Errors
+/// Returns [`IsoInjectError::NotInstallerIso`] without a `.cd-info`, and an
+/// error if `iso` cannot be read or is not an ISO 9660 image.
+pub fn check_installer_iso(iso: &Path) -> Result<(Option<String>, Option<String>), IsoInjectError> {
+    let mut reader = IsoReader::open(iso)?;
+    let cd_info = reader
+        .list("")?
+        .unwrap_or_default()
+        .into_iter()
+        .find(|f| !f.dir && f.path.eq_ignore_ascii_case(ISO_CD_INFO_FILE_NAME))
+        .ok_or_else(|| IsoInjectError::NotInstallerIso(iso.to_path_buf()))?;
+    Ok(parse_cd_info(&String::from_utf8_lossy(
+        &reader.read(&cd_info)?,
+    )))
+}
+
+/// The product name and version (`RELEASE-ISORELEASE`) of a `.cd-info`, a
+/// shell-style file of `KEY='value'` lines.
+pub fn parse_cd_info(cd_info: &str) -> (Option<String>, Option<String>) {
+    let value = |key: &str| {
+        cd_info.lines().find_map(|line| {
+            let (k, v) = line.trim().split_once('=')?;
+            let v = v.trim().trim_matches(|c| c == '\'' || c == '"');
+            (k.trim() == key && !v.is_empty()).then(|| v.to_string())
+        })
+    };
+    let version = match (value("RELEASE"), value("ISORELEASE")) {
+        (Some(release), Some(iso_release)) => Some(format!("{}-{}", release, iso_release)),
+        (release, _) => release,
+    };
+    (value("PRODUCTLONG"), version)
+}
+
+/// The `mode` of an `auto-installer-mode.toml`, or `unknown`.
+fn installer_mode(toml: &str) -> String {
+    toml::from_str::<toml::Table>(toml)
+        .ok()
+        .and_then(|t| t.get("mode")?.as_str().map(str::to_owned))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iso::inject::{inject_files, tests::image};
+
+    fn temp_iso(name: &str, files: &[(&str, &[u8])]) -> std::path::PathBuf {
+        let dir = std::env::temp_dir();
+        let base = dir.join(format!(
+            "pveauto-inspect-{}-{}-base.iso",
+            std::process::id(),
+            name
+        ));
+        let iso = dir.join(format!(
+            "pveauto-inspect-{}-{}.iso",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&base, image(true, false)).unwrap();
+        inject_files(&base, &iso, files).unwrap();
+        std::fs::remove_file(base).unwrap();
+        iso
+    }
+
+    #[test]
+    fn prepared_isos_are_recognised() {
+        let cd_info = b"PRODUCT='pve'\nPRODUCTLONG='Proxmox VE'\nRELEASE='8.2'\nISORELEASE='1'\n";
+        let iso = temp_iso(
+            "prepared",
+            &[
+                (".cd-info", cd_info),
+                ("auto-installer-mode.toml", b"mode = \"http\"\n"),
+                ("answer.toml", b"[global]\n"),
+            ],
+        );
+
+        let inspection = inspect_iso(&iso).unwrap();
+        assert_eq!(inspection.product.as_deref(), Some("Proxmox VE"));
+        assert_eq!(inspection.version.as_deref(), Some("8.2-1"));
+        assert!(inspection.answer_embedded);
+        assert_eq!(inspection.auto_installer_mode.as_deref(), Some("http"));
+        assert!(!inspection.first_boot_embedded);
+        assert!(!inspection.boot.bios() && inspection.boot.mbr && inspection.boot.gpt_backup);
+        assert_eq!(inspection.size, std::fs::metadata(&iso).unwrap().len());
+        assert_eq!(inspection.sha256, sha256_file(&iso).unwrap());
+        assert_eq!(
+            check_installer_iso(&iso).unwrap(),
+            (Some("Proxmox VE".into()), Some("8.2-1".into()))
+        );
+        std::fs::remove_file(iso).unwrap();
+    }
+
+    #[test]
+    fn unprepared_isos_have_no_answer() {
+        let iso = temp_iso("plain", &[]);
+        let inspection = inspect_iso(&iso).unwrap();
+        assert_eq!(inspection.version, None);
+        assert!(!inspection.answer_embedded);
+        assert_eq!(inspection.auto_installer_mode, None);
+        assert_eq!(
+            check_installer_iso(&iso),
+            Err(IsoInjectError::NotInstallerIso(iso.clone()))
+        );
+        std::fs::remove_file(iso).unwrap();
+
+        let not_iso = std::env::temp_dir().join(format!(
+            "pveauto-inspect-{}-not-iso.iso",
+            std::process::id()
+        ));
+        std::fs::write(&not_iso, vec![0; 40_000]).unwrap();
+        assert_eq!(
+            inspect_iso(&not_iso).unwrap_err(),
+            IsoInjectError::NotIso9660(not_iso.clone())
+        );
+        std::fs::remove_file(not_iso).unwrap();
+    }
+
+    #[test]
+    fn cd_info_versions_combine_release_and_iso_release() {
+        assert_eq!(
+            parse_cd_info("RELEASE='9.0'\nISORELEASE='1'\n"),
+            (None, Some("9.0-1".into()))
+        );
+        assert_eq!(
+            parse_cd_info("PRODUCTLONG=\"Proxmox VE\"\nRELEASE=8.1\n"),
+            (Some("Proxmox VE".into()), Some("8.1".into()))
+        );
+        assert_eq!(parse_cd_info(""), (None, None));
+    }
+}
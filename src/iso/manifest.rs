@@ -0,0 +1,199 @@
+use crate::iso::{
+    cache::{unix_seconds, utc_datetime},
+    checksum::{ChecksumAlgorithm, hash_file},
+    constants::BUILD_MANIFEST_SUFFIX,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Version of the manifest layout written by [`BuildManifest::write`].
+pub const BUILD_MANIFEST_FORMAT: u32 = 1;
+
+/// A file and its SHA256, as listed in a [`BuildManifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// Path on disk, or in the ISO for files added to it.
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+impl ManifestFile {
+    /// The entry of `contents` stored at `path`.
+    pub fn new(path: &str, contents: &[u8]) -> Self {
+        Self {
+            path: path.to_string(),
+            size: contents.len() as u64,
+            sha256: format!("{:x}", Sha256::digest(contents)),
+        }
+    }
+
+    /// The entry of the file at `path`, hashed as it is read.
+    pub fn of_file(path: &Path) -> io::Result<Self> {
+        let size = std::fs::metadata(path)?.len();
+        let hashes = hash_file(path, ChecksumAlgorithm::Sha256)?;
+        Ok(Self {
+            path: path.to_string_lossy().into_owned(),
+            size,
+            sha256: hashes.sha256,
+        })
+    }
+}
+
+/// Where the installer of a built ISO gets its answer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    tag = "mode",
+    rename_all = "lowercase",
+    rename_all_fields = "kebab-case"
+)]
+pub enum ManifestAnswer {
+    /// Embedded in the ISO.
+    Iso {
+        /// The answer file, or the inventory host it was generated for.
+        source: String,
+        /// SHA256 of the answer as embedded, after first-boot scripts and
+        /// assets were bundled.
+        sha256: String,
+    },
+    /// Fetched from an answer server.
+    Http {
+        /// `None` when the server is announced by DHCP or DNS.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cert_fingerprint: Option<String>,
+    },
+}
+
+/// The `<iso>.manifest.json` written next to every built ISO, recording what
+/// went into it, for audits and to rebuild it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BuildManifest {
+    pub format: u32,
+    /// `pveauto` and its version, e.g. `pveauto 0.1.0`.
+    pub tool: String,
+    /// The programs that wrote the ISO, e.g. the assistant and xorriso.
+    pub prepared_by: Vec<String>,
+    /// When the build started and the ISO was complete, in UTC.
+    pub started: String,
+    pub finished: String,
+    pub base_iso: ManifestFile,
+    /// The Proxmox VE version of the base ISO, from its `.cd-info`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub iso: ManifestFile,
+    pub answer: ManifestAnswer,
+    /// The files added to the ISO, at their path in it.
+    pub files: Vec<ManifestFile>,
+    pub kernel_args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume_label: Option<String>,
+    /// The GnuPG key that signed the ISO, with `--sign-key`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signed_by: Option<String>,
+}
+
+impl BuildManifest {
+    /// Writes the manifest as pretty-printed JSON to [`manifest_path`] of
+    /// `iso`.
+    ///
+    /// # Returns
+    /// The path written.
+    pub fn write(&self, iso: &Path) -> io::Result<PathBuf> {
+        let path = manifest_path(iso);
+        let mut json = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
+        json.push(b'\n');
+        std::fs::write(&path, json)?;
+        Ok(path)
+    }
+}
+
+/// `pveauto` and its version, for [`BuildManifest::tool`].
+pub fn tool_version() -> String {
+    format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+}
+
+/// `time` in UTC as an RFC 3339 date and time, e.g. `2024-04-24T10:15:00Z`.
+pub fn timestamp(time: SystemTime) -> String {
+    utc_datetime(unix_seconds(time)).to_string()
+}
+
+/// Where the manifest of the ISO at `iso` is kept: `<iso>.manifest.json`.
+pub fn manifest_path(iso: &Path) -> PathBuf {
+    let mut path = OsString::from(iso.as_os_str());
+    path.push(BUILD_MANIFEST_SUFFIX);
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn manifests_are_written_next_to_the_iso() {
+        let dir = std::env::temp_dir().join(format!("pveauto-manifest-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let iso = dir.join("pve.iso");
+
+        let manifest = BuildManifest {
+            format: BUILD_MANIFEST_FORMAT,
+            tool: tool_version(),
+            prepared_by: vec!["the native ISO 9660 writer".into()],
+            started: timestamp(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            finished: timestamp(UNIX_EPOCH + Duration::from_secs(1_700_000_042)),
+            base_iso: ManifestFile::new("proxmox-ve_8.2-1.iso", b"base"),
+            version: Some("8.2-1".into()),
+            iso: ManifestFile::new("pve.iso", b"built"),
+            answer: ManifestAnswer::Http {
+                url: Some("https://10.0.0.1:8443/answer".into()),
+                cert_fingerprint: None,
+            },
+            files: vec![ManifestFile::new("auto-installer-mode.toml", b"mode")],
+            kernel_args: vec!["console=ttyS0,115200".into()],
+            volume_label: None,
+            signed_by: None,
+        };
+        std::fs::write(&iso, b"built").unwrap();
+        assert_eq!(
+            ManifestFile::of_file(&iso).unwrap(),
+            ManifestFile {
+                path: iso.to_string_lossy().into_owned(),
+                ..ManifestFile::new("pve.iso", b"built")
+            }
+        );
+        let path = manifest.write(&iso).unwrap();
+        assert_eq!(path, dir.join("pve.iso.manifest.json"));
+
+        let json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(
+            json["tool"],
+            format!("pveauto {}", env!("CARGO_PKG_VERSION"))
+        );
+        assert_eq!(json["started"], "2023-11-14T22:13:20Z");
+        assert_eq!(json["finished"], "2023-11-14T22:14:02Z");
+        assert_eq!(
+            json["base-iso"]["sha256"],
+            "cae662172fd450bb0cd710a769079c05bfc5d8e35efa6576edc7d0377afdd4a2"
+        );
+        assert_eq!(json["base-iso"]["size"], 4);
+        assert_eq!(json["answer"]["mode"], "http");
+        assert_eq!(json["answer"]["url"], "https://10.0.0.1:8443/answer");
+        assert!(json["answer"].get("cert-fingerprint").is_none());
+        assert!(json.get("signed-by").is_none());
+
+        let read: BuildManifest = serde_json::from_value(json).unwrap();
+        assert_eq!(read, manifest);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,107 @@
+use scraper::{Html, Selector};
+use std::error::Error;
+
+/// Lowest preference a Metalink `priority` can express; also used for URLs without one.
+const LOWEST_PRIORITY: u32 = 999_999;
+
+/// A file described by a Metalink 4 document (RFC 5854), e.g. `<iso>.meta4`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetalinkFile {
+    pub name: String,
+    pub size: Option<u64>,
+    /// The `sha-256` hash, lower-case, when the document has one.
+    pub sha256: Option<String>,
+    /// Where the file can be downloaded, most preferred first.
+    pub urls: Vec<String>,
+}
+
+/// Reads the entry of `file_name` from the Metalink document `xml`.
+///
+/// # Errors
+/// Returns an error if the document has no such file or it has no HTTP(S) URL.
+pub fn parse_metalink(xml: &str, file_name: &str) -> Result<MetalinkFile, Box<dyn Error>> {
+    let document = Html::parse_document(xml);
+    let sel_file = Selector::parse("file[name]")?;
+    let sel_size = Selector::parse("size")?;
+    let sel_hash = Selector::parse(r#"hash[type="sha-256"]"#)?;
+    let sel_url = Selector::parse("url")?;
+
+    let file = document
+        .select(&sel_file)
+        .find(|file| file.value().attr("name") == Some(file_name))
+        .ok_or_else(|| format!("{} is not in the metalink", file_name))?;
+    let text = |selector: &Selector| {
+        file.select(selector)
+            .next()
+            .map(|e| e.text().collect::<String>().trim().to_string())
+    };
+
+    let mut urls: Vec<(u32, String)> = file
+        .select(&sel_url)
+        .map(|url| {
+            let priority = url
+                .value()
+                .attr("priority")
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(LOWEST_PRIORITY);
+            (priority, url.text().collect::<String>().trim().to_string())
+        })
+        .filter(|(_, url)| url.starts_with("https://") || url.starts_with("http://"))
+        .collect();
+    if urls.is_empty() {
+        return Err(format!("The metalink has no HTTP URL for {}", file_name).into());
+    }
+    urls.sort_by_key(|(priority, _)| *priority);
+
+    Ok(MetalinkFile {
+        name: file_name.to_string(),
+        size: text(&sel_size).and_then(|size| size.parse().ok()),
+        sha256: text(&sel_hash).map(|hash| hash.to_lowercase()),
+        urls: urls.into_iter().map(|(_, url)| url).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metalink_urls_are_ordered_by_priority() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <metalink xmlns="urn:ietf:params:xml:ns:metalink">
+              <file name="proxmox-ve_8.1-2.iso">
+                <url>https://old.example/proxmox-ve_8.1-2.iso</url>
+              </file>
+              <file name="proxmox-ve_8.2-1.iso">
+                <size>1331691520</size>
+                <hash type="sha-1">0000</hash>
+                <hash type="sha-256">ABCD</hash>
+                <url location="de" priority="2">https://mirror.example/iso/proxmox-ve_8.2-1.iso?a=1&amp;b=2</url>
+                <url priority="1">https://enterprise.proxmox.com/iso/proxmox-ve_8.2-1.iso</url>
+                <url>ftp://ftp.example/proxmox-ve_8.2-1.iso</url>
+                <url>http://slow.example/proxmox-ve_8.2-1.iso</url>
+              </file>
+            </metalink>"#;
+        assert_eq!(
+            parse_metalink(xml, "proxmox-ve_8.2-1.iso").unwrap(),
+            MetalinkFile {
+                name: "proxmox-ve_8.2-1.iso".into(),
+                size: Some(1331691520),
+                sha256: Some("abcd".into()),
+                urls: vec![
+                    "https://enterprise.proxmox.com/iso/proxmox-ve_8.2-1.iso".into(),
+                    "https://mirror.example/iso/proxmox-ve_8.2-1.iso?a=1&b=2".into(),
+                    "http://slow.example/proxmox-ve_8.2-1.iso".into(),
+                ],
+            }
+        );
+        assert!(parse_metalink(xml, "proxmox-ve_9.0-1.iso").is_err());
+        assert!(
+            parse_metalink(
+                r#"<metalink><file name="a.iso"><url>ftp://x/a.iso</url></file></metalink>"#,
+                "a.iso"
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use crate::iso::{cache::IsoCache, http::HttpFetcher};
+use reqwest::header::{HeaderMap, HeaderValue, RANGE};
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Bytes each mirror is asked for when probed.
+pub const PROBE_BYTES: u64 = 256 * 1024;
+
+/// How long a mirror gets to answer a probe before it is skipped.
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a benchmark is reused unless configured otherwise.
+pub const DEFAULT_BENCHMARK_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// The result of probing one mirror.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MirrorProbe {
+    pub mirror: String,
+    /// Throughput of the probe, or `None` when the mirror failed to answer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes_per_sec: Option<u64>,
+}
+
+/// The probes of a set of mirrors, fastest first, as cached in the ISO cache.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MirrorBenchmark {
+    /// When the mirrors were probed, in seconds since the Unix epoch.
+    pub probed: u64,
+    pub probes: Vec<MirrorProbe>,
+}
+
+impl MirrorBenchmark {
+    /// The fastest mirror that answered.
+    pub fn fastest(&self) -> Option<&str> {
+        self.probes
+            .iter()
+            .find(|probe| probe.bytes_per_sec.is_some())
+            .map(|probe| probe.mirror.as_str())
+    }
+
+    /// Whether this benchmark probed exactly `mirrors` less than `ttl` ago.
+    pub fn is_fresh(&self, mirrors: &[String], ttl: Duration) -> bool {
+        let mut probed: Vec<&str> = self.probes.iter().map(|p| p.mirror.as_str()).collect();
+        let mut wanted: Vec<&str> = mirrors.iter().map(String::as_str).collect();
+        probed.sort_unstable();
+        wanted.sort_unstable();
+        probed == wanted && unix_now().saturating_sub(self.probed) < ttl.as_secs()
+    }
+}
+
+/// Measures the throughput of `url` by asking for its first [`PROBE_BYTES`]
+/// bytes. Servers ignoring the range are read no further than that.
+///
+/// # Errors
+/// Returns an error if the request fails, answers with an error status or
+/// takes longer than [`PROBE_TIMEOUT`].
+pub async fn probe(http: &impl HttpFetcher, url: &str) -> Result<u64, Box<dyn Error>> {
+    let started = Instant::now();
+    let read = async {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            RANGE,
+            HeaderValue::from_str(&format!("bytes=0-{}", PROBE_BYTES - 1))?,
+        );
+        let mut resp = http.get(url, headers).await?.error_for_status()?;
+        let mut read = 0;
+        while read < PROBE_BYTES {
+            match resp.chunk().await? {
+                Some(chunk) => read += chunk.len() as u64,
+                None => break,
+            }
+        }
+        Ok::<u64, Box<dyn Error>>(read.min(PROBE_BYTES))
+    };
+    let read = tokio::time::timeout(PROBE_TIMEOUT, read)
+        .await
+        .map_err(|_| format!("no answer within {} s", PROBE_TIMEOUT.as_secs()))??;
+    let micros = started.elapsed().as_micros().max(1);
+    Ok((u128::from(read) * 1_000_000 / micros)
+        .try_into()
+        .unwrap_or(u64::MAX))
+}
+
+/// Picks the fastest of `mirrors` by probing `path` under each of them in
+/// turn, e.g. the ISO about to be downloaded or `""` for the listing.
+///
+/// A benchmark of the same mirrors cached in `cache` less than `ttl` ago is
+/// reused without probing; a new one is recorded there. `report` is called
+/// with each probe as it completes.
+///
+/// # Errors
+/// Returns an error if no mirror answers.
+pub async fn select_mirror(
+    http: &impl HttpFetcher,
+    cache: &IsoCache,
+    mirrors: &[String],
+    path: &str,
+    ttl: Duration,
+    mut report: impl FnMut(&MirrorProbe),
+) -> Result<MirrorBenchmark, Box<dyn Error>> {
+    if let Some(benchmark) = cache
+        .mirror_benchmark()
+        .filter(|b| b.is_fresh(mirrors, ttl) && b.fastest().is_some())
+    {
+        return Ok(benchmark);
+    }
+
+    let mut probes = Vec::new();
+    for mirror in mirrors {
+        let probe = MirrorProbe {
+            mirror: mirror.clone(),
+            bytes_per_sec: probe(http, &format!("{}{}", mirror, path)).await.ok(),
+        };
+        report(&probe);
+        probes.push(probe);
+    }
+    probes.sort_by_key(|probe| std::cmp::Reverse(probe.bytes_per_sec));
+    let benchmark = MirrorBenchmark {
+        probed: unix_now(),
+        probes,
+    };
+    if benchmark.fastest().is_none() {
+        return Err(format!("None of the {} mirrors answered", mirrors.len()).into());
+    }
+    cache.record_mirror_benchmark(&benchmark)?;
+    Ok(benchmark)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iso::http::{HttpResponse, tests::MockFetcher};
+    use reqwest::Method;
+
+    /// Serves like [`MockFetcher`] after waiting the delay of the first
+    /// matching URL prefix.
+    struct SlowFetcher {
+        inner: MockFetcher,
+        delays: Vec<(&'static str, Duration)>,
+    }
+
+    impl HttpFetcher for SlowFetcher {
+        async fn send(
+            &self,
+            method: Method,
+            url: &str,
+            headers: HeaderMap,
+        ) -> Result<HttpResponse, Box<dyn Error>> {
+            if let Some((_, delay)) = self.delays.iter().find(|(p, _)| url.starts_with(p)) {
+                tokio::time::sleep(*delay).await;
+            }
+            self.inner.send(method, url, headers).await
+        }
+    }
+
+    const ISO: &str = "proxmox-ve_8.2-1.iso";
+
+    #[tokio::test]
+    async fn the_fastest_mirror_is_picked_and_cached() {
+        let dir = std::env::temp_dir().join(format!("pveauto-mirrors-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = IsoCache::new(&dir);
+        let body = vec![0u8; 64 * 1024];
+        let http = SlowFetcher {
+            inner: MockFetcher::default()
+                .route(
+                    "http://slow.lan/iso/proxmox-ve_8.2-1.iso",
+                    None,
+                    body.clone(),
+                )
+                .route("http://fast.lan/iso/proxmox-ve_8.2-1.iso", None, body),
+            delays: vec![("http://slow.lan/", Duration::from_millis(200))],
+        };
+        let mirrors: Vec<String> = [
+            "http://slow.lan/iso/",
+            "http://fast.lan/iso/",
+            "http://down.lan/",
+        ]
+        .map(String::from)
+        .to_vec();
+
+        let mut reported = Vec::new();
+        let benchmark = select_mirror(
+            &http,
+            &cache,
+            &mirrors,
+            ISO,
+            DEFAULT_BENCHMARK_TTL,
+            |probe| reported.push(probe.mirror.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(benchmark.fastest(), Some("http://fast.lan/iso/"));
+        assert_eq!(benchmark.probes[2].mirror, "http://down.lan/");
+        assert_eq!(benchmark.probes[2].bytes_per_sec, None);
+        assert_eq!(reported, mirrors);
+        assert_eq!(http.inner.requests().len(), 3);
+
+        let cached = select_mirror(&http, &cache, &mirrors, ISO, DEFAULT_BENCHMARK_TTL, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(cached, benchmark);
+        assert!(http.inner.requests().is_empty());
+
+        select_mirror(
+            &http,
+            &cache,
+            &mirrors[..2],
+            ISO,
+            DEFAULT_BENCHMARK_TTL,
+            |_| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(http.inner.requests().len(), 2);
+        select_mirror(&http, &cache, &mirrors[..2], ISO, Duration::ZERO, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(http.inner.requests().len(), 2);
+
+        let down = ["http://down.lan/".to_string()];
+        let err = select_mirror(&http, &cache, &down, ISO, Duration::ZERO, |_| {})
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "None of the 1 mirrors answered");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
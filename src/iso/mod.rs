@@ -1,3 +1,32 @@
+pub mod alternates;
+pub mod assets;
+pub mod boot;
+pub mod bundle;
+pub mod cache;
+pub mod channel;
+pub mod checksum;
+pub mod config;
 pub mod constants;
 pub mod downloader;
+pub mod first_boot;
+pub mod flash;
+pub mod http;
+pub mod inject;
+pub mod inspect;
+pub mod manifest;
+pub mod metalink;
+pub mod mirrors;
+pub mod mode;
+pub mod output;
+pub mod packages;
+pub mod pgp;
+pub mod reader;
+pub mod retry;
 pub mod scraper;
+pub mod signature;
+pub mod smoke;
+pub mod space;
+pub mod tar;
+pub mod torrent;
+pub mod verify;
+pub mod writer;
@@ -0,0 +1,89 @@
+use serde::Serialize;
+
+/// Where the installer on a prepared ISO gets its answer from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FetchAnswerFrom {
+    /// `answer.toml` on the ISO itself.
+    Iso,
+    /// An HTTP(S) answer server.
+    Http,
+}
+
+/// Where and how to reach the answer server in `http` mode.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HttpOptions {
+    /// Without one, the installer asks DHCP (option 250) and DNS
+    /// (`proxmox-auto-installer.<search domain>` TXT) for the URL.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// SHA-256 of a self-signed server certificate, as `AB:CD:...`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cert_fingerprint: Option<String>,
+}
+
+impl HttpOptions {
+    pub fn is_empty(&self) -> bool {
+        self.url.is_none() && self.cert_fingerprint.is_none()
+    }
+}
+
+/// The `auto-installer-mode.toml` placed on a prepared ISO, which makes its
+/// boot menu default to the automated installation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AutoInstallerMode {
+    pub mode: FetchAnswerFrom,
+    #[serde(skip_serializing_if = "HttpOptions::is_empty")]
+    pub http: HttpOptions,
+}
+
+impl AutoInstallerMode {
+    pub fn iso() -> Self {
+        Self {
+            mode: FetchAnswerFrom::Iso,
+            http: HttpOptions::default(),
+        }
+    }
+
+    pub fn http(url: Option<&str>, cert_fingerprint: Option<&str>) -> Self {
+        Self {
+            mode: FetchAnswerFrom::Http,
+            http: HttpOptions {
+                url: url.map(str::to_owned),
+                cert_fingerprint: cert_fingerprint.map(str::to_owned),
+            },
+        }
+    }
+
+    pub fn to_toml_string(&self) -> String {
+        toml::to_string(self).expect("AutoInstallerMode always serializes")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iso_mode_has_no_http_table() {
+        assert_eq!(
+            AutoInstallerMode::iso().to_toml_string(),
+            "mode = \"iso\"\n"
+        );
+    }
+
+    #[test]
+    fn http_mode_uses_installer_key_names() {
+        let toml = AutoInstallerMode::http(Some("https://10.0.0.1:8443/answer"), Some("AB:CD"))
+            .to_toml_string();
+        assert_eq!(
+            toml,
+            "mode = \"http\"\n\n[http]\nurl = \"https://10.0.0.1:8443/answer\"\ncert-fingerprint = \"AB:CD\"\n"
+        );
+        assert_eq!(
+            AutoInstallerMode::http(None, None).to_toml_string(),
+            "mode = \"http\"\n"
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use std::{fmt, str::FromStr};
+
+/// The placeholders an [`OutputTemplate`] may use.
+const PLACEHOLDERS: [&str; 4] = ["version", "fqdn", "hostname", "name"];
+
+/* ===================== OUTPUT TEMPLATE ERROR ===================== */
+
+/// Failure while expanding the output path of a built ISO.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputTemplateError {
+    /// A `{` without its `}`, or a `}` without its `{`; holds the template.
+    Unbalanced(String),
+    /// A placeholder that is not one of `version`, `fqdn`, `hostname` or
+    /// `name`; holds it.
+    UnknownPlaceholder(String),
+    /// A placeholder without a value for this build, e.g. `{fqdn}` for a
+    /// network installer, which embeds no answer; holds it.
+    MissingValue(String),
+}
+
+impl OutputTemplateError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Unbalanced(_) => "output.unbalanced_brace",
+            Self::UnknownPlaceholder(_) => "output.unknown_placeholder",
+            Self::MissingValue(_) => "output.missing_value",
+        }
+    }
+}
+
+impl fmt::Display for OutputTemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unbalanced(template) => write!(f, "{} ({})", self.code(), template),
+            Self::UnknownPlaceholder(name) => write!(
+                f,
+                "{} ({{{}}}; expected one of {})",
+                self.code(),
+                name,
+                PLACEHOLDERS.map(|p| format!("{{{}}}", p)).join(", ")
+            ),
+            Self::MissingValue(name) => {
+                write!(
+                    f,
+                    "{} ({{{}}} is not known for this build)",
+                    self.code(),
+                    name
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for OutputTemplateError {}
+
+/* ===================== OUTPUT TEMPLATE ===================== */
+
+/// What the placeholders of an [`OutputTemplate`] expand to for one ISO.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutputValues {
+    /// The Proxmox VE version of the base ISO, e.g. `8.2-1`.
+    pub version: Option<String>,
+    /// The `global.fqdn` of the embedded answer.
+    pub fqdn: Option<String>,
+    /// The inventory name of the host, with `--inventory`.
+    pub name: Option<String>,
+}
+
+/// The output path of a built ISO with `{placeholder}`s, e.g.
+/// `pve-{version}-{fqdn}-auto.iso`, so batch builds and CI artifacts get
+/// predictable names.
+///
+/// Placeholders:
+/// - `{version}`: the Proxmox VE version of the base ISO, from its `.cd-info`
+/// - `{fqdn}`: the `global.fqdn` of the embedded answer
+/// - `{hostname}`: the first label of that FQDN
+/// - `{name}`: the host's name in the inventory
+///
+/// Values are inserted with every character but ASCII letters, digits, `.`,
+/// `_` and `-` replaced by `-`, so they never add directories.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputTemplate(String);
+
+impl OutputTemplate {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the template contains any placeholder.
+    pub fn is_templated(&self) -> bool {
+        self.0.contains('{')
+    }
+
+    /// Substitutes `values`.
+    ///
+    /// # Errors
+    /// [`OutputTemplateError::MissingValue`] for a placeholder `values` have
+    /// no value for.
+    pub fn expand(&self, values: &OutputValues) -> Result<String, OutputTemplateError> {
+        let mut out = String::with_capacity(self.0.len());
+        let mut rest = self.0.as_str();
+
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let end = after
+                .find('}')
+                .ok_or_else(|| OutputTemplateError::Unbalanced(self.0.clone()))?;
+            let name = &after[..end];
+            let value = match name {
+                "version" => values.version.clone(),
+                "fqdn" => values.fqdn.clone(),
+                "hostname" => values
+                    .fqdn
+                    .as_deref()
+                    .and_then(|fqdn| fqdn.split('.').next())
+                    .map(str::to_owned),
+                "name" => values.name.clone(),
+                _ => return Err(OutputTemplateError::UnknownPlaceholder(name.to_owned())),
+            }
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| OutputTemplateError::MissingValue(name.to_owned()))?;
+            out.extend(value.chars().map(|c| match c {
+                'A'..='Z' | 'a'..='z' | '0'..='9' | '.' | '_' | '-' => c,
+                _ => '-',
+            }));
+            rest = &after[end + 1..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+}
+
+impl FromStr for OutputTemplate {
+    type Err = OutputTemplateError;
+
+    /// Accepts any path whose placeholders are known and balanced; plain
+    /// paths are valid templates without placeholders.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unbalanced = || OutputTemplateError::Unbalanced(s.to_owned());
+        let mut rest = s;
+
+        while let Some(start) = rest.find(['{', '}']) {
+            if rest[start..].starts_with('}') {
+                return Err(unbalanced());
+            }
+            let after = &rest[start + 1..];
+            let end = after.find(['{', '}']).ok_or_else(unbalanced)?;
+            if after[end..].starts_with('{') {
+                return Err(unbalanced());
+            }
+            if !PLACEHOLDERS.contains(&&after[..end]) {
+                return Err(OutputTemplateError::UnknownPlaceholder(
+                    after[..end].to_owned(),
+                ));
+            }
+            rest = &after[end + 1..];
+        }
+        Ok(Self(s.to_owned()))
+    }
+}
+
+impl fmt::Display for OutputTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_are_expanded_into_file_names() {
+        let template: OutputTemplate = "out/pve-{version}-{fqdn}-auto.iso".parse().unwrap();
+        assert!(template.is_templated());
+        let values = OutputValues {
+            version: Some("8.2-1".into()),
+            fqdn: Some("pve01.lab.local".into()),
+            name: Some("rack 1/pve01".into()),
+        };
+        assert_eq!(
+            template.expand(&values).unwrap(),
+            "out/pve-8.2-1-pve01.lab.local-auto.iso"
+        );
+        let template: OutputTemplate = "{hostname}_{name}.iso".parse().unwrap();
+        assert_eq!(template.expand(&values).unwrap(), "pve01_rack-1-pve01.iso");
+
+        let plain: OutputTemplate = "proxmox-ve-autoinstall.iso".parse().unwrap();
+        assert!(!plain.is_templated());
+        assert_eq!(
+            plain.expand(&OutputValues::default()).unwrap(),
+            "proxmox-ve-autoinstall.iso"
+        );
+        assert_eq!(
+            "pve-{fqdn}.iso"
+                .parse::<OutputTemplate>()
+                .unwrap()
+                .expand(&OutputValues::default()),
+            Err(OutputTemplateError::MissingValue("fqdn".into()))
+        );
+    }
+
+    #[test]
+    fn malformed_templates_are_rejected() {
+        for template in ["pve-{version.iso", "pve-}.iso", "pve-{{version}}.iso"] {
+            assert_eq!(
+                template.parse::<OutputTemplate>(),
+                Err(OutputTemplateError::Unbalanced(template.into()))
+            );
+        }
+        let err = "pve-{mac}.iso".parse::<OutputTemplate>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "output.unknown_placeholder ({mac}; expected one of {version}, {fqdn}, \
+             {hostname}, {name})"
+        );
+    }
+}
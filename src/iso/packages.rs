@@ -0,0 +1,146 @@
+use crate::iso::constants::ISO_PACKAGE_POOL_DIR;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Every Debian package is an `ar` archive whose first member is `debian-binary`.
+const DEB_MAGIC: &[u8] = b"!<arch>\ndebian-binary";
+
+/* ===================== DEB PACKAGE ERROR ===================== */
+
+/// Failure while loading extra Debian packages for the ISO's package pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebPackageError {
+    /// The package could not be read; holds the path and the I/O error.
+    Unreadable(PathBuf, String),
+    /// The file is not a Debian package or its name does not end in `.deb`.
+    NotDeb(PathBuf),
+    /// Two packages share a file name; holds the name.
+    DuplicateName(String),
+}
+
+impl DebPackageError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Unreadable(..) => "deb_package.unreadable",
+            Self::NotDeb(_) => "deb_package.not_deb",
+            Self::DuplicateName(_) => "deb_package.duplicate_name",
+        }
+    }
+}
+
+impl fmt::Display for DebPackageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unreadable(path, detail) => {
+                write!(f, "{} ({}: {})", self.code(), path.display(), detail)
+            }
+            Self::NotDeb(path) => write!(f, "{} ({})", self.code(), path.display()),
+            Self::DuplicateName(name) => write!(f, "{} ({})", self.code(), name),
+        }
+    }
+}
+
+impl std::error::Error for DebPackageError {}
+
+/* ===================== DEB PACKAGE ===================== */
+
+/// A Debian package to add to the ISO's package pool, which the installer
+/// unpacks and configures along with the Proxmox VE packages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebPackage {
+    pub path: PathBuf,
+    /// File name in the pool, e.g. `qemu-guest-agent_8.0.2_amd64.deb`.
+    pub file_name: String,
+    pub contents: Vec<u8>,
+}
+
+impl DebPackage {
+    /// Reads the package at `path`, which must be named `*.deb` and start
+    /// like a Debian binary package.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, DebPackageError> {
+        let path = path.into();
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .filter(|n| n.len() > ".deb".len() && n.ends_with(".deb"))
+            .ok_or_else(|| DebPackageError::NotDeb(path.clone()))?;
+        let contents = std::fs::read(&path)
+            .map_err(|e| DebPackageError::Unreadable(path.clone(), e.to_string()))?;
+        if !contents.starts_with(DEB_MAGIC) {
+            return Err(DebPackageError::NotDeb(path));
+        }
+
+        Ok(Self {
+            path,
+            file_name,
+            contents,
+        })
+    }
+
+    /// Path of the package on the ISO, e.g. `proxmox/packages/extra.deb`.
+    pub fn iso_path(&self) -> String {
+        format!("{}/{}", ISO_PACKAGE_POOL_DIR, self.file_name)
+    }
+}
+
+/// Loads the packages at `paths`, which must have distinct file names.
+pub fn load_packages(paths: &[impl AsRef<Path>]) -> Result<Vec<DebPackage>, DebPackageError> {
+    let mut packages: Vec<DebPackage> = Vec::with_capacity(paths.len());
+    for path in paths {
+        let package = DebPackage::load(path.as_ref())?;
+        if packages.iter().any(|p| p.file_name == package.file_name) {
+            return Err(DebPackageError::DuplicateName(package.file_name));
+        }
+        packages.push(package);
+    }
+    Ok(packages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pveauto-packages-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn packages_are_loaded_and_checked() {
+        let dir = temp_dir();
+        let deb = b"!<arch>\ndebian-binary   0           0     0     100644  4         `\n2.0\n";
+        std::fs::create_dir_all(dir.join("other")).unwrap();
+        for name in ["extra_1.0_all.deb", "other/extra_1.0_all.deb", "extra.udeb"] {
+            std::fs::write(dir.join(name), deb).unwrap();
+        }
+        std::fs::write(dir.join("text.deb"), "not a package").unwrap();
+
+        let packages = load_packages(&[dir.join("extra_1.0_all.deb")]).unwrap();
+        assert_eq!(packages[0].file_name, "extra_1.0_all.deb");
+        assert_eq!(packages[0].contents, deb);
+        assert_eq!(packages[0].iso_path(), "proxmox/packages/extra_1.0_all.deb");
+
+        let err = load_packages(&[
+            dir.join("extra_1.0_all.deb"),
+            dir.join("other/extra_1.0_all.deb"),
+        ])
+        .unwrap_err();
+        assert_eq!(
+            err,
+            DebPackageError::DuplicateName("extra_1.0_all.deb".into())
+        );
+        for (name, code) in [
+            ("text.deb", "deb_package.not_deb"),
+            ("extra.udeb", "deb_package.not_deb"),
+            ("missing.deb", "deb_package.unreadable"),
+        ] {
+            let err = DebPackage::load(dir.join(name)).unwrap_err();
+            assert_eq!(err.code(), code, "{}", name);
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,777 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use ed25519_dalek::VerifyingKey;
+use rsa::{BigUint, Pkcs1v15Sign, RsaPublicKey};
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512, digest::DynDigest};
+use std::{fmt, io::Read};
+
+/// OpenPGP packet tags understood here.
+const TAG_SIGNATURE: u8 = 2;
+const TAG_PUBLIC_KEY: u8 = 6;
+
+/// Public-key algorithm ids of RSA (encrypt or sign), RSA sign-only and
+/// EdDSA, which GnuPG creates keys with by default.
+const ALGO_RSA: u8 = 1;
+const ALGO_RSA_SIGN_ONLY: u8 = 3;
+const ALGO_EDDSA: u8 = 22;
+
+/// The curve OID of Ed25519 EdDSA keys, 1.3.6.1.4.1.11591.15.1.
+const OID_ED25519: [u8; 9] = [0x2b, 0x06, 0x01, 0x04, 0x01, 0xda, 0x47, 0x0f, 0x01];
+
+/// Signature types: a binary document and a canonical text document.
+const SIG_BINARY: u8 = 0x00;
+const SIG_TEXT: u8 = 0x01;
+
+/// Hashed signature subpackets naming the signing key.
+const SUBPACKET_ISSUER: u8 = 16;
+const SUBPACKET_ISSUER_FINGERPRINT: u8 = 33;
+
+/* ===================== PGP ERROR ===================== */
+
+/// Failure while reading or checking OpenPGP keys and signatures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgpError {
+    /// The data is not a well-formed OpenPGP packet stream or armor block.
+    Malformed(&'static str),
+    /// A version, algorithm or signature type outside the supported subset.
+    Unsupported(String),
+    /// The signature was made by a key that is not in the keyring; holds its id.
+    UnknownKey(String),
+    /// The signature does not match the data.
+    BadSignature,
+    /// The signed data could not be read; holds the I/O error.
+    Io(String),
+}
+
+impl PgpError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Malformed(_) => "pgp.malformed",
+            Self::Unsupported(_) => "pgp.unsupported",
+            Self::UnknownKey(_) => "pgp.unknown_key",
+            Self::BadSignature => "pgp.bad_signature",
+            Self::Io(_) => "pgp.io",
+        }
+    }
+}
+
+impl fmt::Display for PgpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed(what) => write!(f, "{} ({})", self.code(), what),
+            Self::Unsupported(what) | Self::UnknownKey(what) | Self::Io(what) => {
+                write!(f, "{} ({})", self.code(), what)
+            }
+            Self::BadSignature => f.write_str(self.code()),
+        }
+    }
+}
+
+impl std::error::Error for PgpError {}
+
+/* ===================== PUBLIC KEYS ===================== */
+
+/// A v4 RSA or Ed25519 primary key read from an OpenPGP keyring.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+    pub fingerprint: [u8; 20],
+    key: KeyMaterial,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum KeyMaterial {
+    Rsa(RsaPublicKey),
+    Ed25519(VerifyingKey),
+}
+
+impl PublicKey {
+    /// The fingerprint as upper-case hex, as `gpg --fingerprint` prints it
+    /// without spaces.
+    pub fn fingerprint_hex(&self) -> String {
+        hex(&self.fingerprint)
+    }
+
+    /// The key id: the low 64 bits of the fingerprint.
+    pub fn key_id(&self) -> [u8; 8] {
+        self.fingerprint[12..]
+            .try_into()
+            .expect("20-byte fingerprint")
+    }
+
+    /// Whether `value`, the key material of a signature, signs `digest`.
+    fn verifies(&self, digest: &[u8], padding: &Pkcs1v15Sign, value: &[u8]) -> bool {
+        match &self.key {
+            KeyMaterial::Rsa(key) => key.verify(padding.clone(), digest, value).is_ok(),
+            KeyMaterial::Ed25519(key) => ed25519_dalek::Signature::from_slice(value)
+                .is_ok_and(|signature| key.verify_strict(digest, &signature).is_ok()),
+        }
+    }
+}
+
+/// Reads the RSA and Ed25519 primary keys of a binary or ASCII-armored
+/// keyring, such as `proxmox-release-bookworm.gpg` or the output of
+/// `gpg --export`. Subkeys, user ids and keys of other algorithms are skipped.
+///
+/// # Errors
+/// `PgpError::Malformed` when the packets cannot be read or no RSA or
+/// Ed25519 primary key is found.
+pub fn parse_public_keys(data: &[u8]) -> Result<Vec<PublicKey>, PgpError> {
+    let data = dearmor(data)?;
+    let mut keys = Vec::new();
+    for (tag, body) in packets(&data)? {
+        if tag != TAG_PUBLIC_KEY {
+            continue;
+        }
+        if let Some(key) = parse_public_key(body)? {
+            keys.push(key);
+        }
+    }
+    if keys.is_empty() {
+        return Err(PgpError::Malformed("no RSA or Ed25519 public key"));
+    }
+    Ok(keys)
+}
+
+fn parse_public_key(body: &[u8]) -> Result<Option<PublicKey>, PgpError> {
+    let mut reader = Reader(body);
+    if reader.u8()? != 4 {
+        return Ok(None);
+    }
+    reader.take(4)?; // creation time
+    let key = match reader.u8()? {
+        ALGO_RSA | ALGO_RSA_SIGN_ONLY => {
+            let n = BigUint::from_bytes_be(reader.mpi()?);
+            let e = BigUint::from_bytes_be(reader.mpi()?);
+            KeyMaterial::Rsa(
+                RsaPublicKey::new(n, e).map_err(|e| PgpError::Unsupported(e.to_string()))?,
+            )
+        }
+        ALGO_EDDSA => {
+            let oid_len = reader.u8()?;
+            if reader.take(usize::from(oid_len))? != OID_ED25519 {
+                return Ok(None);
+            }
+            // The point is prefixed with 0x40, native compressed encoding.
+            let point = match reader.mpi()? {
+                [0x40, point @ ..] => point,
+                _ => return Err(PgpError::Malformed("invalid Ed25519 point")),
+            };
+            let point = point
+                .try_into()
+                .map_err(|_| PgpError::Malformed("invalid Ed25519 point"))?;
+            KeyMaterial::Ed25519(
+                VerifyingKey::from_bytes(point)
+                    .map_err(|_| PgpError::Malformed("invalid Ed25519 point"))?,
+            )
+        }
+        _ => return Ok(None),
+    };
+
+    let mut hashed = vec![0x99];
+    hashed.extend_from_slice(&(body.len() as u16).to_be_bytes());
+    hashed.extend_from_slice(body);
+    Ok(Some(PublicKey {
+        fingerprint: sha1(&hashed),
+        key,
+    }))
+}
+
+/* ===================== SIGNATURES ===================== */
+
+/// A v4 RSA or Ed25519 signature packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    sig_type: u8,
+    hash_algo: u8,
+    /// The packet from its version through the hashed subpackets, which the
+    /// signed digest covers.
+    hashed: Vec<u8>,
+    /// The key id or fingerprint (low bytes) of the issuer, when present.
+    issuer: Option<[u8; 8]>,
+    left16: [u8; 2],
+    /// The RSA signature, or the Ed25519 `R` and `S` of 32 bytes each.
+    value: Vec<u8>,
+}
+
+impl Signature {
+    /// Reads the first signature of a binary or ASCII-armored detached
+    /// signature, such as `SHA256SUMS.asc`.
+    ///
+    /// # Errors
+    /// `PgpError::Malformed` or `PgpError::Unsupported` when it is not a v4
+    /// RSA or Ed25519 signature over a binary or text document.
+    pub fn parse(data: &[u8]) -> Result<Self, PgpError> {
+        let data = dearmor(data)?;
+        let body = packets(&data)?
+            .into_iter()
+            .find_map(|(tag, body)| (tag == TAG_SIGNATURE).then_some(body))
+            .ok_or(PgpError::Malformed("no signature packet"))?;
+
+        let mut reader = Reader(body);
+        let version = reader.u8()?;
+        if version != 4 {
+            return Err(PgpError::Unsupported(format!(
+                "signature version {}",
+                version
+            )));
+        }
+        let sig_type = reader.u8()?;
+        if !matches!(sig_type, SIG_BINARY | SIG_TEXT) {
+            return Err(PgpError::Unsupported(format!(
+                "signature type 0x{:02x}",
+                sig_type
+            )));
+        }
+        let algo = reader.u8()?;
+        if !matches!(algo, ALGO_RSA | ALGO_RSA_SIGN_ONLY | ALGO_EDDSA) {
+            return Err(PgpError::Unsupported(format!(
+                "public-key algorithm {}",
+                algo
+            )));
+        }
+        let hash_algo = reader.u8()?;
+        let hashed_subpackets = reader.u16_prefixed()?;
+        let hashed = body[..6 + hashed_subpackets.len()].to_vec();
+        let unhashed_subpackets = reader.u16_prefixed()?;
+        let left16 = reader.take(2)?.try_into().expect("two bytes");
+        let value = match algo {
+            ALGO_EDDSA => {
+                let mut value = Vec::with_capacity(64);
+                for half in [reader.mpi()?, reader.mpi()?] {
+                    if half.len() > 32 {
+                        return Err(PgpError::Malformed("invalid Ed25519 signature"));
+                    }
+                    // MPIs drop leading zeroes.
+                    value.resize(value.len() + 32 - half.len(), 0);
+                    value.extend_from_slice(half);
+                }
+                value
+            }
+            _ => reader.mpi()?.to_vec(),
+        };
+
+        let issuer = issuer(hashed_subpackets)?.or(issuer(unhashed_subpackets)?);
+        Ok(Self {
+            sig_type,
+            hash_algo,
+            hashed,
+            issuer,
+            left16,
+            value,
+        })
+    }
+
+    /// Checks this signature over `data` against `keys`.
+    ///
+    /// # Returns
+    /// The key that made the signature.
+    /// # Errors
+    /// `PgpError::UnknownKey` when the issuer is not among `keys`,
+    /// `PgpError::BadSignature` when no candidate key verifies it.
+    pub fn verify<'a>(
+        &self,
+        data: &[u8],
+        keys: &'a [PublicKey],
+    ) -> Result<&'a PublicKey, PgpError> {
+        self.verify_reader(data, keys)
+    }
+
+    /// [`Signature::verify`] over the data read from `reader`, which is
+    /// hashed as it is read, so a multi-gigabyte ISO is never held in memory.
+    ///
+    /// # Errors
+    /// As [`Signature::verify`], and `PgpError::Io` when `reader` fails.
+    pub fn verify_reader<'a>(
+        &self,
+        reader: impl Read,
+        keys: &'a [PublicKey],
+    ) -> Result<&'a PublicKey, PgpError> {
+        let candidates: Vec<&PublicKey> = keys
+            .iter()
+            .filter(|key| self.issuer.is_none_or(|id| key.key_id() == id))
+            .collect();
+        if candidates.is_empty() {
+            return Err(PgpError::UnknownKey(hex(&self.issuer.unwrap_or_default())));
+        }
+
+        let (digest, padding) = self.digest(reader)?;
+        if digest[..2] != self.left16 {
+            return Err(PgpError::BadSignature);
+        }
+        candidates
+            .into_iter()
+            .find(|key| key.verifies(&digest, &padding, &self.value))
+            .ok_or(PgpError::BadSignature)
+    }
+
+    /// Hashes the data read from `reader`, the hashed part of the packet and
+    /// the v4 trailer.
+    fn digest(&self, mut reader: impl Read) -> Result<(Vec<u8>, Pkcs1v15Sign), PgpError> {
+        let (mut hasher, padding): (Box<dyn DynDigest>, _) = match self.hash_algo {
+            8 => (Box::new(Sha256::new()), Pkcs1v15Sign::new::<Sha256>()),
+            9 => (Box::new(Sha384::new()), Pkcs1v15Sign::new::<Sha384>()),
+            10 => (Box::new(Sha512::new()), Pkcs1v15Sign::new::<Sha512>()),
+            11 => (Box::new(Sha224::new()), Pkcs1v15Sign::new::<Sha224>()),
+            other => return Err(PgpError::Unsupported(format!("hash algorithm {}", other))),
+        };
+
+        let mut buf = vec![0; 64 * 1024];
+        let mut previous = None;
+        loop {
+            let read = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(PgpError::Io(e.to_string())),
+            };
+            let chunk = &buf[..read];
+            if self.sig_type == SIG_TEXT {
+                hasher.update(&canonical_text(chunk, previous));
+            } else {
+                hasher.update(chunk);
+            }
+            previous = chunk.last().copied();
+        }
+
+        let mut trailer = vec![4, 0xff];
+        trailer.extend_from_slice(&(self.hashed.len() as u32).to_be_bytes());
+        hasher.update(&self.hashed);
+        hasher.update(&trailer);
+        Ok((hasher.finalize().into_vec(), padding))
+    }
+}
+
+/// The issuer key id from a subpacket area, preferring the fingerprint.
+fn issuer(mut area: &[u8]) -> Result<Option<[u8; 8]>, PgpError> {
+    let mut key_id = None;
+    while !area.is_empty() {
+        let mut reader = Reader(area);
+        let len = match reader.u8()? {
+            len @ 0..192 => usize::from(len),
+            first @ 192..255 => ((usize::from(first) - 192) << 8) + usize::from(reader.u8()?) + 192,
+            255 => u32::from_be_bytes(reader.take(4)?.try_into().expect("four bytes")) as usize,
+        };
+        let subpacket = reader.take(len)?;
+        area = reader.0;
+        let Some((kind, contents)) = subpacket.split_first() else {
+            return Err(PgpError::Malformed("empty subpacket"));
+        };
+        match (kind & 0x7f, contents.len()) {
+            (SUBPACKET_ISSUER_FINGERPRINT, 21) => {
+                return Ok(Some(contents[13..].try_into().expect("eight bytes")));
+            }
+            (SUBPACKET_ISSUER, 8) => key_id = Some(contents.try_into().expect("eight bytes")),
+            _ => {}
+        }
+    }
+    Ok(key_id)
+}
+
+/// Text signatures are made over the document with `\r\n` line endings;
+/// `data` is a chunk of it following the byte `previous`, if any.
+fn canonical_text(data: &[u8], previous: Option<u8>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    for (i, byte) in data.iter().enumerate() {
+        let before = if i == 0 { previous } else { Some(data[i - 1]) };
+        if *byte == b'\n' && before != Some(b'\r') {
+            out.push(b'\r');
+        }
+        out.push(*byte);
+    }
+    out
+}
+
+/* ===================== PACKETS ===================== */
+
+/// Decodes an ASCII-armored block; binary input is returned as is.
+fn dearmor(data: &[u8]) -> Result<Vec<u8>, PgpError> {
+    let text = match std::str::from_utf8(data) {
+        Ok(text) if text.trim_start().starts_with("-----BEGIN PGP ") => text,
+        _ => return Ok(data.to_vec()),
+    };
+
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .skip_while(|l| !l.starts_with("-----BEGIN"));
+    lines.next();
+    // Armor headers (`Version: ...`) end at the first blank line.
+    let mut lines = lines.skip_while(|l| !l.is_empty()).skip(1);
+    let mut encoded = String::new();
+    for line in lines.by_ref() {
+        if line.starts_with("-----END") || line.starts_with('=') {
+            break;
+        }
+        encoded.push_str(line);
+    }
+    STANDARD
+        .decode(encoded)
+        .map_err(|_| PgpError::Malformed("invalid armor"))
+}
+
+/// Splits an OpenPGP packet stream into `(tag, body)` pairs, accepting both
+/// the old and the new header formats.
+fn packets(mut data: &[u8]) -> Result<Vec<(u8, &[u8])>, PgpError> {
+    let mut packets = Vec::new();
+    while !data.is_empty() {
+        let mut reader = Reader(data);
+        let header = reader.u8()?;
+        if header & 0x80 == 0 {
+            return Err(PgpError::Malformed("invalid packet header"));
+        }
+        let (tag, len) = if header & 0x40 != 0 {
+            let len = match reader.u8()? {
+                len @ 0..192 => usize::from(len),
+                first @ 192..224 => {
+                    ((usize::from(first) - 192) << 8) + usize::from(reader.u8()?) + 192
+                }
+                255 => u32::from_be_bytes(reader.take(4)?.try_into().expect("four bytes")) as usize,
+                _ => return Err(PgpError::Unsupported("partial body length".into())),
+            };
+            (header & 0x3f, len)
+        } else {
+            let len = match header & 0x03 {
+                0 => usize::from(reader.u8()?),
+                1 => usize::from(u16::from_be_bytes(
+                    reader.take(2)?.try_into().expect("two bytes"),
+                )),
+                2 => u32::from_be_bytes(reader.take(4)?.try_into().expect("four bytes")) as usize,
+                _ => reader.0.len(),
+            };
+            ((header >> 2) & 0x0f, len)
+        };
+        packets.push((tag, reader.take(len)?));
+        data = reader.0;
+    }
+    Ok(packets)
+}
+
+/// A cursor over packet bytes.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], PgpError> {
+        if len > self.0.len() {
+            return Err(PgpError::Malformed("truncated packet"));
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, PgpError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16_prefixed(&mut self) -> Result<&'a [u8], PgpError> {
+        let len = u16::from_be_bytes(self.take(2)?.try_into().expect("two bytes"));
+        self.take(usize::from(len))
+    }
+
+    /// A multiprecision integer: a bit count, then the big-endian bytes.
+    fn mpi(&mut self) -> Result<&'a [u8], PgpError> {
+        let bits = u16::from_be_bytes(self.take(2)?.try_into().expect("two bytes"));
+        self.take(usize::from(bits).div_ceil(8))
+    }
+}
+
+/* ===================== SHA-1 ===================== */
+
+/// SHA-1, needed only for v4 key fingerprints.
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [
+        0x6745_2301,
+        0xEFCD_AB89,
+        0x98BA_DCFE,
+        0x1032_5476,
+        0xC3D2_E1F0,
+    ];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes(word.try_into().expect("four bytes"));
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..20 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..40 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..60 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut out = [0; 20];
+    for (chunk, word) in out.chunks_exact_mut(4).zip(h) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use rsa::{RsaPrivateKey, traits::PublicKeyParts};
+
+    /// A fixed 1024-bit test key, so no randomness is needed.
+    pub(crate) fn private_key(seed: u8) -> RsaPrivateKey {
+        let (p, q) = match seed {
+            0 => (
+                "f0f308c6c863128c650cd0848a076c643488759a15d0725748ae94b65c76214b742d78c6088b5cfa13451c4d18feef71b6b4cf4594f308cdb2f15ecde9f0ea1f",
+                "eeb3c5cbdbd61fd64484c8b25f7f66437f34adcd16e7bf94ef6f955c5be90eb6bc0196c698797ff8a341188e375073c13707a14bac73e8603c1d21310d375879",
+            ),
+            _ => (
+                "f894f3a4f5403d2001cfe752a0aad2df70a6a7391989c10047fc7123b5063fdcbe4e88f1e64e44ffa548862c13a5b546ca68b928fbc5983b0d70876a3a88a325",
+                "ebc33ed4cde4df297e1e8eeb38a7d4007c98996c9a73b39b05c39f609ecdedde36a6a4d91a9b2d152ffe7594124e19c9a69b22e8ba9fac972c53bf79d3b14f1f",
+            ),
+        };
+        let parse = |s: &str| BigUint::parse_bytes(s.as_bytes(), 16).unwrap();
+        RsaPrivateKey::from_p_q(parse(p), parse(q), BigUint::from(65537u32)).unwrap()
+    }
+
+    fn mpi(value: &BigUint) -> Vec<u8> {
+        let bytes = value.to_bytes_be();
+        let mut out = (value.bits() as u16).to_be_bytes().to_vec();
+        out.extend_from_slice(&bytes);
+        out
+    }
+
+    /// A new-format packet with a five-octet length.
+    fn packet(tag: u8, body: &[u8]) -> Vec<u8> {
+        let mut out = vec![0xc0 | tag, 255];
+        out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        out.extend_from_slice(body);
+        out
+    }
+
+    /// A keyring with the primary key of `key`, written with an old-format header.
+    pub(crate) fn keyring(key: &RsaPrivateKey) -> Vec<u8> {
+        let mut body = vec![4, 0x65, 0x00, 0x00, 0x00, ALGO_RSA];
+        body.extend(mpi(key.n()));
+        body.extend(mpi(key.e()));
+        let mut out = vec![0x99];
+        out.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        out.extend(body);
+        out.extend(packet(13, b"Test Release Key <test@example.com>"));
+        out
+    }
+
+    /// A fixed Ed25519 test key.
+    pub(crate) fn ed25519_key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    /// A keyring with the Ed25519 primary key of `key`, as GnuPG exports it.
+    pub(crate) fn ed25519_keyring(key: &SigningKey) -> Vec<u8> {
+        let mut body = vec![
+            4,
+            0x65,
+            0x00,
+            0x00,
+            0x00,
+            ALGO_EDDSA,
+            OID_ED25519.len() as u8,
+        ];
+        body.extend(OID_ED25519);
+        body.extend(263u16.to_be_bytes());
+        body.push(0x40);
+        body.extend(key.verifying_key().as_bytes());
+        let mut out = packet(TAG_PUBLIC_KEY, &body);
+        out.extend(packet(13, b"Operator <ops@example.com>"));
+        out
+    }
+
+    /// A detached SHA-512 signature of `data` by the RSA `key`.
+    pub(crate) fn sign(key: &RsaPrivateKey, data: &[u8], sig_type: u8) -> Vec<u8> {
+        let public = parse_public_keys(&keyring(key)).unwrap().remove(0);
+        signature_packet(&public, ALGO_RSA, data, sig_type, |digest, padding| {
+            mpi(&BigUint::from_bytes_be(&key.sign(padding, digest).unwrap()))
+        })
+    }
+
+    /// A detached SHA-512 signature of `data` by the Ed25519 `key`.
+    pub(crate) fn sign_ed25519(key: &SigningKey, data: &[u8], sig_type: u8) -> Vec<u8> {
+        let public = parse_public_keys(&ed25519_keyring(key)).unwrap().remove(0);
+        signature_packet(&public, ALGO_EDDSA, data, sig_type, |digest, _| {
+            let signature = key.sign(digest).to_bytes();
+            signature
+                .chunks(32)
+                .flat_map(|half| mpi(&BigUint::from_bytes_be(half)))
+                .collect()
+        })
+    }
+
+    /// A signature packet by `public` over `data`; `value` returns the
+    /// signature MPIs for the digest.
+    fn signature_packet(
+        public: &PublicKey,
+        algo: u8,
+        data: &[u8],
+        sig_type: u8,
+        value: impl FnOnce(&[u8], Pkcs1v15Sign) -> Vec<u8>,
+    ) -> Vec<u8> {
+        let mut subpackets = vec![22, SUBPACKET_ISSUER_FINGERPRINT, 4];
+        subpackets.extend_from_slice(&public.fingerprint);
+        subpackets.extend([5, 2, 0x65, 0x00, 0x00, 0x00]);
+        let mut body = vec![4, sig_type, algo, 10];
+        body.extend_from_slice(&(subpackets.len() as u16).to_be_bytes());
+        body.extend(subpackets);
+
+        let signature = Signature {
+            sig_type,
+            hash_algo: 10,
+            hashed: body.clone(),
+            issuer: None,
+            left16: [0; 2],
+            value: Vec::new(),
+        };
+        let (digest, padding) = signature.digest(data).unwrap();
+
+        body.extend([0, 0]);
+        body.extend_from_slice(&digest[..2]);
+        body.extend(value(&digest, padding));
+        packet(TAG_SIGNATURE, &body)
+    }
+
+    fn armor(kind: &str, data: &[u8]) -> String {
+        format!(
+            "-----BEGIN PGP {kind}-----\nComment: test\n\n{}\n=AAAA\n-----END PGP {kind}-----\n",
+            STANDARD.encode(data)
+        )
+    }
+
+    /* ---------------- VERIFICATION ---------------- */
+
+    #[test]
+    fn detached_signatures_verify() {
+        let key = private_key(0);
+        let keys = parse_public_keys(&keyring(&key)).unwrap();
+        assert_eq!(keys.len(), 1);
+
+        let data = b"0123  proxmox-ve_8.2-1.iso\n";
+        let signature = Signature::parse(&sign(&key, data, SIG_BINARY)).unwrap();
+        assert_eq!(signature.verify(data, &keys), Ok(&keys[0]));
+        assert_eq!(
+            signature.verify(b"4567  proxmox-ve_8.2-1.iso\n", &keys),
+            Err(PgpError::BadSignature)
+        );
+
+        let armored = armor("SIGNATURE", &sign(&key, data, SIG_TEXT));
+        let signature = Signature::parse(armored.as_bytes()).unwrap();
+        assert_eq!(signature.verify(data, &keys), Ok(&keys[0]));
+        assert_eq!(
+            signature.verify(b"0123  proxmox-ve_8.2-1.iso\r\n", &keys),
+            Ok(&keys[0])
+        );
+    }
+
+    /// Reads one byte at a time, to split the data at every position.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let Some((first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = *first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn ed25519_signatures_verify_while_reading() {
+        let key = ed25519_key(3);
+        let mut keyring = keyring(&private_key(0));
+        keyring.extend(ed25519_keyring(&key));
+        let keys = parse_public_keys(&keyring).unwrap();
+        assert_eq!(keys.len(), 2);
+
+        let data = b"ISO 9660 image\nwith\r\nlines\n";
+        let signature = Signature::parse(&sign_ed25519(&key, data, SIG_BINARY)).unwrap();
+        assert_eq!(signature.verify_reader(Trickle(data), &keys), Ok(&keys[1]));
+        assert_eq!(
+            signature.verify(b"ISO 9660 image\n", &keys),
+            Err(PgpError::BadSignature)
+        );
+
+        let signature = Signature::parse(&sign_ed25519(&key, data, SIG_TEXT)).unwrap();
+        assert_eq!(signature.verify_reader(Trickle(data), &keys), Ok(&keys[1]));
+        let signature = Signature::parse(&sign(&private_key(0), data, SIG_TEXT)).unwrap();
+        assert_eq!(signature.verify_reader(Trickle(data), &keys), Ok(&keys[0]));
+    }
+
+    #[test]
+    fn signatures_by_other_keys_are_rejected() {
+        let data = b"data";
+        let signature = Signature::parse(&sign(&private_key(1), data, SIG_BINARY)).unwrap();
+        let keys =
+            parse_public_keys(armor("PUBLIC KEY BLOCK", &keyring(&private_key(0))).as_bytes())
+                .unwrap();
+        assert_eq!(
+            signature.verify(data, &keys).unwrap_err().code(),
+            "pgp.unknown_key"
+        );
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        assert_eq!(
+            parse_public_keys(b"not a key").unwrap_err().code(),
+            "pgp.malformed"
+        );
+        let mut truncated = keyring(&private_key(0));
+        truncated.truncate(40);
+        assert_eq!(
+            parse_public_keys(&truncated),
+            Err(PgpError::Malformed("truncated packet"))
+        );
+        let mut v3 = sign(&private_key(0), b"data", SIG_BINARY);
+        v3[6] = 3;
+        assert_eq!(Signature::parse(&v3).unwrap_err().code(), "pgp.unsupported");
+    }
+
+    /* ---------------- SHA-1 ---------------- */
+
+    #[test]
+    fn sha1_matches_the_reference_values() {
+        assert_eq!(
+            hex(&sha1(b"abc")),
+            "A9993E364706816ABA3E25717850C26C9CD0D89D"
+        );
+        assert_eq!(
+            hex(&sha1(&[b'a'; 1000])),
+            "291E9A6C66994949B57BA5E650361E98FC36B1BA"
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use crate::iso::inject::{
+    IsoInjectError, NamedRoot, SECTOR_SIZE, array, named_root, read_at, read_directory,
+    record_name, volume_label,
+};
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+/// Deepest directory [`IsoReader::walk`] descends into; Rock Ridge lifts the
+/// ISO 9660 limit of 8 levels.
+const MAX_DEPTH: usize = 64;
+
+/// A file or directory of an ISO image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IsoEntry {
+    /// Path from the root directory, e.g. `boot/grub/grub.cfg`, with the
+    /// Rock Ridge or Joliet names, else the lower-cased ISO 9660 names
+    /// without their `;1` version.
+    pub path: String,
+    pub dir: bool,
+    pub sector: u32,
+    /// Size of the file, or of the directory's records.
+    pub size: u32,
+}
+
+impl IsoEntry {
+    /// The last component of [`IsoEntry::path`].
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+/// Read-only access to the files of an ISO 9660 image, e.g. to pull the
+/// embedded `answer.toml`, the kernel or the initrd out of an installer ISO.
+///
+/// Names are taken from the Rock Ridge entries of the primary tree, else from
+/// a Joliet tree, else from the ISO 9660 identifiers. Errors name the image.
+#[derive(Debug)]
+pub struct IsoReader {
+    path: PathBuf,
+    image: File,
+    tree: NamedRoot,
+}
+
+impl IsoReader {
+    /// Opens the image at `iso` and reads its root directory.
+    ///
+    /// # Errors
+    /// Returns an error if `iso` cannot be read or is not an ISO 9660 image.
+    pub fn open(iso: &Path) -> Result<Self, IsoInjectError> {
+        let mut image =
+            File::open(iso).map_err(|e| IsoInjectError::Io(iso.to_path_buf(), e.to_string()))?;
+        let tree = named_root(&mut image).map_err(with_path(iso))?;
+        Ok(Self {
+            path: iso.to_path_buf(),
+            image,
+            tree,
+        })
+    }
+
+    /// The image file, e.g. to read its boot records.
+    pub fn image(&mut self) -> &mut File {
+        &mut self.image
+    }
+
+    /// The volume identifier of the primary volume descriptor, e.g. `PVE`.
+    pub fn volume_label(&mut self) -> Result<String, IsoInjectError> {
+        volume_label(&mut self.image).map_err(with_path(&self.path))
+    }
+
+    /// The entries of the directory at `dir`, `""` for the root directory, or
+    /// `None` when it is missing.
+    pub fn list(&mut self, dir: &str) -> Result<Option<Vec<IsoEntry>>, IsoInjectError> {
+        let Some(records) = self.directory(dir)? else {
+            return Ok(None);
+        };
+        Ok(Some(self.entries(dir.trim_matches('/'), &records)))
+    }
+
+    /// Every entry of the image, each directory followed by its contents.
+    pub fn walk(&mut self) -> Result<Vec<IsoEntry>, IsoInjectError> {
+        let mut entries = Vec::new();
+        let root = self.tree.root.clone();
+        self.walk_into("", &root, &mut HashSet::new(), &mut entries)?;
+        Ok(entries)
+    }
+
+    /// The entry at `path`, relative to the root directory, e.g.
+    /// `boot/grub/grub.cfg`, or `None` when it or one of its directories is
+    /// missing.
+    pub fn find(&mut self, path: &str) -> Result<Option<IsoEntry>, IsoInjectError> {
+        let path = path.trim_matches('/');
+        let (dir, _) = path.rsplit_once('/').unwrap_or(("", path));
+        Ok(self
+            .list(dir)?
+            .and_then(|entries| entries.into_iter().find(|entry| entry.path == path)))
+    }
+
+    /// Reads the contents of the file `entry`.
+    pub fn read(&mut self, entry: &IsoEntry) -> Result<Vec<u8>, IsoInjectError> {
+        if entry.dir {
+            return Err(IsoInjectError::Unsupported("reading a directory as a file"));
+        }
+        read_at(
+            &mut self.image,
+            u64::from(entry.sector) * SECTOR_SIZE,
+            entry.size as usize,
+        )
+        .map_err(with_path(&self.path))
+    }
+
+    /// Copies the file `entry` to `dest`, replacing it, without holding the
+    /// whole file in memory.
+    pub fn extract(&mut self, entry: &IsoEntry, dest: &Path) -> Result<(), IsoInjectError> {
+        if entry.dir {
+            return Err(IsoInjectError::Unsupported("reading a directory as a file"));
+        }
+        self.image
+            .seek(SeekFrom::Start(u64::from(entry.sector) * SECTOR_SIZE))
+            .map_err(|e| IsoInjectError::Io(self.path.clone(), e.to_string()))?;
+        let mut out =
+            File::create(dest).map_err(|e| IsoInjectError::Io(dest.into(), e.to_string()))?;
+        let copied = io::copy(&mut (&mut self.image).take(entry.size.into()), &mut out)
+            .map_err(|e| IsoInjectError::Io(dest.into(), e.to_string()))?;
+        if copied < u64::from(entry.size) {
+            return Err(IsoInjectError::Io(
+                self.path.clone(),
+                format!("{} ends past the end of the image", entry.path),
+            ));
+        }
+        out.sync_all()
+            .map_err(|e| IsoInjectError::Io(dest.into(), e.to_string()))
+    }
+
+    /// The records of the directory at `dir`, or `None` when it is missing.
+    fn directory(&mut self, dir: &str) -> Result<Option<Vec<Vec<u8>>>, IsoInjectError> {
+        let NamedRoot { joliet, skip, .. } = self.tree;
+        let mut records = self.tree.root.clone();
+        for name in dir.split('/').filter(|d| !d.is_empty()) {
+            let Some(record) = records[2..]
+                .iter()
+                .find(|r| r[25] & 0x02 != 0 && record_name(r, joliet, skip) == name)
+            else {
+                return Ok(None);
+            };
+            records =
+                read_directory(&mut self.image, &record.clone()).map_err(with_path(&self.path))?;
+        }
+        Ok(Some(records))
+    }
+
+    /// Appends the entries below the directory `dir` with the `records`;
+    /// directories are entered once, should a record point back up.
+    fn walk_into(
+        &mut self,
+        dir: &str,
+        records: &[Vec<u8>],
+        seen: &mut HashSet<u32>,
+        entries: &mut Vec<IsoEntry>,
+    ) -> Result<(), IsoInjectError> {
+        if dir.split('/').count() > MAX_DEPTH {
+            return Err(IsoInjectError::Unsupported("directories nested too deeply"));
+        }
+        for (record, entry) in records[2..].iter().zip(self.entries(dir, records)) {
+            let enter = entry.dir && seen.insert(entry.sector);
+            let path = entry.path.clone();
+            entries.push(entry);
+            if enter {
+                let children =
+                    read_directory(&mut self.image, record).map_err(with_path(&self.path))?;
+                self.walk_into(&path, &children, seen, entries)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The entries of the directory `dir` with the `records`, without `.` and
+    /// `..`.
+    fn entries(&self, dir: &str, records: &[Vec<u8>]) -> Vec<IsoEntry> {
+        records[2..]
+            .iter()
+            .map(|record| {
+                let name = record_name(record, self.tree.joliet, self.tree.skip);
+                IsoEntry {
+                    path: match dir {
+                        "" => name,
+                        dir => format!("{}/{}", dir, name),
+                    },
+                    dir: record[25] & 0x02 != 0,
+                    sector: u32::from_le_bytes(array(&record[2..6])),
+                    size: u32::from_le_bytes(array(&record[10..14])),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Names `iso` in errors of the image helpers, which do not know its path.
+fn with_path(iso: &Path) -> impl Fn(IsoInjectError) -> IsoInjectError + '_ {
+    move |e| match e {
+        IsoInjectError::Io(_, detail) => IsoInjectError::Io(iso.to_path_buf(), detail),
+        IsoInjectError::NotIso9660(_) => IsoInjectError::NotIso9660(iso.to_path_buf()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iso::inject::tests::{image, inject, nested_image};
+
+    fn temp_iso(name: &str, image: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "pveauto-reader-{}-{}.iso",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&path, image).unwrap();
+        path
+    }
+
+    #[test]
+    fn files_have_their_original_names() {
+        for (rock_ridge, joliet) in [(true, false), (false, false), (false, true)] {
+            let out = inject(
+                &image(rock_ridge, joliet),
+                &format!("reader-{}-{}", rock_ridge, joliet),
+                &[("answer.toml", b"[global]\n")],
+            )
+            .unwrap();
+            let path = temp_iso(&format!("names-{}-{}", rock_ridge, joliet), &out);
+            let mut reader = IsoReader::open(&path).unwrap();
+
+            let root = reader.list("").unwrap().unwrap();
+            let answer = root.iter().find(|e| e.name() == "answer.toml").unwrap();
+            assert_eq!(reader.read(answer).unwrap(), b"[global]\n");
+            if !joliet {
+                assert!(root.iter().any(|e| e.path == "readme.txt"));
+            }
+            assert_eq!(reader.volume_label().unwrap(), "");
+            std::fs::remove_file(path).unwrap();
+        }
+
+        let not_iso = temp_iso("not-iso", &[0; 40_000]);
+        assert_eq!(
+            IsoReader::open(&not_iso).unwrap_err(),
+            IsoInjectError::NotIso9660(not_iso.clone())
+        );
+        std::fs::remove_file(not_iso).unwrap();
+    }
+
+    #[test]
+    fn nested_files_are_listed_and_extracted() {
+        for rock_ridge in [true, false] {
+            let path = temp_iso(
+                &format!("nested-{}", rock_ridge),
+                &nested_image(rock_ridge, ["boot", "grub"], Some(("grub.cfg", b"linux"))),
+            );
+            let mut reader = IsoReader::open(&path).unwrap();
+
+            let paths: Vec<(String, bool)> = reader
+                .walk()
+                .unwrap()
+                .into_iter()
+                .map(|e| (e.path, e.dir))
+                .collect();
+            assert_eq!(
+                paths,
+                [
+                    ("boot".to_string(), true),
+                    ("boot/grub".into(), true),
+                    ("boot/grub/grub.cfg".into(), false),
+                    ("readme.txt".into(), false),
+                ]
+            );
+            assert_eq!(reader.list("/boot/").unwrap().unwrap().len(), 1);
+            assert_eq!(reader.list("efi").unwrap(), None);
+            assert_eq!(reader.find("boot/efi/grub.cfg").unwrap(), None);
+            assert!(reader.find("boot").unwrap().unwrap().dir);
+
+            let config = reader.find("/boot/grub/grub.cfg").unwrap().unwrap();
+            assert_eq!(config.name(), "grub.cfg");
+            let dest = path.with_extension("cfg");
+            reader.extract(&config, &dest).unwrap();
+            assert_eq!(std::fs::read(&dest).unwrap(), b"linux");
+
+            let dir = reader.find("boot/grub").unwrap().unwrap();
+            assert!(reader.read(&dir).is_err());
+            assert!(reader.extract(&dir, &dest).is_err());
+            std::fs::remove_file(dest).unwrap();
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use crate::iso::http::{HttpFetcher, HttpResponse};
+use reqwest::{Method, StatusCode, header::HeaderMap};
+use std::{error::Error, time::Duration};
+
+/// Longest wait between two attempts, however many have failed.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// How often, and how patiently, failed network operations are retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Tries in total, including the first; `1` disables retrying.
+    pub attempts: u32,
+    /// Wait before the first retry, doubled before each further one.
+    pub base_delay: Duration,
+    /// Upper bound of a random extra wait, so that many clients hit by the
+    /// same outage do not retry in lockstep.
+    pub jitter: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 4,
+            base_delay: Duration::from_secs(1),
+            jitter: Duration::from_millis(500),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Tries once and gives up.
+    pub const NONE: Self = Self {
+        attempts: 1,
+        base_delay: Duration::ZERO,
+        jitter: Duration::ZERO,
+    };
+
+    /// The wait before retry number `retry` (1 for the first retry), without
+    /// jitter: `base_delay * 2^(retry - 1)`, at most a minute.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 1u32 << retry.saturating_sub(1).min(16);
+        self.base_delay
+            .checked_mul(factor)
+            .unwrap_or(MAX_RETRY_DELAY)
+            .min(MAX_RETRY_DELAY)
+    }
+
+    /// [`Self::backoff`] plus a random share of `jitter`.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let jitter_ms = self.jitter.as_millis() as u64;
+        let extra = match (jitter_ms, getrandom::u64()) {
+            (0, _) | (_, Err(_)) => 0,
+            (jitter_ms, Ok(random)) => random % (jitter_ms + 1),
+        };
+        self.backoff(retry) + Duration::from_millis(extra)
+    }
+}
+
+/// Whether a response with `status` may succeed when the request is repeated:
+/// server errors, `408 Request Timeout` and `429 Too Many Requests`.
+pub fn is_transient(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// An [`HttpFetcher`] that repeats requests which failed to connect or got a
+/// transient status, waiting as `policy` says between attempts.
+///
+/// Downloads through it also resume after their body is cut off, see
+/// [`HttpFetcher::retry_policy`].
+#[derive(Debug, Clone)]
+pub struct Retrying<F> {
+    inner: F,
+    policy: RetryPolicy,
+    quiet: bool,
+}
+
+impl<F: HttpFetcher> Retrying<F> {
+    /// Wraps `inner`; each retry is announced on standard error unless `quiet`.
+    pub fn new(inner: F, policy: RetryPolicy, quiet: bool) -> Self {
+        Self {
+            inner,
+            policy,
+            quiet,
+        }
+    }
+}
+
+impl<F: HttpFetcher> HttpFetcher for Retrying<F> {
+    async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        headers: HeaderMap,
+    ) -> Result<HttpResponse, Box<dyn Error>> {
+        let mut attempt = 1;
+        loop {
+            let result = self.inner.send(method.clone(), url, headers.clone()).await;
+            let reason = match &result {
+                Ok(resp) if is_transient(resp.status) => resp.status.to_string(),
+                Ok(_) => return result,
+                Err(e) => e.to_string(),
+            };
+            if attempt >= self.policy.attempts {
+                return result;
+            }
+            let delay = self.policy.delay(attempt);
+            if !self.quiet {
+                eprintln!(
+                    "{} {} failed ({}). Retrying in {:.1}s ({}/{})...",
+                    method,
+                    url,
+                    reason,
+                    delay.as_secs_f32(),
+                    attempt,
+                    self.policy.attempts - 1
+                );
+            }
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        self.policy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Answers with the next of `statuses`, or fails to connect for `None`.
+    struct Flaky {
+        statuses: Mutex<Vec<Option<StatusCode>>>,
+    }
+
+    impl HttpFetcher for Flaky {
+        async fn send(
+            &self,
+            _method: Method,
+            url: &str,
+            _headers: HeaderMap,
+        ) -> Result<HttpResponse, Box<dyn Error>> {
+            match self.statuses.lock().unwrap().remove(0) {
+                Some(status) => Ok(HttpResponse::new(url, status, HeaderMap::new(), Vec::new())),
+                None => Err("connection reset".into()),
+            }
+        }
+    }
+
+    fn flaky(statuses: &[Option<StatusCode>]) -> Flaky {
+        Flaky {
+            statuses: Mutex::new(statuses.to_vec()),
+        }
+    }
+
+    const FAST: RetryPolicy = RetryPolicy {
+        attempts: 3,
+        base_delay: Duration::from_millis(1),
+        jitter: Duration::from_millis(1),
+    };
+
+    #[test]
+    fn backoff_doubles_up_to_a_minute() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(7), MAX_RETRY_DELAY);
+        assert_eq!(policy.backoff(u32::MAX), MAX_RETRY_DELAY);
+        for _ in 0..20 {
+            let delay = policy.delay(2);
+            assert!(delay >= Duration::from_secs(2) && delay <= Duration::from_millis(2500));
+        }
+        assert_eq!(RetryPolicy::NONE.delay(1), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried() {
+        let http = Retrying::new(
+            flaky(&[None, Some(StatusCode::BAD_GATEWAY), Some(StatusCode::OK)]),
+            FAST,
+            true,
+        );
+        let resp = http.get("http://mock/", HeaderMap::new()).await.unwrap();
+        assert_eq!(resp.status, StatusCode::OK);
+        assert!(http.inner.statuses.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn retries_stop_at_the_last_attempt_or_a_final_status() {
+        let http = Retrying::new(flaky(&[None, None, None, Some(StatusCode::OK)]), FAST, true);
+        let err = http.head("http://mock/").await.unwrap_err();
+        assert_eq!(err.to_string(), "connection reset");
+        assert_eq!(http.inner.statuses.lock().unwrap().len(), 1);
+
+        let http = Retrying::new(
+            flaky(&[Some(StatusCode::NOT_FOUND), Some(StatusCode::OK)]),
+            FAST,
+            true,
+        );
+        let resp = http.head("http://mock/").await.unwrap();
+        assert_eq!(resp.status, StatusCode::NOT_FOUND);
+    }
+}
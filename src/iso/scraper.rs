@@ -1,21 +1,49 @@
-use crate::iso::constants::{ISO_URL_REGEX_PATTERN, PROX_DL_PG_URL};
+use crate::iso::{
+    channel::IsoChannel,
+    checksum::{Checksum, ChecksumAlgorithm},
+    constants::{
+        ISO_FILE_NAME_REGEX_PATTERN, ISO_INDEX_URL, ISO_URL_REGEX_PATTERN, PROX_DL_PG_URL,
+    },
+    http::HttpFetcher,
+};
 use oxdl::validator::{is_valid_sha256, is_valid_url};
 use regex::Regex;
-use reqwest::Client;
-use scraper::{Html, Selector};
+use reqwest::{
+    StatusCode,
+    header::{ETAG, HeaderMap, HeaderValue, IF_NONE_MATCH},
+};
+use scraper::{ElementRef, Html, Selector};
 use std::error::Error;
 
-/// Fetches the Proxmox VE download page HTML content.
+/// The page the latest release is scraped from and the pattern the ISO URL
+/// found there must match; [`PROX_DL_PG_URL`] and [`ISO_URL_REGEX_PATTERN`]
+/// unless overridden in the configuration, e.g. for a staging mirror.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadPage {
+    pub url: String,
+    pub iso_url_pattern: String,
+}
+
+impl Default for DownloadPage {
+    fn default() -> Self {
+        Self {
+            url: PROX_DL_PG_URL.to_string(),
+            iso_url_pattern: ISO_URL_REGEX_PATTERN.to_string(),
+        }
+    }
+}
+
+/// Fetches the HTML content of the download `page` with `http`.
 ///
 /// # Returns
 /// A string containing the HTML content of the download page.
 /// # Errors
 /// Returns an error if the HTTP request fails.
-pub async fn fetch_dl_page() -> Result<String, Box<dyn Error>> {
-    let client = Client::new();
-    let resp = client.get(PROX_DL_PG_URL).send().await?;
-    let resp = resp.error_for_status()?;
-    Ok(resp.text().await?)
+pub async fn fetch_dl_page(
+    http: &impl HttpFetcher,
+    page: &DownloadPage,
+) -> Result<String, Box<dyn Error>> {
+    fetch_text(http, &page.url).await
 }
 
 /// Validates the scraped ISO URL and SHA256 checksum.
@@ -23,16 +51,21 @@ pub async fn fetch_dl_page() -> Result<String, Box<dyn Error>> {
 /// # Arguments
 /// * `iso_url` - The ISO URL to validate.
 /// * `sha256_checksum` - The SHA256 checksum to validate.
+/// * `iso_url_pattern` - The regex the ISO URL must match.
 /// # Returns
 /// Returns Ok(()) if both the ISO URL and SHA256 checksum are valid, otherwise returns an error.
 /// # Errors
 /// Returns an error if validation fails.
-pub fn validate_return_data(iso_url: &str, sha256_checksum: &str) -> Result<(), Box<dyn Error>> {
+pub fn validate_return_data(
+    iso_url: &str,
+    sha256_checksum: &str,
+    iso_url_pattern: &str,
+) -> Result<(), Box<dyn Error>> {
     if !is_valid_url(iso_url) {
         return Err("Invalid ISO URL".into());
     }
 
-    let iso_url_regex = Regex::new(ISO_URL_REGEX_PATTERN)?;
+    let iso_url_regex = Regex::new(iso_url_pattern)?;
     if !iso_url_regex.is_match(iso_url) {
         return Err("ISO URL does not match expected pattern".into());
     }
@@ -43,15 +76,69 @@ pub fn validate_return_data(iso_url: &str, sha256_checksum: &str) -> Result<(),
     Ok(())
 }
 
-/// Scrapes the Proxmox VE download page to extract the latest ISO URL and its SHA256 checksum.
+/// A published Proxmox VE ISO.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IsoRelease {
+    /// The Proxmox VE version, e.g. `8.2-1`.
+    pub version: String,
+    pub url: String,
+    /// The strongest checksum published for the ISO: SHA256 on the download
+    /// page, SHA512 or else SHA256 in a directory listing.
+    pub checksum: Checksum,
+    /// The day the ISO was published, as `YYYY-MM-DD`, when listed.
+    pub release_date: Option<String>,
+    /// The size in bytes, when listed exactly; the download page only gives
+    /// a rounded size.
+    pub size: Option<u64>,
+}
+
+impl IsoRelease {
+    /// The SHA256 checksum in hex, when that is the checksum known.
+    pub fn sha256(&self) -> Option<&str> {
+        (self.checksum.algorithm == ChecksumAlgorithm::Sha256).then_some(&self.checksum.hex)
+    }
+
+    /// The ISO file name, e.g. `proxmox-ve_8.2-1.iso`.
+    pub fn file_name(&self) -> String {
+        iso_file_name(&self.version)
+    }
+}
+
+/// Scrapes the Proxmox VE download `page` for the latest ISO release.
+///
+/// When the page cannot be fetched or no longer has the expected layout, the
+/// newest release in the directory listing at [`ISO_INDEX_URL`] is used
+/// instead, with the strongest checksum published for it, see
+/// [`published_checksum`]. The page only announces releases, so test ISOs are
+/// looked up with [`get_newest_listed_iso_info`].
+///
+/// # Errors
+/// Returns an error if both the scraping and the directory listing fail.
+pub async fn get_latest_iso_info(
+    http: &impl HttpFetcher,
+    page: &DownloadPage,
+) -> Result<IsoRelease, Box<dyn Error>> {
+    let scraped = match fetch_dl_page(http, page).await {
+        Ok(html) => parse_latest_iso_info(&html, page),
+        Err(e) => Err(e),
+    };
+    match scraped {
+        Ok(release) => Ok(release),
+        Err(_) => get_newest_listed_iso_info(http, ISO_INDEX_URL, IsoChannel::Stable).await,
+    }
+}
+
+/// Extracts the latest ISO release from the HTML of the Proxmox VE download
+/// `page`: its URL, SHA256 checksum and, when shown, its release date.
 ///
-/// # Returns
-/// A tuple containing the ISO URL and SHA256 checksum as strings.
 /// # Errors
-/// Returns an error if the scraping or data validation fails.
-pub async fn get_latest_iso_info() -> Result<(String, String), Box<dyn Error>> {
-    let html = fetch_dl_page().await?;
-    let document = Html::parse_document(&html);
+/// Returns an error if the page has no valid latest ISO entry, or its URL
+/// does not match the page's ISO URL pattern.
+pub fn parse_latest_iso_info(
+    html: &str,
+    page: &DownloadPage,
+) -> Result<IsoRelease, Box<dyn Error>> {
+    let document = Html::parse_document(html);
 
     let sel_latest = Selector::parse("ul.latest-downloads")?;
     let sel_li = Selector::parse("li")?;
@@ -60,6 +147,7 @@ pub async fn get_latest_iso_info() -> Result<(String, String), Box<dyn Error>> {
     let sel_info = Selector::parse("div.download-entry-info")?;
     let sel_dl = Selector::parse("dl")?;
     let sel_shasum = Selector::parse("div.download-entry-shasum")?;
+    let sel_dt = Selector::parse("dt")?;
     let sel_dd = Selector::parse("dd")?;
     let sel_code = Selector::parse("code")?;
 
@@ -92,10 +180,17 @@ pub async fn get_latest_iso_info() -> Result<(String, String), Box<dyn Error>> {
         .next()
         .ok_or("Download entry info not found")?;
 
-    let sha256_checksum = info
-        .select(&sel_dl)
-        .next()
-        .ok_or("DL element not found")?
+    let dl = info.select(&sel_dl).next().ok_or("DL element not found")?;
+    let release_date = dl
+        .select(&sel_dt)
+        .filter(|dt| {
+            let label = dt.text().collect::<String>().to_lowercase();
+            label.contains("updated") || label.contains("date")
+        })
+        .filter_map(|dt| dt.next_siblings().find_map(ElementRef::wrap))
+        .find_map(|dd| release_date(&dd.text().collect::<String>()));
+
+    let sha256_checksum = dl
         .select(&sel_shasum)
         .next()
         .ok_or("SHA sum element not found")?
@@ -111,23 +206,767 @@ pub async fn get_latest_iso_info() -> Result<(String, String), Box<dyn Error>> {
         .trim()
         .to_string();
 
-    validate_return_data(&iso_url, &sha256_checksum)?;
-    Ok((iso_url, sha256_checksum))
+    validate_return_data(&iso_url, &sha256_checksum, &page.iso_url_pattern)?;
+    Ok(IsoRelease {
+        version: iso_version(&iso_url).ok_or("ISO URL has no Proxmox VE version")?,
+        checksum: Checksum::sha256(&sha256_checksum)?,
+        url: iso_url,
+        release_date,
+        size: None,
+    })
+}
+
+/// Fetches the text at `url`.
+///
+/// # Errors
+/// Returns an error if the HTTP request fails.
+async fn fetch_text(http: &impl HttpFetcher, url: &str) -> Result<String, Box<dyn Error>> {
+    let resp = http.get(url, HeaderMap::new()).await?;
+    let resp = resp.error_for_status()?;
+    resp.text().await
+}
+
+/// A fetched page and its `ETag`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    pub body: String,
+    pub etag: Option<String>,
+}
+
+/// Fetches `url`, asking the server to skip the body when it still has `etag`.
+///
+/// # Returns
+/// `None` when the server answers `304 Not Modified`.
+/// # Errors
+/// Returns an error if the HTTP request fails.
+pub async fn fetch_page(
+    http: &impl HttpFetcher,
+    url: &str,
+    etag: Option<&str>,
+) -> Result<Option<Page>, Box<dyn Error>> {
+    let mut headers = HeaderMap::new();
+    if let Some(etag) = etag {
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_str(etag)?);
+    }
+    let resp = http.get(url, headers).await?;
+    if resp.status == StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+    let resp = resp.error_for_status()?;
+    let etag = header_etag(&resp.headers);
+    Ok(Some(Page {
+        body: resp.text().await?,
+        etag,
+    }))
+}
+
+/// Returns the `ETag` of `url` from a `HEAD` request, if the server sends one.
+///
+/// # Errors
+/// Returns an error if the HTTP request fails.
+pub async fn fetch_etag(
+    http: &impl HttpFetcher,
+    url: &str,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let resp = http.head(url).await?.error_for_status()?;
+    Ok(header_etag(&resp.headers))
+}
+
+fn header_etag(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned)
+}
+
+/// Returns the Proxmox VE version of the ISO at `iso_url`, e.g. `8.2-1` for
+/// `.../proxmox-ve_8.2-1.iso`.
+pub fn iso_version(iso_url: &str) -> Option<String> {
+    let file_name = iso_url.rsplit('/').next()?;
+    let file_name_regex = Regex::new(ISO_FILE_NAME_REGEX_PATTERN).ok()?;
+    Some(file_name_regex.captures(file_name)?[1].to_string())
+}
+
+/// Splits a Proxmox VE version such as `8.2-1` or `9.0-BETA-1` into its
+/// major and minor version, whether it is a release rather than a beta, and
+/// its ISO release, for ordering: betas come before the release they precede.
+///
+/// # Returns
+/// `None` if `version` is not `<major>.<minor>-<release>` or
+/// `<major>.<minor>-BETA-<release>`.
+pub fn version_key(version: &str) -> Option<(u32, u32, bool, u32)> {
+    let (major_minor, release) = version.split_once('-')?;
+    let (released, release) = match release.strip_prefix("BETA-") {
+        Some(release) => (false, release),
+        None => (true, release),
+    };
+    let (major, minor) = major_minor.split_once('.')?;
+    let number = |s: &str| {
+        s.bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| s.parse().ok())
+            .flatten()
+    };
+    Some((number(major)?, number(minor)?, released, number(release)?))
+}
+
+/// Returns the file name of the Proxmox VE ISO of `version`.
+pub fn iso_file_name(version: &str) -> String {
+    format!("proxmox-ve_{}.iso", version)
+}
+
+/// A Proxmox VE ISO in a directory listing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListedIso {
+    pub version: String,
+    /// The modification day, as `YYYY-MM-DD`, when listed.
+    pub release_date: Option<String>,
+    /// The size in bytes, when listed exactly rather than as e.g. `1.3G`.
+    pub size: Option<u64>,
+}
+
+/// Extracts the Proxmox VE ISO versions of `channel` linked from the ISO
+/// directory listing.
+///
+/// # Returns
+/// The versions, oldest first and without duplicates.
+/// # Errors
+/// Returns an error if the file name pattern or selector is invalid.
+pub fn parse_iso_versions(html: &str, channel: IsoChannel) -> Result<Vec<String>, Box<dyn Error>> {
+    Ok(parse_iso_listing(html, channel)?
+        .into_iter()
+        .map(|iso| iso.version)
+        .collect())
+}
+
+/// Extracts the Proxmox VE ISOs of `channel` linked from the ISO directory
+/// listing, with the date and size an nginx or Apache index shows next to
+/// each link.
+///
+/// # Returns
+/// The ISOs, oldest first and without duplicates.
+/// # Errors
+/// Returns an error if the file name pattern or selector is invalid.
+pub fn parse_iso_listing(
+    html: &str,
+    channel: IsoChannel,
+) -> Result<Vec<ListedIso>, Box<dyn Error>> {
+    let document = Html::parse_document(html);
+    let sel_link = Selector::parse("a[href]")?;
+    let file_name_regex = Regex::new(ISO_FILE_NAME_REGEX_PATTERN)?;
+
+    let mut isos: Vec<ListedIso> = document
+        .select(&sel_link)
+        .filter_map(|a| {
+            let captures = file_name_regex.captures(a.value().attr("href")?)?;
+            if !channel.includes(&captures[1]) {
+                return None;
+            }
+            let columns = listed_columns(a);
+            let columns: Vec<&str> = columns.split_whitespace().collect();
+            Some(ListedIso {
+                version: captures[1].to_string(),
+                release_date: columns.first().and_then(|date| release_date(date)),
+                size: columns.last().and_then(|size| size.parse().ok()),
+            })
+        })
+        .collect();
+    isos.sort_by_key(|iso| version_key(&iso.version));
+    isos.dedup_by(|a, b| a.version == b.version);
+    Ok(isos)
 }
 
-#[cfg(all(test, feature = "iso-scraper-tests"))]
+/// The text shown after the link `a` on its line of a directory index: the
+/// following text of an nginx `<pre>` listing up to the line break, or the
+/// following cells of an Apache table row.
+fn listed_columns(a: ElementRef) -> String {
+    let in_cell = a
+        .parent()
+        .and_then(ElementRef::wrap)
+        .filter(|parent| parent.value().name() == "td");
+    match in_cell {
+        Some(cell) => cell
+            .next_siblings()
+            .filter_map(ElementRef::wrap)
+            .map(|td| td.text().collect::<String>())
+            .collect::<Vec<_>>()
+            .join(" "),
+        None => a
+            .next_sibling()
+            .and_then(|node| node.value().as_text().map(|text| text.to_string()))
+            .and_then(|text| text.lines().next().map(str::to_owned))
+            .unwrap_or_default(),
+    }
+}
+
+/// Normalizes a date as shown by a directory index or the download page,
+/// `24-Apr-2024`, `2024-04-24` or `April 24, 2024`, to `YYYY-MM-DD`.
+fn release_date(text: &str) -> Option<String> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let month = |name: &str| {
+        let name = name.to_lowercase();
+        (name.len() >= 3)
+            .then(|| MONTHS.iter().position(|m| name.starts_with(m)))
+            .flatten()
+            .map(|i| i as u32 + 1)
+    };
+    let number = |s: &str| {
+        (!s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()))
+            .then(|| s.parse::<u32>().ok())
+            .flatten()
+    };
+
+    let text = text.trim();
+    let (year, month, day) = match text.split('-').collect::<Vec<_>>()[..] {
+        [y, m, d] if y.len() == 4 => (number(y)?, number(m)?, number(d)?),
+        [d, m, y] if y.len() == 4 => (number(y)?, month(m)?, number(d)?),
+        _ => match text
+            .split([' ', ','])
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()[..]
+        {
+            [m, d, y] => (number(y)?, month(m)?, number(d)?),
+            _ => return None,
+        },
+    };
+    ((1..=12).contains(&month) && (1..=31).contains(&day))
+        .then(|| format!("{:04}-{:02}-{:02}", year, month, day))
+}
+
+/// Finds the checksum of `file_name` in `sha256sum`-style `sums`.
+pub fn checksum_for<'a>(sums: &'a str, file_name: &str) -> Option<&'a str> {
+    sums.lines().find_map(|line| {
+        let (checksum, name) = line.split_once(char::is_whitespace)?;
+        (name.trim_start().trim_start_matches('*') == file_name).then_some(checksum)
+    })
+}
+
+/// Lists the Proxmox VE ISO versions of `channel` in the directory listing
+/// at `index_url`: [`ISO_INDEX_URL`] or a mirror of it.
+///
+/// # Returns
+/// The versions, oldest first.
+/// # Errors
+/// Returns an error if the listing cannot be fetched or has no ISO of
+/// `channel`.
+pub async fn list_iso_versions(
+    http: &impl HttpFetcher,
+    index_url: &str,
+    channel: IsoChannel,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let versions = parse_iso_versions(&fetch_text(http, index_url).await?, channel)?;
+    if versions.is_empty() {
+        return Err(channel.nothing_listed().into());
+    }
+    Ok(versions)
+}
+
+/// Fetches the `algorithm` checksum file under `index_url`.
+///
+/// # Returns
+/// `None` when the file is not published.
+/// # Errors
+/// Returns an error if the HTTP request fails.
+async fn fetch_sums(
+    http: &impl HttpFetcher,
+    index_url: &str,
+    algorithm: ChecksumAlgorithm,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let resp = http
+        .get(
+            &format!("{}{}", index_url, algorithm.sums_file()),
+            HeaderMap::new(),
+        )
+        .await?;
+    if resp.status == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    Ok(Some(resp.error_for_status()?.text().await?))
+}
+
+/// Finds the strongest checksum of `file_name` published under `index_url`:
+/// from SHA512SUMS, else SHA256SUMS. Missing checksum files are skipped.
+///
+/// # Returns
+/// `None` when no checksum file lists `file_name`.
+/// # Errors
+/// Returns an error if a checksum file cannot be fetched, or `file_name` is
+/// only listed in MD5SUMS, which is not trusted.
+pub async fn published_checksum(
+    http: &impl HttpFetcher,
+    index_url: &str,
+    file_name: &str,
+) -> Result<Option<Checksum>, Box<dyn Error>> {
+    for algorithm in ChecksumAlgorithm::STRONGEST_FIRST {
+        let Some(sums) = fetch_sums(http, index_url, algorithm).await? else {
+            continue;
+        };
+        if let Some(checksum) = checksum_for(&sums, file_name) {
+            return Ok(Some(Checksum::new(algorithm, checksum)?));
+        }
+    }
+    Ok(None)
+}
+
+/// Looks up the release of a specific Proxmox VE version under `index_url`,
+/// with the strongest checksum published for it. The listing is not read, so
+/// the release date and size are unknown.
+///
+/// # Errors
+/// Returns an error if `version` is malformed, not published, or the data
+/// validation fails.
+pub async fn get_iso_info(
+    http: &impl HttpFetcher,
+    index_url: &str,
+    version: &str,
+) -> Result<IsoRelease, Box<dyn Error>> {
+    if version_key(version).is_none() {
+        return Err(format!("Invalid Proxmox VE version: {}", version).into());
+    }
+    let file_name = iso_file_name(version);
+    let checksum = published_checksum(http, index_url, &file_name)
+        .await?
+        .ok_or_else(|| format!("Proxmox VE {} is not published", version))?;
+    let iso_url = format!("{}{}", index_url, file_name);
+
+    if !is_valid_url(&iso_url) {
+        return Err("Invalid ISO URL".into());
+    }
+    Ok(IsoRelease {
+        version: version.to_string(),
+        url: iso_url,
+        checksum,
+        release_date: None,
+        size: None,
+    })
+}
+
+/// Looks up the newest Proxmox VE ISO of `channel` under `index_url`, for
+/// mirrors and test ISOs, which have no download page to scrape, with its
+/// date and size as listed.
+///
+/// # Errors
+/// Returns an error if the listing or checksums cannot be read.
+pub async fn get_newest_listed_iso_info(
+    http: &impl HttpFetcher,
+    index_url: &str,
+    channel: IsoChannel,
+) -> Result<IsoRelease, Box<dyn Error>> {
+    let newest = parse_iso_listing(&fetch_text(http, index_url).await?, channel)?
+        .pop()
+        .ok_or_else(|| channel.nothing_listed())?;
+    let release = get_iso_info(http, index_url, &newest.version).await?;
+    Ok(IsoRelease {
+        release_date: newest.release_date,
+        size: newest.size,
+        ..release
+    })
+}
+
+/// Lists the Proxmox VE ISOs of `channel` in the directory listing at
+/// `index_url`: [`ISO_INDEX_URL`] or a mirror of it, each with the strongest checksum
+/// published for it and its date and size as listed. Each checksum file is
+/// fetched once.
+///
+/// ISOs without a SHA512 or SHA256 checksum are left out, as they cannot be
+/// verified.
+///
+/// # Returns
+/// The releases, oldest first.
+/// # Errors
+/// Returns an error if the listing or a checksum file cannot be fetched, or
+/// the listing has no ISO of `channel`.
+pub async fn releases(
+    http: &impl HttpFetcher,
+    index_url: &str,
+    channel: IsoChannel,
+) -> Result<Vec<IsoRelease>, Box<dyn Error>> {
+    let listed = parse_iso_listing(&fetch_text(http, index_url).await?, channel)?;
+    if listed.is_empty() {
+        return Err(channel.nothing_listed().into());
+    }
+    let mut sums = Vec::new();
+    for algorithm in ChecksumAlgorithm::STRONGEST_FIRST {
+        if !algorithm.is_trusted() {
+            continue;
+        }
+        if let Some(file) = fetch_sums(http, index_url, algorithm).await? {
+            sums.push((algorithm, file));
+        }
+    }
+
+    Ok(listed
+        .into_iter()
+        .filter_map(|iso| {
+            let file_name = iso_file_name(&iso.version);
+            let checksum = sums.iter().find_map(|(algorithm, file)| {
+                Checksum::new(*algorithm, checksum_for(file, &file_name)?).ok()
+            })?;
+            Some(IsoRelease {
+                url: format!("{}{}", index_url, file_name),
+                version: iso.version,
+                checksum,
+                release_date: iso.release_date,
+                size: iso.size,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_iso_versions() {
+        let html = r#"<html><body><pre>
+            <a href="../">../</a>
+            <a href="proxmox-ve_8.2-1.iso">proxmox-ve_8.2-1.iso</a>
+            <a href="proxmox-ve_8.10-1.iso">proxmox-ve_8.10-1.iso</a>
+            <a href="proxmox-ve_7.4-1.iso">proxmox-ve_7.4-1.iso</a>
+            <a href="proxmox-ve_8.2-1.iso">proxmox-ve_8.2-1.iso</a>
+            <a href="proxmox-ve_9.0-BETA-1.iso">proxmox-ve_9.0-BETA-1.iso</a>
+            <a href="proxmox-ve_8.2-2.iso.torrent">torrent</a>
+            <a href="proxmox-backup-server_3.2-1.iso">pbs</a>
+            <a href="SHA256SUMS">SHA256SUMS</a>
+        </pre></body></html>"#;
+        assert_eq!(
+            parse_iso_versions(html, IsoChannel::Stable).unwrap(),
+            vec!["7.4-1", "8.2-1", "8.10-1"]
+        );
+        assert_eq!(
+            parse_iso_versions(html, IsoChannel::Test).unwrap(),
+            vec!["9.0-BETA-1"]
+        );
+        assert!(
+            parse_iso_versions("<html></html>", IsoChannel::Stable)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn listed_dates_and_sizes_are_read() {
+        let nginx = "<html><body><pre><a href=\"../\">../</a>\n\
+            <a href=\"proxmox-ve_8.1-2.iso\">proxmox-ve_8.1-2.iso</a>    22-Feb-2024 13:26    1346553856\n\
+            <a href=\"proxmox-ve_8.2-1.iso\">proxmox-ve_8.2-1.iso</a>    24-Apr-2024 10:12    1392508928\n\
+            <a href=\"SHA256SUMS\">SHA256SUMS</a>    24-Apr-2024 10:14    2048\n\
+            </pre></body></html>";
+        assert_eq!(
+            parse_iso_listing(nginx, IsoChannel::Stable).unwrap(),
+            [
+                ListedIso {
+                    version: "8.1-2".into(),
+                    release_date: Some("2024-02-22".into()),
+                    size: Some(1346553856),
+                },
+                ListedIso {
+                    version: "8.2-1".into(),
+                    release_date: Some("2024-04-24".into()),
+                    size: Some(1392508928),
+                },
+            ]
+        );
+
+        let apache = r#"<table>
+            <tr><td><a href="proxmox-ve_8.2-1.iso">proxmox-ve_8.2-1.iso</a></td><td align="right">2024-04-24 10:12  </td><td align="right">1.3G</td></tr>
+            </table>"#;
+        assert_eq!(
+            parse_iso_listing(apache, IsoChannel::Stable).unwrap(),
+            [ListedIso {
+                version: "8.2-1".into(),
+                release_date: Some("2024-04-24".into()),
+                size: None,
+            }]
+        );
+
+        let bare = r#"<a href="proxmox-ve_8.2-1.iso"></a>"#;
+        assert_eq!(
+            parse_iso_listing(bare, IsoChannel::Stable).unwrap()[0].release_date,
+            None
+        );
+        for invalid in ["24-Foo-2024", "2024-13-01", "yesterday", ""] {
+            assert_eq!(release_date(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_version_key() {
+        assert_eq!(version_key("8.2-1"), Some((8, 2, true, 1)));
+        assert_eq!(version_key("10.0-12"), Some((10, 0, true, 12)));
+        assert_eq!(version_key("9.0-BETA-1"), Some((9, 0, false, 1)));
+        assert!(version_key("8.4-1") < version_key("9.0-BETA-1"));
+        assert!(version_key("9.0-BETA-2") < version_key("9.0-1"));
+        for invalid in [
+            "8.2",
+            "8-1",
+            "latest",
+            "8.2-1.iso",
+            "+8.2-1",
+            "8.x-1",
+            "9.0-BETA",
+            "9.0-beta-1",
+            "",
+        ] {
+            assert_eq!(version_key(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_parse_latest_iso_info() {
+        let sha256 = "a".repeat(64);
+        let entry = |name: &str| {
+            format!(
+                r#"<li><div class="download-entry-buttons"><a class="button-primary" href="https://enterprise.proxmox.com/iso/{}">Download</a></div>
+                <div class="download-entry-info"><dl><div><dt>Last Updated</dt><dd>April 24, 2024</dd></div><div class="download-entry-shasum"><dt>SHA256SUM</dt><dd><code> {} </code></dd></div></dl></div></li>"#,
+                name, sha256
+            )
+        };
+        let html = format!(
+            r#"<ul class="latest-downloads">{}{}</ul>"#,
+            entry("proxmox-ve_8.2-1-manual.pdf"),
+            entry("proxmox-ve_8.2-1.iso")
+        );
+        let release = parse_latest_iso_info(&html, &DownloadPage::default()).unwrap();
+        assert_eq!(
+            release,
+            IsoRelease {
+                version: "8.2-1".into(),
+                url: "https://enterprise.proxmox.com/iso/proxmox-ve_8.2-1.iso".into(),
+                checksum: Checksum::sha256(&sha256).unwrap(),
+                release_date: Some("2024-04-24".into()),
+                size: None,
+            }
+        );
+        assert_eq!(release.sha256(), Some(sha256.as_str()));
+        assert_eq!(release.file_name(), "proxmox-ve_8.2-1.iso");
+        assert!(parse_latest_iso_info("<html></html>", &DownloadPage::default()).is_err());
+
+        let staging = DownloadPage {
+            url: "https://staging.lan/downloads".into(),
+            iso_url_pattern: r"^https://staging\.lan/iso/proxmox-ve_[\d\.]+-\d+\.iso$".into(),
+        };
+        assert!(parse_latest_iso_info(&html, &staging).is_err());
+        let moved = html.replace("enterprise.proxmox.com", "staging.lan");
+        let release = parse_latest_iso_info(&moved, &staging).unwrap();
+        assert_eq!(release.url, "https://staging.lan/iso/proxmox-ve_8.2-1.iso");
+    }
+
+    #[test]
+    fn test_iso_version() {
+        assert_eq!(
+            iso_version("http://mirror.lan/iso/proxmox-ve_9.0-1.iso").as_deref(),
+            Some("9.0-1")
+        );
+        assert_eq!(
+            iso_version("proxmox-ve_8.2-1.iso").as_deref(),
+            Some("8.2-1")
+        );
+        assert_eq!(
+            iso_version("http://mirror.lan/iso/proxmox-ve_8.2-1.iso.torrent"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_checksum_for() {
+        let sums = "abcd  proxmox-ve_8.2-1.iso\nef01 *proxmox-ve_8.1-2.iso\n";
+        assert_eq!(checksum_for(sums, "proxmox-ve_8.2-1.iso"), Some("abcd"));
+        assert_eq!(checksum_for(sums, "proxmox-ve_8.1-2.iso"), Some("ef01"));
+        assert_eq!(checksum_for(sums, "proxmox-ve_8.2-2.iso"), None);
+    }
+
+    #[tokio::test]
+    async fn latest_iso_info_falls_back_to_the_listing() {
+        use crate::iso::{constants::SHA256SUMS_URL, http::tests::MockFetcher};
+
+        let sha256 = "b".repeat(64);
+        let http = MockFetcher::default()
+            .route(
+                ISO_INDEX_URL,
+                None,
+                r#"<a href="proxmox-ve_8.1-2.iso"></a><a href="proxmox-ve_8.2-1.iso"></a>"#,
+            )
+            .route(
+                SHA256SUMS_URL,
+                None,
+                format!("{}  proxmox-ve_8.2-1.iso\n", sha256),
+            );
+        assert_eq!(
+            get_latest_iso_info(&http, &DownloadPage::default())
+                .await
+                .unwrap(),
+            IsoRelease {
+                version: "8.2-1".into(),
+                url: format!("{}proxmox-ve_8.2-1.iso", ISO_INDEX_URL),
+                checksum: Checksum::sha256(&sha256).unwrap(),
+                release_date: None,
+                size: None,
+            }
+        );
+        assert_eq!(
+            http.requests(),
+            [
+                format!("GET {}", PROX_DL_PG_URL),
+                format!("GET {}", ISO_INDEX_URL),
+                format!("GET {}SHA512SUMS", ISO_INDEX_URL),
+                format!("GET {}", SHA256SUMS_URL)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn the_strongest_published_checksum_is_used() {
+        use crate::iso::http::tests::MockFetcher;
+
+        const MIRROR: &str = "http://mirror.lan/iso/";
+        let sha512 = "c".repeat(128);
+        let http = MockFetcher::default()
+            .route(
+                &format!("{}SHA512SUMS", MIRROR),
+                None,
+                format!("{}  proxmox-ve_8.2-1.iso\n", sha512),
+            )
+            .route(
+                &format!("{}SHA256SUMS", MIRROR),
+                None,
+                format!(
+                    "{}  proxmox-ve_8.2-1.iso\n{}  proxmox-ve_8.1-2.iso\n",
+                    "a".repeat(64),
+                    "b".repeat(64)
+                ),
+            )
+            .route(
+                &format!("{}MD5SUMS", MIRROR),
+                None,
+                format!("{}  proxmox-ve_7.4-1.iso\n", "d".repeat(32)),
+            );
+
+        let release = get_iso_info(&http, MIRROR, "8.2-1").await.unwrap();
+        assert_eq!(release.url, format!("{}proxmox-ve_8.2-1.iso", MIRROR));
+        assert_eq!(release.sha256(), None);
+        assert_eq!(
+            release.checksum,
+            Checksum::new(ChecksumAlgorithm::Sha512, &sha512).unwrap()
+        );
+        assert_eq!(http.requests(), [format!("GET {}SHA512SUMS", MIRROR)]);
+
+        let release = get_iso_info(&http, MIRROR, "8.1-2").await.unwrap();
+        assert_eq!(release.checksum, Checksum::sha256(&"b".repeat(64)).unwrap());
+
+        let md5_only = get_iso_info(&http, MIRROR, "7.4-1").await.unwrap_err();
+        assert!(
+            md5_only.to_string().starts_with("iso.checksum.untrusted"),
+            "{}",
+            md5_only
+        );
+        assert_eq!(
+            get_iso_info(&http, MIRROR, "9.0-1")
+                .await
+                .unwrap_err()
+                .to_string(),
+            "Proxmox VE 9.0-1 is not published"
+        );
+    }
+
+    #[tokio::test]
+    async fn releases_are_listed_with_their_checksums() {
+        use crate::iso::http::tests::MockFetcher;
+
+        const MIRROR: &str = "http://mirror.lan/iso/";
+        let sha512 = "c".repeat(128);
+        let sha256 = "b".repeat(64);
+        let http = MockFetcher::default()
+            .route(
+                MIRROR,
+                None,
+                "<pre><a href=\"proxmox-ve_7.4-1.iso\">proxmox-ve_7.4-1.iso</a> 23-Mar-2023 10:00 1135714304\n\
+                 <a href=\"proxmox-ve_8.1-2.iso\">proxmox-ve_8.1-2.iso</a> 22-Feb-2024 13:26 1346553856\n\
+                 <a href=\"proxmox-ve_8.2-1.iso\">proxmox-ve_8.2-1.iso</a> 24-Apr-2024 10:12 1392508928\n</pre>",
+            )
+            .route(
+                &format!("{}SHA512SUMS", MIRROR),
+                None,
+                format!("{}  proxmox-ve_8.2-1.iso\n", sha512),
+            )
+            .route(
+                &format!("{}SHA256SUMS", MIRROR),
+                None,
+                format!(
+                    "{}  proxmox-ve_8.2-1.iso\n{}  proxmox-ve_8.1-2.iso\n",
+                    "a".repeat(64),
+                    sha256
+                ),
+            );
+
+        let releases = releases(&http, MIRROR, IsoChannel::Stable).await.unwrap();
+        assert_eq!(
+            releases,
+            [
+                IsoRelease {
+                    version: "8.1-2".into(),
+                    url: format!("{}proxmox-ve_8.1-2.iso", MIRROR),
+                    checksum: Checksum::sha256(&sha256).unwrap(),
+                    release_date: Some("2024-02-22".into()),
+                    size: Some(1346553856),
+                },
+                IsoRelease {
+                    version: "8.2-1".into(),
+                    url: format!("{}proxmox-ve_8.2-1.iso", MIRROR),
+                    checksum: Checksum::new(ChecksumAlgorithm::Sha512, &sha512).unwrap(),
+                    release_date: Some("2024-04-24".into()),
+                    size: Some(1392508928),
+                },
+            ]
+        );
+        assert_eq!(
+            http.requests(),
+            [
+                format!("GET {}", MIRROR),
+                format!("GET {}SHA512SUMS", MIRROR),
+                format!("GET {}SHA256SUMS", MIRROR)
+            ]
+        );
+
+        let newest = get_newest_listed_iso_info(&http, MIRROR, IsoChannel::Stable)
+            .await
+            .unwrap();
+        assert_eq!(newest, releases[1]);
+    }
+
+    #[cfg(feature = "iso-scraper-tests")]
+    #[tokio::test]
+    async fn test_list_iso_versions() {
+        let http = reqwest::Client::new();
+        let versions = list_iso_versions(&http, ISO_INDEX_URL, IsoChannel::Stable)
+            .await
+            .unwrap();
+        let latest = versions.last().unwrap();
+        let release = get_iso_info(&http, ISO_INDEX_URL, latest).await.unwrap();
+        assert!(release.url.ends_with(&iso_file_name(latest)));
+        assert!(release.checksum.algorithm >= ChecksumAlgorithm::Sha256);
+
+        let releases = releases(&http, ISO_INDEX_URL, IsoChannel::Stable)
+            .await
+            .unwrap();
+        assert_eq!(&releases.last().unwrap().version, latest);
+    }
+
+    #[cfg(feature = "iso-scraper-tests")]
     #[tokio::test]
     async fn test_get_latest_iso_info() {
-        let result = get_latest_iso_info().await;
+        let result = get_latest_iso_info(&reqwest::Client::new(), &DownloadPage::default()).await;
         assert!(result.is_ok());
-        let (iso_url, sha256_checksum) = result.unwrap();
+        let release = result.unwrap();
         let iso_url_regex = Regex::new(ISO_URL_REGEX_PATTERN).unwrap();
-        assert!(iso_url_regex.is_match(&iso_url));
-        assert_eq!(sha256_checksum.len(), 64);
-        println!("ISO URL: {}", iso_url);
-        println!("SHA256 Checksum: {}", sha256_checksum);
+        assert!(iso_url_regex.is_match(&release.url));
+        assert_eq!(
+            release.checksum.hex.len(),
+            release.checksum.algorithm.hex_len()
+        );
+        println!("ISO URL: {}", release.url);
+        println!("Checksum: {}", release.checksum);
     }
 }
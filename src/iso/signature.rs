@@ -0,0 +1,244 @@
+use crate::iso::{
+    constants::ISO_SIGNATURE_SUFFIX,
+    pgp::{PgpError, PublicKey, Signature, parse_public_keys},
+};
+use crate::util::locate_binary;
+use std::{
+    ffi::OsString,
+    fmt,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// Name of the GnuPG binary, looked up on `$PATH`.
+pub const GPG_BINARY: &str = "gpg";
+
+/// Environment variable overriding the GnuPG binary path.
+pub const GPG_ENV: &str = "PVEAUTO_GPG";
+
+/* ===================== ISO SIGNATURE ERROR ===================== */
+
+/// Failure while signing a built ISO or checking its signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IsoSignatureError {
+    /// A file could not be read; holds the path and the I/O error.
+    Io(PathBuf, String),
+    /// GnuPG could not be started; holds the error.
+    GpgFailed(String),
+    /// GnuPG exited unsuccessfully, e.g. as the key is unknown or its
+    /// passphrase was not entered; holds the exit status and its output.
+    GpgExited(String, String),
+    /// The keyring or the signature is invalid, or the signature does not
+    /// match the ISO.
+    Signature(PgpError),
+}
+
+impl IsoSignatureError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io(..) => "iso.signature.io",
+            Self::GpgFailed(_) => "iso.signature.gpg_failed",
+            Self::GpgExited(..) => "iso.signature.gpg_exited",
+            Self::Signature(_) => "iso.signature.invalid",
+        }
+    }
+}
+
+impl fmt::Display for IsoSignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, detail) => write!(f, "{} ({}: {})", self.code(), path.display(), detail),
+            Self::GpgFailed(detail) => write!(f, "{} ({})", self.code(), detail),
+            Self::GpgExited(status, output) => match output.trim() {
+                "" => write!(f, "{} (gpg {})", self.code(), status),
+                output => write!(f, "{} (gpg {}: {})", self.code(), status, output),
+            },
+            Self::Signature(e) => write!(f, "{} ({})", self.code(), e),
+        }
+    }
+}
+
+impl std::error::Error for IsoSignatureError {}
+
+impl From<PgpError> for IsoSignatureError {
+    fn from(e: PgpError) -> Self {
+        Self::Signature(e)
+    }
+}
+
+/* ===================== GNUPG ===================== */
+
+/// GnuPG, which signs built ISOs with an operator's key so whoever boots
+/// them can check where they came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gpg {
+    program: PathBuf,
+}
+
+impl Gpg {
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+        }
+    }
+
+    /// `$PVEAUTO_GPG` when set, otherwise [`GPG_BINARY`] from `$PATH`.
+    pub fn locate() -> Option<Self> {
+        locate_binary(GPG_ENV, GPG_BINARY).map(Self::new)
+    }
+
+    pub fn program(&self) -> &Path {
+        &self.program
+    }
+
+    /// The command writing an ASCII-armored detached signature of `file`,
+    /// made with `key` (a key id, fingerprint or user id, as for
+    /// `gpg --local-user`), to `signature`.
+    pub fn sign_command(&self, key: &str, file: &Path, signature: &Path) -> Command {
+        let mut command = Command::new(&self.program);
+        command
+            .args(["--batch", "--yes", "--armor", "--digest-algo", "SHA512"])
+            .args(["--local-user", key, "--output"])
+            .arg(signature)
+            .arg("--detach-sign")
+            .arg(file);
+        command
+    }
+
+    /// Signs the ISO at `iso` with `key`, writing the signature to
+    /// [`signature_path`]; a partial signature is removed on failure.
+    ///
+    /// GnuPG asks for the key's passphrase through its agent as usual.
+    ///
+    /// # Returns
+    /// The path of the signature.
+    pub fn sign(&self, key: &str, iso: &Path) -> Result<PathBuf, IsoSignatureError> {
+        let signature = signature_path(iso);
+        let output = self
+            .sign_command(key, iso, &signature)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| {
+                IsoSignatureError::GpgFailed(format!("{}: {}", self.program.display(), e))
+            })?;
+        if !output.status.success() {
+            let _ = std::fs::remove_file(&signature);
+            return Err(IsoSignatureError::GpgExited(
+                output.status.to_string(),
+                String::from_utf8_lossy(&output.stderr).into_owned(),
+            ));
+        }
+        Ok(signature)
+    }
+}
+
+/// Where the signature of the ISO at `iso` is kept: `<iso>.asc`.
+pub fn signature_path(iso: &Path) -> PathBuf {
+    let mut path = OsString::from(iso.as_os_str());
+    path.push(ISO_SIGNATURE_SUFFIX);
+    PathBuf::from(path)
+}
+
+/* ===================== VERIFICATION ===================== */
+
+/// Checks the detached signature [`signature_path`] of the ISO at `iso`
+/// against the keys of the binary or ASCII-armored keyring file `keyring`,
+/// e.g. the operator's `gpg --export --armor <key>`. The ISO is hashed as it
+/// is read, without GnuPG.
+///
+/// # Returns
+/// The key that signed the ISO.
+///
+/// # Errors
+/// [`IsoSignatureError::Io`] when a file cannot be read, and
+/// [`IsoSignatureError::Signature`] when the signature was not made over this
+/// ISO by one of the keys.
+pub fn verify_signature(iso: &Path, keyring: &Path) -> Result<PublicKey, IsoSignatureError> {
+    let read = |path: &Path| {
+        std::fs::read(path).map_err(|e| IsoSignatureError::Io(path.to_path_buf(), e.to_string()))
+    };
+    let keys = parse_public_keys(&read(keyring)?)?;
+    let signature = Signature::parse(&read(&signature_path(iso))?)?;
+    let file =
+        File::open(iso).map_err(|e| IsoSignatureError::Io(iso.to_path_buf(), e.to_string()))?;
+    Ok(signature
+        .verify_reader(BufReader::new(file), &keys)?
+        .clone())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::iso::pgp::tests::{
+        ed25519_key, ed25519_keyring, keyring, private_key, sign_ed25519,
+    };
+    use std::os::unix::fs::PermissionsExt;
+
+    /// A stand-in GnuPG that copies `prepared.sig` next to it to `--output`,
+    /// or fails for the key `unknown`.
+    const FAKE_GPG: &str = "#!/bin/sh\n\
+        while [ $# -gt 1 ]; do\n\
+        case $1 in\n\
+        --local-user) key=$2 ;;\n\
+        --output) out=$2 ;;\n\
+        esac\n\
+        shift\n\
+        done\n\
+        [ \"$key\" = unknown ] && { echo 'gpg: skipped \"unknown\": No secret key' >&2; exit 2; }\n\
+        cp \"$(dirname \"$0\")/prepared.sig\" \"$out\"\n";
+
+    #[test]
+    fn signed_isos_verify_against_the_operator_key() {
+        let dir = std::env::temp_dir().join(format!("pveauto-signature-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let program = dir.join("gpg");
+        std::fs::write(&program, FAKE_GPG).unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let gpg = Gpg::new(program);
+
+        let iso = dir.join("auto.iso");
+        let image = vec![0x5a; 200_000];
+        std::fs::write(&iso, &image).unwrap();
+        let key = ed25519_key(5);
+        std::fs::write(dir.join("prepared.sig"), sign_ed25519(&key, &image, 0)).unwrap();
+        let (operator, stranger) = (dir.join("operator.gpg"), dir.join("stranger.gpg"));
+        std::fs::write(&operator, ed25519_keyring(&key)).unwrap();
+        std::fs::write(&stranger, keyring(&private_key(0))).unwrap();
+
+        assert_eq!(
+            gpg.sign("ops@example.com", &iso),
+            Ok(dir.join("auto.iso.asc"))
+        );
+        let signer = verify_signature(&iso, &operator).unwrap();
+        assert_eq!(
+            signer,
+            parse_public_keys(&ed25519_keyring(&key)).unwrap()[0]
+        );
+        assert_eq!(
+            verify_signature(&iso, &stranger).unwrap_err().code(),
+            "iso.signature.invalid"
+        );
+
+        std::fs::write(&iso, &image[1..]).unwrap();
+        assert_eq!(
+            verify_signature(&iso, &operator),
+            Err(IsoSignatureError::Signature(PgpError::BadSignature))
+        );
+
+        match gpg.sign("unknown", &iso) {
+            Err(IsoSignatureError::GpgExited(status, output)) => {
+                assert!(status.contains('2'), "{}", status);
+                assert!(output.contains("No secret key"), "{}", output);
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(
+            verify_signature(&iso, &operator).unwrap_err().code(),
+            "iso.signature.io"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,284 @@
+use crate::util::{locate_binary, private_dir, unique_suffix};
+use std::{
+    fmt,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+/// Name of the QEMU binary, looked up on `$PATH`.
+pub const QEMU_BINARY: &str = "qemu-system-x86_64";
+
+/// Environment variable overriding the QEMU binary path.
+pub const QEMU_ENV: &str = "PVEAUTO_QEMU";
+
+/// How long the installer gets to start installing; without KVM, booting
+/// alone takes minutes.
+pub const SMOKE_TEST_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Size of the sparse scratch disk; the installer refuses disks much smaller.
+pub const SCRATCH_DISK_SIZE: u64 = 32 << 30;
+
+/// How often the scratch disk is checked for a partition table.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/* ===================== SMOKE TEST ERROR ===================== */
+
+/// Failure of a smoke test of an installer ISO.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SmokeTestError {
+    /// Preparing or reading the scratch disk failed; holds the path and the
+    /// I/O error.
+    Io(PathBuf, String),
+    /// QEMU could not be started; holds the error.
+    QemuFailed(String),
+    /// QEMU exited before the installer partitioned the disk, e.g. as the ISO
+    /// did not boot; holds the exit status and the serial console log.
+    Exited(String, PathBuf),
+    /// The installer did not partition the disk in time; holds the timeout
+    /// in seconds and the serial console log.
+    TimedOut(u64, PathBuf),
+}
+
+impl SmokeTestError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io(..) => "smoke.io",
+            Self::QemuFailed(_) => "smoke.qemu_failed",
+            Self::Exited(..) => "smoke.exited",
+            Self::TimedOut(..) => "smoke.timed_out",
+        }
+    }
+}
+
+impl fmt::Display for SmokeTestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, detail) => write!(f, "{} ({}: {})", self.code(), path.display(), detail),
+            Self::QemuFailed(detail) => write!(f, "{} ({})", self.code(), detail),
+            Self::Exited(status, log) => write!(
+                f,
+                "{} (QEMU {} before the installer wrote to the disk; serial log: {})",
+                self.code(),
+                status,
+                log.display()
+            ),
+            Self::TimedOut(secs, log) => write!(
+                f,
+                "{} (the installer did not write to the disk within {} s; serial log: {})",
+                self.code(),
+                secs,
+                log.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SmokeTestError {}
+
+/* ===================== QEMU ===================== */
+
+/// A QEMU system emulator for x86-64, which boots installer ISOs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Qemu {
+    program: PathBuf,
+}
+
+impl Qemu {
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+        }
+    }
+
+    /// `$PVEAUTO_QEMU` when set, otherwise [`QEMU_BINARY`] from `$PATH`.
+    pub fn locate() -> Option<Self> {
+        locate_binary(QEMU_ENV, QEMU_BINARY).map(Self::new)
+    }
+
+    pub fn program(&self) -> &Path {
+        &self.program
+    }
+
+    /// The command booting a BIOS machine from `iso` with the raw `disk` as
+    /// its first SATA disk (`sda`), user-mode networking and the serial
+    /// console written to `log`. KVM is used when available.
+    pub fn boot_command(&self, iso: &Path, disk: &Path, log: &Path) -> Command {
+        // QEMU option values escape commas by doubling them.
+        let value = |path: &Path| path.to_string_lossy().replace(',', ",,");
+        let mut command = Command::new(&self.program);
+        command
+            .args(["-name", "pveauto-smoke-test", "-machine", "accel=kvm:tcg"])
+            .args(["-m", "4096", "-smp", "2", "-display", "none"])
+            .args(["-no-reboot", "-boot", "d", "-nic", "user,model=e1000"])
+            .arg("-drive")
+            .arg(format!("file={},format=raw,if=ide,index=0", value(disk)))
+            .arg("-drive")
+            .arg(format!(
+                "file={},media=cdrom,if=ide,index=2,readonly=on",
+                value(iso)
+            ))
+            .arg("-serial")
+            .arg(format!("file:{}", log.display()));
+        command
+    }
+}
+
+/// Boots the installer ISO at `iso` in QEMU on a blank scratch disk and
+/// waits until the unattended installation has started, which is taken to be
+/// when the installer has written a partition table to the disk: it has
+/// booted, found its answer and accepted it.
+///
+/// The scratch disk and the serial console log are kept in a private
+/// directory under the temporary directory, which is removed on success; on
+/// failure only the log is kept. QEMU is stopped either way.
+///
+/// # Returns
+/// How long the installer took to start.
+///
+/// # Errors
+/// Returns [`SmokeTestError::Exited`] when QEMU exits first, e.g. as the ISO
+/// does not boot, and [`SmokeTestError::TimedOut`] after `timeout`.
+pub fn smoke_test(qemu: &Qemu, iso: &Path, timeout: Duration) -> Result<Duration, SmokeTestError> {
+    let dir = std::env::temp_dir().join(format!(
+        "pveauto-smoke-{}-{}",
+        std::process::id(),
+        unique_suffix()
+    ));
+    let (disk, log) = (dir.join("scratch.img"), dir.join("serial.log"));
+    let io_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |e: std::io::Error| SmokeTestError::Io(path, e.to_string())
+    };
+    private_dir(&dir).map_err(io_error(&dir))?;
+    let result = File::create(&disk)
+        .and_then(|file| file.set_len(SCRATCH_DISK_SIZE))
+        .map_err(io_error(&disk))
+        .and_then(|_| run(qemu, iso, &disk, &log, timeout));
+
+    let _ = std::fs::remove_file(&disk);
+    if result.is_ok() {
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    result
+}
+
+/// Runs QEMU until `disk` is partitioned, QEMU exits or `timeout` passes.
+fn run(
+    qemu: &Qemu,
+    iso: &Path,
+    disk: &Path,
+    log: &Path,
+    timeout: Duration,
+) -> Result<Duration, SmokeTestError> {
+    let mut child = qemu
+        .boot_command(iso, disk, log)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| SmokeTestError::QemuFailed(format!("{}: {}", qemu.program.display(), e)))?;
+
+    let started = Instant::now();
+    let result = loop {
+        match partitioned(disk) {
+            Ok(true) => break Ok(started.elapsed()),
+            Ok(false) => {}
+            Err(e) => break Err(SmokeTestError::Io(disk.to_path_buf(), e.to_string())),
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => break Err(SmokeTestError::Exited(status.to_string(), log.into())),
+            Ok(None) => {}
+            Err(e) => break Err(SmokeTestError::QemuFailed(e.to_string())),
+        }
+        if started.elapsed() >= timeout {
+            break Err(SmokeTestError::TimedOut(timeout.as_secs(), log.into()));
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+
+    let _ = child.kill();
+    let _ = child.wait();
+    result
+}
+
+/// Whether `disk` starts with an MBR boot signature or a GPT header.
+fn partitioned(disk: &Path) -> std::io::Result<bool> {
+    let mut head = [0u8; 520];
+    File::open(disk)?.read_exact(&mut head)?;
+    Ok(head[510..512] == [0x55, 0xAA] || &head[512..520] == b"EFI PART")
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// A stand-in QEMU that writes a GPT signature to its hard disk when the
+    /// ISO holds `install`, exits when it holds `broken`, and otherwise hangs.
+    const FAKE_QEMU: &str = "#!/bin/sh\n\
+        for arg; do\n\
+        case $arg in\n\
+        file=*,format=raw*) disk=${arg#file=}; disk=${disk%%,format=raw*} ;;\n\
+        file=*,media=cdrom*) iso=${arg#file=}; iso=${iso%%,media=cdrom*} ;;\n\
+        esac\n\
+        done\n\
+        grep -q broken \"$iso\" && exit 3\n\
+        grep -q install \"$iso\" && printf 'EFI PART' | dd of=\"$disk\" bs=1 seek=512 conv=notrunc 2>/dev/null\n\
+        exec sleep 30\n";
+
+    #[test]
+    fn the_installer_must_start_writing_to_the_disk() {
+        let dir = std::env::temp_dir().join(format!("pveauto-smoke-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let program = dir.join("qemu");
+        std::fs::write(&program, FAKE_QEMU).unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let qemu = Qemu::new(program);
+        let iso = dir.join("auto.iso");
+        let timeout = Duration::from_secs(2);
+
+        std::fs::write(&iso, "install").unwrap();
+        assert!(smoke_test(&qemu, &iso, timeout).unwrap() < timeout);
+
+        std::fs::write(&iso, "broken").unwrap();
+        match smoke_test(&qemu, &iso, timeout) {
+            Err(SmokeTestError::Exited(status, log)) => {
+                assert!(status.contains('3'), "{}", status);
+                assert!(log.parent().unwrap().is_dir());
+                assert!(!log.with_file_name("scratch.img").exists());
+                std::fs::remove_dir_all(log.parent().unwrap()).unwrap();
+            }
+            other => panic!("{:?}", other),
+        }
+
+        std::fs::write(&iso, "hang").unwrap();
+        match smoke_test(&qemu, &iso, timeout) {
+            Err(SmokeTestError::TimedOut(2, log)) => {
+                std::fs::remove_dir_all(log.parent().unwrap()).unwrap()
+            }
+            other => panic!("{:?}", other),
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn paths_with_commas_are_escaped() {
+        let command = Qemu::new("qemu").boot_command(
+            Path::new("/isos/a,b.iso"),
+            Path::new("/tmp/disk.img"),
+            Path::new("/tmp/serial.log"),
+        );
+        let args: Vec<String> = command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        assert!(
+            args.contains(&"file=/isos/a,,b.iso,media=cdrom,if=ide,index=2,readonly=on".into())
+        );
+        assert!(args.contains(&"file:/tmp/serial.log".into()));
+    }
+}
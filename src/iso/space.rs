@@ -0,0 +1,287 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Room kept free on top of what a download or build writes, for metadata,
+/// temporary files and whatever else is writing to the filesystem.
+pub const SPACE_HEADROOM: u64 = 256 << 20;
+
+/// Largest file FAT32 can hold: 4 GiB less one byte.
+pub const FAT32_MAX_FILE_SIZE: u64 = (4 << 30) - 1;
+
+/* ===================== SPACE ERROR ===================== */
+
+/// Failure of a free space check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpaceError {
+    /// The filesystem of a path has too little room; holds the path, the
+    /// bytes needed including [`SPACE_HEADROOM`] and the bytes available.
+    Insufficient(PathBuf, u64, u64),
+    /// The filesystem of a path is mounted read-only; holds the path.
+    ReadOnly(PathBuf),
+    /// A file is larger than the filesystem of its path can hold; holds the
+    /// path, the size of the file and the filesystem.
+    FileTooLarge(PathBuf, u64, FilesystemKind),
+}
+
+impl SpaceError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Insufficient(..) => "space.insufficient",
+            Self::ReadOnly(_) => "space.read_only",
+            Self::FileTooLarge(..) => "space.file_too_large",
+        }
+    }
+}
+
+impl fmt::Display for SpaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Insufficient(path, needed, available) => write!(
+                f,
+                "{} ({} has {} free, {} needed)",
+                self.code(),
+                path.display(),
+                gib(*available),
+                gib(*needed)
+            ),
+            Self::ReadOnly(path) => write!(
+                f,
+                "{} ({} is on a read-only filesystem)",
+                self.code(),
+                path.display()
+            ),
+            Self::FileTooLarge(path, size, kind) => write!(
+                f,
+                "{} ({} would be {}, but {} holds files up to {}; \
+                 reformat the medium as exFAT or ext4)",
+                self.code(),
+                path.display(),
+                gib(*size),
+                kind,
+                gib(kind.max_file_size().unwrap_or(u64::MAX))
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SpaceError {}
+
+fn gib(bytes: u64) -> String {
+    format!("{:.2} GiB", bytes as f64 / (1u64 << 30) as f64)
+}
+
+/* ===================== FILESYSTEM ===================== */
+
+/// The filesystems told apart by [`filesystem`]: the FAT family USB sticks
+/// usually come formatted with, and everything else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilesystemKind {
+    Fat32,
+    ExFat,
+    Other,
+}
+
+impl FilesystemKind {
+    /// The largest file the filesystem holds, when it is limited.
+    pub fn max_file_size(self) -> Option<u64> {
+        match self {
+            Self::Fat32 => Some(FAT32_MAX_FILE_SIZE),
+            Self::ExFat | Self::Other => None,
+        }
+    }
+}
+
+impl fmt::Display for FilesystemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Fat32 => "FAT32",
+            Self::ExFat => "exFAT",
+            Self::Other => "the filesystem",
+        })
+    }
+}
+
+/// What is known about the filesystem a path is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Filesystem {
+    pub kind: FilesystemKind,
+    pub read_only: bool,
+}
+
+/// The filesystem that `path` is, or would be, written to, or `None` when
+/// it cannot be determined, e.g. off Unix. The kind is only told apart on
+/// Linux.
+pub fn filesystem(path: &Path) -> Option<Filesystem> {
+    let existing = nearest_existing(path);
+    let (_, read_only) = statvfs(existing)?;
+    Some(Filesystem {
+        kind: filesystem_kind(existing),
+        read_only,
+    })
+}
+
+/// Checks that a file of `size` bytes, when known, can be written to `path`:
+/// that its filesystem is writable and, for FAT32, that the file stays below
+/// 4 GiB. Passes when the filesystem cannot be determined.
+///
+/// # Returns
+/// The filesystem, e.g. to warn about FAT32 when the size is not known yet.
+///
+/// # Errors
+/// [`SpaceError::ReadOnly`] or [`SpaceError::FileTooLarge`].
+pub fn ensure_writable(path: &Path, size: Option<u64>) -> Result<Option<Filesystem>, SpaceError> {
+    let Some(fs) = filesystem(path) else {
+        return Ok(None);
+    };
+    if fs.read_only {
+        return Err(SpaceError::ReadOnly(path.to_path_buf()));
+    }
+    match (size, fs.kind.max_file_size()) {
+        (Some(size), Some(max)) if size > max => {
+            Err(SpaceError::FileTooLarge(path.to_path_buf(), size, fs.kind))
+        }
+        _ => Ok(Some(fs)),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn filesystem_kind(path: &Path) -> FilesystemKind {
+    /// `f_type` of vfat and msdos mounts, and of exFAT mounts.
+    const MSDOS_SUPER_MAGIC: i64 = 0x4d44;
+    const EXFAT_SUPER_MAGIC: i64 = 0x2011_bab0;
+    let Some(path) = c_path(path) else {
+        return FilesystemKind::Other;
+    };
+    // SAFETY: `statfs` is plain data as well, and `path` is NUL-terminated.
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return FilesystemKind::Other;
+    }
+    match stat.f_type as i64 {
+        MSDOS_SUPER_MAGIC => FilesystemKind::Fat32,
+        EXFAT_SUPER_MAGIC => FilesystemKind::ExFat,
+        _ => FilesystemKind::Other,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn filesystem_kind(_path: &Path) -> FilesystemKind {
+    FilesystemKind::Other
+}
+
+/* ===================== FREE SPACE ===================== */
+
+/// Checks that `bytes` plus [`SPACE_HEADROOM`] fit on the filesystem that
+/// `path` is, or would be, written to. Passes when the free space cannot be
+/// determined, e.g. off Unix.
+///
+/// # Errors
+/// [`SpaceError::Insufficient`] when the filesystem has too little room.
+pub fn ensure_space(path: &Path, bytes: u64) -> Result<(), SpaceError> {
+    let needed = bytes.saturating_add(SPACE_HEADROOM);
+    match available_space(path) {
+        Some(available) if available < needed => Err(SpaceError::Insufficient(
+            path.to_path_buf(),
+            needed,
+            available,
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// The bytes an unprivileged user can still write to the filesystem of
+/// `path`, or of its nearest existing ancestor when it does not exist yet.
+pub fn available_space(path: &Path) -> Option<u64> {
+    statvfs(nearest_existing(path)).map(|(available, _)| available)
+}
+
+/// `path` or its nearest existing ancestor.
+fn nearest_existing(path: &Path) -> &Path {
+    path.ancestors()
+        .find(|p| !p.as_os_str().is_empty() && p.exists())
+        .unwrap_or(Path::new("."))
+}
+
+#[cfg(unix)]
+fn c_path(path: &Path) -> Option<std::ffi::CString> {
+    use std::os::unix::ffi::OsStrExt;
+    std::ffi::CString::new(path.as_os_str().as_bytes()).ok()
+}
+
+/// The bytes available to unprivileged users on the filesystem of `path`
+/// and whether it is mounted read-only.
+#[cfg(unix)]
+fn statvfs(path: &Path) -> Option<(u64, bool)> {
+    let path = c_path(path)?;
+    // SAFETY: `statvfs` is plain data, for which all zeroes is a valid
+    // value, and `path` is NUL-terminated.
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some((
+        (stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64),
+        stat.f_flag & libc::ST_RDONLY != 0,
+    ))
+}
+
+#[cfg(not(unix))]
+fn statvfs(_path: &Path) -> Option<(u64, bool)> {
+    None
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn free_space_is_checked_at_the_nearest_existing_directory() {
+        let dir = std::env::temp_dir();
+        let available = available_space(&dir).unwrap();
+        assert!(available > 0);
+        let missing = dir.join(format!("pveauto-space-{}/a/b.iso", std::process::id()));
+        assert!(available_space(&missing).is_some());
+        assert_eq!(ensure_space(&missing, 0), Ok(()));
+
+        match ensure_space(&missing, u64::MAX) {
+            Err(SpaceError::Insufficient(path, needed, free)) => {
+                assert_eq!(path, missing);
+                assert_eq!(needed, u64::MAX);
+                assert!(free < needed);
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(
+            SpaceError::Insufficient("/srv/pve.iso".into(), 3 << 30, 1 << 29).to_string(),
+            "space.insufficient (/srv/pve.iso has 0.50 GiB free, 3.00 GiB needed)"
+        );
+    }
+
+    #[test]
+    fn read_only_and_fat32_filesystems_are_refused() {
+        let dir = std::env::temp_dir();
+        let fs = filesystem(&dir).unwrap();
+        assert!(!fs.read_only);
+        if fs.kind == FilesystemKind::Other {
+            assert_eq!(ensure_writable(&dir, Some(5 << 30)), Ok(Some(fs)));
+        }
+        assert_eq!(
+            FilesystemKind::Fat32.max_file_size(),
+            Some(FAT32_MAX_FILE_SIZE)
+        );
+        assert_eq!(FilesystemKind::ExFat.max_file_size(), None);
+
+        assert_eq!(
+            SpaceError::ReadOnly("/media/usb/pve.iso".into()).to_string(),
+            "space.read_only (/media/usb/pve.iso is on a read-only filesystem)"
+        );
+        assert_eq!(
+            SpaceError::FileTooLarge("/media/usb/pve.iso".into(), 5 << 30, FilesystemKind::Fat32)
+                .to_string(),
+            "space.file_too_large (/media/usb/pve.iso would be 5.00 GiB, but FAT32 holds files \
+             up to 4.00 GiB; reformat the medium as exFAT or ext4)"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use std::io::{self, Read};
+
+/// Size of ustar headers; contents are padded to a multiple of it.
+pub const TAR_BLOCK: usize = 512;
+
+/// Largest entry the 11 octal digits of the ustar size field can describe.
+pub const MAX_ENTRY_SIZE: u64 = 0o77777777777;
+
+/// Longest entry name without the ustar prefix field, which is not used.
+pub const MAX_NAME_LEN: usize = 100;
+
+/// Two zero blocks, which end an archive.
+pub const END_OF_ARCHIVE: [u8; 2 * TAR_BLOCK] = [0; 2 * TAR_BLOCK];
+
+/// The ustar header of a regular file of `size` bytes, owned by root.
+///
+/// `name` must be at most [`MAX_NAME_LEN`] bytes and `size` at most
+/// [`MAX_ENTRY_SIZE`].
+pub fn header(name: &str, mode: u32, size: u64) -> [u8; TAR_BLOCK] {
+    let mut header = [0u8; TAR_BLOCK];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    let octal = |field: &mut [u8], value: u64| {
+        let digits = format!("{:0width$o}", value, width = field.len() - 1);
+        field[..digits.len()].copy_from_slice(digits.as_bytes());
+    };
+    octal(&mut header[100..108], u64::from(mode));
+    octal(&mut header[108..116], 0);
+    octal(&mut header[116..124], 0);
+    octal(&mut header[124..136], size);
+    octal(&mut header[136..148], 0);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[148..156].fill(b' ');
+    let sum = checksum(&header);
+    octal(&mut header[148..155], u64::from(sum));
+    header
+}
+
+/// Appends a ustar entry for a regular file to `archive`.
+pub fn append(archive: &mut Vec<u8>, name: &str, mode: u32, contents: &[u8]) {
+    archive.extend_from_slice(&header(name, mode, contents.len() as u64));
+    archive.extend_from_slice(contents);
+    archive.resize(archive.len() + padding(contents.len() as u64), 0);
+}
+
+/// Zero bytes following `size` bytes of contents up to the next block.
+pub fn padding(size: u64) -> usize {
+    (size.next_multiple_of(TAR_BLOCK as u64) - size) as usize
+}
+
+/// An entry of a ustar archive, as described by its header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TarEntry {
+    pub name: String,
+    pub size: u64,
+    /// Whether it is a regular file rather than a directory, link or device.
+    pub regular: bool,
+}
+
+/// Reads the next header from `reader`, or `None` at the end of the archive.
+///
+/// The entry's contents and [`padding`] follow in `reader`.
+///
+/// # Errors
+/// An `InvalidData` error when the header's checksum or fields are invalid.
+pub fn read_header<R: Read>(reader: &mut R) -> io::Result<Option<TarEntry>> {
+    let mut header = [0u8; TAR_BLOCK];
+    reader.read_exact(&mut header)?;
+    if header.iter().all(|&b| b == 0) {
+        return Ok(None);
+    }
+    let invalid = |what: &str| io::Error::new(io::ErrorKind::InvalidData, what.to_string());
+    let field = |range: std::ops::Range<usize>| {
+        let text = std::str::from_utf8(&header[range]).map_err(|_| invalid("non-UTF-8 header"))?;
+        Ok::<_, io::Error>(text.trim_end_matches(['\0', ' ']).trim_start())
+    };
+    let octal = |range| u64::from_str_radix(field(range)?, 8).map_err(|_| invalid("bad header"));
+
+    let mut unsummed = header;
+    unsummed[148..156].fill(b' ');
+    if octal(148..156)? != u64::from(checksum(&unsummed)) {
+        return Err(invalid("bad header checksum"));
+    }
+    Ok(Some(TarEntry {
+        name: field(0..100)?.to_string(),
+        size: octal(124..136)?,
+        regular: matches!(header[156], b'0' | 0),
+    }))
+}
+
+fn checksum(header: &[u8; TAR_BLOCK]) -> u32 {
+    header.iter().map(|&b| u32::from(b)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers_round_trip_and_are_checked() {
+        let mut archive = Vec::new();
+        append(&mut archive, "answers/answer.toml", 0o644, b"[global]\n");
+        append(&mut archive, "empty", 0o755, b"");
+        archive.extend_from_slice(&END_OF_ARCHIVE);
+        assert_eq!(archive.len(), 5 * TAR_BLOCK);
+
+        let mut reader = &archive[..];
+        let entry = read_header(&mut reader).unwrap().unwrap();
+        assert_eq!(
+            entry,
+            TarEntry {
+                name: "answers/answer.toml".into(),
+                size: 9,
+                regular: true,
+            }
+        );
+        let mut contents = vec![0; entry.size as usize + padding(entry.size)];
+        reader.read_exact(&mut contents).unwrap();
+        assert_eq!(&contents[..9], b"[global]\n");
+        assert_eq!(read_header(&mut reader).unwrap().unwrap().size, 0);
+        assert_eq!(read_header(&mut reader).unwrap(), None);
+
+        archive[0] = b'A';
+        let err = read_header(&mut &archive[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(read_header(&mut &archive[..100]).is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use std::error::Error;
+
+/// Deepest nesting of lists and dictionaries read from a torrent file.
+const MAX_DEPTH: usize = 32;
+
+/// The parts of a single-file `.torrent` needed to fetch it over HTTP.
+///
+/// Peer-to-peer transfer is not supported; a torrent is only useful here
+/// when it lists web seeds (BEP 19, the `url-list` key).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Torrent {
+    /// File name from the `info` dictionary.
+    pub name: String,
+    pub length: Option<u64>,
+    /// Where the file can be downloaded, in the order the torrent lists them.
+    pub web_seeds: Vec<String>,
+}
+
+/// Reads `name`, `length` and the web seeds of the torrent file `bytes`.
+///
+/// Web seeds ending in `/` name a directory and get `name` appended.
+///
+/// # Errors
+/// Returns an error if `bytes` is not valid bencode or has no `info` name.
+pub fn parse_torrent(bytes: &[u8]) -> Result<Torrent, Box<dyn Error>> {
+    let mut input = bytes;
+    let root = Bencode::parse(&mut input, 0)?;
+    let info = root.get(b"info").ok_or("Torrent has no info dictionary")?;
+    let name = info
+        .get(b"name")
+        .and_then(Bencode::as_str)
+        .ok_or("Torrent has no file name")?
+        .to_string();
+    let length = info.get(b"length").and_then(|length| match length {
+        Bencode::Int(length) => u64::try_from(*length).ok(),
+        _ => None,
+    });
+
+    let seeds = match root.get(b"url-list") {
+        Some(Bencode::List(seeds)) => seeds.iter().filter_map(Bencode::as_str).collect(),
+        Some(seed) => seed.as_str().into_iter().collect(),
+        None => Vec::new(),
+    };
+    let web_seeds = seeds
+        .into_iter()
+        .filter(|seed| seed.starts_with("https://") || seed.starts_with("http://"))
+        .map(|seed| match seed.ends_with('/') {
+            true => format!("{}{}", seed, name),
+            false => seed.to_string(),
+        })
+        .collect();
+    Ok(Torrent {
+        name,
+        length,
+        web_seeds,
+    })
+}
+
+/// A bencoded value, borrowing its strings from the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Bencode<'a> {
+    Int(i64),
+    Bytes(&'a [u8]),
+    List(Vec<Bencode<'a>>),
+    Dict(Vec<(&'a [u8], Bencode<'a>)>),
+}
+
+impl<'a> Bencode<'a> {
+    /// Parses one value from the start of `input` and advances past it.
+    fn parse(input: &mut &'a [u8], depth: usize) -> Result<Self, Box<dyn Error>> {
+        if depth > MAX_DEPTH {
+            return Err("Torrent is nested too deeply".into());
+        }
+        match input.first() {
+            Some(b'i') => {
+                let end = find(input, b'e')?;
+                let int = std::str::from_utf8(&input[1..end])?.parse()?;
+                *input = &input[end + 1..];
+                Ok(Self::Int(int))
+            }
+            Some(b'l') => {
+                *input = &input[1..];
+                let mut list = Vec::new();
+                while input.first() != Some(&b'e') {
+                    list.push(Self::parse(input, depth + 1)?);
+                }
+                *input = &input[1..];
+                Ok(Self::List(list))
+            }
+            Some(b'd') => {
+                *input = &input[1..];
+                let mut dict = Vec::new();
+                while input.first() != Some(&b'e') {
+                    let Self::Bytes(key) = Self::parse(input, depth + 1)? else {
+                        return Err("Torrent dictionary key is not a string".into());
+                    };
+                    dict.push((key, Self::parse(input, depth + 1)?));
+                }
+                *input = &input[1..];
+                Ok(Self::Dict(dict))
+            }
+            Some(b'0'..=b'9') => {
+                let colon = find(input, b':')?;
+                let len: usize = std::str::from_utf8(&input[..colon])?.parse()?;
+                let bytes = input
+                    .get(colon + 1..colon + 1 + len)
+                    .ok_or("Torrent ends inside a string")?;
+                *input = &input[colon + 1 + len..];
+                Ok(Self::Bytes(bytes))
+            }
+            Some(_) => Err("Torrent is not valid bencode".into()),
+            None => Err("Torrent ends unexpectedly".into()),
+        }
+    }
+
+    /// The value of `key` in a dictionary.
+    fn get(&self, key: &[u8]) -> Option<&Self> {
+        match self {
+            Self::Dict(dict) => dict.iter().find(|(k, _)| *k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&'a str> {
+        match self {
+            Self::Bytes(bytes) => std::str::from_utf8(bytes).ok(),
+            _ => None,
+        }
+    }
+}
+
+fn find(input: &[u8], byte: u8) -> Result<usize, Box<dyn Error>> {
+    input
+        .iter()
+        .position(|b| *b == byte)
+        .ok_or_else(|| "Torrent ends unexpectedly".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn web_seeds_are_read_from_single_file_torrents() {
+        let torrent = b"d8:announce20:udp://tracker.lan:808:url-listl27:https://mirror.example/iso/41:http://other.example/proxmox-ve_8.2-1.iso14:ftp://ftp.lan/e4:infod6:lengthi1331691520e4:name20:proxmox-ve_8.2-1.iso12:piece lengthi262144e6:pieces0:ee";
+        assert_eq!(
+            parse_torrent(torrent).unwrap(),
+            Torrent {
+                name: "proxmox-ve_8.2-1.iso".into(),
+                length: Some(1331691520),
+                web_seeds: vec![
+                    "https://mirror.example/iso/proxmox-ve_8.2-1.iso".into(),
+                    "http://other.example/proxmox-ve_8.2-1.iso".into(),
+                ],
+            }
+        );
+
+        let single_seed = b"d8:url-list26:https://seed.example/a.iso4:infod4:name5:a.isoee";
+        assert_eq!(
+            parse_torrent(single_seed).unwrap().web_seeds,
+            ["https://seed.example/a.iso"]
+        );
+
+        for invalid in [
+            &b"d4:infod6:lengthi1eee"[..],
+            b"d4:infod4:name99:shortee",
+            b"li1e",
+            b"x",
+            &[b'l'; 64],
+        ] {
+            assert!(parse_torrent(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+}
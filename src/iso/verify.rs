@@ -0,0 +1,539 @@
+use crate::iso::{
+    checksum::{ChecksumAlgorithm, ChecksumError, hash_file},
+    constants::{ISO_INDEX_URL, PROXMOX_RELEASE_KEYS},
+    inspect::check_installer_iso,
+    pgp::{PgpError, PublicKey, Signature, parse_public_keys},
+    scraper::{iso_file_name, iso_version},
+};
+use reqwest::{Client, StatusCode};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Upper bound for each metadata request; the files are a few KiB.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/* ===================== ISO VERIFY ERROR ===================== */
+
+/// Failure while checking a local ISO against the signed Proxmox checksums.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IsoVerifyError {
+    /// A local file could not be read; holds the path and the I/O error.
+    Io(PathBuf, String),
+    /// Downloading release metadata failed; holds the URL and the error.
+    Fetch(String, String),
+    /// The downloaded keyring has no key with a pinned fingerprint.
+    UntrustedKey,
+    /// The keyring or the signature is invalid, or the signature does not
+    /// match the checksum file.
+    Signature(PgpError),
+    /// The only checksum file published is one that is not trusted.
+    Checksum(ChecksumError),
+    /// No checksum file is published; holds the directory looked in.
+    NoChecksums(String),
+    /// No entry of the checksum file has the ISO's checksum; holds the checksum.
+    NotListed(String),
+    /// The ISO is a release whose published checksum differs, i.e. it is
+    /// damaged; holds the release's file name, the algorithm, the published
+    /// and the actual checksum.
+    Corrupt(String, ChecksumAlgorithm, String, String),
+}
+
+impl IsoVerifyError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io(..) => "iso.verify.io",
+            Self::Fetch(..) => "iso.verify.fetch",
+            Self::UntrustedKey => "iso.verify.untrusted_key",
+            Self::Signature(_) => "iso.verify.signature",
+            Self::Checksum(_) => "iso.verify.checksum",
+            Self::NoChecksums(_) => "iso.verify.no_checksums",
+            Self::NotListed(_) => "iso.verify.not_listed",
+            Self::Corrupt(..) => "iso.verify.corrupt",
+        }
+    }
+}
+
+impl fmt::Display for IsoVerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, detail) => write!(f, "{} ({}: {})", self.code(), path.display(), detail),
+            Self::Fetch(url, detail) => write!(f, "{} ({}: {})", self.code(), url, detail),
+            Self::UntrustedKey => write!(
+                f,
+                "{} (expected one of {})",
+                self.code(),
+                PROXMOX_RELEASE_KEYS
+                    .iter()
+                    .map(|(_, fingerprint)| *fingerprint)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::Signature(e) => write!(f, "{} ({})", self.code(), e),
+            Self::Checksum(e) => write!(f, "{} ({})", self.code(), e),
+            Self::NoChecksums(url) => write!(f, "{} ({})", self.code(), url),
+            Self::NotListed(sha256) => write!(f, "{} ({})", self.code(), sha256),
+            Self::Corrupt(file_name, algorithm, published, actual) => write!(
+                f,
+                "{} ({} is published with {} {}, the file has {})",
+                self.code(),
+                file_name,
+                algorithm,
+                published,
+                actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for IsoVerifyError {}
+
+impl From<PgpError> for IsoVerifyError {
+    fn from(e: PgpError) -> Self {
+        Self::Signature(e)
+    }
+}
+
+impl From<ChecksumError> for IsoVerifyError {
+    fn from(e: ChecksumError) -> Self {
+        Self::Checksum(e)
+    }
+}
+
+/* ===================== VERIFICATION ===================== */
+
+/// A local ISO whose checksum is listed in a signed checksum file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedIso {
+    /// The published file name the checksum belongs to.
+    pub file_name: String,
+    /// The algorithm of the checksum file, e.g. SHA512 for `SHA512SUMS`.
+    pub algorithm: ChecksumAlgorithm,
+    pub sha256: String,
+    /// Fingerprint of the key that signed the checksum file.
+    pub signer: String,
+}
+
+/// Downloads the strongest checksum file published on enterprise.proxmox.com
+/// (`SHA512SUMS`, else `SHA256SUMS`) and its signature, checks the signature
+/// and then the checksum of the ISO at `iso`.
+///
+/// The signature is checked against the keyring file `keyring` when given,
+/// otherwise against the Proxmox release keyrings, which are downloaded and
+/// trusted for the keys pinned in [`PROXMOX_RELEASE_KEYS`].
+///
+/// # Errors
+/// Any failed download, an untrusted key, a bad signature, only `MD5SUMS`
+/// being published, or an ISO whose checksum is not listed.
+pub async fn verify_iso(iso: &Path, keyring: Option<&Path>) -> Result<VerifiedIso, IsoVerifyError> {
+    let keys = match keyring {
+        Some(path) => parse_public_keys(&read(path)?)?,
+        None => pinned_keys(&fetch_release_keyring().await?)?,
+    };
+    let signed = fetch_signed_checksums().await?;
+    verify_iso_with(
+        iso,
+        signed.algorithm,
+        &signed.sums,
+        &signed.signature,
+        &keys,
+    )
+}
+
+/// A checksum file and its detached signature, as published.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedChecksums {
+    pub algorithm: ChecksumAlgorithm,
+    pub sums: Vec<u8>,
+    /// The armored or binary signature of `sums` (`<sums file>.asc`).
+    pub signature: Vec<u8>,
+}
+
+/// Downloads the strongest checksum file published on enterprise.proxmox.com
+/// and its signature, without checking either.
+///
+/// # Errors
+/// A failed download, only `MD5SUMS` being published, or none at all.
+pub async fn fetch_signed_checksums() -> Result<SignedChecksums, IsoVerifyError> {
+    for algorithm in ChecksumAlgorithm::STRONGEST_FIRST {
+        let sums_url = format!("{}{}", ISO_INDEX_URL, algorithm.sums_file());
+        let Some(sums) = fetch_if_published(&sums_url).await? else {
+            continue;
+        };
+        if !algorithm.is_trusted() {
+            return Err(ChecksumError::Untrusted(algorithm).into());
+        }
+        let signature = fetch(&format!("{}.asc", sums_url)).await?;
+        return Ok(SignedChecksums {
+            algorithm,
+            sums,
+            signature,
+        });
+    }
+    Err(IsoVerifyError::NoChecksums(ISO_INDEX_URL.to_string()))
+}
+
+/// Downloads the Proxmox release keyrings into one keyring; see
+/// [`pinned_keys`] for trusting it.
+///
+/// A keyring that cannot be downloaded is left out, so an unreachable
+/// Trixie keyring does not stop checking a Proxmox VE 8 ISO.
+///
+/// # Errors
+/// The error of the first keyring when none can be downloaded.
+pub async fn fetch_release_keyring() -> Result<Vec<u8>, IsoVerifyError> {
+    let mut fetched = Vec::with_capacity(PROXMOX_RELEASE_KEYS.len());
+    for (url, _) in PROXMOX_RELEASE_KEYS {
+        fetched.push(fetch(url).await);
+    }
+    merge_keyrings(fetched)
+}
+
+/// Concatenates the keyrings that were `fetched`, or returns the first
+/// error when none was.
+fn merge_keyrings(
+    fetched: Vec<Result<Vec<u8>, IsoVerifyError>>,
+) -> Result<Vec<u8>, IsoVerifyError> {
+    let mut keyring = Vec::new();
+    let mut first_error = None;
+    for result in fetched {
+        match result {
+            Ok(keys) => keyring.extend(keys),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    match first_error {
+        Some(e) if keyring.is_empty() => Err(e),
+        _ => Ok(keyring),
+    }
+}
+
+/// Checks `signature` over `sums`, a checksum file of `algorithm`, against
+/// `keys`, then looks the checksum of the ISO at `iso` up in `sums`.
+///
+/// An unlisted ISO whose release is known, from its `.cd-info` or else its
+/// file name, is reported as [`IsoVerifyError::Corrupt`] when that release
+/// is listed, e.g. after a copy between machines was cut short.
+pub fn verify_iso_with(
+    iso: &Path,
+    algorithm: ChecksumAlgorithm,
+    sums: &[u8],
+    signature: &[u8],
+    keys: &[PublicKey],
+) -> Result<VerifiedIso, IsoVerifyError> {
+    if !algorithm.is_trusted() {
+        return Err(ChecksumError::Untrusted(algorithm).into());
+    }
+    let signer = Signature::parse(signature)?.verify(sums, keys)?;
+    let hashes = hash_file(iso, algorithm).map_err(|e| io_error(iso, e))?;
+    let checksum = hashes
+        .get(algorithm)
+        .ok_or(ChecksumError::Untrusted(algorithm))?;
+    let sums = String::from_utf8_lossy(sums);
+    let Some(file_name) = find_checksum(&sums, checksum) else {
+        return Err(release_file_name(iso)
+            .and_then(|name| {
+                let published = listed_checksum(&sums, &name)?.to_ascii_lowercase();
+                Some(IsoVerifyError::Corrupt(
+                    name,
+                    algorithm,
+                    published,
+                    checksum.to_string(),
+                ))
+            })
+            .unwrap_or_else(|| IsoVerifyError::NotListed(checksum.to_string())));
+    };
+    let file_name = file_name.to_string();
+    Ok(VerifiedIso {
+        file_name,
+        algorithm,
+        sha256: hashes.sha256,
+        signer: signer.fingerprint_hex(),
+    })
+}
+
+/// The keys of `keyring` that are one of the release keys pinned in
+/// [`PROXMOX_RELEASE_KEYS`].
+pub fn pinned_keys(keyring: &[u8]) -> Result<Vec<PublicKey>, IsoVerifyError> {
+    let pinned: Vec<&str> = PROXMOX_RELEASE_KEYS
+        .iter()
+        .map(|(_, fingerprint)| *fingerprint)
+        .collect();
+    keys_pinned_to(keyring, &pinned)
+}
+
+/// The keys of `keyring` whose fingerprint is one of `pinned`.
+fn keys_pinned_to(keyring: &[u8], pinned: &[&str]) -> Result<Vec<PublicKey>, IsoVerifyError> {
+    let keys: Vec<PublicKey> = parse_public_keys(keyring)?
+        .into_iter()
+        .filter(|key| pinned.contains(&key.fingerprint_hex().as_str()))
+        .collect();
+    if keys.is_empty() {
+        return Err(IsoVerifyError::UntrustedKey);
+    }
+    Ok(keys)
+}
+
+/// The file name of the `sha256sum`-style line of `sums` with `sha256`.
+pub fn find_checksum<'a>(sums: &'a str, sha256: &str) -> Option<&'a str> {
+    sums.lines().find_map(|line| {
+        let (checksum, name) = line.split_once(char::is_whitespace)?;
+        checksum
+            .eq_ignore_ascii_case(sha256)
+            .then(|| name.trim_start().trim_start_matches('*'))
+    })
+}
+
+/// The checksum listed in the `sha256sum`-style `sums` for `file_name`.
+pub fn listed_checksum<'a>(sums: &'a str, file_name: &str) -> Option<&'a str> {
+    sums.lines().find_map(|line| {
+        let (checksum, name) = line.split_once(char::is_whitespace)?;
+        (name.trim_start().trim_start_matches('*') == file_name).then_some(checksum)
+    })
+}
+
+/// The published file name of the release the ISO at `iso` claims to be,
+/// from the version in its `.cd-info`, else from its own file name.
+fn release_file_name(iso: &Path) -> Option<String> {
+    check_installer_iso(iso)
+        .ok()
+        .and_then(|(_, version)| version)
+        .or_else(|| iso_version(&iso.file_name()?.to_string_lossy()))
+        .map(|version| iso_file_name(&version))
+}
+
+/// The lower-case hex SHA-256 of the file at `path`, read in chunks.
+pub fn sha256_file(path: &Path) -> Result<String, IsoVerifyError> {
+    hash_file(path, ChecksumAlgorithm::Sha256)
+        .map(|hashes| hashes.sha256)
+        .map_err(|e| io_error(path, e))
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> IsoVerifyError {
+    IsoVerifyError::Io(path.to_path_buf(), e.to_string())
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, IsoVerifyError> {
+    std::fs::read(path).map_err(|e| io_error(path, e))
+}
+
+async fn fetch(url: &str) -> Result<Vec<u8>, IsoVerifyError> {
+    fetch_if_published(url)
+        .await?
+        .ok_or_else(|| IsoVerifyError::Fetch(url.to_string(), StatusCode::NOT_FOUND.to_string()))
+}
+
+/// Downloads `url`, or `None` when the server answers `404 Not Found`.
+async fn fetch_if_published(url: &str) -> Result<Option<Vec<u8>>, IsoVerifyError> {
+    let fetch_error = |e: reqwest::Error| IsoVerifyError::Fetch(url.to_string(), e.to_string());
+    let client = Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .map_err(fetch_error)?;
+    let resp = client.get(url).send().await.map_err(fetch_error)?;
+    if resp.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let resp = resp.error_for_status().map_err(fetch_error)?;
+    Ok(Some(resp.bytes().await.map_err(fetch_error)?.to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::iso::pgp::tests::{
+        ed25519_key, ed25519_keyring, keyring, private_key, sign, sign_ed25519,
+    };
+    use sha2::{Digest, Sha256, Sha512};
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pveauto-iso-verify-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn listed_isos_verify_against_signed_sums() {
+        let dir = temp_dir();
+        let iso = dir.join("proxmox-ve-latest.iso");
+        std::fs::write(&iso, b"not really an iso").unwrap();
+        let sha256 = sha256_file(&iso).unwrap();
+        assert_eq!(
+            sha256,
+            format!("{:x}", Sha256::digest(b"not really an iso"))
+        );
+
+        let sums = format!(
+            "{}  proxmox-ve_8.1-2.iso\n{}  proxmox-ve_8.2-1.iso\n",
+            "0".repeat(64),
+            sha256
+        );
+        let key = private_key(0);
+        let keys = parse_public_keys(&keyring(&key)).unwrap();
+        let signature = sign(&key, sums.as_bytes(), 0);
+
+        let sha256_sums = ChecksumAlgorithm::Sha256;
+        let verified =
+            verify_iso_with(&iso, sha256_sums, sums.as_bytes(), &signature, &keys).unwrap();
+        assert_eq!(verified.file_name, "proxmox-ve_8.2-1.iso");
+        assert_eq!(verified.sha256, sha256);
+        assert_eq!(verified.signer, keys[0].fingerprint_hex());
+
+        let tampered = sums.replace("8.2-1", "8.3-1");
+        assert_eq!(
+            verify_iso_with(&iso, sha256_sums, tampered.as_bytes(), &signature, &keys).unwrap_err(),
+            IsoVerifyError::Signature(PgpError::BadSignature)
+        );
+
+        let sha512_sums = format!(
+            "{}  proxmox-ve_8.2-1.iso\n",
+            format!("{:x}", Sha512::digest(b"not really an iso")).to_uppercase()
+        );
+        let signature = sign(&key, sha512_sums.as_bytes(), 0);
+        let verified = verify_iso_with(
+            &iso,
+            ChecksumAlgorithm::Sha512,
+            sha512_sums.as_bytes(),
+            &signature,
+            &keys,
+        )
+        .unwrap();
+        assert_eq!(
+            (verified.algorithm, verified.sha256),
+            (ChecksumAlgorithm::Sha512, sha256.clone())
+        );
+        assert_eq!(
+            verify_iso_with(
+                &iso,
+                ChecksumAlgorithm::Md5,
+                sha512_sums.as_bytes(),
+                &signature,
+                &keys
+            ),
+            Err(IsoVerifyError::Checksum(ChecksumError::Untrusted(
+                ChecksumAlgorithm::Md5
+            )))
+        );
+
+        std::fs::write(&iso, b"corrupted").unwrap();
+        let signature = sign(&key, sums.as_bytes(), 0);
+        assert_eq!(
+            verify_iso_with(&iso, sha256_sums, sums.as_bytes(), &signature, &keys)
+                .unwrap_err()
+                .code(),
+            "iso.verify.not_listed"
+        );
+
+        // Named as a listed release, it is that release, damaged.
+        let copied = dir.join("proxmox-ve_8.2-1.iso");
+        std::fs::rename(&iso, &copied).unwrap();
+        assert_eq!(
+            verify_iso_with(&copied, sha256_sums, sums.as_bytes(), &signature, &keys),
+            Err(IsoVerifyError::Corrupt(
+                "proxmox-ve_8.2-1.iso".into(),
+                ChecksumAlgorithm::Sha256,
+                sha256,
+                format!("{:x}", Sha256::digest(b"corrupted"))
+            ))
+        );
+        let unlisted = dir.join("proxmox-ve_9.0-1.iso");
+        std::fs::rename(&copied, &unlisted).unwrap();
+        assert_eq!(
+            verify_iso_with(&unlisted, sha256_sums, sums.as_bytes(), &signature, &keys)
+                .unwrap_err()
+                .code(),
+            "iso.verify.not_listed"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn only_the_pinned_release_keys_are_trusted() {
+        assert_eq!(
+            pinned_keys(&keyring(&private_key(0))),
+            Err(IsoVerifyError::UntrustedKey)
+        );
+    }
+
+    #[test]
+    fn any_pinned_release_key_may_sign() {
+        let dir = temp_dir().join("second-key");
+        std::fs::create_dir_all(&dir).unwrap();
+        let iso = dir.join("proxmox-ve_9.0-1.iso");
+        std::fs::write(&iso, b"a trixie release").unwrap();
+        let sums = format!("{}  proxmox-ve_9.0-1.iso\n", sha256_file(&iso).unwrap());
+
+        let (bookworm, trixie, other) = (private_key(0), private_key(1), ed25519_key(7));
+        let fingerprint = |key| parse_public_keys(&keyring(key)).unwrap()[0].fingerprint_hex();
+        let pinned = [fingerprint(&bookworm), fingerprint(&trixie)];
+        let pinned: Vec<&str> = pinned.iter().map(String::as_str).collect();
+        let mut both = keyring(&bookworm);
+        both.extend(keyring(&trixie));
+        both.extend(ed25519_keyring(&other));
+
+        let keys = keys_pinned_to(&both, &pinned).unwrap();
+        assert_eq!(keys.len(), 2);
+        let signature = sign(&trixie, sums.as_bytes(), 0);
+        let verified = verify_iso_with(
+            &iso,
+            ChecksumAlgorithm::Sha256,
+            sums.as_bytes(),
+            &signature,
+            &keys,
+        )
+        .unwrap();
+        assert_eq!(verified.signer, pinned[1]);
+
+        let signature = sign_ed25519(&other, sums.as_bytes(), 0);
+        assert_eq!(
+            verify_iso_with(
+                &iso,
+                ChecksumAlgorithm::Sha256,
+                sums.as_bytes(),
+                &signature,
+                &keys
+            )
+            .unwrap_err()
+            .code(),
+            "iso.verify.signature"
+        );
+        assert_eq!(
+            keys_pinned_to(&ed25519_keyring(&other), &pinned),
+            Err(IsoVerifyError::UntrustedKey)
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unreachable_keyrings_are_left_out() {
+        let unreachable = |url: &str| Err(IsoVerifyError::Fetch(url.into(), "timed out".into()));
+        let bookworm = keyring(&private_key(0));
+        let keys = merge_keyrings(vec![Ok(bookworm.clone()), unreachable("trixie")]).unwrap();
+        assert_eq!(keys, bookworm);
+        assert_eq!(
+            merge_keyrings(vec![unreachable("bookworm"), unreachable("trixie")]),
+            unreachable("bookworm")
+        );
+    }
+
+    #[test]
+    fn checksums_are_found_in_sha256sum_output() {
+        let sums = "ABCD  proxmox-ve_8.2-1.iso\nef01 *proxmox-backup-server_3.2-1.iso\n";
+        assert_eq!(find_checksum(sums, "abcd"), Some("proxmox-ve_8.2-1.iso"));
+        assert_eq!(
+            find_checksum(sums, "EF01"),
+            Some("proxmox-backup-server_3.2-1.iso")
+        );
+        assert_eq!(find_checksum(sums, "1234"), None);
+        assert_eq!(
+            listed_checksum(sums, "proxmox-backup-server_3.2-1.iso"),
+            Some("ef01")
+        );
+        assert_eq!(listed_checksum(sums, "proxmox-ve_8.2"), None);
+    }
+}
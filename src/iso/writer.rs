@@ -0,0 +1,333 @@
+use crate::iso::{
+    boot::{BootRecords, read_boot_records},
+    inject::{IsoInjectError, check_file_names, inject_files},
+};
+use crate::util::{locate_binary, private_dir, unique_suffix};
+use std::{
+    fmt,
+    fs::File,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    str::FromStr,
+};
+
+/// Name of the xorriso binary, looked up on `$PATH`.
+pub const XORRISO_BINARY: &str = "xorriso";
+
+/// Environment variable overriding the xorriso binary path.
+pub const XORRISO_ENV: &str = "PVEAUTO_XORRISO";
+
+/* ===================== BACKENDS ===================== */
+
+/// Which [`IsoWriter`] adds files to ISO images (`--backend`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IsoBackend {
+    /// xorriso when installed, else the native writer.
+    #[default]
+    Auto,
+    /// The crate's own ISO 9660 writer, [`NativeWriter`].
+    Native,
+    /// [`Xorriso`]; fails when it is not installed.
+    Xorriso,
+}
+
+impl IsoBackend {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Native => "native",
+            Self::Xorriso => "xorriso",
+        }
+    }
+}
+
+impl fmt::Display for IsoBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for IsoBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "native" => Ok(Self::Native),
+            "xorriso" => Ok(Self::Xorriso),
+            _ => Err(format!("unknown ISO backend: {}", s)),
+        }
+    }
+}
+
+/// Writes copies of ISO images with files added to them.
+pub trait IsoWriter {
+    /// The backend as named in messages, e.g. `xorriso (/usr/bin/xorriso)`.
+    fn describe(&self) -> String;
+
+    /// Writes a copy of the image at `iso` to `out` with `files`, given as
+    /// `(path, contents)`, added and same-named files replaced, keeping the
+    /// image bootable as BIOS and UEFI, disc and USB drive as before.
+    ///
+    /// `out` is only replaced on success; it may be `iso` itself.
+    fn write(&self, iso: &Path, out: &Path, files: &[(&str, &[u8])]) -> Result<(), IsoInjectError>;
+
+    /// The external command [`IsoWriter::write`] would run to add files at
+    /// `paths`, for `--dry-run`, or `None` when it runs none.
+    fn command(&self, _iso: &Path, _out: &Path, _paths: &[&str]) -> Option<Command> {
+        None
+    }
+}
+
+/// The writer for `backend`.
+///
+/// # Errors
+/// Returns [`IsoInjectError::BackendUnavailable`] when xorriso is asked for
+/// but not installed.
+pub fn select_writer(backend: IsoBackend) -> Result<Box<dyn IsoWriter>, IsoInjectError> {
+    match (backend, Xorriso::locate()) {
+        (IsoBackend::Native, _) | (IsoBackend::Auto, None) => Ok(Box::new(NativeWriter)),
+        (_, Some(xorriso)) => Ok(Box::new(xorriso)),
+        (IsoBackend::Xorriso, None) => Err(IsoInjectError::BackendUnavailable(format!(
+            "{} not found; install it or set ${}",
+            XORRISO_BINARY, XORRISO_ENV
+        ))),
+    }
+}
+
+/* ===================== NATIVE ===================== */
+
+/// Appends the files to the image with [`inject_files`], leaving the
+/// original contents and boot records in place.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NativeWriter;
+
+impl IsoWriter for NativeWriter {
+    fn describe(&self) -> String {
+        "the native ISO 9660 writer".to_string()
+    }
+
+    fn write(&self, iso: &Path, out: &Path, files: &[(&str, &[u8])]) -> Result<(), IsoInjectError> {
+        inject_files(iso, out, files)
+    }
+}
+
+/* ===================== XORRISO ===================== */
+
+/// Writes the image anew with `xorriso`, replaying the boot setup of the
+/// original: its El Torito entries, MBR and GPT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Xorriso {
+    program: PathBuf,
+}
+
+impl Xorriso {
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+        }
+    }
+
+    /// `$PVEAUTO_XORRISO` when set, otherwise [`XORRISO_BINARY`] from `$PATH`.
+    pub fn locate() -> Option<Self> {
+        locate_binary(XORRISO_ENV, XORRISO_BINARY).map(Self::new)
+    }
+
+    pub fn program(&self) -> &Path {
+        &self.program
+    }
+
+    /// The command writing `iso` to `out` with each `(file, path)` of `maps`
+    /// added from the local `file`.
+    fn command_with(&self, iso: &Path, out: &Path, maps: &[(PathBuf, &str)]) -> Command {
+        let mut command = Command::new(&self.program);
+        command
+            .args(["-abort_on", "FAILURE", "-indev"])
+            .arg(iso)
+            .arg("-outdev")
+            .arg(out)
+            .args(["-boot_image", "any", "replay"]);
+        for (file, path) in maps {
+            command.arg("-map").arg(file).arg(format!("/{}", path));
+        }
+        command.arg("-end");
+        command
+    }
+}
+
+impl IsoWriter for Xorriso {
+    fn describe(&self) -> String {
+        format!("{} ({})", XORRISO_BINARY, self.program.display())
+    }
+
+    fn write(&self, iso: &Path, out: &Path, files: &[(&str, &[u8])]) -> Result<(), IsoInjectError> {
+        check_file_names(files)?;
+        let io_error = |path: &Path| {
+            let path = path.to_path_buf();
+            move |e: std::io::Error| IsoInjectError::Io(path, e.to_string())
+        };
+        let before = boot_records_of(iso)?;
+
+        let mut partial = out.as_os_str().to_owned();
+        partial.push(".partial");
+        let partial = PathBuf::from(partial);
+        let staging = std::env::temp_dir().join(format!(
+            "pveauto-xorriso-{}-{}",
+            std::process::id(),
+            unique_suffix()
+        ));
+
+        let result = (|| {
+            // The files may hold password hashes; keep them private.
+            private_dir(&staging).map_err(io_error(&staging))?;
+            let mut maps = Vec::with_capacity(files.len());
+            for (i, (path, contents)) in files.iter().enumerate() {
+                let file = staging.join(i.to_string());
+                std::fs::write(&file, contents).map_err(io_error(&file))?;
+                maps.push((file, *path));
+            }
+            let _ = std::fs::remove_file(&partial);
+            let output = self
+                .command_with(iso, &partial, &maps)
+                .output()
+                .map_err(|e| {
+                    IsoInjectError::BackendFailed(format!("{}: {}", self.describe(), e))
+                })?;
+            if !output.status.success() {
+                return Err(IsoInjectError::BackendFailed(failure_message(&output)));
+            }
+
+            before.check_kept_in(&boot_records_of(&partial)?)?;
+            std::fs::rename(&partial, out).map_err(io_error(out))
+        })();
+
+        let _ = std::fs::remove_dir_all(&staging);
+        if result.is_err() {
+            let _ = std::fs::remove_file(&partial);
+        }
+        result
+    }
+
+    fn command(&self, iso: &Path, out: &Path, paths: &[&str]) -> Option<Command> {
+        let maps: Vec<(PathBuf, &str)> = paths
+            .iter()
+            .map(|path| (PathBuf::from(format!("<{}>", path)), *path))
+            .collect();
+        Some(self.command_with(iso, out, &maps))
+    }
+}
+
+/// The boot records of the image at `path`, with errors naming it.
+fn boot_records_of(path: &Path) -> Result<BootRecords, IsoInjectError> {
+    File::open(path)
+        .map_err(|e| IsoInjectError::Io(path.to_path_buf(), e.to_string()))
+        .and_then(|mut image| read_boot_records(&mut image))
+        .map_err(|e| match e {
+            IsoInjectError::Io(_, detail) => IsoInjectError::Io(path.to_path_buf(), detail),
+            other => other,
+        })
+}
+
+/// The xorriso message explaining a failure: its last `FAILURE` or `SORRY`
+/// line, else its last line of output.
+fn failure_message(output: &Output) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines: Vec<&str> = stderr
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    lines
+        .iter()
+        .rev()
+        .find(|line| line.contains("FAILURE") || line.contains("SORRY"))
+        .or(lines.last())
+        .map(|line| line.to_string())
+        .unwrap_or_else(|| format!("{} exited with {}", XORRISO_BINARY, output.status))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::iso::inject::tests::image;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// A stand-in xorriso copying `-indev` to `-outdev` and logging each
+    /// `-map` as `<path>: <contents>` next to itself; `-indev` files holding
+    /// `broken` fail.
+    const FAKE_XORRISO: &str = "#!/bin/sh\n\
+        log=$0.log\n\
+        while [ $# -gt 0 ]; do\n\
+        case $1 in\n\
+        -indev) in=$2; shift ;;\n\
+        -outdev) out=$2; shift ;;\n\
+        -map) echo \"$3: $(cat \"$2\")\" >> \"$log\"; shift 2 ;;\n\
+        esac\n\
+        shift\n\
+        done\n\
+        grep -q broken \"$in\" && { echo 'libisofs: FAILURE : Cannot read image' >&2; exit 5; }\n\
+        cp \"$in\" \"$out\"\n";
+
+    fn setup(name: &str) -> (PathBuf, Xorriso) {
+        let dir =
+            std::env::temp_dir().join(format!("pveauto-writer-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let program = dir.join("xorriso");
+        std::fs::write(&program, FAKE_XORRISO).unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+        (dir, Xorriso::new(program))
+    }
+
+    #[test]
+    fn xorriso_maps_staged_files_into_the_image() {
+        let (dir, xorriso) = setup("map");
+        let iso = dir.join("base.iso");
+        let out = dir.join("out.iso");
+        std::fs::write(&iso, image(true, false)).unwrap();
+
+        xorriso
+            .write(
+                &iso,
+                &out,
+                &[
+                    ("answer.toml", b"[global]"),
+                    ("proxmox/packages/a.deb", b"deb"),
+                ],
+            )
+            .unwrap();
+        assert_eq!(std::fs::read(&out).unwrap(), image(true, false));
+        assert_eq!(
+            std::fs::read_to_string(dir.join("xorriso.log")).unwrap(),
+            "/answer.toml: [global]\n/proxmox/packages/a.deb: deb\n"
+        );
+        assert!(!dir.join("out.iso.partial").exists());
+
+        std::fs::write(&iso, "broken").unwrap();
+        assert_eq!(
+            xorriso.write(&iso, &out, &[("answer.toml", b"x")]),
+            Err(IsoInjectError::BackendFailed(
+                "libisofs: FAILURE : Cannot read image".into()
+            ))
+        );
+        assert_eq!(
+            xorriso.write(&iso, &out, &[("../answer.toml", b"x")]),
+            Err(IsoInjectError::InvalidName("../answer.toml".into()))
+        );
+        assert_eq!(std::fs::read(&out).unwrap(), image(true, false));
+        assert!(!dir.join("out.iso.partial").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn backends_round_trip() {
+        for backend in [IsoBackend::Auto, IsoBackend::Native, IsoBackend::Xorriso] {
+            assert_eq!(backend.as_str().parse(), Ok(backend));
+        }
+        assert!("genisoimage".parse::<IsoBackend>().is_err());
+        assert_eq!(
+            select_writer(IsoBackend::Native).unwrap().describe(),
+            NativeWriter.describe()
+        );
+    }
+}
@@ -1,3 +1,4 @@
 pub mod answer_file;
 pub mod auto_installer;
 pub mod iso;
+pub mod util;
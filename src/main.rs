@@ -10,8 +10,9 @@ pub async fn run_from<I>(args: I) -> Result<(), ()>
 where
     I: Iterator<Item = String>,
 {
-    execute(dispatch(parse_command_from(args).map_err(|_| ())?)).await;
-    Ok(())
+    execute(dispatch(parse_command_from(args).map_err(|_| ())?))
+        .await
+        .map_err(|_| ())
 }
 #[tokio::main]
 async fn main() {
@@ -0,0 +1,92 @@
+use std::path::{Path, PathBuf};
+
+/* ===================== EXTERNAL TOOLS ===================== */
+
+/// `$env` when set and non-empty, otherwise the first `name` found on `$PATH`.
+pub fn locate_binary(env: &str, name: &str) -> Option<PathBuf> {
+    if let Some(program) = std::env::var_os(env).filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(program));
+    }
+
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+/* ===================== PRIVATE TEMP FILES ===================== */
+
+/// Creates the directory `path` readable by the current user only.
+///
+/// Fails if `path` already exists, so a directory or symlink planted under a
+/// predictable temp name is never reused.
+#[cfg(unix)]
+pub fn private_dir(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    std::fs::DirBuilder::new().mode(0o700).create(path)
+}
+
+#[cfg(not(unix))]
+pub fn private_dir(path: &Path) -> std::io::Result<()> {
+    std::fs::create_dir(path)
+}
+
+/// Creates the file `path` for writing, readable by the current user only.
+///
+/// Like [`private_dir`], fails if `path` already exists, symlinks included.
+#[cfg(unix)]
+pub fn private_file(path: &Path) -> std::io::Result<std::fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+}
+
+#[cfg(not(unix))]
+pub fn private_file(path: &Path) -> std::io::Result<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+}
+
+/// Distinguishes temp files of concurrent calls within one process.
+pub fn unique_suffix() -> u64 {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    COUNTER.fetch_add(1, Ordering::Relaxed)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn private_paths_are_new_and_owner_only() {
+        let dir = std::env::temp_dir().join(format!(
+            "pveauto-util-{}-{}",
+            std::process::id(),
+            unique_suffix()
+        ));
+        private_dir(&dir).unwrap();
+        assert!(private_dir(&dir).is_err());
+        let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        let target = dir.join("target");
+        std::fs::write(&target, "kept").unwrap();
+        let link = dir.join("link");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        assert!(private_file(&link).is_err());
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "kept");
+
+        let file = dir.join("file");
+        private_file(&file).unwrap();
+        let mode = std::fs::metadata(&file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}